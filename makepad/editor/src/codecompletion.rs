@@ -0,0 +1,386 @@
+use render::*;
+use widget::*;
+use std::collections::HashMap;
+use crate::textbuffer::*;
+
+// the words of all textbuffers in the app storage, rebuilt per buffer whenever its token chunks
+// change. buffers stay in the storage once opened, so none are ever removed
+#[derive(Clone, Default)]
+pub struct CodeCompletionIndex {
+    pub text_buffers: HashMap<String, CodeCompletionWords>
+}
+
+#[derive(Clone, Default)]
+pub struct CodeCompletionWords {
+    pub token_chunks_id: u64,
+    pub words: HashMap<String, CodeCompletionWord>
+}
+
+#[derive(Clone, Copy)]
+pub struct CodeCompletionWord {
+    pub count: usize,
+    pub token_type: TokenType
+}
+
+impl CodeCompletionIndex {
+    pub fn update_text_buffer(&mut self, path: &str, text_buffer: &TextBuffer) {
+        if text_buffer.is_loading || text_buffer.token_chunks.len() == 0 {
+            return
+        }
+        if let Some(words) = self.text_buffers.get(path) {
+            if words.token_chunks_id == text_buffer.token_chunks_id {
+                return
+            }
+        }
+        let words = self.text_buffers.entry(path.to_string()).or_insert_with( || CodeCompletionWords::default());
+        words.token_chunks_id = text_buffer.token_chunks_id;
+        words.words.clear();
+        for chunk in &text_buffer.token_chunks {
+            if !CodeCompletion::is_completion_token(chunk.token_type) {
+                continue;
+            }
+            let word: String = text_buffer.flat_text[chunk.offset..(chunk.offset + chunk.len)].iter().collect();
+            let entry = words.words.entry(word).or_insert(CodeCompletionWord {count: 0, token_type: chunk.token_type});
+            entry.count += 1;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CodeCompletionItem {
+    pub word: String,
+    pub token_type: TokenType,
+    pub score: f64
+}
+
+#[derive(Clone, PartialEq)]
+pub enum CodeCompletionEvent {
+    None,
+    Accept {word: String}
+}
+
+#[derive(Clone)]
+pub struct CodeCompletion {
+    pub view: View,
+    pub bg: Quad,
+    pub item_bg: Quad,
+    pub item_text: Text,
+    pub max_items: usize,
+    pub max_visible: usize,
+
+    pub _is_open: bool,
+    pub _prefix: Vec<char>,
+    pub _items: Vec<CodeCompletionItem>,
+    pub _selected: usize,
+    pub _scroll_top: usize,
+    pub _item_areas: Vec<Area>,
+}
+
+impl CodeCompletion {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            view: View::proto_overlay(cx),
+            bg: Quad {
+                z: 10.,
+                ..Quad::proto_with_shader(cx, Self::def_bg_shader(), "CodeCompletion.bg")
+            },
+            item_bg: Quad {
+                z: 10.1,
+                ..Quad::proto(cx)
+            },
+            item_text: Text {
+                z: 10.2,
+                wrapping: Wrapping::Ellipsis(300.),
+                ..Text::proto(cx)
+            },
+            max_items: 64,
+            max_visible: 10,
+            _is_open: false,
+            _prefix: Vec::new(),
+            _items: Vec::new(),
+            _selected: 0,
+            _scroll_top: 0,
+            _item_areas: Vec::new(),
+        }
+    }
+
+    pub fn layout_bg() -> LayoutId {uid!()}
+    pub fn layout_item() -> LayoutId {uid!()}
+    pub fn text_style_item() -> TextStyleId {uid!()}
+    pub fn color_bg() -> ColorId {uid!()}
+    pub fn color_item_selected() -> ColorId {uid!()}
+    pub fn color_item_text() -> ColorId {uid!()}
+    pub fn color_item_text_selected() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::layout_bg().set_base(cx, Layout {
+            padding: Padding {l: 2., t: 2., r: 2., b: 2.},
+            walk: Walk::wh(Width::Fix(320.), Height::Compute),
+            ..Layout::default()
+        });
+        Self::layout_item().set_base(cx, Layout {
+            padding: Padding {l: 6., t: 0., r: 6., b: 0.},
+            align: Align::left_center(),
+            walk: Walk::wh(Width::Fill, Height::Fix(18.)),
+            ..Layout::default()
+        });
+        Self::text_style_item().set_base(cx, Theme::text_style_fixed().base(cx));
+        Self::color_bg().set_base(cx, Theme::color_bg_normal().base(cx));
+        Self::color_item_selected().set_base(cx, Theme::color_bg_marked().base(cx));
+        Self::color_item_text().set_base(cx, Theme::color_text_deselected_focus().base(cx));
+        Self::color_item_text_selected().set_base(cx, Theme::color_text_selected_focus().base(cx));
+    }
+
+    pub fn def_bg_shader() -> ShaderGen {
+        Quad::def_quad_shader().compose(shader_ast!({
+            fn pixel() -> vec4 {
+                df_viewport(pos * vec2(w, h));
+                df_box(0., 0., w, h, 2.);
+                return df_fill(color);
+            }
+        }))
+    }
+
+    pub fn is_completion_token(token_type: TokenType) -> bool {
+        match token_type {
+            TokenType::Identifier | TokenType::TypeName | TokenType::Call | TokenType::ShaderDf => true,
+            _ => false
+        }
+    }
+
+    pub fn is_word_char(ch: char) -> bool {
+        ch.is_alphanumeric() || ch == '_'
+    }
+
+    pub fn is_open(&self) -> bool {
+        self._is_open
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        if self._is_open {
+            self._is_open = false;
+            self._items.truncate(0);
+            self.view.redraw_view_area(cx);
+        }
+    }
+
+    // returns the offset where the word before offset starts
+    pub fn word_start_before(offset: usize, text_buffer: &TextBuffer) -> usize {
+        let mut start = offset;
+        while start > 0 && Self::is_word_char(text_buffer.get_char(start - 1)) {
+            start -= 1;
+        }
+        start
+    }
+
+    // recomputes the list for the word in front of offset, returns true if the popup is open
+    pub fn update_items(&mut self, cx: &mut Cx, offset: usize, text_buffer: &TextBuffer, index: &CodeCompletionIndex, force: bool) -> bool {
+        let start = Self::word_start_before(offset, text_buffer);
+        let mut prefix = Vec::new();
+        for i in start..offset {
+            prefix.push(text_buffer.get_char(i));
+        }
+        if prefix.len() == 0 && !force {
+            self.close(cx);
+            return false
+        }
+
+        // scan the current buffer for proximity to the cursor row
+        let cursor_row = text_buffer.offset_to_text_pos(offset).row;
+        let mut near: HashMap<String, (usize, usize, TokenType)> = HashMap::new();
+        let mut row = 0;
+        for chunk in &text_buffer.token_chunks {
+            if chunk.token_type == TokenType::Newline {
+                row += 1;
+                continue;
+            }
+            if !Self::is_completion_token(chunk.token_type) || chunk.offset == start {
+                continue;
+            }
+            let word: String = text_buffer.flat_text[chunk.offset..(chunk.offset + chunk.len)].iter().collect();
+            let dist = if row > cursor_row {row - cursor_row}else {cursor_row - row};
+            let entry = near.entry(word).or_insert((dist, 0, chunk.token_type));
+            entry.1 += 1;
+            if dist < entry.0 {
+                entry.0 = dist;
+            }
+        }
+
+        let mut frequency: HashMap<&str, (usize, TokenType)> = HashMap::new();
+        for words in index.text_buffers.values() {
+            for (word, cw) in &words.words {
+                let entry = frequency.entry(word).or_insert((0, cw.token_type));
+                entry.0 += cw.count;
+            }
+        }
+
+        let mut items = Vec::new();
+        let mut word_chars = Vec::new();
+        let mut score_word = | word: &str, token_type: TokenType, dist: Option<usize>, count: usize | {
+            word_chars.truncate(0);
+            word_chars.extend(word.chars());
+            if word_chars == prefix {
+                return
+            }
+            if let Some(fuzzy) = FuzzyPicker::fuzzy_score(&prefix, &word_chars) {
+                let proximity = if let Some(dist) = dist {4.0 / (1.0 + dist as f64 / 8.0)} else {0.};
+                items.push(CodeCompletionItem {
+                    word: word.to_string(),
+                    token_type: token_type,
+                    score: fuzzy + proximity + (1.0 + count as f64).ln()
+                });
+            }
+        };
+        for (word, (dist, count, token_type)) in &near {
+            let global = if let Some((global, _)) = frequency.get(word.as_str()) {*global}else {0};
+            score_word(word, *token_type, Some(*dist), (*count).max(global));
+        }
+        for (word, (count, token_type)) in &frequency {
+            if !near.contains_key(*word) {
+                score_word(word, *token_type, None, *count);
+            }
+        }
+        items.sort_by( | a, b | {
+            b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.word.len().cmp(&b.word.len()))
+                .then(a.word.cmp(&b.word))
+        });
+        items.truncate(self.max_items);
+
+        self._prefix = prefix;
+        self._items = items;
+        self._selected = 0;
+        self._scroll_top = 0;
+        self._is_open = self._items.len() > 0;
+        self.view.redraw_view_area(cx);
+        self._is_open
+    }
+
+    pub fn move_selection(&mut self, cx: &mut Cx, delta: isize) {
+        if self._items.len() == 0 {
+            return
+        }
+        let len = self._items.len() as isize;
+        let sel = self._selected as isize + delta;
+        self._selected = if delta.abs() == 1 {
+            ((sel % len + len) % len) as usize
+        }
+        else {
+            sel.max(0).min(len - 1) as usize
+        };
+        if self._selected < self._scroll_top {
+            self._scroll_top = self._selected;
+        }
+        else if self._selected >= self._scroll_top + self.max_visible {
+            self._scroll_top = self._selected + 1 - self.max_visible;
+        }
+        self.view.redraw_view_area(cx);
+    }
+
+    pub fn selected_word(&self) -> Option<String> {
+        if let Some(item) = self._items.get(self._selected) {
+            Some(item.word.clone())
+        }
+        else {
+            None
+        }
+    }
+
+    // keyboard navigation, returns None if the key wasn't consumed by the popup
+    pub fn handle_key_down(&mut self, cx: &mut Cx, ke: &KeyEvent) -> Option<CodeCompletionEvent> {
+        if !self._is_open {
+            return None
+        }
+        match ke.key_code {
+            KeyCode::ArrowUp => {
+                self.move_selection(cx, -1);
+                Some(CodeCompletionEvent::None)
+            },
+            KeyCode::ArrowDown => {
+                self.move_selection(cx, 1);
+                Some(CodeCompletionEvent::None)
+            },
+            KeyCode::PageUp => {
+                self.move_selection(cx, -(self.max_visible as isize));
+                Some(CodeCompletionEvent::None)
+            },
+            KeyCode::PageDown => {
+                self.move_selection(cx, self.max_visible as isize);
+                Some(CodeCompletionEvent::None)
+            },
+            KeyCode::Escape => {
+                self.close(cx);
+                Some(CodeCompletionEvent::None)
+            },
+            KeyCode::Return | KeyCode::Tab => {
+                if ke.modifiers.control || ke.modifiers.logo || ke.modifiers.shift {
+                    return None
+                }
+                if let Some(word) = self.selected_word() {
+                    self.close(cx);
+                    return Some(CodeCompletionEvent::Accept {word: word})
+                }
+                None
+            },
+            _ => None
+        }
+    }
+
+    pub fn handle_code_completion(&mut self, cx: &mut Cx, event: &mut Event) -> CodeCompletionEvent {
+        if !self._is_open {
+            return CodeCompletionEvent::None
+        }
+        for (i, area) in self._item_areas.iter().enumerate() {
+            match event.hits(cx, *area, HitOpt::default()) {
+                Event::FingerHover(_fe) => {
+                    cx.set_hover_mouse_cursor(MouseCursor::Default);
+                },
+                Event::FingerDown(_fe) => {
+                    let index = self._scroll_top + i;
+                    if let Some(item) = self._items.get(index) {
+                        let word = item.word.clone();
+                        self.close(cx);
+                        return CodeCompletionEvent::Accept {word: word}
+                    }
+                },
+                _ => ()
+            }
+        }
+        CodeCompletionEvent::None
+    }
+
+    pub fn draw_code_completion(&mut self, cx: &mut Cx, abs_pos: Vec2) {
+        self._item_areas.truncate(0);
+        if !self._is_open {
+            return
+        }
+        if self.view.begin_view(cx, Layout {
+            abs_origin: Some(abs_pos),
+            ..Default::default()
+        }).is_err() {
+            return
+        }
+
+        self.bg.color = Self::color_bg().base(cx);
+        self.item_text.text_style = Self::text_style_item().base(cx);
+        let color_item_selected = Self::color_item_selected().base(cx);
+        let color_item_text = Self::color_item_text().base(cx);
+        let color_item_text_selected = Self::color_item_text_selected().base(cx);
+        let layout_item = Self::layout_item().base(cx);
+
+        let bg_inst = self.bg.begin_quad(cx, Self::layout_bg().base(cx));
+        let end = (self._scroll_top + self.max_visible).min(self._items.len());
+        for index in self._scroll_top..end {
+            let is_selected = index == self._selected;
+            self.item_bg.color = if is_selected {color_item_selected}else {Color::zero()};
+            let inst = self.item_bg.begin_quad(cx, layout_item.clone());
+            self.item_text.color = if is_selected {color_item_text_selected}else {color_item_text};
+            self.item_text.draw_text(cx, &self._items[index].word);
+            let area = self.item_bg.end_quad(cx, &inst);
+            self._item_areas.push(area);
+            cx.turtle_new_line();
+        }
+        self.bg.end_quad(cx, &bg_inst);
+        self.view.end_view(cx);
+    }
+}
@@ -3,6 +3,13 @@ use widget::*;
 use crate::textbuffer::*;
 use crate::textcursor::*;
 use crate::codeicon::*;
+use crate::codecompletion::*;
+use crate::codehover::*;
+use crate::codeminimap::*;
+use crate::keymap::*;
+use crate::vim::*;
+use crate::shadersyntax::*;
+use crate::codefold::*;
 
 #[derive(Clone)]
 pub struct CodeEditor {
@@ -15,9 +22,14 @@ pub struct CodeEditor {
     pub token_highlight: Quad,
     //pub select_highlight: Quad,
     pub cursor_row: Quad,
+    pub line_mark: Quad,
     pub paren_pair: Quad,
     pub indent_lines: Quad,
     pub code_icon: CodeIcon,
+    pub completion: CodeCompletion,
+    pub hover: CodeHover,
+    pub minimap: CodeMinimap,
+    pub vim_status_line: VimStatusLine,
     pub message_marker: Quad,
     pub text: Text,
     pub line_number_text: Text,
@@ -28,6 +40,7 @@ pub struct CodeEditor {
     pub folded_font_scale: f32,
     pub line_number_width: f32,
     pub draw_line_numbers: bool,
+    pub show_minimap: bool,
     pub top_padding: f32,
     //pub colors: CodeEditorColors,
     pub cursor_blink_speed: f64,
@@ -35,6 +48,15 @@ pub struct CodeEditor {
     pub mark_unmatched_parens: bool,
     pub draw_cursor_row: bool,
     pub folding_depth: usize,
+    pub complete_while_typing: bool,
+    pub soft_wrap: SoftWrap,
+    pub read_only: bool,
+    pub keymap: KeyMap,
+    // modal editing, None is the normal editor
+    pub vim: Option<Vim>,
+    pub line_marks: Vec<LineMark>,
+    // the gutter number shown for each line, None leaves the gutter empty
+    pub line_number_map: Option<Vec<Option<usize>>>,
     pub colors: CodeEditorColors,
 
     //pub _bg_area: Area,
@@ -71,11 +93,24 @@ pub struct CodeEditor {
     pub _line_largest_font: f32,
     pub _anim_folding: AnimFolding,
     
+    pub _fold_ranges: Vec<FoldRange>,
+    pub _fold_ranges_id: u64,
+    pub _folded_lines: Vec<usize>,
+    pub _line_folds: Vec<LineFold>,
+    pub _fold_change: bool,
+    
     pub _monospace_size: Vec2,
     pub _monospace_base: Vec2,
     
     pub _tokens_on_line: usize,
     pub _line_was_folded: bool,
+    pub _wrap_x: Option<f32>,
+    pub _wrap_indent_x: f32,
+    pub _row_start_x: f32,
+    pub _line_start_offset: usize,
+    pub _line_wraps: Vec<usize>,
+    pub _line_bidi_levels: Vec<u8>,
+    pub _line_bidi_cells: Vec<usize>,
     //pub _line_was_visible: bool,
     pub _final_fill_height: f32,
     pub _draw_cursors: DrawCursors,
@@ -95,11 +130,27 @@ pub struct CodeEditor {
     pub _last_lag_mutation_id: u64
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum SoftWrap {
+    Off,
+    ViewWidth,
+    Column(usize)
+}
+
+// a background behind a whole line, or behind a column range of it
+#[derive(Clone)]
+pub struct LineMark {
+    pub line: usize,
+    pub cols: Option<(usize, usize)>,
+    pub color: Color
+}
+
 #[derive(Clone, PartialEq)]
 pub enum CodeEditorEvent {
     None,
     AutoFormat,
     LagChange,
+    FoldChange,
     Change
 }
 
@@ -125,6 +176,9 @@ pub struct CodeEditorColors{
     call:Color,
     type_name:Color,
     theme_name:Color,
+    shader_builtin:Color,
+    shader_storage:Color,
+    shader_df:Color,
     string:Color,
     number:Color,
     comment:Color,
@@ -139,6 +193,43 @@ pub struct CodeEditorColors{
     defocus:Color,
 }
 
+impl CodeEditorColors {
+    // the plain color of a token type, without the highlighting draw_chunk adds
+    pub fn token_color(&self, token_type: TokenType) -> Color {
+        match token_type {
+            TokenType::Whitespace | TokenType::Newline => self.whitespace,
+            TokenType::BuiltinType | TokenType::Keyword | TokenType::Bool | TokenType::TypeDef | TokenType::Fn => self.keyword,
+            TokenType::Error => self.error,
+            TokenType::Warning => self.warning,
+            TokenType::Defocus => self.defocus,
+            TokenType::Flow => self.flow,
+            TokenType::Looping => self.looping,
+            TokenType::Identifier => self.identifier,
+            TokenType::Call => self.call,
+            TokenType::TypeName => self.type_name,
+            TokenType::ThemeName => self.theme_name,
+            TokenType::ShaderBuiltin => self.shader_builtin,
+            TokenType::ShaderStorage => self.shader_storage,
+            TokenType::ShaderDf => self.shader_df,
+            TokenType::Regex | TokenType::String => self.string,
+            TokenType::Number => self.number,
+            TokenType::CommentMultiBegin | TokenType::CommentMultiEnd | TokenType::CommentLine | TokenType::CommentChunk => self.comment,
+            TokenType::ParenOpen | TokenType::ParenClose => self.paren_d1,
+            TokenType::Operator | TokenType::Namespace | TokenType::Hash | TokenType::Splat => self.operator,
+            TokenType::Delimiter | TokenType::Colon => self.delimiter,
+            TokenType::Eof | TokenType::Unexpected => self.unexpected
+        }
+    }
+    
+    pub fn marker_color(&self, level: &TextBufferMessageLevel) -> Color {
+        match level {
+            TextBufferMessageLevel::Warning => self.marker_warning,
+            TextBufferMessageLevel::Error => self.marker_error,
+            TextBufferMessageLevel::Log => self.marker_log,
+        }
+    }
+}
+
 impl CodeEditor {
     
     pub fn proto(cx: &mut Cx) -> Self {
@@ -169,9 +260,14 @@ impl CodeEditor {
 
             cursor: Quad::proto_with_shader(cx, Self::def_cursor_shader(), "Editor.cursor"),
             cursor_row: Quad::proto_with_shader(cx, Self::def_cursor_row_shader(), "Editor.cursor_row"),
+            line_mark: Quad::proto_with_shader(cx, Self::def_line_mark_shader(), "Editor.line_mark"),
             paren_pair: Quad::proto_with_shader(cx, Self::def_paren_pair_shader(), "Editor.paren_pair"),
             message_marker: Quad::proto_with_shader(cx, Self::def_message_marker_shader(), "Editor.message_marker"),
             code_icon: CodeIcon::proto(cx),
+            completion: CodeCompletion::proto(cx),
+            hover: CodeHover::proto(cx),
+            minimap: CodeMinimap::proto(cx),
+            vim_status_line: VimStatusLine::proto(cx),
             bg_layout: Self::layout_bg(),
             text: Text {
                 z: 2.00,
@@ -189,10 +285,18 @@ impl CodeEditor {
             folded_font_scale: 0.07,
             line_number_width: 45.,
             draw_line_numbers: true,
+            show_minimap: true,
             cursor_blink_speed: 0.5,
             top_padding: 27.,
             mark_unmatched_parens: true,
             draw_cursor_row: true,
+            complete_while_typing: true,
+            soft_wrap: SoftWrap::Off,
+            read_only: false,
+            keymap: KeyMap::default_keymap(),
+            vim: None,
+            line_marks: Vec::new(),
+            line_number_map: None,
             _scroll_pos_on_load: None,
             _jump_to_offset: true,
             _monospace_size: Vec2::zero(),
@@ -200,6 +304,13 @@ impl CodeEditor {
             _last_finger_move: None,
             _tokens_on_line: 0,
             _line_was_folded: false,
+            _wrap_x: None,
+            _wrap_indent_x: 0.,
+            _row_start_x: 0.,
+            _line_start_offset: 0,
+            _line_wraps: Vec::new(),
+            _line_bidi_levels: Vec::new(),
+            _line_bidi_cells: Vec::new(),
             //_line_was_visible: false,
             _scroll_pos: Vec2::zero(),
             _visible_lines: 0,
@@ -229,6 +340,11 @@ impl CodeEditor {
                 focussed_line: 0,
                 did_animate: false,
             },
+            _fold_ranges: Vec::new(),
+            _fold_ranges_id: 0,
+            _folded_lines: Vec::new(),
+            _line_folds: Vec::new(),
+            _fold_change: false,
             _select_scroll: None,
             _draw_cursors: DrawCursors::new(),
             _draw_search: DrawCursors::new(),
@@ -288,6 +404,9 @@ impl CodeEditor {
     pub fn color_call()->ColorId{uid!()}
     pub fn color_type_name()->ColorId{uid!()}
     pub fn color_theme_name()->ColorId{uid!()}
+    pub fn color_shader_builtin()->ColorId{uid!()}
+    pub fn color_shader_storage()->ColorId{uid!()}
+    pub fn color_shader_df()->ColorId{uid!()}
     pub fn color_string()->ColorId{uid!()}
     pub fn color_number()->ColorId{uid!()}
     pub fn color_comment()->ColorId{uid!()}
@@ -428,6 +547,14 @@ impl CodeEditor {
         }))
     }
     
+    pub fn def_line_mark_shader() -> ShaderGen {
+        Quad::def_quad_shader().compose(shader_ast!({
+            fn pixel() -> vec4 {
+                return vec4(color.rgb * color.a, color.a);
+            }
+        }))
+    }
+    
     pub fn def_select_highlight_shader() -> ShaderGen {
         Quad::def_quad_shader().compose(shader_ast!({
             fn pixel() -> vec4 {
@@ -476,14 +603,53 @@ impl CodeEditor {
         self._cursor_area.write_uniform_float(cx, Self::uniform_cursor_blink(), self._cursor_blink_flipflop);
     }
     
+    // shows the docs of the shader df_ helper under the mouse
+    fn handle_hover_doc(&mut self, cx: &mut Cx, fe: &FingerHoverEvent, text_buffer: &TextBuffer) {
+        if fe.hover_state == HoverState::Out || fe.any_down || fe.rel.x < self.line_number_width {
+            self.hover.close(cx);
+            return
+        }
+        let offset = self.text.find_closest_offset(cx, &self._text_area, fe.abs);
+        let found = text_buffer.token_chunks.binary_search_by( | chunk | {
+            if chunk.offset > offset {std::cmp::Ordering::Greater}
+            else if chunk.offset + chunk.len <= offset {std::cmp::Ordering::Less}
+            else {std::cmp::Ordering::Equal}
+        });
+        if let Ok(index) = found {
+            let chunk = &text_buffer.token_chunks[index];
+            if chunk.token_type == TokenType::ShaderDf {
+                let word: String = text_buffer.flat_text[chunk.offset..(chunk.offset + chunk.len)].iter().collect();
+                if let Some(doc) = shader_hover_doc(&word) {
+                    let abs_pos = Vec2 {x: fe.abs.x, y: fe.abs.y + self._monospace_size.y};
+                    self.hover.open(cx, chunk.offset, doc, abs_pos);
+                    return
+                }
+            }
+        }
+        self.hover.close(cx);
+    }
+    
     fn handle_finger_down(&mut self, cx: &mut Cx, fe: &FingerDownEvent, text_buffer: &mut TextBuffer) {
         cx.set_down_mouse_cursor(MouseCursor::Text);
         // give us the focus
         self.set_key_focus(cx);
         
+        if self.show_minimap && self.minimap.contains(fe.rel) {
+            self.minimap.start_drag(fe.rel.y);
+            self.scroll_to_minimap_pos(cx, fe.rel.y);
+            return
+        }
+        
         let offset;
         //let scroll_pos = self._bg_area.get_scroll_pos(cx);
         if fe.rel.x < self.line_number_width {
+            // the fold markers sit at the right edge of the gutter
+            if fe.rel.x > self.line_number_width - 12. {
+                let row = self.compute_row_from_ypos(cx, fe.abs.y);
+                if self.toggle_fold(cx, row, text_buffer) {
+                    return
+                }
+            }
             offset = self.compute_offset_from_ypos(cx, fe.abs.y, text_buffer, false);
             let range = text_buffer.get_nearest_line_range(offset);
             self.cursors.set_last_clamp_range(range);
@@ -530,8 +696,8 @@ impl CodeEditor {
         if fe.modifiers.shift {
             if fe.modifiers.logo || fe.modifiers.control { // grid select
                 let pos = self.compute_grid_text_pos_from_abs(cx, fe.abs);
-                self._grid_select_corner = Some(self.cursors.grid_select_corner(pos, text_buffer));
-                self.cursors.grid_select(self._grid_select_corner.unwrap(), pos, text_buffer);
+                self._grid_select_corner = Some(self.grid_select_corner(pos, text_buffer));
+                self.grid_select(self._grid_select_corner.unwrap(), pos, text_buffer);
                 if self.cursors.set.len() == 0 {
                     self.cursors.clear_and_set_last_cursor_head_and_tail(offset, text_buffer);
                 }
@@ -549,6 +715,7 @@ impl CodeEditor {
             }
         }
         
+        self.reveal_cursors(cx, text_buffer);
         self.view.redraw_view_area(cx);
         self._last_finger_move = Some(fe.abs);
         self.update_highlight(cx, text_buffer);
@@ -556,9 +723,13 @@ impl CodeEditor {
     }
     
     fn handle_finger_move(&mut self, cx: &mut Cx, fe: &FingerMoveEvent, text_buffer: &mut TextBuffer) {
+        if self.minimap.is_dragging() {
+            self.scroll_to_minimap_pos(cx, fe.rel.y);
+            return
+        }
         let cursor_moved = if let Some(grid_select_corner) = self._grid_select_corner {
             let pos = self.compute_grid_text_pos_from_abs(cx, fe.abs);
-            self.cursors.grid_select(grid_select_corner, pos, text_buffer)
+            self.grid_select(grid_select_corner, pos, text_buffer)
         }
         else if self._is_row_select {
             let offset = self.compute_offset_from_ypos(cx, fe.abs.y, text_buffer, true);
@@ -583,6 +754,7 @@ impl CodeEditor {
     }
     
     fn handle_finger_up(&mut self, cx: &mut Cx, _fe: &FingerUpEvent, text_buffer: &mut TextBuffer) {
+        self.minimap.end_drag();
         self.cursors.clear_last_clamp_range();
         self._select_scroll = None;
         self._last_finger_move = None;
@@ -593,13 +765,24 @@ impl CodeEditor {
     }
     
     fn handle_key_down(&mut self, cx: &mut Cx, ke: &KeyEvent, text_buffer: &mut TextBuffer) {
+        // a read only editor still moves its cursors and selects
+        if self.read_only {
+            match ke.key_code {
+                KeyCode::Backspace | KeyCode::Delete | KeyCode::Tab | KeyCode::Return => return,
+                _ => ()
+            }
+        }
         let cursor_moved = match ke.key_code {
             KeyCode::ArrowUp => {
                 if ke.modifiers.logo || ke.modifiers.control {
                     false
                 }
+                else if self.soft_wrap != SoftWrap::Off {
+                    self.move_visual_rows(-1, ke.modifiers.shift, text_buffer);
+                    true
+                }
                 else {
-                    if self._anim_folding.state.is_folded() && self.cursors.set.len() == 1 {
+                    if (self._anim_folding.state.is_folded() || self._folded_lines.len() > 0) && self.cursors.set.len() == 1 {
                         // compute the nearest nonfolded line up
                         let delta = self.compute_next_unfolded_line_up(text_buffer);
                         self.cursors.move_up(delta, ke.modifiers.shift, text_buffer);
@@ -614,8 +797,12 @@ impl CodeEditor {
                 if ke.modifiers.logo || ke.modifiers.control {
                     false
                 }
+                else if self.soft_wrap != SoftWrap::Off {
+                    self.move_visual_rows(1, ke.modifiers.shift, text_buffer);
+                    true
+                }
                 else {
-                    if self._anim_folding.state.is_folded() && self.cursors.set.len() == 1 {
+                    if (self._anim_folding.state.is_folded() || self._folded_lines.len() > 0) && self.cursors.set.len() == 1 {
                         // compute the nearest nonfolded line down
                         let delta = self.compute_next_unfolded_line_down(text_buffer);
                         self.cursors.move_down(delta, ke.modifiers.shift, text_buffer);
@@ -669,41 +856,6 @@ impl CodeEditor {
                 self.cursors.delete(text_buffer);
                 true
             },
-            KeyCode::KeyZ => {
-                if ke.modifiers.logo || ke.modifiers.control {
-                    if ke.modifiers.shift { // redo
-                        text_buffer.redo(true, &mut self.cursors);
-                        true
-                    }
-                    else { // undo
-                        text_buffer.undo(true, &mut self.cursors);
-                        true
-                    }
-                }
-                else {
-                    false
-                }
-            },
-            KeyCode::KeyX => { // cut, the actual copy comes from the TextCopy event from the platform layer
-                if ke.modifiers.logo || ke.modifiers.control { // cut
-                    self.cursors.replace_text("", text_buffer);
-                    true
-                }
-                else {
-                    false
-                }
-            },
-            KeyCode::KeyA => { // select all
-                if ke.modifiers.logo || ke.modifiers.control { // cut
-                    self.cursors.select_all(text_buffer);
-                    // don't scroll!
-                    self.view.redraw_view_area(cx);
-                    false
-                }
-                else {
-                    false
-                }
-            },
             KeyCode::Alt => {
                 // how do we find the center line of the view
                 // its simply the top line
@@ -729,10 +881,116 @@ impl CodeEditor {
             _ => false
         };
         if cursor_moved {
-            self.update_highlight(cx, text_buffer);
-            self.scroll_last_cursor_visible(cx, text_buffer, 0.);
-            self.view.redraw_view_area(cx);
-            self.reset_cursor_blinker(cx);
+            self.handle_cursor_moved(cx, text_buffer);
+        }
+    }
+    
+    fn handle_cursor_moved(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        self.reveal_cursors(cx, text_buffer);
+        self.update_highlight(cx, text_buffer);
+        self.scroll_last_cursor_visible(cx, text_buffer, 0.);
+        self.view.redraw_view_area(cx);
+        self.reset_cursor_blinker(cx);
+    }
+    
+    // runs the editing commands of the keymap, None leaves the command to the app
+    fn handle_command(&mut self, cx: &mut Cx, command: Command, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> Option<CodeEditorEvent> {
+        if !command.is_editor_command() {
+            return None
+        }
+        if command != Command::ShowCompletion {
+            self.completion.close(cx);
+        }
+        match command {
+            Command::Undo | Command::Redo | Command::Cut => if !self.read_only {
+                let fold_edit = self.begin_fold_edit(text_buffer);
+                match command {
+                    Command::Undo => text_buffer.undo(true, &mut self.cursors),
+                    Command::Redo => text_buffer.redo(true, &mut self.cursors),
+                    // the actual copy comes from the TextCopy event from the platform layer
+                    _ => self.cursors.replace_text("", text_buffer)
+                }
+                self.end_fold_edit(fold_edit, text_buffer);
+                self.handle_cursor_moved(cx, text_buffer);
+            },
+            Command::SelectAll => {
+                self.cursors.select_all(text_buffer);
+                // don't scroll!
+                self.view.redraw_view_area(cx);
+            },
+            Command::AutoFormat => if !self.read_only {
+                return Some(CodeEditorEvent::AutoFormat)
+            },
+            Command::ShowCompletion => if let Some(completion_index) = completion_index {
+                self.update_completion(cx, text_buffer, completion_index, true);
+            },
+            Command::FoldAtCursor => self.fold_at_cursor(cx, text_buffer),
+            Command::UnfoldAtCursor => self.unfold_at_cursor(cx, text_buffer),
+            _ => ()
+        }
+        Some(CodeEditorEvent::None)
+    }
+    
+    // None when vim leaves the key to the editor
+    fn handle_vim_key(&mut self, cx: &mut Cx, key: VimKey, text_buffer: &mut TextBuffer) -> Option<CodeEditorEvent> {
+        let fold_edit = self.begin_fold_edit(text_buffer);
+        let mutation_id = text_buffer.mutation_id;
+        let vim_event = if let Some(vim) = &mut self.vim {
+            vim.handle_key(key, &mut self.cursors, text_buffer)
+        }
+        else {
+            return None
+        };
+        let mut ce = CodeEditorEvent::None;
+        match vim_event {
+            VimEvent::Pass => return None,
+            VimEvent::None => (),
+            VimEvent::Write => {
+                // saves right away instead of on the lag timer
+                self._last_lag_mutation_id = text_buffer.mutation_id;
+                ce = CodeEditorEvent::LagChange;
+            },
+            VimEvent::Repeat(keys) => for key in keys {
+                self.replay_vim_key(cx, key, text_buffer);
+            }
+        }
+        self.end_fold_edit(fold_edit, text_buffer);
+        self.completion.close(cx);
+        self.handle_cursor_moved(cx, text_buffer);
+        if text_buffer.mutation_id != mutation_id {
+            cx.send_signal(text_buffer.signal, SIGNAL_TEXTBUFFER_DATA_UPDATE);
+        }
+        Some(ce)
+    }
+    
+    // plays back a key of a . repeat, the keys vim passes on are typed into the editor
+    fn replay_vim_key(&mut self, cx: &mut Cx, key: VimKey, text_buffer: &mut TextBuffer) {
+        let vim_event = if let Some(vim) = &mut self.vim {
+            vim.handle_key(key.clone(), &mut self.cursors, text_buffer)
+        }
+        else {
+            return
+        };
+        if vim_event != VimEvent::Pass {
+            return
+        }
+        let key_code = match key {
+            VimKey::Text(input) => {
+                self.handle_text_input(cx, &TextInputEvent {input: input, replace_last: false, was_paste: false}, text_buffer);
+                return
+            },
+            VimKey::Return => KeyCode::Return,
+            VimKey::Backspace => KeyCode::Backspace,
+            VimKey::Delete => KeyCode::Delete,
+            VimKey::Tab => KeyCode::Tab,
+            _ => return
+        };
+        self.handle_key_down(cx, &KeyEvent {key_code: key_code, is_repeat: false, modifiers: KeyModifiers::default(), time: 0.}, text_buffer);
+    }
+    
+    pub fn set_vim_mode(&mut self, enabled: bool) {
+        if enabled != self.vim.is_some() {
+            self.vim = if enabled {Some(Vim::new())}else {None};
         }
     }
     
@@ -773,6 +1031,7 @@ impl CodeEditor {
         else {
             self.cursors.replace_text(&te.input, text_buffer);
         }
+        self.reveal_cursors(cx, text_buffer);
         self.update_highlight(cx, text_buffer);
         self.scroll_last_cursor_visible(cx, text_buffer, 0.);
         self.view.redraw_view_area(cx);
@@ -782,12 +1041,32 @@ impl CodeEditor {
         
     }
     
-    pub fn handle_code_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer) -> CodeEditorEvent {
+    fn update_completion(&mut self, cx: &mut Cx, text_buffer: &TextBuffer, completion_index: &CodeCompletionIndex, force: bool) {
+        if let Some(offset) = self.cursors.get_last_cursor_singular() {
+            self.completion.update_items(cx, offset, text_buffer, completion_index, force);
+        }
+        else {
+            self.completion.close(cx);
+        }
+        self.view.redraw_view_area(cx);
+    }
+    
+    fn accept_completion(&mut self, cx: &mut Cx, word: &str, text_buffer: &mut TextBuffer) {
+        self.cursors.complete_word(word, text_buffer);
+        self.update_highlight(cx, text_buffer);
+        self.scroll_last_cursor_visible(cx, text_buffer, 0.);
+        self.view.redraw_view_area(cx);
+        self.reset_cursor_blinker(cx);
+        
+        cx.send_signal(text_buffer.signal, SIGNAL_TEXTBUFFER_DATA_UPDATE);
+    }
+    
+    pub fn handle_code_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
         if self.view.handle_scroll_bars(cx, event) {
             if let Some(last_finger_move) = self._last_finger_move {
                 if let Some(grid_select_corner) = self._grid_select_corner {
                     let pos = self.compute_grid_text_pos_from_abs(cx, last_finger_move);
-                    self.cursors.grid_select(grid_select_corner, pos, text_buffer);
+                    self.grid_select(grid_select_corner, pos, text_buffer);
                 }
                 else {
                     let offset = self.text.find_closest_offset(cx, &self._text_area, last_finger_move);
@@ -858,16 +1137,37 @@ impl CodeEditor {
             },
             _ => ()
         }
+        // the completion popup sits on top of the editor so it gets the first pick
+        if let CodeCompletionEvent::Accept {word} = self.completion.handle_code_completion(cx, event) {
+            self.accept_completion(cx, &word, text_buffer);
+        }
+        if let Event::FingerScroll(_) = event {
+            self.hover.close(cx);
+        }
         // editor local
         match event.hits(cx, self.view.get_view_area(cx), HitOpt {no_scrolling: true, ..Default::default()}) {
             Event::KeyFocusLost(_kf) => {
+                self.completion.close(cx);
+                self.hover.close(cx);
                 self.view.redraw_view_area(cx)
             },
             Event::FingerDown(fe) => {
+                self.completion.close(cx);
+                self.hover.close(cx);
+                if let Some(vim) = &mut self.vim {
+                    vim.cancel_visual();
+                }
                 self.handle_finger_down(cx, &fe, text_buffer);
             },
-            Event::FingerHover(_fe) => {
-                cx.set_hover_mouse_cursor(MouseCursor::Text);
+            Event::FingerHover(fe) => {
+                if self.show_minimap && self.minimap.contains(fe.rel) {
+                    cx.set_hover_mouse_cursor(MouseCursor::Default);
+                    self.hover.close(cx);
+                }
+                else {
+                    cx.set_hover_mouse_cursor(MouseCursor::Text);
+                    self.handle_hover_doc(cx, &fe, text_buffer);
+                }
             },
             Event::FingerUp(fe) => {
                 self.handle_finger_up(cx, &fe, text_buffer);
@@ -876,10 +1176,45 @@ impl CodeEditor {
                 self.handle_finger_move(cx, &fe, text_buffer);
             },
             Event::KeyDown(ke) => {
-                if ke.key_code == KeyCode::Return && (ke.modifiers.logo || ke.modifiers.control) {
-                    return CodeEditorEvent::AutoFormat
+                self.hover.close(cx);
+                if let Some(ce) = self.completion.handle_key_down(cx, &ke) {
+                    if let CodeCompletionEvent::Accept {word} = ce {
+                        self.accept_completion(cx, &word, text_buffer);
+                    }
+                    self.view.redraw_view_area(cx);
+                    return CodeEditorEvent::None
+                }
+                let vim_key = match &self.vim {
+                    Some(vim) if !self.read_only => vim.key_from_key_down(&ke),
+                    _ => None
+                };
+                if let Some(vim_key) = vim_key {
+                    if let Some(ce) = self.handle_vim_key(cx, vim_key, text_buffer) {
+                        // vim used the key, so the app keymap doesn't run its binding as well (ctrl+r)
+                        *event = Event::None;
+                        return ce
+                    }
                 }
+                match self.keymap.handle_key_down(cx, &ke) {
+                    // the next chord decides what this key does
+                    KeyMapEvent::Pending => return CodeEditorEvent::None,
+                    KeyMapEvent::Command(command) => if let Some(ce) = self.handle_command(cx, command, text_buffer, completion_index) {
+                        return ce
+                    },
+                    KeyMapEvent::None => ()
+                }
+                let fold_edit = self.begin_fold_edit(text_buffer);
                 self.handle_key_down(cx, &ke, text_buffer);
+                self.end_fold_edit(fold_edit, text_buffer);
+                if self.completion.is_open() {
+                    match ke.key_code {
+                        KeyCode::Backspace | KeyCode::Delete => if let Some(completion_index) = completion_index {
+                            self.update_completion(cx, text_buffer, completion_index, false);
+                        },
+                        KeyCode::Shift | KeyCode::Control | KeyCode::Alt | KeyCode::Logo => (),
+                        _ => self.completion.close(cx)
+                    }
+                }
             },
             Event::KeyUp(ke) => {
                 match ke.key_code {
@@ -890,8 +1225,31 @@ impl CodeEditor {
                 }
                 self.reset_cursor_blinker(cx);
             },
-            Event::TextInput(te) => {
+            Event::TextInput(te) => if !self.read_only {
+                if let Some(vim) = &self.vim {
+                    // outside of insert mode typing drives vim
+                    if !vim.is_inserting() {
+                        if !te.was_paste {
+                            for ch in te.input.chars() {
+                                self.handle_vim_key(cx, VimKey::Char(ch), text_buffer);
+                            }
+                        }
+                        return CodeEditorEvent::None
+                    }
+                    self.handle_vim_key(cx, VimKey::Text(te.input.clone()), text_buffer);
+                }
+                let fold_edit = self.begin_fold_edit(text_buffer);
                 self.handle_text_input(cx, &te, text_buffer);
+                self.end_fold_edit(fold_edit, text_buffer);
+                if let Some(completion_index) = completion_index {
+                    let is_word = !te.was_paste && te.input.chars().count() == 1 && te.input.chars().all(CodeCompletion::is_word_char);
+                    if is_word && (self.completion.is_open() || self.complete_while_typing) {
+                        self.update_completion(cx, text_buffer, completion_index, false);
+                    }
+                    else {
+                        self.completion.close(cx);
+                    }
+                }
             },
             Event::TextCopy(_) => match event { // access the original event
                 Event::TextCopy(req) => {
@@ -901,9 +1259,20 @@ impl CodeEditor {
             },
             _ => ()
         };
+        if self._fold_change {
+            self._fold_change = false;
+            return CodeEditorEvent::FoldChange
+        }
         CodeEditorEvent::None
     }
     
+    pub fn toggle_soft_wrap(&mut self, cx: &mut Cx) {
+        self.soft_wrap = if self.soft_wrap == SoftWrap::Off {SoftWrap::ViewWidth}else {SoftWrap::Off};
+        let scroll_pos = self.view.get_scroll_pos(cx);
+        self.view.set_scroll_pos(cx, Vec2 {x: 0., y: scroll_pos.y});
+        self.view.redraw_view_area(cx);
+    }
+    
     pub fn has_key_focus(&self, cx: &Cx) -> bool {
         cx.has_key_focus(self._view_area)
     }
@@ -941,6 +1310,9 @@ impl CodeEditor {
         self.colors.call = Self::color_call().base(cx);
         self.colors.type_name = Self::color_type_name().base(cx);
         self.colors.theme_name = Self::color_theme_name().base(cx);
+        self.colors.shader_builtin = Self::color_shader_builtin().base(cx);
+        self.colors.shader_storage = Self::color_shader_storage().base(cx);
+        self.colors.shader_df = Self::color_shader_df().base(cx);
         self.colors.string = Self::color_string().base(cx);
         self.colors.number = Self::color_number().base(cx);
         self.colors.comment = Self::color_comment().base(cx);
@@ -990,6 +1362,7 @@ impl CodeEditor {
             // layering, this sets the draw call order
             self._highlight_area = cx.new_instance_draw_call(&self.token_highlight.shader, 0).into();
             //cx.new_instance_layer(self.select_highlight.shader_id, 0);
+            cx.new_instance_draw_call(&self.line_mark.shader, 0);
             cx.new_instance_draw_call(&self.cursor_row.shader, 0);
             cx.new_instance_draw_call(&self.selection.shader, 0);
             cx.new_instance_draw_call(&self.message_marker.shader, 0);
@@ -1026,6 +1399,20 @@ impl CodeEditor {
             // initialize all drawing counters/stacks
             self._monospace_base = self.text.get_monospace_base(cx);
             self.set_font_scale(cx, self.open_font_scale);
+            // the x where soft wrapped rows break, leaves a column for the cursor
+            let char_width = self._monospace_base.x * self.base_font_size;
+            self._wrap_x = match self.soft_wrap {
+                SoftWrap::Off => None,
+                SoftWrap::ViewWidth => {
+                    let minimap_width = if self.show_minimap {self.minimap.width}else {0.};
+                    Some((cx.get_width_total() - minimap_width - char_width).max(self.line_number_width + 20. * char_width))
+                },
+                SoftWrap::Column(col) => Some(self.line_number_width + (col.max(20) as f32) * char_width)
+            };
+            self._line_wraps.truncate(0);
+            self._line_start_offset = 0;
+            self._row_start_x = self.line_number_width;
+            self._wrap_indent_x = self.line_number_width;
             self._draw_cursors = DrawCursors::new();
             self._draw_messages = DrawCursors::new();
             self._tokens_on_line = 0;
@@ -1044,6 +1431,7 @@ impl CodeEditor {
                 self._draw_messages.set_next(&text_buffer.messages.cursors);
             }
             self._last_cursor_pos = self.cursors.get_last_cursor_text_pos(text_buffer);
+            self.update_fold_ranges(text_buffer);
             
             // indent
             cx.move_turtle(self.line_number_width, self.top_padding);
@@ -1083,12 +1471,15 @@ impl CodeEditor {
                     if focus_line == line {
                         ypos_at_line = ypos;
                     }
-                    ypos += if geom.was_folded {
+                    ypos += if geom.was_hidden {
+                        0.
+                    }
+                    else if geom.was_folded {
                         self._monospace_base.y * self.base_font_size * self._anim_font_scale
                     }
                     else {
                         self._monospace_base.y * self.base_font_size
-                    }
+                    } * (geom.rows() as f32)
                 }
                 ypos += self._final_fill_height;
                 let dy = self._line_geometry[focus_line].walk.y - ypos_at_line;
@@ -1114,48 +1505,72 @@ impl CodeEditor {
     }
     
     fn draw_new_line(&mut self, cx: &mut Cx) {
+        self.draw_line_chunk_highlights(cx, cx.get_rel_turtle_pos().y);
         // line geometry is used for scrolling look up of cursors
+        let wraps = std::mem::replace(&mut self._line_wraps, Vec::new());
+        let mut walk = cx.get_rel_turtle_pos();
+        walk.y -= self._monospace_size.y * (wraps.len() as f32);
         let line_geom = LineGeom {
-            walk: cx.get_rel_turtle_pos(),
+            walk: walk,
             font_size: self._line_largest_font,
             was_folded: self._line_was_folded,
-            indent_id: if let Some((_, id)) = self._indent_stack.last() {*id}else {0.}
+            was_hidden: false,
+            indent_id: if let Some((_, id)) = self._indent_stack.last() {*id}else {0.},
+            wrap_indent: if wraps.len() > 0 {
+                ((self._wrap_indent_x - self.line_number_width) / self._monospace_size.x).round() as usize
+            }else {0},
+            wraps: wraps
         };
         
         // draw a linenumber if we are visible
         let origin = cx.get_turtle_origin();
-        if self.draw_line_numbers && cx.turtle_line_is_visible(self._monospace_size.y, self._scroll_pos) {
+        if self.draw_line_numbers && (line_geom.wraps.len() > 0 || cx.turtle_line_is_visible(self._monospace_size.y, self._scroll_pos)) {
             // lets format a number, we go to 4 numbers
             // yes this is dumb as rocks. but we need to be cheapnfast
             let chunk = &mut self._line_number_chunk;
             chunk.truncate(0);
-            let line_num = self._line_geometry.len() + 1;
-            let mut scale = 10000;
-            let mut fill = false;
-            loop {
-                let digit = ((line_num / scale) % 10) as u8;
-                if digit != 0 {
-                    fill = true;
+            let line = self._line_geometry.len();
+            let shown_num = match &self.line_number_map {
+                Some(map) => map.get(line).cloned().unwrap_or(None).map( | num | num + 1),
+                None => Some(line + 1)
+            };
+            if let Some(line_num) = shown_num {
+                let mut scale = 10000;
+                let mut fill = false;
+                loop {
+                    let digit = ((line_num / scale) % 10) as u8;
+                    if digit != 0 {
+                        fill = true;
+                    }
+                    if fill {
+                        chunk.push((48 + digit) as char);
+                    }
+                    else {
+                        chunk.push(' ');
+                    }
+                    if scale <= 1 {
+                        break
+                    }
+                    scale /= 10;
                 }
-                if fill {
-                    chunk.push((48 + digit) as char);
+                if line == self._last_cursor_pos.row {
+                    self.line_number_text.color = self.colors.line_number_highlight;
                 }
                 else {
-                    chunk.push(' ');
-                }
-                if scale <= 1 {
-                    break
+                    self.line_number_text.color = self.colors.line_number_normal;
                 }
-                scale /= 10;
-            }
-            if line_num == self._last_cursor_pos.row + 1 {
-                self.line_number_text.color = self.colors.line_number_highlight;
+                let chunk_width = self._monospace_size.x * 5.0;
+                self.line_number_text.add_text(cx, origin.x + (self.line_number_width - chunk_width - 10.), origin.y + line_geom.walk.y, 0, self._line_number_inst.as_mut().unwrap(), chunk, | _, _, _, _ | {0.});
             }
-            else {
-                self.line_number_text.color = self.colors.line_number_normal;
+            // fold toggle
+            let marker = match self.line_fold(line) {
+                LineFold::Open => Some(['-']),
+                LineFold::Folded => Some(['+']),
+                _ => None
+            };
+            if let Some(marker) = marker {
+                self.line_number_text.add_text(cx, origin.x + self.line_number_width - 9., origin.y + line_geom.walk.y, 0, self._line_number_inst.as_mut().unwrap(), &marker, | _, _, _, _ | {0.});
             }
-            let chunk_width = self._monospace_size.x * 5.0;
-            self.line_number_text.add_text(cx, origin.x + (self.line_number_width - chunk_width - 10.), origin.y + line_geom.walk.y, 0, self._line_number_inst.as_mut().unwrap(), chunk, | _, _, _, _ | {0.});
         }
         
         cx.turtle_new_line_min_height(self._monospace_size.y);
@@ -1163,11 +1578,49 @@ impl CodeEditor {
         cx.move_turtle(self.line_number_width, 0.);
         
         self._tokens_on_line = 0;
+        self._row_start_x = self.line_number_width;
+        self._wrap_indent_x = self.line_number_width;
         //self._line_was_visible = false;
         
         self._draw_cursors.process_newline();
         self._draw_messages.process_newline();
         
+        // search for all markings
+        self._line_geometry.push(line_geom);
+        self._line_largest_font = self.text.text_style.font_size;
+    }
+    
+    // continues a soft wrapped line on the next visual row
+    fn draw_wrap_line(&mut self, cx: &mut Cx, col: usize) {
+        self.draw_line_chunk_highlights(cx, cx.get_rel_turtle_pos().y);
+        cx.turtle_new_line_min_height(self._monospace_size.y);
+        cx.move_turtle(self._wrap_indent_x, 0.);
+        self._row_start_x = self._wrap_indent_x;
+        self._line_wraps.push(col);
+        self._draw_cursors.process_newline();
+        self._draw_messages.process_newline();
+    }
+    
+    // returns how much of the chunk fits on the current visual row, breaking to a new row before it if needed
+    fn soft_wrap_chunk(&mut self, cx: &mut Cx, token_type: TokenType, offset: usize, chunk: &[char]) -> usize {
+        let wrap_x = if let Some(wrap_x) = self._wrap_x {wrap_x}else {return chunk.len()};
+        // whitespace hangs over the edge instead of starting a row
+        if token_type == TokenType::Whitespace || token_type == TokenType::Newline {
+            return chunk.len()
+        }
+        let char_width = self._monospace_size.x;
+        let mut x = cx.get_rel_turtle_pos().x;
+        if x + char_width * (chunk.len() as f32) <= wrap_x {
+            return chunk.len()
+        }
+        if x > self._row_start_x + 0.5 * char_width {
+            self.draw_wrap_line(cx, offset - self._line_start_offset);
+            x = self._row_start_x;
+        }
+        (((wrap_x - x) / char_width) as usize).max(1).min(chunk.len())
+    }
+    
+    fn draw_line_chunk_highlights(&mut self, cx: &mut Cx, row_y: f32) {
         // highlighting the selection
         let hl_len = self._highlight_selection.len();
         if hl_len != 0 {
@@ -1185,7 +1638,7 @@ impl CodeEditor {
                     let max_x = self._line_chunk[bp + hl_len].0;
                     self.draw_token_highlight_quad(cx, Rect {
                         x: min_x,
-                        y: line_geom.walk.y + origin.y,
+                        y: row_y + origin.y,
                         w: max_x - min_x,
                         h: self._monospace_size.y,
                     });
@@ -1193,10 +1646,33 @@ impl CodeEditor {
             }
             self._line_chunk.truncate(0);
         }
-        
-        // search for all markings
-        self._line_geometry.push(line_geom);
-        self._line_largest_font = self.text.text_style.font_size;
+    }
+    
+    fn draw_hidden_line(&mut self, cx: &mut Cx) {
+        self._line_geometry.push(LineGeom {
+            walk: cx.get_rel_turtle_pos(),
+            font_size: self._line_largest_font,
+            was_folded: true,
+            was_hidden: true,
+            indent_id: 0.,
+            wraps: Vec::new(),
+            wrap_indent: 0
+        });
+        self._tokens_on_line = 0;
+        self._line_chunk.truncate(0);
+    }
+    
+    fn draw_fold_placeholder(&mut self, cx: &mut Cx, offset: usize) {
+        let chunk = [' ', '.', '.', '.'];
+        if let Some(geom) = cx.walk_turtle_right_no_wrap(
+            self._monospace_size.x * (chunk.len() as f32),
+            self._monospace_size.y,
+            self._scroll_pos
+        ) {
+            self.text.color = self.colors.comment;
+            // the placeholder maps to the first hidden line, clicking it unfolds
+            self.text.add_text(cx, geom.x, geom.y, offset, self._text_inst.as_mut().unwrap(), &chunk, | _, _, _, _ | {0.});
+        }
     }
     
     fn draw_indent_lines(&mut self, cx: &mut Cx, geom_y: f32, tabs: usize) {
@@ -1236,8 +1712,19 @@ impl CodeEditor {
             self.draw_paren_open(token_chunks_index, offset, next_char, chunk);
         }
         
+        // lines inside a fold only keep their geometry so rows still line up
+        if self.line_fold(self._line_geometry.len()) == LineFold::Hidden {
+            match token_type {
+                TokenType::ParenClose => self.draw_paren_close(cx, token_chunks_index, offset, next_char, chunk),
+                TokenType::Newline => self.draw_hidden_line(cx),
+                _ => ()
+            }
+            return
+        }
+        
         // do indent depth walking
         if self._tokens_on_line == 0 {
+            self._line_start_offset = offset;
             let font_scale = match token_type {
                 TokenType::Whitespace => {
                     let tabs = chunk.len()>>2;
@@ -1278,6 +1765,9 @@ impl CodeEditor {
             };
             self.set_font_scale(cx, font_scale);
         }
+        if self._tokens_on_line == 0 {
+            self.layout_line_bidi(cx, flat_text, offset);
+        }
         // colorise indent lines properly
         if self._tokens_on_line < 4 {
             match token_type {
@@ -1296,15 +1786,53 @@ impl CodeEditor {
                 _ => ()
             }
         }
+        // soft wrapping can spread the chunk over several visual rows
+        let mut part_start = 0;
+        loop {
+            let part_len = self.soft_wrap_chunk(cx, token_type, offset + part_start, &chunk[part_start..]);
+            self.draw_chunk_part(cx, token_chunk, chunk, part_start, part_len, message_cursors);
+            part_start += part_len;
+            if part_start >= chunk.len() {
+                break
+            }
+        }
+        if self._tokens_on_line == 0 && token_type == TokenType::Whitespace && self._wrap_x.is_some() {
+            // continuation rows line up with the indentation, but keep most of the row usable
+            let wrap_x = self._wrap_x.unwrap();
+            self._wrap_indent_x = cx.get_rel_turtle_pos().x.min(self.line_number_width + 0.5 * (wrap_x - self.line_number_width));
+        }
+        self._tokens_on_line += 1;
+        
+        // Do all the Paren matching highlighting drawing
+        if token_chunk.token_type == TokenType::ParenClose {
+            self.draw_paren_close(cx, token_chunks_index, offset, next_char, chunk);
+        }
+        else {
+            if token_type == TokenType::Newline {
+                if self.line_fold(self._line_geometry.len()) == LineFold::Folded {
+                    self.draw_fold_placeholder(cx, offset + 1);
+                }
+                self.draw_new_line(cx);
+            }
+        }
+    }
+    
+    // the part of a chunk on one visual row, which is all of it without soft wrapping
+    fn draw_chunk_part(&mut self, cx: &mut Cx, token_chunk: &TokenChunk, chunk: &[char], part_start: usize, part_len: usize, message_cursors: &Vec<TextCursor>) {
+        let token_type = token_chunk.token_type;
+        let next_char = token_chunk.next;
+        let part = &chunk[part_start..(part_start + part_len)];
+        let part_offset = token_chunk.offset + part_start;
         // lets check if the geom is visible
         if let Some(geom) = cx.walk_turtle_right_no_wrap(
-            self._monospace_size.x * (chunk.len() as f32),
+            self._monospace_size.x * (part.len() as f32),
             self._monospace_size.y,
             self._scroll_pos
         ) {
             let mut mark_spaces = 0.0;
-            // determine chunk color
-            self.text.color = match token_type {
+            // determine chunk color, some tokens draw indent lines, highlights or paren pairs too
+            self.text.color = self.colors.token_color(token_type);
+            match token_type {
                 TokenType::Whitespace => {
                     if self._tokens_on_line == 0 && chunk[0] == ' ' {
                         let tabs = chunk.len()>>2;
@@ -1316,7 +1844,6 @@ impl CodeEditor {
                     else if next_char == '\n' {
                         mark_spaces = 1.0;
                     }
-                    self.colors.whitespace
                 },
                 TokenType::Newline => {
                     if self._tokens_on_line == 0 {
@@ -1327,64 +1854,17 @@ impl CodeEditor {
                         self._last_tabs = self._newline_tabs;
                         self._newline_tabs = 0;
                     }
-                    self.colors.whitespace
                 },
-                TokenType::BuiltinType => self.colors.keyword,
-                TokenType::Keyword => self.colors.keyword,
-                TokenType::Bool => self.colors.keyword,
-                TokenType::Error => self.colors.error,
-                TokenType::Warning =>self.colors.warning,
-                TokenType::Defocus => self.colors.defocus,
-                TokenType::Flow => {
-                    self.colors.flow
-                }
-                TokenType::Looping => {
-                    self.colors.looping
-                }
-                TokenType::TypeDef => {
-                    self.colors.keyword
-                }
-                TokenType::Fn => {
-                    self.colors.keyword
-                }
-                TokenType::Identifier => {
+                TokenType::Identifier | TokenType::Call | TokenType::TypeName | TokenType::ThemeName | TokenType::ShaderDf => {
                     if chunk == &self._highlight_token[0..] {
                         self.draw_token_highlight_quad(cx, geom);
-                        
                     }
-                    self.colors.identifier
-                }
-                TokenType::Call => {
-                    if chunk == &self._highlight_token[0..] {
-                        self.draw_token_highlight_quad(cx, geom);
-                    }
-                   self.colors.call
                 },
-                TokenType::TypeName => {
-                    if chunk == &self._highlight_token[0..] {
-                        self.draw_token_highlight_quad(cx, geom);
-                    }
-                    self.colors.type_name
-                },
-                TokenType::ThemeName => {
-                    if chunk == &self._highlight_token[0..] {
-                        self.draw_token_highlight_quad(cx, geom);
-                    }
-                    self.colors.theme_name
-                },
-                TokenType::Regex => self.colors.string,
-                TokenType::String => self.colors.string,
-                TokenType::Number => self.colors.number,
-                TokenType::CommentMultiBegin => self.colors.comment,
-                TokenType::CommentMultiEnd =>self.colors.comment,
-                TokenType::CommentLine => self.colors.comment,
-                TokenType::CommentChunk => self.colors.comment,
                 TokenType::ParenOpen => {
                     let depth = self._paren_stack.len();
                     self._paren_stack.last_mut().unwrap().geom_open = Some(geom);
-                    match depth % 2 {
-                        0 => self.colors.paren_d1,
-                        _ => self.colors.paren_d2,
+                    if depth % 2 == 1 {
+                        self.text.color = self.colors.paren_d2;
                     }
                 },
                 TokenType::ParenClose => {
@@ -1396,41 +1876,37 @@ impl CodeEditor {
                         self.paren_pair.draw_quad_abs(cx, geom);
                     }
                     let depth = self._paren_stack.len();
-                    match depth % 2 {
-                        0 => self.colors.paren_d1,
-                        _ => self.colors.paren_d2,
-                        //_=>self.colors.paren_d3
+                    if depth % 2 == 1 {
+                        self.text.color = self.colors.paren_d2;
                     }
                 },
-                TokenType::Operator => self.colors.operator,
-                TokenType::Namespace => self.colors.operator,
-                TokenType::Hash => self.colors.operator,
-                TokenType::Delimiter => self.colors.delimiter,
-                TokenType::Colon => self.colors.delimiter,
-                TokenType::Splat => self.colors.operator,
-                TokenType::Eof => self.colors.unexpected,
-                TokenType::Unexpected => self.colors.unexpected
-            };
-            
-            if self._tokens_on_line == 0 {
+                _ => ()
+            }
+        
+            // a line counts once, also when its first token wraps over several rows
+            if self._tokens_on_line == 0 && part_start == 0 {
                 self._visible_lines += 1;
                 //self._line_was_visible = true;
             }
-            
+        
             let cursors = &self.cursors.set;
             //let messages_cursors = &text_buffer.message_cursors;
             let last_cursor = self.cursors.last_cursor;
             let draw_cursors = &mut self._draw_cursors;
             let draw_messages = &mut self._draw_messages;
             let height = self._monospace_size.y;
-            
+            // the bidi levels of the part, and the cells the line puts its chars on
+            let part_index = part_offset - self._line_start_offset;
+            let levels = self._line_bidi_levels.get(part_index..(part_index + part.len())).unwrap_or(&[]);
+            let cells = self._line_bidi_cells.get(part_index..(part_index + part.len())).unwrap_or(&[]);
+        
             // actually generate the GPU data for the text
             let z = 2.0; // + self._paren_stack.len() as f32;
             //self.text.z = z;
             if self._highlight_selection.len() > 0 { // slow loop
                 //let draw_search = &mut self._draw_search;
                 let line_chunk = &mut self._line_chunk;
-                self.text.add_text(cx, geom.x, geom.y, offset, self._text_inst.as_mut().unwrap(), &chunk, | ch, offset, x, w | {
+                Self::add_text_bidi(&mut self.text, cx, geom, part_offset, self._text_inst.as_mut().unwrap(), part, part_index, levels, cells, | ch, offset, x, w | {
                     line_chunk.push((x, ch));
                     //draw_search.mark_text_select_only(cursors, offset, x, geom.y, w, height);
                     draw_messages.mark_text_select_only(message_cursors, offset, x, geom.y, w, height);
@@ -1438,23 +1914,53 @@ impl CodeEditor {
                 });
             }
             else { // fast loop
-                self.text.add_text(cx, geom.x, geom.y, offset, self._text_inst.as_mut().unwrap(), &chunk, | ch, offset, x, w | {
+                Self::add_text_bidi(&mut self.text, cx, geom, part_offset, self._text_inst.as_mut().unwrap(), part, part_index, levels, cells, | ch, offset, x, w | {
                     draw_messages.mark_text_select_only(message_cursors, offset, x, geom.y, w, height);
                     draw_cursors.mark_text_with_cursor(cursors, ch, offset, x, geom.y, w, height, z, last_cursor, mark_spaces)
                 });
             }
         }
-        self._tokens_on_line += 1;
-        
-        // Do all the Paren matching highlighting drawing
-        if token_chunk.token_type == TokenType::ParenClose {
-            self.draw_paren_close(cx, token_chunks_index, offset, next_char, chunk);
+    }
+    
+    // right to left text in strings and comments lays out in visual order, with the line as a
+    // left to right paragraph. chars only move between tokens when the line fits on one row,
+    // soft wrapped lines reorder the chars within each token
+    fn layout_line_bidi(&mut self, cx: &Cx, flat_text: &[char], line_start: usize) {
+        self._line_bidi_levels.truncate(0);
+        self._line_bidi_cells.truncate(0);
+        let line_end = flat_text[line_start..].iter().position( | c | *c == '\n').map_or(flat_text.len(), | pos | line_start + pos + 1);
+        let line = &flat_text[line_start..line_end];
+        if !bidi_has_rtl(line) {
+            return
         }
-        else {
-            if token_type == TokenType::Newline {
-                self.draw_new_line(cx);
+        self._line_bidi_levels = bidi_levels(line, Some(0));
+        if let Some(wrap_x) = self._wrap_x {
+            if cx.get_rel_turtle_pos().x + self._monospace_size.x * (line.len() as f32) > wrap_x {
+                return
             }
         }
+        self._line_bidi_cells.resize(line.len(), 0);
+        for (cell, index) in bidi_visual_order(&self._line_bidi_levels).iter().enumerate() {
+            self._line_bidi_cells[*index] = cell;
+        }
+    }
+    
+    // every run of one level lands on the cells the line puts it on, which are contiguous
+    fn add_text_bidi<F>(text: &mut Text, cx: &mut Cx, geom: Rect, offset: usize, aligned: &mut AlignedInstance, part: &[char], part_index: usize, levels: &[u8], cells: &[usize], mut char_callback: F)
+    where F: FnMut(char, usize, f32, f32) -> f32
+    {
+        if levels.is_empty() {
+            return text.add_text(cx, geom.x, geom.y, offset, aligned, part, char_callback)
+        }
+        if cells.is_empty() {
+            return text.add_text_with_levels(cx, geom.x, geom.y, offset, aligned, part, levels, char_callback)
+        }
+        let cell_width = geom.w / (part.len() as f32);
+        for run in bidi_level_runs(levels) {
+            let first_cell = cells[run.clone()].iter().cloned().min().unwrap();
+            let x = geom.x + (first_cell as f32 - part_index as f32) * cell_width;
+            text.add_text_with_levels(cx, x, geom.y, offset + run.start, aligned, &part[run.clone()], &levels[run], &mut char_callback);
+        }
     }
     
     fn draw_token_highlight_quad(&mut self, cx: &mut Cx, geom: Rect) {
@@ -1558,12 +2064,14 @@ impl CodeEditor {
             self.line_number_text.end_text(cx, self._line_number_inst.as_ref().unwrap());
         }
         
+        self.draw_line_marks(cx, text_buffer);
         // unmatched highlighting
         self.draw_paren_unmatched(cx);
         self.draw_cursors(cx);
         //self.do_selection_animations(cx);
         self.draw_selections(cx);
         self.draw_message_markers(cx, text_buffer);
+        self.draw_minimap(cx, text_buffer);
         
         // inject a final page
         self._final_fill_height = cx.get_height_total() - self._monospace_size.y;
@@ -1573,6 +2081,20 @@ impl CodeEditor {
         self.do_selection_scrolling(cx, text_buffer);
         self.place_ime_and_draw_cursor_row(cx);
         self.set_indent_line_highlight_id(cx);
+        self.draw_vim_status_line(cx);
+        self.draw_completion(cx);
+        self.hover.draw_code_hover(cx);
+        
+        // screen readers get the line the cursor is on
+        let pos = self.cursors.get_last_cursor_text_pos(text_buffer);
+        cx.add_access(AccessNode {
+            role: AccessRole::TextEditor,
+            label: "Code editor".to_string(),
+            value: text_buffer.lines.get(pos.row).map_or(String::new(), | line | line.iter().collect()),
+            area: self._view_area,
+            actions: vec![AccessAction::Focus],
+            ..Default::default()
+        });
         
         self.view.end_view(cx);
         
@@ -1590,6 +2112,7 @@ impl CodeEditor {
         let offset = text_buffer.messages.jump_to_offset;
         // make one cursor, and start scrolling towards it
         self.cursors.clear_and_set_last_cursor_head_and_tail(offset, text_buffer);
+        self.reveal_cursors(cx, text_buffer);
         self.scroll_last_cursor_visible(cx, text_buffer, self._final_fill_height * 0.8);
         self.view.redraw_view_area(cx);
     }
@@ -1609,6 +2132,122 @@ impl CodeEditor {
         }
     }
     
+    fn draw_line_marks(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        let width = cx.get_width_total().max(cx.get_turtle_bounds().x) - self.line_number_width;
+        let view_top = self._scroll_pos.y;
+        let view_bottom = view_top + cx.get_height_total();
+        for mark in &self.line_marks {
+            let geom = if let Some(geom) = self._line_geometry.get(mark.line) {geom}else {continue};
+            if geom.was_hidden {
+                continue
+            }
+            let row_height = self._monospace_base.y * geom.font_size;
+            let char_width = self._monospace_base.x * geom.font_size;
+            if geom.walk.y > view_bottom || geom.walk.y + row_height * (geom.rows() as f32) < view_top {
+                continue
+            }
+            self.line_mark.color = mark.color;
+            if let Some((start, end)) = mark.cols {
+                // a column range can span several soft wrapped rows
+                let line_len = text_buffer.lines.get(mark.line).map_or(0, | line | line.len());
+                for row in 0..geom.rows() {
+                    let (row_start, row_end) = geom.row_range(row, line_len);
+                    let (start, end) = (start.max(row_start), end.min(row_end));
+                    if start >= end {
+                        continue
+                    }
+                    let vcol = geom.row_col(start).1;
+                    self.line_mark.draw_quad_rel(cx, Rect {
+                        x: self.line_number_width + (vcol as f32) * char_width,
+                        y: geom.walk.y + (row as f32) * row_height,
+                        w: ((end - start) as f32) * char_width,
+                        h: row_height
+                    });
+                }
+            }
+            else {
+                self.line_mark.draw_quad_rel(cx, Rect {
+                    x: self.line_number_width,
+                    y: geom.walk.y,
+                    w: width,
+                    h: row_height * (geom.rows() as f32)
+                });
+            }
+        }
+    }
+    
+    fn draw_minimap(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        self.minimap._rect = Rect::zero();
+        if !self.show_minimap || self._line_geometry.len() == 0 {
+            return
+        }
+        // the minimap sits left of the vertical scrollbar
+        let bar_size = if let Some(scroll_v) = &self.view.scroll_v {scroll_v.bar_size}else {0.};
+        let rect = Rect {
+            x: cx.get_width_total() - bar_size - self.minimap.width,
+            y: 0.,
+            w: self.minimap.width,
+            h: cx.get_height_total()
+        };
+        if rect.x < self.line_number_width + self.minimap.width {
+            return
+        }
+        // let long lines scroll out from under the minimap
+        let bounds = cx.get_turtle_bounds();
+        if bounds.x > rect.x {
+            cx.set_turtle_bounds(Vec2 {x: bounds.x + self.minimap.width, y: bounds.y});
+        }
+        let top = self.compute_line_from_rel_ypos(self._scroll_pos.y);
+        let bottom = self.compute_line_from_rel_ypos(self._scroll_pos.y + rect.h);
+        self.minimap.draw_code_minimap(cx, rect, text_buffer, &self.colors, (top, bottom), &self._highlight_selection, &self.cursors);
+    }
+    
+    // the first line at or below a y position in the scrolled content
+    fn compute_line_from_rel_ypos(&self, ypos: f32) -> usize {
+        let found = self._line_geometry.binary_search_by( | geom | {
+            if geom.walk.y < ypos {std::cmp::Ordering::Less}else {std::cmp::Ordering::Greater}
+        });
+        match found {
+            Ok(index) | Err(index) => index
+        }
+    }
+    
+    // scrolls so the line under a minimap drag ends up at the top of the view
+    fn scroll_to_minimap_pos(&mut self, cx: &mut Cx, rel_y: f32) {
+        let line = self.minimap.drag_top_line(rel_y).min(self._line_geometry.len().max(1) - 1);
+        if let Some(geom) = self._line_geometry.get(line) {
+            let scroll_pos = self.view.get_scroll_pos(cx);
+            self.view.set_scroll_pos(cx, Vec2 {x: scroll_pos.x, y: geom.walk.y - self.top_padding});
+            self.view.redraw_view_area(cx);
+        }
+    }
+    
+    // along the bottom, above the horizontal scrollbar
+    fn draw_vim_status_line(&mut self, cx: &mut Cx) {
+        let status = if let Some(vim) = &self.vim {vim.status_text()}else {return};
+        let origin = cx.get_turtle_origin();
+        let bar_h = if let Some(scroll_h) = &self.view.scroll_h {scroll_h.bar_size}else {0.};
+        let bar_v = if let Some(scroll_v) = &self.view.scroll_v {scroll_v.bar_size}else {0.};
+        let pos = Vec2 {
+            x: origin.x,
+            y: origin.y + cx.get_height_total() - bar_h - self.vim_status_line.height
+        };
+        let width = cx.get_width_total() - bar_v;
+        self.vim_status_line.draw_vim_status_line(cx, pos, width, &status);
+    }
+    
+    fn draw_completion(&mut self, cx: &mut Cx) {
+        if !self.completion.is_open() {
+            return
+        }
+        // hang the popup below the last cursor
+        if let Some(last_cursor) = self._draw_cursors.last_cursor {
+            let rc = self._draw_cursors.cursors[last_cursor];
+            let scroll_pos = self.view.get_scroll_pos(cx);
+            self.completion.draw_code_completion(cx, Vec2 {x: rc.x - scroll_pos.x, y: rc.y - scroll_pos.y + rc.h});
+        }
+    }
+    
     fn draw_message_markers(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
         let origin = cx.get_turtle_origin();
         let message_markers = &mut self._draw_messages.selections;
@@ -1688,7 +2327,7 @@ impl CodeEditor {
             if let Some(grid_select_corner) = self._grid_select_corner {
                 // self.cursors.grid_select(offset, text_buffer);
                 let pos = self.compute_grid_text_pos_from_abs(cx, select_scroll.abs);
-                self.cursors.grid_select(grid_select_corner, pos, text_buffer);
+                self.grid_select(grid_select_corner, pos, text_buffer);
             }
             else {
                 let offset = self.text.find_closest_offset(cx, &self._text_area, select_scroll.abs);
@@ -1794,9 +2433,10 @@ impl CodeEditor {
             let geom = &self._line_geometry[row];
             let mono_size = Vec2 {x: self._monospace_base.x * geom.font_size, y: self._monospace_base.y * geom.font_size};
             //self.text.get_monospace_size(cx, geom.font_size);
+            let (wrap_row, vcol) = geom.row_col(pos.col);
             let rect = Rect {
-                x: (pos.col as f32) * mono_size.x, // - self.line_number_width,
-                y: geom.walk.y - mono_size.y * 1. - 0.5 * height_pad,
+                x: (vcol as f32) * mono_size.x, // - self.line_number_width,
+                y: geom.walk.y + mono_size.y * (wrap_row as f32 - 1.) - 0.5 * height_pad,
                 w: mono_size.x * 4., // + self.line_number_width,
                 h: mono_size.y * 4. + height_pad
             };
@@ -1805,36 +2445,208 @@ impl CodeEditor {
         }
     }
     
+    // with soft wrapping the grid is made of visual rows, its positions are visual rows and columns
     fn compute_grid_text_pos_from_abs(&mut self, cx: &Cx, abs: Vec2) -> TextPos {
         //
         let rel = self.view.get_view_area(cx).abs_to_rel(cx, abs, false);
         let mut mono_size = Vec2::zero();
+        let mut visual_row = 0;
         for (row, geom) in self._line_geometry.iter().enumerate() {
+            if geom.was_hidden {
+                continue;
+            }
             //let geom = &self._line_geometry[pos.row];
             mono_size = Vec2 {x: self._monospace_base.x * geom.font_size, y: self._monospace_base.y * geom.font_size};
-            if rel.y < geom.walk.y || rel.y >= geom.walk.y && rel.y <= geom.walk.y + mono_size.y { // its on the right line
+            if rel.y < geom.walk.y || rel.y >= geom.walk.y && rel.y <= geom.walk.y + mono_size.y * (geom.rows() as f32) { // its on the right line
                 let col = ((rel.x - self.line_number_width).max(0.) / mono_size.x) as usize;
-                // do a dumb calc
-                return TextPos {row: row, col: col};
+                if self.soft_wrap == SoftWrap::Off {
+                    // do a dumb calc
+                    return TextPos {row: row, col: col};
+                }
+                let wrap_row = (((rel.y - geom.walk.y).max(0.) / mono_size.y) as usize).min(geom.rows() - 1);
+                return TextPos {row: visual_row + wrap_row, col: col}
             }
+            visual_row += geom.rows();
         }
         // otherwise the file is too short, lets use the last line
-        TextPos {row: self._line_geometry.len() - 1, col: (rel.x.max(0.) / mono_size.x) as usize}
+        let row = if self.soft_wrap == SoftWrap::Off {self._line_geometry.len() - 1}else {visual_row.max(1) - 1};
+        TextPos {row: row, col: (rel.x.max(0.) / mono_size.x) as usize}
     }
     
-    fn compute_offset_from_ypos(&mut self, cx: &Cx, ypos_abs: f32, text_buffer: &TextBuffer, end: bool) -> usize {
+    fn compute_row_from_ypos(&self, cx: &Cx, ypos_abs: f32) -> usize {
+        self.compute_wrap_row_from_ypos(cx, ypos_abs).0
+    }
+    
+    // the line and the visual row within that line
+    fn compute_wrap_row_from_ypos(&self, cx: &Cx, ypos_abs: f32) -> (usize, usize) {
         let rel = self.view.get_view_area(cx).abs_to_rel(cx, Vec2 {x: 0.0, y: ypos_abs}, false);
         let mut mono_size;
         // = Vec2::zero();
-        let end_col = if end {1 << 31}else {0};
         for (row, geom) in self._line_geometry.iter().enumerate() {
+            if geom.was_hidden {
+                continue;
+            }
             //let geom = &self._line_geometry[pos.row];
             mono_size = Vec2 {x: self._monospace_base.x * geom.font_size, y: self._monospace_base.y * geom.font_size};
-            if rel.y < geom.walk.y || rel.y >= geom.walk.y && rel.y <= geom.walk.y + mono_size.y { // its on the right line
-                return text_buffer.text_pos_to_offset(TextPos {row: row, col: end_col})
+            if rel.y < geom.walk.y || rel.y >= geom.walk.y && rel.y <= geom.walk.y + mono_size.y * (geom.rows() as f32) { // its on the right line
+                let wrap_row = (((rel.y - geom.walk.y).max(0.) / mono_size.y) as usize).min(geom.rows() - 1);
+                return (row, wrap_row)
+            }
+        }
+        (self._line_geometry.len().max(1) - 1, 0)
+    }
+    
+    fn compute_offset_from_ypos(&mut self, cx: &Cx, ypos_abs: f32, text_buffer: &TextBuffer, end: bool) -> usize {
+        let (row, wrap_row) = self.compute_wrap_row_from_ypos(cx, ypos_abs);
+        if row < self._line_geometry.len() && row < text_buffer.lines.len() {
+            let (start, end_col) = self._line_geometry[row].row_range(wrap_row, text_buffer.lines[row].len());
+            return text_buffer.text_pos_to_offset(TextPos {row: row, col: if end {end_col}else {start}})
+        }
+        let end_col = if end {1 << 31}else {0};
+        text_buffer.text_pos_to_offset(TextPos {row: row, col: end_col})
+    }
+    
+    // the visual row and column of an offset, counting the visual rows of all unhidden lines before it
+    fn compute_visual_pos(&self, offset: usize, text_buffer: &TextBuffer) -> TextPos {
+        let pos = text_buffer.offset_to_text_pos(offset);
+        let mut visual_row = 0;
+        for geom in self._line_geometry.iter().take(pos.row) {
+            if !geom.was_hidden {
+                visual_row += geom.rows();
             }
         }
-        return text_buffer.text_pos_to_offset(TextPos {row: self._line_geometry.len() - 1, col: end_col})
+        if let Some(geom) = self._line_geometry.get(pos.row) {
+            let (wrap_row, vcol) = geom.row_col(pos.col);
+            return TextPos {row: visual_row + wrap_row, col: vcol}
+        }
+        TextPos {row: visual_row, col: pos.col}
+    }
+    
+    // the line and row within that line of a visual row
+    fn compute_line_from_visual_row(&self, visual_row: usize) -> Option<(usize, usize)> {
+        let mut start = 0;
+        for (row, geom) in self._line_geometry.iter().enumerate() {
+            if geom.was_hidden {
+                continue;
+            }
+            if visual_row < start + geom.rows() {
+                return Some((row, visual_row - start))
+            }
+            start += geom.rows();
+        }
+        None
+    }
+    
+    fn grid_select_corner(&mut self, pos: TextPos, text_buffer: &TextBuffer) -> TextPos {
+        if self.soft_wrap == SoftWrap::Off {
+            return self.cursors.grid_select_corner(pos, text_buffer)
+        }
+        self.cursors.insert_undo_group += 1;
+        // the cursor end furthest away from pos, in visual rows and columns
+        let mut max_dist = 0.0;
+        let mut max_pos = TextPos {row: 0, col: 0};
+        for cursor in &self.cursors.set {
+            for end in &[cursor.head, cursor.tail] {
+                let end_pos = self.compute_visual_pos(*end, text_buffer);
+                let dist = end_pos.dist(&pos);
+                if dist >= max_dist {
+                    max_dist = dist;
+                    max_pos = end_pos;
+                }
+            }
+        }
+        max_pos
+    }
+    
+    fn grid_select(&mut self, start_pos: TextPos, end_pos: TextPos, text_buffer: &TextBuffer) -> bool {
+        if self.soft_wrap == SoftWrap::Off {
+            return self.cursors.grid_select(start_pos, end_pos, text_buffer)
+        }
+        self.cursors.insert_undo_group += 1;
+        let (left, right) = if start_pos.col < end_pos.col {(start_pos.col, end_pos.col)}
+        else {(end_pos.col, start_pos.col)};
+        
+        let (top, bottom) = if start_pos.row < end_pos.row {(start_pos.row, end_pos.row)}
+        else {(end_pos.row, start_pos.row)};
+        
+        let mut set = Vec::new();
+        for visual_row in top..(bottom + 1) {
+            let (row, wrap_row) = if let Some(line) = self.compute_line_from_visual_row(visual_row) {line}else {break};
+            if row >= text_buffer.lines.len() {
+                break
+            }
+            let line_len = text_buffer.lines[row].len();
+            let geom = &self._line_geometry[row];
+            let (_, row_end) = geom.row_range(wrap_row, line_len);
+            let left_col = geom.col_at(wrap_row, left, line_len);
+            let right_col = geom.col_at(wrap_row, right, line_len);
+            if left_col < row_end {
+                let offset = text_buffer.text_pos_to_offset(TextPos {row: row, col: 0});
+                let (head, tail) = if start_pos.col < end_pos.col {(right_col, left_col)}else {(left_col, right_col)};
+                set.push(TextCursor {
+                    head: offset + head,
+                    tail: offset + tail,
+                    max: right_col
+                });
+            }
+        }
+        let changed = self.cursors.set != set;
+        self.cursors.set = set;
+        self.cursors.last_cursor = 0;
+        changed
+    }
+    
+    // moves the cursors up or down over visual rows, keeping their visual column
+    fn move_visual_rows(&mut self, rows: isize, only_head: bool, text_buffer: &TextBuffer) {
+        let total_char_count = text_buffer.calc_char_count();
+        let line_geometry = &self._line_geometry;
+        self.cursors.move_heads_with(only_head, text_buffer, | cursor | {
+            let pos = text_buffer.offset_to_text_pos(cursor.head);
+            if pos.row >= line_geometry.len() || pos.row >= text_buffer.lines.len() {
+                return (cursor.head, cursor.max)
+            }
+            let (mut wrap_row, vcol) = line_geometry[pos.row].row_col(pos.col);
+            // max is the text column after horizontal moves, and the visual column after vertical ones
+            let goal = if cursor.max == pos.col {vcol}else {cursor.max};
+            let mut row = pos.row;
+            for _ in 0..rows.abs() {
+                if rows < 0 {
+                    if wrap_row > 0 {
+                        wrap_row -= 1;
+                        continue;
+                    }
+                    if let Some(prev) = (0..row).rev().find( | r | !line_geometry[*r].was_hidden) {
+                        row = prev;
+                        wrap_row = line_geometry[row].rows() - 1;
+                    }
+                    else {
+                        return (0, goal)
+                    }
+                }
+                else {
+                    if wrap_row + 1 < line_geometry[row].rows() {
+                        wrap_row += 1;
+                        continue;
+                    }
+                    let next = ((row + 1)..line_geometry.len().min(text_buffer.lines.len())).find( | r | !line_geometry[*r].was_hidden);
+                    if let Some(next) = next {
+                        row = next;
+                        wrap_row = 0;
+                    }
+                    else {
+                        return (total_char_count, goal)
+                    }
+                }
+            }
+            let geom = &line_geometry[row];
+            let line_len = text_buffer.lines[row].len();
+            let mut col = geom.col_at(wrap_row, goal, line_len);
+            // a cursor at the very end of a wrapped row would show up on the next one
+            if wrap_row + 1 < geom.rows() && col == geom.row_range(wrap_row, line_len).1 && col > 0 {
+                col -= 1;
+            }
+            (text_buffer.text_pos_to_offset(TextPos {row: row, col: col}), goal)
+        });
     }
     
     fn start_code_folding(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
@@ -1907,13 +2719,14 @@ impl CodeEditor {
         let pos = self.cursors.get_last_cursor_text_pos(text_buffer);
         let mut delta = 1;
         if pos.row > 0 && pos.row < self._line_geometry.len() {
-            let mut scan = pos.row - 1;
-            while scan >0 {
-                if !self._line_geometry[scan].was_folded {
+            // the first line counts too, it can be the fold that hides the lines in between
+            let mut scan = pos.row;
+            while scan > 0 {
+                scan -= 1;
+                if !self.line_is_skipped(scan) {
                     delta = pos.row - scan;
                     break;
                 }
-                scan -= 1;
             }
         };
         delta
@@ -1924,7 +2737,7 @@ impl CodeEditor {
         let mut delta = 1;
         let mut scan = pos.row + 1;
         while scan < self._line_geometry.len() {
-            if !self._line_geometry[scan].was_folded {
+            if !self.line_is_skipped(scan) {
                 delta = scan - pos.row;
                 break;
             }
@@ -1934,6 +2747,138 @@ impl CodeEditor {
     }
     
     
+    fn line_is_skipped(&self, line: usize) -> bool {
+        let geom = &self._line_geometry[line];
+        geom.was_hidden || self.line_fold(line) == LineFold::Hidden || self._anim_folding.state.is_folded() && geom.was_folded
+    }
+    
+    pub fn line_fold(&self, line: usize) -> LineFold {
+        if let Some(line_fold) = self._line_folds.get(line) {*line_fold} else {LineFold::None}
+    }
+    
+    fn update_fold_ranges(&mut self, text_buffer: &TextBuffer) {
+        if self._fold_ranges_id != text_buffer.token_chunks_id && text_buffer.token_chunks.len() > 0 {
+            self._fold_ranges_id = text_buffer.token_chunks_id;
+            self._fold_ranges = FoldRange::compute_fold_ranges(&text_buffer.token_chunks);
+            // an edit can remove a folded region, it just opens up again
+            let ranges = &self._fold_ranges;
+            let folded_len = self._folded_lines.len();
+            self._folded_lines.retain( | line | ranges.iter().any( | range | range.start_line == *line));
+            if folded_len != self._folded_lines.len() {
+                self._fold_change = true;
+            }
+        }
+        FoldRange::compute_line_folds(&self._fold_ranges, &self._folded_lines, text_buffer.lines.len(), &mut self._line_folds);
+    }
+    
+    fn fold_lines_changed(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        self._folded_lines.sort();
+        self._folded_lines.dedup();
+        FoldRange::compute_line_folds(&self._fold_ranges, &self._folded_lines, text_buffer.lines.len(), &mut self._line_folds);
+        self.move_cursors_out_of_folds(text_buffer);
+        self._fold_change = true;
+        self.view.redraw_view_area(cx);
+    }
+    
+    // a cursor that gets folded away moves to the line that hides it
+    fn move_cursors_out_of_folds(&mut self, text_buffer: &TextBuffer) {
+        let hidden_row = self.cursors.set.iter().map( | cursor | text_buffer.offset_to_text_pos(cursor.head).row).find( | row | self.line_fold(*row) == LineFold::Hidden);
+        if let Some(row) = hidden_row {
+            let range = self._fold_ranges.iter().find( | range | self.line_fold(range.start_line) == LineFold::Folded && range.contains_hidden_line(row));
+            if let Some(range) = range {
+                let offset = text_buffer.text_pos_to_offset(TextPos {row: range.start_line, col: 1 << 31});
+                self.cursors.clear_and_set_last_cursor_head_and_tail(offset, text_buffer);
+            }
+        }
+    }
+    
+    // and the other way around, a cursor that moves into a fold opens it
+    fn reveal_cursors(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        if self._folded_lines.len() == 0 {
+            return
+        }
+        let mut changed = false;
+        for cursor in &self.cursors.set {
+            let row = text_buffer.offset_to_text_pos(cursor.head).row;
+            if self.line_fold(row) != LineFold::Hidden {
+                continue;
+            }
+            let ranges = &self._fold_ranges;
+            let folded_len = self._folded_lines.len();
+            self._folded_lines.retain( | line | !ranges.iter().any( | range | range.start_line == *line && range.contains_hidden_line(row)));
+            changed |= folded_len != self._folded_lines.len();
+        }
+        if changed {
+            self.fold_lines_changed(cx, text_buffer);
+        }
+    }
+    
+    pub fn toggle_fold(&mut self, cx: &mut Cx, line: usize, text_buffer: &TextBuffer) -> bool {
+        match self.line_fold(line) {
+            LineFold::Open => self._folded_lines.push(line),
+            LineFold::Folded => self._folded_lines.retain( | folded | *folded != line),
+            _ => return false
+        }
+        self.fold_lines_changed(cx, text_buffer);
+        true
+    }
+    
+    // folds the innermost open region around the last cursor
+    pub fn fold_at_cursor(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        let row = self.cursors.get_last_cursor_text_pos(text_buffer).row;
+        let range = self._fold_ranges.iter().rev().find( | range | {
+            range.start_line <= row && row <= range.end_line && self.line_fold(range.start_line) == LineFold::Open
+        }).cloned();
+        if let Some(range) = range {
+            self.toggle_fold(cx, range.start_line, text_buffer);
+        }
+    }
+    
+    pub fn unfold_at_cursor(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        let row = self.cursors.get_last_cursor_text_pos(text_buffer).row;
+        let range = self._fold_ranges.iter().rev().find( | range | {
+            range.start_line <= row && row <= range.end_line && self.line_fold(range.start_line) == LineFold::Folded
+        }).cloned();
+        if let Some(range) = range {
+            self.toggle_fold(cx, range.start_line, text_buffer);
+        }
+    }
+    
+    pub fn fold_all(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        self._folded_lines = self._fold_ranges.iter().map( | range | range.start_line).collect();
+        self.fold_lines_changed(cx, text_buffer);
+    }
+    
+    pub fn unfold_all(&mut self, cx: &mut Cx, text_buffer: &TextBuffer) {
+        self._folded_lines.truncate(0);
+        self.fold_lines_changed(cx, text_buffer);
+    }
+    
+    // folds are stored by line, edits that add or remove lines above them move them along
+    fn begin_fold_edit(&self, text_buffer: &TextBuffer) -> (usize, usize) {
+        let start = self.cursors.set.iter().map( | cursor | cursor.order().0).min().unwrap_or(0);
+        (text_buffer.offset_to_text_pos(start).row, text_buffer.lines.len())
+    }
+    
+    fn end_fold_edit(&mut self, fold_edit: (usize, usize), text_buffer: &TextBuffer) {
+        let (row, old_line_count) = fold_edit;
+        let new_line_count = text_buffer.lines.len();
+        if new_line_count == old_line_count || self._folded_lines.len() == 0 {
+            return
+        }
+        for line in &mut self._folded_lines {
+            if *line > row {
+                if new_line_count > old_line_count {
+                    *line += new_line_count - old_line_count;
+                }
+                else {
+                    *line = line.saturating_sub(old_line_count - new_line_count).max(row);
+                }
+            }
+        }
+        self._fold_change = true;
+    }
+    
     fn compute_focussed_line_for_folding(&self, cx: &Cx, text_buffer: &TextBuffer) -> usize {
         let scroll = self.view.get_scroll_pos(cx);
         let rect = self.view.get_view_area(cx).get_rect(cx, false);
@@ -2000,8 +2945,38 @@ pub struct AnimSelect {
 pub struct LineGeom {
     walk: Vec2,
     was_folded: bool,
+    was_hidden: bool,
     font_size: f32,
-    indent_id: f32
+    indent_id: f32,
+    // the columns where soft wrapped rows start, and the indent of those rows in columns
+    wraps: Vec<usize>,
+    wrap_indent: usize
+}
+
+impl LineGeom {
+    fn rows(&self) -> usize {
+        self.wraps.len() + 1
+    }
+    
+    // the column range of a visual row of this line
+    fn row_range(&self, row: usize, line_len: usize) -> (usize, usize) {
+        let start = if row == 0 {0}else {self.wraps[row - 1].min(line_len)};
+        let end = if row < self.wraps.len() {self.wraps[row].min(line_len)}else {line_len};
+        (start, end)
+    }
+    
+    // the visual row and visual column of a text column
+    fn row_col(&self, col: usize) -> (usize, usize) {
+        let row = self.wraps.iter().take_while( | wrap | **wrap <= col).count();
+        if row == 0 {(0, col)}else {(row, self.wrap_indent + col - self.wraps[row - 1])}
+    }
+    
+    // the text column at a visual column of a visual row
+    fn col_at(&self, row: usize, vcol: usize, line_len: usize) -> usize {
+        let (start, end) = self.row_range(row, line_len);
+        let indent = if row == 0 {0}else {self.wrap_indent};
+        (start + vcol.max(indent) - indent).min(end)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -0,0 +1,92 @@
+use crate::textbuffer::*;
+
+// a foldable region, folding hides the lines between start_line and end_line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FoldRange {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineFold {
+    None,
+    Open,
+    Folded,
+    Hidden
+}
+
+impl FoldRange {
+    // brace pairs and multiline comments that have at least one line to hide
+    // lines are counted in newline tokens, the same way the editor draws them
+    pub fn compute_fold_ranges(token_chunks: &Vec<TokenChunk>) -> Vec<FoldRange> {
+        let mut token_lines = Vec::with_capacity(token_chunks.len());
+        let mut line = 0;
+        for token_chunk in token_chunks {
+            token_lines.push(line);
+            if token_chunk.token_type == TokenType::Newline {
+                line += 1;
+            }
+        }
+        let mut ranges = Vec::new();
+        let mut comment_start = None;
+        for (index, token_chunk) in token_chunks.iter().enumerate() {
+            let range = match token_chunk.token_type {
+                TokenType::ParenOpen => {
+                    let pair = token_chunk.pair_token;
+                    if pair > index && pair < token_chunks.len() {
+                        Some((token_lines[index], token_lines[pair]))
+                    }
+                    else {
+                        None
+                    }
+                },
+                TokenType::CommentMultiBegin => {
+                    comment_start = Some(token_lines[index]);
+                    None
+                },
+                TokenType::CommentMultiEnd => if let Some(start_line) = comment_start.take() {
+                    Some((start_line, token_lines[index]))
+                }
+                else {
+                    None
+                },
+                _ => None
+            };
+            if let Some((start_line, end_line)) = range {
+                if end_line > start_line + 1 {
+                    ranges.push(FoldRange {start_line: start_line, end_line: end_line});
+                }
+            }
+        }
+        // one range per line, the outermost one wins
+        ranges.sort_by( | a, b | a.start_line.cmp(&b.start_line).then(b.end_line.cmp(&a.end_line)));
+        ranges.dedup_by( | a, b | a.start_line == b.start_line);
+        ranges
+    }
+
+    pub fn contains_hidden_line(&self, line: usize) -> bool {
+        line > self.start_line && line < self.end_line
+    }
+
+    pub fn compute_line_folds(ranges: &Vec<FoldRange>, folded_lines: &Vec<usize>, line_count: usize, line_folds: &mut Vec<LineFold>) {
+        line_folds.truncate(0);
+        line_folds.resize(line_count, LineFold::None);
+        for range in ranges {
+            if range.start_line >= line_count {
+                break;
+            }
+            if line_folds[range.start_line] == LineFold::Hidden {
+                continue;
+            }
+            if folded_lines.contains(&range.start_line) {
+                line_folds[range.start_line] = LineFold::Folded;
+                for line in range.start_line + 1..range.end_line.min(line_count) {
+                    line_folds[line] = LineFold::Hidden;
+                }
+            }
+            else {
+                line_folds[range.start_line] = LineFold::Open;
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use render::*;
+use widget::*;
+
+// a small popup with the docs of the token under the mouse
+#[derive(Clone)]
+pub struct CodeHover {
+    pub view: View,
+    pub bg: Quad,
+    pub text: Text,
+
+    pub _token_offset: Option<usize>,
+    pub _doc: &'static str,
+    pub _abs_pos: Vec2,
+}
+
+impl CodeHover {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            view: View::proto_overlay(cx),
+            bg: Quad {
+                z: 10.,
+                ..Quad::proto(cx)
+            },
+            text: Text {
+                z: 10.1,
+                ..Text::proto(cx)
+            },
+            _token_offset: None,
+            _doc: "",
+            _abs_pos: Vec2::zero(),
+        }
+    }
+
+    pub fn layout_bg() -> LayoutId {uid!()}
+    pub fn text_style_doc() -> TextStyleId {uid!()}
+    pub fn color_bg() -> ColorId {uid!()}
+    pub fn color_text() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::layout_bg().set_base(cx, Layout {
+            padding: Padding {l: 6., t: 4., r: 6., b: 4.},
+            walk: Walk::wh(Width::Compute, Height::Compute),
+            ..Layout::default()
+        });
+        Self::text_style_doc().set_base(cx, Theme::text_style_fixed().base(cx));
+        Self::color_bg().set_base(cx, Theme::color_bg_normal().base(cx));
+        Self::color_text().set_base(cx, Theme::color_text_deselected_focus().base(cx));
+    }
+
+    pub fn is_open(&self) -> bool {
+        self._token_offset.is_some()
+    }
+
+    // shows doc for the token at token_offset, the popup hangs below abs_pos
+    pub fn open(&mut self, cx: &mut Cx, token_offset: usize, doc: &'static str, abs_pos: Vec2) {
+        if self._token_offset == Some(token_offset) {
+            return
+        }
+        self._token_offset = Some(token_offset);
+        self._doc = doc;
+        self._abs_pos = abs_pos;
+        self.view.redraw_view_area(cx);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        if self._token_offset.is_some() {
+            self._token_offset = None;
+            self.view.redraw_view_area(cx);
+        }
+    }
+
+    pub fn draw_code_hover(&mut self, cx: &mut Cx) {
+        if self._token_offset.is_none() {
+            return
+        }
+        if self.view.begin_view(cx, Layout {
+            abs_origin: Some(self._abs_pos),
+            ..Default::default()
+        }).is_err() {
+            return
+        }
+        self.bg.color = Self::color_bg().base(cx);
+        self.text.text_style = Self::text_style_doc().base(cx);
+        self.text.color = Self::color_text().base(cx);
+        let bg_inst = self.bg.begin_quad(cx, Self::layout_bg().base(cx));
+        for line in self._doc.lines() {
+            self.text.draw_text(cx, line);
+            cx.turtle_new_line();
+        }
+        self.bg.end_quad(cx, &bg_inst);
+        self.view.end_view(cx);
+    }
+}
@@ -0,0 +1,210 @@
+use render::*;
+
+use crate::textbuffer::*;
+use crate::textcursor::*;
+use crate::codeeditor::*;
+
+// a scaled down overview of the whole buffer along the right edge of the editor,
+// with the visible part, messages, selection matches and cursors marked on it
+#[derive(Clone)]
+pub struct CodeMinimap {
+    pub bg: Quad,
+    pub token: Quad,
+    pub viewport: Quad,
+    pub marker: Quad,
+    pub width: f32,
+    pub max_line_height: f32,
+    pub char_width: f32,
+
+    pub _rect: Rect,
+    pub _line_height: f32,
+    pub _viewport_rect: Rect,
+    pub _drag_offset: Option<f32>,
+    pub _match_lines: Vec<usize>,
+    pub _match_mutation_id: u64,
+    pub _match_chunk: Vec<char>,
+}
+
+impl CodeMinimap {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            bg: Quad {
+                z: 9.,
+                do_h_scroll: false,
+                do_v_scroll: false,
+                ..Quad::proto(cx)
+            },
+            token: Quad {
+                z: 9.1,
+                do_h_scroll: false,
+                do_v_scroll: false,
+                ..Quad::proto(cx)
+            },
+            viewport: Quad {
+                z: 9.2,
+                do_h_scroll: false,
+                do_v_scroll: false,
+                ..Quad::proto(cx)
+            },
+            marker: Quad {
+                z: 9.3,
+                do_h_scroll: false,
+                do_v_scroll: false,
+                ..Quad::proto(cx)
+            },
+            width: 80.,
+            max_line_height: 2.,
+            char_width: 1.,
+            _rect: Rect::zero(),
+            _line_height: 0.,
+            _viewport_rect: Rect::zero(),
+            _drag_offset: None,
+            _match_lines: Vec::new(),
+            _match_mutation_id: 0,
+            _match_chunk: Vec::new(),
+        }
+    }
+
+    pub fn color_bg() -> ColorId {uid!()}
+    pub fn color_viewport() -> ColorId {uid!()}
+    pub fn color_match() -> ColorId {uid!()}
+    pub fn color_cursor() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::color_bg().set_base(cx, color256(37, 37, 37));
+        Self::color_viewport().set_base(cx, color256a(255, 255, 255, 24));
+        Self::color_match().set_base(cx, color256(160, 160, 200));
+        Self::color_cursor().set_base(cx, color256(176, 176, 176));
+    }
+
+    pub fn contains(&self, rel: Vec2) -> bool {
+        rel.x >= self._rect.x && rel.x <= self._rect.x + self._rect.w && rel.y >= self._rect.y && rel.y <= self._rect.y + self._rect.h
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self._drag_offset.is_some()
+    }
+
+    // grabbing the viewport drags it, anywhere else centers the viewport there
+    pub fn start_drag(&mut self, rel_y: f32) {
+        let vp = self._viewport_rect;
+        self._drag_offset = Some(if rel_y >= vp.y && rel_y <= vp.y + vp.h {rel_y - vp.y}else {0.5 * vp.h});
+    }
+
+    pub fn end_drag(&mut self) {
+        self._drag_offset = None;
+    }
+
+    // the line that should end up at the top of the view for a drag at rel_y
+    pub fn drag_top_line(&self, rel_y: f32) -> usize {
+        let y = rel_y - self._rect.y - self._drag_offset.unwrap_or(0.);
+        if self._line_height <= 0. {
+            return 0
+        }
+        (y.max(0.) / self._line_height) as usize
+    }
+
+    // rect is relative to the editor view and does not scroll, visible_lines is the range of lines in view
+    pub fn draw_code_minimap(&mut self, cx: &mut Cx, rect: Rect, text_buffer: &TextBuffer, colors: &CodeEditorColors, visible_lines: (usize, usize), highlight: &Vec<char>, cursors: &TextCursorSet) {
+        self._rect = rect;
+        let line_count = text_buffer.lines.len().max(1);
+        self._line_height = (rect.h / (line_count as f32)).min(self.max_line_height);
+        let line_height = self._line_height;
+
+        // keep the minimap in its own draw call so it layers on top of the text
+        cx.new_instance_draw_call(&self.bg.shader, 0);
+        self.bg.color = Self::color_bg().base(cx);
+        self.bg.draw_quad_rel(cx, rect);
+
+        // the tokens, lines that land on the same pixel row as the one before are skipped
+        let max_cols = (rect.w / self.char_width) as usize;
+        let mut line = 0;
+        let mut col = 0;
+        let mut line_y = 0.;
+        let mut draw_line = true;
+        for token_chunk in &text_buffer.token_chunks {
+            match token_chunk.token_type {
+                TokenType::Newline => {
+                    line += 1;
+                    col = 0;
+                    let y = (line as f32 * line_height).floor();
+                    draw_line = y > line_y;
+                    if draw_line {
+                        line_y = y;
+                    }
+                    continue;
+                },
+                TokenType::Whitespace | TokenType::Eof => {
+                    col += token_chunk.len;
+                    continue;
+                },
+                _ => ()
+            }
+            if draw_line && col < max_cols {
+                let len = token_chunk.len.min(max_cols - col);
+                self.token.color = colors.token_color(token_chunk.token_type);
+                self.token.draw_quad_rel(cx, Rect {
+                    x: rect.x + (col as f32) * self.char_width,
+                    y: rect.y + line_y,
+                    w: (len as f32) * self.char_width,
+                    h: (line_height * 0.8).max(1.)
+                });
+            }
+            col += token_chunk.len;
+        }
+        
+        // the part of the buffer that is in view
+        let (top, bottom) = visible_lines;
+        self._viewport_rect = Rect {
+            x: rect.x,
+            y: rect.y + (top as f32) * line_height,
+            w: rect.w,
+            h: ((bottom.max(top + 1) - top) as f32 * line_height).max(4.)
+        };
+        self.viewport.color = Self::color_viewport().base(cx);
+        self.viewport.draw_quad_rel(cx, self._viewport_rect);
+
+        // messages and selection matches are ticks on the right edge
+        let tick_w = 4.;
+        let tick_h = line_height.max(2.);
+        if text_buffer.messages.mutation_id == text_buffer.mutation_id {
+            for (index, cursor) in text_buffer.messages.cursors.iter().enumerate() {
+                if let Some(body) = text_buffer.messages.bodies.get(index) {
+                    let line = text_buffer.offset_to_text_pos(cursor.head).row;
+                    self.marker.color = colors.marker_color(&body.level);
+                    self.marker.draw_quad_rel(cx, Rect {x: rect.x + rect.w - tick_w, y: rect.y + (line as f32) * line_height, w: tick_w, h: tick_h});
+                }
+            }
+        }
+
+        self.update_match_lines(text_buffer, highlight);
+        self.marker.color = Self::color_match().base(cx);
+        for line in &self._match_lines {
+            self.marker.draw_quad_rel(cx, Rect {x: rect.x + rect.w - 2. * tick_w, y: rect.y + (*line as f32) * line_height, w: tick_w, h: tick_h});
+        }
+
+        self.marker.color = Self::color_cursor().base(cx);
+        for cursor in &cursors.set {
+            let line = text_buffer.offset_to_text_pos(cursor.head).row;
+            self.marker.draw_quad_rel(cx, Rect {x: rect.x, y: rect.y + (line as f32) * line_height, w: rect.w, h: 1.});
+        }
+    }
+
+    // the lines containing the highlighted selection, only recomputed when the text or the selection changes
+    fn update_match_lines(&mut self, text_buffer: &TextBuffer, highlight: &Vec<char>) {
+        if self._match_mutation_id == text_buffer.mutation_id && self._match_chunk == *highlight {
+            return
+        }
+        self._match_mutation_id = text_buffer.mutation_id;
+        self._match_chunk = highlight.clone();
+        self._match_lines.truncate(0);
+        if highlight.len() == 0 {
+            return
+        }
+        for (row, line) in text_buffer.lines.iter().enumerate() {
+            if line.len() >= highlight.len() && line.windows(highlight.len()).any( | window | window == &highlight[..]) {
+                self._match_lines.push(row);
+            }
+        }
+    }
+}
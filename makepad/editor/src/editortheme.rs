@@ -1,10 +1,18 @@
 use render::*;
 use crate::codeeditor::*;
 use crate::codeicon::*;
+use crate::codecompletion::*;
+use crate::codehover::*;
+use crate::codeminimap::*;
+use crate::vim::*;
 
 pub fn set_editor_theme_values(cx: &mut Cx) {
     CodeEditor::theme(cx);
     CodeIcon::theme(cx);
+    CodeCompletion::theme(cx);
+    CodeHover::theme(cx);
+    CodeMinimap::theme(cx);
+    VimStatusLine::theme(cx);
 }
 
 pub fn set_dark_editor_theme(cx: &mut Cx) {
@@ -41,6 +49,9 @@ pub fn set_dark_editor_theme(cx: &mut Cx) {
     CodeEditor::color_call().set_base(cx, color256(220, 220, 174));
     CodeEditor::color_type_name().set_base(cx, color256(86, 201, 177));
     CodeEditor::color_theme_name().set_base(cx, color("gold"));
+    CodeEditor::color_shader_builtin().set_base(cx, color256(78, 201, 220));
+    CodeEditor::color_shader_storage().set_base(cx, color256(197, 134, 192));
+    CodeEditor::color_shader_df().set_base(cx, color256(230, 190, 110));
     
     CodeEditor::color_string().set_base(cx, color256(204, 145, 123));
     CodeEditor::color_number().set_base(cx, color256(182, 206, 170));
@@ -2,6 +2,7 @@ use render::*;
 
 use crate::textbuffer::*;
 use crate::codeeditor::*;
+use crate::codecompletion::*;
 
 #[derive(Clone)]
 pub struct JSEditor {
@@ -18,8 +19,8 @@ impl JSEditor {
         }
     }
     
-    pub fn handle_js_editor(&mut self, cx: &mut Cx, event: &mut Event,  text_buffer: &mut TextBuffer) -> CodeEditorEvent {
-        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer);
+    pub fn handle_js_editor(&mut self, cx: &mut Cx, event: &mut Event,  text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index);
         match ce {
             CodeEditorEvent::AutoFormat => {
                 let formatted = JSTokenizer::auto_format(text_buffer).out_lines;
@@ -31,7 +32,8 @@ impl JSEditor {
         ce
     }
     
-    pub fn draw_js_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
         if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0{
             let mut state = TokenizerState::new(&text_buffer.lines);
             let mut tokenizer = JSTokenizer::new();
@@ -45,6 +47,10 @@ impl JSEditor {
                 }
             }
         }
+    }
+    
+    pub fn draw_js_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
         
         if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
         
@@ -874,6 +880,7 @@ impl JSTokenizer {
                 },
                 // these are followeable by non unary operators
                 TokenType::Identifier | TokenType::BuiltinType | TokenType::TypeName | TokenType::ThemeName |
+                TokenType::ShaderBuiltin | TokenType::ShaderStorage | TokenType::ShaderDf |
                 TokenType::Call | TokenType::String | TokenType::Regex | TokenType::Number |
                 TokenType::Bool | TokenType::Unexpected | TokenType::Error | TokenType::Warning | TokenType::Defocus=> {
                     is_unary_operator = false;
@@ -0,0 +1,307 @@
+use render::*;
+
+use crate::textbuffer::*;
+use crate::codeeditor::*;
+use crate::codecompletion::*;
+
+#[derive(Clone)]
+pub struct JSONEditor {
+    pub code_editor: CodeEditor,
+}
+
+impl JSONEditor {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            code_editor: CodeEditor {
+                folding_depth: 3,
+                ..CodeEditor::proto(cx)
+            }
+        }
+    }
+
+    pub fn handle_json_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index);
+        match ce {
+            CodeEditorEvent::AutoFormat => {
+                let formatted = JSONTokenizer::auto_format(text_buffer).out_lines;
+                self.code_editor.cursors.replace_lines_formatted(formatted, text_buffer);
+                self.code_editor.view.redraw_view_area(cx);
+            },
+            _ => ()
+        }
+        ce
+    }
+
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
+        if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0 {
+            let mut state = TokenizerState::new(&text_buffer.lines);
+            let mut tokenizer = JSONTokenizer::new();
+            let mut pair_stack = Vec::new();
+            loop {
+                let offset = text_buffer.flat_text.len();
+                let token_type = tokenizer.next_token(&mut state, &mut text_buffer.flat_text, &text_buffer.token_chunks);
+                TokenChunk::push_with_pairing(&mut text_buffer.token_chunks, &mut pair_stack, state.next, offset, text_buffer.flat_text.len(), token_type);
+                if token_type == TokenType::Eof {
+                    break
+                }
+            }
+        }
+    }
+
+    pub fn draw_json_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
+
+        if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
+
+        for (index, token_chunk) in text_buffer.token_chunks.iter_mut().enumerate() {
+            self.code_editor.draw_chunk(cx, index, &text_buffer.flat_text, token_chunk, &text_buffer.messages.cursors);
+        }
+
+        self.code_editor.end_code_editor(cx, text_buffer);
+    }
+}
+
+pub struct JSONTokenizer {
+}
+
+impl JSONTokenizer {
+    pub fn new() -> JSONTokenizer {
+        JSONTokenizer {}
+    }
+
+    pub fn next_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, _token_chunks: &Vec<TokenChunk>) -> TokenType {
+        state.advance_with_cur();
+        match state.cur {
+            '\0' => { // eof insert a terminating space and end
+                chunk.push(' ');
+                return TokenType::Eof
+            },
+            '\n' => {
+                chunk.push('\n');
+                return TokenType::Newline
+            },
+            ' ' | '\t' => {
+                chunk.push(state.cur);
+                while state.next == ' ' || state.next == '\t' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Whitespace
+            },
+            '"' => {
+                chunk.push(state.cur);
+                Self::parse_string_tail(state, chunk, '"');
+                return TokenType::String
+            },
+            '-' | '0'..='9' => {
+                chunk.push(state.cur);
+                Self::parse_number_tail(state, chunk);
+                return TokenType::Number
+            },
+            '{' | '[' => {
+                chunk.push(state.cur);
+                return TokenType::ParenOpen
+            },
+            '}' | ']' => {
+                chunk.push(state.cur);
+                return TokenType::ParenClose
+            },
+            ',' => {
+                chunk.push(state.cur);
+                return TokenType::Delimiter
+            },
+            ':' => {
+                chunk.push(state.cur);
+                return TokenType::Colon
+            },
+            'a'..='z' => {
+                let start = chunk.len();
+                chunk.push(state.cur);
+                while state.next_is_letter() {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return match &chunk[start..] {
+                    ['t', 'r', 'u', 'e'] | ['f', 'a', 'l', 's', 'e'] => TokenType::Bool,
+                    ['n', 'u', 'l', 'l'] => TokenType::Keyword,
+                    _ => TokenType::Unexpected
+                }
+            },
+            _ => {
+                chunk.push(state.cur);
+                return TokenType::Unexpected
+            }
+        }
+    }
+
+    // strings end at their quote or the end of the line
+    pub fn parse_string_tail<'a>(state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, quote: char) {
+        while state.next != '\0' && state.next != '\n' {
+            if state.next == '\\' {
+                chunk.push(state.next);
+                state.advance();
+                if state.next != '\0' && state.next != '\n' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+            }
+            else if state.next == quote {
+                chunk.push(state.next);
+                state.advance();
+                break;
+            }
+            else {
+                chunk.push(state.next);
+                state.advance();
+            }
+        }
+    }
+
+    pub fn parse_number_tail<'a>(state: &mut TokenizerState<'a>, chunk: &mut Vec<char>) {
+        while state.next_is_digit() || state.next == '.' || state.next == '_' {
+            chunk.push(state.next);
+            state.advance();
+        }
+        if state.next == 'e' || state.next == 'E' {
+            chunk.push(state.next);
+            state.advance();
+            if state.next == '+' || state.next == '-' {
+                chunk.push(state.next);
+                state.advance();
+            }
+            while state.next_is_digit() {
+                chunk.push(state.next);
+                state.advance();
+            }
+        }
+    }
+
+    // pretty prints nested data, RON tokenizes to the same shapes so it uses this too.
+    // a container goes multiline when it holds a , or : anywhere inside it
+    pub fn auto_format(text_buffer: &mut TextBuffer) -> FormatOutput {
+        let mut out = FormatOutput::new();
+        let mut tp = TokenParser::new(&text_buffer.flat_text, &text_buffer.token_chunks);
+
+        fn begin_token(out: &mut FormatOutput, first_on_line: &mut bool, indent: usize) {
+            if *first_on_line {
+                *first_on_line = false;
+                out.indent(indent);
+            }
+        }
+
+        let mut expanded: Vec<bool> = Vec::new();
+        let mut indent = 0;
+        let mut first_on_line = true;
+        let mut in_comment_line = false;
+        let mut in_comment_multi = false;
+        out.new_line();
+
+        while tp.advance() {
+            match tp.cur_type() {
+                TokenType::Eof => break,
+                TokenType::Whitespace => {
+                    if in_comment_line || in_comment_multi {
+                        if !first_on_line {
+                            out.extend(tp.cur_chunk());
+                        }
+                    }
+                    else if !first_on_line && tp.prev_type() != TokenType::ParenOpen && tp.next_type() != TokenType::Newline
+                        && tp.next_type() != TokenType::ParenClose && tp.next_type() != TokenType::Delimiter && tp.next_type() != TokenType::Colon {
+                        out.add_space();
+                    }
+                },
+                TokenType::Newline => {
+                    // only comments and top level items keep their line breaks
+                    if in_comment_line || in_comment_multi || expanded.len() == 0 && !first_on_line {
+                        in_comment_line = false;
+                        out.new_line();
+                        first_on_line = true;
+                    }
+                },
+                TokenType::ParenOpen => {
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                    let pair = tp.tokens[tp.index].pair_token;
+                    let end = if pair > tp.index {pair}else {tp.tokens.len()};
+                    let expand = tp.tokens[tp.index + 1..end].iter().any( | t | t.token_type == TokenType::Delimiter || t.token_type == TokenType::Colon);
+                    expanded.push(expand);
+                    if expand {
+                        indent += 4;
+                        out.new_line();
+                        first_on_line = true;
+                    }
+                },
+                TokenType::ParenClose => {
+                    if expanded.pop().unwrap_or(false) {
+                        indent = indent.max(4) - 4;
+                        if !first_on_line {
+                            out.strip_space();
+                            out.new_line();
+                            first_on_line = true;
+                        }
+                    }
+                    else {
+                        out.strip_space();
+                    }
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                },
+                TokenType::Delimiter => {
+                    out.strip_space();
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                    // a comment after the comma stays on its line
+                    let mut next = tp.index + 1;
+                    while next < tp.tokens.len() && tp.tokens[next].token_type == TokenType::Whitespace {
+                        next += 1;
+                    }
+                    let comment_follows = next < tp.tokens.len() && tp.tokens[next].token_type == TokenType::CommentLine;
+                    if *expanded.last().unwrap_or(&true) && !comment_follows {
+                        out.new_line();
+                        first_on_line = true;
+                    }
+                    else {
+                        out.add_space();
+                    }
+                },
+                TokenType::Colon => {
+                    out.strip_space();
+                    out.extend(tp.cur_chunk());
+                    out.add_space();
+                },
+                TokenType::CommentLine | TokenType::CommentMultiBegin => {
+                    if tp.cur_type() == TokenType::CommentLine {
+                        in_comment_line = true;
+                    }
+                    else {
+                        in_comment_multi = true;
+                    }
+                    if !first_on_line {
+                        out.add_space();
+                    }
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                },
+                TokenType::CommentMultiEnd => {
+                    in_comment_multi = false;
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                    if *expanded.last().unwrap_or(&false) {
+                        out.new_line();
+                        first_on_line = true;
+                    }
+                },
+                _ => {
+                    begin_token(&mut out, &mut first_on_line, indent);
+                    out.extend(tp.cur_chunk());
+                }
+            }
+        }
+        // end with a newline like the files we read
+        if out.out_lines.last().unwrap().len() > 0 {
+            out.new_line();
+        }
+        out
+    }
+}
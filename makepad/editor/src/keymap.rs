@@ -0,0 +1,413 @@
+use render::*;
+use serde::*;
+
+// everything a key binding can trigger, the code editor runs the editing commands and the app the rest
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    Undo,
+    Redo,
+    Cut,
+    SelectAll,
+    AutoFormat,
+    ShowCompletion,
+    FoldAtCursor,
+    UnfoldAtCursor,
+    OpenFilePicker,
+    OpenCommandPalette,
+    OpenSymbolPicker,
+    GoToDefinition,
+    ReloadWorkspaces,
+    FoldAll,
+    UnfoldAll,
+    ToggleSoftWrap,
+    DiffWithDisk,
+    DiffWithSyncTarget,
+    DiffMarkedFiles,
+    ToggleInlineDiff,
+}
+
+impl Command {
+    pub fn is_editor_command(&self) -> bool {
+        match self {
+            Command::Undo | Command::Redo | Command::Cut | Command::SelectAll | Command::AutoFormat
+                | Command::ShowCompletion | Command::FoldAtCursor | Command::UnfoldAtCursor => true,
+            _ => false
+        }
+    }
+}
+
+// one key press, primary is the platform shortcut modifier (logo on OSX, control elsewhere)
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyChord {
+    pub key_code: KeyCode,
+    pub primary: bool,
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+impl KeyChord {
+    pub fn parse(text: &str) -> Result<KeyChord, String> {
+        let mut chord = KeyChord {
+            key_code: KeyCode::Unknown,
+            primary: false,
+            shift: false,
+            control: false,
+            alt: false,
+            logo: false
+        };
+        // split on + but keep a + key, as in Mod++
+        let mut parts: Vec<&str> = text.split('+').collect();
+        if text.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let (key, modifiers) = parts.split_last().ok_or_else( || format!("empty key chord"))?;
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_ref() {
+                "mod" => chord.primary = true,
+                "shift" => chord.shift = true,
+                "ctrl" | "control" => chord.control = true,
+                "alt" | "option" => chord.alt = true,
+                "cmd" | "logo" | "super" | "win" | "meta" => chord.logo = true,
+                _ => return Err(format!("unknown modifier {} in {}", modifier, text))
+            }
+        }
+        chord.key_code = key_code_from_name(key).ok_or_else( || format!("unknown key {} in {}", key, text))?;
+        Ok(chord)
+    }
+
+    pub fn modifiers(&self, platform_type: &PlatformType) -> KeyModifiers {
+        let primary_logo = primary_is_logo(platform_type);
+        KeyModifiers {
+            shift: self.shift,
+            control: self.control || (self.primary && !primary_logo),
+            alt: self.alt,
+            logo: self.logo || (self.primary && primary_logo)
+        }
+    }
+
+    // a primary chord also takes the primary modifier of the other platforms, like the editor keys
+    // always did, so logo+z undoes on linux and ctrl+z on osx
+    pub fn matches(&self, platform_type: &PlatformType, key_code: &KeyCode, modifiers: &KeyModifiers) -> bool {
+        if self.key_code != *key_code {
+            return false
+        }
+        let own = self.modifiers(platform_type);
+        if own == *modifiers {
+            return true
+        }
+        if !self.primary || self.control || self.logo {
+            return false
+        }
+        KeyModifiers {control: own.logo, logo: own.control, ..own} == *modifiers
+    }
+
+    pub fn label(&self, platform_type: &PlatformType) -> String {
+        let modifiers = self.modifiers(platform_type);
+        let mut label = String::new();
+        if modifiers.control {label.push_str("Ctrl+")}
+        if modifiers.alt {label.push_str("Alt+")}
+        if modifiers.logo {label.push_str(if primary_is_logo(platform_type) {"Cmd+"}else {"Logo+"})}
+        if modifiers.shift {label.push_str("Shift+")}
+        label.push_str(key_code_name(&self.key_code));
+        label
+    }
+}
+
+// a command and the chords that have to be pressed after each other to run it
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBinding {
+    pub keys: Vec<KeyChord>,
+    pub command: Command
+}
+
+impl KeyBinding {
+    // chords are separated by spaces, "Mod+K Mod+0"
+    pub fn parse(keys: &str, command: Command) -> Result<KeyBinding, String> {
+        let mut chords = Vec::new();
+        for text in keys.split_whitespace() {
+            chords.push(KeyChord::parse(text)?);
+        }
+        if chords.len() == 0 {
+            return Err(format!("no keys for {:?}", command))
+        }
+        Ok(KeyBinding {keys: chords, command: command})
+    }
+
+    pub fn label(&self, platform_type: &PlatformType) -> String {
+        let labels: Vec<String> = self.keys.iter().map( | chord | chord.label(platform_type)).collect();
+        labels.join(" ")
+    }
+
+    fn same_keys(&self, platform_type: &PlatformType, keys: &[KeyChord]) -> bool {
+        self.keys.len() == keys.len() && self.starts_with(platform_type, keys)
+    }
+
+    fn starts_with(&self, platform_type: &PlatformType, keys: &[KeyChord]) -> bool {
+        self.keys.len() >= keys.len() && self.keys.iter().zip(keys.iter()).all( | (a, b) | {
+            a.matches(platform_type, &b.key_code, &b.modifiers(platform_type))
+        })
+    }
+}
+
+// how a binding reads in makepad_settings.ron, an empty keys string unbinds the command
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindingConfig {
+    pub keys: String,
+    pub command: Command
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyMapEvent {
+    None,
+    Pending,
+    Command(Command)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct KeyMap {
+    pub bindings: Vec<KeyBinding>,
+    pub _pending: Vec<KeyChord>
+}
+
+const DEFAULT_BINDINGS: &[(&str, Command)] = &[
+    ("Mod+Z", Command::Undo),
+    ("Mod+Shift+Z", Command::Redo),
+    ("Mod+X", Command::Cut),
+    ("Mod+A", Command::SelectAll),
+    ("Mod+Return", Command::AutoFormat),
+    ("Ctrl+Space", Command::ShowCompletion),
+    ("Mod+Shift+[", Command::FoldAtCursor),
+    ("Mod+Shift+]", Command::UnfoldAtCursor),
+    ("Mod+P", Command::OpenFilePicker),
+    ("Mod+Shift+P", Command::OpenCommandPalette),
+    ("Mod+Shift+O", Command::OpenSymbolPicker),
+    ("F12", Command::GoToDefinition),
+    ("Mod+R", Command::ReloadWorkspaces),
+    ("Mod+K Mod+0", Command::FoldAll),
+    ("Mod+K Mod+J", Command::UnfoldAll),
+];
+
+impl KeyMap {
+    pub fn default_keymap() -> KeyMap {
+        KeyMap {
+            bindings: DEFAULT_BINDINGS.iter().map( | (keys, command) | KeyBinding::parse(keys, *command).unwrap()).collect(),
+            _pending: Vec::new()
+        }
+    }
+
+    // the configured bindings replace the defaults of their command, returns the bindings that didn't parse
+    pub fn from_config(config: &[KeyBindingConfig]) -> (KeyMap, Vec<String>) {
+        let mut keymap = Self::default_keymap();
+        keymap.bindings.retain( | binding | !config.iter().any( | item | item.command == binding.command));
+        let mut errors = Vec::new();
+        let mut configured = Vec::new();
+        for item in config {
+            if item.keys.trim().len() == 0 {
+                continue;
+            }
+            match KeyBinding::parse(&item.keys, item.command) {
+                Ok(binding) => configured.push(binding),
+                Err(err) => errors.push(err)
+            }
+        }
+        // configured bindings come first so they win a conflict with a default
+        configured.append(&mut keymap.bindings);
+        keymap.bindings = configured;
+        (keymap, errors)
+    }
+
+    // the same keys bound to two commands, or keys that never get to the longer binding that starts with them
+    pub fn conflicts(&self, platform_type: &PlatformType) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (index, binding) in self.bindings.iter().enumerate() {
+            for other in &self.bindings[index + 1..] {
+                if binding.command == other.command {
+                    continue;
+                }
+                if binding.same_keys(platform_type, &other.keys) || other.same_keys(platform_type, &binding.keys) {
+                    conflicts.push(format!(
+                        "{} is bound to {:?} and {:?}, {:?} wins",
+                        binding.label(platform_type),
+                        binding.command,
+                        other.command,
+                        binding.command
+                    ));
+                }
+                else if binding.starts_with(platform_type, &other.keys) || other.starts_with(platform_type, &binding.keys) {
+                    let (short, long) = if binding.keys.len() < other.keys.len() {(binding, other)}else {(other, binding)};
+                    conflicts.push(format!(
+                        "{} for {:?} shadows {} for {:?}",
+                        short.label(platform_type),
+                        short.command,
+                        long.label(platform_type),
+                        long.command
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
+    // a menu key is a single primary modifier chord on a character key, uppercase means shift
+    pub fn menu_key(&self, command: Command) -> String {
+        for binding in &self.bindings {
+            if binding.command != command || binding.keys.len() != 1 {
+                continue;
+            }
+            let chord = &binding.keys[0];
+            if !chord.primary || chord.control || chord.alt || chord.logo {
+                continue;
+            }
+            let name = key_code_name(&chord.key_code);
+            if name.chars().count() != 1 {
+                continue;
+            }
+            if chord.shift {
+                if name.chars().all( | c | c.is_alphabetic()) {
+                    return name.to_string()
+                }
+                continue;
+            }
+            return name.to_lowercase()
+        }
+        String::new()
+    }
+
+    // modifier keys alone never take part, an unmatched chord after a prefix is tried on its own
+    pub fn handle_key_down(&mut self, cx: &Cx, ke: &KeyEvent) -> KeyMapEvent {
+        match ke.key_code {
+            KeyCode::Shift | KeyCode::Control | KeyCode::Alt | KeyCode::Logo => return KeyMapEvent::None,
+            _ => ()
+        }
+        let chord = KeyChord {
+            key_code: ke.key_code.clone(),
+            primary: false,
+            shift: ke.modifiers.shift,
+            control: ke.modifiers.control,
+            alt: ke.modifiers.alt,
+            logo: ke.modifiers.logo
+        };
+        self._pending.push(chord.clone());
+        let after_prefix = self._pending.len() > 1;
+        let event = self.match_pending(&cx.platform_type);
+        if event == KeyMapEvent::None && after_prefix {
+            self._pending = vec![chord];
+            return self.match_pending(&cx.platform_type)
+        }
+        event
+    }
+
+    fn match_pending(&mut self, platform_type: &PlatformType) -> KeyMapEvent {
+        if let Some(binding) = self.bindings.iter().find( | binding | binding.same_keys(platform_type, &self._pending)) {
+            let command = binding.command;
+            self._pending.truncate(0);
+            return KeyMapEvent::Command(command)
+        }
+        if self.bindings.iter().any( | binding | binding.starts_with(platform_type, &self._pending)) {
+            return KeyMapEvent::Pending
+        }
+        self._pending.truncate(0);
+        KeyMapEvent::None
+    }
+}
+
+fn primary_is_logo(platform_type: &PlatformType) -> bool {
+    match platform_type {
+        PlatformType::OSX => true,
+        _ => false
+    }
+}
+
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Backtick, "`"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::Minus, "-"),
+    (KeyCode::Equals, "="),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::KeyQ, "Q"),
+    (KeyCode::KeyW, "W"),
+    (KeyCode::KeyE, "E"),
+    (KeyCode::KeyR, "R"),
+    (KeyCode::KeyT, "T"),
+    (KeyCode::KeyY, "Y"),
+    (KeyCode::KeyU, "U"),
+    (KeyCode::KeyI, "I"),
+    (KeyCode::KeyO, "O"),
+    (KeyCode::KeyP, "P"),
+    (KeyCode::LBracket, "["),
+    (KeyCode::RBracket, "]"),
+    (KeyCode::Return, "Return"),
+    (KeyCode::KeyA, "A"),
+    (KeyCode::KeyS, "S"),
+    (KeyCode::KeyD, "D"),
+    (KeyCode::KeyF, "F"),
+    (KeyCode::KeyG, "G"),
+    (KeyCode::KeyH, "H"),
+    (KeyCode::KeyJ, "J"),
+    (KeyCode::KeyK, "K"),
+    (KeyCode::KeyL, "L"),
+    (KeyCode::Semicolon, ";"),
+    (KeyCode::Quote, "'"),
+    (KeyCode::Backslash, "\\"),
+    (KeyCode::KeyZ, "Z"),
+    (KeyCode::KeyX, "X"),
+    (KeyCode::KeyC, "C"),
+    (KeyCode::KeyV, "V"),
+    (KeyCode::KeyB, "B"),
+    (KeyCode::KeyN, "N"),
+    (KeyCode::KeyM, "M"),
+    (KeyCode::Comma, ","),
+    (KeyCode::Period, "."),
+    (KeyCode::Slash, "/"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::NumpadAdd, "+"),
+    (KeyCode::ArrowUp, "Up"),
+    (KeyCode::ArrowDown, "Down"),
+    (KeyCode::ArrowLeft, "Left"),
+    (KeyCode::ArrowRight, "Right"),
+];
+
+pub fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    match name.to_lowercase().as_ref() {
+        "esc" => return Some(KeyCode::Escape),
+        "enter" => return Some(KeyCode::Return),
+        _ => ()
+    }
+    KEY_NAMES.iter().find( | (_, key_name) | key_name.eq_ignore_ascii_case(name)).map( | (key_code, _) | key_code.clone())
+}
+
+pub fn key_code_name(key_code: &KeyCode) -> &'static str {
+    KEY_NAMES.iter().find( | (code, _) | code == key_code).map( | (_, name) | *name).unwrap_or("?")
+}
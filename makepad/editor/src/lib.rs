@@ -4,12 +4,36 @@ mod codeeditor;
 pub use crate::codeeditor::*;
 mod textcursor;
 pub use crate::textcursor::*;
+mod codefold;
+pub use crate::codefold::*;
+mod codecompletion;
+pub use crate::codecompletion::*;
+mod codehover;
+pub use crate::codehover::*;
+mod codeminimap;
+pub use crate::codeminimap::*;
+mod textdiff;
+pub use crate::textdiff::*;
+mod keymap;
+pub use crate::keymap::*;
+mod vim;
+pub use crate::vim::*;
+mod shadersyntax;
+pub use crate::shadersyntax::*;
 mod rusteditor;
 pub use crate::rusteditor::*;
 pub mod codeicon;
 pub use crate::codeicon::*;
 mod jseditor;
 pub use crate::jseditor::*;
+mod jsoneditor;
+pub use crate::jsoneditor::*;
+mod roneditor;
+pub use crate::roneditor::*;
+mod tomleditor;
+pub use crate::tomleditor::*;
+mod markdowneditor;
+pub use crate::markdowneditor::*;
 mod plaineditor;
 pub use crate::plaineditor::*;
 mod editortheme;
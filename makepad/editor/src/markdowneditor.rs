@@ -0,0 +1,187 @@
+use render::*;
+
+use crate::textbuffer::*;
+use crate::codeeditor::*;
+use crate::codecompletion::*;
+
+#[derive(Clone)]
+pub struct MarkdownEditor {
+    pub code_editor: CodeEditor,
+}
+
+impl MarkdownEditor {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            code_editor: CodeEditor {
+                folding_depth: 3,
+                soft_wrap: SoftWrap::ViewWidth,
+                ..CodeEditor::proto(cx)
+            }
+        }
+    }
+
+    pub fn handle_markdown_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index)
+    }
+
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
+        if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0 {
+            let mut state = TokenizerState::new(&text_buffer.lines);
+            let mut tokenizer = MarkdownTokenizer::new();
+            let mut pair_stack = Vec::new();
+            loop {
+                let offset = text_buffer.flat_text.len();
+                let token_type = tokenizer.next_token(&mut state, &mut text_buffer.flat_text, &text_buffer.token_chunks);
+                TokenChunk::push_with_pairing(&mut text_buffer.token_chunks, &mut pair_stack, state.next, offset, text_buffer.flat_text.len(), token_type);
+                if token_type == TokenType::Eof {
+                    break
+                }
+            }
+        }
+    }
+
+    pub fn draw_markdown_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
+
+        if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
+
+        for (index, token_chunk) in text_buffer.token_chunks.iter_mut().enumerate() {
+            self.code_editor.draw_chunk(cx, index, &text_buffer.flat_text, token_chunk, &text_buffer.messages.cursors);
+        }
+
+        self.code_editor.end_code_editor(cx, text_buffer);
+    }
+}
+
+pub struct MarkdownTokenizer {
+    pub in_code_block: bool,
+    pub in_heading: bool,
+    pub line_start: bool
+}
+
+impl MarkdownTokenizer {
+    pub fn new() -> MarkdownTokenizer {
+        MarkdownTokenizer {
+            in_code_block: false,
+            in_heading: false,
+            line_start: true
+        }
+    }
+
+    pub fn next_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, _token_chunks: &Vec<TokenChunk>) -> TokenType {
+        state.advance_with_cur();
+        let line_start = self.line_start;
+        if state.cur != ' ' && state.cur != '\t' {
+            self.line_start = state.cur == '\n';
+        }
+        match state.cur {
+            '\0' => { // eof insert a terminating space and end
+                chunk.push(' ');
+                return TokenType::Eof
+            },
+            '\n' => {
+                self.in_heading = false;
+                chunk.push('\n');
+                return TokenType::Newline
+            },
+            ' ' | '\t' => {
+                chunk.push(state.cur);
+                while state.next == ' ' || state.next == '\t' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Whitespace
+            },
+            '`' => {
+                chunk.push(state.cur);
+                if line_start && state.next == '`' {
+                    // a ``` fence, the rest of the line is the language
+                    while state.next == '`' {
+                        chunk.push(state.next);
+                        state.advance();
+                    }
+                    Self::parse_word_tail(state, chunk);
+                    self.in_code_block = !self.in_code_block;
+                    return TokenType::Keyword
+                }
+                if self.in_code_block {
+                    Self::parse_word_tail(state, chunk);
+                    return TokenType::String
+                }
+                while state.next != '\0' && state.next != '\n' {
+                    chunk.push(state.next);
+                    state.advance();
+                    if *chunk.last().unwrap() == '`' {
+                        break;
+                    }
+                }
+                return TokenType::String
+            },
+            _ if self.in_code_block => {
+                chunk.push(state.cur);
+                Self::parse_word_tail(state, chunk);
+                return TokenType::String
+            },
+            '#' if line_start => {
+                chunk.push(state.cur);
+                while state.next == '#' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                self.in_heading = true;
+                return TokenType::TypeName
+            },
+            '-' | '+' | '*' | '>' if line_start && (state.next == ' ' || state.cur == '>') => { // list bullets and quotes
+                chunk.push(state.cur);
+                return TokenType::Operator
+            },
+            '*' | '_' | '~' => { // emphasis
+                chunk.push(state.cur);
+                while state.next == state.cur {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Keyword
+            },
+            '[' | '(' => {
+                chunk.push(state.cur);
+                return TokenType::ParenOpen
+            },
+            ']' | ')' => {
+                chunk.push(state.cur);
+                return TokenType::ParenClose
+            },
+            '0'..='9' if line_start => { // numbered list
+                chunk.push(state.cur);
+                while state.next_is_digit() {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                if state.next == '.' {
+                    chunk.push(state.next);
+                    state.advance();
+                    return TokenType::Operator
+                }
+                Self::parse_word_tail(state, chunk);
+                return TokenType::Identifier
+            },
+            _ => {
+                chunk.push(state.cur);
+                Self::parse_word_tail(state, chunk);
+                if self.in_heading {
+                    return TokenType::TypeName
+                }
+                return TokenType::Identifier
+            }
+        }
+    }
+
+    fn parse_word_tail<'a>(state: &mut TokenizerState<'a>, chunk: &mut Vec<char>) {
+        while state.next != ' ' && state.next != '\t' && state.next != '\n' && state.next != '\0'
+            && state.next != '`' && state.next != '[' && state.next != ']' && state.next != '(' && state.next != ')' && state.next != '*' {
+            chunk.push(state.next);
+            state.advance();
+        }
+    }
+}
@@ -2,6 +2,7 @@ use render::*;
 
 use crate::textbuffer::*;
 use crate::codeeditor::*;
+use crate::codecompletion::*;
 
 #[derive(Clone)]
 pub struct PlainEditor {
@@ -13,18 +14,20 @@ impl PlainEditor {
         let editor = Self {
             code_editor: CodeEditor{
                 folding_depth: 3,
+                soft_wrap: SoftWrap::ViewWidth,
                 ..CodeEditor::proto(cx)
             }
         };
         editor
     }
     
-    pub fn handle_plain_editor(&mut self, cx: &mut Cx, event: &mut Event,  text_buffer: &mut TextBuffer) -> CodeEditorEvent {
-        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer);
+    pub fn handle_plain_editor(&mut self, cx: &mut Cx, event: &mut Event,  text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index);
         ce
     }
     
-    pub fn draw_plain_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
         if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0{
             let mut state = TokenizerState::new(&text_buffer.lines);
             let mut tokenizer = PlainTokenizer::new();
@@ -38,6 +41,10 @@ impl PlainEditor {
                 }
             }
         }
+    }
+    
+    pub fn draw_plain_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
         
         if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
         
@@ -0,0 +1,276 @@
+use render::*;
+
+use crate::textbuffer::*;
+use crate::codeeditor::*;
+use crate::codecompletion::*;
+use crate::jsoneditor::*;
+
+#[derive(Clone)]
+pub struct RONEditor {
+    pub code_editor: CodeEditor,
+}
+
+impl RONEditor {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            code_editor: CodeEditor {
+                folding_depth: 3,
+                ..CodeEditor::proto(cx)
+            }
+        }
+    }
+
+    pub fn handle_ron_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index);
+        match ce {
+            CodeEditorEvent::AutoFormat => {
+                let formatted = JSONTokenizer::auto_format(text_buffer).out_lines;
+                self.code_editor.cursors.replace_lines_formatted(formatted, text_buffer);
+                self.code_editor.view.redraw_view_area(cx);
+            },
+            _ => ()
+        }
+        ce
+    }
+
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
+        if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0 {
+            let mut state = TokenizerState::new(&text_buffer.lines);
+            let mut tokenizer = RONTokenizer::new();
+            let mut pair_stack = Vec::new();
+            loop {
+                let offset = text_buffer.flat_text.len();
+                let token_type = tokenizer.next_token(&mut state, &mut text_buffer.flat_text, &text_buffer.token_chunks);
+                TokenChunk::push_with_pairing(&mut text_buffer.token_chunks, &mut pair_stack, state.next, offset, text_buffer.flat_text.len(), token_type);
+                if token_type == TokenType::Eof {
+                    break
+                }
+            }
+        }
+    }
+
+    pub fn draw_ron_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
+
+        if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
+
+        for (index, token_chunk) in text_buffer.token_chunks.iter_mut().enumerate() {
+            self.code_editor.draw_chunk(cx, index, &text_buffer.flat_text, token_chunk, &text_buffer.messages.cursors);
+        }
+
+        self.code_editor.end_code_editor(cx, text_buffer);
+    }
+}
+
+pub struct RONTokenizer {
+    pub comment_single: bool,
+    pub comment_depth: usize
+}
+
+impl RONTokenizer {
+    pub fn new() -> RONTokenizer {
+        RONTokenizer {
+            comment_single: false,
+            comment_depth: 0
+        }
+    }
+
+    pub fn next_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, _token_chunks: &Vec<TokenChunk>) -> TokenType {
+        let start = chunk.len();
+        if self.comment_depth >0 { // parse comments, these nest like in rust
+            loop {
+                if state.next == '\0' {
+                    self.comment_depth = 0;
+                    return TokenType::CommentChunk
+                }
+                if state.next == '/' && !self.comment_single {
+                    chunk.push(state.next);
+                    state.advance();
+                    if state.next == '*' {
+                        chunk.push(state.next);
+                        state.advance();
+                        self.comment_depth += 1;
+                    }
+                }
+                else if state.next == '*' && !self.comment_single {
+                    chunk.push(state.next);
+                    state.advance();
+                    if state.next == '/' {
+                        self.comment_depth -= 1;
+                        chunk.push(state.next);
+                        state.advance();
+                        if self.comment_depth == 0 {
+                            return TokenType::CommentMultiEnd
+                        }
+                    }
+                }
+                else if state.next == '\n' {
+                    if self.comment_single {
+                        self.comment_depth = 0;
+                    }
+                    if (chunk.len() - start)>0 {
+                        return TokenType::CommentChunk
+                    }
+                    chunk.push(state.next);
+                    state.advance();
+                    return TokenType::Newline
+                }
+                else if state.next == ' ' {
+                    if (chunk.len() - start)>0 {
+                        return TokenType::CommentChunk
+                    }
+                    while state.next == ' ' {
+                        chunk.push(state.next);
+                        state.advance();
+                    }
+                    return TokenType::Whitespace
+                }
+                else {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+            }
+        }
+        state.advance_with_cur();
+        match state.cur {
+            '\0' => { // eof insert a terminating space and end
+                chunk.push(' ');
+                return TokenType::Eof
+            },
+            '\n' => {
+                chunk.push('\n');
+                return TokenType::Newline
+            },
+            ' ' | '\t' => {
+                chunk.push(state.cur);
+                while state.next == ' ' || state.next == '\t' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Whitespace
+            },
+            '/' => {
+                chunk.push(state.cur);
+                if state.next == '/' {
+                    chunk.push(state.next);
+                    state.advance();
+                    self.comment_depth = 1;
+                    self.comment_single = true;
+                    return TokenType::CommentLine
+                }
+                if state.next == '*' {
+                    chunk.push(state.next);
+                    state.advance();
+                    self.comment_depth = 1;
+                    self.comment_single = false;
+                    return TokenType::CommentMultiBegin
+                }
+                return TokenType::Unexpected
+            },
+            '"' => {
+                chunk.push(state.cur);
+                JSONTokenizer::parse_string_tail(state, chunk, '"');
+                return TokenType::String
+            },
+            '\'' => {
+                chunk.push(state.cur);
+                JSONTokenizer::parse_string_tail(state, chunk, '\'');
+                return TokenType::String
+            },
+            'r' if state.next == '#' || state.next == '"' => { // raw string
+                chunk.push(state.cur);
+                let mut hashes = 0;
+                while state.next == '#' {
+                    hashes += 1;
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                if state.next == '"' {
+                    chunk.push(state.next);
+                    state.advance();
+                    while state.next != '\0' && state.next != '\n' {
+                        chunk.push(state.next);
+                        state.advance_with_cur();
+                        if state.cur == '"' {
+                            let mut closing = 0;
+                            while closing < hashes && state.next == '#' {
+                                closing += 1;
+                                chunk.push(state.next);
+                                state.advance();
+                            }
+                            if closing == hashes {
+                                break;
+                            }
+                        }
+                    }
+                }
+                return TokenType::String
+            },
+            '-' | '+' | '0'..='9' => {
+                chunk.push(state.cur);
+                if state.cur == '0' && (state.next == 'x' || state.next == 'b' || state.next == 'o') {
+                    chunk.push(state.next);
+                    state.advance();
+                    while state.next_is_hex() || state.next == '_' {
+                        chunk.push(state.next);
+                        state.advance();
+                    }
+                }
+                else {
+                    JSONTokenizer::parse_number_tail(state, chunk);
+                }
+                return TokenType::Number
+            },
+            '(' | '{' | '[' => {
+                chunk.push(state.cur);
+                return TokenType::ParenOpen
+            },
+            ')' | '}' | ']' => {
+                chunk.push(state.cur);
+                return TokenType::ParenClose
+            },
+            ',' => {
+                chunk.push(state.cur);
+                return TokenType::Delimiter
+            },
+            ':' => {
+                chunk.push(state.cur);
+                return TokenType::Colon
+            },
+            '#' => {
+                chunk.push(state.cur);
+                if state.next == '!' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Hash
+            },
+            'A'..='Z' => { // struct and enum names
+                chunk.push(state.cur);
+                Self::parse_ident_tail(state, chunk);
+                return TokenType::TypeName
+            },
+            'a'..='z' | '_' => {
+                chunk.push(state.cur);
+                Self::parse_ident_tail(state, chunk);
+                return match &chunk[start..] {
+                    ['t', 'r', 'u', 'e'] | ['f', 'a', 'l', 's', 'e'] => TokenType::Bool,
+                    ['i', 'n', 'f'] => TokenType::Number,
+                    _ => if state.next == '(' {TokenType::Call} else {TokenType::Identifier}
+                }
+            },
+            _ => {
+                chunk.push(state.cur);
+                return TokenType::Unexpected
+            }
+        }
+    }
+
+    fn parse_ident_tail<'a>(state: &mut TokenizerState<'a>, chunk: &mut Vec<char>) {
+        while state.next_is_digit() || state.next_is_letter() || state.next == '_' {
+            chunk.push(state.next);
+            state.advance();
+        }
+    }
+}
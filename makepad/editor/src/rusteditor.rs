@@ -2,6 +2,8 @@ use render::*;
 
 use crate::textbuffer::*;
 use crate::codeeditor::*;
+use crate::codecompletion::*;
+use crate::shadersyntax::*;
 
 #[derive(Clone)]
 pub struct RustEditor {
@@ -17,20 +19,23 @@ impl RustEditor {
         editor
     }
       
-    pub fn handle_rust_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer) -> CodeEditorEvent {
-        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer);
-        match ce {
-            CodeEditorEvent::AutoFormat => {
-                let formatted = RustTokenizer::auto_format(text_buffer, false).out_lines;
-                self.code_editor.cursors.replace_lines_formatted(formatted, text_buffer);
-                self.code_editor.view.redraw_view_area(cx);
-            },
-            _ => ()
-        }
-        ce
+    // AutoFormat is passed on, the owner picks rustfmt or the built-in formatter
+    pub fn handle_rust_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index)
     }
     
-    pub fn draw_rust_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+    pub fn auto_format(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        let formatted = RustTokenizer::auto_format(text_buffer, false).out_lines;
+        self.apply_formatted(cx, text_buffer, formatted);
+    }
+    
+    pub fn apply_formatted(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer, formatted: Vec<Vec<char>>) {
+        self.code_editor.cursors.replace_lines_formatted(formatted, text_buffer);
+        self.code_editor.view.redraw_view_area(cx);
+    }
+    
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
         if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0 {
             let mut state = TokenizerState::new(&text_buffer.lines);
             let mut tokenizer = RustTokenizer::new();
@@ -44,6 +49,10 @@ impl RustEditor {
                 }
             }
         }
+    }
+    
+    pub fn draw_rust_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
         
         if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
         
@@ -57,18 +66,55 @@ impl RustEditor {
 
 pub struct RustTokenizer {
     pub comment_single: bool,
-    pub comment_depth: usize
+    pub comment_depth: usize,
+    pub paren_depth: usize,
+    // set after shader_ast until its open paren, then the depth the block closes at
+    pub shader_pending: bool,
+    pub shader_depth: Option<usize>
 }
 
 impl RustTokenizer {
     pub fn new() -> RustTokenizer {
         RustTokenizer {
             comment_single: false,
-            comment_depth: 0
+            comment_depth: 0,
+            paren_depth: 0,
+            shader_pending: false,
+            shader_depth: None
         }
     }
     
+    // rust tokens, with the shader words inside shader_ast!({..}) retyped
     pub fn next_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, token_chunks: &Vec<TokenChunk>) -> TokenType {
+        let start = chunk.len();
+        let token_type = self.next_rust_token(state, chunk, token_chunks);
+        match token_type {
+            TokenType::ParenOpen => {
+                if self.shader_pending {
+                    self.shader_pending = false;
+                    self.shader_depth = Some(self.paren_depth);
+                }
+                self.paren_depth += 1;
+            },
+            TokenType::ParenClose => {
+                self.paren_depth = self.paren_depth.max(1) - 1;
+                if self.shader_depth == Some(self.paren_depth) {
+                    self.shader_depth = None;
+                }
+            },
+            TokenType::Identifier | TokenType::Call if chunk[start..] == ['s', 'h', 'a', 'd', 'e', 'r', '_', 'a', 's', 't'] => {
+                self.shader_pending = true;
+            },
+            _ => if self.shader_depth.is_some() {
+                if let Some(shader_type) = shader_token_type(&chunk[start..], token_type) {
+                    return shader_type
+                }
+            }
+        }
+        token_type
+    }
+    
+    fn next_rust_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, token_chunks: &Vec<TokenChunk>) -> TokenType {
         let start = chunk.len();
         //chunk.truncate(0);
         if self.comment_depth >0 { // parse comments
@@ -1001,7 +1047,8 @@ impl RustTokenizer {
                     
                     is_unary_operator = true;
                 },
-                TokenType::Identifier | TokenType::BuiltinType | TokenType::TypeName | TokenType::ThemeName=> { // these dont reset the angle counter
+                TokenType::Identifier | TokenType::BuiltinType | TokenType::TypeName | TokenType::ThemeName |
+                TokenType::ShaderBuiltin | TokenType::ShaderStorage | TokenType::ShaderDf => { // these dont reset the angle counter
                     is_unary_operator = false;
                     
                     first_after_open = false;
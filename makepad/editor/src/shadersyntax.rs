@@ -0,0 +1,82 @@
+use crate::textbuffer::*;
+
+// the words that mean something special inside a shader_ast!({..}) block,
+// the builtins mirror CxShader::def_builtins and the df_ helpers CxShader::def_df
+
+const SHADER_BUILTINS: &[&'static str] = &[
+    "float", "int", "bool", "vec2", "vec3", "vec4", "mat2", "mat3", "mat4", "texture2d",
+    "abs", "acos", "all", "any", "asin", "atan", "ceil", "clamp", "cos", "cross", "degrees",
+    "dfdx", "dfdy", "distance", "dot", "equal", "exp", "exp2", "faceforward", "floor", "fmod",
+    "fract", "fwidth", "greater_than", "greater_than_equal", "inversesqrt", "length", "less_than",
+    "less_than_equal", "log", "log2", "matrix_comp_mult", "max", "min", "mix", "normalize", "not",
+    "not_equal", "pow", "radians", "reflect", "refract", "sample2d", "sign", "sin", "sizeof",
+    "smoothstep", "sqrt", "step", "tan"
+];
+
+const SHADER_STORAGE: &[&'static str] = &[
+    "Uniform", "UniformVw", "UniformCx", "Instance", "Geometry", "Texture", "Local", "Varying"
+];
+
+const SHADER_DF_DOCS: &[(&'static str, &'static str)] = &[
+    ("df_viewport", "df_viewport(pos: vec2) -> vec2\nStarts a distance field drawing at pos, resets the shape and the result color."),
+    ("df_antialias", "df_antialias(p: vec2) -> float\nThe antialiasing factor for p from its screen space derivatives."),
+    ("df_translate", "df_translate(x: float, y: float) -> vec2\nMoves the drawing position."),
+    ("df_rotate", "df_rotate(a: float, x: float, y: float)\nRotates the drawing position by a radians around x, y."),
+    ("df_scale", "df_scale(f: float, x: float, y: float)\nScales the drawing position by f around x, y."),
+    ("df_clear", "df_clear(color: vec4)\nBlends color over the whole result."),
+    ("df_calc_blur", "df_calc_blur(w: float) -> float\nCoverage for distance w, using the antialiasing and df_blur."),
+    ("df_fill_keep", "df_fill_keep(color: vec4) -> vec4\nFills the current shape and keeps it for more drawing."),
+    ("df_fill", "df_fill(color: vec4) -> vec4\nFills the current shape and starts a new one."),
+    ("df_stroke_keep", "df_stroke_keep(color: vec4, width: float) -> vec4\nStrokes the current shape and keeps it for more drawing."),
+    ("df_stroke", "df_stroke(color: vec4, width: float) -> vec4\nStrokes the current shape and starts a new one."),
+    ("df_glow_keep", "df_glow_keep(color: vec4, width: float) -> vec4\nAdds a glow around the current shape and keeps it."),
+    ("df_glow", "df_glow(color: vec4, width: float) -> vec4\nAdds a glow around the current shape and starts a new one."),
+    ("df_union", "df_union()\nCombines the last field with the shape before it."),
+    ("df_intersect", "df_intersect()\nIntersects the last field with the shape before it."),
+    ("df_subtract", "df_subtract()\nSubtracts the last field from the shape before it."),
+    ("df_gloop", "df_gloop(k: float)\nSmoothly unions the last field with the shape before it, k is the blend radius."),
+    ("df_blend", "df_blend(k: float)\nLinearly blends the last field with the shape before it."),
+    ("df_circle", "df_circle(x: float, y: float, r: float)\nAdds a circle with center x, y and radius r."),
+    ("df_box", "df_box(x: float, y: float, w: float, h: float, r: float)\nAdds a rounded box, r is the corner radius."),
+    ("df_rect", "df_rect(x: float, y: float, w: float, h: float)\nAdds a rectangle."),
+    ("df_move_to", "df_move_to(x: float, y: float)\nStarts a new path at x, y."),
+    ("df_line_to", "df_line_to(x: float, y: float)\nAdds a line from the last path position to x, y."),
+    ("df_close_path", "df_close_path()\nAdds a line back to the start of the path."),
+    ("df_hsv2rgb", "df_hsv2rgb(c: vec4) -> vec4\nConverts a hsv color to rgb, alpha is kept."),
+    ("df_rgb2hsv", "df_rgb2hsv(c: vec4) -> vec4\nConverts a rgb color to hsv, alpha is kept."),
+    ("df_iq_pal", "df_iq_pal(t: float, a: vec3, b: vec3, c: vec3, d: vec3) -> vec3\nA cosine color palette, a + b * cos(6.28318 * (c * t + d))."),
+    ("df_pos", "df_pos: vec2<Local>\nThe current drawing position."),
+    ("df_result", "df_result: vec4<Local>\nThe color drawn so far."),
+    ("df_shape", "df_shape: float<Local>\nThe distance to the current shape."),
+    ("df_field", "df_field: float<Local>\nThe distance to the last primitive."),
+    ("df_blur", "df_blur: float<Local>\nExtra blur width for fills and strokes."),
+    ("df_aa", "df_aa: float<Local>\nThe antialiasing factor set by df_viewport."),
+];
+
+// retypes identifiers inside a shader block, returns None for ordinary words
+pub fn shader_token_type(word: &[char], token_type: TokenType) -> Option<TokenType> {
+    match token_type {
+        TokenType::Identifier | TokenType::Call | TokenType::BuiltinType => {
+            if word.starts_with(&['d', 'f', '_']) {
+                return Some(TokenType::ShaderDf)
+            }
+            if SHADER_BUILTINS.iter().any( | b | b.chars().eq(word.iter().cloned())) {
+                return Some(TokenType::ShaderBuiltin)
+            }
+            None
+        },
+        TokenType::TypeName => {
+            if SHADER_STORAGE.iter().any( | s | s.chars().eq(word.iter().cloned())) {
+                return Some(TokenType::ShaderStorage)
+            }
+            None
+        },
+        _ => None
+    }
+}
+
+pub fn shader_hover_doc(word: &str) -> Option<&'static str> {
+    // the numbered palettes share the docs of df_iq_pal
+    let word = if word.starts_with("df_iq_pal") {"df_iq_pal"} else {word};
+    SHADER_DF_DOCS.iter().find( | (name, _) | *name == word).map( | (_, doc) | *doc)
+}
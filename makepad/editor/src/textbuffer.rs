@@ -1,4 +1,5 @@
  use render::*;
+use serde::*;
 
 use crate::textcursor::*;
 
@@ -132,7 +133,7 @@ impl TextPos {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum TextUndoGrouping {
     Space,
     Newline,
@@ -169,20 +170,50 @@ impl TextUndoGrouping {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TextUndo {
     pub ops: Vec<TextOp>,
     pub grouping: TextUndoGrouping,
     pub cursors: TextCursorSet
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TextOp {
     pub start: usize,
     pub len: usize,
+    #[serde(with = "lines_as_strings")]
     pub lines: Vec<Vec<char>>,
 }
 
+// a line of chars takes a few bytes per char in most formats, a string is much smaller
+mod lines_as_strings {
+    use serde::*;
+    
+    pub fn serialize<S: Serializer>(lines: &Vec<Vec<char>>, serializer: S) -> Result<S::Ok, S::Error> {
+        let lines: Vec<String> = lines.iter().map( | line | line.iter().collect()).collect();
+        lines.serialize(serializer)
+    }
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<char>>, D::Error> {
+        let lines: Vec<String> = Vec::deserialize(deserializer)?;
+        Ok(lines.iter().map( | line | line.chars().collect()).collect())
+    }
+}
+
+// the undo and redo stacks of a buffer, kept with a digest of the text they apply to
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TextUndoHistory {
+    pub digest: u64,
+    pub undo_stack: Vec<TextUndo>,
+    pub redo_stack: Vec<TextUndo>
+}
+
+impl TextUndo {
+    fn calc_size(&self) -> usize {
+        self.ops.iter().map( | op | calc_char_count(&op.lines) + 1).sum()
+    }
+}
+
 fn calc_char_count(lines: &Vec<Vec<char>>) -> usize {
     let mut char_count = 0;
     for line in lines {
@@ -568,6 +599,50 @@ impl TextBuffer {
         }
     }
     
+    // fnv-1a over the text, stable between runs unlike the std hasher
+    pub fn calc_digest(&self) -> u64 {
+        let mut digest: u64 = 0xcbf29ce484222325;
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                digest = (digest ^ ('\n' as u64)).wrapping_mul(0x100000001b3);
+            }
+            for ch in line {
+                digest = (digest ^ (*ch as u64)).wrapping_mul(0x100000001b3);
+            }
+        }
+        digest
+    }
+    
+    // the most recent undo and redo steps that fit in max_size chars of text
+    pub fn get_undo_history(&self, max_size: usize) -> TextUndoHistory {
+        let mut size = 0;
+        let mut keep = | stack: &Vec<TextUndo> | -> Vec<TextUndo> {
+            let mut start = stack.len();
+            while start > 0 && size + stack[start - 1].calc_size() <= max_size {
+                start -= 1;
+                size += stack[start].calc_size();
+            }
+            stack[start..].to_vec()
+        };
+        let redo_stack = keep(&self.redo_stack);
+        let undo_stack = keep(&self.undo_stack);
+        TextUndoHistory {
+            digest: self.calc_digest(),
+            undo_stack: undo_stack,
+            redo_stack: redo_stack
+        }
+    }
+    
+    // only when the text is still the text the history was saved with
+    pub fn restore_undo_history(&mut self, history: &TextUndoHistory) -> bool {
+        if self.is_loading || history.digest != self.calc_digest() {
+            return false
+        }
+        self.undo_stack = history.undo_stack.clone();
+        self.redo_stack = history.redo_stack.clone();
+        true
+    }
+    
     pub fn save_buffer(&mut self) {
         //let out = self.lines.join("\n");
     }
@@ -835,6 +910,10 @@ pub enum TokenType {
     Number,
     Bool,
     
+    ShaderBuiltin,
+    ShaderStorage,
+    ShaderDf,
+    
     CommentLine,
     CommentMultiBegin,
     CommentChunk,
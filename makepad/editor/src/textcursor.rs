@@ -1,8 +1,10 @@
 use render::*;
+use serde::*;
 
 use crate::textbuffer::*;
+use crate::codecompletion::*;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TextCursor {
     pub head: usize,
     pub tail: usize,
@@ -73,22 +75,58 @@ impl TextCursor {
         self.head = text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: text_buffer.lines[pos.row].len()});
     }
     
-    pub fn move_left(&mut self, char_count: usize, _text_buffer: &TextBuffer) {
-        if self.head >= char_count {
-            self.head -= char_count;
+    pub fn move_left(&mut self, char_count: usize, text_buffer: &TextBuffer) {
+        for _ in 0..char_count {
+            if let Some(head) = self.visual_step(false, text_buffer) {
+                self.head = head;
+            }
+            else if self.head > 0 {
+                self.head -= 1;
+            }
         }
-        else {
-            self.head = 0;
+    }
+    
+    pub fn move_right(&mut self, char_count: usize, total_char_count: usize, text_buffer: &TextBuffer) {
+        for _ in 0..char_count {
+            if let Some(head) = self.visual_step(true, text_buffer) {
+                self.head = head;
+            }
+            else if self.head < total_char_count {
+                self.head += 1;
+            }
         }
     }
     
-    pub fn move_right(&mut self, char_count: usize, total_char_count: usize, _text_buffer: &TextBuffer) {
-        if self.head + char_count < total_char_count {
-            self.head += char_count;
+    // on a line with right to left text the cursor keys move the caret visually, so left moves
+    // forward through a right to left run. the caret draws on the leading edge of the char after
+    // it, and carets go left to right by that edge and then by offset. none for left to right lines
+    fn visual_step(&self, right: bool, text_buffer: &TextBuffer) -> Option<usize> {
+        let pos = text_buffer.offset_to_text_pos(self.head);
+        let line = &text_buffer.lines[pos.row];
+        if !bidi_has_rtl(line) {
+            return None
         }
-        else {
-            self.head = total_char_count;
+        let levels = bidi_levels(line, Some(0));
+        let mut edges = vec![0; line.len()];
+        for (cell, index) in bidi_visual_order(&levels).iter().enumerate() {
+            edges[*index] = if levels[*index] & 1 == 1 {cell + 1} else {cell};
         }
+        let key = | col: usize | (if col < line.len() {edges[col]} else {line.len()}, col);
+        let current = key(pos.col);
+        let carets = (0..=line.len()).map(key);
+        let next = if right {
+            carets.filter( | caret | *caret > current).min()
+        }
+        else {
+            carets.filter( | caret | *caret < current).max()
+        };
+        Some(match next {
+            Some((_, col)) => text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: col}),
+            // past the visual end of the line the caret goes to the next or previous line
+            None if right && pos.row + 1 < text_buffer.lines.len() => text_buffer.text_pos_to_offset(TextPos {row: pos.row + 1, col: 0}),
+            None if !right && pos.row > 0 => text_buffer.text_pos_to_offset(TextPos {row: pos.row - 1, col: text_buffer.lines[pos.row - 1].len()}),
+            None => self.head
+        })
     }
     
     pub fn move_up(&mut self, line_count: usize, text_buffer: &TextBuffer) {
@@ -114,7 +152,7 @@ impl TextCursor {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TextCursorSet {
     pub set: Vec<TextCursor>,
     pub last_cursor: usize,
@@ -470,6 +508,37 @@ impl TextCursorSet {
         })
     }
     
+    // replaces the partial word in front of every cursor with word
+    pub fn complete_word(&mut self, word: &str, text_buffer: &mut TextBuffer) {
+        let mut delta: isize = 0;
+        let mut ops = Vec::new();
+        let mut old_max = (TextPos {row: 0, col: 0}, 0);
+        let cursors_clone = self.clone();
+        for cursor in &mut self.set {
+            let (mut start, end) = cursor.delta(delta);
+            if start == end {
+                while start > 0 {
+                    let ch = text_buffer.get_char(start - 1);
+                    if !CodeCompletion::is_word_char(ch) {
+                        break;
+                    }
+                    start -= 1;
+                }
+            }
+            let op = text_buffer.replace_lines_with_string(start, end - start, word);
+            delta += cursor.collapse(start, end, op.len);
+            ops.push(op);
+            old_max = cursor.calc_max(text_buffer, old_max);
+        }
+        self.insert_undo_group += 1;
+        text_buffer.redo_stack.truncate(0);
+        text_buffer.undo_stack.push(TextUndo {
+            ops: ops,
+            grouping: TextUndoGrouping::Block,
+            cursors: cursors_clone
+        })
+    }
+
     pub fn insert_around(&mut self, pre: &str, post: &str, text_buffer: &mut TextBuffer) {
         let mut delta: isize = 0;
         // rolling delta to displace cursors
@@ -757,6 +826,20 @@ impl TextCursorSet {
             
             let cursors_clone = self.clone();
             let op = text_buffer.replace_lines(top_row, bottom_row_old + 1, changed);
+            // keep the cursors on the same text around the replaced range
+            let old_len = op.lines.iter().map( | line | line.len()).sum::<usize>() + op.lines.len() - 1;
+            let old_end = op.start + old_len;
+            let new_end = op.start + op.len;
+            for cursor in &mut self.set {
+                for pos in &mut [&mut cursor.head, &mut cursor.tail] {
+                    if **pos >= old_end {
+                        **pos = **pos - old_end + new_end;
+                    }
+                    else if **pos > new_end {
+                        **pos = new_end;
+                    }
+                }
+            }
             text_buffer.redo_stack.truncate(0);
             text_buffer.undo_stack.push(TextUndo {
                 ops: vec![op],
@@ -902,6 +985,19 @@ impl TextCursorSet {
         self.fuse_adjacent(text_buffer)
     }
     
+    // moves every head with a step that returns the new head and the column to remember
+    pub fn move_heads_with<F>(&mut self, only_head: bool, text_buffer: &TextBuffer, mut step: F)
+    where F: FnMut(&TextCursor) -> (usize, usize) {
+        self.insert_undo_group += 1;
+        for cursor in &mut self.set {
+            let (head, max) = step(cursor);
+            cursor.head = head;
+            cursor.max = max;
+            if !only_head {cursor.tail = cursor.head}
+        }
+        self.fuse_adjacent(text_buffer)
+    }
+    
     pub fn move_left(&mut self, char_count: usize, only_head: bool, text_buffer: &TextBuffer) {
         self.insert_undo_group += 1;
         let mut old_max = (TextPos {row: 0, col: 0}, 0);
@@ -1208,14 +1304,10 @@ impl DrawCursors {
         if !self.first {
             self.first = true;
             if !self.empty {
+                let w = self.right_bottom.x - self.left_top.x;
                 self.selections.push(DrawSel {
                     index: self.next_index - 1,
-                    rc: Rect {
-                        x: self.left_top.x,
-                        y: self.left_top.y,
-                        w: (self.right_bottom.x - self.left_top.x),
-                        h: self.right_bottom.y - self.left_top.y
-                    }
+                    rc: Self::selection_rect(self.left_top.x, self.left_top.y, w, self.right_bottom.y - self.left_top.y)
                 })
             }
             self.right_bottom.y = 0.;
@@ -1225,19 +1317,25 @@ impl DrawCursors {
     pub fn emit_selection_new_line(&mut self) {
         if !self.first {
             self.first = true;
+            let w = (self.right_bottom.x - self.left_top.x) + self.last_w;
             self.selections.push(DrawSel {
                 index: self.next_index - 1,
-                rc: Rect {
-                    x: self.left_top.x,
-                    y: self.left_top.y,
-                    w: (self.right_bottom.x - self.left_top.x) + self.last_w,
-                    h: self.right_bottom.y - self.left_top.y
-                }
+                rc: Self::selection_rect(self.left_top.x, self.left_top.y, w, self.right_bottom.y - self.left_top.y)
             });
             self.right_bottom.y = 0.;
         }
     }
     
+    // a selection that ends on right to left text can end left of where it starts
+    fn selection_rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        if w < 0.0 {
+            Rect {x: x + w, y: y, w: -w, h: h}
+        }
+        else {
+            Rect {x: x, y: y, w: w, h: h}
+        }
+    }
+    
     pub fn process_cursor(&mut self, last_cursor: usize, offset: usize, x: f32, y: f32, h: f32, z: f32) {
         if offset == self.head { // emit a cursor
             if self.next_index > 0 && self.next_index - 1 == last_cursor {
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+// a line diff (Myers) between two texts, and the changed columns within a pair of lines
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiffOp {
+    Equal,
+    Delete,
+    Insert
+}
+
+// a run of lines, Equal advances both sides, Delete only the old and Insert only the new side
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffChunk {
+    pub op: DiffOp,
+    pub old_start: usize,
+    pub new_start: usize,
+    pub len: usize
+}
+
+pub fn diff_lines<'a>(old: &'a [Vec<char>], new: &'a [Vec<char>]) -> Vec<DiffChunk> {
+    // the common head and tail never take part in the search
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix && old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix] {
+        suffix += 1;
+    }
+
+    // compare lines by id instead of by content
+    let mut ids: HashMap<&'a [char], usize> = HashMap::new();
+    let mut a = Vec::new();
+    for line in &old[prefix..old.len() - suffix] {
+        let next = ids.len();
+        a.push(*ids.entry(&line[..]).or_insert(next));
+    }
+    let mut b = Vec::new();
+    for line in &new[prefix..new.len() - suffix] {
+        let next = ids.len();
+        b.push(*ids.entry(&line[..]).or_insert(next));
+    }
+
+    let mut ops = Vec::new();
+    diff_ids(&a, &b, &mut ops);
+    let mut chunks = Vec::new();
+    let mut old_pos = 0;
+    let mut new_pos = 0;
+    push_diff_op(&mut chunks, DiffOp::Equal, &mut old_pos, &mut new_pos, prefix);
+    for op in ops {
+        push_diff_op(&mut chunks, op, &mut old_pos, &mut new_pos, 1);
+    }
+    push_diff_op(&mut chunks, DiffOp::Equal, &mut old_pos, &mut new_pos, suffix);
+    chunks
+}
+
+fn push_diff_op(chunks: &mut Vec<DiffChunk>, op: DiffOp, old_pos: &mut usize, new_pos: &mut usize, len: usize) {
+    if len == 0 {
+        return
+    }
+    let merged = if let Some(last) = chunks.last_mut() {
+        if last.op == op {
+            last.len += len;
+            true
+        }
+        else {false}
+    }
+    else {false};
+    if !merged {
+        chunks.push(DiffChunk {op: op, old_start: *old_pos, new_start: *new_pos, len: len});
+    }
+    match op {
+        DiffOp::Equal => {
+            *old_pos += len;
+            *new_pos += len;
+        },
+        DiffOp::Delete => *old_pos += len,
+        DiffOp::Insert => *new_pos += len
+    }
+}
+
+// the shortest edit script in linear space (Myers' divide and conquer): the middle of the script
+// splits it in two halves that are diffed on their own
+fn diff_ids(a: &[usize], b: &[usize], ops: &mut Vec<DiffOp>) {
+    let mut prefix = 0;
+    while prefix < a.len() && prefix < b.len() && a[prefix] == b[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < a.len() - prefix && suffix < b.len() - prefix && a[a.len() - 1 - suffix] == b[b.len() - 1 - suffix] {
+        suffix += 1;
+    }
+    ops.extend(std::iter::repeat(DiffOp::Equal).take(prefix));
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if a_mid.len() == 0 {
+        ops.extend(std::iter::repeat(DiffOp::Insert).take(b_mid.len()));
+    }
+    else if b_mid.len() == 0 {
+        ops.extend(std::iter::repeat(DiffOp::Delete).take(a_mid.len()));
+    }
+    else {
+        let (x, y) = myers_middle(a_mid, b_mid);
+        diff_ids(&a_mid[..x], &b_mid[..y], ops);
+        diff_ids(&a_mid[x..], &b_mid[y..], ops);
+    }
+    ops.extend(std::iter::repeat(DiffOp::Equal).take(suffix));
+}
+
+// walks the furthest reaching paths from the start and from the end at the same time, and returns
+// where they first overlap. a and b are not empty and differ at both ends, so the point is never
+// one of the ends and both halves are smaller
+fn myers_middle(a: &[usize], b: &[usize]) -> (usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max_d = (n + m + 1) / 2;
+    let v_offset = max_d;
+    let v_len = 2 * max_d + 2;
+    let mut v1 = vec![-1isize; v_len as usize];
+    let mut v2 = vec![-1isize; v_len as usize];
+    v1[(v_offset + 1) as usize] = 0;
+    v2[(v_offset + 1) as usize] = 0;
+    let delta = n - m;
+    // with an odd delta the forward path runs into the reverse one, else the other way around
+    let front = delta % 2 != 0;
+    // diagonals that ran off the edge of the grid are skipped
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let k1_offset = (v_offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && v1[k1_offset - 1] < v1[k1_offset + 1]) {
+                v1[k1_offset + 1]
+            }
+            else {
+                v1[k1_offset - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            v1[k1_offset] = x1;
+            if x1 > n {
+                k1_end += 2;
+            }
+            else if y1 > m {
+                k1_start += 2;
+            }
+            else if front {
+                let k2_offset = v_offset + delta - k1;
+                if k2_offset >= 0 && k2_offset < v_len && v2[k2_offset as usize] != -1 && x1 >= n - v2[k2_offset as usize] {
+                    return (x1 as usize, y1 as usize)
+                }
+            }
+            k1 += 2;
+        }
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let k2_offset = (v_offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && v2[k2_offset - 1] < v2[k2_offset + 1]) {
+                v2[k2_offset + 1]
+            }
+            else {
+                v2[k2_offset - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            v2[k2_offset] = x2;
+            if x2 > n {
+                k2_end += 2;
+            }
+            else if y2 > m {
+                k2_start += 2;
+            }
+            else if !front {
+                let k1_offset = v_offset + delta - k2;
+                if k1_offset >= 0 && k1_offset < v_len && v1[k1_offset as usize] != -1 {
+                    let x1 = v1[k1_offset as usize];
+                    let y1 = v_offset + x1 - k1_offset;
+                    if x1 >= n - x2 {
+                        return (x1 as usize, y1 as usize)
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    // the paths always meet by max_d, nothing in common splits into a delete and an insert
+    (a.len(), 0)
+}
+
+// the changed column ranges of a line and its replacement, without the common head and tail
+pub fn diff_line_cols(old: &[char], new: &[char]) -> ((usize, usize), (usize, usize)) {
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix && old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix] {
+        suffix += 1;
+    }
+    ((prefix, old.len() - suffix), (prefix, new.len() - suffix))
+}
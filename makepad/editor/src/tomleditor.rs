@@ -0,0 +1,257 @@
+use render::*;
+
+use crate::textbuffer::*;
+use crate::codeeditor::*;
+use crate::codecompletion::*;
+
+#[derive(Clone)]
+pub struct TOMLEditor {
+    pub code_editor: CodeEditor,
+}
+
+impl TOMLEditor {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            code_editor: CodeEditor {
+                folding_depth: 3,
+                ..CodeEditor::proto(cx)
+            }
+        }
+    }
+
+    pub fn handle_toml_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: Option<&CodeCompletionIndex>) -> CodeEditorEvent {
+        self.code_editor.handle_code_editor(cx, event, text_buffer, completion_index)
+    }
+
+    // tokenizes the buffer when it changed, also for buffers that aren't drawn
+    pub fn update_token_chunks(text_buffer: &mut TextBuffer) {
+        if text_buffer.needs_token_chunks() && text_buffer.lines.len() >0 {
+            let mut state = TokenizerState::new(&text_buffer.lines);
+            let mut tokenizer = TOMLTokenizer::new();
+            let mut pair_stack = Vec::new();
+            loop {
+                let offset = text_buffer.flat_text.len();
+                let token_type = tokenizer.next_token(&mut state, &mut text_buffer.flat_text, &text_buffer.token_chunks);
+                TokenChunk::push_with_pairing(&mut text_buffer.token_chunks, &mut pair_stack, state.next, offset, text_buffer.flat_text.len(), token_type);
+                if token_type == TokenType::Eof {
+                    break
+                }
+            }
+        }
+    }
+
+    pub fn draw_toml_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        Self::update_token_chunks(text_buffer);
+
+        if self.code_editor.begin_code_editor(cx, text_buffer).is_err() {return}
+
+        for (index, token_chunk) in text_buffer.token_chunks.iter_mut().enumerate() {
+            self.code_editor.draw_chunk(cx, index, &text_buffer.flat_text, token_chunk, &text_buffer.messages.cursors);
+        }
+
+        self.code_editor.end_code_editor(cx, text_buffer);
+    }
+}
+
+pub struct TOMLTokenizer {
+    pub in_comment: bool,
+    // the quote char of the """ or ''' string we are in
+    pub multiline_string: Option<char>,
+    // [table] and [[array]] headers are highlighted as type names
+    pub in_header: bool,
+    pub line_start: bool
+}
+
+impl TOMLTokenizer {
+    pub fn new() -> TOMLTokenizer {
+        TOMLTokenizer {
+            in_comment: false,
+            multiline_string: None,
+            in_header: false,
+            line_start: true
+        }
+    }
+
+    pub fn next_token<'a>(&mut self, state: &mut TokenizerState<'a>, chunk: &mut Vec<char>, _token_chunks: &Vec<TokenChunk>) -> TokenType {
+        let start = chunk.len();
+        if self.in_comment || self.multiline_string.is_some() {
+            let token_type = if self.in_comment {TokenType::CommentChunk} else {TokenType::String};
+            loop {
+                if state.next == '\0' {
+                    self.in_comment = false;
+                    self.multiline_string = None;
+                    return token_type
+                }
+                else if state.next == '\n' {
+                    if (chunk.len() - start)>0 {
+                        return token_type
+                    }
+                    self.in_comment = false;
+                    self.line_start = true;
+                    chunk.push(state.next);
+                    state.advance();
+                    return TokenType::Newline
+                }
+                else if state.next == ' ' {
+                    if (chunk.len() - start)>0 {
+                        return token_type
+                    }
+                    while state.next == ' ' {
+                        chunk.push(state.next);
+                        state.advance();
+                    }
+                    return TokenType::Whitespace
+                }
+                else if let Some(quote) = self.multiline_string {
+                    if state.next == '\\' && quote == '"' {
+                        chunk.push(state.next);
+                        state.advance();
+                        if state.next != '\n' && state.next != '\0' {
+                            chunk.push(state.next);
+                            state.advance();
+                        }
+                    }
+                    else if state.next == quote {
+                        let mut quotes = 0;
+                        while state.next == quote {
+                            quotes += 1;
+                            chunk.push(state.next);
+                            state.advance();
+                        }
+                        if quotes >= 3 {
+                            self.multiline_string = None;
+                            return TokenType::String
+                        }
+                    }
+                    else {
+                        chunk.push(state.next);
+                        state.advance();
+                    }
+                }
+                else {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+            }
+        }
+
+        state.advance_with_cur();
+        let line_start = self.line_start;
+        if state.cur != ' ' && state.cur != '\t' {
+            self.line_start = state.cur == '\n';
+        }
+        match state.cur {
+            '\0' => { // eof insert a terminating space and end
+                chunk.push(' ');
+                return TokenType::Eof
+            },
+            '\n' => {
+                self.in_header = false;
+                chunk.push('\n');
+                return TokenType::Newline
+            },
+            ' ' | '\t' => {
+                chunk.push(state.cur);
+                while state.next == ' ' || state.next == '\t' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                return TokenType::Whitespace
+            },
+            '#' => {
+                chunk.push(state.cur);
+                self.in_comment = true;
+                return TokenType::CommentLine
+            },
+            '"' | '\'' => {
+                let quote = state.cur;
+                chunk.push(quote);
+                if state.next == quote {
+                    chunk.push(state.next);
+                    state.advance();
+                    if state.next != quote { // empty string
+                        return TokenType::String
+                    }
+                    chunk.push(state.next);
+                    state.advance();
+                    self.multiline_string = Some(quote);
+                    return TokenType::String
+                }
+                while state.next != '\0' && state.next != '\n' {
+                    if state.next == '\\' && quote == '"' {
+                        chunk.push(state.next);
+                        state.advance();
+                        if state.next == '\0' || state.next == '\n' {
+                            break;
+                        }
+                    }
+                    else if state.next == quote {
+                        chunk.push(state.next);
+                        state.advance();
+                        break;
+                    }
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                if self.in_header {
+                    return TokenType::TypeName
+                }
+                return TokenType::String
+            },
+            '[' => {
+                chunk.push(state.cur);
+                if line_start {
+                    self.in_header = true;
+                }
+                return TokenType::ParenOpen
+            },
+            '{' => {
+                chunk.push(state.cur);
+                return TokenType::ParenOpen
+            },
+            ']' | '}' => {
+                chunk.push(state.cur);
+                return TokenType::ParenClose
+            },
+            ',' => {
+                chunk.push(state.cur);
+                return TokenType::Delimiter
+            },
+            '=' | '.' => {
+                chunk.push(state.cur);
+                return TokenType::Operator
+            },
+            '+' | '-' | '0'..='9' => { // numbers, and dates and times
+                chunk.push(state.cur);
+                while state.next_is_hex() || state.next == '_' || state.next == '.' || state.next == ':' || state.next == '-'
+                    || state.next == '+' || state.next == 'x' || state.next == 'o' || state.next == 'T' || state.next == 'Z' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                if self.in_header {
+                    return TokenType::TypeName
+                }
+                return TokenType::Number
+            },
+            'a'..='z' | 'A'..='Z' | '_' => { // bare keys
+                chunk.push(state.cur);
+                while state.next_is_letter() || state.next_is_digit() || state.next == '_' || state.next == '-' {
+                    chunk.push(state.next);
+                    state.advance();
+                }
+                if self.in_header {
+                    return TokenType::TypeName
+                }
+                return match &chunk[start..] {
+                    ['t', 'r', 'u', 'e'] | ['f', 'a', 'l', 's', 'e'] => TokenType::Bool,
+                    ['i', 'n', 'f'] | ['n', 'a', 'n'] => TokenType::Number,
+                    _ => TokenType::Identifier
+                }
+            },
+            _ => {
+                chunk.push(state.cur);
+                return TokenType::Unexpected
+            }
+        }
+    }
+}
@@ -0,0 +1,1276 @@
+use render::*;
+use widget::*;
+use std::collections::HashMap;
+
+use crate::textbuffer::*;
+use crate::textcursor::*;
+
+// modal editing on top of the cursor set and the text buffer. the vim cursor is the head of the
+// last cursor and sits on a character, the visual modes select from the anchor up to and including it
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VimMode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VimKey {
+    Char(char),
+    // typed text in insert mode, kept for . repeat
+    Text(String),
+    Ctrl(char),
+    Escape,
+    Return,
+    Backspace,
+    Delete,
+    Tab
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VimEvent {
+    // the editor handles the key as usual
+    Pass,
+    None,
+    Write,
+    // the keys of the last change, played back through the editor
+    Repeat(Vec<VimKey>)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VimRegister {
+    pub text: String,
+    pub linewise: bool
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    NextLine,
+    PrevLine,
+    WordForward(bool),
+    WordBack(bool),
+    WordEnd(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+    MatchPair,
+    Find {ch: char, forward: bool, till: bool},
+    RepeatFind(bool)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MotionKind {
+    Exclusive,
+    Inclusive,
+    Linewise
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum VimAction {
+    Move(Motion),
+    Operate(Operator, Option<Motion>),
+    Replace(char),
+    Key(VimKey)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct VimCommand {
+    register: Option<char>,
+    count: Option<usize>,
+    action: VimAction
+}
+
+enum Parsed<T> {
+    Incomplete,
+    Invalid,
+    Done(T)
+}
+
+#[derive(Clone)]
+pub struct Vim {
+    pub mode: VimMode,
+    pub registers: HashMap<char, VimRegister>,
+    pub command_line: Option<String>,
+    pub message: String,
+    pub _pending: Vec<VimKey>,
+    pub _anchor: usize,
+    pub _pos: usize,
+    pub _want_col: usize,
+    pub _last_find: Option<(char, bool, bool)>,
+    pub _visual_rows: (usize, usize),
+    // the keys of the change being typed, it ends when insert mode does
+    pub _change: Option<Vec<VimKey>>,
+    pub _last_change: Vec<VimKey>,
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self {
+            mode: VimMode::Normal,
+            registers: HashMap::new(),
+            command_line: None,
+            message: String::new(),
+            _pending: Vec::new(),
+            _anchor: 0,
+            _pos: 0,
+            _want_col: 0,
+            _last_find: None,
+            _visual_rows: (0, 0),
+            _change: None,
+            _last_change: Vec::new(),
+        }
+    }
+
+    pub fn is_inserting(&self) -> bool {
+        self.mode == VimMode::Insert && self.command_line.is_none()
+    }
+
+    // the keys vim wants from a key down, text arrives as text input
+    pub fn key_from_key_down(&self, ke: &KeyEvent) -> Option<VimKey> {
+        if ke.modifiers.logo || ke.modifiers.alt {
+            return None
+        }
+        let inserting = self.is_inserting();
+        if ke.modifiers.control {
+            return match ke.key_code {
+                KeyCode::KeyR if !inserting => Some(VimKey::Ctrl('r')),
+                KeyCode::LBracket => Some(VimKey::Escape),
+                _ => None
+            }
+        }
+        match ke.key_code {
+            KeyCode::Escape => Some(VimKey::Escape),
+            KeyCode::Return => Some(VimKey::Return),
+            KeyCode::Backspace => Some(VimKey::Backspace),
+            KeyCode::Delete => Some(VimKey::Delete),
+            KeyCode::Tab => Some(VimKey::Tab),
+            _ if inserting || self.command_line.is_some() => None,
+            KeyCode::ArrowLeft => Some(VimKey::Char('h')),
+            KeyCode::ArrowRight => Some(VimKey::Char('l')),
+            KeyCode::ArrowUp => Some(VimKey::Char('k')),
+            KeyCode::ArrowDown => Some(VimKey::Char('j')),
+            KeyCode::Home => Some(VimKey::Char('0')),
+            KeyCode::End => Some(VimKey::Char('$')),
+            _ => None
+        }
+    }
+
+    pub fn status_text(&self) -> String {
+        if let Some(command_line) = &self.command_line {
+            return format!(":{}", command_line)
+        }
+        let mode = match self.mode {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "-- INSERT --",
+            VimMode::Visual => "-- VISUAL --",
+            VimMode::VisualLine => "-- VISUAL LINE --"
+        };
+        if self.message.len() > 0 {
+            return format!("{}  {}", mode, self.message)
+        }
+        let mut pending = String::new();
+        for key in &self._pending {
+            match key {
+                VimKey::Char(ch) => pending.push(*ch),
+                VimKey::Ctrl(ch) => pending.push_str(&format!("^{}", ch)),
+                _ => ()
+            }
+        }
+        format!("{}  {}", mode, pending)
+    }
+
+    // a click moves the cursors outside of vim
+    pub fn cancel_visual(&mut self) {
+        if self.mode == VimMode::Visual || self.mode == VimMode::VisualLine {
+            self.mode = VimMode::Normal;
+        }
+        self._pending.truncate(0);
+    }
+
+    pub fn handle_key(&mut self, key: VimKey, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        if self.command_line.is_some() {
+            return self.handle_command_line_key(key, cursors, text_buffer)
+        }
+        if self.mode == VimMode::Insert {
+            return match key {
+                VimKey::Escape => {
+                    self.leave_insert(cursors, text_buffer);
+                    VimEvent::None
+                },
+                VimKey::Ctrl(_) => VimEvent::Pass,
+                key => {
+                    if let Some(change) = &mut self._change {
+                        change.push(key);
+                    }
+                    VimEvent::Pass
+                }
+            }
+        }
+        self.message.truncate(0);
+        self._pending.push(key);
+        let keys = self._pending.clone();
+        match self.parse(&keys) {
+            Parsed::Incomplete => VimEvent::None,
+            Parsed::Invalid => {
+                self._pending.truncate(0);
+                VimEvent::None
+            },
+            Parsed::Done(command) => {
+                self._pending.truncate(0);
+                self.execute(command, keys, cursors, text_buffer)
+            }
+        }
+    }
+
+    // [register] [count] (operator [count] (motion | operator) | motion | command)
+    fn parse(&self, keys: &[VimKey]) -> Parsed<VimCommand> {
+        let mut index = 0;
+        let mut register = None;
+        if keys.get(0) == Some(&VimKey::Char('"')) {
+            match keys.get(1) {
+                Some(VimKey::Char(ch)) => register = Some(*ch),
+                Some(_) => return Parsed::Invalid,
+                None => return Parsed::Incomplete
+            }
+            index = 2;
+        }
+        let (count, next) = parse_count(keys, index);
+        index = next;
+        let key = if let Some(key) = keys.get(index) {key}else {return Parsed::Incomplete};
+        let command = | count, action | Parsed::Done(VimCommand {register: register, count: count, action: action});
+
+        if self.mode == VimMode::Normal {
+            let op = match key {
+                VimKey::Char('d') => Some(Operator::Delete),
+                VimKey::Char('c') => Some(Operator::Change),
+                VimKey::Char('y') => Some(Operator::Yank),
+                _ => None
+            };
+            if let Some(op) = op {
+                let (motion_count, next) = parse_count(keys, index + 1);
+                let count = match (count, motion_count) {
+                    (Some(a), Some(b)) => Some(a * b),
+                    (a, b) => a.or(b)
+                };
+                match keys.get(next) {
+                    None => return Parsed::Incomplete,
+                    // doubled, as in dd, works on lines
+                    Some(other) if other == key => return command(count, VimAction::Operate(op, None)),
+                    _ => ()
+                }
+                return match parse_motion(&keys[next..]) {
+                    Parsed::Done(motion) => command(count, VimAction::Operate(op, Some(motion))),
+                    Parsed::Incomplete => Parsed::Incomplete,
+                    Parsed::Invalid => Parsed::Invalid
+                }
+            }
+        }
+        match parse_motion(&keys[index..]) {
+            Parsed::Done(motion) => return command(count, VimAction::Move(motion)),
+            Parsed::Incomplete => return Parsed::Incomplete,
+            Parsed::Invalid => ()
+        }
+        if key == &VimKey::Char('r') && self.mode == VimMode::Normal {
+            return match keys.get(index + 1) {
+                Some(VimKey::Char(ch)) => command(count, VimAction::Replace(*ch)),
+                Some(_) => Parsed::Invalid,
+                None => Parsed::Incomplete
+            }
+        }
+        command(count, VimAction::Key(key.clone()))
+    }
+
+    fn execute(&mut self, command: VimCommand, keys: Vec<VimKey>, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        let pos = self.cursor_pos(cursors, text_buffer);
+        let count = command.count.unwrap_or(1);
+        let visual = self.mode == VimMode::Visual || self.mode == VimMode::VisualLine;
+        match command.action.clone() {
+            VimAction::Move(motion) => {
+                if let Some((target, _)) = self.motion_target(motion, command.count, pos, text_buffer) {
+                    self.move_to(target, cursors, text_buffer);
+                }
+            },
+            VimAction::Operate(op, motion) => {
+                self.operate(op, motion, &command, keys, pos, cursors, text_buffer);
+            },
+            VimAction::Replace(ch) => {
+                let line_len = text_buffer.lines[pos.row].len();
+                if pos.col + count <= line_len {
+                    let start = text_buffer.text_pos_to_offset(pos);
+                    let text: String = std::iter::repeat(ch).take(count).collect();
+                    self.replace(start, start + count, &text, cursors, text_buffer);
+                    self.set_cursor(start + count - 1, cursors, text_buffer);
+                    self._last_change = keys;
+                }
+            },
+            VimAction::Key(key) => {
+                if visual {
+                    return self.execute_visual_key(key, &command, cursors, text_buffer)
+                }
+                return self.execute_key(key, &command, keys, pos, cursors, text_buffer)
+            }
+        }
+        VimEvent::None
+    }
+
+    fn execute_key(&mut self, key: VimKey, command: &VimCommand, keys: Vec<VimKey>, pos: TextPos, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        let count = command.count.unwrap_or(1);
+        let offset = text_buffer.text_pos_to_offset(pos);
+        let line_len = text_buffer.lines[pos.row].len();
+        let ch = match key {
+            VimKey::Char(ch) => ch,
+            VimKey::Ctrl('r') => {
+                for _ in 0..count {
+                    text_buffer.redo(true, cursors);
+                }
+                self.collapse_cursor(cursors, text_buffer);
+                return VimEvent::None
+            },
+            VimKey::Escape => {
+                self.set_cursor(offset, cursors, text_buffer);
+                return VimEvent::None
+            },
+            _ => return VimEvent::None
+        };
+        // the shorthands run as the operator they stand for
+        let shorthand = match ch {
+            'x' => Some((Operator::Delete, Some(Motion::Right))),
+            'X' => Some((Operator::Delete, Some(Motion::Left))),
+            'D' => Some((Operator::Delete, Some(Motion::LineEnd))),
+            'C' => Some((Operator::Change, Some(Motion::LineEnd))),
+            's' => Some((Operator::Change, Some(Motion::Right))),
+            'S' => Some((Operator::Change, None)),
+            'Y' => Some((Operator::Yank, None)),
+            _ => None
+        };
+        if let Some((op, motion)) = shorthand {
+            if line_len == 0 && (ch == 'x' || ch == 'X') {
+                return VimEvent::None
+            }
+            self.operate(op, motion, command, keys, pos, cursors, text_buffer);
+            return VimEvent::None
+        }
+        match ch {
+            'i' | 'a' | 'I' | 'A' => {
+                let insert_at = match ch {
+                    'a' => offset + if line_len > 0 {1}else {0},
+                    'I' => text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: first_non_blank(&text_buffer.lines[pos.row])}),
+                    'A' => offset - pos.col + line_len,
+                    _ => offset
+                };
+                self.enter_insert(insert_at, keys, cursors, text_buffer);
+            },
+            'o' | 'O' => {
+                let line = &text_buffer.lines[pos.row];
+                let indent: String = line[0..first_non_blank(line)].iter().collect();
+                let line_start = offset - pos.col;
+                let insert_at = if ch == 'o' {
+                    self.replace(line_start + line_len, line_start + line_len, &format!("\n{}", indent), cursors, text_buffer);
+                    line_start + line_len + 1 + indent.chars().count()
+                }
+                else {
+                    self.replace(line_start, line_start, &format!("{}\n", indent), cursors, text_buffer);
+                    line_start + indent.chars().count()
+                };
+                self.enter_insert(insert_at, keys, cursors, text_buffer);
+            },
+            'p' | 'P' => {
+                self.paste(command.register, count, ch == 'P', pos, cursors, text_buffer);
+                self._last_change = keys;
+            },
+            'J' => {
+                let mut join_at = None;
+                for _ in 0..count.max(2) - 1 {
+                    if pos.row + 1 >= text_buffer.lines.len() {
+                        break;
+                    }
+                    let line = &text_buffer.lines[pos.row];
+                    let next_line = &text_buffer.lines[pos.row + 1];
+                    let next_start = first_non_blank(next_line);
+                    let glue = if line.len() == 0 || line.last() == Some(&' ') || next_start == next_line.len() || next_line[next_start] == ')' {""}else {" "};
+                    let line_end = text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: line.len()});
+                    let next_offset = text_buffer.text_pos_to_offset(TextPos {row: pos.row + 1, col: next_start});
+                    self.replace(line_end, next_offset, glue, cursors, text_buffer);
+                    join_at = Some(line_end);
+                }
+                if let Some(join_at) = join_at {
+                    self.set_cursor(join_at, cursors, text_buffer);
+                    self._last_change = keys;
+                }
+            },
+            '~' => {
+                let end = (pos.col + count).min(line_len);
+                if end > pos.col {
+                    let text: String = text_buffer.lines[pos.row][pos.col..end].iter().map( | ch | {
+                        if ch.is_uppercase() {ch.to_lowercase().next().unwrap()}else {ch.to_uppercase().next().unwrap()}
+                    }).collect();
+                    self.replace(offset, offset + end - pos.col, &text, cursors, text_buffer);
+                    self.set_cursor(offset + end - pos.col, cursors, text_buffer);
+                    self._last_change = keys;
+                }
+            },
+            'u' => {
+                for _ in 0..count {
+                    text_buffer.undo(true, cursors);
+                }
+                self.collapse_cursor(cursors, text_buffer);
+            },
+            'v' | 'V' => {
+                self.mode = if ch == 'v' {VimMode::Visual}else {VimMode::VisualLine};
+                self._anchor = offset;
+                self._pos = offset;
+                self.update_visual_cursor(cursors, text_buffer);
+            },
+            '.' => if self._last_change.len() > 0 {
+                let mut keys = self._last_change.clone();
+                // a count replaces the one of the change
+                if let Some(count) = command.count {
+                    let start = if keys.get(0) == Some(&VimKey::Char('"')) {2}else {0};
+                    let (_, end) = parse_count(&keys, start);
+                    keys.splice(start..end, count.to_string().chars().map(VimKey::Char));
+                }
+                return VimEvent::Repeat(keys)
+            },
+            ':' => {
+                self.command_line = Some(String::new());
+            },
+            _ => ()
+        }
+        VimEvent::None
+    }
+
+    fn execute_visual_key(&mut self, key: VimKey, command: &VimCommand, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        let ch = match key {
+            VimKey::Char(ch) => ch,
+            VimKey::Escape => ' ',
+            _ => return VimEvent::None
+        };
+        let linewise = self.mode == VimMode::VisualLine;
+        let anchor = text_buffer.offset_to_text_pos(self._anchor);
+        let pos = text_buffer.offset_to_text_pos(self._pos);
+        let (first, last) = if self._anchor <= self._pos {(anchor, pos)}else {(pos, anchor)};
+        let op = match ch {
+            'd' | 'x' => Some(Operator::Delete),
+            'c' | 's' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            _ => None
+        };
+        if let Some(op) = op {
+            self.mode = VimMode::Normal;
+            // the change is kept as the normal mode keys that do the same to the same amount of text
+            let op_char = match op {Operator::Delete => 'd', Operator::Change => 'c', Operator::Yank => 'y'};
+            let mut repeat: Vec<VimKey> = Vec::new();
+            if let Some(register) = command.register {
+                repeat.push(VimKey::Char('"'));
+                repeat.push(VimKey::Char(register));
+            }
+            if linewise {
+                repeat.extend(format!("{}{}{}", last.row - first.row + 1, op_char, op_char).chars().map(VimKey::Char));
+                self.operate_lines(op, command.register, first.row, last.row, repeat, cursors, text_buffer);
+            }
+            else {
+                let start = text_buffer.text_pos_to_offset(first);
+                let end = (text_buffer.text_pos_to_offset(last) + 1).min(text_buffer.calc_char_count());
+                if first.row == last.row {
+                    repeat.extend(format!("{}{}", end - start, if op == Operator::Change {'s'}else {'x'}).chars().map(VimKey::Char));
+                }
+                else {
+                    repeat.truncate(0);
+                }
+                self.operate_range(op, command.register, start, end, repeat, cursors, text_buffer);
+            }
+            return VimEvent::None
+        }
+        match ch {
+            'o' => {
+                std::mem::swap(&mut self._anchor, &mut self._pos);
+                self.update_visual_cursor(cursors, text_buffer);
+            },
+            'v' | 'V' | ' ' => {
+                let mode = if ch == 'v' {VimMode::Visual}else {VimMode::VisualLine};
+                if ch == ' ' || self.mode == mode {
+                    self.mode = VimMode::Normal;
+                    let offset = self._pos;
+                    self.set_cursor(offset, cursors, text_buffer);
+                }
+                else {
+                    self.mode = mode;
+                    self.update_visual_cursor(cursors, text_buffer);
+                }
+            },
+            ':' => {
+                self._visual_rows = (first.row, last.row);
+                self.mode = VimMode::Normal;
+                let offset = self._pos;
+                self.set_cursor(offset, cursors, text_buffer);
+                self.command_line = Some("'<,'>".to_string());
+            },
+            _ => ()
+        }
+        VimEvent::None
+    }
+
+    fn operate(&mut self, op: Operator, motion: Option<Motion>, command: &VimCommand, keys: Vec<VimKey>, pos: TextPos, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        let count = command.count.unwrap_or(1);
+        let motion = if let Some(motion) = motion {motion}else {
+            let last = (pos.row + count - 1).min(text_buffer.lines.len() - 1);
+            self.operate_lines(op, command.register, pos.row, last, keys, cursors, text_buffer);
+            return
+        };
+        // cw changes up to the end of the word, like ce
+        let (target, kind) = if op == Operator::Change && (motion == Motion::WordForward(false) || motion == Motion::WordForward(true)) && !is_blank(char_at(&text_buffer.lines, pos)) {
+            let big = motion == Motion::WordForward(true);
+            let mut target = pos;
+            for i in 0..count {
+                let next = next_pos(&text_buffer.lines, target);
+                let at_word_end = next.map_or(true, | next | char_class(char_at(&text_buffer.lines, next), big) != char_class(char_at(&text_buffer.lines, target), big));
+                if i > 0 || !at_word_end {
+                    target = word_end(&text_buffer.lines, target, big);
+                }
+            }
+            (target, MotionKind::Inclusive)
+        }
+        else if let Some(target) = self.motion_target(motion, command.count, pos, text_buffer) {
+            target
+        }
+        else {
+            return
+        };
+        if kind == MotionKind::Linewise {
+            let (first, last) = if target.row < pos.row {(target.row, pos.row)}else {(pos.row, target.row)};
+            self.operate_lines(op, command.register, first, last, keys, cursors, text_buffer);
+            return
+        }
+        let mut target = target;
+        // a word motion stops at the end of the line its last word is on
+        if let Motion::WordForward(_) = motion {
+            if target.row > pos.row {
+                let row = target.row - 1;
+                let end = TextPos {row: row, col: text_buffer.lines[row].len()};
+                if text_buffer.text_pos_to_offset(end) > text_buffer.text_pos_to_offset(pos) {
+                    target = end;
+                }
+            }
+        }
+        let pos_offset = text_buffer.text_pos_to_offset(pos);
+        let target_offset = text_buffer.text_pos_to_offset(target);
+        let (start, mut end) = if target_offset < pos_offset {(target_offset, pos_offset)}else {(pos_offset, target_offset)};
+        if kind == MotionKind::Inclusive {
+            end = (end + 1).min(text_buffer.calc_char_count());
+        }
+        self.operate_range(op, command.register, start, end, keys, cursors, text_buffer);
+    }
+
+    fn operate_range(&mut self, op: Operator, register: Option<char>, start: usize, end: usize, keys: Vec<VimKey>, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        let mut text = String::new();
+        text_buffer.get_range_as_string(start, end - start, &mut text);
+        self.store_register(register, text, false, op == Operator::Yank);
+        match op {
+            Operator::Yank => {
+                self.set_cursor(start, cursors, text_buffer);
+            },
+            Operator::Delete => {
+                self.replace(start, end, "", cursors, text_buffer);
+                self.set_cursor(start, cursors, text_buffer);
+                if keys.len() > 0 {
+                    self._last_change = keys;
+                }
+            },
+            Operator::Change => {
+                self.replace(start, end, "", cursors, text_buffer);
+                self.enter_insert(start, keys, cursors, text_buffer);
+            }
+        }
+    }
+
+    fn operate_lines(&mut self, op: Operator, register: Option<char>, first: usize, last: usize, keys: Vec<VimKey>, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        let lines: Vec<String> = text_buffer.lines[first..=last].iter().map( | line | line.iter().collect()).collect();
+        self.store_register(register, lines.join("\n"), true, op == Operator::Yank);
+        let line_count = text_buffer.lines.len();
+        let offset = | text_buffer: &TextBuffer, row: usize, col: usize | text_buffer.text_pos_to_offset(TextPos {row: row, col: col});
+        match op {
+            Operator::Yank => {
+                let col = first_non_blank(&text_buffer.lines[first]);
+                let start = offset(text_buffer, first, col);
+                self.set_cursor(start, cursors, text_buffer);
+            },
+            Operator::Delete => {
+                // the newline goes along, from after the lines or else from before them
+                let (start, end) = if last + 1 < line_count {
+                    (offset(text_buffer, first, 0), offset(text_buffer, last + 1, 0))
+                }
+                else if first > 0 {
+                    (offset(text_buffer, first - 1, text_buffer.lines[first - 1].len()), offset(text_buffer, last, text_buffer.lines[last].len()))
+                }
+                else {
+                    (0, offset(text_buffer, last, text_buffer.lines[last].len()))
+                };
+                self.replace(start, end, "", cursors, text_buffer);
+                let row = first.min(text_buffer.lines.len() - 1);
+                let col = first_non_blank(&text_buffer.lines[row]);
+                let start = offset(text_buffer, row, col);
+                self.set_cursor(start, cursors, text_buffer);
+                self._last_change = keys;
+            },
+            Operator::Change => {
+                // the indent of the first line stays
+                let start = offset(text_buffer, first, first_non_blank(&text_buffer.lines[first]));
+                let end = offset(text_buffer, last, text_buffer.lines[last].len());
+                self.replace(start, end, "", cursors, text_buffer);
+                self.enter_insert(start, keys, cursors, text_buffer);
+            }
+        }
+    }
+
+    fn store_register(&mut self, register: Option<char>, text: String, linewise: bool, yank: bool) {
+        let register = register.unwrap_or('"');
+        if register == '_' {
+            return
+        }
+        let value = VimRegister {text: text, linewise: linewise};
+        // an uppercase register appends to the lowercase one
+        if register.is_ascii_uppercase() {
+            let lower = register.to_ascii_lowercase();
+            let entry = self.registers.entry(lower).or_insert(VimRegister {text: String::new(), linewise: linewise});
+            if (entry.linewise || linewise) && entry.text.len() > 0 {
+                entry.text.push('\n');
+            }
+            entry.text.push_str(&value.text);
+            entry.linewise |= linewise;
+            let value = entry.clone();
+            self.registers.insert('"', value);
+            return
+        }
+        if register != '"' {
+            self.registers.insert(register, value.clone());
+        }
+        else if yank {
+            self.registers.insert('0', value.clone());
+        }
+        else if linewise || value.text.contains('\n') {
+            // bigger deletes shift through 1 to 9
+            for index in (1..9).rev() {
+                let from = std::char::from_digit(index, 10).unwrap();
+                if let Some(shifted) = self.registers.get(&from).cloned() {
+                    self.registers.insert(std::char::from_digit(index + 1, 10).unwrap(), shifted);
+                }
+            }
+            self.registers.insert('1', value.clone());
+        }
+        else {
+            self.registers.insert('-', value.clone());
+        }
+        self.registers.insert('"', value);
+    }
+
+    fn paste(&mut self, register: Option<char>, count: usize, before: bool, pos: TextPos, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        let name = register.unwrap_or('"').to_ascii_lowercase();
+        let value = if let Some(value) = self.registers.get(&name) {value.clone()}else {
+            self.message = format!("Nothing in register {}", name);
+            return
+        };
+        let text = vec![value.text.as_str(); count].join(if value.linewise {"\n"}else {""});
+        let line_len = text_buffer.lines[pos.row].len();
+        let line_start = text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: 0});
+        if value.linewise {
+            let row = if before {
+                self.replace(line_start, line_start, &format!("{}\n", text), cursors, text_buffer);
+                pos.row
+            }
+            else {
+                self.replace(line_start + line_len, line_start + line_len, &format!("\n{}", text), cursors, text_buffer);
+                pos.row + 1
+            };
+            let col = first_non_blank(&text_buffer.lines[row]);
+            let offset = text_buffer.text_pos_to_offset(TextPos {row: row, col: col});
+            self.set_cursor(offset, cursors, text_buffer);
+        }
+        else {
+            let insert_at = line_start + pos.col + if before || line_len == 0 {0}else {1};
+            self.replace(insert_at, insert_at, &text, cursors, text_buffer);
+            let len = text.chars().count();
+            self.set_cursor(insert_at + len.max(1) - 1, cursors, text_buffer);
+        }
+    }
+
+    fn motion_target(&mut self, motion: Motion, count: Option<usize>, pos: TextPos, text_buffer: &TextBuffer) -> Option<(TextPos, MotionKind)> {
+        let lines = &text_buffer.lines;
+        let n = count.unwrap_or(1);
+        let last_row = lines.len() - 1;
+        let line_len = lines[pos.row].len();
+        let (target, kind) = match motion {
+            Motion::Left => (TextPos {row: pos.row, col: pos.col.saturating_sub(n)}, MotionKind::Exclusive),
+            Motion::Right => (TextPos {row: pos.row, col: (pos.col + n).min(line_len)}, MotionKind::Exclusive),
+            Motion::Up | Motion::Down => {
+                let row = if motion == Motion::Up {pos.row.saturating_sub(n)}else {(pos.row + n).min(last_row)};
+                let col = self._want_col.min(lines[row].len().max(1) - 1);
+                return Some((TextPos {row: row, col: col}, MotionKind::Linewise))
+            },
+            Motion::NextLine | Motion::PrevLine => {
+                let row = if motion == Motion::PrevLine {pos.row.saturating_sub(n)}else {(pos.row + n).min(last_row)};
+                (TextPos {row: row, col: first_non_blank(&lines[row])}, MotionKind::Linewise)
+            },
+            Motion::WordForward(big) => {
+                let mut target = pos;
+                for _ in 0..n {
+                    target = word_forward(lines, target, big);
+                }
+                (target, MotionKind::Exclusive)
+            },
+            Motion::WordBack(big) => {
+                let mut target = pos;
+                for _ in 0..n {
+                    target = word_back(lines, target, big);
+                }
+                (target, MotionKind::Exclusive)
+            },
+            Motion::WordEnd(big) => {
+                let mut target = pos;
+                for _ in 0..n {
+                    target = word_end(lines, target, big);
+                }
+                (target, MotionKind::Inclusive)
+            },
+            Motion::LineStart => (TextPos {row: pos.row, col: 0}, MotionKind::Exclusive),
+            Motion::FirstNonBlank => (TextPos {row: pos.row, col: first_non_blank(&lines[pos.row])}, MotionKind::Exclusive),
+            Motion::LineEnd => {
+                let row = (pos.row + n - 1).min(last_row);
+                self._want_col = std::usize::MAX;
+                return Some((TextPos {row: row, col: lines[row].len().max(1) - 1}, MotionKind::Inclusive))
+            },
+            Motion::FirstLine | Motion::LastLine => {
+                let row = match count {
+                    Some(count) => (count.max(1) - 1).min(last_row),
+                    None => if motion == Motion::FirstLine {0}else {last_row}
+                };
+                (TextPos {row: row, col: first_non_blank(&lines[row])}, MotionKind::Linewise)
+            },
+            Motion::MatchPair => (match_pair(pos, text_buffer)?, MotionKind::Inclusive),
+            Motion::Find {ch, forward, till} => {
+                self._last_find = Some((ch, forward, till));
+                (find_in_line(lines, pos, ch, forward, till, n)?, MotionKind::Inclusive)
+            },
+            Motion::RepeatFind(reverse) => {
+                let (ch, forward, till) = self._last_find?;
+                let forward = forward != reverse;
+                // repeating a till starts past the character it stopped at
+                let from = if till && pos.col < line_len {
+                    TextPos {row: pos.row, col: if forward {pos.col + 1}else {pos.col.saturating_sub(1)}}
+                }else {pos};
+                let target = find_in_line(lines, from, ch, forward, till, n)?;
+                (target, if forward {MotionKind::Inclusive}else {MotionKind::Exclusive})
+            }
+        };
+        let kind = match motion {
+            Motion::Find {forward: false, ..} => MotionKind::Exclusive,
+            _ => kind
+        };
+        self._want_col = target.col;
+        Some((target, kind))
+    }
+
+    fn move_to(&mut self, target: TextPos, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        let target = clamp_normal(&text_buffer.lines, target);
+        let offset = text_buffer.text_pos_to_offset(target);
+        if self.mode == VimMode::Normal {
+            self.place_cursor(offset, cursors, text_buffer);
+        }
+        else {
+            self._pos = offset;
+            self.update_visual_cursor(cursors, text_buffer);
+        }
+    }
+
+    fn cursor_pos(&self, cursors: &TextCursorSet, text_buffer: &TextBuffer) -> TextPos {
+        let offset = if self.mode == VimMode::Normal {cursors.set[cursors.last_cursor].head}else {self._pos};
+        clamp_normal(&text_buffer.lines, text_buffer.offset_to_text_pos(offset))
+    }
+
+    fn update_visual_cursor(&self, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        let (anchor, pos) = (self._anchor, self._pos);
+        let (head, tail) = if self.mode == VimMode::VisualLine {
+            let anchor_row = text_buffer.offset_to_text_pos(anchor).row;
+            let pos_row = text_buffer.offset_to_text_pos(pos).row;
+            let first = text_buffer.text_pos_to_offset(TextPos {row: anchor_row.min(pos_row), col: 0});
+            let last_row = anchor_row.max(pos_row);
+            let last = text_buffer.text_pos_to_offset(TextPos {row: last_row, col: text_buffer.lines[last_row].len()});
+            if pos_row >= anchor_row {(last, first)}else {(first, last)}
+        }
+        else {
+            let total = text_buffer.calc_char_count();
+            if pos >= anchor {((pos + 1).min(total), anchor)}else {(pos, (anchor + 1).min(total))}
+        };
+        set_single_cursor(head, tail, cursors, text_buffer);
+    }
+
+    // puts the cursor on offset, or on the last character of its line
+    fn set_cursor(&mut self, offset: usize, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        let pos = clamp_normal(&text_buffer.lines, text_buffer.offset_to_text_pos(offset));
+        self._want_col = pos.col;
+        self.place_cursor(text_buffer.text_pos_to_offset(pos), cursors, text_buffer);
+    }
+
+    fn place_cursor(&self, offset: usize, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        set_single_cursor(offset, offset, cursors, text_buffer);
+    }
+
+    fn collapse_cursor(&mut self, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        let (start, _) = cursors.set[cursors.last_cursor].order();
+        self.set_cursor(start, cursors, text_buffer);
+    }
+
+    fn replace(&self, start: usize, end: usize, text: &str, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        set_single_cursor(end, start, cursors, text_buffer);
+        cursors.replace_text(text, text_buffer);
+    }
+
+    fn enter_insert(&mut self, offset: usize, keys: Vec<VimKey>, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        self.mode = VimMode::Insert;
+        self._change = Some(keys);
+        self.place_cursor(offset, cursors, text_buffer);
+    }
+
+    fn leave_insert(&mut self, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+        self.mode = VimMode::Normal;
+        if let Some(mut change) = self._change.take() {
+            change.push(VimKey::Escape);
+            self._last_change = change;
+        }
+        let pos = text_buffer.offset_to_text_pos(cursors.set[cursors.last_cursor].head);
+        let offset = text_buffer.text_pos_to_offset(TextPos {row: pos.row, col: pos.col.saturating_sub(1)});
+        self.set_cursor(offset, cursors, text_buffer);
+    }
+
+    fn handle_command_line_key(&mut self, key: VimKey, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        let command_line = self.command_line.as_mut().unwrap();
+        match key {
+            VimKey::Escape => self.command_line = None,
+            VimKey::Backspace => if command_line.pop().is_none() {
+                self.command_line = None;
+            },
+            VimKey::Char(ch) => command_line.push(ch),
+            VimKey::Text(text) => command_line.push_str(&text),
+            VimKey::Return => {
+                let command_line = self.command_line.take().unwrap();
+                return self.execute_ex(&command_line, cursors, text_buffer)
+            },
+            _ => ()
+        }
+        VimEvent::None
+    }
+
+    // :w, :[range]s/pattern/replacement/[g] and :[line], patterns are plain text not regular expressions
+    fn execute_ex(&mut self, command_line: &str, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) -> VimEvent {
+        let pos = self.cursor_pos(cursors, text_buffer);
+        let last_row = text_buffer.lines.len() - 1;
+        let (range, rest) = parse_ex_range(command_line.trim(), pos.row, last_row, self._visual_rows);
+        let rest = rest.trim();
+        match rest {
+            "w" | "write" => return VimEvent::Write,
+            "" => if let Some((_, row)) = range {
+                let row = row.min(last_row);
+                let col = first_non_blank(&text_buffer.lines[row]);
+                let offset = text_buffer.text_pos_to_offset(TextPos {row: row, col: col});
+                self.set_cursor(offset, cursors, text_buffer);
+            },
+            _ if rest.starts_with('s') && rest.len() > 1 && !rest[1..].starts_with(char::is_alphanumeric) => {
+                let (first, last) = range.unwrap_or((pos.row, pos.row));
+                self.substitute(&rest[1..], first.min(last_row), last.min(last_row), cursors, text_buffer);
+            },
+            _ => self.message = format!("Not an editor command: {}", rest)
+        }
+        VimEvent::None
+    }
+
+    fn substitute(&mut self, args: &str, first: usize, last: usize, cursors: &mut TextCursorSet, text_buffer: &mut TextBuffer) {
+        let mut chars = args.chars();
+        let delimiter = chars.next().unwrap();
+        let parts = split_unescaped(chars.as_str(), delimiter);
+        let pattern = parts.get(0).cloned().unwrap_or_default();
+        let replacement = parts.get(1).cloned().unwrap_or_default();
+        let global = parts.get(2).map_or(false, | flags | flags.contains('g'));
+        if pattern.len() == 0 {
+            self.message = "No previous pattern".to_string();
+            return
+        }
+        let mut substitutions = 0;
+        let mut changed_rows = Vec::new();
+        let mut new_lines = Vec::new();
+        for row in first..=last {
+            let line: String = text_buffer.lines[row].iter().collect();
+            let found = line.matches(pattern.as_str()).count();
+            if found == 0 {
+                new_lines.push(line);
+                continue;
+            }
+            changed_rows.push(row);
+            if global {
+                substitutions += found;
+                new_lines.push(line.replace(pattern.as_str(), &replacement));
+            }
+            else {
+                substitutions += 1;
+                new_lines.push(line.replacen(pattern.as_str(), &replacement, 1));
+            }
+        }
+        if substitutions == 0 {
+            self.message = format!("Pattern not found: {}", pattern);
+            return
+        }
+        let start = text_buffer.text_pos_to_offset(TextPos {row: first, col: 0});
+        let end = text_buffer.text_pos_to_offset(TextPos {row: last, col: text_buffer.lines[last].len()});
+        self.replace(start, end, &new_lines.join("\n"), cursors, text_buffer);
+        let row = *changed_rows.last().unwrap();
+        let offset = text_buffer.text_pos_to_offset(TextPos {row: row, col: first_non_blank(&text_buffer.lines[row])});
+        self.set_cursor(offset, cursors, text_buffer);
+        self._last_change.truncate(0);
+        let plural = | n: usize | if n == 1 {""}else {"s"};
+        self.message = format!("{} substitution{} on {} line{}", substitutions, plural(substitutions), changed_rows.len(), plural(changed_rows.len()));
+    }
+}
+
+fn set_single_cursor(head: usize, tail: usize, cursors: &mut TextCursorSet, text_buffer: &TextBuffer) {
+    // a new undo group, so typing after a vim command doesn't merge with it
+    cursors.insert_undo_group += 1;
+    let mut cursor = TextCursor {head: head, tail: tail, max: 0};
+    cursor.calc_max(text_buffer, (TextPos {row: 0, col: 0}, 0));
+    cursors.set = vec![cursor];
+    cursors.last_cursor = 0;
+}
+
+fn parse_count(keys: &[VimKey], start: usize) -> (Option<usize>, usize) {
+    let mut index = start;
+    let mut count = None;
+    while let Some(VimKey::Char(ch)) = keys.get(index) {
+        match ch.to_digit(10) {
+            // a leading 0 is the line start motion
+            Some(0) if count.is_none() => break,
+            Some(digit) => count = Some(count.unwrap_or(0) * 10 + digit as usize),
+            None => break
+        }
+        index += 1;
+    }
+    (count, index)
+}
+
+fn parse_motion(keys: &[VimKey]) -> Parsed<Motion> {
+    let key = if let Some(key) = keys.get(0) {key}else {return Parsed::Incomplete};
+    let ch = match key {
+        VimKey::Backspace => return Parsed::Done(Motion::Left),
+        VimKey::Return => return Parsed::Done(Motion::NextLine),
+        VimKey::Char(ch) => *ch,
+        _ => return Parsed::Invalid
+    };
+    let motion = match ch {
+        'h' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'k' => Motion::Up,
+        'j' => Motion::Down,
+        '+' => Motion::NextLine,
+        '-' => Motion::PrevLine,
+        'w' => Motion::WordForward(false),
+        'W' => Motion::WordForward(true),
+        'b' => Motion::WordBack(false),
+        'B' => Motion::WordBack(true),
+        'e' => Motion::WordEnd(false),
+        'E' => Motion::WordEnd(true),
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        'G' => Motion::LastLine,
+        '%' => Motion::MatchPair,
+        ';' => Motion::RepeatFind(false),
+        ',' => Motion::RepeatFind(true),
+        'g' => return match keys.get(1) {
+            Some(VimKey::Char('g')) => Parsed::Done(Motion::FirstLine),
+            Some(_) => Parsed::Invalid,
+            None => Parsed::Incomplete
+        },
+        'f' | 'F' | 't' | 'T' => return match keys.get(1) {
+            Some(VimKey::Char(target)) => Parsed::Done(Motion::Find {ch: *target, forward: ch == 'f' || ch == 't', till: ch == 't' || ch == 'T'}),
+            Some(_) => Parsed::Invalid,
+            None => Parsed::Incomplete
+        },
+        _ => return Parsed::Invalid
+    };
+    Parsed::Done(motion)
+}
+
+// [address[,address]] where an address is a line number, ., $ or '< and '>, % is every line
+fn parse_ex_range(command_line: &str, row: usize, last_row: usize, visual_rows: (usize, usize)) -> (Option<(usize, usize)>, &str) {
+    if command_line.starts_with('%') {
+        return (Some((0, last_row)), &command_line[1..])
+    }
+    let parse_address = | text: &str | -> Option<(usize, usize)> {
+        if text.starts_with('.') {
+            return Some((row, 1))
+        }
+        if text.starts_with('$') {
+            return Some((last_row, 1))
+        }
+        if text.starts_with("'<") {
+            return Some((visual_rows.0, 2))
+        }
+        if text.starts_with("'>") {
+            return Some((visual_rows.1, 2))
+        }
+        let digits = text.chars().take_while( | ch | ch.is_ascii_digit()).count();
+        if digits > 0 {
+            let line: usize = text[..digits].parse().ok()?;
+            return Some((line.max(1) - 1, digits))
+        }
+        None
+    };
+    if let Some((first, len)) = parse_address(command_line) {
+        let rest = &command_line[len..];
+        if rest.starts_with(',') {
+            if let Some((last, len)) = parse_address(&rest[1..]) {
+                return (Some((first.min(last), first.max(last))), &rest[1 + len..])
+            }
+        }
+        return (Some((first, first)), rest)
+    }
+    (None, command_line)
+}
+
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            if let Some(next) = chars.next() {
+                if next != delimiter {
+                    parts.last_mut().unwrap().push('\\');
+                }
+                parts.last_mut().unwrap().push(next);
+            }
+        }
+        else if ch == delimiter {
+            parts.push(String::new());
+        }
+        else {
+            parts.last_mut().unwrap().push(ch);
+        }
+    }
+    parts
+}
+
+fn is_blank(ch: char) -> bool {
+    ch == ' ' || ch == '\t' || ch == '\n'
+}
+
+// whitespace, word characters and the rest, a big word is anything but whitespace
+fn char_class(ch: char, big: bool) -> usize {
+    if is_blank(ch) {0}
+    else if big || ch.is_alphanumeric() || ch == '_' {1}
+    else {2}
+}
+
+fn first_non_blank(line: &[char]) -> usize {
+    line.iter().position( | ch | !is_blank(*ch)).unwrap_or(line.len())
+}
+
+fn clamp_normal(lines: &[Vec<char>], pos: TextPos) -> TextPos {
+    let row = pos.row.min(lines.len() - 1);
+    TextPos {row: row, col: pos.col.min(lines[row].len().max(1) - 1)}
+}
+
+// the end of a line reads as a newline
+fn char_at(lines: &[Vec<char>], pos: TextPos) -> char {
+    lines[pos.row].get(pos.col).cloned().unwrap_or('\n')
+}
+
+fn next_pos(lines: &[Vec<char>], pos: TextPos) -> Option<TextPos> {
+    if pos.col < lines[pos.row].len() {
+        Some(TextPos {row: pos.row, col: pos.col + 1})
+    }
+    else if pos.row + 1 < lines.len() {
+        Some(TextPos {row: pos.row + 1, col: 0})
+    }
+    else {
+        None
+    }
+}
+
+fn prev_pos(lines: &[Vec<char>], pos: TextPos) -> Option<TextPos> {
+    if pos.col > 0 {
+        Some(TextPos {row: pos.row, col: pos.col - 1})
+    }
+    else if pos.row > 0 {
+        Some(TextPos {row: pos.row - 1, col: lines[pos.row - 1].len()})
+    }
+    else {
+        None
+    }
+}
+
+// the start of the next word, an empty line counts as a word
+fn word_forward(lines: &[Vec<char>], start: TextPos, big: bool) -> TextPos {
+    let mut pos = start;
+    let class = char_class(char_at(lines, pos), big);
+    if class != 0 {
+        loop {
+            pos = if let Some(next) = next_pos(lines, pos) {next}else {return pos};
+            if char_class(char_at(lines, pos), big) != class {
+                break;
+            }
+        }
+    }
+    loop {
+        if char_class(char_at(lines, pos), big) != 0 || (lines[pos.row].len() == 0 && pos.row != start.row) {
+            return pos
+        }
+        pos = if let Some(next) = next_pos(lines, pos) {next}else {return pos};
+    }
+}
+
+fn word_end(lines: &[Vec<char>], start: TextPos, big: bool) -> TextPos {
+    let mut pos = if let Some(next) = next_pos(lines, start) {next}else {return start};
+    while char_class(char_at(lines, pos), big) == 0 {
+        pos = if let Some(next) = next_pos(lines, pos) {next}else {return pos};
+    }
+    let class = char_class(char_at(lines, pos), big);
+    loop {
+        match next_pos(lines, pos) {
+            Some(next) if char_class(char_at(lines, next), big) == class => pos = next,
+            _ => return pos
+        }
+    }
+}
+
+fn word_back(lines: &[Vec<char>], start: TextPos, big: bool) -> TextPos {
+    let mut pos = if let Some(prev) = prev_pos(lines, start) {prev}else {return start};
+    while char_class(char_at(lines, pos), big) == 0 {
+        if lines[pos.row].len() == 0 && pos.row != start.row {
+            return pos
+        }
+        pos = if let Some(prev) = prev_pos(lines, pos) {prev}else {return pos};
+    }
+    let class = char_class(char_at(lines, pos), big);
+    loop {
+        match prev_pos(lines, pos) {
+            Some(prev) if char_class(char_at(lines, prev), big) == class => pos = prev,
+            _ => return pos
+        }
+    }
+}
+
+fn find_in_line(lines: &[Vec<char>], pos: TextPos, ch: char, forward: bool, till: bool, count: usize) -> Option<TextPos> {
+    let line = &lines[pos.row];
+    let mut col = pos.col;
+    for _ in 0..count {
+        col = if forward {
+            col + 1 + line.get(col + 1..)?.iter().position( | c | *c == ch)?
+        }
+        else {
+            line[..col.min(line.len())].iter().rposition( | c | *c == ch)?
+        };
+    }
+    if till {
+        col = if forward {col - 1}else {col + 1};
+    }
+    Some(TextPos {row: pos.row, col: col})
+}
+
+// jumps from the first bracket at or after pos on its line to the one it pairs with
+fn match_pair(pos: TextPos, text_buffer: &TextBuffer) -> Option<TextPos> {
+    if text_buffer.token_chunks_id != text_buffer.mutation_id {
+        return None
+    }
+    let offset = text_buffer.text_pos_to_offset(pos);
+    let line_end = offset - pos.col + text_buffer.lines[pos.row].len();
+    let token_chunks = &text_buffer.token_chunks;
+    for (index, token_chunk) in token_chunks.iter().enumerate() {
+        if token_chunk.offset + token_chunk.len <= offset {
+            continue;
+        }
+        if token_chunk.offset > line_end {
+            return None
+        }
+        match token_chunk.token_type {
+            TokenType::ParenOpen | TokenType::ParenClose => {
+                let pair = token_chunk.pair_token;
+                if pair == index || pair >= token_chunks.len() {
+                    return None
+                }
+                return Some(text_buffer.offset_to_text_pos(token_chunks[pair].offset))
+            },
+            _ => ()
+        }
+    }
+    None
+}
+
+// the mode, pending keys and messages along the bottom of the editor
+#[derive(Clone)]
+pub struct VimStatusLine {
+    pub view: View,
+    pub bg: Quad,
+    pub text: Text,
+    pub height: f32,
+}
+
+impl VimStatusLine {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            view: View::proto_overlay(cx),
+            bg: Quad {
+                z: 10.,
+                ..Quad::proto(cx)
+            },
+            text: Text {
+                z: 10.1,
+                wrapping: Wrapping::None,
+                ..Text::proto(cx)
+            },
+            height: 20.,
+        }
+    }
+
+    pub fn text_style_status() -> TextStyleId {uid!()}
+    pub fn color_bg() -> ColorId {uid!()}
+    pub fn color_text() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::text_style_status().set_base(cx, Theme::text_style_fixed().base(cx));
+        Self::color_bg().set_base(cx, color256(37, 37, 37));
+        Self::color_text().set_base(cx, Theme::color_text_deselected_focus().base(cx));
+    }
+
+    // abs_pos is the top left corner of the line
+    pub fn draw_vim_status_line(&mut self, cx: &mut Cx, abs_pos: Vec2, width: f32, status: &str) {
+        if self.view.begin_view(cx, Layout {
+            abs_origin: Some(abs_pos),
+            ..Default::default()
+        }).is_err() {
+            return
+        }
+        self.bg.color = Self::color_bg().base(cx);
+        self.text.text_style = Self::text_style_status().base(cx);
+        self.text.color = Self::color_text().base(cx);
+        let bg_inst = self.bg.begin_quad(cx, Layout {
+            align: Align::left_center(),
+            padding: Padding {l: 6., t: 0., r: 6., b: 0.},
+            walk: Walk::wh(Width::Fix(width), Height::Fix(self.height)),
+            ..Layout::default()
+        });
+        self.text.draw_text(cx, status);
+        self.bg.end_quad(cx, &bg_inst);
+        self.view.end_view(cx);
+    }
+}
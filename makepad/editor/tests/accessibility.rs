@@ -0,0 +1,30 @@
+use render::*;
+
+mod common;
+use common::*;
+
+// the access node of the code editor, queried on the headless backend
+
+#[test]
+fn code_editor_access() {
+    let (_app, mut snapshot) = editor_snapshot("fn main() {\n    let x = 1;\n}\n");
+    let tree = snapshot.access_tree();
+    let editor = tree.find(AccessRole::TextEditor, "Code editor").expect(&tree.dump());
+    assert!(!editor.focused);
+    assert_eq!(editor.node.value, "fn main() {");
+    assert!(editor.bounds.w > 300. && editor.bounds.h > 200.);
+    assert!(tree.focused().is_none());
+
+    // clicking the second line focuses the editor and moves the cursor there
+    snapshot.click(300., 80.);
+    snapshot.advance(0.1);
+    let tree = snapshot.access_tree();
+    let editor = tree.find(AccessRole::TextEditor, "Code editor").unwrap();
+    assert!(editor.focused);
+    assert_eq!(editor.node.value, "    let x = 1;");
+
+    snapshot.text_input(" // one");
+    snapshot.advance(0.1);
+    let tree = snapshot.access_tree();
+    assert_eq!(tree.focused().unwrap().node.value, "    let x = 1; // one");
+}
@@ -0,0 +1,70 @@
+use render::*;
+use editor::*;
+
+mod common;
+use common::*;
+
+// right to left strings draw reversed, while clicks and cursor keys work on logical offsets
+
+// the x, y and advance of the glyph drawn for a char
+fn char_geom(cx: &Cx, area: &Area, char_offset: usize) -> (f32, f32, f32) {
+    let read = area.get_read_ref(cx).unwrap();
+    let offset = | instance_type | area.get_instance_offset(cx, instance_type).unwrap();
+    let (x_o, y_o) = (offset(Text::instance_base_x().instance_type()), offset(Text::instance_base_y().instance_type()));
+    let advance_o = offset(Text::instance_advance().instance_type());
+    let char_offset_o = offset(Text::instance_char_offset().instance_type());
+    let get = | index: usize, o: usize | read.buffer[read.offset + o + index * read.slots];
+    let index = (0..read.count).find( | index | get(*index, char_offset_o) as usize == char_offset).unwrap();
+    (get(index, x_o), get(index, y_o), get(index, advance_o))
+}
+
+#[test]
+fn code_editor_right_to_left_string() {
+    let (app, mut snapshot) = editor_snapshot("let s = \"שלום\";\n");
+
+    // the hebrew chars at offsets 9 to 12 draw from right to left, between the quotes
+    let area = app.borrow().plain_editor.code_editor._text_area;
+    let (quote_x, _, _) = char_geom(&snapshot.cx, &area, 8);
+    let (shin_x, shin_y, shin_advance) = char_geom(&snapshot.cx, &area, 9);
+    let (mem_x, _, mem_advance) = char_geom(&snapshot.cx, &area, 12);
+    let (end_quote_x, _, _) = char_geom(&snapshot.cx, &area, 13);
+    assert!(shin_advance < 0.0 && mem_advance < 0.0);
+    assert!(quote_x < mem_x && mem_x < shin_x && shin_x < end_quote_x);
+
+    // the left edge of the shin is the right edge of the lamed, the cursor goes before the lamed
+    snapshot.click(shin_x + 1., shin_y + 5.);
+    // left moves forward through the right to left word, to after the lamed
+    snapshot.key_down(KeyCode::ArrowLeft, KeyModifiers::default());
+    snapshot.text_input("א");
+    // and right moves back, to before the lamed
+    snapshot.key_down(KeyCode::ArrowRight, KeyModifiers::default());
+    snapshot.key_down(KeyCode::ArrowRight, KeyModifiers::default());
+    snapshot.text_input("ב");
+    snapshot.advance(0.1);
+    assert_eq!(app.borrow().text_buffer.get_as_string(), "let s = \"שבלאום\";\n");
+}
+
+#[test]
+fn cursor_keys_move_visually() {
+    let mut cx = Cx::default();
+    let mut text_buffer = TextBuffer::default();
+    text_buffer.load_from_utf8(&mut cx, "ab שלום cd\nx");
+    let mut cursors = TextCursorSet::new();
+    let mut heads = Vec::new();
+    for _ in 0..12 {
+        heads.push(cursors.set[0].head);
+        cursors.move_right(1, false, &text_buffer);
+    }
+    // the hebrew word at 3 to 6 is drawn reversed, so the caret walks it from the mem on the
+    // left to the shin on the right, and then on to the space after it
+    assert_eq!(heads, vec![0, 1, 2, 6, 5, 4, 3, 7, 8, 9, 10, 11]);
+    for head in heads.iter().rev() {
+        cursors.move_left(1, false, &text_buffer);
+        assert_eq!(cursors.set[0].head, *head);
+    }
+    // left to right lines move logically
+    cursors.move_right(11, false, &text_buffer);
+    assert_eq!(cursors.set[0].head, 11);
+    cursors.move_right(1, false, &text_buffer);
+    assert_eq!(cursors.set[0].head, 12);
+}
@@ -0,0 +1,67 @@
+use render::*;
+use widget::*;
+use editor::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// a window with a plain editor on a text buffer, shared by the editor tests
+
+pub struct EditorApp {
+    pub desktop_window: DesktopWindow,
+    pub plain_editor: PlainEditor,
+    pub text_buffer: TextBuffer,
+}
+
+impl EditorApp {
+    pub fn proto(cx: &mut Cx, text: &str) -> Self {
+        set_dark_widget_theme(cx);
+        set_dark_editor_theme(cx);
+        let mut text_buffer = TextBuffer {
+            signal: cx.new_signal(),
+            ..TextBuffer::default()
+        };
+        text_buffer.load_from_utf8(cx, text);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 400., y: 300.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            plain_editor: PlainEditor::proto(cx),
+            text_buffer: text_buffer,
+        }
+    }
+
+    pub fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+        self.plain_editor.handle_plain_editor(cx, event, &mut self.text_buffer, None);
+    }
+
+    pub fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.plain_editor.draw_plain_editor(cx, &mut self.text_buffer);
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+// the app stays reachable from the test to look at the editor and the text buffer
+pub fn editor_snapshot(text: &str) -> (Rc<RefCell<EditorApp>>, Snapshot) {
+    let mut cx = Cx::default();
+    let app = Rc::new(RefCell::new(EditorApp::proto(&mut cx, text)));
+    let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+    let handler_app = app.clone();
+    let snapshot = Snapshot::new(cx, Box::new(move | cx, event | {
+        let mut app = handler_app.borrow_mut();
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            cxafterdraw.after_draw(cx);
+            return
+        }
+        app.handle_app(cx, event);
+    }));
+    (app, snapshot)
+}
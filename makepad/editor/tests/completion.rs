@@ -0,0 +1,62 @@
+use render::*;
+use editor::*;
+
+// completion ranks the words of all buffers by how well they match, how close they are to the
+// cursor and how often they are used, and accepting one completes every cursor
+
+fn load(cx: &mut Cx, text: &str) -> TextBuffer {
+    let mut text_buffer = TextBuffer::default();
+    text_buffer.load_from_utf8(cx, text);
+    RustEditor::update_token_chunks(&mut text_buffer);
+    text_buffer
+}
+
+#[test]
+fn completion_ranks_near_and_frequent_words() {
+    let mut cx = Cx::default();
+    let mut text = "let far_value = 1;\n".to_string();
+    text.push_str(&"\n".repeat(30));
+    text.push_str("let fat_value = 2;\nfa");
+    let current = load(&mut cx, &text);
+    let other = load(&mut cx, &"fan_value();\n".repeat(20));
+    let rare = load(&mut cx, "fax_value();\n");
+    let mut index = CodeCompletionIndex::default();
+    index.update_text_buffer("a.rs", &current);
+    index.update_text_buffer("b.rs", &other);
+    index.update_text_buffer("c.rs", &rare);
+
+    let mut completion = CodeCompletion::proto(&mut cx);
+    assert!(completion.update_items(&mut cx, text.chars().count(), &current, &index, false));
+    let words: Vec<&str> = completion._items.iter().map( | item | item.word.as_str()).collect();
+    // all four match "fa" the same way, the word on the line above wins over one used everywhere
+    assert_eq!(words, vec!["fat_value", "fan_value", "far_value", "fax_value"]);
+    assert_eq!(completion.selected_word(), Some("fat_value".to_string()));
+
+    // the word being typed isn't offered
+    let typed = load(&mut cx, "fat_value\nfat_v");
+    assert!(completion.update_items(&mut cx, 15, &typed, &index, false));
+    assert_eq!(completion._items[0].word, "fat_value");
+    assert!(!completion._items.iter().any( | item | item.word == "fat_v"));
+}
+
+#[test]
+fn complete_word_at_every_cursor() {
+    let mut cx = Cx::default();
+    let text = "let fo = 1;\nfo + f_x;\n  fo\n";
+    let mut text_buffer = load(&mut cx, text);
+    let mut cursors = TextCursorSet::new();
+    cursors.clear_and_set_last_cursor_head_and_tail(6, &text_buffer);
+    cursors.add_last_cursor_head_and_tail(14, &text_buffer);
+    cursors.add_last_cursor_head_and_tail(26, &text_buffer);
+
+    cursors.complete_word("foo_bar", &mut text_buffer);
+    assert_eq!(text_buffer.get_as_string(), "let foo_bar = 1;\nfoo_bar + f_x;\n  foo_bar\n");
+    let heads: Vec<usize> = cursors.set.iter().map( | cursor | cursor.head).collect();
+    assert_eq!(heads, vec![11, 24, 41]);
+    assert!(cursors.set.iter().all( | cursor | !cursor.has_selection()));
+
+    // a single undo step puts all of them back
+    assert_eq!(text_buffer.undo_stack.len(), 1);
+    text_buffer.undo(false, &mut cursors);
+    assert_eq!(text_buffer.get_as_string(), text);
+}
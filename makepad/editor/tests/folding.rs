@@ -0,0 +1,140 @@
+use render::*;
+use widget::*;
+use editor::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// brace pairs and multiline comments fold, and the cursor keys step over folded lines
+
+fn fold_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut cx = Cx::default();
+    let mut text_buffer = TextBuffer::default();
+    text_buffer.load_from_utf8(&mut cx, text);
+    RustEditor::update_token_chunks(&mut text_buffer);
+    FoldRange::compute_fold_ranges(&text_buffer.token_chunks).iter().map( | range | (range.start_line, range.end_line)).collect()
+}
+
+fn line_folds(ranges: &[(usize, usize)], folded_lines: Vec<usize>, line_count: usize) -> Vec<LineFold> {
+    let ranges = ranges.iter().map( | (start_line, end_line) | FoldRange {start_line: *start_line, end_line: *end_line}).collect();
+    let mut line_folds = Vec::new();
+    FoldRange::compute_line_folds(&ranges, &folded_lines, line_count, &mut line_folds);
+    line_folds
+}
+
+#[test]
+fn brace_and_comment_ranges() {
+    let text = "fn a() {\n    let x = 1;\n}\n/* a\n   b\n*/\nfn b() {}\nfn c() { x }\n/* one */\nfn d() {\n}\n";
+    // a pair needs a line in between to hide
+    assert_eq!(fold_ranges(text), vec![(0, 2), (3, 5)]);
+}
+
+#[test]
+fn nested_ranges() {
+    let text = "impl A {\n    fn b() {\n        if x {\n            y\n        }\n    }\n}\nfn c() { f(\n    1\n    )\n}\n";
+    // the brace and the paren of line 7 both start there, the one that ends last is kept
+    let ranges = fold_ranges(text);
+    assert_eq!(ranges, vec![(0, 6), (1, 5), (2, 4), (7, 10)]);
+
+    use LineFold::*;
+    assert_eq!(line_folds(&ranges, vec![1, 7], 11), vec![
+        Open, Folded, Hidden, Hidden, Hidden, None, None, Folded, Hidden, Hidden, None
+    ]);
+    // a fold inside a folded one stays hidden
+    assert_eq!(line_folds(&ranges, vec![0, 2], 11), vec![
+        Folded, Hidden, Hidden, Hidden, Hidden, Hidden, None, Open, None, None, None
+    ]);
+}
+
+#[test]
+fn ranges_past_the_last_line() {
+    // the text got shorter than the ranges, for instance while they are computed again
+    use LineFold::*;
+    assert_eq!(line_folds(&[(1, 10), (12, 14)], vec![1, 12], 4), vec![None, Folded, Hidden, Hidden]);
+    assert_eq!(line_folds(&[(1, 10)], vec![], 0), vec![]);
+}
+
+struct FoldApp {
+    desktop_window: DesktopWindow,
+    rust_editor: RustEditor,
+    text_buffer: TextBuffer,
+}
+
+impl FoldApp {
+    fn proto(cx: &mut Cx, text: &str) -> Self {
+        set_dark_widget_theme(cx);
+        set_dark_editor_theme(cx);
+        let mut text_buffer = TextBuffer {
+            signal: cx.new_signal(),
+            ..TextBuffer::default()
+        };
+        text_buffer.load_from_utf8(cx, text);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 400., y: 300.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            rust_editor: RustEditor::proto(cx),
+            text_buffer: text_buffer,
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+        self.rust_editor.handle_rust_editor(cx, event, &mut self.text_buffer, None);
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.rust_editor.draw_rust_editor(cx, &mut self.text_buffer);
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+fn cursor_row(app: &Rc<RefCell<FoldApp>>) -> usize {
+    let app = app.borrow();
+    app.text_buffer.offset_to_text_pos(app.rust_editor.code_editor.cursors.set[0].head).row
+}
+
+#[test]
+fn cursor_skips_folded_lines() {
+    let mut cx = Cx::default();
+    let app = Rc::new(RefCell::new(FoldApp::proto(&mut cx, "fn a() {\n    let x = 1;\n    let y = 2;\n}\nfn b() {}\n")));
+    let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+    let handler_app = app.clone();
+    let mut snapshot = Snapshot::new(cx, Box::new(move | cx, event | {
+        let mut app = handler_app.borrow_mut();
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            cxafterdraw.after_draw(cx);
+            return
+        }
+        app.handle_app(cx, event);
+    }));
+    snapshot.click(46., 62.);
+    assert_eq!(cursor_row(&app), 0);
+
+    let toggle_fold = | snapshot: &mut Snapshot | {
+        let app = &mut *app.borrow_mut();
+        assert!(app.rust_editor.code_editor.toggle_fold(&mut snapshot.cx, 0, &app.text_buffer));
+    };
+    toggle_fold(&mut snapshot);
+    snapshot.advance(0.5);
+    assert_eq!(app.borrow().rust_editor.code_editor.line_fold(1), LineFold::Hidden);
+    snapshot.key_down(KeyCode::ArrowDown, KeyModifiers::default());
+    assert_eq!(cursor_row(&app), 3);
+    snapshot.key_down(KeyCode::ArrowUp, KeyModifiers::default());
+    assert_eq!(cursor_row(&app), 0);
+    // stepping over them doesn't unfold
+    assert_eq!(app.borrow().rust_editor.code_editor.line_fold(0), LineFold::Folded);
+
+    // open again every line is a step
+    toggle_fold(&mut snapshot);
+    snapshot.advance(0.5);
+    snapshot.key_down(KeyCode::ArrowDown, KeyModifiers::default());
+    assert_eq!(cursor_row(&app), 1);
+}
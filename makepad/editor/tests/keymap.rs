@@ -0,0 +1,113 @@
+use render::*;
+use editor::*;
+
+// parsing key bindings, the primary modifier on each platform and the conflicts between bindings
+
+fn modifiers(shift: bool, control: bool, alt: bool, logo: bool) -> KeyModifiers {
+    KeyModifiers {shift: shift, control: control, alt: alt, logo: logo}
+}
+
+fn key_down(keymap: &mut KeyMap, cx: &Cx, key_code: KeyCode, modifiers: KeyModifiers) -> KeyMapEvent {
+    keymap.handle_key_down(cx, &KeyEvent {key_code: key_code, is_repeat: false, modifiers: modifiers, time: 0.})
+}
+
+#[test]
+fn key_chord_parse() {
+    let chord = KeyChord::parse("Mod+Shift+Z").unwrap();
+    assert_eq!(chord.key_code, KeyCode::KeyZ);
+    assert!(chord.primary && chord.shift && !chord.control && !chord.alt && !chord.logo);
+
+    // modifier and key names ignore case and have aliases
+    let chord = KeyChord::parse("ctrl+option+cmd+enter").unwrap();
+    assert_eq!(chord.key_code, KeyCode::Return);
+    assert!(!chord.primary && chord.control && chord.alt && chord.logo);
+    assert_eq!(KeyChord::parse("Esc").unwrap().key_code, KeyCode::Escape);
+    assert_eq!(KeyChord::parse("F12").unwrap().key_code, KeyCode::F12);
+
+    // a + key after a modifier
+    let chord = KeyChord::parse("Mod++").unwrap();
+    assert_eq!(chord.key_code, KeyCode::NumpadAdd);
+    assert!(chord.primary);
+
+    assert!(KeyChord::parse("").is_err());
+    assert!(KeyChord::parse("Hyper+Z").is_err());
+    assert!(KeyChord::parse("Mod+Nope").is_err());
+
+    let binding = KeyBinding::parse("Mod+K  Mod+0", Command::FoldAll).unwrap();
+    assert_eq!(binding.keys.len(), 2);
+    assert_eq!(binding.label(&PlatformType::Linux), "Ctrl+K Ctrl+0");
+    assert!(KeyBinding::parse(" ", Command::FoldAll).is_err());
+}
+
+#[test]
+fn key_chord_primary_modifier() {
+    let chord = KeyChord::parse("Mod+Shift+Z").unwrap();
+    assert_eq!(chord.modifiers(&PlatformType::Linux), modifiers(true, true, false, false));
+    assert_eq!(chord.modifiers(&PlatformType::Windows), modifiers(true, true, false, false));
+    assert_eq!(chord.modifiers(&PlatformType::OSX), modifiers(true, false, false, true));
+    assert_eq!(chord.label(&PlatformType::Linux), "Ctrl+Shift+Z");
+    assert_eq!(chord.label(&PlatformType::OSX), "Cmd+Shift+Z");
+
+    // the primary modifier of the other platforms works too, but not both at once
+    for platform_type in &[PlatformType::Linux, PlatformType::OSX] {
+        assert!(chord.matches(platform_type, &KeyCode::KeyZ, &modifiers(true, true, false, false)));
+        assert!(chord.matches(platform_type, &KeyCode::KeyZ, &modifiers(true, false, false, true)));
+        assert!(!chord.matches(platform_type, &KeyCode::KeyZ, &modifiers(true, true, false, true)));
+        assert!(!chord.matches(platform_type, &KeyCode::KeyZ, &modifiers(false, true, false, false)));
+        assert!(!chord.matches(platform_type, &KeyCode::KeyX, &modifiers(true, true, false, false)));
+    }
+
+    // an explicit modifier means just that one
+    let chord = KeyChord::parse("Ctrl+Space").unwrap();
+    assert!(chord.matches(&PlatformType::OSX, &KeyCode::Space, &modifiers(false, true, false, false)));
+    assert!(!chord.matches(&PlatformType::OSX, &KeyCode::Space, &modifiers(false, false, false, true)));
+}
+
+#[test]
+fn keymap_handle_key_down() {
+    let mut cx = Cx::default();
+    cx.platform_type = PlatformType::Linux;
+    let mut keymap = KeyMap::default_keymap();
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyZ, modifiers(false, true, false, false)), KeyMapEvent::Command(Command::Undo));
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyZ, modifiers(false, false, false, true)), KeyMapEvent::Command(Command::Undo));
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyZ, modifiers(true, true, false, false)), KeyMapEvent::Command(Command::Redo));
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyZ, modifiers(false, false, false, false)), KeyMapEvent::None);
+
+    // a two chord binding waits for its second chord, modifier keys don't count
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyK, modifiers(false, true, false, false)), KeyMapEvent::Pending);
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::Control, modifiers(false, true, false, false)), KeyMapEvent::None);
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::Key0, modifiers(false, true, false, false)), KeyMapEvent::Command(Command::FoldAll));
+    // an unmatched chord after a prefix is tried on its own
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyK, modifiers(false, true, false, false)), KeyMapEvent::Pending);
+    assert_eq!(key_down(&mut keymap, &cx, KeyCode::KeyP, modifiers(false, true, false, false)), KeyMapEvent::Command(Command::OpenFilePicker));
+}
+
+#[test]
+fn keymap_conflicts() {
+    assert_eq!(KeyMap::default_keymap().conflicts(&PlatformType::Linux), Vec::<String>::new());
+    assert_eq!(KeyMap::default_keymap().conflicts(&PlatformType::OSX), Vec::<String>::new());
+
+    // configured bindings replace the defaults of their command and win a clash with the others
+    let config = vec![
+        KeyBindingConfig {keys: "Mod+P".to_string(), command: Command::ToggleSoftWrap},
+        KeyBindingConfig {keys: "Mod+Q Mod+Q".to_string(), command: Command::DiffWithDisk},
+        KeyBindingConfig {keys: "Mod+Q".to_string(), command: Command::DiffMarkedFiles},
+        KeyBindingConfig {keys: "".to_string(), command: Command::Undo},
+        KeyBindingConfig {keys: "Mod+Nope".to_string(), command: Command::Redo},
+    ];
+    let (keymap, errors) = KeyMap::from_config(&config);
+    assert_eq!(errors, vec!["unknown key Nope in Mod+Nope".to_string()]);
+    assert!(!keymap.bindings.iter().any( | binding | binding.command == Command::Undo || binding.command == Command::Redo));
+    assert_eq!(keymap.conflicts(&PlatformType::Linux), vec![
+        "Ctrl+P is bound to ToggleSoftWrap and OpenFilePicker, ToggleSoftWrap wins".to_string(),
+        "Ctrl+Q for DiffMarkedFiles shadows Ctrl+Q Ctrl+Q for DiffWithDisk".to_string(),
+    ]);
+
+    // the primary modifier clashes with the same explicit one, in either order
+    let config = vec![
+        KeyBindingConfig {keys: "Cmd+P".to_string(), command: Command::ToggleSoftWrap},
+    ];
+    let (keymap, _) = KeyMap::from_config(&config);
+    assert_eq!(keymap.conflicts(&PlatformType::OSX).len(), 1);
+    assert_eq!(keymap.conflicts(&PlatformType::Linux).len(), 1);
+}
@@ -0,0 +1,64 @@
+use render::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod common;
+use common::*;
+
+#[test]
+fn code_editor_typing() {
+    let (_app, mut snapshot) = editor_snapshot("fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n");
+    snapshot.assert_snapshot(&snapshot_path!("code_editor"), 2);
+
+    // put the cursor at the end of the first line and type a new line
+    snapshot.click(300., 60.);
+    snapshot.key_down(KeyCode::Return, KeyModifiers::default());
+    snapshot.text_input("let y = x;");
+    snapshot.advance(0.1);
+    snapshot.assert_snapshot(&snapshot_path!("code_editor_typed"), 2);
+
+    // the blink timer is due half a blink after typing and hides the cursor
+    snapshot.advance(0.3);
+    snapshot.assert_snapshot(&snapshot_path!("code_editor_blink"), 2);
+}
+
+#[test]
+fn minimap_drag_scrolls() {
+    let text: String = (0..300).map( | i | format!("let line_{} = {};\n", i, i)).collect();
+    let (app, mut snapshot) = editor_snapshot(&text);
+    let (view_rect, minimap_rect, line_height) = {
+        let app = &mut *app.borrow_mut();
+        let code_editor = &mut app.plain_editor.code_editor;
+        (code_editor.view.get_rect(&snapshot.cx), code_editor.minimap._rect, code_editor.minimap._line_height)
+    };
+    assert!(line_height > 0.);
+    // the minimap is relative to the editor view, the fingers are in window coordinates
+    let x = view_rect.x + minimap_rect.x + 0.5 * minimap_rect.w;
+    let scrolled = | app: &Rc<RefCell<EditorApp>> | {
+        let app = app.borrow();
+        let code_editor = &app.plain_editor.code_editor;
+        (code_editor._scroll_pos.y, code_editor.minimap._viewport_rect)
+    };
+    assert_eq!(scrolled(&app).0, 0.);
+
+    // pressing below the viewport centers it there
+    let rel_y = minimap_rect.y + 0.5 * minimap_rect.h;
+    snapshot.finger_down(x, view_rect.y + rel_y, KeyModifiers::default());
+    let (scroll_y, viewport) = scrolled(&app);
+    assert!(scroll_y > 0.);
+    assert!((viewport.y + 0.5 * viewport.h - rel_y).abs() < 2. * line_height);
+
+    // and dragging moves it along with the finger, the viewport is drawn from the scroll position
+    snapshot.finger_move(x, view_rect.y + rel_y + 40., KeyModifiers::default());
+    let (scroll_y2, viewport2) = scrolled(&app);
+    assert!(scroll_y2 > scroll_y);
+    assert!((viewport2.y - viewport.y - 40.).abs() < line_height);
+
+    // up at the top of the minimap the view is back at the start
+    snapshot.finger_move(x, view_rect.y, KeyModifiers::default());
+    assert_eq!(scrolled(&app).0, 0.);
+    snapshot.finger_up(x, view_rect.y, KeyModifiers::default());
+    // the drag is over, moving doesn't scroll
+    snapshot.finger_move(x, view_rect.y + rel_y, KeyModifiers::default());
+    assert_eq!(scrolled(&app).0, 0.);
+}
@@ -0,0 +1,74 @@
+use render::*;
+use editor::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod common;
+use common::*;
+
+// lines wider than the wrap column break into indented rows, which the cursor keys walk
+// through one row at a time
+
+const TEXT: &str = "fn wrapped() {\n    let total = first_value + second_value + third_value;\n}\n";
+
+fn soft_wrap_snapshot(text: &str, soft_wrap: SoftWrap) -> (Rc<RefCell<EditorApp>>, Snapshot) {
+    let (app, mut snapshot) = editor_snapshot(text);
+    {
+        let code_editor = &mut app.borrow_mut().plain_editor.code_editor;
+        code_editor.soft_wrap = soft_wrap;
+        code_editor.view.redraw_view_area(&mut snapshot.cx);
+    }
+    snapshot.advance(0.1);
+    (app, snapshot)
+}
+
+fn cursor_pos(app: &Rc<RefCell<EditorApp>>) -> (usize, usize) {
+    let app = app.borrow();
+    let pos = app.text_buffer.offset_to_text_pos(app.plain_editor.code_editor.cursors.set[0].head);
+    (pos.row, pos.col)
+}
+
+#[test]
+fn code_editor_soft_wrap() {
+    let (_app, snapshot) = soft_wrap_snapshot(TEXT, SoftWrap::Column(24));
+    snapshot.assert_snapshot(&snapshot_path!("code_editor_soft_wrap"), 2);
+}
+
+#[test]
+fn cursor_keys_move_by_row() {
+    let (app, mut snapshot) = soft_wrap_snapshot(TEXT, SoftWrap::Column(24));
+    // the start of the first line
+    snapshot.click(46., 62.);
+    assert_eq!(cursor_pos(&app), (0, 0));
+    let mut positions = Vec::new();
+    for _ in 0..5 {
+        snapshot.key_down(KeyCode::ArrowDown, KeyModifiers::default());
+        positions.push(cursor_pos(&app));
+    }
+    // the long line takes four of the steps down before the closing brace
+    assert_eq!(positions.iter().map( | pos | pos.0).collect::<Vec<usize>>(), vec![1, 1, 1, 1, 2]);
+    assert!(positions.windows(2).take(3).all( | pair | pair[0].1 < pair[1].1));
+    // and back up through the same rows
+    for pos in positions[0..4].iter().rev() {
+        snapshot.key_down(KeyCode::ArrowUp, KeyModifiers::default());
+        assert_eq!(cursor_pos(&app), *pos);
+    }
+
+    // without wrapping the same line is a single row
+    let (app, mut snapshot) = soft_wrap_snapshot(TEXT, SoftWrap::Off);
+    snapshot.click(46., 62.);
+    snapshot.key_down(KeyCode::ArrowDown, KeyModifiers::default());
+    snapshot.key_down(KeyCode::ArrowDown, KeyModifiers::default());
+    assert_eq!(cursor_pos(&app).0, 2);
+}
+
+#[test]
+fn visible_lines_count_wrapped_lines_once() {
+    // the first token of the line is longer than a row, so it is split over several
+    let text = "a_very_long_identifier_that_needs_several_rows_to_fit\nb\nc\n";
+    let (wrapped, _snapshot) = soft_wrap_snapshot(text, SoftWrap::Column(20));
+    let (unwrapped, _snapshot) = soft_wrap_snapshot(text, SoftWrap::Off);
+    let visible_lines = unwrapped.borrow().plain_editor.code_editor._visible_lines;
+    assert!(visible_lines >= 3);
+    assert_eq!(wrapped.borrow().plain_editor.code_editor._visible_lines, visible_lines);
+}
@@ -0,0 +1,122 @@
+use editor::*;
+
+// the line diff as an edit script: applying it to the old text gives the new one, and it is
+// as short as the longest common subsequence allows
+
+fn lines(text: &str) -> Vec<Vec<char>> {
+    text.split('\n').map( | line | line.chars().collect()).collect()
+}
+
+// replays the chunks on old, checking that they cover both sides in order
+fn apply(old: &[Vec<char>], new: &[Vec<char>], chunks: &[DiffChunk]) -> Vec<Vec<char>> {
+    let mut out = Vec::new();
+    let mut old_pos = 0;
+    let mut new_pos = 0;
+    for chunk in chunks {
+        assert!(chunk.len > 0);
+        assert_eq!((chunk.old_start, chunk.new_start), (old_pos, new_pos));
+        match chunk.op {
+            DiffOp::Equal => {
+                for i in 0..chunk.len {
+                    assert_eq!(old[old_pos + i], new[new_pos + i]);
+                    out.push(old[old_pos + i].clone());
+                }
+                old_pos += chunk.len;
+                new_pos += chunk.len;
+            },
+            DiffOp::Delete => old_pos += chunk.len,
+            DiffOp::Insert => {
+                out.extend_from_slice(&new[new_pos..new_pos + chunk.len]);
+                new_pos += chunk.len;
+            }
+        }
+    }
+    assert_eq!((old_pos, new_pos), (old.len(), new.len()));
+    out
+}
+
+fn lcs_len(old: &[Vec<char>], new: &[Vec<char>]) -> usize {
+    let mut row = vec![0; new.len() + 1];
+    for a in old {
+        let mut diag = 0;
+        for (j, b) in new.iter().enumerate() {
+            let up = row[j + 1];
+            row[j + 1] = if a == b {diag + 1} else {row[j + 1].max(row[j])};
+            diag = up;
+        }
+    }
+    row[new.len()]
+}
+
+fn check_diff(old: &[Vec<char>], new: &[Vec<char>]) {
+    let chunks = diff_lines(old, new);
+    assert_eq!(apply(old, new, &chunks), new.to_vec());
+    let equal: usize = chunks.iter().filter( | chunk | chunk.op == DiffOp::Equal).map( | chunk | chunk.len).sum();
+    assert_eq!(equal, lcs_len(old, new));
+}
+
+#[test]
+fn diff_lines_edge_cases() {
+    let empty: Vec<Vec<char>> = Vec::new();
+    check_diff(&empty, &empty);
+    check_diff(&empty, &lines("a\nb"));
+    check_diff(&lines("a\nb"), &empty);
+    check_diff(&lines("a\nb\nc"), &lines("a\nb\nc"));
+    check_diff(&lines("a\nb\nc"), &lines("x\ny"));
+    check_diff(&lines("a\nb\na\nb"), &lines("b\na\nb\na"));
+
+    let chunks = diff_lines(&lines("a\nb\nc\nd"), &lines("a\nx\nc\nd\ne"));
+    assert_eq!(chunks, vec![
+        DiffChunk {op: DiffOp::Equal, old_start: 0, new_start: 0, len: 1},
+        DiffChunk {op: DiffOp::Delete, old_start: 1, new_start: 1, len: 1},
+        DiffChunk {op: DiffOp::Insert, old_start: 2, new_start: 1, len: 1},
+        DiffChunk {op: DiffOp::Equal, old_start: 2, new_start: 2, len: 2},
+        DiffChunk {op: DiffOp::Insert, old_start: 4, new_start: 4, len: 1},
+    ]);
+}
+
+#[test]
+fn diff_lines_round_trip() {
+    // a small line alphabet so the texts share plenty of lines in different places
+    let mut seed = 0x2545f491u32;
+    let mut next = | range: u32 | {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed % range
+    };
+    for _ in 0..300 {
+        let old_len = next(40) as usize;
+        let old: Vec<Vec<char>> = (0..old_len).map( | _ | vec![(b'a' + next(6) as u8) as char]).collect();
+        // edit a copy so the two are related, like two versions of a file
+        let mut new = old.clone();
+        for _ in 0..next(8) {
+            let pos = if new.len() > 0 {next(new.len() as u32) as usize} else {0};
+            match next(3) {
+                0 if new.len() > 0 => {new.remove(pos);},
+                1 if new.len() > 0 => new[pos] = vec![(b'a' + next(6) as u8) as char],
+                _ => new.insert(pos, vec![(b'a' + next(6) as u8) as char])
+            }
+        }
+        check_diff(&old, &new);
+        check_diff(&new, &old);
+    }
+}
+
+#[test]
+fn diff_lines_large() {
+    // a long edit script, which a trace of every step would keep in quadratic memory
+    let old: Vec<Vec<char>> = (0..5000).map( | i | format!("line {}", i).chars().collect()).collect();
+    let new: Vec<Vec<char>> = (0..5000).map( | i | if i % 2 == 0 {format!("line {}", i)} else {format!("edit {}", i)}.chars().collect()).collect();
+    let chunks = diff_lines(&old, &new);
+    assert_eq!(apply(&old, &new, &chunks), new);
+    let equal: usize = chunks.iter().filter( | chunk | chunk.op == DiffOp::Equal).map( | chunk | chunk.len).sum();
+    assert_eq!(equal, 2500);
+}
+
+#[test]
+fn diff_line_cols_changed_range() {
+    let old: Vec<char> = "let a = 1;".chars().collect();
+    let new: Vec<char> = "let abc = 1;".chars().collect();
+    assert_eq!(diff_line_cols(&old, &new), ((5, 5), (5, 7)));
+}
@@ -0,0 +1,182 @@
+use render::*;
+use editor::*;
+
+// the token chunks of the data file editors and of shader blocks in rust, as (text, type) with
+// whitespace and newlines left out
+
+fn tokens(text: &str, update_token_chunks: fn(&mut TextBuffer)) -> Vec<(String, TokenType)> {
+    let mut cx = Cx::default();
+    let mut text_buffer = TextBuffer::default();
+    text_buffer.load_from_utf8(&mut cx, text);
+    update_token_chunks(&mut text_buffer);
+    text_buffer.token_chunks.iter().filter( | chunk | !matches!(chunk.token_type, TokenType::Whitespace | TokenType::Newline | TokenType::Eof)).map( | chunk | (text_buffer.flat_text[chunk.offset..chunk.offset + chunk.len].iter().collect(), chunk.token_type)).collect()
+}
+
+fn tok(text: &str, token_type: TokenType) -> (String, TokenType) {
+    (text.to_string(), token_type)
+}
+
+#[test]
+fn json_tokens() {
+    use TokenType::*;
+    assert_eq!(tokens(r#"{"a\"b": [1, -2.5e3, true, null], "u": "\u00e9"}"#, JSONEditor::update_token_chunks), vec![
+        tok("{", ParenOpen),
+        tok(r#""a\"b""#, String),
+        tok(":", Colon),
+        tok("[", ParenOpen),
+        tok("1", Number),
+        tok(",", Delimiter),
+        tok("-2.5e3", Number),
+        tok(",", Delimiter),
+        tok("true", Bool),
+        tok(",", Delimiter),
+        tok("null", Keyword),
+        tok("]", ParenClose),
+        tok(",", Delimiter),
+        tok(r#""u""#, String),
+        tok(":", Colon),
+        tok(r#""\u00e9""#, String),
+        tok("}", ParenClose),
+    ]);
+    // json has no comments, they show up as errors
+    let comment = tokens("1 // c", JSONEditor::update_token_chunks);
+    assert_eq!(comment[0], tok("1", Number));
+    assert!(comment[1..].iter().all( | (_, token_type) | *token_type == Unexpected));
+}
+
+#[test]
+fn ron_tokens() {
+    use TokenType::*;
+    let text = "Config(\n  name: \"a\\\"b\", // c\n  r: r#\"x\"y\"#, ch: 'c', /* m\n n */ o: Some(-1.5), e: Enum::A\n)";
+    assert_eq!(tokens(text, RONEditor::update_token_chunks), vec![
+        tok("Config", TypeName),
+        tok("(", ParenOpen),
+        tok("name", Identifier),
+        tok(":", Colon),
+        tok("\"a\\\"b\"", String),
+        tok(",", Delimiter),
+        tok("//", CommentLine),
+        tok("c", CommentChunk),
+        tok("r", Identifier),
+        tok(":", Colon),
+        tok("r#\"x\"y\"#", String),
+        tok(",", Delimiter),
+        tok("ch", Identifier),
+        tok(":", Colon),
+        tok("'c'", String),
+        tok(",", Delimiter),
+        tok("/*", CommentMultiBegin),
+        tok("m", CommentChunk),
+        tok("n", CommentChunk),
+        tok("*/", CommentMultiEnd),
+        tok("o", Identifier),
+        tok(":", Colon),
+        tok("Some", TypeName),
+        tok("(", ParenOpen),
+        tok("-1.5", Number),
+        tok(")", ParenClose),
+        tok(",", Delimiter),
+        tok("e", Identifier),
+        tok(":", Colon),
+        tok("Enum", TypeName),
+        tok(":", Colon),
+        tok(":", Colon),
+        tok("A", TypeName),
+        tok(")", ParenClose),
+    ]);
+}
+
+#[test]
+fn toml_tokens() {
+    use TokenType::*;
+    let text = "# c\n[pkg.a]\nname = \"a\\\"b\" # t\nlit = 'c:\\x'\nml = \"\"\"x\ny\"\"\"\nn = 1_000\nd = 1979-05-27\n[[bin]]\n";
+    assert_eq!(tokens(text, TOMLEditor::update_token_chunks), vec![
+        tok("#", CommentLine),
+        tok("c", CommentChunk),
+        tok("[", ParenOpen),
+        tok("pkg", TypeName),
+        tok(".", Operator),
+        tok("a", TypeName),
+        tok("]", ParenClose),
+        tok("name", Identifier),
+        tok("=", Operator),
+        tok("\"a\\\"b\"", String),
+        tok("#", CommentLine),
+        tok("t", CommentChunk),
+        tok("lit", Identifier),
+        tok("=", Operator),
+        tok("'c:\\x'", String),
+        tok("ml", Identifier),
+        tok("=", Operator),
+        // a multi line string is a chunk per line
+        tok("\"\"\"", String),
+        tok("x", String),
+        tok("y\"\"\"", String),
+        tok("n", Identifier),
+        tok("=", Operator),
+        tok("1_000", Number),
+        tok("d", Identifier),
+        tok("=", Operator),
+        tok("1979-05-27", Number),
+        tok("[", ParenOpen),
+        tok("[", ParenOpen),
+        tok("bin", TypeName),
+        tok("]", ParenClose),
+        tok("]", ParenClose),
+    ]);
+}
+
+#[test]
+fn markdown_tokens() {
+    use TokenType::*;
+    let text = "# Title\n*em* **strong** `a\\` [link](url)\n```rust\nfn a() {}\n```\n- item\n";
+    assert_eq!(tokens(text, MarkdownEditor::update_token_chunks), vec![
+        tok("#", TypeName),
+        tok("Title", TypeName),
+        tok("*", Keyword),
+        tok("em", Identifier),
+        tok("*", Keyword),
+        tok("**", Keyword),
+        tok("strong", Identifier),
+        tok("**", Keyword),
+        // a backslash doesn't escape inside a code span
+        tok("`a\\`", String),
+        tok("[", ParenOpen),
+        tok("link", Identifier),
+        tok("]", ParenClose),
+        tok("(", ParenOpen),
+        tok("url", Identifier),
+        tok(")", ParenClose),
+        // a fenced block is code, whatever its language
+        tok("```rust", Keyword),
+        tok("fn", String),
+        tok("a", String),
+        tok("(", String),
+        tok(")", String),
+        tok("{}", String),
+        tok("```", Keyword),
+        tok("-", Operator),
+        tok("item", Identifier),
+    ]);
+}
+
+#[test]
+fn shader_ast_tokens() {
+    use TokenType::*;
+    let text = "let a = mix(x, y); let b: vec4<Instance> = df_circle(v);\nshader_ast!({\n    let color: vec4<Instance> = vec4(0.);\n    let t: float<Uniform> = 1.0;\n    fn pixel() -> vec4 {\n        df_viewport(pos);\n        return mix(color, vec4(1.), t);\n    }\n});\nlet c = mix(vec4(1.), df_box);\n";
+    let tokens = tokens(text, RustEditor::update_token_chunks);
+    let types_of = | word: &str | -> Vec<TokenType> {tokens.iter().filter( | (text, _) | text == word).map( | (_, token_type) | *token_type).collect()};
+    // the same words before, inside and after the shader block
+    assert_eq!(types_of("mix"), vec![Call, ShaderBuiltin, Call]);
+    assert_eq!(types_of("vec4"), vec![Identifier, ShaderBuiltin, ShaderBuiltin, ShaderBuiltin, ShaderBuiltin, Call]);
+    assert_eq!(types_of("float"), vec![ShaderBuiltin]);
+    assert_eq!(types_of("Instance"), vec![TypeName, ShaderStorage]);
+    assert_eq!(types_of("Uniform"), vec![ShaderStorage]);
+    assert_eq!(types_of("df_circle"), vec![Call]);
+    assert_eq!(types_of("df_viewport"), vec![ShaderDf]);
+    assert_eq!(types_of("df_box"), vec![Identifier]);
+    // rust words in the block stay rust
+    assert_eq!(types_of("fn"), vec![Fn]);
+    assert_eq!(types_of("return"), vec![Flow]);
+    assert_eq!(types_of("pos"), vec![Identifier]);
+}
@@ -0,0 +1,80 @@
+use render::*;
+use editor::*;
+
+// the undo history saved with a file, and given back only to the text it was saved with
+
+fn load(text: &str) -> TextBuffer {
+    let mut cx = Cx::default();
+    let mut text_buffer = TextBuffer::default();
+    text_buffer.load_from_utf8(&mut cx, text);
+    text_buffer
+}
+
+// types the texts as separate undo steps and undoes the last one, so both stacks have steps
+fn edited(text: &str, typed: &[&str]) -> TextBuffer {
+    let mut text_buffer = load(text);
+    let mut cursors = TextCursorSet::new();
+    for text in typed {
+        cursors.replace_text(text, &mut text_buffer);
+    }
+    text_buffer.undo(false, &mut cursors);
+    text_buffer
+}
+
+#[test]
+fn undo_history_round_trip() {
+    let text_buffer = edited("fn main() {}\n", &["a\n", "bc", "def"]);
+    assert_eq!(text_buffer.get_as_string(), "a\nbcfn main() {}\n");
+    let history = text_buffer.get_undo_history(1024);
+    assert_eq!((history.undo_stack.len(), history.redo_stack.len()), (2, 1));
+
+    // through the file and into a fresh buffer of the same text
+    let saved = serde_json::to_string(&history).unwrap();
+    let history: TextUndoHistory = serde_json::from_str(&saved).unwrap();
+    let mut restored = load("a\nbcfn main() {}\n");
+    assert!(restored.restore_undo_history(&history));
+
+    let mut cursors = TextCursorSet::new();
+    restored.redo(false, &mut cursors);
+    assert_eq!(restored.get_as_string(), "a\nbcdeffn main() {}\n");
+    restored.undo(false, &mut cursors);
+    restored.undo(false, &mut cursors);
+    restored.undo(false, &mut cursors);
+    assert_eq!(restored.get_as_string(), "fn main() {}\n");
+    assert_eq!(restored.undo_stack.len(), 0);
+}
+
+#[test]
+fn undo_history_size_limit() {
+    let text_buffer = edited("", &["aaaa", "bbbb", "cccc", "dddd"]);
+    // the redo step keeps the 4 chars it puts back, the undo steps only remove chars. the redo
+    // step goes first, then the most recent undo steps that still fit
+    let history = text_buffer.get_undo_history(6);
+    assert_eq!((history.undo_stack.len(), history.redo_stack.len()), (1, 1));
+    assert_eq!(history.undo_stack[0].ops[0].len, 4);
+    let history = text_buffer.get_undo_history(0);
+    assert_eq!((history.undo_stack.len(), history.redo_stack.len()), (0, 0));
+}
+
+#[test]
+fn undo_history_digest_mismatch() {
+    let text_buffer = edited("one\ntwo", &["x", "y"]);
+    let history = text_buffer.get_undo_history(1024);
+
+    // the file changed outside the editor
+    let mut changed = load("xone\nthree");
+    assert!(!changed.restore_undo_history(&history));
+    assert_eq!((changed.undo_stack.len(), changed.redo_stack.len()), (0, 0));
+
+    assert!(load("xone\ntwo").restore_undo_history(&history));
+
+    // a buffer that is still loading has no text to compare yet
+    let mut loading = TextBuffer {is_loading: true, ..load("xone\ntwo")};
+    assert!(!loading.restore_undo_history(&history));
+
+    // the digest covers the line breaks and is stable between buffers
+    assert_eq!(load("ab\nc").calc_digest(), load("ab\nc").calc_digest());
+    assert_ne!(load("ab\nc").calc_digest(), load("a\nbc").calc_digest());
+    assert_ne!(load("abc").calc_digest(), load("ab\nc").calc_digest());
+    assert_eq!(load("ab\r\nc").calc_digest(), load("ab\nc").calc_digest());
+}
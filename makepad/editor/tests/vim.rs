@@ -0,0 +1,358 @@
+use render::*;
+use editor::*;
+
+// the vim state machine driven by keys against a text buffer, with the keys vim passes on
+// typed in the way the code editor does
+
+struct VimTest {
+    vim: Vim,
+    cursors: TextCursorSet,
+    text_buffer: TextBuffer,
+}
+
+impl VimTest {
+    fn new(text: &str) -> Self {
+        let mut cx = Cx::default();
+        let mut text_buffer = TextBuffer::default();
+        text_buffer.load_from_utf8(&mut cx, text);
+        Self {
+            vim: Vim::new(),
+            cursors: TextCursorSet::new(),
+            text_buffer: text_buffer,
+        }
+    }
+
+    // chars are keys, with <esc>, <cr>, <bs> and <c-r> for the others
+    fn keys(&mut self, keys: &str) -> &mut Self {
+        let mut rest = keys;
+        while let Some(ch) = rest.chars().next() {
+            let named = [
+                ("<esc>", VimKey::Escape),
+                ("<cr>", VimKey::Return),
+                ("<bs>", VimKey::Backspace),
+                ("<c-r>", VimKey::Ctrl('r')),
+            ].iter().find( | (name, _) | rest.starts_with(name)).cloned();
+            let key = if let Some((name, key)) = named {
+                rest = &rest[name.len()..];
+                key
+            }
+            else {
+                rest = &rest[ch.len_utf8()..];
+                // in insert mode typing arrives as text input
+                if self.vim.is_inserting() {VimKey::Text(ch.to_string())}else {VimKey::Char(ch)}
+            };
+            self.key(key);
+        }
+        self
+    }
+
+    fn key(&mut self, key: VimKey) {
+        match self.vim.handle_key(key.clone(), &mut self.cursors, &mut self.text_buffer) {
+            VimEvent::Pass => match key {
+                VimKey::Text(text) => self.cursors.replace_text(&text, &mut self.text_buffer),
+                VimKey::Return => self.cursors.replace_text("\n", &mut self.text_buffer),
+                VimKey::Backspace => self.cursors.backspace(&mut self.text_buffer),
+                VimKey::Delete => self.cursors.delete(&mut self.text_buffer),
+                _ => ()
+            },
+            VimEvent::Repeat(keys) => for key in keys {
+                self.key(key);
+            },
+            _ => ()
+        }
+    }
+
+    fn text(&self) -> String {
+        self.text_buffer.get_as_string()
+    }
+
+    fn pos(&self) -> (usize, usize) {
+        let pos = self.text_buffer.offset_to_text_pos(self.cursors.set[self.cursors.last_cursor].head);
+        (pos.row, pos.col)
+    }
+
+    fn register(&self, name: char) -> Option<(String, bool)> {
+        self.vim.registers.get(&name).map( | register | (register.text.clone(), register.linewise))
+    }
+}
+
+#[test]
+fn vim_word_motions() {
+    let mut t = VimTest::new("let foo = bar.baz();\nnext line\n");
+    t.keys("w");
+    assert_eq!(t.pos(), (0, 4));
+    t.keys("w");
+    assert_eq!(t.pos(), (0, 8));
+    t.keys("e");
+    assert_eq!(t.pos(), (0, 12));
+    t.keys("b");
+    assert_eq!(t.pos(), (0, 10));
+    // big words skip punctuation
+    t.keys("W");
+    assert_eq!(t.pos(), (1, 0));
+    t.keys("B");
+    assert_eq!(t.pos(), (0, 10));
+    t.keys("0");
+    assert_eq!(t.pos(), (0, 0));
+}
+
+#[test]
+fn vim_line_motions() {
+    let mut t = VimTest::new("one\n    two\nthree\n");
+    t.keys("$");
+    assert_eq!(t.pos(), (0, 2));
+    // the column is kept past the end of the shorter lines
+    t.keys("jj");
+    assert_eq!(t.pos(), (2, 4));
+    t.keys("k^");
+    assert_eq!(t.pos(), (1, 4));
+    t.keys("G");
+    assert_eq!(t.pos(), (3, 0));
+    t.keys("gg");
+    assert_eq!(t.pos(), (0, 0));
+    t.keys("2G");
+    assert_eq!(t.pos(), (1, 4));
+}
+
+#[test]
+fn vim_find_motions() {
+    let mut t = VimTest::new("a(b, c, d)\n");
+    t.keys("f,");
+    assert_eq!(t.pos(), (0, 3));
+    t.keys(";");
+    assert_eq!(t.pos(), (0, 6));
+    t.keys(",");
+    assert_eq!(t.pos(), (0, 3));
+    t.keys("t)");
+    assert_eq!(t.pos(), (0, 8));
+    t.keys("F(");
+    assert_eq!(t.pos(), (0, 1));
+    // a find that fails stays put
+    t.keys("fz");
+    assert_eq!(t.pos(), (0, 1));
+}
+
+#[test]
+fn vim_counts() {
+    let mut t = VimTest::new("a b c d e f\n");
+    t.keys("3w");
+    assert_eq!(t.pos(), (0, 6));
+    t.keys("2b");
+    assert_eq!(t.pos(), (0, 2));
+    // the counts of the operator and the motion multiply
+    t.keys("2d2w");
+    assert_eq!(t.text(), "a f\n");
+    let mut t = VimTest::new("1\n2\n3\n4\n");
+    t.keys("j2dd");
+    assert_eq!(t.text(), "1\n4\n");
+    assert_eq!(t.pos(), (1, 0));
+    t.keys("3x");
+    assert_eq!(t.text(), "1\n\n");
+}
+
+#[test]
+fn vim_operators() {
+    let mut t = VimTest::new("let foo = bar;\n");
+    t.keys("wdw");
+    assert_eq!(t.text(), "let = bar;\n");
+    // e on the end of a word goes to the end of the next one
+    t.keys("de");
+    assert_eq!(t.text(), "let ;\n");
+    t.keys("D");
+    assert_eq!(t.text(), "let \n");
+
+    // cw stops at the end of the word and enters insert mode
+    let mut t = VimTest::new("let foo = bar;\n");
+    t.keys("wcwbaz<esc>");
+    assert_eq!(t.text(), "let baz = bar;\n");
+    assert_eq!(t.vim.mode, VimMode::Normal);
+    assert_eq!(t.pos(), (0, 6));
+    t.keys("ct;x<esc>");
+    assert_eq!(t.text(), "let bax;\n");
+
+    // a word motion doesn't delete the newline
+    let mut t = VimTest::new("one two\nthree\n");
+    t.keys("wdw");
+    assert_eq!(t.text(), "one \nthree\n");
+}
+
+#[test]
+fn vim_line_operators() {
+    let mut t = VimTest::new("    one\ntwo\nthree\n");
+    t.keys("yyjp");
+    assert_eq!(t.text(), "    one\ntwo\n    one\nthree\n");
+    assert_eq!(t.pos(), (2, 4));
+    t.keys("ddP");
+    assert_eq!(t.text(), "    one\ntwo\n    one\nthree\n");
+    assert_eq!(t.pos(), (2, 4));
+    t.keys("kdj");
+    assert_eq!(t.text(), "    one\nthree\n");
+    // cc keeps the indent
+    t.keys("kccx<esc>");
+    assert_eq!(t.text(), "    x\nthree\n");
+    // the last line takes the newline before it along
+    t.keys("Gdd");
+    assert_eq!(t.text(), "    x\nthree");
+}
+
+#[test]
+fn vim_paste() {
+    let mut t = VimTest::new("abc\n");
+    t.keys("xp");
+    assert_eq!(t.text(), "bac\n");
+    assert_eq!(t.pos(), (0, 1));
+    t.keys("yl$3p");
+    assert_eq!(t.text(), "bacaaa\n");
+    assert_eq!(t.pos(), (0, 5));
+    t.keys("0P");
+    assert_eq!(t.text(), "abacaaa\n");
+}
+
+#[test]
+fn vim_registers() {
+    let mut t = VimTest::new("one two three\nfour\n");
+    t.keys("\"ayw");
+    assert_eq!(t.register('a'), Some(("one ".to_string(), false)));
+    // an uppercase register appends
+    t.keys("w\"Ayw");
+    assert_eq!(t.register('a'), Some(("one two ".to_string(), false)));
+    t.keys("$\"ap");
+    assert_eq!(t.text(), "one two threeone two \nfour\n");
+
+    // yanks go to 0, small deletes to -, line deletes shift through 1 to 9
+    let mut t = VimTest::new("one two\nthree\nfour\n");
+    t.keys("yw");
+    assert_eq!(t.register('0'), Some(("one ".to_string(), false)));
+    t.keys("dw");
+    assert_eq!(t.register('-'), Some(("one ".to_string(), false)));
+    assert_eq!(t.register('0'), Some(("one ".to_string(), false)));
+    t.keys("dddd");
+    assert_eq!(t.register('1'), Some(("three".to_string(), true)));
+    assert_eq!(t.register('2'), Some(("two".to_string(), true)));
+    assert_eq!(t.register('"'), Some(("three".to_string(), true)));
+
+    // the black hole register keeps the others
+    t.keys("\"_dd");
+    assert_eq!(t.text(), "");
+    assert_eq!(t.register('"'), Some(("three".to_string(), true)));
+    t.keys("\"2p");
+    assert_eq!(t.text(), "\ntwo");
+
+    let mut t = VimTest::new("abc\n");
+    t.keys("\"zp");
+    assert_eq!(t.text(), "abc\n");
+    assert_eq!(t.vim.message, "Nothing in register z");
+}
+
+#[test]
+fn vim_visual_mode() {
+    let mut t = VimTest::new("one two three\n");
+    t.keys("wve");
+    assert_eq!(t.vim.mode, VimMode::Visual);
+    // the selection includes the char under the cursor
+    assert_eq!(t.cursors.get_all_as_string(&t.text_buffer), "two");
+    t.keys("y");
+    assert_eq!(t.vim.mode, VimMode::Normal);
+    assert_eq!(t.register('"'), Some(("two".to_string(), false)));
+    assert_eq!(t.pos(), (0, 4));
+    // o swaps the ends, escape leaves visual mode where the cursor is
+    t.keys("vlloh<esc>");
+    assert_eq!(t.vim.mode, VimMode::Normal);
+    assert_eq!(t.pos(), (0, 3));
+    t.keys("lvld");
+    assert_eq!(t.text(), "one o three\n");
+
+    let mut t = VimTest::new("one\ntwo\nthree\nfour\n");
+    t.keys("jVj");
+    assert_eq!(t.vim.mode, VimMode::VisualLine);
+    assert_eq!(t.cursors.get_all_as_string(&t.text_buffer), "two\nthree");
+    t.keys("d");
+    assert_eq!(t.text(), "one\nfour\n");
+    assert_eq!(t.register('"'), Some(("two\nthree".to_string(), true)));
+    // v and V switch between the visual modes
+    t.keys("vV");
+    assert_eq!(t.vim.mode, VimMode::VisualLine);
+    t.keys("v");
+    assert_eq!(t.vim.mode, VimMode::Visual);
+    t.keys("v");
+    assert_eq!(t.vim.mode, VimMode::Normal);
+}
+
+#[test]
+fn vim_insert_and_repeat() {
+    let mut t = VimTest::new("a\nb\nc\n");
+    t.keys("Ax<esc>");
+    assert_eq!(t.text(), "ax\nb\nc\n");
+    assert_eq!(t.pos(), (0, 1));
+    t.keys("j.");
+    assert_eq!(t.text(), "ax\nbx\nc\n");
+    t.keys("j.");
+    assert_eq!(t.text(), "ax\nbx\ncx\n");
+
+    let mut t = VimTest::new("a b c d e f\n");
+    t.keys("dw.");
+    assert_eq!(t.text(), "c d e f\n");
+    // a count replaces the one of the change
+    t.keys("3.");
+    assert_eq!(t.text(), "f\n");
+
+    // a visual delete repeats on the same amount of text
+    let mut t = VimTest::new("abcdef\n");
+    t.keys("vld.");
+    assert_eq!(t.text(), "ef\n");
+
+    let mut t = VimTest::new("one\n");
+    t.keys("otwo<cr>three<esc>");
+    assert_eq!(t.text(), "one\ntwo\nthree\n");
+    assert_eq!(t.vim.mode, VimMode::Normal);
+}
+
+#[test]
+fn vim_undo_redo() {
+    let mut t = VimTest::new("one two\n");
+    t.keys("dwdw");
+    assert_eq!(t.text(), "\n");
+    t.keys("u");
+    assert_eq!(t.text(), "two\n");
+    t.keys("u");
+    assert_eq!(t.text(), "one two\n");
+    t.keys("<c-r>");
+    assert_eq!(t.text(), "two\n");
+}
+
+#[test]
+fn vim_pending_keys() {
+    let mut t = VimTest::new("one two\n");
+    // an operator waits for its motion, escape drops it
+    t.keys("d");
+    assert_eq!(t.vim._pending, vec![VimKey::Char('d')]);
+    t.keys("<esc>w");
+    assert_eq!(t.vim._pending, vec![]);
+    assert_eq!(t.text(), "one two\n");
+    assert_eq!(t.pos(), (0, 4));
+    t.keys("\"");
+    assert_eq!(t.vim._pending, vec![VimKey::Char('"')]);
+    t.keys("a2");
+    assert_eq!(t.vim._pending.len(), 3);
+    t.keys("x");
+    assert_eq!(t.text(), "one o\n");
+    assert_eq!(t.register('a'), Some(("tw".to_string(), false)));
+}
+
+#[test]
+fn vim_key_from_key_down() {
+    let mut vim = Vim::new();
+    let key = | key_code, control | KeyEvent {
+        key_code: key_code,
+        is_repeat: false,
+        modifiers: KeyModifiers {control: control, ..KeyModifiers::default()},
+        time: 0.
+    };
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::KeyR, true)), Some(VimKey::Ctrl('r')));
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::ArrowLeft, false)), Some(VimKey::Char('h')));
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::KeyR, false)), None);
+    vim.mode = VimMode::Insert;
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::KeyR, true)), None);
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::ArrowLeft, false)), None);
+    assert_eq!(vim.key_from_key_down(&key(KeyCode::LBracket, true)), Some(VimKey::Escape));
+}
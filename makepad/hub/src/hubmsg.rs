@@ -131,6 +131,28 @@ pub enum HubMsg {
         path: String,
         done: bool
     },
+    
+    WorkspaceSymbolsRequest {
+        uid: HubUid
+    },
+    
+    WorkspaceSymbolsResponse {
+        uid: HubUid,
+        workspace: String,
+        symbols: Vec<HubSymbol>
+    },
+    
+    RustFormatRequest {
+        uid: HubUid,
+        path: String,
+        data: String
+    },
+    
+    RustFormatResponse {
+        uid: HubUid,
+        path: String,
+        result: Result<String, String>
+    },
 }
 
 impl HubMsg{
@@ -217,6 +239,69 @@ pub struct HubWsConfig {
 }
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HubSymbolKind {
+    Fn,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Type,
+    Mod,
+    Const,
+    Static,
+    MacroRules,
+}
+
+impl HubSymbolKind {
+    pub fn from_keyword(keyword: &str) -> Option<HubSymbolKind> {
+        match keyword {
+            "fn" => Some(HubSymbolKind::Fn),
+            "struct" => Some(HubSymbolKind::Struct),
+            "enum" => Some(HubSymbolKind::Enum),
+            "trait" => Some(HubSymbolKind::Trait),
+            "impl" => Some(HubSymbolKind::Impl),
+            "type" => Some(HubSymbolKind::Type),
+            "mod" => Some(HubSymbolKind::Mod),
+            "const" => Some(HubSymbolKind::Const),
+            "static" => Some(HubSymbolKind::Static),
+            "macro_rules" => Some(HubSymbolKind::MacroRules),
+            _ => None
+        }
+    }
+    
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            HubSymbolKind::Fn => "fn",
+            HubSymbolKind::Struct => "struct",
+            HubSymbolKind::Enum => "enum",
+            HubSymbolKind::Trait => "trait",
+            HubSymbolKind::Impl => "impl",
+            HubSymbolKind::Type => "type",
+            HubSymbolKind::Mod => "mod",
+            HubSymbolKind::Const => "const",
+            HubSymbolKind::Static => "static",
+            HubSymbolKind::MacroRules => "macro_rules",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubSymbol {
+    pub name: String,
+    pub kind: HubSymbolKind,
+    pub path: String,
+    // where the name is, for an impl where the impl keyword is
+    pub row: usize,
+    pub col: usize,
+    pub offset: usize,
+    // the closing brace or semicolon of the item, the offset is just past it
+    pub end_row: usize,
+    pub end_offset: usize,
+    // how many item bodies it is in, an fn in an impl is 1
+    pub depth: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LocMessage {
     pub path: String,
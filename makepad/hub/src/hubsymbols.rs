@@ -0,0 +1,274 @@
+use crate::hubmsg::*;
+
+const SKIP_KEYWORDS: &[&'static str] = &["mut", "unsafe", "extern", "async", "pub", "crate", "self", "super", "where", "for", "impl", "dyn"];
+
+// a deliberately simple scanner, it only has to find item definitions by name, where they end and
+// how they nest. the hub indexes the workspace with it, files that are open in the editor are
+// indexed from their token chunks instead.
+// offsets are counted in chars with '\r' skipped so they line up with the editor textbuffer
+pub fn scan_rust_symbols(path: &str, source: &str, symbols: &mut Vec<HubSymbol>) {
+    let chars: Vec<char> = source.chars().filter( | c | *c != '\r').collect();
+    let mut i = 0;
+    let mut row = 0;
+    let mut col = 0;
+    // an item header runs up to its body or semicolon, items in a body are one deeper
+    let mut in_header = false;
+    let mut header_parens = 0;
+    let mut brace_depth = 0;
+    // the item whose header we're in, and the items whose bodies we're in with their brace depth
+    let mut header_symbol = None;
+    let mut body_depths: Vec<(usize, Option<usize>)> = Vec::new();
+    fn end_symbol(symbols: &mut Vec<HubSymbol>, symbol: Option<usize>, row: usize, offset: usize) {
+        if let Some(symbol) = symbol {
+            symbols[symbol].end_row = row;
+            symbols[symbol].end_offset = offset;
+        }
+    }
+
+    fn is_ident_start(c: char) -> bool {
+        c == '_' || c.is_alphabetic()
+    }
+    fn is_ident_char(c: char) -> bool {
+        c == '_' || c.is_alphanumeric()
+    }
+    fn advance(chars: &[char], i: &mut usize, row: &mut usize, col: &mut usize, count: usize) {
+        for _ in 0..count {
+            if *i >= chars.len() {
+                return
+            }
+            if chars[*i] == '\n' {
+                *row += 1;
+                *col = 0;
+            }
+            else {
+                *col += 1;
+            }
+            *i += 1;
+        }
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = if i + 1 < chars.len() {chars[i + 1]} else {'\0'};
+        if c == '/' && next == '/' { // line comment
+            while i < chars.len() && chars[i] != '\n' {
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+            }
+        }
+        else if c == '/' && next == '*' { // block comment, these nest in rust
+            let mut depth = 0;
+            while i < chars.len() {
+                let next = if i + 1 < chars.len() {chars[i + 1]} else {'\0'};
+                if chars[i] == '/' && next == '*' {
+                    depth += 1;
+                    advance(&chars, &mut i, &mut row, &mut col, 2);
+                }
+                else if chars[i] == '*' && next == '/' {
+                    depth -= 1;
+                    advance(&chars, &mut i, &mut row, &mut col, 2);
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                else {
+                    advance(&chars, &mut i, &mut row, &mut col, 1);
+                }
+            }
+        }
+        else if c == '"' {
+            advance(&chars, &mut i, &mut row, &mut col, 1);
+            while i < chars.len() && chars[i] != '"' {
+                let skip = if chars[i] == '\\' {2} else {1};
+                advance(&chars, &mut i, &mut row, &mut col, skip);
+            }
+            advance(&chars, &mut i, &mut row, &mut col, 1);
+        }
+        else if c == 'r' && (next == '"' || next == '#') && (i == 0 || !is_ident_char(chars[i - 1])) {
+            // raw string, count the hashes
+            let mut hashes = 0;
+            let mut j = i + 1;
+            while j < chars.len() && chars[j] == '#' {
+                hashes += 1;
+                j += 1;
+            }
+            if j >= chars.len() || chars[j] != '"' { // not a raw string after all
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+                continue;
+            }
+            let count = j - i + 1;
+            advance(&chars, &mut i, &mut row, &mut col, count);
+            while i < chars.len() {
+                if chars[i] == '"' {
+                    let mut count = 0;
+                    while count < hashes && i + 1 + count < chars.len() && chars[i + 1 + count] == '#' {
+                        count += 1;
+                    }
+                    if count == hashes {
+                        advance(&chars, &mut i, &mut row, &mut col, hashes + 1);
+                        break;
+                    }
+                }
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+            }
+        }
+        else if c == '\'' {
+            // char literal or lifetime
+            if next == '\\' {
+                advance(&chars, &mut i, &mut row, &mut col, 2);
+                while i < chars.len() && chars[i] != '\'' && chars[i] != '\n' {
+                    advance(&chars, &mut i, &mut row, &mut col, 1);
+                }
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+            }
+            else if i + 2 < chars.len() && chars[i + 2] == '\'' {
+                advance(&chars, &mut i, &mut row, &mut col, 3);
+            }
+            else {
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+            }
+        }
+        else if is_ident_start(c) {
+            let start = i;
+            let (start_row, start_col) = (row, col);
+            while i < chars.len() && is_ident_char(chars[i]) {
+                advance(&chars, &mut i, &mut row, &mut col, 1);
+            }
+            let word: String = chars[start..i].iter().collect();
+            // things like fn(u8) or impl Trait in a header are types, not items
+            if in_header {
+                continue;
+            }
+            if word == "impl" {
+                header_symbol = Some(symbols.len());
+                symbols.push(HubSymbol {
+                    name: rust_impl_name(&chars[i..]),
+                    kind: HubSymbolKind::Impl,
+                    path: path.to_string(),
+                    row: start_row,
+                    col: start_col,
+                    offset: start,
+                    end_row: start_row,
+                    end_offset: start,
+                    depth: body_depths.len()
+                });
+                in_header = true;
+                header_parens = 0;
+                continue;
+            }
+            let kind = if let Some(kind) = HubSymbolKind::from_keyword(&word) {kind} else {continue};
+            // find the name, skipping whitespace, a macro bang and modifiers like mut
+            let mut j = i;
+            let mut name_row = row;
+            let mut name_col = col;
+            loop {
+                while j < chars.len() && (chars[j].is_whitespace() || (kind == HubSymbolKind::MacroRules && chars[j] == '!')) {
+                    advance(&chars, &mut j, &mut name_row, &mut name_col, 1);
+                }
+                if j >= chars.len() || !is_ident_start(chars[j]) {
+                    break;
+                }
+                let name_start = j;
+                let (start_row, start_col) = (name_row, name_col);
+                while j < chars.len() && is_ident_char(chars[j]) {
+                    advance(&chars, &mut j, &mut name_row, &mut name_col, 1);
+                }
+                let name: String = chars[name_start..j].iter().collect();
+                if name == "mut" {
+                    continue;
+                }
+                // something like const fn, let the outer loop pick up the fn
+                if name == "_" || HubSymbolKind::from_keyword(&name).is_some() || SKIP_KEYWORDS.contains(&name.as_ref()) {
+                    break;
+                }
+                header_symbol = Some(symbols.len());
+                symbols.push(HubSymbol {
+                    name: name,
+                    kind: kind,
+                    path: path.to_string(),
+                    row: start_row,
+                    col: start_col,
+                    offset: name_start,
+                    end_row: start_row,
+                    end_offset: name_start,
+                    depth: body_depths.len()
+                });
+                in_header = true;
+                header_parens = 0;
+                i = j;
+                row = name_row;
+                col = name_col;
+                break;
+            }
+        }
+        else {
+            match c {
+                '(' | '[' => header_parens += 1,
+                ')' | ']' => if header_parens > 0 {header_parens -= 1},
+                ';' => if header_parens == 0 {
+                    in_header = false;
+                    end_symbol(symbols, header_symbol.take(), row, i + 1);
+                },
+                '{' => {
+                    if in_header && header_parens == 0 {
+                        in_header = false;
+                        body_depths.push((brace_depth, header_symbol.take()));
+                    }
+                    brace_depth += 1;
+                },
+                '}' => {
+                    if brace_depth > 0 {
+                        brace_depth -= 1;
+                    }
+                    while body_depths.last().map_or(false, | (depth, _) | *depth == brace_depth) {
+                        let (_, symbol) = body_depths.pop().unwrap();
+                        end_symbol(symbols, symbol, row, i + 1);
+                    }
+                },
+                _ => ()
+            }
+            advance(&chars, &mut i, &mut row, &mut col, 1);
+        }
+    }
+    // items that are still open end with the file
+    end_symbol(symbols, header_symbol, row, chars.len());
+    for (_, symbol) in body_depths {
+        end_symbol(symbols, symbol, row, chars.len());
+    }
+}
+
+// turns ' <T> Foo<T> for Bar where T: Baz {' into 'Foo<T> for Bar'
+pub fn rust_impl_name(header: &[char]) -> String {
+    let mut name = String::new();
+    let mut angle_depth = 0;
+    let mut parens = 0;
+    let mut skip_generics = true;
+    for c in header {
+        match c {
+            '{' | ';' if parens == 0 => break,
+            '(' | '[' => parens += 1,
+            ')' | ']' => parens -= 1,
+            '<' => angle_depth += 1,
+            '>' => angle_depth -= 1,
+            _ => ()
+        }
+        if skip_generics {
+            // the generics right after impl
+            if *c == '<' || angle_depth > 0 || *c == '>' || c.is_whitespace() {
+                continue;
+            }
+            skip_generics = false;
+        }
+        if c.is_whitespace() {
+            if !name.ends_with(' ') {
+                name.push(' ');
+            }
+        }
+        else {
+            name.push(*c);
+        }
+    }
+    if let Some(pos) = name.find(" where ") {
+        name.truncate(pos);
+    }
+    name.trim().to_string()
+}
@@ -4,6 +4,7 @@ use crate::hubrouter::*;
 use crate::hubclient::*;
 use crate::httpserver::*;
 use crate::wasmstrip::*;
+use crate::hubsymbols::*;
 
 use serde::{Deserialize};
 use std::sync::{Arc, Mutex};
@@ -38,9 +39,10 @@ pub enum HubWsError {
     LocErrors(Vec<LocMessage>)
 }
 
-const INCLUDED_FILES: &[&'static str] = &[".json", ".toml", ".js", ".rs", ".txt", ".text", ".ron", ".html"];
+const INCLUDED_FILES: &[&'static str] = &[".json", ".toml", ".js", ".rs", ".txt", ".text", ".ron", ".html", ".md"];
 const EXCLUDED_FILES: &[&'static str] = &["key.ron","todo.txt","makepad_state.ron"];
-const EXCLUDED_DIRS: &[&'static str] = &["target",".git","edit_repo"];
+const EXCLUDED_DIRS: &[&'static str] = &["target",".git","edit_repo","makepad_undo"];
+const RUST_FORMAT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 impl HubWorkspace {
     
@@ -403,6 +405,22 @@ impl HubWorkspace {
                 ws.file_write(htc.from, uid, &path, data);
                 Ok(())
             },
+            HubMsg::WorkspaceSymbolsRequest {uid} => {
+                let symbols = ws.workspace_symbols(EXCLUDED_DIRS);
+                ws.route_send.send(ToHubMsg {
+                    to: HubMsgTo::Client(htc.from),
+                    msg: HubMsg::WorkspaceSymbolsResponse {
+                        uid: uid,
+                        workspace: ws.workspace.clone(),
+                        symbols: symbols
+                    }
+                });
+                Ok(())
+            },
+            HubMsg::RustFormatRequest {uid, path, data} => {
+                ws.rust_format(htc.from, uid, path, data);
+                Ok(())
+            },
             HubMsg::BuildKill {uid} => {
                 ws.process_kill(uid);
                 Ok(())
@@ -971,6 +989,101 @@ impl HubWorkspace {
         }
     }
     
+    // runs rustfmt from the directory of the file so it picks up the project's rustfmt.toml.
+    // it runs on its own thread so the hub keeps serving requests, and answers when rustfmt is done
+    pub fn rust_format(&mut self, from: HubAddr, uid: HubUid, path: String, data: String) {
+        let route_send = self.route_send.clone();
+        let file_dir = self.project_split_from_path(uid, &path).map( | (abs_dir, _project, sub_path) | {
+            if let Some(dir_pos) = sub_path.rfind('/') {
+                format!("{}/{}", abs_dir, &sub_path[..dir_pos])
+            }
+            else {
+                abs_dir
+            }
+        });
+        std::thread::spawn(move || {
+            let result = match file_dir {
+                Ok(file_dir) => Self::run_rustfmt(&file_dir, data),
+                Err(_) => Err(format!("cannot find project for {}", path))
+            };
+            route_send.send(ToHubMsg {
+                to: HubMsgTo::Client(from),
+                msg: HubMsg::RustFormatResponse {uid, path, result}
+            });
+        });
+    }
+    
+    // a rustfmt that hangs is killed after RUST_FORMAT_TIMEOUT. its output is read while it runs,
+    // so a large file can't fill the pipe and stall it
+    fn run_rustfmt(file_dir: &str, data: String) -> Result<String, String> {
+        use std::io::{Read, Write};
+        let mut command = std::process::Command::new("rustfmt");
+        // like cargo fmt, the edition of the crate. without a crate rustfmt.toml decides
+        if let Some(edition) = Self::crate_edition(file_dir) {
+            command.args(["--edition", &edition]);
+        }
+        let mut child = command
+            .current_dir(file_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err( | e | format!("cannot start rustfmt: {}", e)) ?;
+        
+        let mut stdin = child.stdin.take().unwrap();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(data.as_bytes());
+        });
+        let read_pipe = | mut pipe: Box<dyn Read + Send> | std::thread::spawn(move || {
+            let mut out = Vec::new();
+            let _ = pipe.read_to_end(&mut out);
+            out
+        });
+        let stdout = read_pipe(Box::new(child.stdout.take().unwrap()));
+        let stderr = read_pipe(Box::new(child.stderr.take().unwrap()));
+        
+        let started = std::time::Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => if started.elapsed() > RUST_FORMAT_TIMEOUT {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("rustfmt took longer than {} seconds", RUST_FORMAT_TIMEOUT.as_secs()))
+                }
+                else {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                },
+                Err(e) => return Err(format!("rustfmt failed: {}", e))
+            }
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            return Err(String::from_utf8_lossy(&stderr).to_string())
+        }
+        String::from_utf8(stdout).map_err( | _ | "rustfmt returned invalid utf8".to_string())
+    }
+    
+    // the edition in the Cargo.toml of the crate the directory is in, a workspace Cargo.toml
+    // without a package is skipped
+    fn crate_edition(dir: &str) -> Option<String> {
+        let mut dir = std::path::Path::new(dir);
+        loop {
+            if let Ok(cargo) = std::fs::read_to_string(dir.join("Cargo.toml")) {
+                if let Ok(Value::Table(table)) = cargo.parse::<Value>() {
+                    if let Some(Value::Table(package)) = table.get("package") {
+                        return Some(match package.get("edition") {
+                            Some(Value::String(edition)) => edition.clone(),
+                            _ => "2015".to_string()
+                        })
+                    }
+                }
+            }
+            dir = dir.parent() ?;
+        }
+    }
+    
     pub fn file_write(&mut self, from: HubAddr, uid: HubUid, path: &str, data: Vec<u8>) {
         if let Ok((abs_dir, _project, sub_path)) = self.project_split_from_path(uid, path) {
             
@@ -1110,6 +1223,40 @@ impl HubWorkspace {
         };
         root
     }
+    
+    pub fn workspace_symbols(&mut self, dir_ex: &[&str]) -> Vec<HubSymbol> {
+        fn read_recur(abs_path: &str, path: &str, dir_ex: &[&str], symbols: &mut Vec<HubSymbol>) {
+            if let Ok(read_dir) = fs::read_dir(abs_path) {
+                for entry in read_dir {
+                    if let Ok(entry) = entry {
+                        if let Ok(ty) = entry.file_type() {
+                            if let Ok(name) = entry.file_name().into_string() {
+                                if ty.is_dir() {
+                                    if dir_ex.contains(&name.as_ref()) {
+                                        continue;
+                                    }
+                                    read_recur(&format!("{}/{}", abs_path, name), &format!("{}/{}", path, name), dir_ex, symbols);
+                                }
+                                else if name.ends_with(".rs") {
+                                    if let Ok(source) = fs::read_to_string(format!("{}/{}", abs_path, name)) {
+                                        scan_rust_symbols(&format!("{}/{}", path, name), &source, symbols);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        
+        let mut symbols = Vec::new();
+        if let Ok(projects) = self.projects.lock() {
+            for (project, abs_path) in projects.iter() {
+                read_recur(&abs_path, &project, dir_ex, &mut symbols);
+            }
+        }
+        symbols
+    }
 }
 
 fn rel_to_abs_path(abs_root: &str, path: &str) -> String {
@@ -22,5 +22,8 @@ pub use crate::hubmsg::*;
 mod httpserver;
 pub use crate::httpserver::*;
 
+mod hubsymbols;
+pub use crate::hubsymbols::*;
+
 mod wasmstrip;
 pub use crate::wasmstrip::*;
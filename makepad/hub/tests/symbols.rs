@@ -0,0 +1,76 @@
+use hub::*;
+
+// the rust item scanner behind the workspace index and the outline
+
+fn scan(source: &str) -> Vec<(String, String, usize)> {
+    let mut symbols = Vec::new();
+    scan_rust_symbols("a/lib.rs", source, &mut symbols);
+    symbols.into_iter().map( | symbol | (symbol.kind.keyword().to_string(), symbol.name, symbol.depth)).collect()
+}
+
+fn symbol(kind: &str, name: &str, depth: usize) -> (String, String, usize) {
+    (kind.to_string(), name.to_string(), depth)
+}
+
+#[test]
+fn scan_items_and_nesting() {
+    let source = "pub struct Foo<T> {a: fn(u8) -> u8}\r\n\
+        impl<T: Clone> Iterator for Foo<T> where T: Copy {\n\
+            type Item = [u8; 4];\n\
+            pub(crate) const fn next(&mut self, f: impl Fn()) -> Option<Self::Item> {let f: fn() = g; None}\n\
+        }\n\
+        mod inner {\n\
+            enum E {A = { 1 }, B}\n\
+            static mut S: u8 = 0;\n\
+        }\n\
+        macro_rules! m {() => {}}\n\
+        trait T {fn t();}\n";
+    assert_eq!(scan(source), vec![
+        symbol("struct", "Foo", 0),
+        symbol("impl", "Iterator for Foo<T>", 0),
+        symbol("type", "Item", 1),
+        symbol("fn", "next", 1),
+        symbol("mod", "inner", 0),
+        symbol("enum", "E", 1),
+        symbol("static", "S", 1),
+        symbol("macro_rules", "m", 0),
+        symbol("trait", "T", 0),
+        symbol("fn", "t", 1),
+    ]);
+}
+
+#[test]
+fn scan_skips_comments_and_strings() {
+    let source = "// fn a() {}\n/* fn b() { /* fn c() */ } */\nconst S: &str = \"fn d() {\";\nconst R: &str = r#\"fn e() \"{\"#;\nconst C: char = '{';\nfn f<'a>(x: &'a u8) {}\n";
+    assert_eq!(scan(source), vec![
+        symbol("const", "S", 0),
+        symbol("const", "R", 0),
+        symbol("const", "C", 0),
+        symbol("fn", "f", 0),
+    ]);
+
+    // rows and columns of the name, in chars with '\r' skipped like the offset
+    let mut symbols = Vec::new();
+    scan_rust_symbols("a/lib.rs", "\r\n  fn é() {}\nstruct X;", &mut symbols);
+    assert_eq!((symbols[0].row, symbols[0].col, symbols[0].offset), (1, 5, 6));
+    assert_eq!((symbols[1].row, symbols[1].col, symbols[1].offset), (2, 7, 20));
+}
+
+#[test]
+fn scan_item_ends() {
+    // the closing brace or semicolon of each item, with the offset just past it
+    let mut symbols = Vec::new();
+    scan_rust_symbols("a/lib.rs", "struct A;\nfn b() {\n    fn c() {}\n}\nimpl A {\n}\n", &mut symbols);
+    let ends: Vec<(HubSymbolKind, String, usize, usize, usize)> = symbols.into_iter().map( | symbol | (symbol.kind, symbol.name, symbol.row, symbol.end_row, symbol.end_offset)).collect();
+    assert_eq!(ends, vec![
+        (HubSymbolKind::Struct, "A".to_string(), 0, 0, 9),
+        (HubSymbolKind::Fn, "b".to_string(), 1, 3, 34),
+        (HubSymbolKind::Fn, "c".to_string(), 2, 2, 32),
+        (HubSymbolKind::Impl, "A".to_string(), 4, 5, 45),
+    ]);
+    
+    // an item that isn't closed ends with the file
+    let mut symbols = Vec::new();
+    scan_rust_symbols("a/lib.rs", "fn d() {\n", &mut symbols);
+    assert_eq!((symbols[0].end_row, symbols[0].end_offset), (1, 9));
+}
@@ -8,6 +8,14 @@ use crate::filetree::*;
 use crate::buildmanager::*;
 use crate::makepadtheme::*;
 
+pub const MENU_VIEW_FOLD_ALL: usize = 10;
+pub const MENU_VIEW_UNFOLD_ALL: usize = 11;
+pub const MENU_VIEW_SOFT_WRAP: usize = 12;
+pub const MENU_VIEW_DIFF_DISK: usize = 13;
+pub const MENU_VIEW_DIFF_SYNC: usize = 14;
+pub const MENU_VIEW_DIFF_MARKED: usize = 15;
+pub const MENU_VIEW_DIFF_INLINE: usize = 16;
+
 pub struct App {
     pub app_window_state_template: AppWindowState,
     pub app_window_template: AppWindow,
@@ -28,66 +36,7 @@ impl App {
         set_dark_makepad_theme(cx);
         let ms = cx.new_signal();
         Self {
-            menu: Menu::main(vec![
-                Menu::sub("Makepad", "M", vec![
-                    Menu::item("About Makepad", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Preferences", ",", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Quit Makepad", "q", false, ms, 0),
-                ]),
-                Menu::sub("File", "f", vec![
-                    Menu::item("New File", "n", true, ms, 2),
-                    Menu::item("New Window", "N", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Add Folder to Workspace", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Save As", "S", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Rename", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Close Editor", "w", false, ms, 2),
-                    Menu::item("Remove Folder from Workspace", "", false, ms, 2),
-                    Menu::item("Close Window", "W", false, ms, 2),
-                ]),                 
-                Menu::sub("Edit", "e", vec![
-                    Menu::item("Undo", "z", false, ms, 2),
-                    Menu::item("Redo", "Z", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Cut", "x", false, ms, 2),
-                    Menu::item("Copy", "c", false, ms, 2),
-                    Menu::item("Paste", "v", false, ms, 3),
-                    Menu::line(),
-                    Menu::item("Find", "", false, ms, 2),
-                    Menu::item("Replace", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Find in Files", "", false, ms, 2),
-                    Menu::item("Replace in Files", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Toggle Line Comment", "", false, ms, 3),
-                    Menu::item("Toggle Block Comment", "", false, ms, 3),
-                ]),
-                Menu::sub("Selection", "s", vec![
-                    Menu::item("Select All", "a", false, ms, 2),
-                ]),
-                Menu::sub("View", "v", vec![
-                    Menu::item("Zoom In", "+", false, ms, 2),
-                    Menu::item("Zoom Out", "-", false, ms, 2),
-                ]),
-                Menu::sub("Run", "s", vec![
-                    Menu::item("Start Program", "`", false, ms, 2),
-                    Menu::item("Stop Program", "~", false, ms, 2),
-                ]),
-                Menu::sub("Window", "w", vec![
-                    Menu::item("Minimize", "m", false, ms, 2),
-                    Menu::item("Zoom", "", false, ms, 2),
-                    Menu::line(),
-                    Menu::item("Bring All to Front", "", false, ms, 2),
-                ]),
-                Menu::sub("Help", "h", vec![
-                    Menu::item("About Makepad", "",false, ms, 2),
-                ])
-            ]),
+            menu: Self::menu(ms, &KeyMap::default_keymap()),
             menu_signal:ms,
             app_window_template: AppWindow::style(cx),
             app_window_state_template: AppWindowState {
@@ -101,11 +50,18 @@ impl App {
                     first: Box::new(DockItem::TabControl {
                         current: 0,
                         previous: 0,
-                        tabs: vec![DockTab {
-                            closeable: false,
-                            title: "Files".to_string(),
-                            item: Panel::FileTree
-                        }]
+                        tabs: vec![
+                            DockTab {
+                                closeable: false,
+                                title: "Files".to_string(),
+                                item: Panel::FileTree
+                            },
+                            DockTab {
+                                closeable: false,
+                                title: "Outline".to_string(),
+                                item: Panel::Outline
+                            }
+                        ]
                     }),
                     last: Box::new(DockItem::Splitter {
                         axis: Axis::Horizontal,
@@ -162,6 +118,80 @@ impl App {
         }
     }
     
+    // the menu keys come from the keymap
+    pub fn menu(ms: Signal, keymap: &KeyMap) -> Menu {
+        Menu::main(vec![
+            Menu::sub("Makepad", "M", vec![
+                Menu::item("About Makepad", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Preferences", ",", false, ms, 2),
+                Menu::line(),
+                Menu::item("Quit Makepad", "q", false, ms, 0),
+            ]),
+            Menu::sub("File", "f", vec![
+                Menu::item("New File", "n", true, ms, 2),
+                Menu::item("New Window", "N", false, ms, 2),
+                Menu::line(),
+                Menu::item("Add Folder to Workspace", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Save As", "S", false, ms, 2),
+                Menu::line(),
+                Menu::item("Rename", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Close Editor", "w", false, ms, 2),
+                Menu::item("Remove Folder from Workspace", "", false, ms, 2),
+                Menu::item("Close Window", "W", false, ms, 2),
+            ]),                 
+            Menu::sub("Edit", "e", vec![
+                Menu::item("Undo", &keymap.menu_key(Command::Undo), false, ms, 2),
+                Menu::item("Redo", &keymap.menu_key(Command::Redo), false, ms, 2),
+                Menu::line(),
+                Menu::item("Cut", &keymap.menu_key(Command::Cut), false, ms, 2),
+                Menu::item("Copy", "c", false, ms, 2),
+                Menu::item("Paste", "v", false, ms, 3),
+                Menu::line(),
+                Menu::item("Find", "", false, ms, 2),
+                Menu::item("Replace", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Find in Files", "", false, ms, 2),
+                Menu::item("Replace in Files", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Toggle Line Comment", "", false, ms, 3),
+                Menu::item("Toggle Block Comment", "", false, ms, 3),
+            ]),
+            Menu::sub("Selection", "s", vec![
+                Menu::item("Select All", &keymap.menu_key(Command::SelectAll), false, ms, 2),
+            ]),
+            Menu::sub("View", "v", vec![
+                Menu::item("Zoom In", "+", false, ms, 2),
+                Menu::item("Zoom Out", "-", false, ms, 2),
+                Menu::line(),
+                Menu::item("Fold All", &keymap.menu_key(Command::FoldAll), true, ms, MENU_VIEW_FOLD_ALL),
+                Menu::item("Unfold All", &keymap.menu_key(Command::UnfoldAll), true, ms, MENU_VIEW_UNFOLD_ALL),
+                Menu::line(),
+                Menu::item("Toggle Soft Wrap", &keymap.menu_key(Command::ToggleSoftWrap), true, ms, MENU_VIEW_SOFT_WRAP),
+                Menu::line(),
+                Menu::item("Compare With File on Disk", &keymap.menu_key(Command::DiffWithDisk), true, ms, MENU_VIEW_DIFF_DISK),
+                Menu::item("Compare With Sync Target", &keymap.menu_key(Command::DiffWithSyncTarget), true, ms, MENU_VIEW_DIFF_SYNC),
+                Menu::item("Compare Marked Files", &keymap.menu_key(Command::DiffMarkedFiles), true, ms, MENU_VIEW_DIFF_MARKED),
+                Menu::item("Toggle Inline Diff", &keymap.menu_key(Command::ToggleInlineDiff), true, ms, MENU_VIEW_DIFF_INLINE),
+            ]),
+            Menu::sub("Run", "s", vec![
+                Menu::item("Start Program", "`", false, ms, 2),
+                Menu::item("Stop Program", "~", false, ms, 2),
+            ]),
+            Menu::sub("Window", "w", vec![
+                Menu::item("Minimize", "m", false, ms, 2),
+                Menu::item("Zoom", "", false, ms, 2),
+                Menu::line(),
+                Menu::item("Bring All to Front", "", false, ms, 2),
+            ]),
+            Menu::sub("Help", "h", vec![
+                Menu::item("About Makepad", "",false, ms, 2),
+            ])
+        ])
+    }
+    
     pub fn default_layout(&mut self, cx: &mut Cx) {
         self.state.windows = vec![self.app_window_state_template.clone()];
         self.windows = vec![self.app_window_template.clone()];
@@ -169,6 +199,7 @@ impl App {
     }
     
     pub fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        let mut key_command = None;
         match event {
             Event::Construct => {
                 self.storage.init(cx);
@@ -176,11 +207,9 @@ impl App {
                     self.default_layout(cx);
                 }
             },
-            Event::KeyDown(ke) => match ke.key_code {
-                KeyCode::KeyR => if ke.modifiers.logo || ke.modifiers.control {
-                    self.storage.reload_workspaces();
-                },
-                _ => ()
+            // the windows see the key first, so an opened picker doesn't get it
+            Event::KeyDown(ke) => if let KeyMapEvent::Command(command) = self.storage.keymap.handle_key_down(cx, ke) {
+                key_command = Some(command);
             },
             Event::Signal(se) => {
                 // process network messages for hub_ui
@@ -195,9 +224,34 @@ impl App {
                         }
                     }
                 }
+                if self.menu_signal.is_signal(se) {
+                    let command = match se.value {
+                        MENU_VIEW_FOLD_ALL => Some(Command::FoldAll),
+                        MENU_VIEW_UNFOLD_ALL => Some(Command::UnfoldAll),
+                        MENU_VIEW_SOFT_WRAP => Some(Command::ToggleSoftWrap),
+                        MENU_VIEW_DIFF_DISK => Some(Command::DiffWithDisk),
+                        MENU_VIEW_DIFF_SYNC => Some(Command::DiffWithSyncTarget),
+                        MENU_VIEW_DIFF_MARKED => Some(Command::DiffMarkedFiles),
+                        MENU_VIEW_DIFF_INLINE => Some(Command::ToggleInlineDiff),
+                        _ => None
+                    };
+                    if let Some(command) = command {
+                        self.handle_command(cx, command);
+                    }
+                }
                 if self.storage.settings_changed.is_signal(se) {
                     // we have to reload settings.
                     self.storage.reload_workspaces();
+                    self.menu = Self::menu(self.menu_signal, &self.storage.keymap);
+                    self.build_manager.log_warnings(cx, &self.storage.keymap_warnings);
+                    let vim_mode = self.storage.settings.vim_mode;
+                    self.app_window_template.set_keymap(&self.storage.keymap);
+                    self.app_window_template.set_vim_mode(vim_mode);
+                    for window in &mut self.windows {
+                        window.set_keymap(&self.storage.keymap);
+                        window.set_vim_mode(vim_mode);
+                    }
+                    cx.redraw_child_area(Area::All);
                 }
             },
             Event::FileRead(fr) => {
@@ -272,7 +326,7 @@ impl App {
                     }
                 }
                 else {
-                    for (_path, atb) in &mut self.storage.text_buffers {
+                    for (_path, atb) in self.storage.text_buffers.iter_mut().chain(self.storage.disk_buffers.iter_mut()) {
                         if let Some(utf8_data) = atb.file_read.resolve_utf8(fr) {
                             if let Ok(utf8_data) = utf8_data {
                                 atb.text_buffer.load_from_utf8(cx, utf8_data);
@@ -280,6 +334,8 @@ impl App {
                             }
                         }
                     }
+                    self.storage.load_undo_history(fr);
+                    self.storage.restore_undo_histories();
                 }
             },
             
@@ -289,6 +345,26 @@ impl App {
             window.handle_app_window(cx, event, window_index, &mut self.state, &mut self.storage, &mut self.build_manager);
             // break;
         }
+        // edits, file reads and formatting all change buffers while handling events
+        self.storage.update_completion_index();
+        if let Some(command) = key_command {
+            // unless a window used up the key
+            if *event != Event::None {
+                self.handle_command(cx, command);
+            }
+        }
+    }
+    
+    // editing commands are run by the focussed code editor itself
+    pub fn handle_command(&mut self, cx: &mut Cx, command: Command) {
+        match command {
+            Command::ReloadWorkspaces => self.storage.reload_workspaces(),
+            _ => if !command.is_editor_command() {
+                for (window_index, window) in self.windows.iter_mut().enumerate() {
+                    window.handle_command(cx, command, &self.menu, window_index, &mut self.state, &mut self.storage);
+                }
+            }
+        }
     }
     
     
@@ -4,10 +4,18 @@ use editor::*;
 use hub::*;
 use crate::appwindow::*;
 use crate::filetree::*;
+use crate::diffview::*;
+use crate::fileeditor::*;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use crate::workspace_main;
 
+// per file, in chars of text kept by the undo steps
+const UNDO_HISTORY_MAX_SIZE: usize = 256 * 1024;
+// one history file per buffer, so a save only writes the history of that buffer. the hub leaves
+// the directory out of the workspace
+const UNDO_HISTORY_DIR: &str = "makepad_undo";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppSettings {
     pub build_on_save: bool,
@@ -16,6 +24,27 @@ pub struct AppSettings {
     pub workspaces: HashMap<String, HubWsConfig>,
     pub builds: Vec<BuildTarget>,
     pub sync: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub formatter: FormatterConfig,
+    // bindings replace the default keys of their command
+    #[serde(default)]
+    pub keymap: Vec<KeyBindingConfig>,
+    // modal editing in the file editors
+    #[serde(default)]
+    pub vim_mode: bool,
+}
+
+// rustfmt runs in the workspace, the built-in formatter is the offline fallback
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FormatterConfig {
+    Rustfmt,
+    Builtin
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        FormatterConfig::Rustfmt
+    }
 }
 
 impl Default for AppSettings {
@@ -54,7 +83,10 @@ impl Default for AppSettings {
                 project: "makepad".to_string(),
                 package: "makepad".to_string(),
                 config: "check".to_string()
-            }]
+            }],
+            formatter: FormatterConfig::default(),
+            keymap: Vec::new(),
+            vim_mode: false
         }
     }
 }
@@ -76,14 +108,34 @@ pub struct AppStorage {
     pub hub_ui_message: Signal,
     pub settings_changed: Signal,
     pub settings: AppSettings,
+    pub keymap: KeyMap,
+    // key bindings that don't parse or clash, shown in the log
+    pub keymap_warnings: Vec<String>,
     pub file_tree_file_read: FileRead,
     pub app_state_file_read: FileRead,
     pub app_settings_file_read: FileRead,
-    pub text_buffers: HashMap<String, AppTextBuffer>
+    // undo histories read back for buffers that weren't ready for them yet, by path
+    pub undo_histories: HashMap<String, TextUndoHistory>,
+    pub text_buffers: HashMap<String, AppTextBuffer>,
+    // snapshots of files as they are on disk, to compare the buffers against
+    pub disk_buffers: HashMap<String, AppTextBuffer>,
+    pub completion_index: CodeCompletionIndex,
+    pub workspace_symbols: HashMap<String, Vec<HubSymbol>>,
+    pub workspace_trees: HashMap<String, WorkspaceFileTreeNode>,
+    pub format_requests: Vec<FormatRequest>
+}
+
+pub struct FormatRequest {
+    pub uid: HubUid,
+    pub path: String,
+    pub window_index: usize,
+    pub editor_id: u64,
+    pub mutation_id: u64
 }
 
 pub struct AppTextBuffer {
     pub file_read: FileRead,
+    pub undo_file_read: FileRead,
     pub read_msg: Option<ToHubMsg>,
     //pub write_msg: Option<ToHubMsg>,
     pub text_buffer: TextBuffer,
@@ -100,11 +152,19 @@ impl AppStorage {
             hub_ui_message: cx.new_signal(),
             settings_changed: cx.new_signal(),
             settings: AppSettings::default(),
+            keymap: KeyMap::default_keymap(),
+            keymap_warnings: Vec::new(),
             //rust_compiler: RustCompiler::style(cx),
             text_buffers: HashMap::new(),
+            disk_buffers: HashMap::new(),
+            completion_index: CodeCompletionIndex::default(),
+            workspace_symbols: HashMap::new(),
+            workspace_trees: HashMap::new(),
+            format_requests: Vec::new(),
             file_tree_file_read: FileRead::default(),
             app_state_file_read: FileRead::default(),
-            app_settings_file_read: FileRead::default()
+            app_settings_file_read: FileRead::default(),
+            undo_histories: HashMap::new()
         }
     }
     
@@ -139,6 +199,7 @@ impl AppStorage {
         match ron::de::from_str(utf8_data) {
             Ok(settings) => {
                 self.settings = settings;
+                self.load_keymap(cx);
                 cx.send_signal(self.settings_changed, 0);
                 
                 // so now, here we restart our hub_server if need be.
@@ -152,6 +213,18 @@ impl AppStorage {
         }
     }
     
+    fn load_keymap(&mut self, cx: &mut Cx) {
+        let (keymap, errors) = KeyMap::from_config(&self.settings.keymap);
+        self.keymap_warnings.truncate(0);
+        for error in errors {
+            self.keymap_warnings.push(format!("Cannot parse key binding {}", error));
+        }
+        for conflict in keymap.conflicts(&cx.platform_type) {
+            self.keymap_warnings.push(format!("Key binding conflict: {}", conflict));
+        }
+        self.keymap = keymap;
+    }
+    
     pub fn restart_hub_server(&mut self) {
         if let Some(hub_server) = &mut self.hub_server {
             hub_server.terminate();
@@ -186,56 +259,228 @@ impl AppStorage {
         cx.file_write("makepad_state.ron", ron.as_bytes());
     }
     
-    pub fn text_buffer_from_path(&mut self, cx: &mut Cx, path: &str) -> &mut TextBuffer {
-        
-        // if online, fallback to readfile
-        let atb = if !cx.platform_type.is_desktop() || path.find('/').is_none() {
-            let atb = self.text_buffers.entry(path.to_string()).or_insert_with( || {
-                AppTextBuffer {
-                    file_read: cx.file_read(path),
-                    read_msg: None,
-                    // write_msg: None,
-                    text_buffer: TextBuffer {
-                        is_loading: true,
-                        signal: cx.new_signal(),
-                        mutation_id: 1,
-                        ..Default::default()
+    // the history file of a buffer, the path escaped into a single file name
+    pub fn undo_history_path(path: &str) -> String {
+        let name = path.replace('%', "%25").replace('/', "%2F").replace('\\', "%5C").replace(':', "%3A");
+        format!("{}/{}.ron", UNDO_HISTORY_DIR, name)
+    }
+    
+    // picks up the history file of a buffer, if this was its read
+    pub fn load_undo_history(&mut self, fr: &FileReadEvent) {
+        for (path, atb) in self.text_buffers.iter_mut() {
+            if let Some(utf8_data) = atb.undo_file_read.resolve_utf8(fr) {
+                // a buffer without history has no file
+                if let Ok(utf8_data) = utf8_data {
+                    match ron::de::from_str(utf8_data) {
+                        Ok(history) => {
+                            self.undo_histories.insert(path.clone(), history);
+                        },
+                        Err(e) => {
+                            println!("Cannot deserialize undo history of {} {:?}", path, e);
+                        }
                     }
                 }
-            });
+                return
+            }
+        }
+    }
+    
+    // gives freshly loaded buffers their history back, histories of files that changed since are dropped
+    pub fn restore_undo_histories(&mut self) {
+        let text_buffers = &mut self.text_buffers;
+        self.undo_histories.retain( | path, history | {
+            if let Some(atb) = text_buffers.get_mut(path) {
+                let text_buffer = &mut atb.text_buffer;
+                if !text_buffer.is_loading && text_buffer.undo_stack.len() == 0 && text_buffer.redo_stack.len() == 0 {
+                    return text_buffer.restore_undo_history(history)
+                }
+            }
+            true
+        });
+    }
+    
+    fn save_undo_history(&mut self, cx: &mut Cx, path: &str) {
+        if let Some(atb) = self.text_buffers.get(path) {
+            // don't overwrite the history we haven't read yet
+            if atb.undo_file_read.is_pending() {
+                return
+            }
+            // whatever was still waiting to be restored is out of date now
+            self.undo_histories.remove(path);
+            let undo_path = Self::undo_history_path(path);
+            let history = atb.text_buffer.get_undo_history(UNDO_HISTORY_MAX_SIZE);
+            if history.undo_stack.len() > 0 || history.redo_stack.len() > 0 {
+                let ron = ron::ser::to_string(&history).unwrap();
+                cx.dir_create(UNDO_HISTORY_DIR);
+                cx.file_write(&undo_path, ron.as_bytes());
+            }
+            else {
+                cx.file_delete(&undo_path);
+            }
+        }
+    }
+    
+    pub fn text_buffer_from_path(&mut self, cx: &mut Cx, path: &str) -> &mut TextBuffer {
+        let hub_ui = &mut self.hub_ui;
+        let atb = self.text_buffers.entry(path.to_string()).or_insert_with( || {
+            let mut atb = Self::read_app_text_buffer(cx, hub_ui, path);
+            if cx.platform_type.is_desktop() {
+                atb.undo_file_read = cx.file_read(&Self::undo_history_path(path));
+            }
             atb
+        });
+        &mut atb.text_buffer
+    }
+    
+    // the file as it is on disk, read once per reload_disk_text_buffer
+    pub fn disk_text_buffer_from_path(&mut self, cx: &mut Cx, path: &str) -> &mut TextBuffer {
+        let hub_ui = &mut self.hub_ui;
+        let atb = self.disk_buffers.entry(path.to_string()).or_insert_with( || Self::read_app_text_buffer(cx, hub_ui, path));
+        &mut atb.text_buffer
+    }
+    
+    pub fn reload_disk_text_buffer(&mut self, cx: &mut Cx, path: &str) {
+        let atb = Self::read_app_text_buffer(cx, &mut self.hub_ui, path);
+        self.disk_buffers.insert(path.to_string(), atb);
+    }
+    
+    pub fn diff_source_text_buffer(&mut self, cx: &mut Cx, source: &DiffSource) -> &mut TextBuffer {
+        match source {
+            DiffSource::Buffer {path} => self.text_buffer_from_path(cx, path),
+            DiffSource::Disk {path} => self.disk_text_buffer_from_path(cx, path)
+        }
+    }
+    
+    fn read_app_text_buffer(cx: &mut Cx, hub_ui: &mut Option<HubUI>, path: &str) -> AppTextBuffer {
+        // if online, fallback to readfile
+        if !cx.platform_type.is_desktop() || path.find('/').is_none() {
+            AppTextBuffer {
+                file_read: cx.file_read(path),
+                undo_file_read: FileRead::default(),
+                read_msg: None,
+                // write_msg: None,
+                text_buffer: TextBuffer {
+                    is_loading: true,
+                    signal: cx.new_signal(),
+                    mutation_id: 1,
+                    ..Default::default()
+                }
+            }
         }
         else {
-            let hub_ui = self.hub_ui.as_mut().unwrap();
-            let atb = self.text_buffers.entry(path.to_string()).or_insert_with( || {
-                // lets find the right workspace
-                let workspace_pos = path.find('/').unwrap();
-                let uid = hub_ui.route_send.alloc_uid();
-                let (workspace, rest) = path.split_at(workspace_pos);
-                let (_, rest) = rest.split_at(1);
-                let msg = ToHubMsg {
-                    to: HubMsgTo::Workspace(workspace.to_string()),
-                    msg: HubMsg::FileReadRequest {
-                        uid: uid.clone(),
-                        path: rest.to_string()
-                    }
-                };
-                hub_ui.route_send.send(msg.clone());
-                AppTextBuffer {
-                    file_read: FileRead::default(),
-                    read_msg: Some(msg),
-                    // write_msg: None,
-                    text_buffer: TextBuffer {
-                        is_loading: true,
-                        signal: cx.new_signal(),
-                        mutation_id: 1,
-                        ..Default::default()
+            let hub_ui = hub_ui.as_mut().unwrap();
+            // lets find the right workspace
+            let workspace_pos = path.find('/').unwrap();
+            let uid = hub_ui.route_send.alloc_uid();
+            let (workspace, rest) = path.split_at(workspace_pos);
+            let (_, rest) = rest.split_at(1);
+            let msg = ToHubMsg {
+                to: HubMsgTo::Workspace(workspace.to_string()),
+                msg: HubMsg::FileReadRequest {
+                    uid: uid.clone(),
+                    path: rest.to_string()
+                }
+            };
+            hub_ui.route_send.send(msg.clone());
+            AppTextBuffer {
+                file_read: FileRead::default(),
+                undo_file_read: FileRead::default(),
+                read_msg: Some(msg),
+                // write_msg: None,
+                text_buffer: TextBuffer {
+                    is_loading: true,
+                    signal: cx.new_signal(),
+                    mutation_id: 1,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+    
+    // the same file in the other workspace of a sync pair
+    pub fn sync_target_path(&self, path: &str) -> Option<String> {
+        for (sync, points) in &self.settings.sync {
+            if path.starts_with(sync) {
+                if let Some(point) = points.first() {
+                    return Some(format!("{}{}", point, &path[sync.len()..]))
+                }
+            }
+            for point in points {
+                if path.starts_with(point) {
+                    return Some(format!("{}{}", sync, &path[point.len()..]))
+                }
+            }
+        }
+        None
+    }
+    
+    pub fn text_buffer_and_completion_index(&mut self, cx: &mut Cx, path: &str) -> (&mut TextBuffer, &CodeCompletionIndex) {
+        self.text_buffer_from_path(cx, path);
+        (&mut self.text_buffers.get_mut(path).unwrap().text_buffer, &self.completion_index)
+    }
+    
+    // keeps the completion words in sync with every changed buffer, drawn or not
+    pub fn update_completion_index(&mut self) {
+        for (path, atb) in self.text_buffers.iter_mut() {
+            FileEditor::update_token_chunks(path, &mut atb.text_buffer);
+            self.completion_index.update_text_buffer(path, &atb.text_buffer);
+        }
+    }
+    
+    // the symbol index of the workspace is stale after a save. only the saved file is indexed
+    // again, the hub indexes the whole workspace once when it is loaded
+    pub fn update_workspace_symbols(&mut self, path: &str) {
+        let workspace_pos = if let Some(workspace_pos) = path.find('/') {workspace_pos} else {return};
+        let (workspace, file_path) = (&path[..workspace_pos], &path[workspace_pos + 1..]);
+        if !path.ends_with(".rs") {
+            return
+        }
+        if let (Some(symbols), Some(atb)) = (self.workspace_symbols.get_mut(workspace), self.text_buffers.get_mut(path)) {
+            symbols.retain( | symbol | symbol.path != file_path);
+            RustEditor::update_token_chunks(&mut atb.text_buffer);
+            symbols.extend(text_buffer_symbols(file_path, &atb.text_buffer));
+        }
+    }
+    
+    // all definitions with this name across the workspaces, with the app path of their file
+    pub fn find_workspace_symbols(&self, name: &str) -> Vec<(String, HubSymbol)> {
+        let mut found = Vec::new();
+        for (workspace, symbols) in &self.workspace_symbols {
+            for symbol in symbols {
+                if symbol.name == name && symbol.kind != HubSymbolKind::Impl {
+                    found.push((format!("{}/{}", workspace, symbol.path), symbol.clone()));
+                }
+            }
+        }
+        found.sort_by( | a, b | a.0.cmp(&b.0).then(a.1.offset.cmp(&b.1.offset)));
+        found
+    }
+    
+    // the app paths of every file in the workspace trees we got from the hub
+    pub fn workspace_file_paths(&self) -> Vec<String> {
+        fn paths_recur(node: &WorkspaceFileTreeNode, base: &str, paths: &mut Vec<String>) {
+            match node {
+                WorkspaceFileTreeNode::File {name, ..} => {
+                    paths.push(format!("{}/{}", base, name));
+                },
+                WorkspaceFileTreeNode::Folder {name, folder, ..} => {
+                    let base = format!("{}/{}", base, name);
+                    for child in folder {
+                        paths_recur(child, &base, paths);
                     }
                 }
-            });
-            atb
-        };
-        &mut atb.text_buffer
+            }
+        }
+        let mut paths = Vec::new();
+        for (workspace, tree) in &self.workspace_trees {
+            if let WorkspaceFileTreeNode::Folder {folder, ..} = tree {
+                for child in folder {
+                    paths_recur(child, workspace, &mut paths);
+                }
+            }
+        }
+        paths.sort();
+        paths
     }
     
     pub fn text_buffer_file_write(&mut self, cx: &mut Cx, path: &str) {
@@ -263,6 +508,13 @@ impl AppStorage {
                     let uid = hub_ui.route_send.alloc_uid();
                     let utf8_bytes = utf8_data.into_bytes();
                     send_file_write_request(hub_ui, uid, path, &utf8_bytes);
+                    // the file on disk matches the buffer again
+                    if let Some(disk) = self.disk_buffers.get_mut(path) {
+                        if !disk.text_buffer.is_loading {
+                            disk.text_buffer.lines = atb.text_buffer.lines.clone();
+                            disk.text_buffer.mutation_id += 1;
+                        }
+                    }
                     // lets send our file write to all sync points.
                     for (sync, points) in &self.settings.sync{
                         if path.starts_with(sync){
@@ -273,6 +525,8 @@ impl AppStorage {
                             }
                         }
                     }
+                    self.update_workspace_symbols(path);
+                    self.save_undo_history(cx, path);
                 }
             }
             else { // its not a workspace, its a system (settings) file
@@ -284,10 +538,41 @@ impl AppStorage {
                         self.load_settings(cx, &utf8_data);
                     };
                 }
+                self.save_undo_history(cx, path);
             }
         }
     }
     
+    // sends the buffer to rustfmt in its workspace, returns false if the built-in formatter should run
+    pub fn rust_format_request(&mut self, path: &str, window_index: usize, editor_id: u64) -> bool {
+        if self.settings.formatter != FormatterConfig::Rustfmt || !path.ends_with(".rs") {
+            return false
+        }
+        let workspace_pos = if let Some(workspace_pos) = path.find('/') {workspace_pos} else {return false};
+        let hub_ui = if let Some(hub_ui) = &mut self.hub_ui {hub_ui} else {return false};
+        let atb = if let Some(atb) = self.text_buffers.get(path) {atb} else {return false};
+        if atb.text_buffer.is_loading {
+            return false
+        }
+        let uid = hub_ui.route_send.alloc_uid();
+        hub_ui.route_send.send(ToHubMsg {
+            to: HubMsgTo::Workspace(path[..workspace_pos].to_string()),
+            msg: HubMsg::RustFormatRequest {
+                uid: uid,
+                path: path[workspace_pos + 1..].to_string(),
+                data: atb.text_buffer.get_as_string()
+            }
+        });
+        self.format_requests.push(FormatRequest {
+            uid: uid,
+            path: path.to_string(),
+            window_index: window_index,
+            editor_id: editor_id,
+            mutation_id: atb.text_buffer.mutation_id
+        });
+        true
+    }
+    
     pub fn reload_workspaces(&mut self) {
         let hub_ui = self.hub_ui.as_mut().unwrap();
         let uid = hub_ui.route_send.alloc_uid();
@@ -330,6 +615,10 @@ impl AppStorage {
                         to: HubMsgTo::Workspace(workspace.clone()),
                         msg: HubMsg::ListPackagesRequest {uid: uid}
                     });
+                    hub_ui.route_send.send(ToHubMsg {
+                        to: HubMsgTo::Workspace(workspace.clone()),
+                        msg: HubMsg::WorkspaceSymbolsRequest {uid: uid}
+                    });
                 }
                 self.workspaces_request_uid = uid;
                 // add all workspace nodes
@@ -353,7 +642,7 @@ impl AppStorage {
                     window.file_panel.file_tree.view.redraw_view_area(cx);
                 }
                 // lets resend the file load we haven't gotten
-                for (_path, atb) in &mut self.text_buffers {
+                for (_path, atb) in self.text_buffers.iter_mut().chain(self.disk_buffers.iter_mut()) {
                     if let Some(cth_msg) = &atb.read_msg {
                         hub_ui.route_send.send(cth_msg.clone())
                     }
@@ -364,6 +653,7 @@ impl AppStorage {
                 // replace a workspace node
                 if let WorkspaceFileTreeNode::Folder {name, ..} = &tree {
                     let workspace = name.clone();
+                    self.workspace_trees.insert(workspace.clone(), tree.clone());
                     // insert each filetree at the right childnode
                     for (window_index, window) in windows.iter_mut().enumerate() {
                         if let FileNode::Folder {folder, ..} = &mut window.file_panel.file_tree.root_node {
@@ -380,8 +670,31 @@ impl AppStorage {
                     }
                 }
             },
+            HubMsg::WorkspaceSymbolsResponse {uid, workspace, symbols} => if uid == self.workspaces_request_uid {
+                self.workspace_symbols.insert(workspace, symbols);
+            },
+            HubMsg::RustFormatResponse {uid, result, ..} => if let Some(index) = self.format_requests.iter().position( | r | r.uid == uid) {
+                let request = self.format_requests.remove(index);
+                let atb = if let Some(atb) = self.text_buffers.get_mut(&request.path) {atb} else {return};
+                // the buffer was edited while rustfmt ran, drop the result
+                if atb.text_buffer.mutation_id != request.mutation_id {
+                    return
+                }
+                let file_editor = if let Some(window) = windows.get_mut(request.window_index) {
+                    if let Some(file_editor) = window.file_editors.get(request.editor_id) {file_editor} else {return}
+                } else {return};
+                match result {
+                    Ok(formatted) => {
+                        file_editor.apply_formatted(cx, &mut atb.text_buffer, TextBuffer::split_string_to_lines(&formatted));
+                    },
+                    Err(err) => {
+                        println!("rustfmt failed, using the built-in formatter: {}", err);
+                        file_editor.auto_format(cx, &mut atb.text_buffer);
+                    }
+                }
+            },
             HubMsg::FileReadResponse {uid, data, ..} => {
-                for (_path, atb) in &mut self.text_buffers {
+                for (_path, atb) in self.text_buffers.iter_mut().chain(self.disk_buffers.iter_mut()) {
                     if let Some(cth_msg) = &atb.read_msg {
                         if let HubMsg::FileReadRequest {uid: read_uid, ..} = &cth_msg.msg {
                             if *read_uid == uid {
@@ -400,6 +713,7 @@ impl AppStorage {
                         }
                     }
                 }
+                self.restore_undo_histories();
             },
             _ => {}
         }
@@ -421,4 +735,157 @@ pub fn hub_to_tree(node: &WorkspaceFileTreeNode) -> FileNode {
             }
         }
     }
-}
\ No newline at end of file
+}
+// the items of an open rust file, found in the token chunks of the rust tokenizer. fn is an Fn
+// token, struct, enum, trait and impl are TypeDef tokens and the other items are keywords.
+// the symbols are the same as the ones the hub scans from the file on disk
+pub fn text_buffer_symbols(path: &str, text_buffer: &TextBuffer) -> Vec<HubSymbol> {
+    let chunks = &text_buffer.token_chunks;
+    let chunk_text = | index: usize | -> String {
+        let chunk = &chunks[index];
+        text_buffer.flat_text[chunk.offset..chunk.offset + chunk.len].iter().collect()
+    };
+    // skips whitespace and comments
+    let next_token = | mut index: usize | -> usize {
+        index += 1;
+        while index < chunks.len() && chunks[index].token_type.should_ignore() {
+            index += 1;
+        }
+        index
+    };
+    let mut symbols = Vec::new();
+    // the closing braces of the item bodies we are in
+    let mut bodies: Vec<usize> = Vec::new();
+    let mut index = 0;
+    while index < chunks.len() {
+        while bodies.last().map_or(false, | end | *end < index) {
+            bodies.pop();
+        }
+        let kind = match chunks[index].token_type {
+            TokenType::Fn | TokenType::TypeDef | TokenType::Keyword => HubSymbolKind::from_keyword(&chunk_text(index)),
+            TokenType::Identifier | TokenType::Call if chunk_text(index) == "macro_rules" => Some(HubSymbolKind::MacroRules),
+            _ => None
+        };
+        let kind = if let Some(kind) = kind {kind} else {
+            index += 1;
+            continue;
+        };
+        
+        // the name, an impl is named after its header
+        let mut name_index = next_token(index);
+        if name_index < chunks.len() && (chunk_text(name_index) == "mut" || kind == HubSymbolKind::MacroRules && chunk_text(name_index) == "!") {
+            name_index = next_token(name_index);
+        }
+        if kind == HubSymbolKind::Impl {
+            name_index = index;
+        }
+        else if name_index >= chunks.len() || !is_symbol_name(&chunk_text(name_index), chunks[name_index].token_type) {
+            // things like fn(u8) are types, and in const fn the fn is the item
+            index += 1;
+            continue;
+        }
+        
+        // the header runs up to the body or the semicolon, parens in it are skipped whole
+        let mut end = name_index;
+        let mut has_body = false;
+        while end < chunks.len() {
+            match chunks[end].token_type {
+                TokenType::ParenOpen => {
+                    if chunk_text(end) == "{" {
+                        has_body = true;
+                        break;
+                    }
+                    let pair = chunks[end].pair_token;
+                    if pair > end && pair < chunks.len() {
+                        end = pair;
+                    }
+                },
+                TokenType::ParenClose => break,
+                TokenType::Delimiter if chunk_text(end) == ";" => break,
+                _ => ()
+            }
+            end += 1;
+        }
+        let end_chunk = if has_body {
+            let pair = chunks[end].pair_token;
+            if pair > end && pair < chunks.len() {pair} else {chunks.len() - 1}
+        }
+        else {
+            end.min(chunks.len() - 1)
+        };
+        
+        let offset = chunks[name_index].offset;
+        let pos = text_buffer.offset_to_text_pos(offset);
+        symbols.push(HubSymbol {
+            name: if kind == HubSymbolKind::Impl {
+                rust_impl_name(&text_buffer.flat_text[offset + chunks[index].len..])
+            }
+            else {
+                chunk_text(name_index)
+            },
+            kind: kind,
+            path: path.to_string(),
+            row: pos.row,
+            col: pos.col,
+            offset: offset,
+            end_row: text_buffer.offset_to_text_pos(chunks[end_chunk].offset).row,
+            end_offset: chunks[end_chunk].offset + chunks[end_chunk].len,
+            depth: bodies.len()
+        });
+        // items in the body are one deeper
+        if has_body {
+            bodies.push(end_chunk);
+        }
+        index = end + 1;
+    }
+    symbols
+}
+
+fn is_symbol_name(name: &str, token_type: TokenType) -> bool {
+    match token_type {
+        TokenType::Keyword | TokenType::Fn | TokenType::TypeDef | TokenType::Flow | TokenType::Looping | TokenType::Bool => false,
+        _ => name != "_" && name.starts_with( | c: char | c == '_' || c.is_alphabetic())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn symbols(text: &str) -> Vec<HubSymbol> {
+        let mut cx = Cx::default();
+        let mut text_buffer = TextBuffer::default();
+        text_buffer.load_from_utf8(&mut cx, text);
+        RustEditor::update_token_chunks(&mut text_buffer);
+        text_buffer_symbols("a/lib.rs", &text_buffer)
+    }
+    
+    // the open file is indexed from its tokens, the hub scans the text. both find the same items
+    #[test]
+    fn token_symbols_match_the_hub() {
+        let sources = [
+            "pub struct Foo<T> {a: fn(u8) -> u8}\n\
+            impl<T: Clone> Iterator for Foo<T> where T: Copy {\n\
+                type Item = [u8; 4];\n\
+                pub(crate) const fn next(&mut self, f: impl Fn()) -> Option<Self::Item> {let f: fn() = g; None}\n\
+            }\n\
+            mod inner {\n\
+                enum E {A = { 1 }, B}\n\
+                static mut S: u8 = 0;\n\
+            }\n\
+            macro_rules! m {() => {}}\n\
+            trait T {fn t();}\n",
+            "// fn a() {}\n/* fn b() { /* fn c() */ } */\nconst S: &str = \"fn d() {\";\nconst R: &str = r\"fn e() {\";\nconst C: char = '{';\nfn f<'a>(x: &'a u8) -> &'static u8 {x}\n",
+            "struct A;\nfn b() {\n    fn c() {}\n}\nimpl A {\n}\n",
+        ];
+        for source in sources.iter() {
+            let mut scanned = Vec::new();
+            scan_rust_symbols("a/lib.rs", source, &mut scanned);
+            assert_eq!(symbols(source), scanned);
+        }
+        let names: Vec<String> = symbols(sources[0]).into_iter().map( | symbol | format!("{} {}", symbol.kind.keyword(), symbol.name)).collect();
+        assert_eq!(names, vec![
+            "struct Foo", "impl Iterator for Foo<T>", "type Item", "fn next", "mod inner", "enum E", "static S", "macro_rules m", "trait T", "fn t"
+        ]);
+    }
+}
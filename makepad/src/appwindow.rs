@@ -3,13 +3,17 @@ use render::*;
 use widget::*;
 use serde::*;
 use editor::*;
+use hub::*;
+use std::collections::HashMap;
 
 use crate::appstorage::*;
 use crate::fileeditor::*;
+use crate::diffview::*;
 use crate::filetree::*;
 use crate::filepanel::*;
 use crate::loglist::*;
 use crate::logitem::*;
+use crate::outline::*;
 use crate::keyboard::*;
 use crate::buildmanager::*;
 use crate::homepage::*;
@@ -20,8 +24,10 @@ pub enum Panel {
     LogItem,
     Keyboard,
     FileTree,
+    Outline,
     FileEditorTarget,
-    FileEditor {path: String, scroll_pos:Vec2, editor_id: u64}
+    FileEditor {path: String, scroll_pos:Vec2, editor_id: u64},
+    Diff {left: DiffSource, right: DiffSource, inline: bool, diff_id: u64}
 }
 
 #[derive(Clone)]
@@ -32,8 +38,20 @@ pub struct AppWindow {
     pub log_item: LogItem,
     pub log_list: LogList,
     pub keyboard: Keyboard,
+    pub outline: Outline,
+    pub picker: FuzzyPicker,
+    pub command_palette: CommandPalette,
     pub file_editors: Elements<u64, FileEditor, FileEditorTemplates>,
+    pub diff_views: Elements<u64, DiffView, DiffView>,
     pub dock: Dock<Panel>,
+    pub _focussed_editor: Option<(String, u64)>,
+    pub _picker_targets: Vec<PickerTarget>,
+}
+
+#[derive(Clone)]
+pub enum PickerTarget {
+    File {path: String},
+    Offset {path: String, offset: usize},
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -46,7 +64,28 @@ pub struct AppWindowState {
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct AppState {
-    pub windows: Vec<AppWindowState>
+    pub windows: Vec<AppWindowState>,
+    #[serde(default)]
+    pub recent_files: Vec<String>,
+    #[serde(default)]
+    pub folded_lines: HashMap<String, Vec<usize>>
+}
+
+impl AppState {
+    pub fn add_recent_file(&mut self, path: &str) {
+        self.recent_files.retain( | recent | recent != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(32);
+    }
+    
+    pub fn set_folded_lines(&mut self, path: &str, folded_lines: Vec<usize>) {
+        if folded_lines.len() > 0 {
+            self.folded_lines.insert(path.to_string(), folded_lines);
+        }
+        else {
+            self.folded_lines.remove(path);
+        }
+    }
 }
 
 impl AppWindow {
@@ -60,14 +99,24 @@ impl AppWindow {
             file_editors: Elements::new(FileEditorTemplates {
                 rust_editor: RustEditor::proto(cx),
                 js_editor: JSEditor::proto(cx),
+                json_editor: JSONEditor::proto(cx),
+                ron_editor: RONEditor::proto(cx),
+                toml_editor: TOMLEditor::proto(cx),
+                markdown_editor: MarkdownEditor::proto(cx),
                 plain_editor: PlainEditor::proto(cx)
             }),
+            diff_views: Elements::new(DiffView::proto(cx)),
             home_page: HomePage::proto(cx),
             keyboard: Keyboard::proto(cx),
             log_item: LogItem::proto(cx),
             log_list: LogList::proto(cx),
+            outline: Outline::proto(cx),
+            picker: FuzzyPicker::proto(cx),
+            command_palette: CommandPalette::proto(cx),
             file_panel: FilePanel::proto(cx),
             dock: Dock ::proto(cx),
+            _focussed_editor: None,
+            _picker_targets: Vec::new(),
         }
     }
     
@@ -91,9 +140,40 @@ impl AppWindow {
             _ => ()
         }
         
+        match self.picker.handle_fuzzy_picker(cx, event) {
+            FuzzyPickerEvent::Select {index} => match self._picker_targets.get(index).cloned() {
+                Some(PickerTarget::File {path}) => {
+                    if self.focus_or_new_editor(cx, window_index, state, &path) {
+                        storage.save_state(cx, state);
+                    }
+                },
+                Some(PickerTarget::Offset {path, offset}) => {
+                    self.open_path_at_offset(cx, window_index, state, storage, &path, offset);
+                },
+                None => ()
+            },
+            FuzzyPickerEvent::Cancel => {
+                if let Some((path, _)) = self._focussed_editor.clone() {
+                    self.focus_or_new_editor(cx, window_index, state, &path);
+                }
+            },
+            _ => ()
+        }
+        
+        match self.command_palette.handle_command_palette(cx, event) {
+            CommandPaletteEvent::Fire {..} | CommandPaletteEvent::Cancel => {
+                if let Some((path, _)) = self._focussed_editor.clone() {
+                    self.focus_or_new_editor(cx, window_index, state, &path);
+                }
+            },
+            _ => ()
+        }
+        
         let dock_items = &mut state.windows[window_index].dock_items;
         let mut dock_walker = self.dock.walker(dock_items);
         let mut file_tree_event = FileTreeEvent::None;
+        let mut recent_file = None;
+        let mut fold_change = None;
         while let Some(item) = dock_walker.walk_handle_dock(cx, event) {
             match item {
                 Panel::LogList => {
@@ -123,12 +203,28 @@ impl AppWindow {
                 Panel::FileTree => {
                     file_tree_event = self.file_panel.handle_file_panel(cx, event);
                 },
+                Panel::Outline => {
+                    match self.outline.handle_outline(cx, event, storage) {
+                        OutlineEvent::SelectSymbol {path} => {
+                            file_tree_event = FileTreeEvent::SelectFile {path: path};
+                        },
+                        _ => ()
+                    }
+                },
                 Panel::FileEditor {path, scroll_pos, editor_id} => {
                     if let Some(file_editor) = &mut self.file_editors.get(*editor_id) {
+                        if file_editor.has_key_focus(cx) {
+                            let is_new_focus = if let Some((focussed_path, _)) = &self._focussed_editor {focussed_path != path} else {true};
+                            self._focussed_editor = Some((path.clone(), *editor_id));
+                            self.outline.set_path(cx, path);
+                            if is_new_focus {
+                                recent_file = Some(path.clone());
+                            }
+                        }
                         
-                        let text_buffer = storage.text_buffer_from_path(cx, path);
+                        let (text_buffer, completion_index) = storage.text_buffer_and_completion_index(cx, path);
                         
-                        match file_editor.handle_file_editor(cx, event, text_buffer) {
+                        match file_editor.handle_file_editor(cx, event, text_buffer, completion_index) {
                             FileEditorEvent::LagChange => {
                                 
                                 // HERE WE SAVE
@@ -142,13 +238,35 @@ impl AppWindow {
                                 
                                 //app_global.rust_compiler.restart_rust_checker(cx, &mut app_global.text_buffers);
                             },
+                            FileEditorEvent::FoldChange => {
+                                fold_change = Some((path.clone(), file_editor.get_folded_lines()));
+                            },
+                            FileEditorEvent::AutoFormat => {
+                                if !storage.rust_format_request(path, window_index, *editor_id) {
+                                    let text_buffer = storage.text_buffer_from_path(cx, path);
+                                    file_editor.auto_format(cx, text_buffer);
+                                }
+                            },
                             _ => ()
                         }
                         *scroll_pos = file_editor.get_scroll_pos(cx);
                     }
+                },
+                Panel::Diff {diff_id, ..} => {
+                    if let Some(diff_view) = self.diff_views.get(*diff_id) {
+                        diff_view.handle_diff_view(cx, event, &storage.completion_index);
+                    }
                 }
             }
         }
+        if let Some(recent_file) = recent_file {
+            state.add_recent_file(&recent_file);
+            storage.save_state(cx, state);
+        }
+        if let Some((path, folded_lines)) = fold_change {
+            state.set_folded_lines(&path, folded_lines);
+            storage.save_state(cx, state);
+        }
         match file_tree_event {
             FileTreeEvent::DragMove {fe, ..} => {
                 self.dock.dock_drag_move(cx, fe);
@@ -191,6 +309,7 @@ impl AppWindow {
             DockEvent::DockTabCloned {tab_control_id, tab_id} => {
                 // lets change up our editor_id
                 let max_id = self.highest_file_editor_id();
+                let max_diff_id = self.highest_diff_id();
                 let mut dock_walker = self.dock.walker(dock_items);
                 while let Some((ctrl_id,dock_item)) = dock_walker.walk_dock_item() {
                     match dock_item {
@@ -203,6 +322,10 @@ impl AppWindow {
                                         break;
                                         // and now it needs to scroll the new one....
                                     },
+                                    Panel::Diff {diff_id, ..} => {
+                                        *diff_id = max_diff_id + 1;
+                                        break;
+                                    },
                                     _ => ()
                                 }
                             }
@@ -221,6 +344,7 @@ impl AppWindow {
 
         self.dock.draw_dock(cx);
         
+        let folded_lines = &state.folded_lines;
         let dock_items = &mut state.windows[window_index].dock_items;
         let mut dock_walker = self.dock.walker(dock_items);
         let file_panel = &mut self.file_panel;
@@ -255,6 +379,9 @@ impl AppWindow {
                 Panel::FileTree => {
                     file_panel.draw_file_panel(cx);
                 },
+                Panel::Outline => {
+                    self.outline.draw_outline(cx, storage);
+                },
                 Panel::FileEditor {path, scroll_pos, editor_id} => {
                     let text_buffer = storage.text_buffer_from_path(cx, path);
                     let mut set_key_focus = false;
@@ -262,18 +389,270 @@ impl AppWindow {
                         set_key_focus = true;
                         let mut editor = FileEditor::create_file_editor_for_path(path, tmpl);
                         editor.set_scroll_pos_on_load(*scroll_pos);
+                        if let Some(folded_lines) = folded_lines.get(path) {
+                            editor.set_folded_lines_on_load(folded_lines.clone());
+                        }
                         editor
                     });
                     file_editor.draw_file_editor(cx, text_buffer);
                     if set_key_focus {
                         file_editor.set_key_focus(cx);
                     }
+                    if path == self.outline.get_path() && self.outline.needs_update(text_buffer) {
+                        self.outline.view.redraw_view_area(cx);
+                    }
+                },
+                Panel::Diff {left, right, inline, diff_id} => {
+                    let editor_templates = &self.file_editors.template;
+                    let diff_view = self.diff_views.get_draw(cx, *diff_id, | cx, tmpl | {
+                        DiffView::create_for_path(cx, tmpl, editor_templates, right.path())
+                    });
+                    diff_view.draw_diff_view(cx, storage, left, right, *inline);
                 }
             }
         }
+        self.picker.draw_fuzzy_picker(cx);
+        self.command_palette.draw_command_palette(cx);
         self.desktop_window.end_desktop_window(cx);
     }
     
+    // the commands of the keymap and the menu that act on a window
+    pub fn handle_command(&mut self, cx: &mut Cx, command: Command, menu: &Menu, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        match command {
+            Command::OpenSymbolPicker => {
+                self.command_palette.close(cx);
+                self.open_symbol_picker(cx, storage);
+            },
+            Command::OpenCommandPalette => {
+                self.picker.close(cx);
+                self.command_palette.open(cx, menu);
+            },
+            Command::OpenFilePicker => {
+                self.command_palette.close(cx);
+                self.open_file_picker(cx, state, storage);
+            },
+            Command::GoToDefinition => if !self.picker.is_open() && !self.command_palette.is_open() {
+                self.go_to_definition(cx, window_index, state, storage);
+            },
+            Command::FoldAll | Command::UnfoldAll => {
+                self.fold_all_in_focussed_editor(cx, command == Command::FoldAll, state, storage);
+            },
+            Command::ToggleSoftWrap => self.toggle_soft_wrap_in_focussed_editor(cx),
+            Command::DiffWithDisk => self.diff_focussed_editor_with_disk(cx, window_index, state, storage),
+            Command::DiffWithSyncTarget => self.diff_focussed_editor_with_sync_target(cx, window_index, state, storage),
+            Command::DiffMarkedFiles => self.diff_marked_files(cx, window_index, state, storage),
+            Command::ToggleInlineDiff => self.toggle_inline_diff(cx, window_index, state, storage),
+            _ => ()
+        }
+    }
+    
+    // every code editor in the window, and the templates new ones are cloned from
+    pub fn set_keymap(&mut self, keymap: &KeyMap) {
+        self.file_editors.template.set_keymap(keymap);
+        for file_editor in self.file_editors.iter() {
+            file_editor.get_code_editor().keymap = keymap.clone();
+        }
+        self.diff_views.template.set_keymap(keymap);
+        for diff_view in self.diff_views.iter() {
+            diff_view.set_keymap(keymap);
+        }
+        self.log_item.code_editor.keymap = keymap.clone();
+    }
+    
+    // only the file editors, the diff and log editors are read only
+    pub fn set_vim_mode(&mut self, enabled: bool) {
+        self.file_editors.template.set_vim_mode(enabled);
+        for file_editor in self.file_editors.iter() {
+            file_editor.get_code_editor().set_vim_mode(enabled);
+        }
+    }
+    
+    // fold or unfold every region in the focussed editor
+    pub fn fold_all_in_focussed_editor(&mut self, cx: &mut Cx, fold: bool, state: &mut AppState, storage: &mut AppStorage) {
+        let (path, editor_id) = if let Some(focussed_editor) = &self._focussed_editor {focussed_editor.clone()} else {return};
+        let file_editor = if let Some(file_editor) = self.file_editors.get(editor_id) {file_editor} else {return};
+        let text_buffer = storage.text_buffer_from_path(cx, &path);
+        let code_editor = file_editor.get_code_editor();
+        if fold {
+            code_editor.fold_all(cx, text_buffer);
+        }
+        else {
+            code_editor.unfold_all(cx, text_buffer);
+        }
+        state.set_folded_lines(&path, file_editor.get_folded_lines());
+        storage.save_state(cx, state);
+    }
+    
+    pub fn toggle_soft_wrap_in_focussed_editor(&mut self, cx: &mut Cx) {
+        let editor_id = if let Some((_, editor_id)) = &self._focussed_editor {*editor_id} else {return};
+        if let Some(file_editor) = self.file_editors.get(editor_id) {
+            file_editor.get_code_editor().toggle_soft_wrap(cx);
+        }
+    }
+    
+    // compares the focussed buffer with the file as it is on disk
+    pub fn diff_focussed_editor_with_disk(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        let path = if let Some((path, _)) = &self._focussed_editor {path.clone()} else {return};
+        storage.reload_disk_text_buffer(cx, &path);
+        if self.open_diff_tab(cx, window_index, state, DiffSource::Disk {path: path.clone()}, DiffSource::Buffer {path: path}) {
+            storage.save_state(cx, state);
+        }
+    }
+    
+    // compares the focussed buffer with its copy in the other side of the sync settings
+    pub fn diff_focussed_editor_with_sync_target(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        let path = if let Some((path, _)) = &self._focussed_editor {path.clone()} else {return};
+        let sync_path = if let Some(sync_path) = storage.sync_target_path(&path) {sync_path} else {return};
+        if self.open_diff_tab(cx, window_index, state, DiffSource::Buffer {path: sync_path}, DiffSource::Buffer {path: path}) {
+            storage.save_state(cx, state);
+        }
+    }
+    
+    // compares the two files marked in the file tree, control or logo click marks a second one
+    pub fn diff_marked_files(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        let paths = self.file_panel.file_tree.get_marked_file_paths();
+        if paths.len() != 2 {
+            return
+        }
+        if self.open_diff_tab(cx, window_index, state, DiffSource::Buffer {path: paths[0].clone()}, DiffSource::Buffer {path: paths[1].clone()}) {
+            storage.save_state(cx, state);
+        }
+    }
+    
+    // flips the focussed diff between side by side and inline
+    pub fn toggle_inline_diff(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        let dock_items = &mut state.windows[window_index].dock_items;
+        let mut dock_walker = self.dock.walker(dock_items);
+        let mut changed = false;
+        while let Some((_ctrl_id, dock_item)) = dock_walker.walk_dock_item() {
+            if let DockItem::TabControl {tabs, ..} = dock_item {
+                for tab in tabs.iter_mut() {
+                    if let Panel::Diff {inline, diff_id, ..} = &mut tab.item {
+                        if let Some(diff_view) = self.diff_views.get(*diff_id) {
+                            if diff_view.has_key_focus(cx) {
+                                *inline = !*inline;
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if changed {
+            cx.redraw_child_area(Area::All);
+            storage.save_state(cx, state);
+        }
+    }
+    
+    pub fn open_path_at_offset(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage, path: &str, offset: usize) {
+        if self.focus_or_new_editor(cx, window_index, state, path) {
+            storage.save_state(cx, state);
+        }
+        let text_buffer = storage.text_buffer_from_path(cx, path);
+        text_buffer.messages.jump_to_offset = offset;
+        cx.send_signal(text_buffer.signal, SIGNAL_TEXTBUFFER_JUMP_TO_OFFSET);
+    }
+    
+    // lists the items of the focussed rust file
+    pub fn open_symbol_picker(&mut self, cx: &mut Cx, storage: &mut AppStorage) {
+        let path = if let Some((path, _)) = &self._focussed_editor {path.clone()} else {return};
+        if !path.ends_with(".rs") {
+            return
+        }
+        let text_buffer = storage.text_buffer_from_path(cx, &path);
+        RustEditor::update_token_chunks(text_buffer);
+        let mut items = Vec::new();
+        self._picker_targets.truncate(0);
+        for symbol in text_buffer_symbols(&path, text_buffer) {
+            items.push(FuzzyPickerItem {
+                label: symbol.name.clone(),
+                detail: format!("{} - line {}", symbol.kind.keyword(), symbol.row + 1),
+                boost: 0.
+            });
+            self._picker_targets.push(PickerTarget::Offset {path: path.clone(), offset: symbol.offset});
+        }
+        self.picker.open(cx, items);
+    }
+    
+    // quick open over the files of all workspaces, recently used files come first
+    pub fn open_file_picker(&mut self, cx: &mut Cx, state: &AppState, storage: &AppStorage) {
+        let mut paths: Vec<String> = state.recent_files.clone();
+        for path in storage.workspace_file_paths() {
+            if !state.recent_files.contains(&path) {
+                paths.push(path);
+            }
+        }
+        let mut items = Vec::new();
+        self._picker_targets.truncate(0);
+        for (index, path) in paths.into_iter().enumerate() {
+            items.push(FuzzyPickerItem {
+                label: path_file_name(&path),
+                detail: path.clone(),
+                boost: if index < state.recent_files.len() {3.0 / (1.0 + 0.25 * index as f64)} else {0.}
+            });
+            self._picker_targets.push(PickerTarget::File {path: path});
+        }
+        self.picker.open(cx, items);
+    }
+    
+    // jumps to the definition of the word under the cursor, the focussed file wins over the workspace index
+    pub fn go_to_definition(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, storage: &mut AppStorage) {
+        let (path, editor_id) = if let Some(focussed) = &self._focussed_editor {focussed.clone()} else {return};
+        let file_editor = if let Some(file_editor) = self.file_editors.get(editor_id) {file_editor} else {return};
+        let text_buffer = storage.text_buffer_from_path(cx, &path);
+        if text_buffer.is_loading {
+            return
+        }
+        let offset = if let Some(offset) = file_editor.get_code_editor().cursors.get_last_cursor_singular() {offset} else {return};
+        let mut start = offset;
+        while start > 0 && CodeCompletion::is_word_char(text_buffer.get_char(start - 1)) {
+            start -= 1;
+        }
+        let mut end = offset;
+        while CodeCompletion::is_word_char(text_buffer.get_char(end)) {
+            end += 1;
+        }
+        if start == end {
+            return
+        }
+        let word: String = (start..end).map( | i | text_buffer.get_char(i)).collect();
+        
+        let mut targets = Vec::new();
+        let mut items = Vec::new();
+        if path.ends_with(".rs") {
+            RustEditor::update_token_chunks(text_buffer);
+            for symbol in text_buffer_symbols(&path, text_buffer) {
+                if symbol.name == word && symbol.kind != HubSymbolKind::Impl {
+                    items.push(FuzzyPickerItem {
+                        label: word.clone(),
+                        detail: format!("{} - {}:{}", symbol.kind.keyword(), path, symbol.row + 1),
+                        boost: 0.
+                    });
+                    targets.push(PickerTarget::Offset {path: path.clone(), offset: symbol.offset});
+                }
+            }
+        }
+        if targets.len() == 0 {
+            for (symbol_path, symbol) in storage.find_workspace_symbols(&word) {
+                items.push(FuzzyPickerItem {
+                    label: word.clone(),
+                    detail: format!("{} - {}:{}", symbol.kind.keyword(), symbol_path, symbol.row + 1),
+                    boost: 0.
+                });
+                targets.push(PickerTarget::Offset {path: symbol_path, offset: symbol.offset});
+            }
+        }
+        if targets.len() == 1 {
+            if let Some(PickerTarget::Offset {path, offset}) = targets.pop() {
+                self.open_path_at_offset(cx, window_index, state, storage, &path, offset);
+            }
+        }
+        else if targets.len() > 1 {
+            self._picker_targets = targets;
+            self.picker.open(cx, items);
+        }
+    }
+    
     pub fn highest_file_editor_id(&self) -> u64 {
         let mut max_id = 0;
         for id in &self.file_editors.element_list {
@@ -284,6 +663,58 @@ impl AppWindow {
         max_id
     }
     
+    pub fn highest_diff_id(&self) -> u64 {
+        self.diff_views.element_list.iter().cloned().max().unwrap_or(0)
+    }
+    
+    // focusses the diff of these sources or opens it next to the editors
+    pub fn open_diff_tab(&mut self, cx: &mut Cx, window_index: usize, state: &mut AppState, left: DiffSource, right: DiffSource) -> bool {
+        let mut target_ctrl_id = None;
+        let dock_items = &mut state.windows[window_index].dock_items;
+        let mut dock_walker = self.dock.walker(dock_items);
+        while let Some((ctrl_id, dock_item)) = dock_walker.walk_dock_item() {
+            if let DockItem::TabControl {current, tabs, ..} = dock_item {
+                for (id, tab) in tabs.iter().enumerate() {
+                    match &tab.item {
+                        Panel::Diff {left: tab_left, right: tab_right, ..} => if *tab_left == left && *tab_right == right {
+                            *current = id;
+                            cx.redraw_child_area(Area::All);
+                            return false
+                        },
+                        Panel::FileEditorTarget => {
+                            target_ctrl_id = Some(ctrl_id);
+                        },
+                        _ => ()
+                    }
+                }
+            }
+        }
+        let target_ctrl_id = if let Some(target_ctrl_id) = target_ctrl_id {target_ctrl_id} else {return false};
+        let new_tab = DockTab {
+            closeable: true,
+            title: DiffSource::tab_title(&left, &right),
+            item: Panel::Diff {
+                left: left,
+                right: right,
+                inline: false,
+                diff_id: self.highest_diff_id() + 1
+            }
+        };
+        let dock_items = &mut state.windows[window_index].dock_items;
+        let mut dock_walker = self.dock.walker(dock_items);
+        while let Some((ctrl_id, dock_item)) = dock_walker.walk_dock_item() {
+            if ctrl_id == target_ctrl_id {
+                if let DockItem::TabControl {current, tabs, ..} = dock_item {
+                    tabs.insert(*current + 1, new_tab);
+                    *current = *current + 1;
+                    cx.redraw_child_area(Area::All);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    
     pub fn new_file_editor_tab(&self, path: &str) -> DockTab<Panel> {
         DockTab {
             closeable: true,
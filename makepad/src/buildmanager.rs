@@ -39,6 +39,17 @@ pub struct ActiveBuild {
 
 impl BuildManager {
     
+    // problems that don't come from a build, like bad settings, go in the log as well
+    pub fn log_warnings(&mut self, cx: &mut Cx, warnings: &[String]) {
+        if warnings.len() == 0 {
+            return
+        }
+        for warning in warnings {
+            self.log_items.push(HubLogItem::Warning(warning.clone()));
+        }
+        cx.send_signal(self.signal, SIGNAL_BUILD_MANAGER_NEW_LOG_ITEM);
+    }
+    
     fn gc_textbuffer_messages(&self, cx: &mut Cx, storage: &mut AppStorage) {
         // clear all files we missed
         for (_, atb) in &mut storage.text_buffers {
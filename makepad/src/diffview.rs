@@ -0,0 +1,328 @@
+use render::*;
+use widget::*;
+use editor::*;
+use serde::*;
+
+use crate::fileeditor::*;
+use crate::appstorage::*;
+
+// one side of a diff, the open buffer or the file as it is on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DiffSource {
+    Buffer {path: String},
+    Disk {path: String}
+}
+
+impl DiffSource {
+    pub fn path(&self) -> &str {
+        match self {
+            DiffSource::Buffer {path} => path,
+            DiffSource::Disk {path} => path
+        }
+    }
+
+    pub fn tab_title(left: &DiffSource, right: &DiffSource) -> String {
+        let left_name = path_file_name(left.path());
+        let right_name = path_file_name(right.path());
+        if left_name == right_name {
+            format!("{} diff", right_name)
+        }
+        else {
+            format!("{} / {}", left_name, right_name)
+        }
+    }
+}
+
+// two read only editors showing copies of the sources with filler lines, so both sides
+// line up and scroll together. inline shows the deleted and inserted lines in one editor
+#[derive(Clone)]
+pub struct DiffView {
+    pub splitter: Splitter,
+    pub left: FileEditor,
+    pub right: FileEditor,
+    pub left_buffer: TextBuffer,
+    pub right_buffer: TextBuffer,
+    pub _inline: bool,
+    pub _source_ids: (u64, u64),
+    pub _scroll_pos: Vec2,
+    pub _diff_timer: Timer,
+    pub _diff_due: bool,
+}
+
+#[derive(Default)]
+struct DiffSide {
+    lines: Vec<Vec<char>>,
+    numbers: Vec<Option<usize>>,
+    marks: Vec<LineMark>,
+}
+
+impl DiffSide {
+    fn push_line(&mut self, line: Vec<char>, number: Option<usize>, color: Option<Color>) -> usize {
+        let index = self.lines.len();
+        if let Some(color) = color {
+            self.marks.push(LineMark {line: index, cols: None, color: color});
+        }
+        self.lines.push(line);
+        self.numbers.push(number);
+        index
+    }
+
+    fn mark_cols(&mut self, line: usize, cols: (usize, usize), color: Color) {
+        if cols.0 < cols.1 {
+            self.marks.push(LineMark {line: line, cols: Some(cols), color: color});
+        }
+    }
+
+    fn load_into(self, cx: &mut Cx, file_editor: &mut FileEditor, text_buffer: &mut TextBuffer) {
+        let code_editor = file_editor.get_code_editor();
+        code_editor.line_marks = self.marks;
+        code_editor.line_number_map = Some(self.numbers);
+        // the old cursors can point past the end of the new text
+        code_editor.cursors = TextCursorSet::new();
+        code_editor.view.redraw_view_area(cx);
+        text_buffer.lines = if self.lines.len() > 0 {self.lines}else {vec![Vec::new()]};
+        text_buffer.is_loading = false;
+        text_buffer.mutation_id += 1;
+    }
+}
+
+impl DiffView {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            splitter: Splitter {
+                axis: Axis::Vertical,
+                align: SplitterAlign::Weighted,
+                pos: 0.5,
+                ..Splitter::proto(cx)
+            },
+            left: FileEditor::Plain(PlainEditor::proto(cx)),
+            right: FileEditor::Plain(PlainEditor::proto(cx)),
+            left_buffer: TextBuffer {
+                is_loading: true,
+                mutation_id: 1,
+                ..TextBuffer::default()
+            },
+            right_buffer: TextBuffer {
+                is_loading: true,
+                mutation_id: 1,
+                ..TextBuffer::default()
+            },
+            _inline: false,
+            _source_ids: (0, 0),
+            _scroll_pos: Vec2::zero(),
+            _diff_timer: Timer::empty(),
+            _diff_due: false,
+        }
+    }
+
+    // how long after an edit to a source the diff is rebuilt, typing doesn't diff every keystroke
+    pub fn diff_delay() -> f64 {0.3}
+
+    pub fn color_delete() -> ColorId {uid!()}
+    pub fn color_delete_chars() -> ColorId {uid!()}
+    pub fn color_insert() -> ColorId {uid!()}
+    pub fn color_insert_chars() -> ColorId {uid!()}
+    pub fn color_filler() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::color_delete().set_base(cx, color256a(160, 40, 40, 56));
+        Self::color_delete_chars().set_base(cx, color256a(210, 60, 60, 110));
+        Self::color_insert().set_base(cx, color256a(40, 140, 60, 56));
+        Self::color_insert_chars().set_base(cx, color256a(60, 190, 90, 110));
+        Self::color_filler().set_base(cx, color256a(128, 128, 128, 14));
+    }
+
+    // both sides use the editor for the file type of path
+    pub fn create_for_path(cx: &mut Cx, template: &DiffView, editors: &FileEditorTemplates, path: &str) -> DiffView {
+        let mut left = FileEditor::create_file_editor_for_path(path, editors);
+        let mut right = FileEditor::create_file_editor_for_path(path, editors);
+        Self::setup_code_editor(left.get_code_editor(), false);
+        Self::setup_code_editor(right.get_code_editor(), true);
+        DiffView {
+            left: left,
+            right: right,
+            left_buffer: TextBuffer {
+                signal: cx.new_signal(),
+                ..template.left_buffer.clone()
+            },
+            right_buffer: TextBuffer {
+                signal: cx.new_signal(),
+                ..template.right_buffer.clone()
+            },
+            ..template.clone()
+        }
+    }
+
+    fn setup_code_editor(code_editor: &mut CodeEditor, show_minimap: bool) {
+        code_editor.read_only = true;
+        // wrapped rows would break the line up between the sides
+        code_editor.soft_wrap = SoftWrap::Off;
+        code_editor.show_minimap = show_minimap;
+        code_editor.vim = None;
+    }
+
+    pub fn set_keymap(&mut self, keymap: &KeyMap) {
+        self.left.get_code_editor().keymap = keymap.clone();
+        self.right.get_code_editor().keymap = keymap.clone();
+    }
+
+    pub fn has_key_focus(&self, cx: &Cx) -> bool {
+        self.left.has_key_focus(cx) || self.right.has_key_focus(cx)
+    }
+
+    pub fn handle_diff_view(&mut self, cx: &mut Cx, event: &mut Event, completion_index: &CodeCompletionIndex) {
+        if let Event::Timer(te) = event {
+            if self._diff_timer.is_timer(te) {
+                self._diff_timer = Timer::empty();
+                self._diff_due = true;
+                self.left.get_code_editor().view.redraw_view_area(cx);
+                self.right.get_code_editor().view.redraw_view_area(cx);
+            }
+        }
+        if !self._inline {
+            if let SplitterEvent::Moving {..} = self.splitter.handle_splitter(cx, event) {
+                self.left.get_code_editor().view.redraw_view_area(cx);
+                self.right.get_code_editor().view.redraw_view_area(cx);
+            }
+            self.left.handle_file_editor(cx, event, &mut self.left_buffer, completion_index);
+        }
+        self.right.handle_file_editor(cx, event, &mut self.right_buffer, completion_index);
+        if !self._inline {
+            self.sync_scroll_pos(cx);
+        }
+    }
+
+    // whichever side scrolled takes the other one along
+    fn sync_scroll_pos(&mut self, cx: &mut Cx) {
+        let left_pos = self.left.get_scroll_pos(cx);
+        let right_pos = self.right.get_scroll_pos(cx);
+        if left_pos != self._scroll_pos {
+            self._scroll_pos = left_pos;
+            let code_editor = self.right.get_code_editor();
+            code_editor.view.set_scroll_pos(cx, left_pos);
+            code_editor.view.redraw_view_area(cx);
+        }
+        else if right_pos != self._scroll_pos {
+            self._scroll_pos = right_pos;
+            let code_editor = self.left.get_code_editor();
+            code_editor.view.set_scroll_pos(cx, right_pos);
+            code_editor.view.redraw_view_area(cx);
+        }
+    }
+
+    pub fn draw_diff_view(&mut self, cx: &mut Cx, storage: &mut AppStorage, left: &DiffSource, right: &DiffSource, inline: bool) {
+        self.update_diff(cx, storage, left, right, inline);
+        if inline {
+            self.right.draw_file_editor(cx, &mut self.right_buffer);
+        }
+        else {
+            self.splitter.begin_splitter(cx);
+            self.left.draw_file_editor(cx, &mut self.left_buffer);
+            self.splitter.mid_splitter(cx);
+            self.right.draw_file_editor(cx, &mut self.right_buffer);
+            self.splitter.end_splitter(cx);
+        }
+    }
+
+    // rebuilds the aligned copies when either source changed. the first diff and switching
+    // between inline and side by side are immediate, edits are diffed diff_delay later
+    fn update_diff(&mut self, cx: &mut Cx, storage: &mut AppStorage, left: &DiffSource, right: &DiffSource, inline: bool) {
+        let (left_loading, left_id) = {
+            let text_buffer = storage.diff_source_text_buffer(cx, left);
+            (text_buffer.is_loading, text_buffer.mutation_id)
+        };
+        let (right_loading, right_id) = {
+            let text_buffer = storage.diff_source_text_buffer(cx, right);
+            (text_buffer.is_loading, text_buffer.mutation_id)
+        };
+        if left_loading || right_loading || (self._source_ids == (left_id, right_id) && self._inline == inline) {
+            return
+        }
+        let has_diff = !self.right_buffer.is_loading;
+        if has_diff && self._inline == inline && !self._diff_due {
+            // edits during the wait are picked up by the same diff
+            if self._diff_timer.is_empty() {
+                self._diff_timer = cx.start_timer(Self::diff_delay(), false);
+            }
+            return
+        }
+        cx.stop_timer(&mut self._diff_timer);
+        self._diff_due = false;
+        self._source_ids = (left_id, right_id);
+        self._inline = inline;
+        let old = storage.diff_source_text_buffer(cx, left).lines.clone();
+        let new = storage.diff_source_text_buffer(cx, right).lines.clone();
+
+        let delete = Self::color_delete().base(cx);
+        let delete_chars = Self::color_delete_chars().base(cx);
+        let insert = Self::color_insert().base(cx);
+        let insert_chars = Self::color_insert_chars().base(cx);
+        let filler = Self::color_filler().base(cx);
+
+        let mut left_side = DiffSide::default();
+        let mut right_side = DiffSide::default();
+        let chunks = diff_lines(&old, &new);
+        let mut index = 0;
+        while index < chunks.len() {
+            let chunk = &chunks[index];
+            if chunk.op == DiffOp::Equal {
+                for i in 0..chunk.len {
+                    if !inline {
+                        left_side.push_line(old[chunk.old_start + i].clone(), Some(chunk.old_start + i), None);
+                    }
+                    right_side.push_line(new[chunk.new_start + i].clone(), Some(chunk.new_start + i), None);
+                }
+                index += 1;
+                continue
+            }
+            // a change is the run of deletes and inserts up to the next equal chunk
+            let mut deleted = Vec::new();
+            let mut inserted = Vec::new();
+            while index < chunks.len() && chunks[index].op != DiffOp::Equal {
+                let chunk = &chunks[index];
+                for i in 0..chunk.len {
+                    match chunk.op {
+                        DiffOp::Delete => deleted.push(chunk.old_start + i),
+                        _ => inserted.push(chunk.new_start + i)
+                    }
+                }
+                index += 1;
+            }
+            if inline {
+                let deleted_lines: Vec<usize> = deleted.iter().map( | line | right_side.push_line(old[*line].clone(), None, Some(delete))).collect();
+                let inserted_lines: Vec<usize> = inserted.iter().map( | line | right_side.push_line(new[*line].clone(), Some(*line), Some(insert))).collect();
+                for i in 0..deleted.len().min(inserted.len()) {
+                    let (old_cols, new_cols) = diff_line_cols(&old[deleted[i]], &new[inserted[i]]);
+                    right_side.mark_cols(deleted_lines[i], old_cols, delete_chars);
+                    right_side.mark_cols(inserted_lines[i], new_cols, insert_chars);
+                }
+            }
+            else {
+                // the shorter side is padded with filler lines
+                for i in 0..deleted.len().max(inserted.len()) {
+                    let left_line = if let Some(line) = deleted.get(i) {
+                        left_side.push_line(old[*line].clone(), Some(*line), Some(delete))
+                    }
+                    else {
+                        left_side.push_line(Vec::new(), None, Some(filler))
+                    };
+                    let right_line = if let Some(line) = inserted.get(i) {
+                        right_side.push_line(new[*line].clone(), Some(*line), Some(insert))
+                    }
+                    else {
+                        right_side.push_line(Vec::new(), None, Some(filler))
+                    };
+                    if i < deleted.len() && i < inserted.len() {
+                        let (old_cols, new_cols) = diff_line_cols(&old[deleted[i]], &new[inserted[i]]);
+                        left_side.mark_cols(left_line, old_cols, delete_chars);
+                        right_side.mark_cols(right_line, new_cols, insert_chars);
+                    }
+                }
+            }
+        }
+        if !inline {
+            left_side.load_into(cx, &mut self.left, &mut self.left_buffer);
+        }
+        right_side.load_into(cx, &mut self.right, &mut self.right_buffer);
+    }
+}
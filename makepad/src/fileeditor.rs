@@ -6,6 +6,10 @@ use editor::*;
 pub struct FileEditorTemplates {
     pub rust_editor: RustEditor,
     pub js_editor: JSEditor,
+    pub json_editor: JSONEditor,
+    pub ron_editor: RONEditor,
+    pub toml_editor: TOMLEditor,
+    pub markdown_editor: MarkdownEditor,
     pub plain_editor: PlainEditor
     //text_editor: TextEditor
 }
@@ -14,14 +18,42 @@ pub struct FileEditorTemplates {
 pub enum FileEditor {
     Rust(RustEditor),
     JS(JSEditor),
+    JSON(JSONEditor),
+    RON(RONEditor),
+    TOML(TOMLEditor),
+    Markdown(MarkdownEditor),
     Plain(PlainEditor)
     //Text(TextEditor)
 }
 
+impl FileEditorTemplates {
+    pub fn set_keymap(&mut self, keymap: &KeyMap) {
+        self.rust_editor.code_editor.keymap = keymap.clone();
+        self.js_editor.code_editor.keymap = keymap.clone();
+        self.json_editor.code_editor.keymap = keymap.clone();
+        self.ron_editor.code_editor.keymap = keymap.clone();
+        self.toml_editor.code_editor.keymap = keymap.clone();
+        self.markdown_editor.code_editor.keymap = keymap.clone();
+        self.plain_editor.code_editor.keymap = keymap.clone();
+    }
+    
+    pub fn set_vim_mode(&mut self, enabled: bool) {
+        self.rust_editor.code_editor.set_vim_mode(enabled);
+        self.js_editor.code_editor.set_vim_mode(enabled);
+        self.json_editor.code_editor.set_vim_mode(enabled);
+        self.ron_editor.code_editor.set_vim_mode(enabled);
+        self.toml_editor.code_editor.set_vim_mode(enabled);
+        self.markdown_editor.code_editor.set_vim_mode(enabled);
+        self.plain_editor.code_editor.set_vim_mode(enabled);
+    }
+}
+
 #[derive(Clone)]
 pub enum FileEditorEvent {
     None,
     LagChange,
+    FoldChange,
+    AutoFormat,
     Change
 }
 
@@ -29,16 +61,24 @@ fn code_editor_to_file_editor(event: CodeEditorEvent)->FileEditorEvent {
     match event {
         CodeEditorEvent::Change => FileEditorEvent::Change,
         CodeEditorEvent::LagChange => FileEditorEvent::LagChange,
+        CodeEditorEvent::FoldChange => FileEditorEvent::FoldChange,
         _ => FileEditorEvent::None
     }
 }
 
 impl FileEditor {
-    pub fn handle_file_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer) -> FileEditorEvent {
+    pub fn handle_file_editor(&mut self, cx: &mut Cx, event: &mut Event, text_buffer: &mut TextBuffer, completion_index: &CodeCompletionIndex) -> FileEditorEvent {
         match self {
-            FileEditor::Rust(re) => code_editor_to_file_editor(re.handle_rust_editor(cx, event, text_buffer)),
-            FileEditor::JS(re) => code_editor_to_file_editor(re.handle_js_editor(cx, event, text_buffer)),
-            FileEditor::Plain(re) => code_editor_to_file_editor(re.handle_plain_editor(cx, event, text_buffer)),
+            FileEditor::Rust(re) => match re.handle_rust_editor(cx, event, text_buffer, Some(completion_index)) {
+                CodeEditorEvent::AutoFormat => FileEditorEvent::AutoFormat,
+                ce => code_editor_to_file_editor(ce)
+            },
+            FileEditor::JS(re) => code_editor_to_file_editor(re.handle_js_editor(cx, event, text_buffer, Some(completion_index))),
+            FileEditor::JSON(re) => code_editor_to_file_editor(re.handle_json_editor(cx, event, text_buffer, Some(completion_index))),
+            FileEditor::RON(re) => code_editor_to_file_editor(re.handle_ron_editor(cx, event, text_buffer, Some(completion_index))),
+            FileEditor::TOML(re) => code_editor_to_file_editor(re.handle_toml_editor(cx, event, text_buffer, Some(completion_index))),
+            FileEditor::Markdown(re) => code_editor_to_file_editor(re.handle_markdown_editor(cx, event, text_buffer, Some(completion_index))),
+            FileEditor::Plain(re) => code_editor_to_file_editor(re.handle_plain_editor(cx, event, text_buffer, Some(completion_index))),
         }
     }
     
@@ -46,14 +86,46 @@ impl FileEditor {
         match self {
             FileEditor::Rust(re) => re.code_editor.set_key_focus(cx),
             FileEditor::JS(re) => re.code_editor.set_key_focus(cx),
+            FileEditor::JSON(re) => re.code_editor.set_key_focus(cx),
+            FileEditor::RON(re) => re.code_editor.set_key_focus(cx),
+            FileEditor::TOML(re) => re.code_editor.set_key_focus(cx),
+            FileEditor::Markdown(re) => re.code_editor.set_key_focus(cx),
             FileEditor::Plain(re) => re.code_editor.set_key_focus(cx),
         }
     }
 
+    pub fn has_key_focus(&self, cx: &Cx) -> bool {
+        match self {
+            FileEditor::Rust(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::JS(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::JSON(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::RON(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::TOML(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::Markdown(re) => re.code_editor.has_key_focus(cx),
+            FileEditor::Plain(re) => re.code_editor.has_key_focus(cx),
+        }
+    }
+    
+    pub fn get_code_editor(&mut self) -> &mut CodeEditor {
+        match self {
+            FileEditor::Rust(re) => &mut re.code_editor,
+            FileEditor::JS(re) => &mut re.code_editor,
+            FileEditor::JSON(re) => &mut re.code_editor,
+            FileEditor::RON(re) => &mut re.code_editor,
+            FileEditor::TOML(re) => &mut re.code_editor,
+            FileEditor::Markdown(re) => &mut re.code_editor,
+            FileEditor::Plain(re) => &mut re.code_editor,
+        }
+    }
+
     pub fn get_scroll_pos(&mut self, cx: &mut Cx)->Vec2{
         match self {
             FileEditor::Rust(re) => re.code_editor.view.get_scroll_pos(cx),
             FileEditor::JS(re) => re.code_editor.view.get_scroll_pos(cx),
+            FileEditor::JSON(re) => re.code_editor.view.get_scroll_pos(cx),
+            FileEditor::RON(re) => re.code_editor.view.get_scroll_pos(cx),
+            FileEditor::TOML(re) => re.code_editor.view.get_scroll_pos(cx),
+            FileEditor::Markdown(re) => re.code_editor.view.get_scroll_pos(cx),
             FileEditor::Plain(re) => re.code_editor.view.get_scroll_pos(cx),
         }
     }
@@ -62,25 +134,74 @@ impl FileEditor {
         match self {
             FileEditor::Rust(re) => re.code_editor._scroll_pos_on_load = Some(pos),
             FileEditor::JS(re) => re.code_editor._scroll_pos_on_load = Some(pos),
+            FileEditor::JSON(re) => re.code_editor._scroll_pos_on_load = Some(pos),
+            FileEditor::RON(re) => re.code_editor._scroll_pos_on_load = Some(pos),
+            FileEditor::TOML(re) => re.code_editor._scroll_pos_on_load = Some(pos),
+            FileEditor::Markdown(re) => re.code_editor._scroll_pos_on_load = Some(pos),
             FileEditor::Plain(re) => re.code_editor._scroll_pos_on_load = Some(pos),
         }
     }
 
+    pub fn set_folded_lines_on_load(&mut self, folded_lines: Vec<usize>) {
+        self.get_code_editor()._folded_lines = folded_lines;
+    }
+    
+    pub fn get_folded_lines(&mut self) -> Vec<usize> {
+        self.get_code_editor()._folded_lines.clone()
+    }
+    
+    // the built-in formatter, used when rustfmt is off or not available
+    pub fn auto_format(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
+        if let FileEditor::Rust(re) = self {
+            re.auto_format(cx, text_buffer);
+        }
+    }
+    
+    pub fn apply_formatted(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer, formatted: Vec<Vec<char>>) {
+        if let FileEditor::Rust(re) = self {
+            re.apply_formatted(cx, text_buffer, formatted);
+        }
+    }
+    
     pub fn draw_file_editor(&mut self, cx: &mut Cx, text_buffer: &mut TextBuffer) {
         match self {
             FileEditor::Rust(re) => re.draw_rust_editor(cx, text_buffer),
             FileEditor::JS(re) => re.draw_js_editor(cx, text_buffer),
+            FileEditor::JSON(re) => re.draw_json_editor(cx, text_buffer),
+            FileEditor::RON(re) => re.draw_ron_editor(cx, text_buffer),
+            FileEditor::TOML(re) => re.draw_toml_editor(cx, text_buffer),
+            FileEditor::Markdown(re) => re.draw_markdown_editor(cx, text_buffer),
             FileEditor::Plain(re) => re.draw_plain_editor(cx, text_buffer),
         }
     }
     
     pub fn create_file_editor_for_path(path: &str, template: &FileEditorTemplates) -> FileEditor {
         // check which file extension we have to spawn a new editor
-        if path.ends_with(".rs") || path.ends_with(".toml")  || path.ends_with(".ron"){
+        if path.ends_with(".rs") {
             FileEditor::Rust(RustEditor {
                 ..template.rust_editor.clone()
             })
         }
+        else if path.ends_with(".json") {
+            FileEditor::JSON(JSONEditor {
+                ..template.json_editor.clone()
+            })
+        }
+        else if path.ends_with(".ron") {
+            FileEditor::RON(RONEditor {
+                ..template.ron_editor.clone()
+            })
+        }
+        else if path.ends_with(".toml") {
+            FileEditor::TOML(TOMLEditor {
+                ..template.toml_editor.clone()
+            })
+        }
+        else if path.ends_with(".md") {
+            FileEditor::Markdown(MarkdownEditor {
+                ..template.markdown_editor.clone()
+            })
+        }
         else if path.ends_with(".js") || path.ends_with(".html"){
             FileEditor::JS(JSEditor {
                 ..template.js_editor.clone()
@@ -92,6 +213,31 @@ impl FileEditor {
             })
         }
     }
+    
+    // tokenizes a buffer with the editor create_file_editor_for_path would pick for it
+    pub fn update_token_chunks(path: &str, text_buffer: &mut TextBuffer) {
+        if path.ends_with(".rs") {
+            RustEditor::update_token_chunks(text_buffer)
+        }
+        else if path.ends_with(".json") {
+            JSONEditor::update_token_chunks(text_buffer)
+        }
+        else if path.ends_with(".ron") {
+            RONEditor::update_token_chunks(text_buffer)
+        }
+        else if path.ends_with(".toml") {
+            TOMLEditor::update_token_chunks(text_buffer)
+        }
+        else if path.ends_with(".md") {
+            MarkdownEditor::update_token_chunks(text_buffer)
+        }
+        else if path.ends_with(".js") || path.ends_with(".html") {
+            JSEditor::update_token_chunks(text_buffer)
+        }
+        else {
+            PlainEditor::update_token_chunks(text_buffer)
+        }
+    }
 }
 
 pub fn path_file_name(path: &str) -> String {
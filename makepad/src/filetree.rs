@@ -430,6 +430,19 @@ impl FileTree {
         paths
     }
     
+    pub fn get_marked_file_paths(&mut self) -> Vec<String> {
+        let mut paths = Vec::new();
+        let mut file_walker = FileWalker::new(&mut self.root_node);
+        while let Some((_depth, _index, _len, node)) = file_walker.walk() {
+            let is_filenode = if let FileNode::File {..} = node {true} else {false};
+            let node_draw = if let Some(node_draw) = node.get_draw() {node_draw}else {continue};
+            if is_filenode && node_draw.marked != 0 {
+                paths.push(file_walker.current_path());
+            }
+        }
+        paths
+    }
+    
     pub fn handle_file_tree(&mut self, cx: &mut Cx, event: &mut Event) -> FileTreeEvent {
         
         // alright. someone clicking on the tree items.
@@ -455,7 +468,14 @@ impl FileTree {
                 Event::AnimEnded(_) => {
                     node_draw.animator.end();
                 },
-                Event::FingerDown(_fe) => {
+                Event::FingerDown(fe) => {
+                    // control or logo adds a file to the marked ones without opening it
+                    if is_filenode && (fe.modifiers.control || fe.modifiers.logo) {
+                        node_draw.marked = cx.event_id;
+                        node_draw.animator.play_anim(cx, FileTreeItemDraw::get_over_anim(cx, counter, true));
+                        counter += 1;
+                        continue;
+                    }
                     // mark ourselves, unmark others
                     if is_filenode {
                         select_node = 1;
@@ -530,6 +550,8 @@ impl FileTree {
         
         //unmark non selected nodes and also set even/odd animations to make sure its rendered properly
         if unmark_nodes {
+            // marked files draw their label and access node differently
+            self.view.redraw_view_area(cx);
             let mut file_walker = FileWalker::new(&mut self.root_node);
             let mut counter = 0;
             while let Some((_depth, _index, _len, node)) = file_walker.walk() {
@@ -610,6 +632,15 @@ impl FileTree {
         let color_tree_file = FileTreeItemDraw::color_tree_file().base(cx);
         self.item_draw.tree_text.text_style = FileTreeItemDraw::text_style_label().base(cx);
         
+        cx.begin_access(AccessNode {
+            role: AccessRole::Tree,
+            label: "Files".to_string(),
+            area: self.view.get_view_area(cx),
+            ..Default::default()
+        });
+        // the depths of the folder nodes still open in the access tree
+        let mut access_folders = Vec::new();
+        
         while let Some((depth, index, len, node)) = file_walker.walk() {
             
             let is_first = index == 0;
@@ -642,6 +673,30 @@ impl FileTree {
             node_draw.animator.set_area(cx, inst.clone().into());
             let is_marked = node_draw.marked != 0;
             
+            while access_folders.last().map_or(false, | d | *d >= depth) {
+                access_folders.pop();
+                cx.end_access();
+            }
+            let is_open = node.is_open();
+            let access_node = AccessNode {
+                role: AccessRole::TreeItem,
+                label: node.name(),
+                area: inst.clone().into(),
+                selected: is_marked,
+                ..Default::default()
+            };
+            if let FileNode::Folder {..} = node {
+                cx.begin_access(AccessNode {
+                    expanded: Some(is_open),
+                    actions: vec![if is_open {AccessAction::Collapse} else {AccessAction::Expand}],
+                    ..access_node
+                });
+                access_folders.push(depth);
+            }
+            else {
+                cx.add_access(AccessNode {actions: vec![AccessAction::Select], ..access_node});
+            }
+            
             for i in 0..(depth - 1) {
                 if i == depth - 2 { // our own thread.
                     let area = self.item_draw.filler.draw_quad(cx, filler_walk);
@@ -754,6 +809,11 @@ impl FileTree {
             }
         }
         
+        for _ in 0..access_folders.len() {
+            cx.end_access();
+        }
+        cx.end_access();
+        
         // draw filler nodes
         if row_height > 0.{
             let view_total = cx.get_turtle_bounds();
@@ -799,3 +859,98 @@ impl FileTree {
     
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FileTreeApp {
+        desktop_window: DesktopWindow,
+        file_tree: FileTree,
+    }
+
+    impl FileTreeApp {
+        fn proto(cx: &mut Cx) -> Self {
+            set_dark_widget_theme(cx);
+            FileTree::theme(cx);
+            let file = | name: &str | FileNode::File {name: name.to_string(), draw: None};
+            let folder = | name: &str, folder: Vec<FileNode> | FileNode::Folder {name: name.to_string(), draw: None, state: NodeState::Open, folder: folder};
+            Self {
+                desktop_window: DesktopWindow {
+                    window: Window {
+                        create_inner_size: Some(Vec2 {x: 300., y: 240.}),
+                        ..Window::proto(cx)
+                    },
+                    ..DesktopWindow::proto(cx)
+                },
+                file_tree: FileTree {
+                    root_node: folder("", vec![
+                        folder("render", vec![folder("src", vec![file("cx.rs"), file("quad.rs")]), file("Cargo.toml")]),
+                        folder("widget", vec![file("dock.rs")]),
+                        file("README.md"),
+                    ]),
+                    ..FileTree::proto(cx)
+                },
+            }
+        }
+
+        fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+            self.desktop_window.handle_desktop_window(cx, event);
+            self.file_tree.handle_file_tree(cx, event);
+        }
+
+        fn draw_app(&mut self, cx: &mut Cx) {
+            if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+                return
+            }
+            self.file_tree.draw_file_tree(cx);
+            self.desktop_window.end_desktop_window(cx);
+        }
+    }
+
+    #[test]
+    fn file_tree_fold() {
+        let mut snapshot = snapshot_app!(FileTreeApp);
+        snapshot.assert_snapshot(&snapshot_path!("file_tree"), 2);
+
+        // close the src folder and select a file
+        snapshot.click(60., 50.);
+        snapshot.click(60., 110.);
+        snapshot.advance(0.5);
+        snapshot.assert_snapshot(&snapshot_path!("file_tree_closed"), 2);
+    }
+
+    #[test]
+    fn file_tree_access() {
+        let mut snapshot = snapshot_app!(FileTreeApp);
+        let tree = snapshot.access_tree();
+        let files = tree.find_all(AccessRole::Tree);
+        assert_eq!(files.len(), 1);
+        let top: Vec<String> = tree.children(files[0]).iter().map( | n | n.node.label.clone()).collect();
+        assert_eq!(top, vec!["render", "widget", "README.md"]);
+        let src = tree.find(AccessRole::TreeItem, "src").expect(&tree.dump());
+        assert_eq!(src.node.expanded, Some(true));
+        assert_eq!(src.node.actions, vec![AccessAction::Collapse]);
+        assert_eq!(tree.children(src).len(), 2);
+        let file = tree.find(AccessRole::TreeItem, "cx.rs").unwrap();
+        assert_eq!(file.node.expanded, None);
+        assert!(!file.node.selected);
+
+        // collapse through the bounds the tree reports, then select a file
+        let rect = src.bounds;
+        snapshot.click(rect.x + rect.w * 0.5, rect.y + rect.h * 0.5);
+        snapshot.advance(0.5);
+        let rect = tree.find(AccessRole::TreeItem, "Cargo.toml").unwrap().bounds;
+        let tree = snapshot.access_tree();
+        let src = tree.find(AccessRole::TreeItem, "src").unwrap();
+        assert_eq!(src.node.expanded, Some(false));
+        assert_eq!(tree.children(src).len(), 0);
+        assert!(tree.find(AccessRole::TreeItem, "cx.rs").is_none());
+
+        let moved = tree.find(AccessRole::TreeItem, "Cargo.toml").unwrap().bounds;
+        assert!(moved.y < rect.y);
+        snapshot.click(moved.x + moved.w * 0.5, moved.y + moved.h * 0.5);
+        snapshot.advance(0.5);
+        assert!(snapshot.access_tree().find(AccessRole::TreeItem, "Cargo.toml").unwrap().node.selected);
+    }
+}
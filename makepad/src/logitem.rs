@@ -14,11 +14,13 @@ impl LogItem {
         let editor = Self {
             code_editor: CodeEditor {
                 draw_line_numbers: false,
+                show_minimap: false,
                 draw_cursor_row: false,
                 line_number_width: 10.,
                 top_padding: 10.,
                 mark_unmatched_parens: false,
                 folding_depth: 3,
+                soft_wrap: SoftWrap::ViewWidth,
                 ..CodeEditor::proto(cx)
             },
             text_buffer: TextBuffer {
@@ -65,7 +67,7 @@ impl LogItem {
     
     pub fn handle_log_item(&mut self, cx: &mut Cx, event: &mut Event) -> CodeEditorEvent {
         let text_buffer = &mut self.text_buffer;
-        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer);
+        let ce = self.code_editor.handle_code_editor(cx, event, text_buffer, None);
         match ce {
             CodeEditorEvent::AutoFormat => {
                 let formatted = RustTokenizer::auto_format(text_buffer, true).out_lines;
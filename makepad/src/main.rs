@@ -2,6 +2,8 @@ pub mod keyboard;
 pub use crate::keyboard::*;
 mod fileeditor;
 pub use crate::fileeditor::*;
+mod diffview;
+pub use crate::diffview::*;
 mod filepanel;
 pub use crate::filepanel::*;
 mod homepage;
@@ -10,6 +12,8 @@ mod loglist;
 pub use crate::loglist::*;
 mod logitem; 
 pub use crate::logitem::*;
+mod outline;
+pub use crate::outline::*;
 mod app;
 pub use crate::app::*;
 mod appwindow;
@@ -2,12 +2,14 @@ use render::*;
 use crate::filetree::*;
 use crate::loglist::*;
 use crate::homepage::*;
+use crate::diffview::*;
 
 pub fn set_makepad_theme_values(cx: &mut Cx){
 
     HomePage::theme(cx);
     FileTree::theme(cx);
     LogList::theme(cx);
+    DiffView::theme(cx);
 }
 
 pub fn set_dark_makepad_theme(cx: &mut Cx) {
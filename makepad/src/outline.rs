@@ -0,0 +1,154 @@
+use render::*;
+use widget::*;
+use editor::*;
+use hub::*;
+use crate::appstorage::*;
+use crate::loglist::*;
+
+#[derive(Clone)]
+pub struct Outline {
+    pub view: ScrollView,
+    pub list: ListLogic,
+    pub item_draw: LogItemDraw,
+    pub symbols: Vec<HubSymbol>,
+    pub _path: String,
+    pub _token_chunks_id: u64,
+}
+
+#[derive(Clone)]
+pub enum OutlineEvent {
+    SelectSymbol {path: String},
+    None,
+}
+
+impl Outline {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            view: ScrollView::proto(cx),
+            list: ListLogic::default(),
+            item_draw: LogItemDraw {
+                text: Text {
+                    wrapping: Wrapping::Ellipsis(400.),
+                    ..Text::proto(cx)
+                },
+                ..LogItemDraw::proto(cx)
+            },
+            symbols: Vec::new(),
+            _path: String::new(),
+            _token_chunks_id: 0,
+        }
+    }
+
+    // follows the last focussed file editor
+    pub fn set_path(&mut self, cx: &mut Cx, path: &str) {
+        if self._path != path {
+            self._path = path.to_string();
+            self._token_chunks_id = 0;
+            self.symbols.truncate(0);
+            self.list.set_list_len(0);
+            self.view.redraw_view_area(cx);
+        }
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self._path
+    }
+
+    pub fn needs_update(&self, text_buffer: &TextBuffer) -> bool {
+        self._token_chunks_id != text_buffer.token_chunks_id
+    }
+
+    fn update_symbols(&mut self, storage: &AppStorage) {
+        if !self._path.ends_with(".rs") {
+            return
+        }
+        if let Some(atb) = storage.text_buffers.get(&self._path) {
+            if self.needs_update(&atb.text_buffer) && atb.text_buffer.token_chunks.len() > 0 {
+                self._token_chunks_id = atb.text_buffer.token_chunks_id;
+                self.symbols = text_buffer_symbols(&self._path, &atb.text_buffer);
+            }
+        }
+    }
+
+    pub fn handle_outline(&mut self, cx: &mut Cx, event: &mut Event, storage: &mut AppStorage) -> OutlineEvent {
+        self.list.set_list_len(self.symbols.len());
+
+        self.list.handle_list_scroll_bars(cx, event, &mut self.view);
+
+        let le = self.list.handle_list_logic(cx, event, ListSelect::None, | cx, item_event, item, item_index | match item_event {
+            ListLogicEvent::Animate(ae) => {
+                item.animator.calc_area(cx, item.animator.area, ae.time);
+            },
+            ListLogicEvent::AnimEnded => {
+                item.animator.end();
+            },
+            ListLogicEvent::Select => {
+                item.animator.play_anim(cx, LogItemDraw::get_over_anim(cx, item_index, true));
+            },
+            ListLogicEvent::Deselect => {
+                item.animator.play_anim(cx, LogItemDraw::get_default_anim(cx, item_index, false));
+            },
+            ListLogicEvent::Cleanup => {
+                item.animator.play_anim(cx, LogItemDraw::get_default_anim_cut(cx, item_index, item.is_selected));
+            },
+            ListLogicEvent::Over => {
+                item.animator.play_anim(cx, LogItemDraw::get_over_anim(cx, item_index, item.is_selected));
+            },
+            ListLogicEvent::Out => {
+                item.animator.play_anim(cx, LogItemDraw::get_default_anim(cx, item_index, item.is_selected));
+            }
+        });
+
+        match le {
+            ListEvent::SelectSingle(select_index) => {
+                self.view.redraw_view_area(cx);
+                if let Some(symbol) = self.symbols.get(select_index) {
+                    let text_buffer = storage.text_buffer_from_path(cx, &self._path);
+                    text_buffer.messages.jump_to_offset = symbol.offset;
+                    cx.send_signal(text_buffer.signal, SIGNAL_TEXTBUFFER_JUMP_TO_OFFSET);
+                    return OutlineEvent::SelectSymbol {path: self._path.clone()}
+                }
+                OutlineEvent::None
+            },
+            _ => OutlineEvent::None
+        }
+    }
+
+    pub fn draw_outline(&mut self, cx: &mut Cx, storage: &AppStorage) {
+        self.update_symbols(storage);
+        self.list.set_list_len(self.symbols.len());
+
+        self.item_draw.text.text_style = LogItemDraw::text_style_item().base(cx);
+        let layout_item = LogItemDraw::layout_item().base(cx);
+        let row_height = layout_item.walk.height.fixed();
+
+        if self.list.begin_list(cx, &mut self.view, row_height).is_err() {return}
+
+        let mut counter = 0;
+        for i in self.list.start_item..self.list.end_item {
+            let list_item = &mut self.list.list_items[i];
+            let symbol = &self.symbols[i];
+            list_item.animator.init(cx, | cx | LogItemDraw::get_default_anim(cx, i, false));
+            let item_draw = &mut self.item_draw;
+            item_draw.item_bg.color = list_item.animator.last_color(cx, Quad::instance_color());
+            let bg_inst = item_draw.item_bg.begin_quad(cx, layout_item.clone());
+            cx.walk_turtle(Walk::wh(Width::Fix(4. + 12. * symbol.depth as f32), Height::Fix(0.)));
+            item_draw.text.color = item_draw.path_color.base(cx);
+            item_draw.text.draw_text(cx, &format!("{} ", symbol.kind.keyword()));
+            item_draw.text.color = item_draw.message_color.base(cx);
+            item_draw.text.draw_text(cx, &symbol.name);
+            let bg_area = item_draw.item_bg.end_quad(cx, &bg_inst);
+            list_item.animator.set_area(cx, bg_area);
+            counter += 1;
+        }
+
+        self.list.walk_turtle_to_end(cx, row_height);
+
+        for _ in self.list.end_item..self.list.end_fill {
+            self.item_draw.draw_filler(cx, counter);
+            counter += 1;
+        }
+
+        self.list.end_list(cx, &mut self.view);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::cx::*;
+
+// semantic information for screen readers and tests. widgets describe themselves with access
+// nodes while they draw, each view keeps the nodes of its last draw so cached views keep theirs,
+// and access_tree stitches them into one tree per window that a test can query headlessly.
+// no platform layer hands the tree to at-spi, uia or nsaccessibility yet, so screen readers
+// still see nothing
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessRole {
+    Group,
+    Window,
+    Button,
+    Tab,
+    Tree,
+    TreeItem,
+    TextEditor,
+}
+
+impl Default for AccessRole {
+    fn default() -> AccessRole {
+        AccessRole::Group
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessAction {
+    Click,
+    Select,
+    Expand,
+    Collapse,
+    Focus,
+}
+
+// bounds and focus are not stored, they come from the area when the tree is built
+#[derive(Clone, Debug, Default)]
+pub struct AccessNode {
+    pub role: AccessRole,
+    pub label: String,
+    pub value: String,
+    pub area: Area,
+    pub selected: bool,
+    pub expanded: Option<bool>,
+    pub actions: Vec<AccessAction>,
+}
+
+// a node as a view stores it, with the range of draw calls it was open for so sub views
+// drawn inside it end up as its children
+#[derive(Clone, Default)]
+pub struct CxAccessNode {
+    pub node: AccessNode,
+    pub parent: Option<usize>,
+    pub draw_calls_begin: usize,
+    pub draw_calls_end: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct AccessTreeNode {
+    pub node: AccessNode,
+    pub bounds: Rect,
+    pub focused: bool,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+// node 0 is the window
+#[derive(Clone, Debug, Default)]
+pub struct AccessTree {
+    pub nodes: Vec<AccessTreeNode>,
+}
+
+impl AccessTree {
+    pub fn find(&self, role: AccessRole, label: &str) -> Option<&AccessTreeNode> {
+        self.nodes.iter().find( | n | n.node.role == role && n.node.label == label)
+    }
+
+    pub fn find_all(&self, role: AccessRole) -> Vec<&AccessTreeNode> {
+        self.nodes.iter().filter( | n | n.node.role == role).collect()
+    }
+
+    pub fn focused(&self) -> Option<&AccessTreeNode> {
+        self.nodes.iter().find( | n | n.focused)
+    }
+
+    pub fn children(&self, node: &AccessTreeNode) -> Vec<&AccessTreeNode> {
+        node.children.iter().map( | id | &self.nodes[*id]).collect()
+    }
+
+    // one line per node, indented by depth
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        if self.nodes.len() > 0 {
+            self.dump_recur(0, 0, &mut out);
+        }
+        out
+    }
+
+    fn dump_recur(&self, node_id: usize, depth: usize, out: &mut String) {
+        let n = &self.nodes[node_id];
+        for _ in 0..depth {
+            out.push_str("  ");
+        }
+        out.push_str(&format!("{:?} {:?}", n.node.role, n.node.label));
+        if n.node.value.len() != 0 {
+            out.push_str(&format!(" value:{:?}", n.node.value));
+        }
+        if n.node.selected {
+            out.push_str(" selected");
+        }
+        if let Some(expanded) = n.node.expanded {
+            out.push_str(if expanded {" expanded"} else {" collapsed"});
+        }
+        if n.focused {
+            out.push_str(" focused");
+        }
+        out.push_str(&format!(" {} {} {} {}\n", n.bounds.x, n.bounds.y, n.bounds.w, n.bounds.h));
+        for child_id in &n.children {
+            self.dump_recur(*child_id, depth + 1, out);
+        }
+    }
+}
+
+impl Cx {
+    // opens a node in the current view, nodes added until end_access are its children
+    pub fn begin_access(&mut self, node: AccessNode) {
+        if let Some(view_id) = self.view_stack.last() {
+            let cxview = &mut self.views[*view_id];
+            let parent = cxview.access_stack.last().cloned();
+            cxview.access_stack.push(cxview.access_nodes.len());
+            cxview.access_nodes.push(CxAccessNode {
+                node: node,
+                parent: parent,
+                draw_calls_begin: cxview.draw_calls_len,
+                draw_calls_end: cxview.draw_calls_len,
+            });
+        }
+    }
+
+    pub fn end_access(&mut self) {
+        if let Some(view_id) = self.view_stack.last() {
+            let cxview = &mut self.views[*view_id];
+            if let Some(index) = cxview.access_stack.pop() {
+                cxview.access_nodes[index].draw_calls_end = cxview.draw_calls_len;
+            }
+        }
+    }
+
+    pub fn add_access(&mut self, node: AccessNode) {
+        self.begin_access(node);
+        self.end_access();
+    }
+
+    pub fn access_tree(&self, window_id: usize) -> AccessTree {
+        let mut tree = AccessTree::default();
+        let window = &self.windows[window_id];
+        tree.nodes.push(AccessTreeNode {
+            node: AccessNode {role: AccessRole::Window, ..Default::default()},
+            bounds: Rect {x: 0., y: 0., w: window.window_geom.inner_size.x, h: window.window_geom.inner_size.y},
+            focused: false,
+            parent: None,
+            children: Vec::new()
+        });
+        if let Some(main_pass_id) = window.main_pass_id {
+            if let Some(main_view_id) = self.passes[main_pass_id].main_view_id {
+                self.access_tree_recur(main_view_id, 0, &mut tree);
+            }
+        }
+        tree
+    }
+
+    fn access_tree_recur(&self, view_id: usize, parent_id: usize, tree: &mut AccessTree) {
+        let cxview = &self.views[view_id];
+        let base = tree.nodes.len();
+        for cxnode in &cxview.access_nodes {
+            let parent = cxnode.parent.map( | p | base + p).unwrap_or(parent_id);
+            let id = tree.nodes.len();
+            tree.nodes[parent].children.push(id);
+            tree.nodes.push(AccessTreeNode {
+                node: cxnode.node.clone(),
+                bounds: cxnode.node.area.get_rect(self, false),
+                focused: cxnode.node.area != Area::Empty && cxnode.node.area == self.key_focus,
+                parent: Some(parent),
+                children: Vec::new()
+            });
+        }
+        for draw_call_id in 0..cxview.draw_calls_len {
+            let sub_view_id = cxview.draw_calls[draw_call_id].sub_view_id;
+            if sub_view_id != 0 {
+                // the innermost node that was open when the sub view was drawn
+                let parent = cxview.access_nodes.iter().rposition( | n | {
+                    n.draw_calls_begin <= draw_call_id && draw_call_id < n.draw_calls_end
+                }).map( | i | base + i).unwrap_or(parent_id);
+                self.access_tree_recur(sub_view_id, parent, tree);
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::cx::*;
+
+// drives a Cx without a platform event loop, for unit tests and scripted automation.
+// with the virtual clock set, timers live on the clock and animations, frames and timers
+// only move when the clock is advanced
+
+pub const AUTOMATION_FRAME_TIME: f64 = 1. / 60.;
+
+#[derive(Clone, Default)]
+pub struct CxVirtualClock {
+    pub time: f64,
+    pub timers: Vec<CxVirtualTimer>,
+}
+
+#[derive(Clone, Default)]
+pub struct CxVirtualTimer {
+    pub timer_id: u64,
+    pub interval: f64,
+    pub repeats: bool,
+    pub due: f64
+}
+
+impl CxVirtualClock {
+    pub fn start_timer(&mut self, timer_id: u64, interval: f64, repeats: bool) {
+        self.timers.push(CxVirtualTimer {timer_id, interval, repeats, due: self.time + interval});
+    }
+
+    pub fn stop_timer(&mut self, timer_id: u64) {
+        self.timers.retain( | timer | timer.timer_id != timer_id);
+    }
+
+    // timer events for everything due now, in order of due time. a repeating timer fires for every
+    // interval that passed and keeps its phase, a zero interval one fires once a frame
+    pub fn due_timers(&mut self) -> Vec<Event> {
+        let mut due: Vec<(f64, u64)> = Vec::new();
+        for timer in &mut self.timers {
+            while timer.due <= self.time {
+                due.push((timer.due, timer.timer_id));
+                if !timer.repeats {
+                    timer.due = std::f64::INFINITY;
+                }
+                else if timer.interval > 0. {
+                    timer.due += timer.interval;
+                }
+                else {
+                    timer.due = self.time + AUTOMATION_FRAME_TIME;
+                }
+            }
+        }
+        self.timers.retain( | timer | timer.due != std::f64::INFINITY);
+        due.sort_by( | a, b | a.partial_cmp(b).unwrap());
+        due.iter().map( | (_, timer_id) | Event::Timer(TimerEvent {timer_id: *timer_id})).collect()
+    }
+
+    // moves the clock a frame, but not past end, and returns the timers that came due
+    pub fn step(&mut self, frame_time: f64, end: f64) -> Vec<Event> {
+        self.time = (self.time + frame_time).min(end);
+        self.due_timers()
+    }
+}
+
+impl Cx {
+    // timers run on the virtual clock when it is set, and on the platform otherwise
+    pub fn start_timer(&mut self, interval: f64, repeats: bool) -> Timer {
+        self.timer_id += 1;
+        if let Some(clock) = &mut self.virtual_clock {
+            clock.start_timer(self.timer_id, interval, repeats);
+        }
+        else {
+            self.start_platform_timer(self.timer_id, interval, repeats);
+        }
+        Timer {timer_id: self.timer_id}
+    }
+
+    pub fn stop_timer(&mut self, timer: &mut Timer) {
+        if timer.timer_id != 0 {
+            if let Some(clock) = &mut self.virtual_clock {
+                clock.stop_timer(timer.timer_id);
+            }
+            else {
+                self.stop_platform_timer(timer.timer_id);
+            }
+            timer.timer_id = 0;
+        }
+    }
+
+    // switches to the virtual clock and gives shaders that no backend compiled their slot
+    // layout, so an app can draw and be hit tested without a gpu
+    pub fn automation_begin(&mut self) {
+        self.virtual_clock = Some(CxVirtualClock::default());
+        for sh in &mut self.shaders {
+            if sh.platform.is_none() {
+                sh.mapping = CxShaderMapping::from_shader_gen(&sh.shader_gen);
+            }
+        }
+    }
+
+    pub fn automation_time(&self) -> f64 {
+        self.virtual_clock.as_ref().map( | clock | clock.time).unwrap_or(0.)
+    }
+
+    // what a platform does before its event loop
+    pub fn automation_construct<F>(&mut self, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        #[cfg(all(not(feature = "ipc"), any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+        self.load_theme_fonts();
+        self.call_event_handler(&mut event_handler, &mut Event::Construct);
+        self.redraw_child_area(Area::All);
+    }
+
+    // delivers an event the way a platform would, with finger capture and key state kept up to date
+    pub fn automation_event<F>(&mut self, event: &mut Event, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        match event {
+            Event::FingerDown(fe) => {
+                fe.tap_count = self.process_tap_count(fe.digit, fe.abs, fe.time);
+            },
+            Event::KeyDown(ke) => {
+                self.process_key_down(ke.clone());
+            },
+            Event::KeyUp(ke) => {
+                self.process_key_up(ke);
+            },
+            _ => ()
+        }
+        self.call_event_handler(&mut event_handler, event);
+        match event {
+            Event::FingerUp(fe) => {
+                self.captured_fingers[fe.digit] = Area::Empty;
+            },
+            Event::FingerHover(_) => {
+                self._finger_over_last_area = self.finger_over_last_area;
+            },
+            _ => ()
+        }
+        self.call_signals(&mut event_handler);
+    }
+
+    // runs the draw event if anything asked for a redraw. the clip uniforms that hit testing
+    // reads are normally set by the renderer, so they are set here instead
+    pub fn automation_draw<F>(&mut self, mut event_handler: F) -> bool
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        if self.redraw_child_areas.len() == 0 && self.redraw_parent_areas.len() == 0 {
+            return false
+        }
+        self.call_draw_event(&mut event_handler);
+        for cxview in &mut self.views {
+            if cxview.uniforms.len() != 0 {
+                cxview.set_clipping_uniforms();
+            }
+        }
+        self.call_signals(&mut event_handler);
+        true
+    }
+
+    // one vsync at the current clock time: animations, frame callbacks, signals and a redraw.
+    // windows are created here too, at the size they asked for
+    pub fn automation_paint<F>(&mut self, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        let time = self.automation_time();
+        if self.playing_anim_areas.len() != 0 {
+            self.call_animation_event(&mut event_handler, time);
+        }
+        if self.frame_callbacks.len() != 0 {
+            self.call_frame_event(&mut event_handler, time);
+        }
+        self.call_signals(&mut event_handler);
+        self.automation_draw(&mut event_handler);
+
+        let mut created = Vec::new();
+        let mut closed = Vec::new();
+        for (index, window) in self.windows.iter_mut().enumerate() {
+            window.window_state = match &window.window_state {
+                CxWindowState::Create {inner_size, position, ..} => {
+                    let window_geom = WindowGeom {
+                        dpi_factor: self.default_dpi_factor,
+                        position: position.unwrap_or(Vec2::zero()),
+                        inner_size: *inner_size,
+                        outer_size: *inner_size,
+                        ..WindowGeom::default()
+                    };
+                    created.push((index, window.window_geom.clone(), window_geom.clone()));
+                    window.window_geom = window_geom;
+                    CxWindowState::Created
+                },
+                CxWindowState::Close => {
+                    closed.push(index);
+                    CxWindowState::Closed
+                },
+                CxWindowState::Created => CxWindowState::Created,
+                CxWindowState::Closed => CxWindowState::Closed
+            };
+            window.window_command = CxWindowCmd::None;
+        }
+        for (window_id, old_geom, new_geom) in created {
+            if let Some(main_pass_id) = self.windows[window_id].main_pass_id {
+                self.redraw_pass_and_sub_passes(main_pass_id);
+            }
+            self.call_event_handler(&mut event_handler, &mut Event::WindowGeomChange(WindowGeomChangeEvent {
+                window_id,
+                old_geom,
+                new_geom
+            }));
+        }
+        for window_id in closed {
+            self.windows_free.push(window_id);
+            self.call_event_handler(&mut event_handler, &mut Event::WindowClosed(WindowClosedEvent {window_id}));
+        }
+    }
+
+    // paints until nothing asks for a redraw, bounded for apps that redraw every frame
+    pub fn automation_settle<F>(&mut self, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        for _ in 0..16 {
+            self.automation_paint(&mut event_handler);
+            if self.redraw_child_areas.len() == 0 && self.redraw_parent_areas.len() == 0 {
+                break;
+            }
+        }
+    }
+
+    // moves the virtual clock forward a frame at a time, firing due timers and painting each frame
+    pub fn automation_advance<F>(&mut self, seconds: f64, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event)
+    {
+        let end = self.automation_time() + seconds;
+        while end - self.automation_time() > 1e-9 {
+            let timers = if let Some(clock) = &mut self.virtual_clock {
+                clock.step(AUTOMATION_FRAME_TIME, end)
+            }
+            else {
+                panic!("automation_advance needs automation_begin first")
+            };
+            for mut event in timers {
+                self.automation_event(&mut event, &mut event_handler);
+            }
+            self.automation_paint(&mut event_handler);
+        }
+    }
+}
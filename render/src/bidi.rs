@@ -0,0 +1,244 @@
+use std::ops::Range;
+
+// the unicode bidirectional algorithm (uax #9) for laying out mixed left-to-right and
+// right-to-left text. it resolves an embedding level for every char of a paragraph, chars at odd
+// levels run right to left, and reorders runs of levels into visual order. explicit embeddings,
+// overrides and isolates are not supported, their formatting chars are ignored like other
+// boundary neutrals
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BidiClass {
+    L, // left to right
+    R, // right to left
+    AL, // arabic letter
+    EN, // european number
+    ES, // european separator
+    ET, // european terminator
+    AN, // arabic number
+    CS, // common separator
+    NSM, // nonspacing mark
+    BN, // boundary neutral
+    B, // paragraph separator
+    S, // segment separator
+    WS, // whitespace
+    ON, // other neutral
+}
+
+// a condensed version of the bidi classes in the unicode character database, which covers the
+// right-to-left scripts, numbers, punctuation and common symbols. everything else is L
+pub fn bidi_class(c: char) -> BidiClass {
+    let c = c as u32;
+    match c {
+        0x09 | 0x0B | 0x1F => BidiClass::S,
+        0x0A | 0x0D | 0x1C..=0x1E | 0x85 | 0x2029 => BidiClass::B,
+        0x0C | 0x20 | 0x1680 | 0x2000..=0x200A | 0x2028 | 0x205F | 0x3000 => BidiClass::WS,
+        0x00..=0x08 | 0x0E..=0x1B | 0x7F..=0x9F | 0xAD | 0x200B..=0x200D | 0x202A..=0x202E | 0x2060..=0x206F | 0xFEFF => BidiClass::BN,
+        0x30..=0x39 | 0xB2 | 0xB3 | 0xB9 | 0x06F0..=0x06F9 | 0x2070 | 0x2074..=0x2079 | 0x2080..=0x2089 | 0xFF10..=0xFF19 => BidiClass::EN,
+        0x2B | 0x2D | 0x207A | 0x207B | 0x208A | 0x208B | 0x2212 | 0xFB29 | 0xFE62 | 0xFE63 | 0xFF0B | 0xFF0D => BidiClass::ES,
+        0x23..=0x25 | 0xA2..=0xA5 | 0xB0 | 0xB1 | 0x0609 | 0x060A | 0x066A | 0x2030..=0x2034 | 0x20A0..=0x20CF | 0xFE5F | 0xFE69 | 0xFE6A | 0xFF03..=0xFF05 => BidiClass::ET,
+        0x2C | 0x2E | 0x2F | 0x3A | 0xA0 | 0x060C | 0x202F | 0x2044 | 0xFE50 | 0xFE52 | 0xFE55 | 0xFF0C | 0xFF0E | 0xFF0F | 0xFF1A => BidiClass::CS,
+        0x0600..=0x0605 | 0x0660..=0x0669 | 0x066B | 0x066C | 0x06DD | 0x08E2 => BidiClass::AN,
+        0x0300..=0x036F | 0x0483..=0x0489 | 0x0591..=0x05BD | 0x05BF | 0x05C1 | 0x05C2 | 0x05C4 | 0x05C5 | 0x05C7
+            | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 | 0x06E7 | 0x06E8 | 0x06EA..=0x06ED
+            | 0x0711 | 0x0730..=0x074A | 0x07A6..=0x07B0 | 0x07EB..=0x07F3 | 0x08D3..=0x08E1 | 0x08E3..=0x08FF
+            | 0x20D0..=0x20FF | 0xFB1E | 0xFE00..=0xFE0F | 0xFE20..=0xFE2F => BidiClass::NSM,
+        0x200E => BidiClass::L,
+        0x200F | 0x0590..=0x05FF | 0x07C0..=0x085F | 0xFB1D..=0xFB4F | 0x10800..=0x10FFF | 0x1E800..=0x1EDFF | 0x1EF00..=0x1EFFF => BidiClass::R,
+        0x0608 | 0x060B | 0x060D | 0x061B..=0x064A | 0x066D..=0x06D5 | 0x06E5 | 0x06E6 | 0x06EE | 0x06EF | 0x06FA..=0x07BF
+            | 0x0860..=0x08D2 | 0xFB50..=0xFD3D | 0xFD40..=0xFDFF | 0xFE70..=0xFEFE | 0x1EE00..=0x1EEFF => BidiClass::AL,
+        0x21 | 0x22 | 0x26..=0x2A | 0x3B..=0x40 | 0x5B..=0x60 | 0x7B..=0x7E | 0xA1 | 0xA6..=0xA9 | 0xAB | 0xAC | 0xAE | 0xAF
+            | 0xB4 | 0xB6..=0xB8 | 0xBB..=0xBF | 0xD7 | 0xF7 | 0x060E | 0x060F | 0x06DE | 0x06E9 | 0x2010..=0x2027
+            | 0x2035..=0x2043 | 0x2045..=0x205E | 0x2190..=0x2BFF | 0x3001..=0x3004 | 0x3008..=0x3020 | 0xFD3E | 0xFD3F
+            | 0xFE30..=0xFE4F | 0xFE51 | 0xFE54 | 0xFE56..=0xFE5E | 0xFE60 | 0xFE61 | 0xFE64..=0xFE68 | 0xFE6B
+            | 0xFF01 | 0xFF02 | 0xFF06..=0xFF0A | 0xFF1B..=0xFF20 | 0xFF3B..=0xFF40 | 0xFF5B..=0xFF65 | 0x1F000..=0x1FAFF => BidiClass::ON,
+        _ => BidiClass::L
+    }
+}
+
+// text without right-to-left chars or arabic numbers lays out left to right, so it can skip the
+// algorithm altogether
+pub fn bidi_has_rtl(chars: &[char]) -> bool {
+    chars.iter().any( | c | match bidi_class(*c) {
+        BidiClass::R | BidiClass::AL | BidiClass::AN => true,
+        _ => false
+    })
+}
+
+// resolves the embedding level of every char. every line is a paragraph, of which the base
+// level is given, or follows its first strong char when it is none
+pub fn bidi_levels(chars: &[char], base_level: Option<u8>) -> Vec<u8> {
+    let mut levels = Vec::with_capacity(chars.len());
+    let mut start = 0;
+    while start < chars.len() {
+        let end = chars[start..].iter().position( | c | bidi_class(*c) == BidiClass::B).map_or(chars.len(), | pos | start + pos + 1);
+        levels.extend(bidi_paragraph_levels(&chars[start..end], base_level));
+        start = end;
+    }
+    levels
+}
+
+fn bidi_paragraph_levels(chars: &[char], base_level: Option<u8>) -> Vec<u8> {
+    let original: Vec<BidiClass> = chars.iter().map( | c | bidi_class(*c)).collect();
+    let base_level = base_level.unwrap_or_else( || {
+        original.iter().find_map( | class | match class {
+            BidiClass::L => Some(0),
+            BidiClass::R | BidiClass::AL => Some(1),
+            _ => None
+        }).unwrap_or(0)
+    });
+    let sos = if base_level & 1 == 0 {BidiClass::L} else {BidiClass::R};
+
+    // boundary neutrals take no part in resolving the other chars
+    let indices: Vec<usize> = (0..chars.len()).filter( | i | original[*i] != BidiClass::BN).collect();
+    let mut classes: Vec<BidiClass> = indices.iter().map( | i | original[*i]).collect();
+
+    // w1: nonspacing marks take the class of the char before them
+    let mut prev = sos;
+    for class in classes.iter_mut() {
+        if *class == BidiClass::NSM {
+            *class = prev;
+        }
+        prev = *class;
+    }
+    // w2 and w3: european numbers after arabic letters are arabic numbers, arabic letters are R
+    let mut last_strong = sos;
+    for class in classes.iter_mut() {
+        match *class {
+            BidiClass::L | BidiClass::R => last_strong = *class,
+            BidiClass::AL => {
+                last_strong = BidiClass::AL;
+                *class = BidiClass::R;
+            }
+            BidiClass::EN if last_strong == BidiClass::AL => *class = BidiClass::AN,
+            _ => ()
+        }
+    }
+    // w4: a single separator between two numbers of the same kind joins them
+    for i in 1..classes.len().max(1) - 1 {
+        let (before, after) = (classes[i - 1], classes[i + 1]);
+        match classes[i] {
+            BidiClass::ES if before == BidiClass::EN && after == BidiClass::EN => classes[i] = BidiClass::EN,
+            BidiClass::CS if before == after && (before == BidiClass::EN || before == BidiClass::AN) => classes[i] = before,
+            _ => ()
+        }
+    }
+    // w5: terminators next to european numbers are part of them
+    let mut i = 0;
+    while i < classes.len() {
+        if classes[i] == BidiClass::ET {
+            let end = classes[i..].iter().position( | class | *class != BidiClass::ET).map_or(classes.len(), | pos | i + pos);
+            let touches_number = (i > 0 && classes[i - 1] == BidiClass::EN) || (end < classes.len() && classes[end] == BidiClass::EN);
+            if touches_number {
+                for class in &mut classes[i..end] {
+                    *class = BidiClass::EN;
+                }
+            }
+            i = end;
+        }
+        else {
+            i += 1;
+        }
+    }
+    // w6 and w7: remaining separators and terminators are neutral, european numbers in left to
+    // right text are L
+    let mut last_strong = sos;
+    for class in classes.iter_mut() {
+        match *class {
+            BidiClass::ES | BidiClass::ET | BidiClass::CS => *class = BidiClass::ON,
+            BidiClass::L | BidiClass::R => last_strong = *class,
+            BidiClass::EN if last_strong == BidiClass::L => *class = BidiClass::L,
+            _ => ()
+        }
+    }
+    // n1 and n2: neutrals between chars of the same direction take it, other neutrals take the
+    // direction of the paragraph. numbers count as R
+    let strong = | class: BidiClass | match class {
+        BidiClass::L => Some(BidiClass::L),
+        BidiClass::R | BidiClass::EN | BidiClass::AN => Some(BidiClass::R),
+        _ => None
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if strong(classes[i]).is_none() {
+            let end = classes[i..].iter().position( | class | strong(*class).is_some()).map_or(classes.len(), | pos | i + pos);
+            let before = if i == 0 {sos} else {strong(classes[i - 1]).unwrap()};
+            let after = if end == classes.len() {sos} else {strong(classes[end]).unwrap()};
+            let class = if before == after {before} else {sos};
+            for neutral in &mut classes[i..end] {
+                *neutral = class;
+            }
+            i = end;
+        }
+        else {
+            i += 1;
+        }
+    }
+    // i1 and i2: the implicit levels
+    let is_rtl = base_level & 1 == 1;
+    let mut levels = vec![base_level; chars.len()];
+    for (index, class) in indices.iter().zip(classes.iter()) {
+        levels[*index] = match class {
+            BidiClass::R if !is_rtl => base_level + 1,
+            BidiClass::EN | BidiClass::AN if !is_rtl => base_level + 2,
+            BidiClass::L | BidiClass::EN | BidiClass::AN if is_rtl => base_level + 1,
+            _ => base_level
+        };
+    }
+    // boundary neutrals stay with the char before them
+    for i in 0..chars.len() {
+        if original[i] == BidiClass::BN {
+            levels[i] = if i == 0 {base_level} else {levels[i - 1]};
+        }
+    }
+    // l1: separators, and whitespace before them and at the end of the paragraph, are at the
+    // paragraph level
+    let mut trailing = true;
+    for i in (0..chars.len()).rev() {
+        match original[i] {
+            BidiClass::B | BidiClass::S => {
+                levels[i] = base_level;
+                trailing = true;
+            }
+            BidiClass::WS | BidiClass::BN if trailing => levels[i] = base_level,
+            _ => trailing = false
+        }
+    }
+    levels
+}
+
+// the runs of chars at the same level, in logical order
+pub fn bidi_level_runs(levels: &[u8]) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=levels.len() {
+        if i == levels.len() || levels[i] != levels[start] {
+            runs.push(start..i);
+            start = i;
+        }
+    }
+    runs
+}
+
+// l2: returns the logical index of every visual position on a line. from the highest level down
+// to the lowest odd level, every sequence at that level or higher is reversed
+pub fn bidi_visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().cloned().max().unwrap_or(0);
+    let lowest_odd = levels.iter().cloned().min().unwrap_or(0) | 1;
+    let mut level = highest;
+    while level >= lowest_odd {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] >= level {
+                let end = order[i..].iter().position( | index | levels[*index] < level).map_or(order.len(), | pos | i + pos);
+                order[i..end].reverse();
+                i = end;
+            }
+            else {
+                i += 1;
+            }
+        }
+        level -= 1;
+    }
+    order
+}
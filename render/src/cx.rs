@@ -18,37 +18,49 @@ pub use crate::colors::*;
 pub use crate::elements::*;
 pub use crate::animator::*;
 pub use crate::area::*;
+pub use crate::automation::*;
+pub use crate::accessibility::*;
+pub use crate::bidi::*;
 pub use crate::menu::*;
 pub use crate::theming::*;
 
-#[cfg(all(not(feature = "ipc"), target_os = "linux"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "linux"))]
 pub use crate::cx_linux::*;
-#[cfg(all(not(feature = "ipc"), target_os = "linux"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "linux"))]
 pub use crate::cx_opengl::*;
 
-#[cfg(all(not(feature = "ipc"), target_os = "macos"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "macos"))]
 pub use crate::cx_macos::*;
-#[cfg(all(not(feature = "ipc"), target_os = "macos"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "macos"))]
 pub use crate::cx_metal::*;
-#[cfg(all(not(feature = "ipc"), target_os = "macos"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "macos"))]
 pub use crate::cx_metalsl::*;
 
-#[cfg(all(not(feature="ipc"),target_os = "windows"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "windows"))]
 pub use crate::cx_windows::*;
-#[cfg(all(not(feature="ipc"),target_os = "windows"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "windows"))]
 pub use crate::cx_dx11::*;
-#[cfg(all(not(feature = "ipc"), target_os = "windows"))]
+#[cfg(all(not(feature = "ipc"), not(feature = "headless"), target_os = "windows"))]
 pub use crate::cx_hlsl::*;
 
 #[cfg(all(not(feature = "ipc"), target_arch = "wasm32"))]
 pub use crate::cx_webgl::*;
 
-#[cfg(all(not(feature = "ipc"), any(target_arch = "wasm32", target_os = "linux")))]
+#[cfg(all(not(feature = "ipc"), any(target_arch = "wasm32", all(not(feature = "headless"), target_os = "linux"))))]
 pub use crate::cx_glsl::*;
 
 #[cfg(all(not(feature = "ipc"), any(target_os = "linux", target_os = "macos", target_os = "windows")))]
 pub use crate::cx_desktop::*;
 
+#[cfg(all(not(feature = "ipc"), feature = "headless", any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub use crate::cx_headless::*;
+#[cfg(all(not(feature = "ipc"), feature = "headless", any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub use crate::cx_software::*;
+#[cfg(all(not(feature = "ipc"), feature = "headless", any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub use crate::cx_softsl::*;
+#[cfg(all(not(feature = "ipc"), feature = "headless", any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub use crate::snapshot::*;
+
 #[cfg(all(not(feature = "ipc"), target_arch = "wasm32"))]
 pub use crate::cx_wasm32::*;
 
@@ -94,6 +106,7 @@ pub struct Cx {
     pub views_free: Vec<usize>,
 
     pub fonts: Vec<CxFont>,
+    pub font_fallbacks: Vec<Vec<usize>>,
     pub fonts_atlas: CxFontsAtlas,
     pub textures: Vec<CxTexture>,
     pub textures_free: Vec<usize>,
@@ -148,6 +161,7 @@ pub struct Cx {
     pub _frame_callbacks: Vec<Area>,
 
     pub signals: Vec<(Signal, usize)>,
+    pub virtual_clock: Option<CxVirtualClock>,
 
     pub theme_colors: HashMap<(ColorId,ClassId), Color>,
     pub theme_text_styles: HashMap<(TextStyleId,ClassId), TextStyle>,
@@ -200,6 +214,7 @@ impl Default for Cx {
             views_free: Vec::new(),
 
             fonts: Vec::new(),
+            font_fallbacks: Vec::new(),
             fonts_atlas: CxFontsAtlas::default(),
             textures: textures,
             textures_free: Vec::new(),
@@ -261,6 +276,7 @@ impl Default for Cx {
 
             //custom_before_draw:Vec::new(),
             signals: Vec::new(),
+            virtual_clock: None,
 
             panic_now: false,
             panic_redraw: false,
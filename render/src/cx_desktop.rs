@@ -55,6 +55,17 @@ impl Cx {
         0
     }
     
+    pub fn file_delete(&mut self, path: &str) {
+        // a file that isn't there is deleted already
+        let _ = std::fs::remove_file(path);
+    }
+    
+    pub fn dir_create(&mut self, path: &str) {
+        if std::fs::create_dir_all(path).is_err() {
+            println!("ERROR CREATING DIR {}", path);
+        }
+    }
+    
     pub fn process_desktop_pre_event<F>(&mut self, event: &mut Event, mut event_handler: F)
     where F: FnMut(&mut Cx, &mut Event)
     {
@@ -191,10 +202,10 @@ impl Cx {
     }
     
     pub fn load_theme_fonts(&mut self) {
-        // lets load all fonts that aren't loaded yet
+        // lets load all fonts that aren't loaded yet, fallbacks wait until a char needs them
         for cxfont in &mut self.fonts{
             let path = cxfont.path.clone();
-            if cxfont.font_loaded.is_none() {
+            if cxfont.font_loaded.is_none() && !cxfont.lazy && !cxfont.failed {
                 // load it
                 let file_result = File::open(&path);
                 if let Ok(mut file) = file_result {
@@ -204,6 +215,7 @@ impl Cx {
                         let mut font = CxFont::default();
                         if font.load_from_ttf_bytes(&buffer).is_err() {
                             println!("Error loading font {} ", path);
+                            cxfont.failed = true;
                         }
                         else {
                             font.path = path.clone();
@@ -213,6 +225,7 @@ impl Cx {
                 }
                 else {
                     println!("Error loading font {} ", path);
+                    cxfont.failed = true;
                 }
                 
             }
@@ -0,0 +1,328 @@
+use crate::cx::*;
+use std::sync::Mutex;
+use time::precise_time_ns;
+
+// the headless platform, windows are images drawn by the software renderer and the event
+// loop runs until the app is idle with no timers left, or the last window closes
+
+static mut HEADLESS_SIGNALS: *mut Mutex<Vec<Event>> = std::ptr::null_mut();
+
+impl Cx {
+    pub fn event_loop<F>(&mut self, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event),
+    {
+        self.headless_init(&mut event_handler);
+
+        loop {
+            let time_now = self.headless_time_now();
+            let mut events = Vec::new();
+            unsafe {
+                if let Ok(mut signals) = (*HEADLESS_SIGNALS).lock() {
+                    events.append(&mut signals);
+                }
+            }
+            events.append(&mut self.headless_due_timers(time_now));
+            events.push(Event::Paint);
+
+            self.headless_process_events(events, &mut event_handler);
+            if self.platform.terminated {
+                break;
+            }
+            if self.headless_is_idle() {
+                // sleep until the next timer, or stop when nothing can happen anymore
+                let next_due = self.platform.timers.iter().fold(f64::INFINITY, | due, timer | due.min(timer.due));
+                let signals_pending = unsafe {(*HEADLESS_SIGNALS).lock().map( | s | !s.is_empty()).unwrap_or(false)};
+                if next_due == f64::INFINITY && !signals_pending {
+                    break;
+                }
+                if !signals_pending {
+                    let wait = (next_due - self.headless_time_now()).max(0.);
+                    std::thread::sleep(std::time::Duration::from_micros((wait * 1_000_000.) as u64));
+                }
+            }
+            else {
+                std::thread::sleep(std::time::Duration::from_millis(16));
+            }
+        }
+    }
+
+    // compiles the shaders and constructs the app, what event_loop does before looping
+    pub fn headless_init<F>(&mut self, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event),
+    {
+        self.platform_type = if cfg!(target_os = "macos") {
+            PlatformType::OSX
+        }
+        else if cfg!(target_os = "windows") {
+            PlatformType::Windows
+        }
+        else {
+            PlatformType::Linux
+        };
+
+        unsafe {
+            if HEADLESS_SIGNALS.is_null() {
+                HEADLESS_SIGNALS = Box::into_raw(Box::new(Mutex::new(Vec::new())));
+            }
+        }
+        self.platform.time_start = precise_time_ns();
+
+        self.software_compile_all_shaders();
+
+        self.load_theme_fonts();
+
+        self.call_event_handler(&mut event_handler, &mut Event::Construct);
+
+        self.redraw_child_area(Area::All);
+    }
+
+    // runs one batch of events through the app, an Event::Paint draws the dirty passes
+    pub fn headless_process_events<F>(&mut self, events: Vec<Event>, mut event_handler: F)
+    where F: FnMut(&mut Cx, &mut Event),
+    {
+        let mut passes_todo = Vec::new();
+        for mut event in events {
+
+            self.process_desktop_pre_event(&mut event, &mut event_handler);
+
+            match &event {
+                Event::WindowSetHoverCursor(mc) => {
+                    self.set_hover_mouse_cursor(mc.clone());
+                },
+                Event::WindowGeomChange(re) => {
+                    for headless_window in &mut self.platform.windows {if headless_window.window_id == re.window_id {
+                        headless_window.window_geom = re.new_geom.clone();
+                        break;
+                    }}
+                    self.windows[re.window_id].window_geom = re.new_geom.clone();
+                    if re.old_geom.inner_size != re.new_geom.inner_size {
+                        if let Some(main_pass_id) = self.windows[re.window_id].main_pass_id {
+                            self.redraw_pass_and_sub_passes(main_pass_id);
+                        }
+                    }
+                    self.call_event_handler(&mut event_handler, &mut event);
+                },
+                Event::WindowClosed(wc) => {
+                    self.windows[wc.window_id].window_state = CxWindowState::Closed;
+                    self.windows_free.push(wc.window_id);
+                    let window_id = wc.window_id;
+                    self.platform.windows.retain( | w | w.window_id != window_id);
+                    if self.platform.windows.is_empty() {
+                        self.platform.terminated = true;
+                    }
+                    self.call_event_handler(&mut event_handler, &mut event);
+                },
+                Event::Paint => {
+                    let _vsync = self.process_desktop_paint_callbacks(self.headless_time_now(), &mut event_handler);
+
+                    // construct or destruct windows
+                    let mut created = Vec::new();
+                    let mut closed = Vec::new();
+                    for (index, window) in self.windows.iter_mut().enumerate() {
+                        window.window_state = match &window.window_state {
+                            CxWindowState::Create {inner_size, position, ..} => {
+                                let window_geom = WindowGeom {
+                                    dpi_factor: self.default_dpi_factor,
+                                    position: position.unwrap_or(Vec2::zero()),
+                                    inner_size: *inner_size,
+                                    outer_size: *inner_size,
+                                    ..WindowGeom::default()
+                                };
+                                created.push((index, window.window_geom.clone(), window_geom.clone()));
+                                window.window_geom = window_geom.clone();
+                                self.platform.windows.push(HeadlessWindow {
+                                    window_id: index,
+                                    window_geom,
+                                    image: SoftImage::default(),
+                                    depth: Vec::new()
+                                });
+                                CxWindowState::Created
+                            },
+                            CxWindowState::Close => {
+                                closed.push(index);
+                                CxWindowState::Closed
+                            },
+                            CxWindowState::Created => CxWindowState::Created,
+                            CxWindowState::Closed => CxWindowState::Closed
+                        };
+                        window.window_command = CxWindowCmd::None;
+                    }
+                    // there is no window manager to report the size, so tell the app here
+                    for (window_id, old_geom, new_geom) in created {
+                        if let Some(main_pass_id) = self.windows[window_id].main_pass_id {
+                            self.redraw_pass_and_sub_passes(main_pass_id);
+                        }
+                        self.call_event_handler(&mut event_handler, &mut Event::WindowGeomChange(WindowGeomChangeEvent {
+                            window_id,
+                            old_geom,
+                            new_geom
+                        }));
+                    }
+                    for window_id in closed {
+                        self.windows_free.push(window_id);
+                        self.platform.windows.retain( | w | w.window_id != window_id);
+                        self.call_event_handler(&mut event_handler, &mut Event::WindowClosed(WindowClosedEvent {window_id}));
+                        if self.platform.windows.is_empty() {
+                            self.platform.terminated = true;
+                        }
+                    }
+
+                    let time_now = self.headless_time_now();
+                    while let Some((timer_id, interval, repeats)) = self.platform.start_timer.pop() {
+                        self.platform.timers.push(HeadlessTimer {timer_id, interval, repeats, due: time_now + interval});
+                    }
+
+                    while let Some(timer_id) = self.platform.stop_timer.pop() {
+                        self.platform.timers.retain( | timer | timer.timer_id != timer_id);
+                    }
+
+                    // build a list of renderpasses to repaint
+                    let mut windows_need_repaint = 0;
+                    self.compute_passes_to_repaint(&mut passes_todo, &mut windows_need_repaint);
+
+                    for pass_id in &passes_todo {
+                        match self.passes[*pass_id].dep_of.clone() {
+                            CxPassDepOf::Window(window_id) => {
+                                let index = if let Some(index) = self.platform.windows.iter().position( | w | w.window_id == window_id) {
+                                    index
+                                }
+                                else {
+                                    continue;
+                                };
+                                let dpi_factor = self.platform.windows[index].window_geom.dpi_factor;
+                                let inner_size = self.platform.windows[index].window_geom.inner_size;
+                                self.passes[*pass_id].set_dpi_factor(dpi_factor);
+                                self.passes[*pass_id].paint_dirty = false;
+                                let mut image = std::mem::take(&mut self.platform.windows[index].image);
+                                let mut depth = std::mem::take(&mut self.platform.windows[index].depth);
+                                self.draw_pass_to_window(*pass_id, dpi_factor, &mut image, &mut depth, inner_size);
+                                self.platform.windows[index].image = image;
+                                self.platform.windows[index].depth = depth;
+                            }
+                            CxPassDepOf::Pass(parent_pass_id) => {
+                                let dpi_factor = self.get_delegated_dpi_factor(parent_pass_id);
+                                self.draw_pass_to_texture(*pass_id, dpi_factor);
+                            },
+                            CxPassDepOf::None => {
+                                self.draw_pass_to_texture(*pass_id, 1.0);
+                            }
+                        }
+                    }
+                },
+                Event::Signal {..} => {
+                    self.call_event_handler(&mut event_handler, &mut event);
+                    self.call_signals(&mut event_handler);
+                },
+                Event::None => {
+                },
+                _ => {
+                    self.call_event_handler(&mut event_handler, &mut event);
+                }
+            }
+            if self.process_desktop_post_event(&mut event) {
+                self.platform.terminated = true;
+            }
+        }
+    }
+
+    // timer events for everything due at time_now, repeating timers are rescheduled
+    pub fn headless_due_timers(&mut self, time_now: f64) -> Vec<Event> {
+        let mut events = Vec::new();
+        for timer in &mut self.platform.timers {
+            if timer.due <= time_now {
+                events.push(Event::Timer(TimerEvent {timer_id: timer.timer_id}));
+                timer.due = if timer.repeats {time_now + timer.interval} else {f64::INFINITY};
+            }
+        }
+        self.platform.timers.retain( | timer | timer.due != f64::INFINITY);
+        events
+    }
+
+    pub fn headless_is_idle(&self) -> bool {
+        self.playing_anim_areas.is_empty() && self.redraw_parent_areas.is_empty() && self.redraw_child_areas.is_empty() && self.frame_callbacks.is_empty()
+    }
+
+    pub fn headless_time_now(&self) -> f64 {
+        if let Some(clock) = &self.virtual_clock {
+            return clock.time
+        }
+        (precise_time_ns() - self.platform.time_start) as f64 / 1_000_000_000.0
+    }
+
+    // the last frame drawn into a window, row 0 on top
+    pub fn headless_window_image(&self, window_id: usize) -> Option<&SoftImage> {
+        self.platform.windows.iter().find( | w | w.window_id == window_id).map( | w | &w.image)
+    }
+
+    pub fn headless_texture_image(&self, texture_id: usize) -> Option<&SoftImage> {
+        self.textures.get(texture_id).map( | t | &t.platform.image)
+    }
+
+    pub fn show_text_ime(&mut self, x: f32, y: f32) {
+        self.platform.set_ime_position = Some(Vec2 {x, y});
+    }
+
+    pub fn hide_text_ime(&mut self) {
+    }
+
+    pub fn set_window_outer_size(&mut self, size: Vec2) {
+        self.platform.set_window_outer_size = Some(size);
+    }
+
+    pub fn set_window_position(&mut self, pos: Vec2) {
+        self.platform.set_window_position = Some(pos);
+    }
+
+    pub fn start_platform_timer(&mut self, timer_id: u64, interval: f64, repeats: bool) {
+        self.platform.start_timer.push((timer_id, interval, repeats));
+    }
+
+    pub fn stop_platform_timer(&mut self, timer_id: u64) {
+        self.platform.stop_timer.push(timer_id);
+    }
+
+    pub fn post_signal(signal: Signal, value: usize) {
+        unsafe {
+            if HEADLESS_SIGNALS.is_null() {
+                return
+            }
+            if let Ok(mut signals) = (*HEADLESS_SIGNALS).lock() {
+                signals.push(Event::Signal(SignalEvent {signal_id: signal.signal_id, value}));
+            }
+        }
+    }
+
+    pub fn update_menu(&mut self, _menu: &Menu) {
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct HeadlessWindow {
+    pub window_id: usize,
+    pub window_geom: WindowGeom,
+    pub image: SoftImage,
+    pub depth: Vec<f32>
+}
+
+#[derive(Clone, Default)]
+pub struct HeadlessTimer {
+    pub timer_id: u64,
+    pub interval: f64,
+    pub repeats: bool,
+    pub due: f64
+}
+
+#[derive(Clone, Default)]
+pub struct CxPlatform {
+    pub set_window_position: Option<Vec2>,
+    pub set_window_outer_size: Option<Vec2>,
+    pub set_ime_position: Option<Vec2>,
+    pub start_timer: Vec<(u64, f64, bool)>,
+    pub stop_timer: Vec<u64>,
+    pub text_clipboard_response: Option<String>,
+    pub windows: Vec<HeadlessWindow>,
+    pub timers: Vec<HeadlessTimer>,
+    pub time_start: u64,
+    pub terminated: bool,
+    pub desktop: CxDesktop,
+}
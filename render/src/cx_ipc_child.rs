@@ -32,6 +32,12 @@ impl Cx {
         return 0
     }
     
+    pub fn file_delete(&mut self, _path: &str) {
+    }
+    
+    pub fn dir_create(&mut self, _path: &str) {
+    }
+    
     pub fn set_window_outer_size(&mut self, _size: Vec2) {
     }
     
@@ -44,15 +50,10 @@ impl Cx {
     pub fn hide_text_ime(&mut self) {
     }
     
-    pub fn start_timer(&mut self, _interval: f64, _repeats: bool) -> Timer {
-        self.timer_id += 1;
-        Timer {timer_id: 0}
+    pub fn start_platform_timer(&mut self, _timer_id: u64, _interval: f64, _repeats: bool) {
     }
     
-    pub fn stop_timer(&mut self, timer: &mut Timer) {
-        if timer.timer_id != 0 {
-            timer.timer_id = 0;
-        }
+    pub fn stop_platform_timer(&mut self, _timer_id: u64) {
     }
     
     pub fn http_send(&self, _verb: &str, _path: &str, _domain: &str, _port: &str, _body: &str) {
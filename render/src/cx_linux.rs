@@ -253,17 +253,12 @@ impl Cx{
         self.platform.set_window_position = Some(pos);
     }
     
-    pub fn start_timer(&mut self, interval: f64, repeats: bool) -> Timer {
-        self.timer_id += 1;
-        self.platform.start_timer.push((self.timer_id, interval, repeats));
-        Timer {timer_id: self.timer_id}
+    pub fn start_platform_timer(&mut self, timer_id: u64, interval: f64, repeats: bool) {
+        self.platform.start_timer.push((timer_id, interval, repeats));
     }
     
-    pub fn stop_timer(&mut self, timer: &mut Timer) {
-        if timer.timer_id != 0 {
-            self.platform.stop_timer.push(timer.timer_id);
-            timer.timer_id = 0;
-        }
+    pub fn stop_platform_timer(&mut self, timer_id: u64) {
+        self.platform.stop_timer.push(timer_id);
     }
     
     pub fn post_signal(signal: Signal, value: usize) {
@@ -0,0 +1,1510 @@
+use crate::cx::*;
+use std::collections::HashMap;
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+// runs the shader ast on the cpu for the headless backend. the ast is resolved once into a
+// tree with numbered variables, and floats carry their screen space derivatives along
+// (forward mode) so dfdx, dfdy and fwidth work per pixel without shading 2x2 quads
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoftDual {
+    pub v: f32,
+    pub dx: f32,
+    pub dy: f32
+}
+
+impl SoftDual {
+    pub fn new(v: f32) -> SoftDual {
+        SoftDual {v, dx: 0., dy: 0.}
+    }
+
+    // f(v) with f'(v) as d
+    fn chain(self, v: f32, d: f32) -> SoftDual {
+        SoftDual {v, dx: self.dx * d, dy: self.dy * d}
+    }
+
+    fn sqrt(self) -> SoftDual {
+        let r = self.v.sqrt();
+        self.chain(r, if r > 0. {0.5 / r} else {0.})
+    }
+
+    fn sin(self) -> SoftDual {self.chain(self.v.sin(), self.v.cos())}
+    fn cos(self) -> SoftDual {self.chain(self.v.cos(), -self.v.sin())}
+
+    fn tan(self) -> SoftDual {
+        let t = self.v.tan();
+        self.chain(t, 1. + t * t)
+    }
+
+    fn asin(self) -> SoftDual {
+        let d = (1. - self.v * self.v).sqrt();
+        self.chain(self.v.asin(), if d > 0. {1. / d} else {0.})
+    }
+
+    fn acos(self) -> SoftDual {
+        let d = (1. - self.v * self.v).sqrt();
+        self.chain(self.v.acos(), if d > 0. {-1. / d} else {0.})
+    }
+
+    fn atan2(self, x: SoftDual) -> SoftDual {
+        let den = self.v * self.v + x.v * x.v;
+        if den == 0. {
+            return SoftDual::new(0.)
+        }
+        SoftDual {
+            v: self.v.atan2(x.v),
+            dx: (x.v * self.dx - self.v * x.dx) / den,
+            dy: (x.v * self.dy - self.v * x.dy) / den
+        }
+    }
+
+    fn exp(self) -> SoftDual {
+        let e = self.v.exp();
+        self.chain(e, e)
+    }
+
+    fn exp2(self) -> SoftDual {
+        let e = self.v.exp2();
+        self.chain(e, e * std::f32::consts::LN_2)
+    }
+
+    fn ln(self) -> SoftDual {self.chain(self.v.ln(), 1. / self.v)}
+    fn log2(self) -> SoftDual {self.chain(self.v.log2(), 1. / (self.v * std::f32::consts::LN_2))}
+
+    fn pow(self, e: SoftDual) -> SoftDual {
+        let r = self.v.powf(e.v);
+        let d_base = if e.v == 0. {0.} else {e.v * self.v.powf(e.v - 1.)};
+        let d_exp = if self.v > 0. {r * self.v.ln()} else {0.};
+        SoftDual {
+            v: r,
+            dx: self.dx * d_base + e.dx * d_exp,
+            dy: self.dy * d_base + e.dy * d_exp
+        }
+    }
+
+    fn abs(self) -> SoftDual {self.chain(self.v.abs(), if self.v < 0. {-1.} else {1.})}
+    fn min(self, o: SoftDual) -> SoftDual {if o.v < self.v {o} else {self}}
+    fn max(self, o: SoftDual) -> SoftDual {if o.v > self.v {o} else {self}}
+
+    // functions that are flat almost everywhere
+    fn floor(self) -> SoftDual {SoftDual::new(self.v.floor())}
+    fn ceil(self) -> SoftDual {SoftDual::new(self.v.ceil())}
+
+    fn sign(self) -> SoftDual {
+        SoftDual::new(if self.v > 0. {1.} else if self.v < 0. {-1.} else {0.})
+    }
+}
+
+impl Add for SoftDual {
+    type Output = SoftDual;
+    fn add(self, o: SoftDual) -> SoftDual {SoftDual {v: self.v + o.v, dx: self.dx + o.dx, dy: self.dy + o.dy}}
+}
+
+impl Sub for SoftDual {
+    type Output = SoftDual;
+    fn sub(self, o: SoftDual) -> SoftDual {SoftDual {v: self.v - o.v, dx: self.dx - o.dx, dy: self.dy - o.dy}}
+}
+
+impl Mul for SoftDual {
+    type Output = SoftDual;
+    fn mul(self, o: SoftDual) -> SoftDual {
+        SoftDual {v: self.v * o.v, dx: self.dx * o.v + self.v * o.dx, dy: self.dy * o.v + self.v * o.dy}
+    }
+}
+
+impl Div for SoftDual {
+    type Output = SoftDual;
+    fn div(self, o: SoftDual) -> SoftDual {
+        let den = o.v * o.v;
+        SoftDual {v: self.v / o.v, dx: (self.dx * o.v - self.v * o.dx) / den, dy: (self.dy * o.v - self.v * o.dy) / den}
+    }
+}
+
+impl Neg for SoftDual {
+    type Output = SoftDual;
+    fn neg(self) -> SoftDual {SoftDual {v: -self.v, dx: -self.dx, dy: -self.dy}}
+}
+
+// matrices are column major like the uniform buffers, and don't carry derivatives
+#[derive(Clone, Copy, Debug)]
+pub enum SoftValue {
+    Void,
+    Bool(usize, [bool; 4]),
+    Int(i64),
+    Float(usize, [SoftDual; 4]),
+    Mat(usize, [f32; 16]),
+    Texture(usize)
+}
+
+impl SoftValue {
+    pub fn float(v: f32) -> SoftValue {
+        SoftValue::from_f32s(&[v])
+    }
+
+    pub fn from_f32s(v: &[f32]) -> SoftValue {
+        let n = v.len().min(4);
+        let mut out = [SoftDual::default(); 4];
+        for i in 0..n {
+            out[i] = SoftDual::new(v[i]);
+        }
+        SoftValue::Float(n, out)
+    }
+
+    fn from_duals<F>(n: usize, f: F) -> SoftValue where F: Fn(usize) -> SoftDual {
+        let mut out = [SoftDual::default(); 4];
+        for (i, out) in out.iter_mut().enumerate().take(n) {
+            *out = f(i);
+        }
+        SoftValue::Float(n.min(4), out)
+    }
+
+    fn from_bools<F>(n: usize, f: F) -> SoftValue where F: Fn(usize) -> bool {
+        let mut out = [false; 4];
+        for (i, out) in out.iter_mut().enumerate().take(n) {
+            *out = f(i);
+        }
+        SoftValue::Bool(n.min(4), out)
+    }
+
+    pub fn zero(ty: &str) -> SoftValue {
+        match ty {
+            "int" => SoftValue::Int(0),
+            "bool" => SoftValue::Bool(1, [false; 4]),
+            "vec2" => SoftValue::from_f32s(&[0., 0.]),
+            "vec3" => SoftValue::from_f32s(&[0., 0., 0.]),
+            "vec4" => SoftValue::from_f32s(&[0., 0., 0., 0.]),
+            "mat2" => SoftValue::Mat(2, [0.; 16]),
+            "mat3" => SoftValue::Mat(3, [0.; 16]),
+            "mat4" => SoftValue::Mat(4, [0.; 16]),
+            _ => SoftValue::float(0.)
+        }
+    }
+
+    pub fn lanes(&self) -> usize {
+        match self {
+            SoftValue::Float(n, _) | SoftValue::Bool(n, _) => *n,
+            SoftValue::Mat(n, _) => n * n,
+            _ => 1
+        }
+    }
+
+    // scalars broadcast to any index
+    pub fn dual(&self, i: usize) -> SoftDual {
+        match self {
+            SoftValue::Float(n, v) => v[if *n <= 1 {0} else {i.min(n - 1)}],
+            SoftValue::Int(v) => SoftDual::new(*v as f32),
+            SoftValue::Bool(..) => SoftDual::new(if self.flag(i) {1.} else {0.}),
+            SoftValue::Mat(n, m) => SoftDual::new(m[i.min(n * n - 1)]),
+            _ => SoftDual::default()
+        }
+    }
+
+    pub fn to_f32(&self, i: usize) -> f32 {
+        self.dual(i).v
+    }
+
+    pub fn flag(&self, i: usize) -> bool {
+        match self {
+            SoftValue::Bool(n, v) => v[if *n <= 1 {0} else {i.min(n - 1)}],
+            SoftValue::Int(v) => *v != 0,
+            SoftValue::Float(..) => self.dual(i).v != 0.,
+            _ => false
+        }
+    }
+
+    pub fn to_int(&self) -> i64 {
+        match self {
+            SoftValue::Int(v) => *v,
+            SoftValue::Bool(..) => if self.flag(0) {1} else {0},
+            _ => self.dual(0).v as i64
+        }
+    }
+
+    fn map1<F>(&self, f: F) -> SoftValue where F: Fn(SoftDual) -> SoftDual {
+        SoftValue::from_duals(self.lanes(), | i | f(self.dual(i)))
+    }
+
+    fn map2<F>(&self, b: &SoftValue, f: F) -> SoftValue where F: Fn(SoftDual, SoftDual) -> SoftDual {
+        SoftValue::from_duals(self.lanes().max(b.lanes()), | i | f(self.dual(i), b.dual(i)))
+    }
+
+    fn map3<F>(&self, b: &SoftValue, c: &SoftValue, f: F) -> SoftValue where F: Fn(SoftDual, SoftDual, SoftDual) -> SoftDual {
+        SoftValue::from_duals(self.lanes().max(b.lanes()), | i | f(self.dual(i), b.dual(i), c.dual(i)))
+    }
+
+    fn dot(&self, b: &SoftValue) -> SoftDual {
+        let mut sum = SoftDual::default();
+        for i in 0..self.lanes().max(b.lanes()) {
+            sum = sum + self.dual(i) * b.dual(i);
+        }
+        sum
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoftVar {
+    Local(usize),
+    Global(usize)
+}
+
+// an assignment target, a variable with an optional swizzle and index
+#[derive(Clone)]
+pub struct SoftPlace {
+    pub var: SoftVar,
+    pub lanes: Option<(usize, [usize; 4])>,
+    pub index: Option<Box<SoftExpr>>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoftCtor {
+    Float(usize),
+    Int,
+    Bool,
+    Mat(usize)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoftBuiltin {
+    Sizeof,
+    Radians,
+    Degrees,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Pow,
+    Exp,
+    Log,
+    Exp2,
+    Log2,
+    Sqrt,
+    InverseSqrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Fract,
+    Fmod,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+    Step,
+    Smoothstep,
+    Length,
+    Distance,
+    Dot,
+    Cross,
+    Normalize,
+    Faceforward,
+    Reflect,
+    Refract,
+    MatrixCompMult,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+    Equal,
+    NotEqual,
+    Any,
+    All,
+    Not,
+    Dfdx,
+    Dfdy,
+    Fwidth,
+    Sample2d
+}
+
+impl SoftBuiltin {
+    pub fn from_name(name: &str) -> Option<SoftBuiltin> {
+        Some(match name {
+            "sizeof" => SoftBuiltin::Sizeof,
+            "radians" => SoftBuiltin::Radians,
+            "degrees" => SoftBuiltin::Degrees,
+            "sin" => SoftBuiltin::Sin,
+            "cos" => SoftBuiltin::Cos,
+            "tan" => SoftBuiltin::Tan,
+            "asin" => SoftBuiltin::Asin,
+            "acos" => SoftBuiltin::Acos,
+            "atan" => SoftBuiltin::Atan,
+            "pow" => SoftBuiltin::Pow,
+            "exp" => SoftBuiltin::Exp,
+            "log" => SoftBuiltin::Log,
+            "exp2" => SoftBuiltin::Exp2,
+            "log2" => SoftBuiltin::Log2,
+            "sqrt" => SoftBuiltin::Sqrt,
+            "inversesqrt" => SoftBuiltin::InverseSqrt,
+            "abs" => SoftBuiltin::Abs,
+            "sign" => SoftBuiltin::Sign,
+            "floor" => SoftBuiltin::Floor,
+            "ceil" => SoftBuiltin::Ceil,
+            "fract" => SoftBuiltin::Fract,
+            "fmod" => SoftBuiltin::Fmod,
+            "min" => SoftBuiltin::Min,
+            "max" => SoftBuiltin::Max,
+            "clamp" => SoftBuiltin::Clamp,
+            "mix" => SoftBuiltin::Mix,
+            "step" => SoftBuiltin::Step,
+            "smoothstep" => SoftBuiltin::Smoothstep,
+            "length" => SoftBuiltin::Length,
+            "distance" => SoftBuiltin::Distance,
+            "dot" => SoftBuiltin::Dot,
+            "cross" => SoftBuiltin::Cross,
+            "normalize" => SoftBuiltin::Normalize,
+            "faceforward" => SoftBuiltin::Faceforward,
+            "reflect" => SoftBuiltin::Reflect,
+            "refract" => SoftBuiltin::Refract,
+            "matrix_comp_mult" => SoftBuiltin::MatrixCompMult,
+            "less_than" => SoftBuiltin::LessThan,
+            "less_than_equal" => SoftBuiltin::LessThanEqual,
+            "greater_than" => SoftBuiltin::GreaterThan,
+            "greater_than_equal" => SoftBuiltin::GreaterThanEqual,
+            "equal" => SoftBuiltin::Equal,
+            "not_equal" => SoftBuiltin::NotEqual,
+            "any" => SoftBuiltin::Any,
+            "all" => SoftBuiltin::All,
+            "not" => SoftBuiltin::Not,
+            "dfdx" => SoftBuiltin::Dfdx,
+            "dfdy" => SoftBuiltin::Dfdy,
+            "fwidth" => SoftBuiltin::Fwidth,
+            "sample2d" => SoftBuiltin::Sample2d,
+            _ => return None
+        })
+    }
+}
+
+#[derive(Clone)]
+pub enum SoftExpr {
+    Value(SoftValue),
+    Var(SoftVar),
+    Swizzle(Box<SoftExpr>, usize, [usize; 4]),
+    Index(Box<SoftExpr>, Box<SoftExpr>),
+    Assign(SoftPlace, Box<SoftExpr>),
+    AssignOp(SoftPlace, ShBinOp, Box<SoftExpr>, Box<SoftExpr>),
+    Binary(ShBinOp, Box<SoftExpr>, Box<SoftExpr>),
+    Neg(Box<SoftExpr>),
+    Not(Box<SoftExpr>),
+    Block(Vec<SoftExpr>),
+    Call(usize, Vec<SoftExpr>),
+    Builtin(SoftBuiltin, Vec<SoftExpr>),
+    Construct(SoftCtor, Vec<SoftExpr>),
+    If(Box<SoftExpr>, Box<SoftExpr>, Option<Box<SoftExpr>>),
+    While(Box<SoftExpr>, Box<SoftExpr>),
+    For(usize, Box<SoftExpr>, Box<SoftExpr>, Box<SoftExpr>),
+    Return(Option<Box<SoftExpr>>),
+    Break,
+    Continue
+}
+
+#[derive(Clone)]
+pub struct SoftFn {
+    pub name: String,
+    pub args: usize,
+    pub frame: usize,
+    pub body: SoftExpr,
+    pub globals_used: Vec<usize>,
+    pub calls: Vec<usize>
+}
+
+#[derive(Clone)]
+pub struct SoftGlobal {
+    pub name: String,
+    pub ty: String,
+    pub store: ShVarStore
+}
+
+// where a global is read from in an attribute or uniform buffer
+#[derive(Clone, Copy, Debug)]
+pub struct SoftSlot {
+    pub global: usize,
+    pub offset: usize,
+    pub slots: usize
+}
+
+#[derive(Clone)]
+pub struct SoftShader {
+    pub globals: Vec<SoftGlobal>,
+    pub fns: Vec<SoftFn>,
+    pub vertex_fn: usize,
+    pub pixel_fn: usize,
+    pub geometries: Vec<SoftSlot>,
+    pub instances: Vec<SoftSlot>,
+    pub uniforms_cx: Vec<SoftSlot>,
+    pub uniforms_vw: Vec<SoftSlot>,
+    pub uniforms_dr: Vec<SoftSlot>,
+    pub uniform_colors: Vec<(usize, ColorId)>,
+    pub textures: Vec<(usize, usize)>,
+    // zeroed before every vertex() and pixel() run
+    pub resets: Vec<usize>,
+    // the vertex outputs pixel() reads, interpolated over the triangle
+    pub pixel_inputs: Vec<usize>
+}
+
+struct SoftCompiler<'a> {
+    sg: &'a ShaderGen,
+    globals: Vec<SoftGlobal>,
+    global_ids: HashMap<String, usize>,
+    consts: HashMap<String, SoftValue>,
+    fns: Vec<Option<SoftFn>>,
+    fn_ids: HashMap<String, usize>,
+    fn_todo: Vec<usize>,
+    scope: Vec<(String, usize)>,
+    frame: usize,
+    frame_max: usize,
+    globals_used: Vec<usize>,
+    calls: Vec<usize>
+}
+
+impl SoftShader {
+    pub fn compile(sg: &ShaderGen, mapping: &CxShaderMapping) -> Result<SoftShader, SlErr> {
+        let mut compiler = SoftCompiler {
+            sg,
+            globals: Vec::new(),
+            global_ids: HashMap::new(),
+            consts: HashMap::new(),
+            fns: Vec::new(),
+            fn_ids: HashMap::new(),
+            fn_todo: Vec::new(),
+            scope: Vec::new(),
+            frame: 0,
+            frame_max: 0,
+            globals_used: Vec::new(),
+            calls: Vec::new()
+        };
+        let vertex_fn = compiler.fn_id("vertex") ?;
+        let pixel_fn = compiler.fn_id("pixel") ?;
+        while let Some(fn_id) = compiler.fn_todo.pop() {
+            compiler.compile_fn(fn_id) ?;
+        }
+
+        let fns: Vec<SoftFn> = compiler.fns.into_iter().map( | f | f.unwrap()).collect();
+        let globals = compiler.globals;
+        let global_ids = compiler.global_ids;
+
+        let packed = | vars: &Vec<ShVar>, aligned: bool | {
+            let mut out = Vec::new();
+            let mut offset = 0;
+            for var in vars {
+                let slots = sg.get_type_slots(&var.ty);
+                // uniforms don't straddle a 4 float boundary
+                if aligned && (offset & 3) != 0 && (offset & 3) + slots > 4 {
+                    offset += 4 - (offset & 3);
+                }
+                if let Some(global) = global_ids.get(&var.name) {
+                    out.push(SoftSlot {global: *global, offset, slots});
+                }
+                offset += slots;
+            }
+            out
+        };
+        let geometries = packed(&mapping.geometries, false);
+        let instances = packed(&mapping.instances, false);
+        let uniforms_cx = packed(&mapping.uniforms_cx, true);
+        let uniforms_vw = packed(&mapping.uniforms_vw, true);
+        let uniforms_dr = packed(&mapping.uniforms_dr, true);
+
+        // textures are bound by their slot index
+        let mut textures = Vec::new();
+        for (slot, texture) in mapping.texture_slots.iter().enumerate() {
+            if let Some(global) = global_ids.get(&texture.name) {
+                textures.push((*global, slot));
+            }
+        }
+
+        let mut uniform_colors = Vec::new();
+        let mut resets = Vec::new();
+        for (index, global) in globals.iter().enumerate() {
+            match &global.store {
+                ShVarStore::UniformColor(color_id) => uniform_colors.push((index, *color_id)),
+                ShVarStore::Local | ShVarStore::Varying => resets.push(index),
+                _ => ()
+            }
+        }
+
+        // walk the functions pixel() can reach for the vertex outputs it reads
+        let mut reached = vec![pixel_fn];
+        let mut i = 0;
+        while i < reached.len() {
+            for call in &fns[reached[i]].calls {
+                if !reached.contains(call) {
+                    reached.push(*call);
+                }
+            }
+            i += 1;
+        }
+        let mut pixel_inputs = Vec::new();
+        for fn_id in reached {
+            for global in &fns[fn_id].globals_used {
+                let is_input = matches!(globals[*global].store, ShVarStore::Varying | ShVarStore::Geometry | ShVarStore::Instance(_));
+                if is_input && !pixel_inputs.contains(global) {
+                    pixel_inputs.push(*global);
+                }
+            }
+        }
+
+        Ok(SoftShader {
+            globals,
+            fns,
+            vertex_fn,
+            pixel_fn,
+            geometries,
+            instances,
+            uniforms_cx,
+            uniforms_vw,
+            uniforms_dr,
+            uniform_colors,
+            textures,
+            resets,
+            pixel_inputs
+        })
+    }
+}
+
+impl<'a> SoftCompiler<'a> {
+    fn fn_id(&mut self, name: &str) -> Result<usize, SlErr> {
+        if let Some(fn_id) = self.fn_ids.get(name) {
+            return Ok(*fn_id)
+        }
+        if let Some(shfn) = self.sg.find_fn(name) {
+            if shfn.block.is_some() {
+                let fn_id = self.fns.len();
+                self.fns.push(None);
+                self.fn_ids.insert(name.to_string(), fn_id);
+                self.fn_todo.push(fn_id);
+                return Ok(fn_id)
+            }
+        }
+        Err(SlErr {msg: format!("Cannot find function {}", name)})
+    }
+
+    fn global_id(&mut self, name: &str) -> Option<usize> {
+        if let Some(global) = self.global_ids.get(name) {
+            return Some(*global)
+        }
+        if let Some(var) = self.sg.find_var(name) {
+            let global = self.globals.len();
+            self.globals.push(SoftGlobal {
+                name: var.name.clone(),
+                ty: var.ty.clone(),
+                store: var.store.clone()
+            });
+            self.global_ids.insert(name.to_string(), global);
+            return Some(global)
+        }
+        None
+    }
+
+    fn const_value(&mut self, name: &str) -> Result<Option<SoftValue>, SlErr> {
+        if let Some(value) = self.consts.get(name) {
+            return Ok(Some(*value))
+        }
+        if let Some(cnst) = self.sg.find_const(name) {
+            let scope = std::mem::take(&mut self.scope);
+            let expr = self.compile_expr(&cnst.value);
+            self.scope = scope;
+            let mut machine = SoftMachine::new(&[], 0);
+            let value = machine.eval(&expr ?);
+            self.consts.insert(name.to_string(), value);
+            return Ok(Some(value))
+        }
+        Ok(None)
+    }
+
+    fn alloc_local(&mut self, name: &str) -> usize {
+        let slot = self.frame;
+        self.frame += 1;
+        self.frame_max = self.frame_max.max(self.frame);
+        self.scope.push((name.to_string(), slot));
+        slot
+    }
+
+    fn compile_fn(&mut self, fn_id: usize) -> Result<(), SlErr> {
+        let name = self.fn_ids.iter().find( | (_, id) | **id == fn_id).unwrap().0.clone();
+        let shfn = self.sg.find_fn(&name).unwrap();
+        self.scope.truncate(0);
+        self.frame = 0;
+        self.frame_max = 0;
+        self.globals_used.truncate(0);
+        self.calls.truncate(0);
+        for arg in &shfn.args {
+            self.alloc_local(&arg.name);
+        }
+        let body = self.compile_block(shfn.block.as_ref().unwrap()) ?;
+        self.fns[fn_id] = Some(SoftFn {
+            name,
+            args: shfn.args.len(),
+            frame: self.frame_max,
+            body,
+            globals_used: self.globals_used.clone(),
+            calls: self.calls.clone()
+        });
+        Ok(())
+    }
+
+    fn compile_block(&mut self, block: &ShBlock) -> Result<SoftExpr, SlErr> {
+        let scope_len = self.scope.len();
+        let frame = self.frame;
+        let mut stmts = Vec::new();
+        for stmt in &block.stmts {
+            match &**stmt {
+                ShStmt::ShLet(stmt) => {
+                    // the initializer can't see the name it defines
+                    let init = self.compile_expr(&stmt.init) ?;
+                    let slot = self.alloc_local(&stmt.name);
+                    let place = SoftPlace {var: SoftVar::Local(slot), lanes: None, index: None};
+                    stmts.push(SoftExpr::Assign(place, Box::new(init)));
+                },
+                ShStmt::ShExpr(expr) | ShStmt::ShSemi(expr) => {
+                    stmts.push(self.compile_expr(expr) ?);
+                }
+            }
+        }
+        self.scope.truncate(scope_len);
+        self.frame = frame;
+        Ok(SoftExpr::Block(stmts))
+    }
+
+    fn compile_var(&mut self, name: &str) -> Option<SoftVar> {
+        if let Some((_, slot)) = self.scope.iter().rev().find( | (n, _) | n == name) {
+            return Some(SoftVar::Local(*slot))
+        }
+        if self.sg.find_const(name).is_some() {
+            return None
+        }
+        if let Some(global) = self.global_id(name) {
+            if !self.globals_used.contains(&global) {
+                self.globals_used.push(global);
+            }
+            return Some(SoftVar::Global(global))
+        }
+        None
+    }
+
+    fn compile_place(&mut self, expr: &ShExpr) -> Result<SoftPlace, SlErr> {
+        match expr {
+            ShExpr::ShId(id) => {
+                if let Some(var) = self.compile_var(&id.name) {
+                    return Ok(SoftPlace {var, lanes: None, index: None})
+                }
+                Err(SlErr {msg: format!("Cannot assign to {}", id.name)})
+            },
+            ShExpr::ShField(field) => {
+                let mut place = self.compile_place(&field.base) ?;
+                let (n, lanes) = swizzle_lanes(&field.member) ?;
+                if place.index.is_some() {
+                    return Err(SlErr {msg: format!("Cannot assign to a swizzle of an index .{}", field.member)})
+                }
+                place.lanes = Some(match place.lanes {
+                    Some((_, inner)) => (n, [inner[lanes[0]], inner[lanes[1]], inner[lanes[2]], inner[lanes[3]]]),
+                    None => (n, lanes)
+                });
+                Ok(place)
+            },
+            ShExpr::ShIndex(index) => {
+                let mut place = self.compile_place(&index.base) ?;
+                if place.index.is_some() {
+                    return Err(SlErr {msg: "Cannot assign to a nested index".to_string()})
+                }
+                place.index = Some(Box::new(self.compile_expr(&index.index) ?));
+                Ok(place)
+            },
+            ShExpr::ShParen(paren) => self.compile_place(&paren.expr),
+            _ => Err(SlErr {msg: "Cannot assign to expression".to_string()})
+        }
+    }
+
+    fn compile_exprs(&mut self, exprs: &[Box<ShExpr>]) -> Result<Vec<SoftExpr>, SlErr> {
+        let mut out = Vec::new();
+        for expr in exprs {
+            out.push(self.compile_expr(expr) ?);
+        }
+        Ok(out)
+    }
+
+    fn compile_expr(&mut self, expr: &ShExpr) -> Result<SoftExpr, SlErr> {
+        Ok(match expr {
+            ShExpr::ShId(id) => {
+                if let Some(var) = self.compile_var(&id.name) {
+                    SoftExpr::Var(var)
+                }
+                else if let Some(value) = self.const_value(&id.name) ? {
+                    SoftExpr::Value(value)
+                }
+                else {
+                    return Err(SlErr {msg: format!("Id {} not resolved, is it declared?", id.name)})
+                }
+            },
+            ShExpr::ShLit(lit) => SoftExpr::Value(match lit {
+                ShLit::Int(v) => SoftValue::Int(*v),
+                ShLit::Float(v) => SoftValue::float(*v as f32),
+                ShLit::Bool(v) => SoftValue::Bool(1, [*v, false, false, false]),
+                ShLit::Str(v) => return Err(SlErr {msg: format!("String {} can only be used in color()", v)})
+            }),
+            ShExpr::ShField(field) => {
+                let (n, lanes) = swizzle_lanes(&field.member) ?;
+                SoftExpr::Swizzle(Box::new(self.compile_expr(&field.base) ?), n, lanes)
+            },
+            ShExpr::ShIndex(index) => SoftExpr::Index(
+                Box::new(self.compile_expr(&index.base) ?),
+                Box::new(self.compile_expr(&index.index) ?)
+            ),
+            ShExpr::ShAssign(assign) => {
+                let right = self.compile_expr(&assign.right) ?;
+                SoftExpr::Assign(self.compile_place(&assign.left) ?, Box::new(right))
+            },
+            ShExpr::ShAssignOp(assign) => {
+                let right = self.compile_expr(&assign.right) ?;
+                let left = self.compile_expr(&assign.left) ?;
+                SoftExpr::AssignOp(self.compile_place(&assign.left) ?, assign.op.clone(), Box::new(left), Box::new(right))
+            },
+            ShExpr::ShBinary(binary) => SoftExpr::Binary(
+                binary.op.clone(),
+                Box::new(self.compile_expr(&binary.left) ?),
+                Box::new(self.compile_expr(&binary.right) ?)
+            ),
+            ShExpr::ShUnary(unary) => {
+                let expr = Box::new(self.compile_expr(&unary.expr) ?);
+                match unary.op {
+                    ShUnaryOp::Neg => SoftExpr::Neg(expr),
+                    ShUnaryOp::Not => SoftExpr::Not(expr)
+                }
+            },
+            ShExpr::ShParen(paren) => self.compile_expr(&paren.expr) ?,
+            ShExpr::ShBlock(block) => self.compile_block(block) ?,
+            ShExpr::ShCall(call) => self.compile_call(call) ?,
+            ShExpr::ShIf(shif) => SoftExpr::If(
+                Box::new(self.compile_expr(&shif.cond) ?),
+                Box::new(self.compile_block(&shif.then_branch) ?),
+                if let Some(else_branch) = &shif.else_branch {
+                    Some(Box::new(self.compile_expr(else_branch) ?))
+                }
+                else {
+                    None
+                }
+            ),
+            ShExpr::ShWhile(shwhile) => SoftExpr::While(
+                Box::new(self.compile_expr(&shwhile.cond) ?),
+                Box::new(self.compile_block(&shwhile.body) ?)
+            ),
+            ShExpr::ShForLoop(shfor) => {
+                let from = self.compile_expr(&shfor.from) ?;
+                let to = self.compile_expr(&shfor.to) ?;
+                let scope_len = self.scope.len();
+                let frame = self.frame;
+                let slot = self.alloc_local(&shfor.iter);
+                let body = self.compile_block(&shfor.body) ?;
+                self.scope.truncate(scope_len);
+                self.frame = frame;
+                SoftExpr::For(slot, Box::new(from), Box::new(to), Box::new(body))
+            },
+            ShExpr::ShReturn(ret) => SoftExpr::Return(
+                if let Some(expr) = &ret.expr {
+                    Some(Box::new(self.compile_expr(expr) ?))
+                }
+                else {
+                    None
+                }
+            ),
+            ShExpr::ShBreak(_) => SoftExpr::Break,
+            ShExpr::ShContinue(_) => SoftExpr::Continue,
+        })
+    }
+
+    fn compile_call(&mut self, call: &ShCall) -> Result<SoftExpr, SlErr> {
+        if let Some(shfn) = self.sg.find_fn(&call.call) {
+            if shfn.block.is_some() {
+                let fn_id = self.fn_id(&call.call) ?;
+                if !self.calls.contains(&fn_id) {
+                    self.calls.push(fn_id);
+                }
+                return Ok(SoftExpr::Call(fn_id, self.compile_exprs(&call.args) ?))
+            }
+            if call.call == "color" {
+                if let Some(ShExpr::ShLit(ShLit::Str(name))) = call.args.first().map( | arg | &**arg) {
+                    let col = color(name);
+                    return Ok(SoftExpr::Value(SoftValue::from_f32s(&[col.r, col.g, col.b, col.a])))
+                }
+                return Err(SlErr {msg: "color() needs a string literal".to_string()})
+            }
+            if let Some(builtin) = SoftBuiltin::from_name(&call.call) {
+                return Ok(SoftExpr::Builtin(builtin, self.compile_exprs(&call.args) ?))
+            }
+            return Err(SlErr {msg: format!("Function {} is not supported by the software renderer", call.call)})
+        }
+        let ctor = match call.call.as_ref() {
+            "float" => SoftCtor::Float(1),
+            "vec2" => SoftCtor::Float(2),
+            "vec3" => SoftCtor::Float(3),
+            "vec4" => SoftCtor::Float(4),
+            "int" => SoftCtor::Int,
+            "bool" => SoftCtor::Bool,
+            "mat2" => SoftCtor::Mat(2),
+            "mat3" => SoftCtor::Mat(3),
+            "mat4" => SoftCtor::Mat(4),
+            _ => return Err(SlErr {msg: format!("Cannot find function {}", call.call)})
+        };
+        Ok(SoftExpr::Construct(ctor, self.compile_exprs(&call.args) ?))
+    }
+}
+
+fn swizzle_lanes(member: &str) -> Result<(usize, [usize; 4]), SlErr> {
+    let mut lanes = [0; 4];
+    if member.is_empty() || member.len() > 4 {
+        return Err(SlErr {msg: format!("member {} is not a valid swizzle", member)})
+    }
+    for (i, chr) in member.chars().enumerate() {
+        lanes[i] = match chr {
+            'x' | 'r' => 0,
+            'y' | 'g' => 1,
+            'z' | 'b' => 2,
+            'w' | 'a' => 3,
+            _ => return Err(SlErr {msg: format!("member {} is not a valid swizzle", member)})
+        };
+    }
+    Ok((member.len(), lanes))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SoftFlow {
+    None,
+    Return,
+    Break,
+    Continue
+}
+
+// evaluates compiled functions, globals hold the uniforms, attributes and shader wide
+// variables of one invocation and the stack the locals of the running functions
+pub struct SoftMachine<'a> {
+    pub fns: &'a [SoftFn],
+    pub globals: Vec<SoftValue>,
+    pub textures: Vec<Option<&'a SoftImage>>,
+    stack: Vec<SoftValue>,
+    base: usize,
+    flow: SoftFlow,
+    ret: SoftValue
+}
+
+impl<'a> SoftMachine<'a> {
+    pub fn new(fns: &'a [SoftFn], globals: usize) -> SoftMachine<'a> {
+        SoftMachine {
+            fns,
+            globals: vec![SoftValue::Void; globals],
+            textures: Vec::new(),
+            stack: Vec::new(),
+            base: 0,
+            flow: SoftFlow::None,
+            ret: SoftValue::Void
+        }
+    }
+
+    pub fn for_shader(shader: &'a SoftShader, textures: Vec<Option<&'a SoftImage>>) -> SoftMachine<'a> {
+        let mut machine = SoftMachine::new(&shader.fns, shader.globals.len());
+        for (index, global) in shader.globals.iter().enumerate() {
+            machine.globals[index] = SoftValue::zero(&global.ty);
+        }
+        for (global, slot) in &shader.textures {
+            machine.globals[*global] = SoftValue::Texture(*slot);
+        }
+        machine.textures = textures;
+        machine
+    }
+
+    pub fn load_slots(&mut self, slots: &[SoftSlot], data: &[f32]) {
+        for slot in slots {
+            if slot.offset + slot.slots > data.len() {
+                continue;
+            }
+            let values = &data[slot.offset..slot.offset + slot.slots];
+            self.globals[slot.global] = match slot.slots {
+                4 if self.globals[slot.global].lanes() == 4 => {
+                    if let SoftValue::Mat(..) = self.globals[slot.global] {
+                        let mut m = [0.; 16];
+                        m[0..4].copy_from_slice(values);
+                        SoftValue::Mat(2, m)
+                    }
+                    else {
+                        SoftValue::from_f32s(values)
+                    }
+                },
+                9 | 16 => {
+                    let mut m = [0.; 16];
+                    m[0..slot.slots].copy_from_slice(values);
+                    SoftValue::Mat(if slot.slots == 9 {3} else {4}, m)
+                },
+                _ => SoftValue::from_f32s(values)
+            };
+        }
+    }
+
+    pub fn reset(&mut self, shader: &SoftShader) {
+        for global in &shader.resets {
+            self.globals[*global] = SoftValue::zero(&shader.globals[*global].ty);
+        }
+    }
+
+    pub fn call(&mut self, fn_id: usize, args: &[SoftValue]) -> SoftValue {
+        let fns = self.fns;
+        let base = self.stack.len();
+        self.stack.extend_from_slice(args);
+        self.stack.resize(base + fns[fn_id].frame.max(args.len()), SoftValue::Void);
+        self.run(fn_id, base)
+    }
+
+    fn call_exprs(&mut self, fn_id: usize, args: &[SoftExpr]) -> SoftValue {
+        let fns = self.fns;
+        let base = self.stack.len();
+        for arg in args {
+            let value = self.eval(arg);
+            self.stack.push(value);
+        }
+        self.stack.resize(base + fns[fn_id].frame.max(args.len()), SoftValue::Void);
+        self.run(fn_id, base)
+    }
+
+    fn run(&mut self, fn_id: usize, base: usize) -> SoftValue {
+        let fns = self.fns;
+        let old_base = std::mem::replace(&mut self.base, base);
+        self.eval(&fns[fn_id].body);
+        let ret = if self.flow == SoftFlow::Return {self.ret} else {SoftValue::Void};
+        self.flow = SoftFlow::None;
+        self.stack.truncate(base);
+        self.base = old_base;
+        ret
+    }
+
+    fn read(&self, var: SoftVar) -> SoftValue {
+        match var {
+            SoftVar::Local(slot) => self.stack[self.base + slot],
+            SoftVar::Global(global) => self.globals[global]
+        }
+    }
+
+    fn store(&mut self, place: &SoftPlace, value: SoftValue) {
+        let lanes = if let Some(index) = &place.index {
+            let i = (self.eval(index).to_int().max(0) as usize).min(3);
+            match place.lanes {
+                Some((_, lanes)) => Some((1, [lanes[i], 0, 0, 0])),
+                None => Some((1, [i, 0, 0, 0]))
+            }
+        }
+        else {
+            place.lanes
+        };
+        let target = match place.var {
+            SoftVar::Local(slot) => &mut self.stack[self.base + slot],
+            SoftVar::Global(global) => &mut self.globals[global]
+        };
+        match lanes {
+            None => *target = value,
+            Some((n, lanes)) => match target {
+                SoftValue::Float(_, v) => for i in 0..n {
+                    v[lanes[i]] = value.dual(i);
+                },
+                SoftValue::Bool(_, v) => for i in 0..n {
+                    v[lanes[i]] = value.flag(i);
+                },
+                _ => *target = value
+            }
+        }
+    }
+
+    pub fn eval(&mut self, expr: &SoftExpr) -> SoftValue {
+        match expr {
+            SoftExpr::Value(value) => *value,
+            SoftExpr::Var(var) => self.read(*var),
+            SoftExpr::Swizzle(base, n, lanes) => {
+                let base = self.eval(base);
+                if let SoftValue::Bool(..) = base {
+                    return SoftValue::from_bools(*n, | i | base.flag(lanes[i]))
+                }
+                SoftValue::from_duals(*n, | i | base.dual(lanes[i]))
+            },
+            SoftExpr::Index(base, index) => {
+                let base = self.eval(base);
+                let i = self.eval(index).to_int().max(0) as usize;
+                if let SoftValue::Mat(n, m) = base {
+                    let col = i.min(n - 1) * n;
+                    return SoftValue::from_f32s(&m[col..col + n])
+                }
+                SoftValue::Float(1, [base.dual(i), SoftDual::default(), SoftDual::default(), SoftDual::default()])
+            },
+            SoftExpr::Assign(place, right) => {
+                let value = self.eval(right);
+                self.store(place, value);
+                value
+            },
+            SoftExpr::AssignOp(place, op, left, right) => {
+                let left = self.eval(left);
+                let right = self.eval(right);
+                let value = binary(op, &left, &right);
+                self.store(place, value);
+                value
+            },
+            SoftExpr::Binary(op, left, right) => match op {
+                ShBinOp::And => {
+                    let value = self.eval(left).flag(0) && self.eval(right).flag(0);
+                    SoftValue::Bool(1, [value, false, false, false])
+                },
+                ShBinOp::Or => {
+                    let value = self.eval(left).flag(0) || self.eval(right).flag(0);
+                    SoftValue::Bool(1, [value, false, false, false])
+                },
+                _ => {
+                    let left = self.eval(left);
+                    let right = self.eval(right);
+                    binary(op, &left, &right)
+                }
+            },
+            SoftExpr::Neg(expr) => match self.eval(expr) {
+                SoftValue::Int(v) => SoftValue::Int(-v),
+                SoftValue::Mat(n, m) => {
+                    let mut out = m;
+                    for v in out.iter_mut() {
+                        *v = -*v;
+                    }
+                    SoftValue::Mat(n, out)
+                },
+                value => value.map1( | x | -x)
+            },
+            SoftExpr::Not(expr) => {
+                let value = self.eval(expr);
+                SoftValue::from_bools(value.lanes(), | i | !value.flag(i))
+            },
+            SoftExpr::Block(stmts) => {
+                for stmt in stmts {
+                    self.eval(stmt);
+                    if self.flow != SoftFlow::None {
+                        break;
+                    }
+                }
+                SoftValue::Void
+            },
+            SoftExpr::Call(fn_id, args) => self.call_exprs(*fn_id, args),
+            SoftExpr::Builtin(builtin, args) => {
+                let mut a = [SoftValue::Void; 3];
+                for (i, arg) in args.iter().enumerate().take(3) {
+                    a[i] = self.eval(arg);
+                }
+                self.builtin(*builtin, &a)
+            },
+            SoftExpr::Construct(ctor, args) => {
+                let mut a = [SoftValue::Void; 16];
+                let mut len = 0;
+                for arg in args.iter().take(16) {
+                    a[len] = self.eval(arg);
+                    len += 1;
+                }
+                construct(*ctor, &a[0..len])
+            },
+            SoftExpr::If(cond, then_branch, else_branch) => {
+                if self.eval(cond).flag(0) {
+                    self.eval(then_branch);
+                }
+                else if let Some(else_branch) = else_branch {
+                    self.eval(else_branch);
+                }
+                SoftValue::Void
+            },
+            SoftExpr::While(cond, body) => {
+                while self.eval(cond).flag(0) {
+                    self.eval(body);
+                    if self.loop_flow() {
+                        break;
+                    }
+                }
+                SoftValue::Void
+            },
+            SoftExpr::For(slot, from, to, body) => {
+                let from = self.eval(from).to_int();
+                let to = self.eval(to).to_int();
+                for i in from..to {
+                    self.stack[self.base + slot] = SoftValue::Int(i);
+                    self.eval(body);
+                    if self.loop_flow() {
+                        break;
+                    }
+                }
+                SoftValue::Void
+            },
+            SoftExpr::Return(expr) => {
+                self.ret = if let Some(expr) = expr {self.eval(expr)} else {SoftValue::Void};
+                self.flow = SoftFlow::Return;
+                SoftValue::Void
+            },
+            SoftExpr::Break => {
+                self.flow = SoftFlow::Break;
+                SoftValue::Void
+            },
+            SoftExpr::Continue => {
+                self.flow = SoftFlow::Continue;
+                SoftValue::Void
+            }
+        }
+    }
+
+    // true when the loop has to stop
+    fn loop_flow(&mut self) -> bool {
+        match self.flow {
+            SoftFlow::Break => {
+                self.flow = SoftFlow::None;
+                true
+            },
+            SoftFlow::Continue => {
+                self.flow = SoftFlow::None;
+                false
+            },
+            SoftFlow::Return => true,
+            SoftFlow::None => false
+        }
+    }
+
+    fn builtin(&self, builtin: SoftBuiltin, a: &[SoftValue; 3]) -> SoftValue {
+        let zero = SoftDual::new(0.);
+        let one = SoftDual::new(1.);
+        match builtin {
+            SoftBuiltin::Sizeof => SoftValue::Int(a[0].lanes() as i64),
+            SoftBuiltin::Radians => a[0].map1( | x | x * SoftDual::new(std::f32::consts::PI / 180.)),
+            SoftBuiltin::Degrees => a[0].map1( | x | x * SoftDual::new(180. / std::f32::consts::PI)),
+            SoftBuiltin::Sin => a[0].map1( | x | x.sin()),
+            SoftBuiltin::Cos => a[0].map1( | x | x.cos()),
+            SoftBuiltin::Tan => a[0].map1( | x | x.tan()),
+            SoftBuiltin::Asin => a[0].map1( | x | x.asin()),
+            SoftBuiltin::Acos => a[0].map1( | x | x.acos()),
+            SoftBuiltin::Atan => if let SoftValue::Void = a[1] {
+                a[0].map1( | x | x.atan2(one))
+            }
+            else {
+                a[0].map2(&a[1], | y, x | y.atan2(x))
+            },
+            SoftBuiltin::Pow => a[0].map2(&a[1], | x, e | x.pow(e)),
+            SoftBuiltin::Exp => a[0].map1( | x | x.exp()),
+            SoftBuiltin::Log => a[0].map1( | x | x.ln()),
+            SoftBuiltin::Exp2 => a[0].map1( | x | x.exp2()),
+            SoftBuiltin::Log2 => a[0].map1( | x | x.log2()),
+            SoftBuiltin::Sqrt => a[0].map1( | x | x.sqrt()),
+            SoftBuiltin::InverseSqrt => a[0].map1( | x | one / x.sqrt()),
+            SoftBuiltin::Abs => match a[0] {
+                SoftValue::Int(v) => SoftValue::Int(v.abs()),
+                _ => a[0].map1( | x | x.abs())
+            },
+            SoftBuiltin::Sign => a[0].map1( | x | x.sign()),
+            SoftBuiltin::Floor => a[0].map1( | x | x.floor()),
+            SoftBuiltin::Ceil => a[0].map1( | x | x.ceil()),
+            SoftBuiltin::Fract => a[0].map1( | x | x - x.floor()),
+            SoftBuiltin::Fmod => a[0].map2(&a[1], | x, y | x - y * (x / y).floor()),
+            SoftBuiltin::Min => a[0].map2(&a[1], | x, y | x.min(y)),
+            SoftBuiltin::Max => a[0].map2(&a[1], | x, y | x.max(y)),
+            SoftBuiltin::Clamp => a[0].map3(&a[1], &a[2], | x, lo, hi | x.max(lo).min(hi)),
+            SoftBuiltin::Mix => a[0].map3(&a[1], &a[2], | x, y, t | x + (y - x) * t),
+            SoftBuiltin::Step => a[0].map2(&a[1], | e, x | if x.v < e.v {zero} else {one}),
+            SoftBuiltin::Smoothstep => a[2].map3(&a[0], &a[1], | x, e0, e1 | {
+                let t = ((x - e0) / (e1 - e0)).max(zero).min(one);
+                t * t * (SoftDual::new(3.) - SoftDual::new(2.) * t)
+            }),
+            SoftBuiltin::Length => SoftValue::Float(1, [a[0].dot(&a[0]).sqrt(), zero, zero, zero]),
+            SoftBuiltin::Distance => {
+                let d = a[0].map2(&a[1], | x, y | x - y);
+                SoftValue::Float(1, [d.dot(&d).sqrt(), zero, zero, zero])
+            },
+            SoftBuiltin::Dot => SoftValue::Float(1, [a[0].dot(&a[1]), zero, zero, zero]),
+            SoftBuiltin::Cross => {
+                let (x, y) = (&a[0], &a[1]);
+                SoftValue::Float(3, [
+                    x.dual(1) * y.dual(2) - x.dual(2) * y.dual(1),
+                    x.dual(2) * y.dual(0) - x.dual(0) * y.dual(2),
+                    x.dual(0) * y.dual(1) - x.dual(1) * y.dual(0),
+                    zero
+                ])
+            },
+            SoftBuiltin::Normalize => {
+                let len = a[0].dot(&a[0]).sqrt();
+                a[0].map1( | x | x / len)
+            },
+            SoftBuiltin::Faceforward => if a[2].dot(&a[1]).v < 0. {a[0]} else {a[0].map1( | x | -x)},
+            SoftBuiltin::Reflect => {
+                let d = a[1].dot(&a[0]);
+                a[0].map2(&a[1], | i, n | i - SoftDual::new(2.) * d * n)
+            },
+            SoftBuiltin::Refract => {
+                let d = a[1].dot(&a[0]);
+                let eta = a[2].dual(0);
+                let k = one - eta * eta * (one - d * d);
+                if k.v < 0. {
+                    a[0].map1( | _ | zero)
+                }
+                else {
+                    a[0].map2(&a[1], | i, n | eta * i - (eta * d + k.sqrt()) * n)
+                }
+            },
+            SoftBuiltin::MatrixCompMult => match (a[0], a[1]) {
+                (SoftValue::Mat(n, x), SoftValue::Mat(_, y)) => {
+                    let mut out = [0.; 16];
+                    for i in 0..16 {
+                        out[i] = x[i] * y[i];
+                    }
+                    SoftValue::Mat(n, out)
+                },
+                _ => a[0]
+            },
+            SoftBuiltin::LessThan => compare_lanes(&a[0], &a[1], | x, y | x < y),
+            SoftBuiltin::LessThanEqual => compare_lanes(&a[0], &a[1], | x, y | x <= y),
+            SoftBuiltin::GreaterThan => compare_lanes(&a[0], &a[1], | x, y | x > y),
+            SoftBuiltin::GreaterThanEqual => compare_lanes(&a[0], &a[1], | x, y | x >= y),
+            SoftBuiltin::Equal => compare_lanes(&a[0], &a[1], | x, y | x == y),
+            SoftBuiltin::NotEqual => compare_lanes(&a[0], &a[1], | x, y | x != y),
+            SoftBuiltin::Any => {
+                let value = (0..a[0].lanes()).any( | i | a[0].flag(i));
+                SoftValue::Bool(1, [value, false, false, false])
+            },
+            SoftBuiltin::All => {
+                let value = (0..a[0].lanes()).all( | i | a[0].flag(i));
+                SoftValue::Bool(1, [value, false, false, false])
+            },
+            SoftBuiltin::Not => SoftValue::from_bools(a[0].lanes(), | i | !a[0].flag(i)),
+            SoftBuiltin::Dfdx => a[0].map1( | x | SoftDual::new(x.dx)),
+            SoftBuiltin::Dfdy => a[0].map1( | x | SoftDual::new(x.dy)),
+            SoftBuiltin::Fwidth => a[0].map1( | x | SoftDual::new(x.dx.abs() + x.dy.abs())),
+            SoftBuiltin::Sample2d => {
+                if let SoftValue::Texture(slot) = a[0] {
+                    if let Some(Some(image)) = self.textures.get(slot) {
+                        return sample_bilinear(image, a[1].dual(0), a[1].dual(1))
+                    }
+                }
+                SoftValue::from_f32s(&[0., 0., 0., 0.])
+            }
+        }
+    }
+}
+
+// v = 0 is the top row, like the metal backend and the order passes render rows in
+fn sample_bilinear(image: &SoftImage, u: SoftDual, v: SoftDual) -> SoftValue {
+    if image.width == 0 || image.height == 0 {
+        return SoftValue::from_f32s(&[0., 0., 0., 0.])
+    }
+    let x = u * SoftDual::new(image.width as f32) - SoftDual::new(0.5);
+    let y = v * SoftDual::new(image.height as f32) - SoftDual::new(0.5);
+    let x0 = x.v.floor();
+    let y0 = y.v.floor();
+    let fx = x - SoftDual::new(x0);
+    let fy = y - SoftDual::new(y0);
+    let one = SoftDual::new(1.);
+    let c00 = image.texel(x0 as isize, y0 as isize);
+    let c10 = image.texel(x0 as isize + 1, y0 as isize);
+    let c01 = image.texel(x0 as isize, y0 as isize + 1);
+    let c11 = image.texel(x0 as isize + 1, y0 as isize + 1);
+    SoftValue::from_duals(4, | i | {
+        let top = SoftDual::new(c00[i]) * (one - fx) + SoftDual::new(c10[i]) * fx;
+        let bottom = SoftDual::new(c01[i]) * (one - fx) + SoftDual::new(c11[i]) * fx;
+        top * (one - fy) + bottom * fy
+    })
+}
+
+fn compare_lanes<F>(a: &SoftValue, b: &SoftValue, f: F) -> SoftValue where F: Fn(f32, f32) -> bool {
+    SoftValue::from_bools(a.lanes().max(b.lanes()), | i | f(a.to_f32(i), b.to_f32(i)))
+}
+
+fn construct(ctor: SoftCtor, args: &[SoftValue]) -> SoftValue {
+    match ctor {
+        SoftCtor::Int => SoftValue::Int(args.first().map_or(0, | a | a.to_int())),
+        SoftCtor::Bool => SoftValue::Bool(1, [args.first().is_some_and(| a | a.flag(0)), false, false, false]),
+        SoftCtor::Float(n) => {
+            let mut out = [SoftDual::default(); 4];
+            let mut len = 0;
+            for arg in args {
+                for i in 0..arg.lanes() {
+                    if len < 4 {
+                        out[len] = arg.dual(i);
+                        len += 1;
+                    }
+                }
+            }
+            // vec3(x) splats
+            if len == 1 {
+                for i in 1..4 {
+                    out[i] = out[0];
+                }
+            }
+            SoftValue::Float(n, out)
+        },
+        SoftCtor::Mat(n) => {
+            let mut m = [0.; 16];
+            if let Some(SoftValue::Mat(from, src)) = args.first() {
+                // the overlapping upper left part, identity elsewhere
+                for c in 0..n {
+                    for r in 0..n {
+                        m[c * n + r] = if c < *from && r < *from {src[c * from + r]} else if c == r {1.} else {0.};
+                    }
+                }
+            }
+            else if args.len() == 1 && args[0].lanes() == 1 {
+                for i in 0..n {
+                    m[i * n + i] = args[0].to_f32(0);
+                }
+            }
+            else {
+                let mut len = 0;
+                for arg in args {
+                    for i in 0..arg.lanes() {
+                        if len < n * n {
+                            m[len] = arg.to_f32(i);
+                            len += 1;
+                        }
+                    }
+                }
+            }
+            SoftValue::Mat(n, m)
+        }
+    }
+}
+
+fn mat_mul(a: &SoftValue, b: &SoftValue) -> SoftValue {
+    match (a, b) {
+        (SoftValue::Mat(n, x), SoftValue::Mat(_, y)) => {
+            let n = *n;
+            let mut out = [0.; 16];
+            for c in 0..n {
+                for r in 0..n {
+                    let mut sum = 0.;
+                    for k in 0..n {
+                        sum += x[k * n + r] * y[c * n + k];
+                    }
+                    out[c * n + r] = sum;
+                }
+            }
+            SoftValue::Mat(n, out)
+        },
+        (SoftValue::Mat(n, m), v @ SoftValue::Float(..)) if v.lanes() > 1 => {
+            let n = *n;
+            SoftValue::from_duals(n, | r | {
+                let mut sum = SoftDual::default();
+                for c in 0..n {
+                    sum = sum + SoftDual::new(m[c * n + r]) * v.dual(c);
+                }
+                sum
+            })
+        },
+        (v @ SoftValue::Float(..), SoftValue::Mat(n, m)) if v.lanes() > 1 => {
+            let n = *n;
+            SoftValue::from_duals(n, | c | {
+                let mut sum = SoftDual::default();
+                for r in 0..n {
+                    sum = sum + v.dual(r) * SoftDual::new(m[c * n + r]);
+                }
+                sum
+            })
+        },
+        _ => mat_lanes(a, b, | x, y | x * y)
+    }
+}
+
+// elementwise on matrices, with scalars broadcast
+fn mat_lanes<F>(a: &SoftValue, b: &SoftValue, f: F) -> SoftValue where F: Fn(f32, f32) -> f32 {
+    let n = match (a, b) {
+        (SoftValue::Mat(n, _), _) | (_, SoftValue::Mat(n, _)) => *n,
+        _ => 1
+    };
+    let mut out = [0.; 16];
+    for (i, out) in out.iter_mut().enumerate().take(n * n) {
+        *out = f(a.to_f32(i), b.to_f32(i));
+    }
+    SoftValue::Mat(n, out)
+}
+
+fn binary(op: &ShBinOp, a: &SoftValue, b: &SoftValue) -> SoftValue {
+    let is_mat = matches!((a, b), (SoftValue::Mat(..), _) | (_, SoftValue::Mat(..)));
+    if let (SoftValue::Int(x), SoftValue::Int(y)) = (a, b) {
+        let (x, y) = (*x, *y);
+        let int = | v: i64 | SoftValue::Int(v);
+        let boolean = | v: bool | SoftValue::Bool(1, [v, false, false, false]);
+        return match op {
+            ShBinOp::Add | ShBinOp::AddEq => int(x.wrapping_add(y)),
+            ShBinOp::Sub | ShBinOp::SubEq => int(x.wrapping_sub(y)),
+            ShBinOp::Mul | ShBinOp::MulEq => int(x.wrapping_mul(y)),
+            ShBinOp::Div | ShBinOp::DivEq => int(if y == 0 {0} else {x / y}),
+            ShBinOp::Rem | ShBinOp::RemEq => int(if y == 0 {0} else {x % y}),
+            ShBinOp::BitXor | ShBinOp::BitXorEq => int(x ^ y),
+            ShBinOp::BitAnd | ShBinOp::BitAndEq => int(x & y),
+            ShBinOp::BitOr | ShBinOp::BitOrEq => int(x | y),
+            ShBinOp::Shl | ShBinOp::ShlEq => int(x.wrapping_shl(y as u32)),
+            ShBinOp::Shr | ShBinOp::ShrEq => int(x.wrapping_shr(y as u32)),
+            ShBinOp::Eq => boolean(x == y),
+            ShBinOp::Ne => boolean(x != y),
+            ShBinOp::Lt => boolean(x < y),
+            ShBinOp::Le => boolean(x <= y),
+            ShBinOp::Gt => boolean(x > y),
+            ShBinOp::Ge => boolean(x >= y),
+            ShBinOp::And => boolean(x != 0 && y != 0),
+            ShBinOp::Or => boolean(x != 0 || y != 0),
+        }
+    }
+    let compare = | f: &dyn Fn(f32, f32) -> bool | {
+        SoftValue::Bool(1, [f(a.to_f32(0), b.to_f32(0)), false, false, false])
+    };
+    match op {
+        ShBinOp::Add | ShBinOp::AddEq => if is_mat {mat_lanes(a, b, | x, y | x + y)} else {a.map2(b, | x, y | x + y)},
+        ShBinOp::Sub | ShBinOp::SubEq => if is_mat {mat_lanes(a, b, | x, y | x - y)} else {a.map2(b, | x, y | x - y)},
+        ShBinOp::Mul | ShBinOp::MulEq => if is_mat {mat_mul(a, b)} else {a.map2(b, | x, y | x * y)},
+        ShBinOp::Div | ShBinOp::DivEq => if is_mat {mat_lanes(a, b, | x, y | x / y)} else {a.map2(b, | x, y | x / y)},
+        ShBinOp::Rem | ShBinOp::RemEq => a.map2(b, | x, y | x - y * (x / y).floor()),
+        ShBinOp::Eq => {
+            let value = (0..a.lanes().max(b.lanes())).all( | i | a.to_f32(i) == b.to_f32(i));
+            SoftValue::Bool(1, [value, false, false, false])
+        },
+        ShBinOp::Ne => {
+            let value = (0..a.lanes().max(b.lanes())).any( | i | a.to_f32(i) != b.to_f32(i));
+            SoftValue::Bool(1, [value, false, false, false])
+        },
+        ShBinOp::Lt => compare(&| x, y | x < y),
+        ShBinOp::Le => compare(&| x, y | x <= y),
+        ShBinOp::Gt => compare(&| x, y | x > y),
+        ShBinOp::Ge => compare(&| x, y | x >= y),
+        ShBinOp::And => compare(&| x, y | x != 0. && y != 0.),
+        ShBinOp::Or => compare(&| x, y | x != 0. || y != 0.),
+        // bit operations only exist on ints
+        _ => binary(op, &SoftValue::Int(a.to_int()), &SoftValue::Int(b.to_int()))
+    }
+}
+
+// the generated source isn't used, the shader is only assembled to typecheck it like the gpu backends do
+impl<'a> SlCx<'a> {
+    pub fn map_call(&self, name: &str, args: &[Sl]) -> MapCallResult {
+        match name {
+            "color" => {
+                let col = color(&args[0].sl);
+                MapCallResult::Rewrite(
+                    format!("vec4({},{},{},{})", col.r, col.g, col.b, col.a),
+                    "vec4".to_string()
+                )
+            },
+            _ => MapCallResult::None
+        }
+    }
+
+    pub fn mat_mul(&self, left: &str, right: &str) -> String {
+        format!("{}*{}", left, right)
+    }
+
+    pub fn map_type(&self, ty: &str) -> String {
+        ty.to_string()
+    }
+
+    pub fn map_constructor(&self, name: &str, args: &[Sl]) -> String {
+        let mut out = String::new();
+        out.push_str(name);
+        out.push('(');
+        for (i, arg) in args.iter().enumerate() {
+            if i != 0 {
+                out.push_str(", ");
+            }
+            out.push_str(&arg.sl);
+        }
+        out.push(')');
+        out
+    }
+
+    pub fn map_var(&mut self, var: &ShVar) -> String {
+        var.name.clone()
+    }
+}
@@ -0,0 +1,521 @@
+use crate::cx::*;
+use std::mem;
+
+// the headless renderer, draws passes into cpu side images with the shader interpreter.
+// it follows the gl backend: blend ONE, ONE_MINUS_SRC_ALPHA on premultiplied color and
+// a LEQUAL depth test, with row 0 as the top of the pass like the metal backend
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct SoftImage {
+    pub width: usize,
+    pub height: usize,
+    // 0xAARRGGBB, like ImageBGRA textures
+    pub pixels: Vec<u32>
+}
+
+impl SoftImage {
+    pub fn new(width: usize, height: usize) -> SoftImage {
+        SoftImage {width, height, pixels: vec![0; width * height]}
+    }
+
+    // returns true if the size changed
+    pub fn resize(&mut self, width: usize, height: usize) -> bool {
+        if self.width == width && self.height == height && self.pixels.len() == width * height {
+            return false
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+        true
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        let pixel = pack_color([color.r, color.g, color.b, color.a]);
+        for p in self.pixels.iter_mut() {
+            *p = pixel;
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        self.pixels[y * self.width + x]
+    }
+
+    // clamps to the edge, as rgba floats
+    pub fn texel(&self, x: isize, y: isize) -> [f32; 4] {
+        let x = x.max(0).min(self.width as isize - 1) as usize;
+        let y = y.max(0).min(self.height as isize - 1) as usize;
+        unpack_color(self.pixels[y * self.width + x])
+    }
+
+    // rgba bytes, row 0 on top
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.pixels.len() * 4);
+        for p in &self.pixels {
+            out.push((p >> 16) as u8);
+            out.push((p >> 8) as u8);
+            out.push(*p as u8);
+            out.push((p >> 24) as u8);
+        }
+        out
+    }
+}
+
+pub fn pack_color(c: [f32; 4]) -> u32 {
+    let q = | v: f32 | (v.clamp(0., 1.) * 255. + 0.5) as u32;
+    (q(c[3]) << 24) | (q(c[0]) << 16) | (q(c[1]) << 8) | q(c[2])
+}
+
+pub fn unpack_color(p: u32) -> [f32; 4] {
+    [
+        ((p >> 16) & 0xff) as f32 / 255.,
+        ((p >> 8) & 0xff) as f32 / 255.,
+        (p & 0xff) as f32 / 255.,
+        (p >> 24) as f32 / 255.
+    ]
+}
+
+// what a pass draws into, an empty depth buffer turns the depth test off
+pub struct SoftTarget<'a> {
+    pub color: &'a mut SoftImage,
+    pub depth: &'a mut Vec<f32>
+}
+
+impl Cx {
+
+    pub fn render_view(&mut self, pass_id: usize, view_id: usize, target: &mut SoftTarget, zbias: &mut f32, zbias_step: f32) {
+        // tad ugly otherwise the borrow checker locks 'self' and we can't recur
+        let draw_calls_len = self.views[view_id].draw_calls_len;
+        self.views[view_id].set_clipping_uniforms();
+        self.views[view_id].uniform_view_transform(&Mat4::identity());
+        for draw_call_id in 0..draw_calls_len {
+            let sub_view_id = self.views[view_id].draw_calls[draw_call_id].sub_view_id;
+            if sub_view_id != 0 {
+                self.render_view(pass_id, sub_view_id, target, zbias, zbias_step);
+                continue;
+            }
+            let shader_id = self.views[view_id].draw_calls[draw_call_id].shader_id;
+            if self.shaders[shader_id].platform.is_none() {
+                if let Err(err) = Self::software_compile_shader(&mut self.shaders[shader_id]) {
+                    panic!("Got software shader compile error:: {}", err.msg);
+                }
+            }
+            {
+                let draw_call = &mut self.views[view_id].draw_calls[draw_call_id];
+                let sh = &self.shaders[shader_id];
+                if !draw_call.uniforms.is_empty() {
+                    if let Some(zbias_offset) = sh.mapping.zbias_uniform_prop {
+                        draw_call.uniforms[zbias_offset] = *zbias;
+                        *zbias += zbias_step;
+                    }
+                }
+                draw_call.instance_dirty = false;
+                draw_call.uniforms_dirty = false;
+                for texture_id in &draw_call.textures_2d {
+                    let cxtexture = &mut self.textures[*texture_id as usize];
+                    if cxtexture.update_image {
+                        Self::update_platform_texture_image2d(cxtexture);
+                    }
+                }
+            }
+
+            let cxview = &self.views[view_id];
+            let draw_call = &cxview.draw_calls[draw_call_id];
+            let sh = &self.shaders[shader_id];
+            let soft = &sh.platform.as_ref().unwrap().soft;
+            if sh.mapping.instance_slots == 0 {
+                continue;
+            }
+
+            let textures = draw_call.textures_2d.iter().map( | texture_id | {
+                let image = &self.textures[*texture_id as usize].platform.image;
+                if !image.pixels.is_empty() {Some(image)} else {None}
+            }).collect();
+            let mut machine = SoftMachine::for_shader(soft, textures);
+            machine.load_slots(&soft.uniforms_cx, &self.passes[pass_id].uniforms);
+            machine.load_slots(&soft.uniforms_vw, &cxview.uniforms);
+            machine.load_slots(&soft.uniforms_dr, &draw_call.uniforms);
+            for (global, color_id) in &soft.uniform_colors {
+                let color = self.theme_colors.get(&(*color_id, ClassId::base())).cloned().unwrap_or(Color::zero());
+                machine.globals[*global] = SoftValue::from_f32s(&[color.r, color.g, color.b, color.a]);
+            }
+
+            let geometry = &sh.shader_gen;
+            let geometry_slots = sh.mapping.geometry_slots.max(1);
+            let vertex_count = geometry.geometry_vertices.len() / geometry_slots;
+            let instance_slots = sh.mapping.instance_slots;
+            let instances = draw_call.instance.len() / instance_slots;
+            let mut vertices = Vec::with_capacity(vertex_count);
+            for instance in 0..instances {
+                let instance_data = &draw_call.instance[instance * instance_slots..(instance + 1) * instance_slots];
+                vertices.truncate(0);
+                for vertex in 0..vertex_count {
+                    machine.reset(soft);
+                    machine.load_slots(&soft.instances, instance_data);
+                    machine.load_slots(&soft.geometries, &geometry.geometry_vertices[vertex * geometry_slots..(vertex + 1) * geometry_slots]);
+                    let position = machine.call(soft.vertex_fn, &[]);
+                    vertices.push(SoftVertex {
+                        clip: [position.to_f32(0), position.to_f32(1), position.to_f32(2), position.to_f32(3)],
+                        inputs: soft.pixel_inputs.iter().map( | global | machine.globals[*global]).collect()
+                    });
+                }
+                for tri in geometry.geometry_indices.chunks(3) {
+                    if tri.len() == 3 && (tri[0] as usize) < vertex_count && (tri[1] as usize) < vertex_count && (tri[2] as usize) < vertex_count {
+                        software_draw_triangle(
+                            soft,
+                            &mut machine,
+                            [&vertices[tri[0] as usize], &vertices[tri[1] as usize], &vertices[tri[2] as usize]],
+                            target
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn draw_pass_to_window(&mut self, pass_id: usize, dpi_factor: f32, image: &mut SoftImage, depth: &mut Vec<f32>, inner_size: Vec2) {
+        let view_id = self.passes[pass_id].main_view_id.unwrap();
+        let pass_size = self.passes[pass_id].pass_size;
+        self.passes[pass_id].set_ortho_matrix(Vec2::zero(), pass_size);
+        self.passes[pass_id].uniform_camera_view(&Mat4::identity());
+        self.passes[pass_id].set_dpi_factor(dpi_factor);
+
+        let clear_color = if self.passes[pass_id].color_textures.is_empty() {
+            Color::zero()
+        }
+        else {
+            match self.passes[pass_id].color_textures[0].clear_color {
+                ClearColor::InitWith(color) => color,
+                ClearColor::ClearWith(color) => color
+            }
+        };
+        let clear_depth = match self.passes[pass_id].clear_depth {
+            ClearDepth::InitWith(depth) => depth,
+            ClearDepth::ClearWith(depth) => depth
+        };
+
+        let width = (inner_size.x * dpi_factor) as usize;
+        let height = (inner_size.y * dpi_factor) as usize;
+        image.resize(width, height);
+        image.clear(clear_color);
+        depth.truncate(0);
+        depth.resize(width * height, clear_depth as f32);
+
+        let mut zbias = 0.0;
+        let zbias_step = self.passes[pass_id].zbias_step;
+        let mut target = SoftTarget {color: image, depth};
+        self.render_view(pass_id, view_id, &mut target, &mut zbias, zbias_step);
+    }
+
+    pub fn draw_pass_to_texture(&mut self, pass_id: usize, inherit_dpi_factor: f32) {
+        let pass_size = self.passes[pass_id].pass_size;
+        self.passes[pass_id].set_ortho_matrix(Vec2::zero(), pass_size);
+        self.passes[pass_id].uniform_camera_view(&Mat4::identity());
+        self.passes[pass_id].paint_dirty = false;
+
+        let dpi_factor = if let Some(override_dpi_factor) = self.passes[pass_id].override_dpi_factor {
+            override_dpi_factor
+        }
+        else {
+            inherit_dpi_factor
+        };
+        self.passes[pass_id].set_dpi_factor(dpi_factor);
+
+        // only the first color texture is drawn into, like gl_FragColor in the gl backend
+        let color_texture = self.passes[pass_id].color_textures.first().cloned();
+        let mut image = SoftImage::default();
+        if let Some(color_texture) = &color_texture {
+            let cxtexture = &mut self.textures[color_texture.texture_id];
+            let realloc = Self::update_platform_render_target(cxtexture, dpi_factor, pass_size);
+            match color_texture.clear_color {
+                ClearColor::InitWith(color) => if realloc {
+                    cxtexture.platform.image.clear(color);
+                },
+                ClearColor::ClearWith(color) => {
+                    cxtexture.platform.image.clear(color);
+                }
+            }
+            image = mem::take(&mut cxtexture.platform.image);
+        }
+
+        let mut depth = mem::take(&mut self.passes[pass_id].platform.depth);
+        if self.passes[pass_id].depth_texture.is_some() {
+            let len = image.width * image.height;
+            match self.passes[pass_id].clear_depth {
+                ClearDepth::InitWith(clear_depth) => if depth.len() != len {
+                    depth.truncate(0);
+                    depth.resize(len, clear_depth as f32);
+                },
+                ClearDepth::ClearWith(clear_depth) => {
+                    depth.truncate(0);
+                    depth.resize(len, clear_depth as f32);
+                }
+            }
+        }
+        else {
+            depth.truncate(0);
+        }
+
+        let mut zbias = 0.0;
+        let zbias_step = self.passes[pass_id].zbias_step;
+        let view_id = self.passes[pass_id].main_view_id.unwrap();
+        {
+            let mut target = SoftTarget {color: &mut image, depth: &mut depth};
+            self.render_view(pass_id, view_id, &mut target, &mut zbias, zbias_step);
+        }
+
+        self.passes[pass_id].platform.depth = depth;
+        if let Some(color_texture) = &color_texture {
+            self.textures[color_texture.texture_id].platform.image = image;
+        }
+    }
+
+    pub fn software_compile_all_shaders(&mut self) {
+        for sh in &mut self.shaders {
+            let softsh = Self::software_compile_shader(sh);
+            if let Err(err) = softsh {
+                panic!("Got software shader compile error:: {}", err.msg);
+            }
+        };
+    }
+
+    pub fn software_compile_shader(sh: &mut CxShader) -> Result<(), SlErr> {
+        let sg = &sh.shader_gen;
+        // typecheck the shader the same way the gpu backends do before running it
+        let mut const_cx = SlCx {
+            depth: 0,
+            target: SlTarget::Constant,
+            defargs_fn: "".to_string(),
+            defargs_call: "".to_string(),
+            call_prefix: "_".to_string(),
+            shader_gen: sg,
+            scope: Vec::new(),
+            fn_deps: Vec::new(),
+            fn_done: Vec::new(),
+            auto_vary: Vec::new()
+        };
+        for cnst in &sg.flat_consts() {
+            let const_init = assemble_const_init(cnst, &mut const_cx) ?;
+            if cnst.ty != const_init.ty {
+                return Err(SlErr {msg: format!("Constant {} init value {} is not the right type {}", cnst.name, const_init.sl, cnst.ty)})
+            }
+        }
+        for (target, entry) in [(SlTarget::Vertex, "vertex"), (SlTarget::Pixel, "pixel")] {
+            let mut fn_cx = SlCx {
+                depth: 0,
+                target,
+                defargs_fn: "".to_string(),
+                defargs_call: "".to_string(),
+                call_prefix: "".to_string(),
+                shader_gen: sg,
+                scope: Vec::new(),
+                fn_deps: vec![entry.to_string()],
+                fn_done: Vec::new(),
+                auto_vary: Vec::new()
+            };
+            assemble_fn_and_deps(sg, &mut fn_cx) ?;
+        }
+
+        let mapping = CxShaderMapping::from_shader_gen(sg);
+        sh.platform = Some(CxPlatformShader {
+            soft: SoftShader::compile(sg, &mapping) ?
+        });
+        sh.mapping = mapping;
+        Ok(())
+    }
+
+    pub fn update_platform_texture_image2d(cxtexture: &mut CxTexture) {
+        if cxtexture.desc.width.is_none() || cxtexture.desc.height.is_none() {
+            println!("update_platform_texture_image2d without width/height");
+            return;
+        }
+        let width = cxtexture.desc.width.unwrap();
+        let height = cxtexture.desc.height.unwrap();
+        cxtexture.platform.alloc_desc = cxtexture.desc.clone();
+        cxtexture.platform.width = width as u64;
+        cxtexture.platform.height = height as u64;
+        cxtexture.platform.image.resize(width, height);
+        let len = (width * height).min(cxtexture.image_u32.len());
+        cxtexture.platform.image.pixels[0..len].copy_from_slice(&cxtexture.image_u32[0..len]);
+        cxtexture.update_image = false;
+    }
+
+    pub fn update_platform_render_target(cxtexture: &mut CxTexture, dpi_factor: f32, size: Vec2) -> bool {
+        let width = if let Some(width) = cxtexture.desc.width {width as u64} else {(size.x * dpi_factor) as u64};
+        let height = if let Some(height) = cxtexture.desc.height {height as u64} else {(size.y * dpi_factor) as u64};
+
+        if cxtexture.platform.width == width && cxtexture.platform.height == height && cxtexture.platform.alloc_desc == cxtexture.desc {
+            return false
+        }
+        match cxtexture.desc.format {
+            TextureFormat::Default | TextureFormat::RenderBGRA => (),
+            _ => {
+                println!("update_platform_render_target unsupported texture format");
+                return false;
+            }
+        }
+        cxtexture.platform.alloc_desc = cxtexture.desc.clone();
+        cxtexture.platform.width = width;
+        cxtexture.platform.height = height;
+        cxtexture.platform.image = SoftImage::new(width as usize, height as usize);
+        true
+    }
+}
+
+pub struct SoftVertex {
+    pub clip: [f32; 4],
+    pub inputs: Vec<SoftValue>
+}
+
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+// pixels exactly on an edge belong to the triangle if it's a top or left edge
+fn is_top_left(a: (f32, f32), b: (f32, f32)) -> bool {
+    let dx = b.0 - a.0;
+    let dy = b.1 - a.1;
+    dy < 0. || (dy == 0. && dx > 0.)
+}
+
+pub fn software_draw_triangle(soft: &SoftShader, machine: &mut SoftMachine, tri: [&SoftVertex; 3], target: &mut SoftTarget) {
+    // no near plane clipping, triangles crossing w = 0 are dropped
+    if tri.iter().any( | v | v.clip[3] <= 0.) {
+        return
+    }
+    let width = target.color.width;
+    let height = target.color.height;
+    if width == 0 || height == 0 {
+        return
+    }
+    let screen = | v: &SoftVertex | {
+        let w = v.clip[3];
+        (
+            (v.clip[0] / w * 0.5 + 0.5) * width as f32,
+            (0.5 - v.clip[1] / w * 0.5) * height as f32,
+            (v.clip[2] / w) * 0.5 + 0.5,
+            1. / w
+        )
+    };
+    let mut v = [tri[0], tri[1], tri[2]];
+    let mut s = [screen(v[0]), screen(v[1]), screen(v[2])];
+    let p = | i: usize, s: &[(f32, f32, f32, f32); 3] | (s[i].0, s[i].1);
+    let mut area = edge(p(0, &s), p(1, &s), p(2, &s));
+    if area < 0. {
+        v.swap(1, 2);
+        s.swap(1, 2);
+        area = -area;
+    }
+    if area == 0. || !area.is_finite() {
+        return
+    }
+    let min_x = s.iter().fold(f32::INFINITY, | m, s | m.min(s.0)).floor().max(0.) as usize;
+    let min_y = s.iter().fold(f32::INFINITY, | m, s | m.min(s.1)).floor().max(0.) as usize;
+    let max_x = (s.iter().fold(f32::NEG_INFINITY, | m, s | m.max(s.0)).ceil() as isize).min(width as isize).max(0) as usize;
+    let max_y = (s.iter().fold(f32::NEG_INFINITY, | m, s | m.max(s.1)).ceil() as isize).min(height as isize).max(0) as usize;
+    let top_left = [
+        is_top_left(p(1, &s), p(2, &s)),
+        is_top_left(p(2, &s), p(0, &s)),
+        is_top_left(p(0, &s), p(1, &s))
+    ];
+
+    // perspective correct weights at a point
+    let weights = | x: f32, y: f32 | {
+        let l = [
+            edge(p(1, &s), p(2, &s), (x, y)) / area,
+            edge(p(2, &s), p(0, &s), (x, y)) / area,
+            edge(p(0, &s), p(1, &s), (x, y)) / area
+        ];
+        let q = [l[0] * s[0].3, l[1] * s[1].3, l[2] * s[2].3];
+        let sum = q[0] + q[1] + q[2];
+        (l, [q[0] / sum, q[1] / sum, q[2] / sum])
+    };
+
+    for py in min_y..max_y {
+        for px in min_x..max_x {
+            let x = px as f32 + 0.5;
+            let y = py as f32 + 0.5;
+            let e = [
+                edge(p(1, &s), p(2, &s), (x, y)),
+                edge(p(2, &s), p(0, &s), (x, y)),
+                edge(p(0, &s), p(1, &s), (x, y))
+            ];
+            if (0..3).any( | i | e[i] < 0. || (e[i] == 0. && !top_left[i])) {
+                continue;
+            }
+            let (l, w) = weights(x, y);
+            // relative to the first vertex so flat triangles get exactly the same depth
+            let z = s[0].2 + l[1] * (s[1].2 - s[0].2) + l[2] * (s[2].2 - s[0].2);
+            if !(0. ..=1.).contains(&z) {
+                continue;
+            }
+            let offset = py * width + px;
+            if !target.depth.is_empty() && z > target.depth[offset] {
+                continue;
+            }
+            let (_, wx) = weights(x + 1., y);
+            let (_, wy) = weights(x, y + 1.);
+
+            machine.reset(soft);
+            for (i, global) in soft.pixel_inputs.iter().enumerate() {
+                machine.globals[*global] = match v[0].inputs[i] {
+                    SoftValue::Float(n, _) => {
+                        let lerp = | w: &[f32; 3], c: usize | {
+                            w[0] * v[0].inputs[i].to_f32(c) + w[1] * v[1].inputs[i].to_f32(c) + w[2] * v[2].inputs[i].to_f32(c)
+                        };
+                        let mut out = [SoftDual::default(); 4];
+                        for (c, out) in out.iter_mut().enumerate().take(n) {
+                            let value = lerp(&w, c);
+                            *out = SoftDual {v: value, dx: lerp(&wx, c) - value, dy: lerp(&wy, c) - value};
+                        }
+                        SoftValue::Float(n, out)
+                    },
+                    value => value
+                };
+            }
+            let color = machine.call(soft.pixel_fn, &[]);
+            let src = [
+                color.to_f32(0).clamp(0., 1.),
+                color.to_f32(1).clamp(0., 1.),
+                color.to_f32(2).clamp(0., 1.),
+                color.to_f32(3).clamp(0., 1.)
+            ];
+            let dst = unpack_color(target.color.pixels[offset]);
+            let inv = 1. - src[3];
+            target.color.pixels[offset] = pack_color([
+                src[0] + dst[0] * inv,
+                src[1] + dst[1] * inv,
+                src[2] + dst[2] * inv,
+                src[3] + dst[3] * inv
+            ]);
+            if !target.depth.is_empty() {
+                target.depth[offset] = z;
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CxPlatformShader {
+    pub soft: SoftShader
+}
+
+#[derive(Clone, Default)]
+pub struct CxPlatformView {
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct CxPlatformDrawCall {
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct CxPlatformTexture {
+    pub alloc_desc: TextureDesc,
+    pub width: u64,
+    pub height: u64,
+    pub image: SoftImage
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct CxPlatformPass {
+    pub depth: Vec<f32>
+}
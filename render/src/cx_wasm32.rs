@@ -39,7 +39,9 @@ impl Cx {
                     // send the UI our deps, overlap with shadercompiler
                     let mut load_deps = Vec::<String>::new();
                     for cxfont in &self.fonts{
-                        load_deps.push(cxfont.path.clone());
+                        if !cxfont.lazy {
+                            load_deps.push(cxfont.path.clone());
+                        }
                     }
                     // other textures, things
                     self.platform.from_wasm.load_deps(load_deps);
@@ -461,6 +463,12 @@ impl Cx {
         return 0
     }
     
+    pub fn file_delete(&mut self, _path: &str) {
+    }
+    
+    pub fn dir_create(&mut self, _path: &str) {
+    }
+    
     pub fn set_window_outer_size(&mut self, _size: Vec2) {
     }
     
@@ -475,17 +483,12 @@ impl Cx {
         self.platform.from_wasm.hide_text_ime();
     }
     
-    pub fn start_timer(&mut self, interval: f64, repeats: bool) -> Timer {
-        self.timer_id += 1;
-        self.platform.from_wasm.start_timer(self.timer_id, interval, repeats);
-        Timer {timer_id: self.timer_id}
+    pub fn start_platform_timer(&mut self, timer_id: u64, interval: f64, repeats: bool) {
+        self.platform.from_wasm.start_timer(timer_id, interval, repeats);
     }
     
-    pub fn stop_timer(&mut self, timer: &mut Timer) {
-        if timer.timer_id != 0 {
-            self.platform.from_wasm.stop_timer(timer.timer_id);
-            timer.timer_id = 0;
-        }
+    pub fn stop_platform_timer(&mut self, timer_id: u64) {
+        self.platform.from_wasm.stop_timer(timer_id);
     }
     
     pub fn http_send(&self, _verb: &str, _path: &str, _domain: &str, _port: &str, _body: &str) {
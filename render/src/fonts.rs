@@ -13,6 +13,8 @@ impl Cx {
     pub fn load_font(&mut self, path: &str) -> Font {
         let found = self.fonts.iter().position( | v | v.path == path);
         if let Some(font_id) = found {
+            // a theme font loads up front, even if it is also somebody's fallback
+            self.fonts[font_id].lazy = false;
             return Font {
                 font_id: Some(font_id),
             }
@@ -28,6 +30,81 @@ impl Cx {
             font_id: Some(font_id)
         }
     }
+    
+    // registers a list of fonts to try for chars a text style font has no glyph for.
+    // they load when a char first needs them, paths that don't exist are skipped
+    pub fn load_font_fallbacks(&mut self, paths: &[&str]) -> FontFallbacks {
+        let mut font_ids = Vec::new();
+        for path in paths {
+            let font_id = if let Some(font_id) = self.fonts.iter().position( | v | v.path == *path) {
+                font_id
+            }
+            else {
+                self.fonts.push(CxFont {
+                    path: path.to_string(),
+                    lazy: true,
+                    ..Default::default()
+                });
+                self.fonts.len() - 1
+            };
+            font_ids.push(font_id);
+        }
+        let fallbacks_id = if let Some(fallbacks_id) = self.font_fallbacks.iter().position( | v | *v == font_ids) {
+            fallbacks_id
+        }
+        else {
+            self.font_fallbacks.push(font_ids);
+            self.font_fallbacks.len() - 1
+        };
+        FontFallbacks {
+            fallbacks_id: Some(fallbacks_id)
+        }
+    }
+    
+    // wasm has no files to read synchronously, so there text goes without fallbacks.
+    // a font that doesn't load is marked failed, so neither this nor the theme tries it again
+    pub fn load_lazy_font(&mut self, font_id: usize) {
+        let cxfont = &mut self.fonts[font_id];
+        if !cxfont.lazy || cxfont.failed {
+            return
+        }
+        cxfont.lazy = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Ok(bytes) = std::fs::read(&cxfont.path) {
+                if cxfont.load_from_ttf_bytes(&bytes).is_err() {
+                    println!("Error loading font {} ", cxfont.path);
+                    cxfont.failed = true;
+                }
+            }
+            else {
+                cxfont.failed = true;
+            }
+        }
+    }
+    
+    // the font for a char, the text style font if it has a glyph for it or else the first
+    // fallback that does. chars no font has a glyph for use the missing glyph of the text style font
+    pub fn find_font_glyph(&mut self, text_style: &TextStyle, c: char) -> (usize, usize) {
+        let font_id = text_style.font.font_id.unwrap();
+        let glyph_id = self.fonts[font_id].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map.get(c as u32);
+        if glyph_id != 0 {
+            return (font_id, glyph_id)
+        }
+        if let Some(fallbacks_id) = text_style.fallbacks.fallbacks_id {
+            for i in 0..self.font_fallbacks[fallbacks_id].len() {
+                let fallback_id = self.font_fallbacks[fallbacks_id][i];
+                self.load_lazy_font(fallback_id);
+                if let Some(font) = &self.fonts[fallback_id].font_loaded {
+                    let glyph_id = font.char_code_to_glyph_index_map.get(c as u32);
+                    if glyph_id != 0 {
+                        return (fallback_id, glyph_id)
+                    }
+                }
+            }
+        }
+        (font_id, 0)
+    }
 }
 
 #[derive(Copy, Clone, Default)]
@@ -35,6 +112,41 @@ pub struct Font{
     pub font_id: Option<usize>
 }
 
+#[derive(Copy, Clone, Default)]
+pub struct FontFallbacks {
+    pub fallbacks_id: Option<usize>
+}
+
+// fonts that come with the os and cover cjk and symbols, for use as fallbacks
+pub fn system_fallback_font_paths() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+            "/Library/Fonts/Arial Unicode.ttf",
+            "/System/Library/Fonts/Apple Symbols.ttf",
+        ]
+    }
+    else if cfg!(target_os = "windows") {
+        &[
+            "C:\\Windows\\Fonts\\seguisym.ttf",
+            "C:\\Windows\\Fonts\\seguiemj.ttf",
+            "C:\\Windows\\Fonts\\simhei.ttf",
+            "C:\\Windows\\Fonts\\malgun.ttf",
+        ]
+    }
+    else if cfg!(target_os = "linux") {
+        &[
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+            "/usr/share/fonts/TTF/DejaVuSans.ttf",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+            "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+        ]
+    }
+    else {
+        &[]
+    }
+}
+
 pub struct TrapezoidText {
     shader: Shader,
     trapezoidator: Trapezoidator
@@ -181,11 +293,7 @@ impl TrapezoidText {
             let cxfont = &cx.fonts[font_id];
             let font = cxfont.font_loaded.as_ref().unwrap();
             
-            let slot = if c < '\u{10000}' {
-                cx.fonts[font_id].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map[c as usize]
-            } else {
-                0
-            };
+            let slot = cx.fonts[font_id].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map.get(c as u32);
             
             if slot == 0 {
                 return
@@ -250,9 +358,9 @@ impl TrapezoidText {
                 let atlas_page = &cxfont.atlas_pages[todo.atlas_page_id];
                 let glyph = &font.glyphs[todo.glyph_id];
                 
-                if todo.glyph_id == font.char_code_to_glyph_index_map[10] ||
-                todo.glyph_id == font.char_code_to_glyph_index_map[9] ||
-                todo.glyph_id == font.char_code_to_glyph_index_map[13] {
+                if todo.glyph_id == font.char_code_to_glyph_index_map.get(10) ||
+                todo.glyph_id == font.char_code_to_glyph_index_map.get(9) ||
+                todo.glyph_id == font.char_code_to_glyph_index_map.get(13) {
                     return
                 }
                 
@@ -331,7 +439,14 @@ impl CxAfterDraw {
         if cx.fonts_atlas.atlas_todo.len()>0 {
             self.atlas_pass.begin_pass(cx);
             self.atlas_pass.set_size(cx, cx.fonts_atlas.texture_size);
-            self.atlas_pass.add_color_texture(cx, &mut self.atlas_texture, ClearColor::InitWith(Color::zero()));
+            let clear_color = if cx.fonts_atlas.clear_texture {
+                cx.fonts_atlas.clear_texture = false;
+                ClearColor::ClearWith(Color::zero())
+            }
+            else {
+                ClearColor::InitWith(Color::zero())
+            };
+            self.atlas_pass.add_color_texture(cx, &mut self.atlas_texture, clear_color);
             let _ = self.atlas_view.begin_view(cx, Layout::default());
             let mut atlas_todo = Vec::new();
             std::mem::swap(&mut cx.fonts_atlas.atlas_todo, &mut atlas_todo);
@@ -343,6 +458,15 @@ impl CxAfterDraw {
             self.atlas_view.end_view(cx);
             self.atlas_pass.end_pass(cx);
         }
+        
+        // the text drawn without the glyphs that didn't fit draws again into an empty atlas
+        if cx.fonts_atlas.alloc_full {
+            cx.fonts_atlas.reset_atlas(&mut cx.fonts);
+            cx.redraw_child_area(Area::All);
+        }
+        else {
+            cx.fonts_atlas.was_reset = false;
+        }
         //println!("TOTALT TIME {}", Cx::profile_time_ns() - start);
     }
 }
@@ -352,6 +476,10 @@ pub struct CxFont {
     pub path: String,
     pub font_loaded: Option<font::Font>,
     pub atlas_pages: Vec<CxFontAtlasPage>,
+    // fallback fonts load on first use instead of with the theme
+    pub lazy: bool,
+    // the file is missing or isn't a font
+    pub failed: bool,
 }
 
 pub const ATLAS_SUBPIXEL_SLOTS: usize = 64;
@@ -380,6 +508,12 @@ pub struct CxFontsAtlasTodo {
     pub subpixel_id: usize
 }
 
+// the atlas doesn't grow beyond what every gpu backend supports for a texture
+pub const ATLAS_MAX_SIZE: f32 = 16384.0;
+
+// the glyphs of all fonts share one atlas texture. when a glyph doesn't fit, the atlas is emptied
+// after the frame and everything draws again, and when the glyphs of a single frame don't fit
+// an empty atlas it grows
 #[derive(Default)]
 pub struct CxFontsAtlas {
     pub texture_id: usize,
@@ -388,17 +522,25 @@ pub struct CxFontsAtlas {
     pub alloc_ypos: f32,
     pub alloc_hmax: f32,
     pub atlas_todo: Vec<CxFontsAtlasTodo>,
+    // a glyph didn't fit this frame
+    pub alloc_full: bool,
+    // the atlas was emptied after the previous frame
+    pub was_reset: bool,
+    // the texture still has the glyphs from before the reset
+    pub clear_texture: bool,
 }
 
 impl CxFontsAtlas {
-    pub fn alloc_atlas_glyph(&mut self, path: &str, w: f32, h: f32) -> CxFontAtlasGlyph {
+    // rows of glyphs, left to right and top to bottom. None when the glyph doesn't fit anymore
+    pub fn alloc_atlas_glyph(&mut self, w: f32, h: f32) -> Option<CxFontAtlasGlyph> {
         if w + self.alloc_xpos >= self.texture_size.x {
             self.alloc_xpos = 0.0;
             self.alloc_ypos += self.alloc_hmax + 1.0;
             self.alloc_hmax = 0.0;
         }
-        if h + self.alloc_ypos >= self.texture_size.y {
-            println!("FONT ATLAS FULL {}, TODO FIX THIS", path);
+        if w >= self.texture_size.x || h + self.alloc_ypos >= self.texture_size.y {
+            self.alloc_full = true;
+            return None
         }
         if h > self.alloc_hmax {
             self.alloc_hmax = h;
@@ -409,16 +551,29 @@ impl CxFontsAtlas {
         
         self.alloc_xpos += w + 1.0;
         
-        if h > self.alloc_hmax {
-            self.alloc_hmax = h;
-        }
-        
-        CxFontAtlasGlyph {
+        Some(CxFontAtlasGlyph {
             tx1: tx1,
             ty1: ty1,
             tx2: tx1 + (w / self.texture_size.x),
             ty2: ty1 + (h / self.texture_size.y)
+        })
+    }
+    
+    // drops every glyph, the pages of the fonts are made again when text draws
+    pub fn reset_atlas(&mut self, fonts: &mut Vec<CxFont>) {
+        if self.alloc_full && self.was_reset && self.texture_size.y < ATLAS_MAX_SIZE {
+            self.texture_size.y = (self.texture_size.y * 2.0).min(ATLAS_MAX_SIZE);
+        }
+        for cxfont in fonts {
+            cxfont.atlas_pages.clear();
         }
+        self.atlas_todo.truncate(0);
+        self.alloc_xpos = 0.0;
+        self.alloc_ypos = 0.0;
+        self.alloc_hmax = 0.0;
+        self.alloc_full = false;
+        self.was_reset = true;
+        self.clear_texture = true;
     }
 }
 
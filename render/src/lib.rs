@@ -8,29 +8,29 @@ mod theming;
 #[macro_use]
 mod cx;
 
-#[cfg(all(not(feature="ipc"),target_os = "linux"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "linux"))]
 mod cx_opengl;
-#[cfg(all(not(feature="ipc"),target_os = "linux"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "linux"))]
 mod cx_xlib;
-#[cfg(all(not(feature="ipc"),any(target_os = "linux")))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),any(target_os = "linux")))]
 mod cx_linux;
 
-#[cfg(all(not(feature="ipc"),target_os = "macos"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "macos"))]
 mod cx_metal;
-#[cfg(all(not(feature="ipc"),target_os = "macos"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "macos"))]
 mod cx_metalsl;
-#[cfg(all(not(feature="ipc"),target_os = "macos"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "macos"))]
 mod cx_cocoa;
-#[cfg(all(not(feature="ipc"),any(target_os = "macos")))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),any(target_os = "macos")))]
 mod cx_macos;
 
-#[cfg(all(not(feature="ipc"),target_os = "windows"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "windows"))]
 mod cx_dx11;
-#[cfg(all(not(feature="ipc"),target_os = "windows"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "windows"))]
 mod cx_hlsl;
-#[cfg(all(not(feature="ipc"),target_os = "windows"))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),target_os = "windows"))]
 mod cx_win32;
-#[cfg(all(not(feature="ipc"),any(target_os = "windows")))]
+#[cfg(all(not(feature="ipc"),not(feature="headless"),any(target_os = "windows")))]
 mod cx_windows;
 
 #[cfg(all(not(feature="ipc"),target_arch = "wasm32"))]
@@ -38,12 +38,21 @@ mod cx_webgl;
 #[cfg(all(not(feature="ipc"),target_arch = "wasm32"))]
 mod cx_wasm32;
 
-#[cfg(all(not(feature="ipc"),any(target_arch = "wasm32", target_os = "linux")))]
+#[cfg(all(not(feature="ipc"),any(target_arch = "wasm32", all(not(feature="headless"),target_os = "linux"))))]
 mod cx_glsl;
 
 #[cfg(all(not(feature="ipc"),any(target_os = "linux", target_os="macos", target_os="windows")))]
 mod cx_desktop;
 
+#[cfg(all(not(feature="ipc"),feature="headless",any(target_os = "linux", target_os="macos", target_os="windows")))]
+mod cx_headless;
+#[cfg(all(not(feature="ipc"),feature="headless",any(target_os = "linux", target_os="macos", target_os="windows")))]
+mod cx_software;
+#[cfg(all(not(feature="ipc"),feature="headless",any(target_os = "linux", target_os="macos", target_os="windows")))]
+mod cx_softsl;
+#[cfg(all(not(feature="ipc"),feature="headless",any(target_os = "linux", target_os="macos", target_os="windows")))]
+mod snapshot;
+
 #[cfg(feature="ipc")]
 mod cx_ipc_child;
 
@@ -86,6 +95,9 @@ mod blit;
 mod text;
 mod events;
 mod menu; 
+mod automation;
+mod accessibility;
+mod bidi;
 
 pub use crate::cx::*;
 pub use crate::quad::*;
@@ -24,6 +24,25 @@ impl Menu {
     pub fn line() -> Menu {
         Menu::Line
     }
+    // how a menu key reads on this platform, an uppercase key means shift
+    pub fn key_label(key: &str, platform_type: &PlatformType) -> String {
+        if key.len() == 0 {
+            return String::new()
+        }
+        let modifier = match platform_type {
+            PlatformType::OSX => "Cmd+",
+            _ => "Ctrl+"
+        };
+        let mut label = modifier.to_string();
+        for c in key.chars() {
+            if c.is_uppercase() {
+                label.push_str("Shift+");
+            }
+            label.extend(c.to_uppercase());
+        }
+        label
+    }
+    
     pub fn item(name: &str, key: &str, enabled:bool, signal: Signal, value: usize) -> Menu {
         Menu::Item {
             name: name.to_string(),
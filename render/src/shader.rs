@@ -302,6 +302,33 @@ pub struct CxShaderMapping {
     pub zbias_uniform_prop: Option<usize>
 }
 
+impl CxShaderMapping {
+    // the slot layout of a shader, which is the same for every backend
+    pub fn from_shader_gen(sg: &ShaderGen) -> CxShaderMapping {
+        let texture_slots = sg.flat_vars( | v | if let ShVarStore::Texture = *v {true} else {false});
+        let geometries = sg.flat_vars( | v | if let ShVarStore::Geometry = *v {true} else {false});
+        let instances = sg.flat_vars( | v | if let ShVarStore::Instance(_) = *v {true} else {false});
+        let uniforms_cx = sg.flat_vars( | v | if let ShVarStore::UniformCx = *v {true} else {false});
+        let uniforms_vw = sg.flat_vars( | v | if let ShVarStore::UniformVw = *v {true} else {false});
+        let uniforms_dr = sg.flat_vars( | v | if let ShVarStore::Uniform(_) = *v {true} else {false});
+        let uniform_props = UniformProps::construct(sg, &uniforms_dr);
+        CxShaderMapping {
+            zbias_uniform_prop: uniform_props.find_zbias_uniform_prop(),
+            instance_props: InstanceProps::construct(sg, &instances),
+            rect_instance_props: RectInstanceProps::construct(sg, &instances),
+            uniform_props: uniform_props,
+            geometry_slots: sg.compute_slot_total(&geometries),
+            instance_slots: sg.compute_slot_total(&instances),
+            instances: instances,
+            geometries: geometries,
+            uniforms_dr: uniforms_dr,
+            uniforms_vw: uniforms_vw,
+            uniforms_cx: uniforms_cx,
+            texture_slots: texture_slots,
+        }
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct CxShader {
     pub name: String,
@@ -0,0 +1,474 @@
+use crate::cx::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+// a test harness for golden image tests. it runs an app on the headless backend with the virtual
+// clock, feeds it a script of events and compares the window against a png next to the test.
+// the images come from the harness' own minimal rasterizer, which fills the instanced quads and
+// text glyphs of the draw lists on the cpu without running any shader, see raster_window.
+// set raster to SnapshotRaster::Shaders to compare the window the headless backend draws by
+// interpreting the real shaders instead.
+// a missing png fails the test, run with MAKEPAD_UPDATE_SNAPSHOTS=1 to write new ones and to
+// accept a changed look
+
+pub type SnapshotEventHandler = Box<dyn FnMut(&mut Cx, &mut Event)>;
+
+// what draws the images a snapshot compares
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapshotRaster {
+    DrawLists,
+    Shaders
+}
+
+pub struct Snapshot {
+    pub cx: Cx,
+    pub raster: SnapshotRaster,
+    pub frame_time: f64,
+    pub finger_start: Vec2,
+    pub event_handler: SnapshotEventHandler,
+}
+
+#[macro_export]
+macro_rules!snapshot_app {
+    ( $ app: ident) => {{
+        let mut cx = Cx::default();
+        let mut app = $ app::proto(&mut cx);
+        let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+        Snapshot::new(cx, Box::new(move | cx, event | {
+            if let Event::Draw = event {
+                app.draw_app(cx);
+                cxafterdraw.after_draw(cx);
+                return
+            }
+            app.handle_app(cx, event);
+        }))
+    }};
+}
+
+// the png of a snapshot in the tests/snapshots directory of the crate the test is in
+#[macro_export]
+macro_rules!snapshot_path {
+    ( $ name: expr) => {
+        format!("{}/tests/snapshots/{}.png", env!("CARGO_MANIFEST_DIR"), $ name)
+    };
+}
+
+impl Snapshot {
+    pub fn new(mut cx: Cx, mut event_handler: SnapshotEventHandler) -> Snapshot {
+        cx.virtual_clock = Some(CxVirtualClock::default());
+        Self::find_font_paths(&mut cx);
+        cx.headless_init(&mut *event_handler);
+        // the window chrome depends on the platform, keep the images the same on every os
+        cx.platform_type = PlatformType::Linux;
+        let mut snapshot = Snapshot {
+            cx,
+            raster: SnapshotRaster::DrawLists,
+            frame_time: AUTOMATION_FRAME_TIME,
+            finger_start: Vec2::zero(),
+            event_handler
+        };
+        snapshot.settle();
+        snapshot
+    }
+
+    // fonts load relative to the working directory, and cargo test runs in the crate directory.
+    // the fallback fonts of the os differ per machine, so the images are drawn without them
+    fn find_font_paths(cx: &mut Cx) {
+        let cwd = std::env::current_dir().unwrap_or(PathBuf::from("."));
+        for cxfont in &mut cx.fonts {
+            if system_fallback_font_paths().contains(&cxfont.path.as_str()) {
+                cxfont.failed = true;
+                continue;
+            }
+            if cxfont.font_loaded.is_some() || Path::new(&cxfont.path).exists() {
+                continue;
+            }
+            if let Some(path) = cwd.ancestors().map( | dir | dir.join(&cxfont.path)).find( | path | path.exists()) {
+                cxfont.path = path.to_string_lossy().to_string();
+            }
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.cx.headless_time_now()
+    }
+
+    pub fn window_id(&self) -> usize {
+        self.cx.platform.windows.first().map( | w | w.window_id).unwrap_or(0)
+    }
+
+    pub fn image(&self) -> SoftImage {
+        match self.raster {
+            SnapshotRaster::DrawLists => self.cx.raster_window(self.window_id()),
+            SnapshotRaster::Shaders => self.cx.headless_window_image(self.window_id()).cloned()
+        }.expect("snapshot app has no window")
+    }
+
+    pub fn access_tree(&self) -> AccessTree {
+        self.cx.access_tree(self.window_id())
+    }
+
+    pub fn process(&mut self, events: Vec<Event>) {
+        self.cx.headless_process_events(events, &mut *self.event_handler);
+    }
+
+    // paints until nothing asks for a redraw, animations and timers only move with advance
+    pub fn settle(&mut self) {
+        for _ in 0..16 {
+            self.process(vec![Event::Paint]);
+            if self.cx.redraw_child_areas.is_empty() && self.cx.redraw_parent_areas.is_empty() {
+                break;
+            }
+        }
+    }
+
+    // steps the clock a frame at a time, firing timers and playing animations
+    pub fn advance(&mut self, seconds: f64) {
+        let end = self.time() + seconds;
+        while end - self.time() > 1e-9 {
+            let frame_time = self.frame_time;
+            let mut events = self.cx.virtual_clock.as_mut().expect("snapshot runs on the virtual clock").step(frame_time, end);
+            events.push(Event::Paint);
+            self.process(events);
+        }
+        self.settle();
+    }
+
+    pub fn event(&mut self, event: Event) {
+        self.process(vec![event]);
+        self.settle();
+    }
+
+    pub fn finger_down(&mut self, x: f32, y: f32, modifiers: KeyModifiers) {
+        self.finger_start = Vec2 {x, y};
+        let event = Event::FingerDown(FingerDownEvent {
+            window_id: self.window_id(),
+            abs: self.finger_start,
+            rel: self.finger_start,
+            modifiers,
+            time: self.time(),
+            ..Default::default()
+        });
+        self.event(event);
+    }
+
+    pub fn finger_move(&mut self, x: f32, y: f32, modifiers: KeyModifiers) {
+        let event = Event::FingerMove(FingerMoveEvent {
+            window_id: self.window_id(),
+            abs: Vec2 {x, y},
+            abs_start: self.finger_start,
+            rel: Vec2 {x, y},
+            rel_start: self.finger_start,
+            modifiers,
+            time: self.time(),
+            ..Default::default()
+        });
+        self.event(event);
+    }
+
+    pub fn finger_up(&mut self, x: f32, y: f32, modifiers: KeyModifiers) {
+        let event = Event::FingerUp(FingerUpEvent {
+            window_id: self.window_id(),
+            abs: Vec2 {x, y},
+            abs_start: self.finger_start,
+            rel: Vec2 {x, y},
+            rel_start: self.finger_start,
+            modifiers,
+            time: self.time(),
+            ..Default::default()
+        });
+        self.event(event);
+    }
+
+    pub fn finger_hover(&mut self, x: f32, y: f32) {
+        let event = Event::FingerHover(FingerHoverEvent {
+            window_id: self.window_id(),
+            abs: Vec2 {x, y},
+            rel: Vec2 {x, y},
+            time: self.time(),
+            ..Default::default()
+        });
+        self.event(event);
+    }
+
+    pub fn click(&mut self, x: f32, y: f32) {
+        self.finger_down(x, y, KeyModifiers::default());
+        self.finger_up(x, y, KeyModifiers::default());
+    }
+
+    pub fn drag(&mut self, from: Vec2, to: Vec2) {
+        self.finger_down(from.x, from.y, KeyModifiers::default());
+        self.finger_move(to.x, to.y, KeyModifiers::default());
+        self.finger_up(to.x, to.y, KeyModifiers::default());
+    }
+
+    pub fn key_down(&mut self, key_code: KeyCode, modifiers: KeyModifiers) {
+        let event = Event::KeyDown(KeyEvent {
+            key_code,
+            is_repeat: false,
+            modifiers,
+            time: self.time()
+        });
+        self.event(event);
+    }
+
+    pub fn key_up(&mut self, key_code: KeyCode, modifiers: KeyModifiers) {
+        let event = Event::KeyUp(KeyEvent {
+            key_code,
+            is_repeat: false,
+            modifiers,
+            time: self.time()
+        });
+        self.event(event);
+    }
+
+    pub fn text_input(&mut self, input: &str) {
+        self.event(Event::TextInput(TextInputEvent {
+            input: input.to_string(),
+            replace_last: false,
+            was_paste: false
+        }));
+    }
+
+    pub fn timer(&mut self, timer_id: u64) {
+        self.event(Event::Timer(TimerEvent {timer_id}));
+    }
+
+    // compares the window against the png at path, allowing each channel to be off by tolerance.
+    // on a mismatch it writes name.actual.png and name.diff.png next to it and panics
+    pub fn assert_snapshot(&self, path: &str, tolerance: u8) {
+        let image = self.image();
+        let path = Path::new(path);
+        if std::env::var("MAKEPAD_UPDATE_SNAPSHOTS").is_ok() {
+            if let Err(err) = image.save_png(path) {
+                panic!("cannot write snapshot {}: {}", path.display(), err);
+            }
+            return
+        }
+        if !path.exists() {
+            let actual_path = path.with_extension("actual.png");
+            let _ = image.save_png(&actual_path);
+            panic!(
+                "snapshot {} is missing, wrote {}, run with MAKEPAD_UPDATE_SNAPSHOTS=1 to accept it",
+                path.display(),
+                actual_path.display()
+            );
+        }
+        let expected = match SoftImage::load_png(path) {
+            Ok(expected) => expected,
+            Err(err) => panic!("cannot read snapshot {}: {}", path.display(), err)
+        };
+        let actual_path = path.with_extension("actual.png");
+        let diff_path = path.with_extension("diff.png");
+        if expected.width != image.width || expected.height != image.height {
+            let _ = image.save_png(&actual_path);
+            panic!(
+                "snapshot {} is {}x{} but the window is {}x{}, wrote {}",
+                path.display(),
+                expected.width,
+                expected.height,
+                image.width,
+                image.height,
+                actual_path.display()
+            );
+        }
+        let (diff, failed) = image.diff(&expected, tolerance);
+        if failed > 0 {
+            let _ = image.save_png(&actual_path);
+            let _ = diff.save_png(&diff_path);
+            panic!(
+                "snapshot {} differs in {} pixels, wrote {} and {}",
+                path.display(),
+                failed,
+                actual_path.display(),
+                diff_path.display()
+            );
+        }
+        let _ = std::fs::remove_file(&actual_path);
+        let _ = std::fs::remove_file(&diff_path);
+    }
+}
+
+impl Cx {
+    // the minimal rasterizer of the snapshots. it walks the draw lists of the main pass of a window
+    // in order and fills the rect of every instance with an x, y, w, h and color, or for text the
+    // glyph from the font atlas, blended like the gpu backends do. as no shader runs, rounded
+    // corners, outlines and gradients come out as flat rects. the font atlas is the one the
+    // headless backend drew
+    pub fn raster_window(&self, window_id: usize) -> Option<SoftImage> {
+        let window = self.platform.windows.iter().find( | w | w.window_id == window_id) ?;
+        let dpi_factor = window.window_geom.dpi_factor;
+        let inner_size = window.window_geom.inner_size;
+        let mut image = SoftImage::new((inner_size.x * dpi_factor) as usize, (inner_size.y * dpi_factor) as usize);
+        let pass_id = self.windows[window_id].main_pass_id ?;
+        if let Some(color_texture) = self.passes[pass_id].color_textures.first() {
+            match color_texture.clear_color {
+                ClearColor::InitWith(color) | ClearColor::ClearWith(color) => image.clear(color)
+            }
+        }
+        if let Some(view_id) = self.passes[pass_id].main_view_id {
+            self.raster_view(view_id, dpi_factor, &mut image);
+        }
+        Some(image)
+    }
+
+    fn raster_view(&self, view_id: usize, dpi_factor: f32, image: &mut SoftImage) {
+        let cxview = &self.views[view_id];
+        let scroll = cxview.get_snapped_scroll();
+        let clip = if cxview.clipped {
+            cxview.rect
+        }
+        else {
+            Rect {x: -50000., y: -50000., w: 100000., h: 100000.}
+        };
+        for draw_call in &cxview.draw_calls[0..cxview.draw_calls_len] {
+            if draw_call.sub_view_id != 0 {
+                self.raster_view(draw_call.sub_view_id, dpi_factor, image);
+                continue;
+            }
+            let sh = &self.shaders[draw_call.shader_id];
+            let slots = sh.mapping.instance_slots;
+            let props = &sh.mapping.rect_instance_props;
+            let (x, y, w, h, color) = match (props.x, props.y, props.w, props.h, raster_instance_prop(sh, "color")) {
+                (Some(x), Some(y), Some(w), Some(h), Some(color)) if slots > 0 => (x, y, w, h, color),
+                _ => continue
+            };
+            let do_scroll = raster_uniform(sh, draw_call, "view_do_scroll", 2).map( | s | Vec2 {x: s[0], y: s[1]}).unwrap_or(Vec2 {x: 1., y: 1.});
+            let shift = Vec2 {x: -scroll.x * do_scroll.x, y: -scroll.y * do_scroll.y};
+            // text is every draw call that samples glyphs from a texture
+            let atlas = match (raster_instance_prop(sh, "font_tc"), draw_call.textures_2d.first()) {
+                (Some(font_tc), Some(texture_id)) => Some((font_tc, &self.textures[*texture_id as usize].platform.image)),
+                _ => None
+            };
+            let curve = raster_uniform(sh, draw_call, "curve", 1).map( | s | s[0]).unwrap_or(1.);
+            let brightness = raster_uniform(sh, draw_call, "brightness", 1).map( | s | s[0]).unwrap_or(1.);
+            for instance in draw_call.instance.chunks_exact(slots) {
+                let c = &instance[color..color + 4];
+                if let Some((font_tc, atlas)) = atlas {
+                    // a glyph grows up from y, with the first texture row at the bottom
+                    let tc = &instance[font_tc..font_tc + 4];
+                    let rect = Rect {x: instance[x] + shift.x, y: instance[y] - instance[h] + shift.y, w: instance[w], h: instance[h]};
+                    raster_rect(image, rect, clip, dpi_factor, | u, v | {
+                        let s = sample_coverage(atlas, tc[0] + (tc[2] - tc[0]) * u, tc[3] + (tc[1] - tc[3]) * v).powf(curve);
+                        [s * c[0] * brightness * c[3], s * c[1] * brightness * c[3], s * c[2] * brightness * c[3], s * c[3]]
+                    });
+                }
+                else {
+                    let rect = Rect {x: instance[x] + shift.x, y: instance[y] + shift.y, w: instance[w], h: instance[h]};
+                    raster_rect(image, rect, clip, dpi_factor, | _, _ | [c[0] * c[3], c[1] * c[3], c[2] * c[3], c[3]]);
+                }
+            }
+        }
+    }
+}
+
+fn raster_instance_prop(sh: &CxShader, name: &str) -> Option<usize> {
+    sh.mapping.instance_props.props.iter().find( | prop | prop.name == name).map( | prop | prop.offset)
+}
+
+fn raster_uniform<'a>(sh: &CxShader, draw_call: &'a DrawCall, name: &str, slots: usize) -> Option<&'a [f32]> {
+    let prop = sh.mapping.uniform_props.props.iter().find( | prop | prop.name == name) ?;
+    draw_call.uniforms.get(prop.offset..prop.offset + slots)
+}
+
+// blends premultiplied color into the pixels with their center inside the clipped rect, the
+// pixel function gets the position in the rect from 0 to 1
+fn raster_rect<F>(image: &mut SoftImage, rect: Rect, clip: Rect, dpi_factor: f32, pixel: F)
+where F: Fn(f32, f32) -> [f32; 4]
+{
+    if rect.w <= 0. || rect.h <= 0. {
+        return
+    }
+    let x1 = rect.x.max(clip.x) * dpi_factor;
+    let y1 = rect.y.max(clip.y) * dpi_factor;
+    let x2 = (rect.x + rect.w).min(clip.x + clip.w) * dpi_factor;
+    let y2 = (rect.y + rect.h).min(clip.y + clip.h) * dpi_factor;
+    let first = | v: f32, max: usize | ((v - 0.5).ceil().max(0.) as usize).min(max);
+    for py in first(y1, image.height)..first(y2, image.height) {
+        for px in first(x1, image.width)..first(x2, image.width) {
+            let u = ((px as f32 + 0.5) / dpi_factor - rect.x) / rect.w;
+            let v = ((py as f32 + 0.5) / dpi_factor - rect.y) / rect.h;
+            let src = pixel(u, v);
+            let offset = py * image.width + px;
+            let dst = unpack_color(image.pixels[offset]);
+            image.pixels[offset] = pack_color([
+                src[0] + dst[0] * (1. - src[3]),
+                src[1] + dst[1] * (1. - src[3]),
+                src[2] + dst[2] * (1. - src[3]),
+                src[3] + dst[3] * (1. - src[3])
+            ]);
+        }
+    }
+}
+
+// the first channel of the atlas, bilinear like sample2d
+fn sample_coverage(atlas: &SoftImage, u: f32, v: f32) -> f32 {
+    if atlas.width == 0 || atlas.height == 0 {
+        return 0.
+    }
+    let x = u * atlas.width as f32 - 0.5;
+    let y = v * atlas.height as f32 - 0.5;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as isize, y0 as isize);
+    let top = atlas.texel(x0, y0)[0] * (1. - fx) + atlas.texel(x0 + 1, y0)[0] * fx;
+    let bottom = atlas.texel(x0, y0 + 1)[0] * (1. - fx) + atlas.texel(x0 + 1, y0 + 1)[0] * fx;
+    top * (1. - fy) + bottom * fy
+}
+
+impl SoftImage {
+    // pixels off by more than tolerance are red in the diff, the rest is a faded grey of self
+    pub fn diff(&self, other: &SoftImage, tolerance: u8) -> (SoftImage, usize) {
+        let mut diff = SoftImage::new(self.width, self.height);
+        let mut failed = 0;
+        for (i, (a, b)) in self.pixels.iter().zip(other.pixels.iter()).enumerate() {
+            let off = (0..4).any( | c | {
+                let ca = ((a >> (c * 8)) & 0xff) as i32;
+                let cb = ((b >> (c * 8)) & 0xff) as i32;
+                (ca - cb).abs() > tolerance as i32
+            });
+            diff.pixels[i] = if off {
+                failed += 1;
+                0xffff0000
+            }
+            else {
+                let grey = (((a >> 16) & 0xff) + ((a >> 8) & 0xff) + (a & 0xff)) / 12;
+                0xff000000 | (grey << 16) | (grey << 8) | grey
+            };
+        }
+        (diff, failed)
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err( | e | e.to_string())?;
+        }
+        let file = File::create(path).map_err( | e | e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err( | e | e.to_string())?;
+        writer.write_image_data(&self.to_rgba()).map_err( | e | e.to_string())
+    }
+
+    pub fn load_png(path: &Path) -> Result<SoftImage, String> {
+        let file = File::open(path).map_err( | e | e.to_string())?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let (info, mut reader) = decoder.read_info().map_err( | e | e.to_string())?;
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).map_err( | e | e.to_string())?;
+        let channels = match info.color_type {
+            png::ColorType::RGBA => 4,
+            png::ColorType::RGB => 3,
+            _ => return Err(format!("unsupported png color type {:?}", info.color_type))
+        };
+        let mut image = SoftImage::new(info.width as usize, info.height as usize);
+        for (i, p) in data.chunks(channels).take(image.pixels.len()).enumerate() {
+            let a = if channels == 4 {p[3] as u32} else {0xff};
+            image.pixels[i] = (a << 24) | ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32;
+        }
+        Ok(image)
+    }
+}
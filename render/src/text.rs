@@ -1,5 +1,6 @@
 
 use crate::cx::*;
+use std::ops::Range;
 
 #[derive(Clone)]
 pub enum Wrapping {
@@ -13,28 +14,56 @@ pub enum Wrapping {
 #[derive(Clone, Copy)]
 pub struct TextStyle {
     pub font: Font,
+    pub fallbacks: FontFallbacks,
     pub font_size: f32,
     pub brightness: f32,
     pub curve: f32,
     pub line_spacing: f32,
     pub top_drop: f32,
     pub height_factor: f32,
+    pub shaping: bool,
 }
 
 impl Default for TextStyle {
     fn default() -> Self {
         TextStyle {
             font: Font::default(),
+            fallbacks: FontFallbacks::default(),
             font_size: 8.0,
             brightness: 1.0,
             curve: 0.7,
             line_spacing: 1.4,
             top_drop: 1.1,
             height_factor: 1.3,
+            shaping: true,
         }
     }
 }
 
+// a glyph as laid out by shaping, with the number of chars it stands for and its advance in ems
+#[derive(Clone, Copy, Debug)]
+pub struct ShapedGlyph {
+    pub font_id: usize,
+    pub glyph_id: usize,
+    pub char_count: usize,
+    pub advance: f32,
+}
+
+// the shaped glyphs of a run of chars with one bidi level
+#[derive(Clone, Debug)]
+pub struct ShapedRun {
+    pub run: Range<usize>,
+    pub level: u8,
+    pub shaped: Vec<ShapedGlyph>,
+}
+
+impl ShapedRun {
+    // the advance of the run in ems
+    pub fn advance(&self) -> f32 {
+        self.shaped.iter().map( | shaped | shaped.advance).sum()
+    }
+}
+
 #[derive(Clone)]
 pub struct Text {
     pub class: ClassId,
@@ -86,6 +115,7 @@ impl Text {
     pub fn instance_font_size()->InstanceFloat{uid!()}
     pub fn instance_marker()->InstanceFloat{uid!()}
     pub fn instance_char_offset()->InstanceFloat{uid!()}
+    pub fn instance_advance()->InstanceFloat{uid!()}
     
     pub fn uniform_zbias()->UniformFloat{uid!()}
     pub fn uniform_brightness()->UniformFloat{uid!()}
@@ -113,6 +143,7 @@ impl Text {
             let font_size: Self::instance_font_size();
             let char_offset:Self::instance_char_offset();
             let marker: Self::instance_marker();
+            let advance: Self::instance_advance();
             
             let tex_coord1: vec2<Varying>;
             let tex_coord2: vec2<Varying>;
@@ -223,44 +254,112 @@ impl Text {
         return aligned
     }
     
-    pub fn add_text<F>(&mut self, cx: &mut Cx, geom_x: f32, geom_y: f32, char_offset: usize, aligned: &mut AlignedInstance, chunk: &[char], mut char_callback: F)
+    pub fn add_text<F>(&mut self, cx: &mut Cx, geom_x: f32, geom_y: f32, char_offset: usize, aligned: &mut AlignedInstance, chunk: &[char], char_callback: F)
     where F: FnMut(char, usize, f32, f32) -> f32
     {
-        let text_style = &self.text_style;
-        let mut geom_x = geom_x;
-        let mut char_offset = char_offset;
-        let font_id = text_style.font.font_id.unwrap();
+        // a chunk is laid out as a left to right paragraph
+        if bidi_has_rtl(chunk) {
+            let levels = bidi_levels(chunk, Some(0));
+            self.add_text_with_levels(cx, geom_x, geom_y, char_offset, aligned, chunk, &levels, char_callback)
+        }
+        else {
+            self.add_text_with_levels(cx, geom_x, geom_y, char_offset, aligned, chunk, &[], char_callback)
+        }
+    }
+    
+    // lays out a chunk in visual order, from the bidi levels of its chars. chars at odd levels run
+    // right to left, and an empty slice of levels lays out all of them left to right. the char
+    // callback still sees the chars in logical order, with the x of the edge they start at and an
+    // advance that is negative for chars that run right to left
+    pub fn add_text_with_levels<F>(&mut self, cx: &mut Cx, geom_x: f32, geom_y: f32, char_offset: usize, aligned: &mut AlignedInstance, chunk: &[char], levels: &[u8], char_callback: F)
+    where F: FnMut(char, usize, f32, f32) -> f32
+    {
+        let runs = if levels.is_empty() {
+            vec![(0..chunk.len(), 0)]
+        }
+        else {
+            let runs = bidi_level_runs(levels);
+            let run_levels: Vec<u8> = runs.iter().map( | run | levels[run.start]).collect();
+            bidi_visual_order(&run_levels).into_iter().map( | index | (runs[index].clone(), run_levels[index])).collect()
+        };
+        let shaped_runs = runs.into_iter().map( | (run, level) | {
+            let shaped = self.shape_chunk(cx, &chunk[run.clone()]);
+            ShapedRun {run, level, shaped}
+        }).collect();
+        self.add_shaped_text(cx, geom_x, geom_y, char_offset, aligned, chunk, shaped_runs, char_callback)
+    }
+    
+    // lays out runs of a chunk that are shaped already, in the order given. callers that measured
+    // the chunk with shape_chunk pass its glyphs on instead of shaping the chunk again
+    pub fn add_shaped_text<F>(&mut self, cx: &mut Cx, geom_x: f32, geom_y: f32, char_offset: usize, aligned: &mut AlignedInstance, chunk: &[char], runs: Vec<ShapedRun>, mut char_callback: F)
+    where F: FnMut(char, usize, f32, f32) -> f32
+    {
+        // place the glyphs of every run, and the chars they stand for
+        let mut placed = Vec::new();
+        let mut char_geoms = vec![None; chunk.len()];
+        let mut x = geom_x;
+        for ShapedRun {run, level, shaped} in runs {
+            let mut shaped_glyphs = Vec::new();
+            let mut start = run.start;
+            for shaped in shaped {
+                shaped_glyphs.push((start, shaped));
+                start += shaped.char_count;
+            }
+            let is_rtl = level & 1 == 1;
+            if is_rtl {
+                shaped_glyphs.reverse();
+            }
+            for (start, shaped) in shaped_glyphs {
+                let font = cx.fonts[shaped.font_id].font_loaded.as_ref().unwrap();
+                if shaped.glyph_id >= font.glyphs.len() {
+                    println!("GLYPHID OUT OF BOUNDS {} {} len is {}", chunk[start] as usize, shaped.glyph_id, font.glyphs.len());
+                    continue;
+                }
+                let advance = shaped.advance * self.text_style.font_size * 96.0 / 72.0 * self.font_scale;
+                // a ligature draws once, the chars it stands for split its advance
+                let char_advance = advance / shaped.char_count as f32;
+                for i in 0..shaped.char_count {
+                    char_geoms[start + i] = Some(if is_rtl {
+                        (x + advance - char_advance * i as f32, -char_advance)
+                    }
+                    else {
+                        (x + char_advance * i as f32, char_advance)
+                    });
+                }
+                placed.push((start, shaped, x, if is_rtl {-advance} else {advance}));
+                x += advance;
+            }
+        }
         
-        let cxfont = &mut cx.fonts[font_id];
+        let mut markers = vec![0.0; chunk.len()];
+        for (i, char_geom) in char_geoms.iter().enumerate() {
+            if let Some((x, w)) = char_geom {
+                markers[i] = char_callback(chunk[i], char_offset + i, *x, *w);
+            }
+        }
         
+        let text_style = &self.text_style;
         let dpi_factor = cx.current_dpi_factor;
         
         //let geom_y = (geom_y * dpi_factor).floor() / dpi_factor;
-        let atlas_page_id = cxfont.get_atlas_page_id(dpi_factor, text_style.font_size);
-        
-        let font = &mut cxfont.font_loaded.as_ref().unwrap();
-        
-        let font_size_logical = text_style.font_size * 96.0 / (72.0 * font.units_per_em);
-        let font_size_pixels = font_size_logical * dpi_factor;
-        
-        let atlas_page = &mut cxfont.atlas_pages[atlas_page_id];
-        
         let instance = {
             let cxview = &mut cx.views[aligned.inst.view_id];
             let draw_call = &mut cxview.draw_calls[aligned.inst.draw_call_id];
             &mut draw_call.instance
         };
         
-        for wc in chunk {
-            let unicode = *wc as usize;
-            let glyph_id = font.char_code_to_glyph_index_map[unicode];
-            if glyph_id >= font.glyphs.len() {
-                println!("GLYPHID OUT OF BOUNDS {} {} len is {}", unicode, glyph_id, font.glyphs.len());
-                continue;
-            }
-            let glyph = &font.glyphs[glyph_id];
+        for (start, shaped, geom_x, advance) in placed {
+            // glyphs can come from a fallback font, and every font has its own atlas pages
+            let font_id = shaped.font_id;
+            let cxfont = &mut cx.fonts[font_id];
+            let atlas_page_id = cxfont.get_atlas_page_id(dpi_factor, text_style.font_size);
+            let font = cxfont.font_loaded.as_ref().unwrap();
+            let font_size_logical = text_style.font_size * 96.0 / (72.0 * font.units_per_em);
+            let font_size_pixels = font_size_logical * dpi_factor;
+            let atlas_page = &mut cxfont.atlas_pages[atlas_page_id];
             
-            let advance = glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale;
+            let glyph_id = shaped.glyph_id;
+            let glyph = &font.glyphs[glyph_id];
             
             // snap width/height to pixel granularity
             let w = ((glyph.bounds.p_max.x - glyph.bounds.p_min.x) * font_size_pixels).ceil() + 1.0;
@@ -287,12 +386,10 @@ impl Text {
                 (subpixel_x_fract * 7.0) as usize
             };
             
-            let tc = if let Some(tc) = &atlas_page.atlas_glyphs[glyph_id][subpixel_id] {
+            let tc = if let Some(tc) = atlas_page.atlas_glyphs[glyph_id][subpixel_id] {
                 tc
             }
-            else {
-                // see if we can fit it
-                // allocate slot
+            else if let Some(tc) = cx.fonts_atlas.alloc_atlas_glyph(w, h) {
                 cx.fonts_atlas.atlas_todo.push(CxFontsAtlasTodo {
                     subpixel_x_fract,
                     subpixel_y_fract,
@@ -301,17 +398,14 @@ impl Text {
                     glyph_id,
                     subpixel_id
                 });
-                
-                atlas_page.atlas_glyphs[glyph_id][subpixel_id] = Some(
-                    cx.fonts_atlas.alloc_atlas_glyph(&cxfont.path, w, h)
-                );
-                
-                atlas_page.atlas_glyphs[glyph_id][subpixel_id].as_ref().unwrap()
+                atlas_page.atlas_glyphs[glyph_id][subpixel_id] = Some(tc);
+                tc
+            }
+            else {
+                // the atlas is full, after this frame it is emptied and the text draws again
+                continue;
             };
             
-            // lets allocate
-            let marker = char_callback(*wc, char_offset, geom_x, advance);
-            
             let data = [
                 tc.tx1,
                 tc.ty1,
@@ -329,18 +423,62 @@ impl Text {
                 geom_x,
                 geom_y,
                 text_style.font_size,
-                char_offset as f32, // char_offset
-                marker, // marker
+                (char_offset + start) as f32, // char_offset
+                markers[start], // marker
+                advance, // advance, negative when the glyph runs right to left
             ];
             instance.extend_from_slice(&data);
             // !TODO make sure a derived shader adds 'empty' values here.
             
-            geom_x += advance;
-            char_offset += 1;
             aligned.inst.instance_count += 1;
         }
     }
     
+    // picks a font for every char and maps runs of chars in the same font to glyphs. with shaping
+    // it substitutes ligatures and kerns pairs of glyphs, without it glyphs from a fallback font
+    // take the advance of the missing glyph, which keeps monospace text on its grid
+    pub fn shape_chunk(&self, cx: &mut Cx, chunk: &[char]) -> Vec<ShapedGlyph> {
+        let text_style = &self.text_style;
+        let shaping = text_style.shaping;
+        let style_font_id = text_style.font.font_id.unwrap();
+        let font_glyphs: Vec<(usize, usize)> = chunk.iter().map( | c | cx.find_font_glyph(text_style, *c)).collect();
+        let mut shaped = Vec::with_capacity(chunk.len());
+        let mut start = 0;
+        while start < font_glyphs.len() {
+            let font_id = font_glyphs[start].0;
+            let end = font_glyphs[start..].iter().position( | (id, _) | *id != font_id).map_or(font_glyphs.len(), | len | start + len);
+            let font = cx.fonts[font_id].font_loaded.as_ref().unwrap();
+            let glyph_ids: Vec<usize> = font_glyphs[start..end].iter().map( | (_, glyph_id) | *glyph_id).collect();
+            let glyphs = if shaping {
+                font.ligatures.substitute(&glyph_ids)
+            }
+            else {
+                glyph_ids.iter().map( | glyph_id | (*glyph_id, 1)).collect()
+            };
+            for (i, (glyph_id, char_count)) in glyphs.iter().enumerate() {
+                let mut advance = font.glyphs.get(*glyph_id).map_or(0.0, | glyph | glyph.horizontal_metrics.advance_width);
+                if shaping {
+                    if let Some((next_glyph_id, _)) = glyphs.get(i + 1) {
+                        advance += font.kerning.get(*glyph_id, *next_glyph_id);
+                    }
+                }
+                let mut advance = advance / font.units_per_em;
+                if !shaping && font_id != style_font_id {
+                    let style_font = cx.fonts[style_font_id].font_loaded.as_ref().unwrap();
+                    advance = style_font.glyphs[0].horizontal_metrics.advance_width / style_font.units_per_em;
+                }
+                shaped.push(ShapedGlyph {
+                    font_id: font_id,
+                    glyph_id: *glyph_id,
+                    char_count: *char_count,
+                    advance: advance
+                });
+            }
+            start = end;
+        }
+        shaped
+    }
+    
     pub fn end_text(&mut self, cx: &mut Cx, aligned: &AlignedInstance)->Area{
         cx.update_aligned_instance_count(aligned);
         aligned.inst.into()
@@ -350,6 +488,7 @@ impl Text {
         let mut aligned = self.begin_text(cx);
         
         let mut chunk = Vec::new();
+        let mut emitted = Vec::new();
         let mut width = 0.0;
         let mut elipct = 0;
         let text_style = &self.text_style;
@@ -357,23 +496,19 @@ impl Text {
         let height_factor = text_style.height_factor;
         let mut iter = text.chars().peekable();
         
-        let font_id = text_style.font.font_id.unwrap();
-        let font_size_logical = text_style.font_size * 96.0 / (72.0 * cx.fonts[font_id].font_loaded.as_ref().unwrap().units_per_em);
+        let font_size_logical = font_size * 96.0 / 72.0;
         
         while let Some(c) = iter.next() {
             let last = iter.peek().is_none();
             
             let mut emit = last;
             
-            let slot = if c < '\u{10000}' {
-                cx.fonts[font_id].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map[c as usize]
-            } else {
-                0
-            };
+            let (font_id, slot) = cx.find_font_glyph(&self.text_style, c);
             
             if slot != 0 {
-                let glyph = &cx.fonts[font_id].font_loaded.as_ref().unwrap().glyphs[slot];
-                width += glyph.horizontal_metrics.advance_width * font_size_logical * self.font_scale;
+                let font = cx.fonts[font_id].font_loaded.as_ref().unwrap();
+                let glyph = &font.glyphs[slot];
+                width += glyph.horizontal_metrics.advance_width / font.units_per_em * font_size_logical * self.font_scale;
                 match self.wrapping {
                     Wrapping::Char => {
                         chunk.push(c);
@@ -409,13 +544,15 @@ impl Text {
             }
             if emit {
                 let height = font_size * height_factor * self.font_scale;
+                // ligatures and kerning change the width of the chunk
+                let shaped = ShapedRun {run: 0..chunk.len(), level: 0, shaped: self.shape_chunk(cx, &chunk)};
                 let geom = cx.walk_turtle(Walk {
-                    width: Width::Fix(width),
+                    width: Width::Fix(shaped.advance() * font_size_logical * self.font_scale),
                     height: Height::Fix(height),
                     margin: Margin::zero()
                 });
                 
-                self.add_text(cx, geom.x, geom.y, 0, &mut aligned, &chunk, | _, _, _, _ | {0.0});
+                emitted.push((chunk.clone(), geom, shaped));
                 width = 0.0;
                 chunk.truncate(0);
                 match self.wrapping {
@@ -427,6 +564,44 @@ impl Text {
                 }
             }
         }
+        
+        // chunks are laid out before they are drawn, because right to left text reorders the
+        // chunks on a row
+        let chars: Vec<char> = emitted.iter().flat_map( | (chunk, _, _) | chunk.iter().cloned()).collect();
+        if !bidi_has_rtl(&chars) {
+            for (chunk, geom, shaped) in emitted {
+                self.add_shaped_text(cx, geom.x, geom.y, 0, &mut aligned, &chunk, vec![shaped], | _, _, _, _ | {0.0});
+            }
+            return self.end_text(cx, &aligned)
+        }
+        let levels = bidi_levels(&chars, None);
+        let mut row_start = 0;
+        let mut row_offset = 0;
+        while row_start < emitted.len() {
+            let (row_x, row_y) = (emitted[row_start].1.x, emitted[row_start].1.y);
+            let row_end = emitted[row_start..].iter().position( | (_, geom, _) | geom.y != row_y).map_or(emitted.len(), | len | row_start + len);
+            // split the chunks into runs of one level, and draw them in visual order
+            let mut runs = Vec::new();
+            let mut offset = row_offset;
+            for (chunk, _, _) in &emitted[row_start..row_end] {
+                for run in bidi_level_runs(&levels[offset..offset + chunk.len()]) {
+                    runs.push(offset + run.start..offset + run.end);
+                }
+                offset += chunk.len();
+            }
+            let run_levels: Vec<u8> = runs.iter().map( | run | levels[run.start]).collect();
+            let mut x = row_x;
+            for index in bidi_visual_order(&run_levels) {
+                // runs of one level are shaped once, for their width and to draw them
+                let run = runs[index].clone();
+                let shaped = ShapedRun {run: 0..run.len(), level: run_levels[index], shaped: self.shape_chunk(cx, &chars[run.clone()])};
+                let advance = shaped.advance();
+                self.add_shaped_text(cx, x, row_y, 0, &mut aligned, &chars[run], vec![shaped], | _, _, _, _ | {0.0});
+                x += advance * font_size_logical * self.font_scale;
+            }
+            row_start = row_end;
+            row_offset = offset;
+        }
         self.end_text(cx, &aligned)
     }
     
@@ -436,41 +611,34 @@ impl Text {
         let spos = Vec2 {x: pos.x + scroll_pos.x, y: pos.y + scroll_pos.y};
         let x_o = area.get_instance_offset(cx, Self::instance_base_x().instance_type()).unwrap();
         let y_o = area.get_instance_offset(cx, Self::instance_base_y().instance_type()).unwrap();
-        let w_o = area.get_instance_offset(cx, Self::instance_w().instance_type()).unwrap();
+        let advance_o = area.get_instance_offset(cx, Self::instance_advance().instance_type()).unwrap();
         let font_size_o = area.get_instance_offset(cx, Self::instance_font_size().instance_type()).unwrap();
         let char_offset_o = area.get_instance_offset(cx, Self::instance_char_offset().instance_type()).unwrap();
         let read = area.get_read_ref(cx);
         let text_style = &self.text_style;
         let line_spacing = text_style.line_spacing;
-        let mut index = 0;
         if let Some(read) = read {
-            while index < read.count {
-                let y = read.buffer[read.offset + y_o + index * read.slots];
-                let font_size = read.buffer[read.offset + font_size_o + index * read.slots];
-                if y + font_size * line_spacing > spos.y { // alright lets find our next x
-                    while index < read.count {
-                        let x = read.buffer[read.offset + x_o + index * read.slots];
-                        let y = read.buffer[read.offset + y_o + index * read.slots];
-                        //let font_size = read.buffer[read.offset + font_size_o + index* read.slots];
-                        let w = read.buffer[read.offset + w_o + index * read.slots];
-                        if x > spos.x + w * 0.5 || y > spos.y {
-                            let prev_index = if index == 0 {0}else {index - 1};
-                            let prev_x = read.buffer[read.offset + x_o + prev_index * read.slots];
-                            let prev_w = read.buffer[read.offset + w_o + index * read.slots];
-                            if index < read.count - 1 && prev_x > spos.x + prev_w { // fix newline jump-back
-                                return read.buffer[read.offset + char_offset_o + index * read.slots] as usize;
-                            }
-                            return read.buffer[read.offset + char_offset_o + prev_index * read.slots] as usize;
-                        }
-                        index += 1;
-                    }
-                }
-                index += 1;
-            }
             if read.count == 0 {
                 return 0
             }
-            return read.buffer[read.offset + char_offset_o + (read.count - 1) * read.slots] as usize;
+            let get = | index: usize, o: usize | read.buffer[read.offset + o + index * read.slots];
+            // the row is the first one that reaches below the position, or else the last one
+            let mut index = (0..read.count).find( | index | get(*index, y_o) + get(*index, font_size_o) * line_spacing > spos.y).unwrap_or(read.count - 1);
+            let row_y = get(index, y_o);
+            // the offset of a char is where the cursor goes before it, which is its right edge
+            // when it runs right to left. the closest of those edges on the row wins
+            let mut closest: Option<(f32, usize)> = None;
+            while index < read.count && get(index, y_o) == row_y {
+                let x = get(index, x_o);
+                let advance = get(index, advance_o);
+                let edge = if advance < 0.0 {x - advance} else {x};
+                let distance = (edge - spos.x).abs();
+                if closest.map_or(true, | (closest_distance, _) | distance < closest_distance) {
+                    closest = Some((distance, get(index, char_offset_o) as usize));
+                }
+                index += 1;
+            }
+            return closest.unwrap().1
         }
         return 0
     }
@@ -478,7 +646,7 @@ impl Text {
     pub fn get_monospace_base(&self, cx: &Cx) -> Vec2 {
         let font_id = self.text_style.font.font_id.unwrap();
         let font = cx.fonts[font_id].font_loaded.as_ref().unwrap();
-        let slot = font.char_code_to_glyph_index_map[33];
+        let slot = font.char_code_to_glyph_index_map.get(33);
         let glyph = &font.glyphs[slot];
         
         //let font_size = if let Some(font_size) = font_size{font_size}else{self.font_size};
@@ -144,6 +144,8 @@ impl View{
         // update drawlist ids
         cxview.redraw_id = cx.redraw_id;
         cxview.draw_calls_len = 0;
+        cxview.access_nodes.truncate(0);
+        cxview.access_stack.truncate(0);
         
         cx.view_stack.push(view_id);
         
@@ -385,7 +387,9 @@ pub struct CxView {
     pub unsnapped_scroll: Vec2,
     pub platform: CxPlatformView,
     pub rect: Rect,
-    pub clipped: bool
+    pub clipped: bool,
+    pub access_nodes: Vec<CxAccessNode>,
+    pub access_stack: Vec<usize>
 }
 
 impl CxView {
@@ -413,10 +417,10 @@ impl CxView {
         }))
     }
     
-    //pub fn get_scroll_pos(&self) -> Vec2 {
-    //    return self.unsnapped_scroll
-        //return Vec2 {x: self.uniforms[VW_UNI_SCROLL + 0], y: self.uniforms[VW_UNI_SCROLL + 1]}
-    //}
+    // the snapped scroll the shaders see, the areas report the unsnapped one
+    pub fn get_snapped_scroll(&self) -> Vec2 {
+        Vec2 {x: self.uniforms[VW_UNI_SCROLL], y: self.uniforms[VW_UNI_SCROLL + 1]}
+    }
     
     pub fn uniform_view_transform(&mut self, v: &Mat4) {
         //dump in uniforms
@@ -0,0 +1,107 @@
+use std::ops::Range;
+
+/// A sparse map from character codes to glyph indices.
+///
+/// The map is stored as a sorted list of runs of consecutive character codes, so that fonts which
+/// cover the astral planes, or map large ranges to a single glyph, stay small. Character codes
+/// that are not in the map are mapped to the missing glyph, which has index 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CharCodeToGlyphIndexMap {
+    runs: Vec<Run>,
+}
+
+impl CharCodeToGlyphIndexMap {
+    /// Creates a new empty map.
+    pub fn new() -> CharCodeToGlyphIndexMap {
+        CharCodeToGlyphIndexMap::default()
+    }
+
+    /// Returns the index of the glyph for `char_code`, or 0 if `self` has no glyph for it.
+    pub fn get(&self, char_code: u32) -> usize {
+        let index = self.runs.partition_point(|run| run.char_codes.end <= char_code);
+        match self.runs.get(index) {
+            Some(run) if run.char_codes.start <= char_code => run.glyph_index(char_code),
+            _ => 0,
+        }
+    }
+
+    /// Returns an iterator over the character codes in `self` and their glyph indices, in order
+    /// of character code.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (u32, usize)> + 'a {
+        self.runs.iter().flat_map(|run| {
+            run.char_codes
+                .clone()
+                .map(move |char_code| (char_code, run.glyph_index(char_code)))
+        })
+    }
+
+    /// Maps `char_code` to `glyph_index`.
+    pub fn insert(&mut self, char_code: u32, glyph_index: usize) {
+        self.insert_run(char_code..char_code + 1, glyph_index, 1);
+    }
+
+    /// Maps the character codes in `char_codes` to consecutive glyph indices, starting with
+    /// `glyph_index`.
+    pub fn insert_sequential(&mut self, char_codes: Range<u32>, glyph_index: usize) {
+        self.insert_run(char_codes, glyph_index, 1);
+    }
+
+    /// Maps all the character codes in `char_codes` to `glyph_index`.
+    pub fn insert_constant(&mut self, char_codes: Range<u32>, glyph_index: usize) {
+        self.insert_run(char_codes, glyph_index, 0);
+    }
+
+    fn insert_run(&mut self, mut char_codes: Range<u32>, mut glyph_index: usize, step: usize) {
+        // the missing glyph is implied, so it is never stored
+        if glyph_index == 0 {
+            if step == 0 {
+                return;
+            }
+            char_codes.start += 1;
+            glyph_index = 1;
+        }
+        if char_codes.start >= char_codes.end {
+            return;
+        }
+        if let Some(last) = self.runs.last_mut() {
+            if last.char_codes.end == char_codes.start {
+                // a run of one code can still become either kind of run
+                let previous = last.glyph_index(char_codes.start - 1);
+                let joined_step = if last.char_codes.len() == 1 {
+                    glyph_index.wrapping_sub(previous)
+                } else {
+                    last.step
+                };
+                if joined_step <= 1
+                    && previous + joined_step == glyph_index
+                    && (char_codes.len() == 1 || step == joined_step)
+                {
+                    last.step = joined_step;
+                    last.char_codes.end = char_codes.end;
+                    return;
+                }
+            }
+        }
+        let run = Run {
+            char_codes,
+            glyph_index,
+            step,
+        };
+        // runs almost always arrive in order, but a font is allowed to be sloppy
+        let index = self.runs.partition_point(|other| other.char_codes.start < run.char_codes.start);
+        self.runs.insert(index, run);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Run {
+    char_codes: Range<u32>,
+    glyph_index: usize,
+    step: usize,
+}
+
+impl Run {
+    fn glyph_index(&self, char_code: u32) -> usize {
+        self.glyph_index + (char_code - self.char_codes.start) as usize * self.step
+    }
+}
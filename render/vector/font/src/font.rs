@@ -1,4 +1,4 @@
-use crate::Glyph;
+use crate::{CharCodeToGlyphIndexMap, Glyph, Kerning, Ligatures};
 use geometry::Rectangle;
 
 /// A font.
@@ -9,6 +9,8 @@ pub struct Font {
     pub descender: f32,
     pub line_gap: f32,
     pub bounds: Rectangle,
-    pub char_code_to_glyph_index_map: Vec<usize>,
+    pub char_code_to_glyph_index_map: CharCodeToGlyphIndexMap,
     pub glyphs: Vec<Glyph>,
+    pub kerning: Kerning,
+    pub ligatures: Ligatures,
 }
\ No newline at end of file
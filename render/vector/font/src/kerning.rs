@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+/// Adjustments to the advance width of a glyph that depend on the glyph that follows it.
+///
+/// The adjustments are grouped into lookups, which are all applied, and add up. Within a lookup,
+/// only the first subtable that applies to a pair of glyphs is used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Kerning {
+    pub lookups: Vec<Vec<KerningSubtable>>,
+}
+
+impl Kerning {
+    /// Creates a new kerning without any adjustments.
+    pub fn new() -> Kerning {
+        Kerning::default()
+    }
+
+    /// Returns `true` if `self` does not adjust any pair of glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.lookups.iter().all(|subtables| subtables.is_empty())
+    }
+
+    /// Returns the adjustment to the advance width of the glyph with index `left` when it is
+    /// followed by the glyph with index `right`, in font units.
+    pub fn get(&self, left: usize, right: usize) -> f32 {
+        self.lookups
+            .iter()
+            .filter_map(|subtables| {
+                subtables
+                    .iter()
+                    .find_map(|subtable| subtable.get(left, right))
+            })
+            .fold(0.0, |sum, adjustment| sum + adjustment)
+    }
+}
+
+/// A set of kerning adjustments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KerningSubtable {
+    /// Adjustments for individual pairs of glyphs. The subtable applies only to the pairs it
+    /// contains.
+    Pairs(HashMap<(usize, usize), f32>),
+    /// Adjustments for pairs of classes of glyphs. The subtable applies to every pair of which
+    /// the left glyph is in `left_classes`. Right glyphs that are not in `right_classes` are in
+    /// class 0.
+    Classes {
+        left_classes: HashMap<usize, usize>,
+        right_classes: HashMap<usize, usize>,
+        right_class_count: usize,
+        adjustments: Vec<f32>,
+    },
+}
+
+impl KerningSubtable {
+    /// Returns the adjustment for the pair of glyphs `left` and `right`, or `None` if `self` does
+    /// not apply to the pair.
+    pub fn get(&self, left: usize, right: usize) -> Option<f32> {
+        match self {
+            KerningSubtable::Pairs(pairs) => pairs.get(&(left, right)).cloned(),
+            KerningSubtable::Classes {
+                left_classes,
+                right_classes,
+                right_class_count,
+                adjustments,
+            } => {
+                let left_class = *left_classes.get(&left)?;
+                let right_class = right_classes.get(&right).cloned().unwrap_or(0);
+                if right_class >= *right_class_count {
+                    return None;
+                }
+                adjustments
+                    .get(left_class * right_class_count + right_class)
+                    .cloned()
+            }
+        }
+    }
+}
@@ -1,12 +1,18 @@
 pub mod outline;
 
+mod char_code_to_glyph_index_map;
 mod font;
 mod glyph;
 mod horizontal_metrics;
+mod kerning;
+mod ligatures;
 mod outline_point;
 
+pub use self::char_code_to_glyph_index_map::CharCodeToGlyphIndexMap;
 pub use self::font::Font;
 pub use self::glyph::Glyph;
 pub use self::horizontal_metrics::HorizontalMetrics;
+pub use self::kerning::{Kerning, KerningSubtable};
+pub use self::ligatures::{Ligature, Ligatures};
 pub use self::outline::Outline;
 pub use self::outline_point::OutlinePoint;
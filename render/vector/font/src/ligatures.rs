@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+/// Substitutions that replace a sequence of glyphs with a single ligature glyph.
+///
+/// The substitutions are grouped into lookups, which are applied one after the other, each to
+/// the output of the previous one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ligatures {
+    pub lookups: Vec<HashMap<usize, Vec<Ligature>>>,
+}
+
+impl Ligatures {
+    /// Creates a new set of ligatures without any substitutions.
+    pub fn new() -> Ligatures {
+        Ligatures::default()
+    }
+
+    /// Returns `true` if `self` does not substitute any sequence of glyphs.
+    pub fn is_empty(&self) -> bool {
+        self.lookups.iter().all(|ligatures| ligatures.is_empty())
+    }
+
+    /// Substitutes the ligatures in `glyph_indices`.
+    ///
+    /// Returns the resulting glyph indices, each paired with the number of glyphs in
+    /// `glyph_indices` it replaces.
+    pub fn substitute(&self, glyph_indices: &[usize]) -> Vec<(usize, usize)> {
+        let mut glyphs: Vec<(usize, usize)> =
+            glyph_indices.iter().map(|&index| (index, 1)).collect();
+        for ligatures in &self.lookups {
+            let mut substituted = Vec::with_capacity(glyphs.len());
+            let mut index = 0;
+            while index < glyphs.len() {
+                let (glyph_index, count) = glyphs[index];
+                // the first ligature that matches wins, so fonts list the longest ones first
+                let ligature = ligatures.get(&glyph_index).and_then(|ligatures| {
+                    ligatures.iter().find(|ligature| {
+                        let rest = &glyphs[index + 1..];
+                        rest.len() >= ligature.components.len()
+                            && ligature
+                                .components
+                                .iter()
+                                .zip(rest)
+                                .all(|(&component, &(glyph_index, _))| component == glyph_index)
+                    })
+                });
+                match ligature {
+                    Some(ligature) => {
+                        let end = index + 1 + ligature.components.len();
+                        let count = glyphs[index..end].iter().map(|&(_, count)| count).sum();
+                        substituted.push((ligature.glyph_index, count));
+                        index = end;
+                    }
+                    None => {
+                        substituted.push((glyph_index, count));
+                        index += 1;
+                    }
+                }
+            }
+            glyphs = substituted;
+        }
+        glyphs
+    }
+}
+
+/// A ligature glyph, and the glyphs that follow the first glyph of the sequence it replaces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ligature {
+    pub components: Vec<usize>,
+    pub glyph_index: usize,
+}
@@ -0,0 +1,210 @@
+use crate::{CubicSegment, Point, Transform, Transformation, Vector};
+use internal_iter::InternalIterator;
+use std::f32::consts::PI;
+
+/// An elliptical arc segment in 2-dimensional Euclidian space, in the endpoint parameterization
+/// used by SVG.
+///
+/// The arc runs from `p0` to `p1` along an ellipse with radii `radii`, the x-axis of which is
+/// rotated by `x_axis_rotation` radians. Of the four arcs that satisfy these constraints,
+/// `large_arc` selects one that spans more than 180 degrees, and `sweep` one that runs in the
+/// direction of increasing angles. If the radii are too small for an ellipse to pass through both
+/// endpoints, they are scaled up until one does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArcSegment {
+    pub p0: Point,
+    pub radii: Vector,
+    pub x_axis_rotation: f32,
+    pub large_arc: bool,
+    pub sweep: bool,
+    pub p1: Point,
+}
+
+impl ArcSegment {
+    /// Creates a new elliptical arc segment with the given endpoints and ellipse.
+    pub fn new(
+        p0: Point,
+        radii: Vector,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        p1: Point,
+    ) -> ArcSegment {
+        ArcSegment {
+            p0,
+            radii,
+            x_axis_rotation,
+            large_arc,
+            sweep,
+            p1,
+        }
+    }
+
+    /// Returns an iterator over cubic Bezier curve segments that approximate `self`, each of which
+    /// spans at most 90 degrees of the ellipse.
+    ///
+    /// An arc with a zero radius is a line segment, and an arc with coincident endpoints is
+    /// omitted entirely.
+    pub fn to_cubics(self) -> ToCubics {
+        ToCubics { segment: self }
+    }
+
+    /// Returns an iterator over the points of a polyline that approximates `self` with tolerance
+    /// `epsilon`, *excluding* the first point.
+    pub fn linearize(self, epsilon: f32) -> Linearize {
+        Linearize {
+            segment: self,
+            epsilon,
+        }
+    }
+}
+
+impl Transform for ArcSegment {
+    fn transform<T>(self, t: &T) -> ArcSegment
+    where
+        T: Transformation,
+    {
+        // The image of an ellipse under an affine transformation is another ellipse. Its axes
+        // follow from the singular value decomposition of the linear part of the transformation,
+        // applied to the axes of the original ellipse.
+        let e0 = t.transform_vector(Vector::new(1.0, 0.0));
+        let e1 = t.transform_vector(Vector::new(0.0, 1.0));
+        let (sin, cos) = self.x_axis_rotation.sin_cos();
+        let a = (e0 * cos + e1 * sin) * self.radii.x.abs();
+        let b = (e1 * cos - e0 * sin) * self.radii.y.abs();
+        let e = (a.x + b.y) / 2.0;
+        let f = (a.x - b.y) / 2.0;
+        let g = (a.y + b.x) / 2.0;
+        let h = (a.y - b.x) / 2.0;
+        let q = e.hypot(h);
+        let r = f.hypot(g);
+        ArcSegment::new(
+            self.p0.transform(t),
+            Vector::new(q + r, (q - r).abs()),
+            (h.atan2(e) + g.atan2(f)) / 2.0,
+            self.large_arc,
+            // A transformation that flips the orientation of the plane reverses the direction of
+            // the arc.
+            self.sweep != (e0.cross(e1) < 0.0),
+            self.p1.transform(t),
+        )
+    }
+
+    fn transform_mut<T>(&mut self, t: &T)
+    where
+        T: Transformation,
+    {
+        *self = self.transform(t);
+    }
+}
+
+/// An iterator over cubic Bezier curve segments that approximate an elliptical arc segment.
+#[derive(Clone, Copy)]
+pub struct ToCubics {
+    segment: ArcSegment,
+}
+
+impl InternalIterator for ToCubics {
+    type Item = CubicSegment;
+
+    fn for_each<F>(self, f: &mut F) -> bool
+    where
+        F: FnMut(CubicSegment) -> bool,
+    {
+        let ArcSegment {
+            p0,
+            radii,
+            x_axis_rotation,
+            large_arc,
+            sweep,
+            p1,
+        } = self.segment;
+        if p0 == p1 {
+            return true;
+        }
+        let mut rx = radii.x.abs();
+        let mut ry = radii.y.abs();
+        if rx == 0.0 || ry == 0.0 {
+            return f(CubicSegment::new(p0, p0, p1, p1));
+        }
+
+        // Convert the arc to its center parameterization, as described in the implementation
+        // notes of the SVG specification. All vectors below are in the coordinate system of the
+        // ellipse, and are rotated back into place when points are computed.
+        let (sin, cos) = x_axis_rotation.sin_cos();
+        let rotate = |v: Vector| Vector::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y);
+        let d = (p0 - p1) / 2.0;
+        let v = Vector::new(cos * d.x + sin * d.y, cos * d.y - sin * d.x);
+        let lambda = (v.x / rx).powi(2) + (v.y / ry).powi(2);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let numerator = rx * rx * ry * ry - rx * rx * v.y * v.y - ry * ry * v.x * v.x;
+        let denominator = rx * rx * v.y * v.y + ry * ry * v.x * v.x;
+        let mut k = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            k = -k;
+        }
+        let c = Vector::new(k * rx * v.y / ry, -k * ry * v.x / rx);
+        let center = p0.lerp(p1, 0.5) + rotate(c);
+        let start_angle = ((v.y - c.y) / ry).atan2((v.x - c.x) / rx);
+        let end_angle = ((-v.y - c.y) / ry).atan2((-v.x - c.x) / rx);
+        let mut sweep_angle = end_angle - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        // Approximate each piece of the arc with a cubic Bezier curve segment, the control points
+        // of which lie on the tangents at its endpoints.
+        let count = (sweep_angle.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let delta = sweep_angle / count as f32;
+        let k = 4.0 / 3.0 * (delta / 4.0).tan();
+        let point = |angle: f32| center + rotate(Vector::new(rx * angle.cos(), ry * angle.sin()));
+        let tangent = |angle: f32| rotate(Vector::new(-rx * angle.sin(), ry * angle.cos())) * k;
+        let mut q0 = p0;
+        for index in 0..count {
+            let angle_0 = start_angle + delta * index as f32;
+            let angle_1 = angle_0 + delta;
+            let q3 = if index + 1 == count {
+                p1
+            } else {
+                point(angle_1)
+            };
+            if !f(CubicSegment::new(
+                q0,
+                q0 + tangent(angle_0),
+                q3 - tangent(angle_1),
+                q3,
+            )) {
+                return false;
+            }
+            q0 = q3;
+        }
+        true
+    }
+}
+
+/// An iterator over the points of a polyline that approximates `self` with tolerance `epsilon`,
+/// *excluding* the first point.
+#[derive(Clone, Copy)]
+pub struct Linearize {
+    segment: ArcSegment,
+    epsilon: f32,
+}
+
+impl InternalIterator for Linearize {
+    type Item = Point;
+
+    fn for_each<F>(self, f: &mut F) -> bool
+    where
+        F: FnMut(Point) -> bool,
+    {
+        let epsilon = self.epsilon;
+        self.segment
+            .to_cubics()
+            .for_each(&mut |segment| segment.linearize(epsilon).for_each(f))
+    }
+}
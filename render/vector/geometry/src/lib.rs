@@ -1,3 +1,5 @@
+pub mod arc_segment;
+pub mod cubic_segment;
 pub mod quadratic_segment;
 
 mod affine_transformation;
@@ -12,6 +14,8 @@ mod trapezoid;
 mod vector;
 
 pub use self::affine_transformation::AffineTransformation;
+pub use self::arc_segment::ArcSegment;
+pub use self::cubic_segment::CubicSegment;
 pub use self::f32_ext::F32Ext;
 pub use self::line_segment::LineSegment;
 pub use self::linear_transformation::LinearTransformation;
@@ -1,7 +1,17 @@
-use crate::LinePathCommand;
+use crate::{LinePathCommand, Stroke, StrokeStyle};
 use internal_iter::InternalIterator;
 
 /// An extension trait for iterators over line path commands.
-pub trait LinePathIterator: InternalIterator<Item = LinePathCommand> {}
+pub trait LinePathIterator: InternalIterator<Item = LinePathCommand> {
+    /// Returns an iterator over line path commands for the closed contours that fill the outline
+    /// of a stroke along `self` with style `style`. Round joins and caps are approximated with
+    /// tolerance `epsilon`.
+    fn stroke(self, style: StrokeStyle, epsilon: f32) -> Stroke<Self>
+    where
+        Self: Sized,
+    {
+        Stroke::new(self, style, epsilon)
+    }
+}
 
 impl<I> LinePathIterator for I where I: InternalIterator<Item = LinePathCommand> {}
@@ -1,5 +1,5 @@
 use crate::PathCommand;
-use geometry::{Point, Transform, Transformation};
+use geometry::{ArcSegment, Point, Transform, Transformation, Vector};
 use internal_iter::{
     ExtendFromInternalIterator, FromInternalIterator, InternalIterator, IntoInternalIterator,
 };
@@ -12,6 +12,7 @@ use std::slice::Iter;
 pub struct Path {
     verbs: Vec<Verb>,
     points: Vec<Point>,
+    arcs: Vec<Arc>,
 }
 
 impl Path {
@@ -30,6 +31,7 @@ impl Path {
         Commands {
             verbs: self.verbs.iter().cloned(),
             points: self.points.iter().cloned(),
+            arcs: self.arcs.iter().cloned(),
         }
     }
 
@@ -57,6 +59,34 @@ impl Path {
         self.points.push(p);
     }
 
+    /// Adds a cubic Bezier curve segment to the current contour, starting at the current point.
+    pub fn cubic_to(&mut self, p1: Point, p2: Point, p: Point) {
+        self.verbs.push(Verb::CubicTo);
+        self.points.push(p1);
+        self.points.push(p2);
+        self.points.push(p);
+    }
+
+    /// Adds an elliptical arc segment to the current contour, starting at the current point. See
+    /// `ArcSegment` for the meaning of the arguments.
+    pub fn arc_to(
+        &mut self,
+        radii: Vector,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        p: Point,
+    ) {
+        self.verbs.push(Verb::ArcTo);
+        self.arcs.push(Arc {
+            radii,
+            x_axis_rotation,
+            large_arc,
+            sweep,
+        });
+        self.points.push(p);
+    }
+
     /// Closes the current contour.
     pub fn close(&mut self) {
         self.verbs.push(Verb::Close);
@@ -66,6 +96,7 @@ impl Path {
     pub fn clear(&mut self) {
         self.verbs.clear();
         self.points.clear();
+        self.arcs.clear();
     }
 }
 
@@ -79,6 +110,10 @@ impl ExtendFromInternalIterator<PathCommand> for Path {
                 PathCommand::MoveTo(p) => self.move_to(p),
                 PathCommand::LineTo(p) => self.line_to(p),
                 PathCommand::QuadraticTo(p1, p) => self.quadratic_to(p1, p),
+                PathCommand::CubicTo(p1, p2, p) => self.cubic_to(p1, p2, p),
+                PathCommand::ArcTo(radii, x_axis_rotation, large_arc, sweep, p) => {
+                    self.arc_to(radii, x_axis_rotation, large_arc, sweep, p)
+                }
                 PathCommand::Close => self.close(),
             }
             true
@@ -113,6 +148,21 @@ impl Transform for Path {
         for point in self.points_mut() {
             point.transform_mut(t);
         }
+        for arc in &mut self.arcs {
+            // The shape of the transformed arc does not depend on its endpoints.
+            let segment = ArcSegment::new(
+                Point::origin(),
+                arc.radii,
+                arc.x_axis_rotation,
+                arc.large_arc,
+                arc.sweep,
+                Point::origin(),
+            )
+            .transform(t);
+            arc.radii = segment.radii;
+            arc.x_axis_rotation = segment.x_axis_rotation;
+            arc.sweep = segment.sweep;
+        }
     }
 }
 
@@ -121,6 +171,7 @@ impl Transform for Path {
 pub struct Commands<'a> {
     verbs: Cloned<Iter<'a, Verb>>,
     points: Cloned<Iter<'a, Point>>,
+    arcs: Cloned<Iter<'a, Arc>>,
 }
 
 impl<'a> Iterator for Commands<'a> {
@@ -133,6 +184,21 @@ impl<'a> Iterator for Commands<'a> {
             Verb::QuadraticTo => {
                 PathCommand::QuadraticTo(self.points.next().unwrap(), self.points.next().unwrap())
             }
+            Verb::CubicTo => PathCommand::CubicTo(
+                self.points.next().unwrap(),
+                self.points.next().unwrap(),
+                self.points.next().unwrap(),
+            ),
+            Verb::ArcTo => {
+                let arc = self.arcs.next().unwrap();
+                PathCommand::ArcTo(
+                    arc.radii,
+                    arc.x_axis_rotation,
+                    arc.large_arc,
+                    arc.sweep,
+                    self.points.next().unwrap(),
+                )
+            }
             Verb::Close => PathCommand::Close,
         })
     }
@@ -143,5 +209,15 @@ enum Verb {
     MoveTo,
     LineTo,
     QuadraticTo,
+    CubicTo,
+    ArcTo,
     Close,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Arc {
+    radii: Vector,
+    x_axis_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+}
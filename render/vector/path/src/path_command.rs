@@ -1,4 +1,4 @@
-use geometry::{Point, Transform, Transformation};
+use geometry::{ArcSegment, Point, Transform, Transformation, Vector};
 
 /// A command in a path
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -6,6 +6,10 @@ pub enum PathCommand {
     MoveTo(Point),
     LineTo(Point),
     QuadraticTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    /// An elliptical arc with the given radii, x-axis rotation in radians, large arc flag, sweep
+    /// flag and endpoint. See `ArcSegment` for their meaning.
+    ArcTo(Vector, f32, bool, bool, Point),
     Close,
 }
 
@@ -20,6 +24,22 @@ impl Transform for PathCommand {
             PathCommand::QuadraticTo(p1, p) => {
                 PathCommand::QuadraticTo(p1.transform(t), p.transform(t))
             }
+            PathCommand::CubicTo(p1, p2, p) => {
+                PathCommand::CubicTo(p1.transform(t), p2.transform(t), p.transform(t))
+            }
+            PathCommand::ArcTo(radii, x_axis_rotation, large_arc, sweep, p) => {
+                // The shape of the transformed arc does not depend on its start point.
+                let arc =
+                    ArcSegment::new(Point::origin(), radii, x_axis_rotation, large_arc, sweep, p)
+                        .transform(t);
+                PathCommand::ArcTo(
+                    arc.radii,
+                    arc.x_axis_rotation,
+                    arc.large_arc,
+                    arc.sweep,
+                    arc.p1,
+                )
+            }
             PathCommand::Close => PathCommand::Close,
         }
     }
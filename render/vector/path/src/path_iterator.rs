@@ -1,5 +1,5 @@
-use crate::{LinePathCommand, PathCommand};
-use geometry::QuadraticSegment;
+use crate::{LinePathCommand, LinePathIterator, PathCommand, Stroke, StrokeStyle};
+use geometry::{ArcSegment, CubicSegment, QuadraticSegment};
 use internal_iter::InternalIterator;
 
 /// An extension trait for iterators over path commands.
@@ -15,6 +15,15 @@ pub trait PathIterator: InternalIterator<Item = PathCommand> {
             epsilon,
         }
     }
+
+    /// Returns an iterator over line path commands for the closed contours that fill the outline
+    /// of a stroke along `self` with style `style`, approximated with tolerance `epsilon`.
+    fn stroke(self, style: StrokeStyle, epsilon: f32) -> Stroke<Linearize<Self>>
+    where
+        Self: Sized,
+    {
+        self.linearize(epsilon).stroke(style, epsilon)
+    }
 }
 
 impl<I> PathIterator for I where I: InternalIterator<Item = PathCommand> {}
@@ -58,6 +67,27 @@ where
                             f(LinePathCommand::LineTo(p))
                         })
                 }
+                PathCommand::CubicTo(p1, p2, p) => {
+                    CubicSegment::new(current_point.unwrap(), p1, p2, p)
+                        .linearize(epsilon)
+                        .for_each(&mut |p| {
+                            current_point = Some(p);
+                            f(LinePathCommand::LineTo(p))
+                        })
+                }
+                PathCommand::ArcTo(radii, x_axis_rotation, large_arc, sweep, p) => ArcSegment::new(
+                    current_point.unwrap(),
+                    radii,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    p,
+                )
+                .linearize(epsilon)
+                .for_each(&mut |p| {
+                    current_point = Some(p);
+                    f(LinePathCommand::LineTo(p))
+                }),
                 PathCommand::Close => {
                     current_point = initial_point;
                     f(LinePathCommand::Close)
@@ -66,3 +96,103 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinePath, Path};
+    use geometry::{AffineTransformation, LinearTransformation, Point, Transform, Vector};
+
+    fn points(path: &Path, epsilon: f32) -> Vec<Point> {
+        let line_path: LinePath = path.commands().linearize(epsilon).collect();
+        line_path.points().to_vec()
+    }
+
+    #[test]
+    fn test_cubic() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.cubic_to(
+            Point::new(0.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 0.0),
+        );
+        let coarse = points(&path, 0.1);
+        let fine = points(&path, 0.001);
+        assert!(coarse.len() > 2 && fine.len() > coarse.len());
+        assert_eq!(fine.last(), Some(&Point::new(1.0, 0.0)));
+        // The curve is split in half first, so its highest point is on the polyline.
+        assert!(fine.contains(&Point::new(0.5, 0.75)));
+        assert!(fine.iter().all(|p| p.y >= 0.0 && p.y <= 0.75));
+    }
+
+    #[test]
+    fn test_arc() {
+        let arc = |radii, large_arc, sweep| {
+            let mut path = Path::new();
+            path.move_to(Point::new(1.0, 0.0));
+            path.arc_to(radii, 0.0, large_arc, sweep, Point::new(-1.0, 0.0));
+            points(&path, 0.001)
+        };
+        let ccw = arc(Vector::new(1.0, 1.0), false, true);
+        assert!(ccw
+            .iter()
+            .all(|p| (p.to_vector().length() - 1.0).abs() < 1E-3 && p.y > -1E-3));
+        assert!(ccw.iter().any(|p| p.y > 0.999));
+        assert_eq!(ccw.last(), Some(&Point::new(-1.0, 0.0)));
+        let cw = arc(Vector::new(1.0, 1.0), false, false);
+        assert!(cw
+            .iter()
+            .all(|p| (p.to_vector().length() - 1.0).abs() < 1E-3 && p.y < 1E-3));
+        // Radii that are too small are scaled up until the ellipse fits.
+        let scaled = arc(Vector::new(0.5, 0.5), true, true);
+        assert_eq!(scaled, ccw);
+
+        // The large arc goes around the far side of the ellipse.
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.arc_to(Vector::new(2.0, 1.0), 0.0, true, true, Point::new(2.0, 0.0));
+        let large = points(&path, 0.001);
+        assert!(large.iter().any(|p| p.y < -1.86) && large.iter().any(|p| p.x < -0.999));
+        // A zero radius makes a line.
+        path.clear();
+        path.move_to(Point::new(0.0, 0.0));
+        path.arc_to(
+            Vector::new(0.0, 1.0),
+            0.0,
+            false,
+            true,
+            Point::new(2.0, 0.0),
+        );
+        assert_eq!(
+            points(&path, 0.001),
+            [Point::new(0.0, 0.0), Point::new(2.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut path = Path::new();
+        path.move_to(Point::new(1.0, 0.0));
+        path.arc_to(
+            Vector::new(1.0, 1.0),
+            0.0,
+            false,
+            true,
+            Point::new(-1.0, 0.0),
+        );
+        // A skewed transformation that flips the orientation of the plane.
+        let t = AffineTransformation::new(
+            LinearTransformation::new(Vector::new(2.0, 1.0), Vector::new(1.0, -3.0)),
+            Vector::new(5.0, 7.0),
+        );
+        let transformed = points(&path.transform(&t), 0.001);
+        assert!(transformed.len() > 2);
+        for p in transformed {
+            // Map every point back to the unit circle with the inverse of `t`.
+            let v = p - Point::new(5.0, 7.0);
+            let u = Vector::new(-3.0 * v.x - v.y, -v.x + 2.0 * v.y) / -7.0;
+            assert!((u.length() - 1.0).abs() < 2E-3 && u.y > -2E-3);
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use crate::{LinePathCommand, LinePathIterator};
+use geometry::{Point, Vector};
+use internal_iter::InternalIterator;
+use std::f32::consts::PI;
+
+/// The shape of a stroke at the corners where two of its segments meet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineJoin {
+    /// Extends the outer edges of both segments until they meet. Falls back to a bevel if the
+    /// ratio of the length of the miter to the width of the stroke exceeds the given limit.
+    Miter(f32),
+    /// Rounds the corner off with a circular arc.
+    Round,
+    /// Cuts the corner off with a straight line.
+    Bevel,
+}
+
+/// The shape of a stroke at the ends of an open contour.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LineCap {
+    /// Ends the stroke at the endpoint.
+    Butt,
+    /// Ends the stroke with a half circle around the endpoint.
+    Round,
+    /// Extends the stroke past the endpoint by half its width.
+    Square,
+}
+
+/// The style of a stroke.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+    /// The alternating lengths of the dashes and gaps of the stroke, starting with a dash. A
+    /// pattern with an odd number of lengths is repeated to make it even. An empty pattern, or one
+    /// without a positive length, draws a solid stroke.
+    pub dashes: Vec<f32>,
+    /// The distance into the dash pattern at which each contour starts.
+    pub dash_offset: f32,
+}
+
+impl StrokeStyle {
+    /// Creates a new solid stroke style with the given width.
+    pub fn new(width: f32) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            ..StrokeStyle::default()
+        }
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            line_join: LineJoin::Miter(4.0),
+            line_cap: LineCap::Butt,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+/// An iterator over line path commands for the closed contours that fill the outline of a stroke.
+///
+/// Every segment, join and cap of the stroke becomes a separate contour, all with the same
+/// orientation, so the outline should be filled with the non-zero winding rule.
+#[derive(Clone, Debug)]
+pub struct Stroke<P> {
+    path: P,
+    style: StrokeStyle,
+    epsilon: f32,
+}
+
+impl<P> Stroke<P> {
+    pub(crate) fn new(path: P, style: StrokeStyle, epsilon: f32) -> Stroke<P> {
+        Stroke {
+            path,
+            style,
+            epsilon,
+        }
+    }
+}
+
+impl<P> InternalIterator for Stroke<P>
+where
+    P: LinePathIterator,
+{
+    type Item = LinePathCommand;
+
+    fn for_each<F>(self, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let stroker = Stroker {
+            style: &self.style,
+            epsilon: self.epsilon,
+        };
+        let mut contour = Vec::new();
+        let mut initial_point = None;
+        if !self.path.for_each(&mut |command| match command {
+            LinePathCommand::MoveTo(p) => {
+                let result = stroker.stroke_contour(&contour, false, f);
+                contour.clear();
+                contour.push(p);
+                initial_point = Some(p);
+                result
+            }
+            LinePathCommand::LineTo(p) => {
+                // After a close, the next contour starts where the previous one did.
+                if contour.is_empty() {
+                    contour.extend(initial_point);
+                }
+                contour.push(p);
+                true
+            }
+            LinePathCommand::Close => {
+                let result = stroker.stroke_contour(&contour, true, f);
+                contour.clear();
+                result
+            }
+        }) {
+            return false;
+        }
+        stroker.stroke_contour(&contour, false, f)
+    }
+}
+
+struct Stroker<'a> {
+    style: &'a StrokeStyle,
+    epsilon: f32,
+}
+
+impl<'a> Stroker<'a> {
+    fn stroke_contour<F>(&self, points: &[Point], is_closed: bool, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        // A contour that consists of a single move is not drawn.
+        if points.is_empty() || points.len() == 1 && !is_closed {
+            return true;
+        }
+        match self.dash_pattern() {
+            Some(pattern) => {
+                for dash in self.dash(&pattern, points, is_closed) {
+                    if !self.stroke_polyline(&dash, false, f) {
+                        return false;
+                    }
+                }
+                true
+            }
+            None => self.stroke_polyline(points, is_closed, f),
+        }
+    }
+
+    fn dash_pattern(&self) -> Option<Vec<f32>> {
+        let dashes = &self.style.dashes;
+        if dashes.iter().any(|length| *length < 0.0) || dashes.iter().sum::<f32>() <= 0.0 {
+            return None;
+        }
+        let mut pattern = dashes.clone();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_slice(dashes);
+        }
+        Some(pattern)
+    }
+
+    fn dash(&self, pattern: &[f32], points: &[Point], is_closed: bool) -> Vec<Vec<Point>> {
+        // Find where in the pattern the contour starts.
+        let mut offset = self
+            .style
+            .dash_offset
+            .rem_euclid(pattern.iter().sum::<f32>());
+        let mut index = 0;
+        while offset > 0.0 && offset >= pattern[index] {
+            offset -= pattern[index];
+            index = (index + 1) % pattern.len();
+        }
+        let mut remaining = pattern[index] - offset;
+
+        let mut dashes = Vec::new();
+        let mut dash = Vec::new();
+        if index % 2 == 0 {
+            dash.push(points[0]);
+        }
+        let closing_point = if is_closed { Some(points[0]) } else { None };
+        for (p0, p1) in points
+            .iter()
+            .cloned()
+            .zip(points[1..].iter().cloned().chain(closing_point))
+        {
+            let length = (p1 - p0).length();
+            let mut distance = 0.0;
+            while length - distance > remaining {
+                distance += remaining;
+                dash.push(p0.lerp(p1, distance / length));
+                if index % 2 == 0 {
+                    dashes.push(dash.split_off(0));
+                }
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+            remaining -= length - distance;
+            if index % 2 == 0 {
+                dash.push(p1);
+            }
+        }
+        if index % 2 == 0 && dash.len() >= 2 {
+            dashes.push(dash);
+        }
+        dashes
+    }
+
+    fn stroke_polyline<F>(&self, points: &[Point], is_closed: bool, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let mut points = points.to_vec();
+        points.dedup();
+        if is_closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() == 1 {
+            return self.stroke_dot(points[0], f);
+        }
+
+        let half_width = self.style.width / 2.0;
+        let count = points.len();
+        let segment_count = if is_closed { count } else { count - 1 };
+        for index in 0..segment_count {
+            let p0 = points[index];
+            let p1 = points[(index + 1) % count];
+            let normal = normal(p1 - p0) * half_width;
+            if !polygon(&[p0 - normal, p1 - normal, p1 + normal, p0 + normal], f) {
+                return false;
+            }
+        }
+        let join_range = if is_closed { 0..count } else { 1..count - 1 };
+        for index in join_range {
+            let p0 = points[(index + count - 1) % count];
+            let p1 = points[index];
+            let p2 = points[(index + 1) % count];
+            if !self.join(p0, p1, p2, f) {
+                return false;
+            }
+        }
+        if !is_closed {
+            if !self.cap(points[0], points[0] - points[1], f) {
+                return false;
+            }
+            if !self.cap(points[count - 1], points[count - 1] - points[count - 2], f) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn join<F>(&self, p0: Point, p1: Point, p2: Point, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let half_width = self.style.width / 2.0;
+        let n0 = normal(p1 - p0);
+        let n1 = normal(p2 - p1);
+        let cross = n0.cross(n1);
+        if cross.abs() < 1E-6 && n0.dot(n1) > 0.0 {
+            return true;
+        }
+        // The join goes on the outside of the corner, which is to the right of a left turn.
+        let side = if cross > 0.0 { -half_width } else { half_width };
+        let v0 = n0 * side;
+        let v1 = n1 * side;
+        match self.style.line_join {
+            LineJoin::Miter(limit) => {
+                let v = (v0 + v1) / 2.0;
+                let length = v.length();
+                if length > 0.0 && half_width / length <= limit {
+                    let miter = p1 + v * (half_width * half_width / v.dot(v));
+                    polygon(&[p1, p1 + v0, miter, p1 + v1], f)
+                } else {
+                    polygon(&[p1, p1 + v0, p1 + v1], f)
+                }
+            }
+            LineJoin::Round => {
+                let angle = v0.cross(v1).atan2(v0.dot(v1));
+                polygon(&self.fan(p1, v0, angle), f)
+            }
+            LineJoin::Bevel => polygon(&[p1, p1 + v0, p1 + v1], f),
+        }
+    }
+
+    fn cap<F>(&self, p: Point, direction: Vector, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let half_width = self.style.width / 2.0;
+        let normal = normal(direction) * half_width;
+        match self.style.line_cap {
+            LineCap::Butt => true,
+            LineCap::Round => polygon(&self.fan(p, normal, -PI), f),
+            LineCap::Square => {
+                let v = direction.normalize().unwrap() * half_width;
+                polygon(&[p + normal, p + normal + v, p - normal + v, p - normal], f)
+            }
+        }
+    }
+
+    // A contour of zero length has no direction, so its caps become a circle or an axis aligned
+    // square around its point.
+    fn stroke_dot<F>(&self, p: Point, f: &mut F) -> bool
+    where
+        F: FnMut(LinePathCommand) -> bool,
+    {
+        let half_width = self.style.width / 2.0;
+        match self.style.line_cap {
+            LineCap::Butt => true,
+            LineCap::Round => polygon(&self.fan(p, Vector::new(half_width, 0.0), 2.0 * PI)[1..], f),
+            LineCap::Square => {
+                let v = Vector::new(half_width, half_width);
+                let w = Vector::new(half_width, -half_width);
+                polygon(&[p - v, p + w, p + v, p - w], f)
+            }
+        }
+    }
+
+    // Returns the center, followed by the points of a circular arc around it that starts at
+    // `center + v` and spans `angle` radians, with tolerance `epsilon`.
+    fn fan(&self, center: Point, v: Vector, angle: f32) -> Vec<Point> {
+        let radius = v.length();
+        let step = if self.epsilon < radius {
+            2.0 * (1.0 - self.epsilon / radius).acos()
+        } else {
+            PI / 2.0
+        };
+        let count = (angle.abs() / step).ceil().max(1.0) as usize;
+        let mut points = vec![center];
+        for index in 0..=count {
+            let (sin, cos) = (angle * index as f32 / count as f32).sin_cos();
+            points.push(center + Vector::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos));
+        }
+        points
+    }
+}
+
+// Returns the unit vector to the left of `v`.
+fn normal(v: Vector) -> Vector {
+    let v = v.normalize().unwrap();
+    Vector::new(-v.y, v.x)
+}
+
+// Emits a closed contour through the given points, oriented so that its signed area is positive.
+// Contours without area are skipped.
+fn polygon<F>(points: &[Point], f: &mut F) -> bool
+where
+    F: FnMut(LinePathCommand) -> bool,
+{
+    let mut area = 0.0;
+    for (index, p0) in points.iter().enumerate() {
+        let p1 = points[(index + 1) % points.len()];
+        area += p0.to_vector().cross(p1.to_vector());
+    }
+    if area == 0.0 {
+        return true;
+    }
+    let mut points: Vec<Point> = points.to_vec();
+    if area < 0.0 {
+        points.reverse();
+    }
+    if !f(LinePathCommand::MoveTo(points[0])) {
+        return false;
+    }
+    for p in &points[1..] {
+        if !f(LinePathCommand::LineTo(*p)) {
+            return false;
+        }
+    }
+    f(LinePathCommand::Close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinePath, PathIterator};
+    use geometry::{AffineTransformation, Transform};
+
+    fn polygons<P: LinePathIterator>(path: P) -> Vec<Vec<Point>> {
+        let mut polygons = Vec::new();
+        let mut is_closed = true;
+        path.for_each(&mut |command| {
+            match command {
+                LinePathCommand::MoveTo(p) => {
+                    assert!(is_closed);
+                    is_closed = false;
+                    polygons.push(vec![p]);
+                }
+                LinePathCommand::LineTo(p) => polygons.last_mut().unwrap().push(p),
+                LinePathCommand::Close => is_closed = true,
+            }
+            true
+        });
+        assert!(is_closed);
+        polygons
+    }
+
+    fn polyline(points: &[(f32, f32)], is_closed: bool) -> LinePath {
+        let mut path = LinePath::new();
+        path.move_to(Point::new(points[0].0, points[0].1));
+        for &(x, y) in &points[1..] {
+            path.line_to(Point::new(x, y));
+        }
+        if is_closed {
+            path.close();
+        }
+        path
+    }
+
+    fn stroke(path: &LinePath, style: StrokeStyle) -> Vec<Vec<Point>> {
+        let polygons = polygons(path.commands().stroke(style, 0.01));
+        for polygon in &polygons {
+            let mut area = 0.0;
+            for (index, p0) in polygon.iter().enumerate() {
+                let p1 = polygon[(index + 1) % polygon.len()];
+                area += p0.to_vector().cross(p1.to_vector());
+            }
+            assert!(area > 0.0);
+        }
+        polygons
+    }
+
+    fn x_range(polygon: &[Point]) -> (f32, f32) {
+        polygon.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+            (min.min(p.x), max.max(p.x))
+        })
+    }
+
+    #[test]
+    fn test_segment() {
+        let path = polyline(&[(0.0, 0.0), (10.0, 0.0)], false);
+        assert_eq!(
+            stroke(&path, StrokeStyle::new(2.0)),
+            [[
+                Point::new(0.0, -1.0),
+                Point::new(10.0, -1.0),
+                Point::new(10.0, 1.0),
+                Point::new(0.0, 1.0),
+            ]]
+        );
+
+        let style = StrokeStyle {
+            line_cap: LineCap::Square,
+            ..StrokeStyle::new(2.0)
+        };
+        let polygons = stroke(&path, style);
+        assert_eq!(polygons.len(), 3);
+        assert_eq!(x_range(&polygons[1]), (-1.0, 0.0));
+        assert_eq!(x_range(&polygons[2]), (10.0, 11.0));
+
+        let style = StrokeStyle {
+            line_cap: LineCap::Round,
+            ..StrokeStyle::new(2.0)
+        };
+        let polygons = stroke(&path, style);
+        assert_eq!(polygons.len(), 3);
+        for (polygon, center) in polygons[1..].iter().zip(&[0.0, 10.0]) {
+            assert!(polygon
+                .iter()
+                .all(|p| (*p - Point::new(*center, 0.0)).length() <= 1.0 + 1E-5));
+            let (min, max) = x_range(polygon);
+            assert!((max - min - 1.0).abs() < 1E-5);
+        }
+    }
+
+    #[test]
+    fn test_joins() {
+        let path = polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], false);
+        let join = |line_join| {
+            let polygons = stroke(
+                &path,
+                StrokeStyle {
+                    line_join,
+                    ..StrokeStyle::new(2.0)
+                },
+            );
+            assert_eq!(polygons.len(), 3);
+            polygons[2].clone()
+        };
+        // The path turns left, so the joins go on the right side of the corner.
+        let miter = join(LineJoin::Miter(4.0));
+        assert_eq!(miter.len(), 4);
+        assert!(miter.contains(&Point::new(11.0, -1.0)));
+        let bevel = join(LineJoin::Bevel);
+        assert_eq!(bevel.len(), 3);
+        assert!(bevel.contains(&Point::new(10.0, -1.0)) && bevel.contains(&Point::new(11.0, 0.0)));
+        // A miter of a right angle is sqrt(2) times as long as the stroke is wide.
+        assert_eq!(join(LineJoin::Miter(1.4)), bevel);
+        let round = join(LineJoin::Round);
+        assert!(round.len() > 4);
+        assert!(round.iter().all(|p| p.x > 10.0 - 1E-5
+            && p.y < 1E-5
+            && (*p - Point::new(10.0, 0.0)).length() <= 1.0 + 1E-5));
+
+        // A closed contour has joins at every corner, and no caps.
+        let square = polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)], true);
+        let style = StrokeStyle {
+            line_cap: LineCap::Round,
+            ..StrokeStyle::new(2.0)
+        };
+        assert_eq!(stroke(&square, style).len(), 8);
+    }
+
+    #[test]
+    fn test_dashes() {
+        let path = polyline(&[(0.0, 0.0), (10.0, 0.0)], false);
+        let dashes = |dashes: &[f32], dash_offset| -> Vec<(f32, f32)> {
+            let style = StrokeStyle {
+                dashes: dashes.to_vec(),
+                dash_offset,
+                ..StrokeStyle::new(2.0)
+            };
+            let mut ranges = Vec::new();
+            for polygon in stroke(&path, style) {
+                ranges.push(x_range(&polygon));
+            }
+            ranges
+        };
+        assert_eq!(dashes(&[2.0, 3.0], 0.0), [(0.0, 2.0), (5.0, 7.0)]);
+        assert_eq!(
+            dashes(&[2.0, 3.0], 1.0),
+            [(0.0, 1.0), (4.0, 6.0), (9.0, 10.0)]
+        );
+        assert_eq!(
+            dashes(&[2.0, 3.0], -4.0),
+            [(0.0, 1.0), (4.0, 6.0), (9.0, 10.0)]
+        );
+        // An odd pattern repeats, so its second dash is a gap.
+        assert_eq!(dashes(&[3.0], 0.0), [(0.0, 3.0), (6.0, 9.0)]);
+        // A pattern without length draws a solid line.
+        assert_eq!(dashes(&[0.0, 0.0], 0.0), [(0.0, 10.0)]);
+
+        // Dashes of zero length draw dots with round caps.
+        let style = StrokeStyle {
+            line_cap: LineCap::Round,
+            dashes: vec![0.0, 5.0],
+            ..StrokeStyle::new(2.0)
+        };
+        let polygons = stroke(&polyline(&[(0.0, 0.0), (12.0, 0.0)], false), style);
+        assert_eq!(polygons.len(), 3);
+        for (polygon, center) in polygons.iter().zip(&[0.0, 5.0, 10.0]) {
+            assert!(polygon
+                .iter()
+                .all(|p| ((*p - Point::new(*center, 0.0)).length() - 1.0).abs() < 1E-5));
+        }
+    }
+
+    #[test]
+    fn test_dots() {
+        let mut path = LinePath::new();
+        path.move_to(Point::new(1.0, 1.0));
+        path.move_to(Point::new(5.0, 5.0));
+        path.close();
+        let style = StrokeStyle {
+            line_cap: LineCap::Square,
+            ..StrokeStyle::new(2.0)
+        };
+        // Only the closed contour is drawn.
+        assert_eq!(
+            stroke(&path, style),
+            [[
+                Point::new(4.0, 4.0),
+                Point::new(6.0, 4.0),
+                Point::new(6.0, 6.0),
+                Point::new(4.0, 6.0),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut path = crate::Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.quadratic_to(Point::new(5.0, 5.0), Point::new(10.0, 0.0));
+        path.line_to(Point::new(10.0, -5.0));
+        let t = AffineTransformation::translation(geometry::Vector::new(3.0, 4.0));
+        let style = StrokeStyle {
+            line_join: LineJoin::Round,
+            line_cap: LineCap::Round,
+            ..StrokeStyle::new(2.0)
+        };
+        let transformed_first = polygons(
+            path.clone()
+                .transform(&t)
+                .commands()
+                .stroke(style.clone(), 0.01),
+        );
+        let stroked_first = polygons(
+            path.commands()
+                .stroke(style, 0.01)
+                .map(|command| command.transform(&t)),
+        );
+        assert_eq!(transformed_first.len(), stroked_first.len());
+        for (polygon_0, polygon_1) in transformed_first.iter().zip(&stroked_first) {
+            assert_eq!(polygon_0.len(), polygon_1.len());
+            for (p0, p1) in polygon_0.iter().zip(polygon_1) {
+                assert!((*p0 - *p1).length() < 1E-4);
+            }
+        }
+    }
+}
@@ -0,0 +1,743 @@
+//! Outlines from the CFF and CFF2 tables of OpenType fonts.
+//!
+//! The glyphs in these tables are described by Type 2 charstrings, which draw cubic Bezier
+//! curves. Outlines consist of quadratic b-spline curves, so each cubic curve is approximated by
+//! a sequence of quadratic curves.
+
+use crate::{Error, Reader, Result};
+use font::{Outline, OutlinePoint};
+use geometry::{Point, Rectangle};
+
+/// The maximum distance, in font units, between a cubic curve and its quadratic approximation.
+const TOLERANCE: f32 = 0.5;
+
+/// The maximum depth of nested subroutine calls allowed by the specification.
+const MAX_SUBR_DEPTH: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct CffParser<'a> {
+    is_cff2: bool,
+    charstrings: Index<'a>,
+    global_subrs: Index<'a>,
+    font_dicts: Vec<FontDict<'a>>,
+    fd_select: Option<&'a [u8]>,
+    region_counts: Vec<usize>,
+}
+
+impl<'a> CffParser<'a> {
+    pub fn new(bytes: &'a [u8], is_cff2: bool) -> Result<CffParser<'a>> {
+        let mut reader = Reader::new(bytes);
+        reader.skip(2)?;
+        let header_size = reader.read_u8()? as usize;
+        let (top_dict, global_subrs_start) = if is_cff2 {
+            let top_dict_length = reader.read_u16()? as usize;
+            let top_dict_end = header_size + top_dict_length;
+            let top_dict = parse_dict(bytes.get(header_size..top_dict_end).ok_or(Error)?)?;
+            (top_dict, top_dict_end)
+        } else {
+            // the name index is followed by the top dict index, the string index and the
+            // global subroutine index. only the first font in the table is used
+            let (_, name_index_length) =
+                Index::parse(bytes.get(header_size..).ok_or(Error)?, false)?;
+            let top_dict_index_start = header_size + name_index_length;
+            let (top_dict_index, top_dict_index_length) =
+                Index::parse(bytes.get(top_dict_index_start..).ok_or(Error)?, false)?;
+            let top_dict = parse_dict(top_dict_index.get(0)?)?;
+            let string_index_start = top_dict_index_start + top_dict_index_length;
+            let (_, string_index_length) =
+                Index::parse(bytes.get(string_index_start..).ok_or(Error)?, false)?;
+            (top_dict, string_index_start + string_index_length)
+        };
+        let (global_subrs, _) =
+            Index::parse(bytes.get(global_subrs_start..).ok_or(Error)?, is_cff2)?;
+        if top_dict
+            .get_last(CHARSTRING_TYPE)
+            .map_or(false, |charstring_type| charstring_type != 2.0)
+        {
+            return Err(Error);
+        }
+        let charstrings_offset = top_dict.get_last(CHAR_STRINGS).ok_or(Error)? as usize;
+        let (charstrings, _) =
+            Index::parse(bytes.get(charstrings_offset..).ok_or(Error)?, is_cff2)?;
+        // cid keyed fonts and cff2 fonts have a font dict for every group of glyphs, other fonts
+        // have a single private dict
+        let font_dicts = match top_dict.get_last(FD_ARRAY) {
+            Some(fd_array_offset) => {
+                let (fd_array, _) =
+                    Index::parse(bytes.get(fd_array_offset as usize..).ok_or(Error)?, is_cff2)?;
+                let mut font_dicts = Vec::new();
+                for index in 0..fd_array.count {
+                    font_dicts.push(FontDict::parse(
+                        bytes,
+                        &parse_dict(fd_array.get(index)?)?,
+                        is_cff2,
+                    )?);
+                }
+                font_dicts
+            }
+            None => vec![FontDict::parse(bytes, &top_dict, is_cff2)?],
+        };
+        let fd_select = match top_dict.get_last(FD_SELECT) {
+            Some(fd_select_offset) => Some(bytes.get(fd_select_offset as usize..).ok_or(Error)?),
+            None => None,
+        };
+        let region_counts = match top_dict.get_last(VSTORE) {
+            Some(vstore_offset) => {
+                parse_region_counts(bytes.get(vstore_offset as usize..).ok_or(Error)?)?
+            }
+            None => Vec::new(),
+        };
+        Ok(CffParser {
+            is_cff2,
+            charstrings,
+            global_subrs,
+            font_dicts,
+            fd_select,
+            region_counts,
+        })
+    }
+
+    /// Returns the outline of the glyph with the given index, and its bounds.
+    pub fn parse_outline(&self, index: usize) -> Result<(Outline, Rectangle)> {
+        let font_dict = self
+            .font_dicts
+            .get(self.parse_font_dict_index(index)?)
+            .ok_or(Error)?;
+        let mut interpreter = Interpreter {
+            parser: self,
+            local_subrs: font_dict.local_subrs,
+            stack: Vec::new(),
+            stem_count: 0,
+            has_width: self.is_cff2,
+            vsindex: font_dict.vsindex,
+            position: Point::origin(),
+            contour: Vec::new(),
+            outline: Outline::new(),
+            is_done: false,
+        };
+        interpreter.execute(self.charstrings.get(index)?, 0)?;
+        interpreter.close_contour();
+        let outline = interpreter.outline;
+        let mut points = outline.points().iter().map(|point| point.point);
+        let bounds = match points.next() {
+            Some(first) => {
+                let (p_min, p_max) = points.fold((first, first), |(p_min, p_max), point| {
+                    (
+                        Point::new(p_min.x.min(point.x), p_min.y.min(point.y)),
+                        Point::new(p_max.x.max(point.x), p_max.y.max(point.y)),
+                    )
+                });
+                Rectangle::new(p_min, p_max)
+            }
+            None => Rectangle::default(),
+        };
+        Ok((outline, bounds))
+    }
+
+    fn parse_font_dict_index(&self, index: usize) -> Result<usize> {
+        let bytes = match self.fd_select {
+            Some(bytes) => bytes,
+            None => return Ok(0),
+        };
+        let mut reader = Reader::new(bytes);
+        match reader.read_u8()? {
+            0 => {
+                reader.skip(index)?;
+                Ok(reader.read_u8()? as usize)
+            }
+            3 => {
+                let range_count = reader.read_u16()? as usize;
+                let mut first = reader.read_u16()? as usize;
+                for _ in 0..range_count {
+                    let font_dict_index = reader.read_u8()? as usize;
+                    let next = reader.read_u16()? as usize;
+                    if (first..next).contains(&index) {
+                        return Ok(font_dict_index);
+                    }
+                    first = next;
+                }
+                Err(Error)
+            }
+            4 => {
+                let range_count = reader.read_u32()? as usize;
+                let mut first = reader.read_u32()? as usize;
+                for _ in 0..range_count {
+                    let font_dict_index = reader.read_u16()? as usize;
+                    let next = reader.read_u32()? as usize;
+                    if (first..next).contains(&index) {
+                        return Ok(font_dict_index);
+                    }
+                    first = next;
+                }
+                Err(Error)
+            }
+            _ => Err(Error),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FontDict<'a> {
+    local_subrs: Index<'a>,
+    vsindex: usize,
+}
+
+impl<'a> FontDict<'a> {
+    fn parse(bytes: &'a [u8], dict: &Dict, is_cff2: bool) -> Result<FontDict<'a>> {
+        let (size, offset) = match dict.get(PRIVATE) {
+            Some(&[.., size, offset]) => (size as usize, offset as usize),
+            _ => {
+                return Ok(FontDict {
+                    local_subrs: Index::empty(),
+                    vsindex: 0,
+                })
+            }
+        };
+        let private_dict = parse_dict(bytes.get(offset..offset + size).ok_or(Error)?)?;
+        // the offset of the local subroutines is relative to the private dict
+        let local_subrs = match private_dict.get_last(SUBRS) {
+            Some(subrs_offset) => {
+                Index::parse(
+                    bytes.get(offset + subrs_offset as usize..).ok_or(Error)?,
+                    is_cff2,
+                )?
+                .0
+            }
+            None => Index::empty(),
+        };
+        Ok(FontDict {
+            local_subrs,
+            vsindex: private_dict.get_last(VSINDEX).unwrap_or(0.0) as usize,
+        })
+    }
+}
+
+/// An array of variable length objects.
+#[derive(Clone, Copy, Debug)]
+struct Index<'a> {
+    count: usize,
+    offset_size: usize,
+    offsets: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> Index<'a> {
+    fn empty() -> Index<'a> {
+        Index {
+            count: 0,
+            offset_size: 1,
+            offsets: &[],
+            data: &[],
+        }
+    }
+
+    /// Parses an index at the start of `bytes`, and returns it along with its length in bytes.
+    /// The count of an index is 32 bits in CFF2.
+    fn parse(bytes: &'a [u8], is_cff2: bool) -> Result<(Index<'a>, usize)> {
+        let mut reader = Reader::new(bytes);
+        let count = if is_cff2 {
+            reader.read_u32()? as usize
+        } else {
+            reader.read_u16()? as usize
+        };
+        let count_size = if is_cff2 { 4 } else { 2 };
+        if count == 0 {
+            return Ok((Index::empty(), count_size));
+        }
+        let offset_size = reader.read_u8()? as usize;
+        if offset_size < 1 || offset_size > 4 {
+            return Err(Error);
+        }
+        let offsets_start = count_size + 1;
+        let offsets_end = offsets_start + (count + 1) * offset_size;
+        let offsets = bytes.get(offsets_start..offsets_end).ok_or(Error)?;
+        let mut index = Index {
+            count,
+            offset_size,
+            offsets,
+            data: &[],
+        };
+        // offsets are relative to the byte before the data
+        let data_length = index.offset(count)?.checked_sub(1).ok_or(Error)?;
+        index.data = bytes
+            .get(offsets_end..offsets_end + data_length)
+            .ok_or(Error)?;
+        Ok((index, offsets_end + data_length))
+    }
+
+    fn offset(&self, index: usize) -> Result<usize> {
+        let bytes = self
+            .offsets
+            .get(index * self.offset_size..(index + 1) * self.offset_size)
+            .ok_or(Error)?;
+        Ok(bytes
+            .iter()
+            .fold(0, |offset, &byte| offset << 8 | byte as usize))
+    }
+
+    fn get(&self, index: usize) -> Result<&'a [u8]> {
+        if index >= self.count {
+            return Err(Error);
+        }
+        let start = self.offset(index)?.checked_sub(1).ok_or(Error)?;
+        let end = self.offset(index + 1)?.checked_sub(1).ok_or(Error)?;
+        self.data.get(start..end).ok_or(Error)
+    }
+
+    /// The bias that is added to subroutine numbers, so that small numbers can be encoded in
+    /// fewer bytes.
+    fn subr_bias(&self) -> i32 {
+        if self.count < 1240 {
+            107
+        } else if self.count < 33900 {
+            1131
+        } else {
+            32768
+        }
+    }
+}
+
+// two byte operators are escaped with 12
+const CHAR_STRINGS: u16 = 17;
+const PRIVATE: u16 = 18;
+const SUBRS: u16 = 19;
+const VSINDEX: u16 = 22;
+const VSTORE: u16 = 24;
+const CHARSTRING_TYPE: u16 = 12 << 8 | 6;
+const FD_ARRAY: u16 = 12 << 8 | 36;
+const FD_SELECT: u16 = 12 << 8 | 37;
+
+/// The operators in a dict and their operands, in order.
+#[derive(Clone, Debug, Default)]
+struct Dict {
+    entries: Vec<(u16, Vec<f32>)>,
+}
+
+impl Dict {
+    fn get(&self, operator: u16) -> Option<&[f32]> {
+        self.entries
+            .iter()
+            .find(|(other, _)| *other == operator)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    // most operators take a single operand
+    fn get_last(&self, operator: u16) -> Option<f32> {
+        self.get(operator)
+            .and_then(|operands| operands.last().cloned())
+    }
+}
+
+fn parse_dict(bytes: &[u8]) -> Result<Dict> {
+    let mut reader = Reader::new(bytes);
+    let mut dict = Dict::default();
+    let mut operands = Vec::new();
+    while !reader.bytes.is_empty() {
+        let byte = reader.read_u8()?;
+        match byte {
+            0..=22 | 24..=27 | 31 => {
+                let operator = if byte == 12 {
+                    12 << 8 | reader.read_u8()? as u16
+                } else {
+                    byte as u16
+                };
+                dict.entries.push((operator, operands.split_off(0)));
+            }
+            // blend is only used for operands of hinting operators, which are not needed
+            23 => {}
+            28 => operands.push(reader.read_i16()? as f32),
+            29 => operands.push(reader.read_u32()? as i32 as f32),
+            30 => operands.push(read_real(&mut reader)?),
+            32..=246 => operands.push(byte as f32 - 139.0),
+            247..=250 => {
+                operands.push(((byte as f32 - 247.0) * 256.0) + reader.read_u8()? as f32 + 108.0)
+            }
+            251..=254 => {
+                operands.push(-((byte as f32 - 251.0) * 256.0) - reader.read_u8()? as f32 - 108.0)
+            }
+            _ => return Err(Error),
+        }
+    }
+    Ok(dict)
+}
+
+// a real number is encoded as a string of nibbles
+fn read_real(reader: &mut Reader) -> Result<f32> {
+    let mut string = String::new();
+    loop {
+        let byte = reader.read_u8()?;
+        for &nibble in &[byte >> 4, byte & 0xF] {
+            match nibble {
+                0..=9 => string.push((b'0' + nibble) as char),
+                0xA => string.push('.'),
+                0xB => string.push('E'),
+                0xC => string.push_str("E-"),
+                0xE => string.push('-'),
+                0xF => return string.parse().map_err(|_| Error),
+                _ => return Err(Error),
+            }
+        }
+    }
+}
+
+// returns the number of regions in each item variation data of a variation store, blends in
+// charstrings have a delta for each of them
+fn parse_region_counts(bytes: &[u8]) -> Result<Vec<usize>> {
+    // the store is preceded by its length
+    let bytes = bytes.get(2..).ok_or(Error)?;
+    let mut reader = Reader::new(bytes);
+    reader.skip(6)?;
+    let item_variation_data_count = reader.read_u16()? as usize;
+    let mut region_counts = Vec::new();
+    for _ in 0..item_variation_data_count {
+        let offset = reader.read_u32()? as usize;
+        let mut reader = Reader::new(bytes.get(offset..).ok_or(Error)?);
+        reader.skip(4)?;
+        region_counts.push(reader.read_u16()? as usize);
+    }
+    Ok(region_counts)
+}
+
+/// An interpreter for Type 2 charstrings, which builds an outline.
+struct Interpreter<'a, 'b> {
+    parser: &'b CffParser<'a>,
+    local_subrs: Index<'a>,
+    stack: Vec<f32>,
+    stem_count: usize,
+    // the first stack clearing operator of a CFF charstring can have the advance width as an
+    // extra operand. it is not needed, since the hmtx table has it too
+    has_width: bool,
+    vsindex: usize,
+    position: Point,
+    contour: Vec<OutlinePoint>,
+    outline: Outline,
+    is_done: bool,
+}
+
+impl<'a, 'b> Interpreter<'a, 'b> {
+    fn execute(&mut self, bytes: &[u8], depth: usize) -> Result<()> {
+        if depth > MAX_SUBR_DEPTH {
+            return Err(Error);
+        }
+        let mut reader = Reader::new(bytes);
+        while !reader.bytes.is_empty() && !self.is_done {
+            let byte = reader.read_u8()?;
+            match byte {
+                // hstem, vstem, hstemhm and vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stem_count += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                // rmoveto
+                21 => {
+                    self.take_width(self.stack.len() > 2);
+                    let (dx, dy) = (self.arg(0)?, self.arg(1)?);
+                    self.move_to(dx, dy);
+                }
+                // hmoveto
+                22 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dx = self.arg(0)?;
+                    self.move_to(dx, 0.0);
+                }
+                // vmoveto
+                4 => {
+                    self.take_width(self.stack.len() > 1);
+                    let dy = self.arg(0)?;
+                    self.move_to(0.0, dy);
+                }
+                // rlineto
+                5 => {
+                    for pair in self.stack.split_off(0).chunks_exact(2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                }
+                // hlineto and vlineto alternate between horizontal and vertical lines
+                6 | 7 => {
+                    let mut is_horizontal = byte == 6;
+                    for d in self.stack.split_off(0) {
+                        if is_horizontal {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                        is_horizontal = !is_horizontal;
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    for args in self.stack.split_off(0).chunks_exact(6) {
+                        self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5]);
+                    }
+                }
+                // callsubr and callgsubr
+                10 | 29 => {
+                    let subrs = if byte == 10 {
+                        self.local_subrs
+                    } else {
+                        self.parser.global_subrs
+                    };
+                    let index = self.stack.pop().ok_or(Error)? as i32 + subrs.subr_bias();
+                    if index < 0 {
+                        return Err(Error);
+                    }
+                    self.execute(subrs.get(index as usize)?, depth + 1)?;
+                }
+                // return
+                11 => return Ok(()),
+                12 => {
+                    let operator = reader.read_u8()?;
+                    let args = self.stack.split_off(0);
+                    match (operator, args.len()) {
+                        // flex
+                        (35, 13) => {
+                            self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5]);
+                            self.curve_to(args[6], args[7], args[8], args[9], args[10], args[11]);
+                        }
+                        // hflex
+                        (34, 7) => {
+                            self.curve_to(args[0], 0.0, args[1], args[2], args[3], 0.0);
+                            self.curve_to(args[4], 0.0, args[5], -args[2], args[6], 0.0);
+                        }
+                        // hflex1
+                        (36, 9) => {
+                            self.curve_to(args[0], args[1], args[2], args[3], args[4], 0.0);
+                            let dy = -(args[1] + args[3] + args[7]);
+                            self.curve_to(args[5], 0.0, args[6], args[7], args[8], dy);
+                        }
+                        // flex1, the last point is on the axis along which the curve moves least
+                        (37, 11) => {
+                            let dx: f32 = args[0..10].iter().step_by(2).sum();
+                            let dy: f32 = args[1..10].iter().step_by(2).sum();
+                            let (dx6, dy6) = if dx.abs() > dy.abs() {
+                                (args[10], -dy)
+                            } else {
+                                (-dx, args[10])
+                            };
+                            self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5]);
+                            self.curve_to(args[6], args[7], args[8], args[9], dx6, dy6);
+                        }
+                        // the arithmetic operators are deprecated, and do not draw anything
+                        _ => {}
+                    }
+                }
+                // endchar
+                14 => {
+                    if self.parser.is_cff2 {
+                        return Err(Error);
+                    }
+                    self.take_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    self.stack.clear();
+                    self.is_done = true;
+                }
+                // vsindex
+                15 => {
+                    self.vsindex = self.stack.pop().ok_or(Error)? as usize;
+                }
+                // blend keeps the default values, and drops their deltas for each region
+                16 => {
+                    let count = self.stack.pop().ok_or(Error)? as usize;
+                    let region_count = *self.parser.region_counts.get(self.vsindex).ok_or(Error)?;
+                    let start = self
+                        .stack
+                        .len()
+                        .checked_sub(count * (region_count + 1))
+                        .ok_or(Error)?;
+                    self.stack.truncate(start + count);
+                }
+                // hintmask and cntrmask, the operands are implied vstems
+                19 | 20 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stem_count += self.stack.len() / 2;
+                    self.stack.clear();
+                    reader.skip((self.stem_count + 7) / 8)?;
+                }
+                // rcurveline
+                24 => {
+                    let args = self.stack.split_off(0);
+                    if args.len() < 8 {
+                        return Err(Error);
+                    }
+                    let (curves, line) = args.split_at(args.len() - 2);
+                    for args in curves.chunks_exact(6) {
+                        self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5]);
+                    }
+                    self.line_to(line[0], line[1]);
+                }
+                // rlinecurve
+                25 => {
+                    let args = self.stack.split_off(0);
+                    if args.len() < 8 {
+                        return Err(Error);
+                    }
+                    let (lines, curve) = args.split_at(args.len() - 6);
+                    for pair in lines.chunks_exact(2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                    self.curve_to(curve[0], curve[1], curve[2], curve[3], curve[4], curve[5]);
+                }
+                // vvcurveto, the first curve can start with a horizontal move
+                26 => {
+                    let mut args = self.stack.split_off(0);
+                    let mut dx1 = if args.len() % 2 == 1 {
+                        args.remove(0)
+                    } else {
+                        0.0
+                    };
+                    for args in args.chunks_exact(4) {
+                        self.curve_to(dx1, args[0], args[1], args[2], 0.0, args[3]);
+                        dx1 = 0.0;
+                    }
+                }
+                // hhcurveto, the first curve can start with a vertical move
+                27 => {
+                    let mut args = self.stack.split_off(0);
+                    let mut dy1 = if args.len() % 2 == 1 {
+                        args.remove(0)
+                    } else {
+                        0.0
+                    };
+                    for args in args.chunks_exact(4) {
+                        self.curve_to(args[0], dy1, args[1], args[2], args[3], 0.0);
+                        dy1 = 0.0;
+                    }
+                }
+                // vhcurveto and hvcurveto alternate between curves that start vertical and end
+                // horizontal and the other way around. the last curve can end with a move along
+                // the other axis
+                30 | 31 => {
+                    let args = self.stack.split_off(0);
+                    let mut starts_horizontal = byte == 31;
+                    let mut index = 0;
+                    while index + 4 <= args.len() {
+                        let last = if args.len() - index == 5 {
+                            args[index + 4]
+                        } else {
+                            0.0
+                        };
+                        let a = &args[index..index + 4];
+                        if starts_horizontal {
+                            self.curve_to(a[0], 0.0, a[1], a[2], last, a[3]);
+                        } else {
+                            self.curve_to(0.0, a[0], a[1], a[2], a[3], last);
+                        }
+                        starts_horizontal = !starts_horizontal;
+                        index += 4;
+                    }
+                }
+                28 => self.stack.push(reader.read_i16()? as f32),
+                32..=246 => self.stack.push(byte as f32 - 139.0),
+                247..=250 => self
+                    .stack
+                    .push(((byte as f32 - 247.0) * 256.0) + reader.read_u8()? as f32 + 108.0),
+                251..=254 => self
+                    .stack
+                    .push(-((byte as f32 - 251.0) * 256.0) - reader.read_u8()? as f32 - 108.0),
+                // a 16.16 fixed point number
+                255 => self.stack.push(reader.read_u32()? as i32 as f32 / 65536.0),
+                _ => return Err(Error),
+            }
+        }
+        Ok(())
+    }
+
+    fn take_width(&mut self, has_extra_operand: bool) {
+        if !self.has_width {
+            self.has_width = true;
+            if has_extra_operand {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    // takes the operands of a moveto
+    fn arg(&self, index: usize) -> Result<f32> {
+        self.stack.get(index).cloned().ok_or(Error)
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.stack.clear();
+        self.close_contour();
+        self.position = Point::new(self.position.x + dx, self.position.y + dy);
+        self.push(true, self.position);
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.position = Point::new(self.position.x + dx, self.position.y + dy);
+        self.push(true, self.position);
+    }
+
+    fn curve_to(&mut self, dx1: f32, dy1: f32, dx2: f32, dy2: f32, dx3: f32, dy3: f32) {
+        let p0 = self.position;
+        let p1 = Point::new(p0.x + dx1, p0.y + dy1);
+        let p2 = Point::new(p1.x + dx2, p1.y + dy2);
+        let p3 = Point::new(p2.x + dx3, p2.y + dy3);
+        // the error of approximating a cubic curve with a single quadratic curve is proportional
+        // to the size of its third derivative, and shrinks with the cube of the number of pieces
+        let third_x = p3.x - 3.0 * p2.x + 3.0 * p1.x - p0.x;
+        let third_y = p3.y - 3.0 * p2.y + 3.0 * p1.y - p0.y;
+        let error = (third_x * third_x + third_y * third_y).sqrt() * 3f32.sqrt() / 36.0;
+        let piece_count = (error / TOLERANCE).cbrt().ceil().max(1.0).min(16.0) as usize;
+        for piece in 0..piece_count {
+            let t0 = piece as f32 / piece_count as f32;
+            let t1 = (piece + 1) as f32 / piece_count as f32;
+            let q0 = cubic_point(p0, p1, p2, p3, t0);
+            let q3 = cubic_point(p0, p1, p2, p3, t1);
+            // the control points of the piece, from the derivatives at its ends
+            let (d0, d1) = (
+                cubic_derivative(p0, p1, p2, p3, t0),
+                cubic_derivative(p0, p1, p2, p3, t1),
+            );
+            let scale = (t1 - t0) / 3.0;
+            let q1 = Point::new(q0.x + d0.0 * scale, q0.y + d0.1 * scale);
+            let q2 = Point::new(q3.x - d1.0 * scale, q3.y - d1.1 * scale);
+            // the quadratic curve that best matches a cubic curve has its control point at the
+            // average of the points where the tangents at its ends would put it
+            let control = Point::new(
+                (3.0 * (q1.x + q2.x) - q0.x - q3.x) / 4.0,
+                (3.0 * (q1.y + q2.y) - q0.y - q3.y) / 4.0,
+            );
+            self.push(false, control);
+            self.push(true, if piece + 1 == piece_count { p3 } else { q3 });
+        }
+        self.position = p3;
+    }
+
+    fn push(&mut self, is_on_curve: bool, point: Point) {
+        self.contour.push(OutlinePoint { is_on_curve, point });
+    }
+
+    // contours are closed implicitly, so a last point on top of the first one is redundant
+    fn close_contour(&mut self) {
+        if self.contour.len() > 1 && self.contour.last() == self.contour.first() {
+            self.contour.pop();
+        }
+        if self.contour.len() > 1 {
+            let mut contour = self.outline.begin_contour();
+            for point in self.contour.drain(..) {
+                contour.push(point);
+            }
+            contour.end();
+        }
+        self.contour.clear();
+    }
+}
+
+fn cubic_point(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> Point {
+    let s = 1.0 - t;
+    let (a, b, c, d) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+    Point::new(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+
+fn cubic_derivative(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> (f32, f32) {
+    let s = 1.0 - t;
+    let (a, b, c) = (3.0 * s * s, 6.0 * s * t, 3.0 * t * t);
+    (
+        a * (p1.x - p0.x) + b * (p2.x - p1.x) + c * (p3.x - p2.x),
+        a * (p1.y - p0.y) + b * (p2.y - p1.y) + c * (p3.y - p2.y),
+    )
+}
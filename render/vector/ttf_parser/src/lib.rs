@@ -1,8 +1,15 @@
-use font::{Font, Glyph, HorizontalMetrics, Outline, OutlinePoint};
+mod cff;
+
+use cff::CffParser;
+use font::{
+    CharCodeToGlyphIndexMap, Font, Glyph, HorizontalMetrics, Kerning, KerningSubtable, Ligature,
+    Ligatures, Outline, OutlinePoint,
+};
 use geometry::{
     AffineTransformation, LinearTransformation, Point, Rectangle, Transform, Vector,
 };
 use internal_iter::ExtendFromInternalIterator;
+use std::collections::HashMap;
 use std::{mem, result};
 
 #[derive(Clone, Debug)]
@@ -99,23 +106,7 @@ impl<'a> GlyphsParser<'a> {
     }
 
     fn parse_horizontal_metrics(&self, index: usize) -> Result<HorizontalMetrics> {
-        let mut reader = Reader::new(self.hmtx_table_bytes);
-        if index < self.advance_width_count {
-            reader.skip(index * 4)?;
-            Ok(HorizontalMetrics {
-                advance_width: reader.read_u16()? as f32,
-                left_side_bearing: reader.read_i16()? as f32,
-            })
-        } else {
-            reader.skip((self.advance_width_count - 1) * 4)?;
-            let advance_width = reader.read_u16()? as f32;
-            reader.skip(2)?;
-            reader.skip((index - self.advance_width_count) * 2)?;
-            Ok(HorizontalMetrics {
-                advance_width,
-                left_side_bearing: reader.read_i16()? as f32,
-            })
-        }
+        parse_horizontal_metrics(self.hmtx_table_bytes, self.advance_width_count, index)
     }
 
     fn parse_simple_glyph(
@@ -513,16 +504,26 @@ pub struct Error;
 pub fn parse_ttf(bytes: &[u8]) -> Result<Font> {
     let mut reader = Reader::new(&bytes[0..12]);
     let sfnt_version = reader.read_u32()?;
-    if ![0x00010000, u32::from_be_bytes(*b"true")].contains(&sfnt_version) {
+    if ![
+        0x00010000,
+        u32::from_be_bytes(*b"true"),
+        u32::from_be_bytes(*b"OTTO"),
+    ]
+    .contains(&sfnt_version)
+    {
         return Err(Error);
     }
     let table_count = reader.read_u16()? as usize;
     reader.skip(6)?;
+    let mut cff_table_bytes = None;
     let mut cmap_table_bytes = None;
     let mut glyf_table_bytes = None;
+    let mut gpos_table_bytes = None;
+    let mut gsub_table_bytes = None;
     let mut head_table_bytes = None;
     let mut hhea_table_bytes = None;
     let mut hmtx_table_bytes = None;
+    let mut kern_table_bytes = None;
     let mut loca_table_bytes = None;
     let mut maxp_table_bytes = None;
     for index in 0..table_count {
@@ -533,22 +534,25 @@ pub fn parse_ttf(bytes: &[u8]) -> Result<Font> {
         let length = reader.read_u32()? as usize;
         let table_bytes = &bytes[offset..][..length];
         match &table_tag.to_be_bytes() {
+            b"CFF " => cff_table_bytes = Some((table_bytes, false)),
+            b"CFF2" => cff_table_bytes = Some((table_bytes, true)),
             b"cmap" => cmap_table_bytes = Some(table_bytes),
             b"glyf" => glyf_table_bytes = Some(table_bytes),
+            b"GPOS" => gpos_table_bytes = Some(table_bytes),
+            b"GSUB" => gsub_table_bytes = Some(table_bytes),
             b"head" => head_table_bytes = Some(table_bytes),
             b"hhea" => hhea_table_bytes = Some(table_bytes),
             b"hmtx" => hmtx_table_bytes = Some(table_bytes),
+            b"kern" => kern_table_bytes = Some(table_bytes),
             b"loca" => loca_table_bytes = Some(table_bytes),
             b"maxp" => maxp_table_bytes = Some(table_bytes),
             _ => {}
         }
     }
     let cmap_table_bytes = cmap_table_bytes.ok_or(Error)?;
-    let glyf_table_bytes = glyf_table_bytes.ok_or(Error)?;
     let head_table_bytes = head_table_bytes.ok_or(Error)?;
     let hhea_table_bytes = hhea_table_bytes.ok_or(Error)?;
     let hmtx_table_bytes = hmtx_table_bytes.ok_or(Error)?;
-    let loca_table_bytes = loca_table_bytes.ok_or(Error)?;
     let maxp_table_bytes = maxp_table_bytes.ok_or(Error)?;
     let mut reader = Reader::new(hhea_table_bytes);
     reader.skip(4)?;
@@ -560,7 +564,6 @@ pub fn parse_ttf(bytes: &[u8]) -> Result<Font> {
     let mut reader = Reader::new(maxp_table_bytes);
     reader.skip(4)?;
     let glyph_count = reader.read_u16()? as usize;
-    reader.skip(26)?;
     let mut reader = Reader::new(head_table_bytes);
     reader.skip(18)?;
     let units_per_em = reader.read_u16()? as f32;
@@ -572,6 +575,35 @@ pub fn parse_ttf(bytes: &[u8]) -> Result<Font> {
     reader.skip(6)?;
     let index_to_loc_format = IndexToLocFormat::from_i16(reader.read_i16()?).ok_or(Error)?;
     reader.skip(2)?;
+    // fonts with cff outlines have no glyf and loca tables
+    let glyphs = match cff_table_bytes {
+        Some((cff_table_bytes, is_cff2)) => {
+            let parser = CffParser::new(cff_table_bytes, is_cff2)?;
+            (0..glyph_count)
+                .map(|index| {
+                    let (outline, bounds) = parser.parse_outline(index)?;
+                    Ok(Glyph {
+                        horizontal_metrics: parse_horizontal_metrics(
+                            hmtx_table_bytes,
+                            advance_width_count,
+                            index,
+                        )?,
+                        bounds,
+                        outline,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        None => GlyphsParser::new(
+            glyph_count,
+            advance_width_count,
+            hmtx_table_bytes,
+            index_to_loc_format,
+            loca_table_bytes.ok_or(Error)?,
+            glyf_table_bytes.ok_or(Error)?,
+        )
+        .parse_glyphs()?,
+    };
     Ok(Font {
         units_per_em,
         ascender,
@@ -579,43 +611,86 @@ pub fn parse_ttf(bytes: &[u8]) -> Result<Font> {
         line_gap,
         bounds,
         char_code_to_glyph_index_map: parse_char_code_to_glyph_index_map(cmap_table_bytes)?,
-        glyphs: GlyphsParser::new(
-            glyph_count,
-            advance_width_count,
-            hmtx_table_bytes,
-            index_to_loc_format,
-            loca_table_bytes,
-            glyf_table_bytes,
-        )
-        .parse_glyphs()?,
+        glyphs,
+        // the layout tables are optional, so a font with a broken one still loads, unshaped
+        kerning: parse_kerning(gpos_table_bytes, kern_table_bytes).unwrap_or_default(),
+        ligatures: gsub_table_bytes
+            .map_or(Ok(Ligatures::new()), parse_ligatures)
+            .unwrap_or_default(),
     })
 }
 
-fn parse_char_code_to_glyph_index_map(bytes: &[u8]) -> Result<Vec<usize>> {
+fn parse_horizontal_metrics(
+    hmtx_table_bytes: &[u8],
+    advance_width_count: usize,
+    index: usize,
+) -> Result<HorizontalMetrics> {
+    let mut reader = Reader::new(hmtx_table_bytes);
+    if index < advance_width_count {
+        reader.skip(index * 4)?;
+        Ok(HorizontalMetrics {
+            advance_width: reader.read_u16()? as f32,
+            left_side_bearing: reader.read_i16()? as f32,
+        })
+    } else {
+        reader.skip((advance_width_count - 1) * 4)?;
+        let advance_width = reader.read_u16()? as f32;
+        reader.skip(2)?;
+        reader.skip((index - advance_width_count) * 2)?;
+        Ok(HorizontalMetrics {
+            advance_width,
+            left_side_bearing: reader.read_i16()? as f32,
+        })
+    }
+}
+
+fn parse_char_code_to_glyph_index_map(bytes: &[u8]) -> Result<CharCodeToGlyphIndexMap> {
     let mut reader = Reader::new(bytes);
     reader.skip(2)?;
-    let mut subtable_bytes = None;
     let subtable_count = reader.read_u16()? as usize;
+    let mut subtables = Vec::new();
     for _ in 0..subtable_count {
         let platform_id = reader.read_u16()?;
         let encoding_id = reader.read_u16()?;
         let offset = reader.read_u32()? as usize;
-        if let (0, _) | (3, 1) | (3, 10) = (platform_id, encoding_id) {
-            subtable_bytes = Some(&bytes[offset..]);
-            break;
+        // prefer subtables that cover all of unicode, then the basic multilingual plane, then
+        // mac roman, which agrees with unicode for ascii
+        let rank = match (platform_id, encoding_id) {
+            (3, 10) | (0, 4) => 0,
+            (0, 6) => 1,
+            (3, 1) | (0, _) => 2,
+            (1, 0) => 3,
+            _ => continue,
+        };
+        subtables.push((rank, bytes.get(offset..).ok_or(Error)?));
+    }
+    subtables.sort_by_key(|&(rank, _)| rank);
+    for (_, subtable_bytes) in subtables {
+        let format = Reader::new(subtable_bytes).read_u16()?;
+        let bytes = &subtable_bytes[2..];
+        match format {
+            0 => return parse_char_code_to_glyph_index_map_format_0(bytes),
+            4 => return parse_char_code_to_glyph_index_map_format_4(bytes),
+            6 => return parse_char_code_to_glyph_index_map_format_6(bytes),
+            12 => return parse_char_code_to_glyph_index_map_format_12(bytes, false),
+            13 => return parse_char_code_to_glyph_index_map_format_12(bytes, true),
+            _ => {}
         }
     }
-    let subtable_bytes = subtable_bytes.ok_or(Error)?;
-    let mut reader = Reader::new(subtable_bytes);
-    let format = reader.read_u16()?;
-    let bytes = &subtable_bytes[2..];
-    match format {
-        4 => parse_char_code_to_glyph_index_map_format_4(bytes),
-        _ => Err(Error),
+    Err(Error)
+}
+
+fn parse_char_code_to_glyph_index_map_format_0(bytes: &[u8]) -> Result<CharCodeToGlyphIndexMap> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(4)?;
+    let mut char_code_to_glyph_index_map = CharCodeToGlyphIndexMap::new();
+    for code in 0..256 {
+        char_code_to_glyph_index_map.insert(code, reader.read_u8()? as usize);
     }
+    Ok(char_code_to_glyph_index_map)
 }
 
-fn parse_char_code_to_glyph_index_map_format_4(bytes: &[u8]) -> Result<Vec<usize>> {
+fn parse_char_code_to_glyph_index_map_format_4(bytes: &[u8]) -> Result<CharCodeToGlyphIndexMap> {
     let mut reader = Reader::new(bytes);
     reader.skip(4)?;
     let seg_count = reader.read_u16()? as usize / 2;
@@ -624,6 +699,9 @@ fn parse_char_code_to_glyph_index_map_format_4(bytes: &[u8]) -> Result<Vec<usize
     let start_code_bytes_start = end_code_bytes_end + 2;
     let id_delta_bytes_start = start_code_bytes_start + seg_count * 2;
     let id_range_offset_bytes_start = id_delta_bytes_start + seg_count * 2;
+    if id_range_offset_bytes_start + seg_count * 2 > bytes.len() {
+        return Err(Error);
+    }
     let end_code_bytes = &bytes[end_code_bytes_start..end_code_bytes_end];
     let start_code_bytes = &bytes[start_code_bytes_start..id_delta_bytes_start];
     let id_delta_bytes = &bytes[id_delta_bytes_start..id_range_offset_bytes_start];
@@ -632,28 +710,402 @@ fn parse_char_code_to_glyph_index_map_format_4(bytes: &[u8]) -> Result<Vec<usize
     let mut start_code_reader = Reader::new(start_code_bytes);
     let mut id_delta_reader = Reader::new(id_delta_bytes);
     let mut id_range_offset_reader = Reader::new(id_range_offset_bytes);
-    let mut char_code_to_glyph_index_map = Vec::new();
+    let mut char_code_to_glyph_index_map = CharCodeToGlyphIndexMap::new();
     for seg_index in 0..seg_count {
-        let end_code = end_code_reader.read_u16()?;
-        let start_code = start_code_reader.read_u16()?;
-        let id_delta = id_delta_reader.read_u16()? as usize;
+        let end_code = end_code_reader.read_u16()? as u32;
+        let start_code = start_code_reader.read_u16()? as u32;
+        let id_delta = id_delta_reader.read_u16()? as u32;
         let id_range_offset = id_range_offset_reader.read_u16()? as usize;
-        for code in start_code..end_code {
-            let mut id = if id_range_offset == 0 {
-                code
-            } else {
-                let id_range_bytes = &id_range_offset_bytes[(seg_index * 2)..];
-                let mut reader = Reader::new(id_range_bytes);
-                reader.skip(id_range_offset + (code - start_code) as usize * 2)?;
-                let id = reader.read_u16()?;
-                id
-            } as usize;
-            if id != 0 {
-                id = (id + id_delta) % 65536;
+        if start_code > end_code {
+            continue;
+        }
+        if id_range_offset == 0 {
+            // the delta wraps around, so a segment can map to two runs of glyphs
+            let wrap_code = (0x10000 - id_delta).max(start_code).min(end_code + 1);
+            char_code_to_glyph_index_map
+                .insert_sequential(start_code..wrap_code, (start_code + id_delta) as usize);
+            char_code_to_glyph_index_map.insert_sequential(
+                wrap_code..(end_code + 1),
+                ((wrap_code + id_delta) & 0xFFFF) as usize,
+            );
+        } else {
+            let mut reader = Reader::new(&id_range_offset_bytes[(seg_index * 2)..]);
+            reader.skip(id_range_offset)?;
+            for code in start_code..=end_code {
+                let id = reader.read_u16()? as u32;
+                if id != 0 {
+                    char_code_to_glyph_index_map.insert(code, ((id + id_delta) & 0xFFFF) as usize);
+                }
             }
-            char_code_to_glyph_index_map.resize(code as usize + 1, 0);
-            char_code_to_glyph_index_map[code as usize] = id;
         }
     }
     Ok(char_code_to_glyph_index_map)
 }
+
+fn parse_char_code_to_glyph_index_map_format_6(bytes: &[u8]) -> Result<CharCodeToGlyphIndexMap> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(4)?;
+    let first_code = reader.read_u16()? as u32;
+    let entry_count = reader.read_u16()? as u32;
+    let mut char_code_to_glyph_index_map = CharCodeToGlyphIndexMap::new();
+    for code in first_code..(first_code + entry_count) {
+        char_code_to_glyph_index_map.insert(code, reader.read_u16()? as usize);
+    }
+    Ok(char_code_to_glyph_index_map)
+}
+
+// formats 12 and 13 share a layout, but in format 13 every code in a group maps to the same glyph
+fn parse_char_code_to_glyph_index_map_format_12(
+    bytes: &[u8],
+    is_many_to_one: bool,
+) -> Result<CharCodeToGlyphIndexMap> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(10)?;
+    let group_count = reader.read_u32()? as usize;
+    let mut char_code_to_glyph_index_map = CharCodeToGlyphIndexMap::new();
+    for _ in 0..group_count {
+        let start_char_code = reader.read_u32()?;
+        let end_char_code = reader.read_u32()?;
+        let glyph_index = reader.read_u32()? as usize;
+        if start_char_code > end_char_code || end_char_code > 0x10FFFF {
+            return Err(Error);
+        }
+        let char_codes = start_char_code..(end_char_code + 1);
+        if is_many_to_one {
+            char_code_to_glyph_index_map.insert_constant(char_codes, glyph_index);
+        } else {
+            char_code_to_glyph_index_map.insert_sequential(char_codes, glyph_index);
+        }
+    }
+    Ok(char_code_to_glyph_index_map)
+}
+
+// the kern table is only used for fonts that do not kern in their GPOS table
+fn parse_kerning(gpos_bytes: Option<&[u8]>, kern_bytes: Option<&[u8]>) -> Result<Kerning> {
+    if let Some(bytes) = gpos_bytes {
+        let kerning = parse_kerning_gpos(bytes)?;
+        if !kerning.is_empty() {
+            return Ok(kerning);
+        }
+    }
+    match kern_bytes {
+        Some(bytes) => parse_kerning_kern(bytes),
+        None => Ok(Kerning::new()),
+    }
+}
+
+fn parse_kerning_kern(bytes: &[u8]) -> Result<Kerning> {
+    let mut reader = Reader::new(bytes);
+    // the apple version of the table has a 32 bit version, which starts with a 1
+    if reader.read_u16()? != 0 {
+        return Ok(Kerning::new());
+    }
+    let subtable_count = reader.read_u16()? as usize;
+    let mut kerning = Kerning::new();
+    let mut subtable_bytes = reader.bytes;
+    for _ in 0..subtable_count {
+        let mut reader = Reader::new(subtable_bytes);
+        reader.skip(2)?;
+        let length = reader.read_u16()? as usize;
+        let coverage = reader.read_u16()?;
+        let format = coverage >> 8;
+        if format != 0 {
+            subtable_bytes = subtable_bytes.get(length..).ok_or(Error)?;
+            continue;
+        }
+        let pair_count = reader.read_u16()? as usize;
+        reader.skip(6)?;
+        // only horizontal kerning values, rather than minimum or cross-stream ones
+        if coverage & 0x7 == 0x1 {
+            let mut pairs = HashMap::new();
+            for _ in 0..pair_count {
+                let left = reader.read_u16()? as usize;
+                let right = reader.read_u16()? as usize;
+                let value = reader.read_i16()? as f32;
+                pairs.insert((left, right), value);
+            }
+            kerning.lookups.push(vec![KerningSubtable::Pairs(pairs)]);
+        }
+        // the length of large subtables overflows, so skip them by their pair count instead
+        subtable_bytes = subtable_bytes.get((14 + pair_count * 6)..).ok_or(Error)?;
+    }
+    Ok(kerning)
+}
+
+fn parse_kerning_gpos(bytes: &[u8]) -> Result<Kerning> {
+    let mut kerning = Kerning::new();
+    for lookup_index in parse_feature_lookup_indices(bytes, &[b"kern"])? {
+        let mut subtables = Vec::new();
+        for subtable_bytes in parse_lookup_subtables(bytes, lookup_index, 2, 9)? {
+            let mut reader = Reader::new(subtable_bytes);
+            let format = reader.read_u16()?;
+            let coverage = parse_coverage(subtable_bytes, reader.read_u16()? as usize)?;
+            let value_format_1 = reader.read_u16()?;
+            let value_format_2 = reader.read_u16()?;
+            let value_size_2 = value_format_2.count_ones() as usize * 2;
+            match format {
+                1 => {
+                    reader.skip(2)?;
+                    let mut pairs = HashMap::new();
+                    for left in coverage {
+                        let pair_set_offset = reader.read_u16()? as usize;
+                        let mut reader =
+                            Reader::new(subtable_bytes.get(pair_set_offset..).ok_or(Error)?);
+                        let pair_count = reader.read_u16()? as usize;
+                        for _ in 0..pair_count {
+                            let right = reader.read_u16()? as usize;
+                            let adjustment = read_x_advance(&mut reader, value_format_1)?;
+                            reader.skip(value_size_2)?;
+                            // an earlier pair in the same subtable takes precedence
+                            pairs.entry((left, right)).or_insert(adjustment);
+                        }
+                    }
+                    subtables.push(KerningSubtable::Pairs(pairs));
+                }
+                2 => {
+                    let left_class_def =
+                        parse_class_def(subtable_bytes, reader.read_u16()? as usize)?;
+                    let right_classes =
+                        parse_class_def(subtable_bytes, reader.read_u16()? as usize)?;
+                    let left_class_count = reader.read_u16()? as usize;
+                    let right_class_count = reader.read_u16()? as usize;
+                    let mut adjustments = Vec::with_capacity(left_class_count * right_class_count);
+                    for _ in 0..left_class_count * right_class_count {
+                        adjustments.push(read_x_advance(&mut reader, value_format_1)?);
+                        reader.skip(value_size_2)?;
+                    }
+                    // glyphs that are covered but not in a class are in class 0
+                    let left_classes = coverage
+                        .into_iter()
+                        .map(|glyph_index| {
+                            (
+                                glyph_index,
+                                left_class_def.get(&glyph_index).cloned().unwrap_or(0),
+                            )
+                        })
+                        .collect();
+                    subtables.push(KerningSubtable::Classes {
+                        left_classes,
+                        right_classes,
+                        right_class_count,
+                        adjustments,
+                    });
+                }
+                _ => {}
+            }
+        }
+        kerning.lookups.push(subtables);
+    }
+    Ok(kerning)
+}
+
+// reads a value record, and returns the adjustment it makes to the advance width
+fn read_x_advance(reader: &mut Reader, value_format: u16) -> Result<f32> {
+    let mut x_advance = 0.0;
+    // every field is 16 bits, the x advance is the third one
+    for bit in 0..8 {
+        if value_format & (1 << bit) != 0 {
+            let value = reader.read_i16()?;
+            if bit == 2 {
+                x_advance = value as f32;
+            }
+        }
+    }
+    Ok(x_advance)
+}
+
+fn parse_ligatures(bytes: &[u8]) -> Result<Ligatures> {
+    let mut ligatures = Ligatures::new();
+    for lookup_index in parse_feature_lookup_indices(bytes, &[b"liga", b"rlig"])? {
+        let mut lookup: HashMap<usize, Vec<Ligature>> = HashMap::new();
+        for subtable_bytes in parse_lookup_subtables(bytes, lookup_index, 4, 7)? {
+            let mut reader = Reader::new(subtable_bytes);
+            if reader.read_u16()? != 1 {
+                continue;
+            }
+            let coverage = parse_coverage(subtable_bytes, reader.read_u16()? as usize)?;
+            reader.skip(2)?;
+            for first in coverage {
+                let ligature_set_bytes = subtable_bytes
+                    .get(reader.read_u16()? as usize..)
+                    .ok_or(Error)?;
+                let mut reader = Reader::new(ligature_set_bytes);
+                let ligature_count = reader.read_u16()? as usize;
+                for _ in 0..ligature_count {
+                    let offset = reader.read_u16()? as usize;
+                    let mut reader = Reader::new(ligature_set_bytes.get(offset..).ok_or(Error)?);
+                    let glyph_index = reader.read_u16()? as usize;
+                    let component_count = reader.read_u16()? as usize;
+                    let mut components = Vec::new();
+                    for _ in 1..component_count {
+                        components.push(reader.read_u16()? as usize);
+                    }
+                    lookup.entry(first).or_insert_with(Vec::new).push(Ligature {
+                        components,
+                        glyph_index,
+                    });
+                }
+            }
+        }
+        ligatures.lookups.push(lookup);
+    }
+    Ok(ligatures)
+}
+
+// returns the indices of the lookups for the features with one of the given tags, in the order
+// in which they are applied. only the default language of the default script, or else of the
+// latin script, is used, so that lookups that several scripts share apply once
+fn parse_feature_lookup_indices(bytes: &[u8], feature_tags: &[&[u8; 4]]) -> Result<Vec<usize>> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(4)?;
+    let script_list_bytes = bytes.get(reader.read_u16()? as usize..).ok_or(Error)?;
+    let feature_list_bytes = bytes.get(reader.read_u16()? as usize..).ok_or(Error)?;
+    let mut reader = Reader::new(script_list_bytes);
+    let script_count = reader.read_u16()? as usize;
+    let mut scripts = Vec::new();
+    for _ in 0..script_count {
+        let tag = reader.read_u32()?.to_be_bytes();
+        let offset = reader.read_u16()? as usize;
+        let rank = match &tag {
+            b"DFLT" => 0,
+            b"latn" => 1,
+            _ => 2,
+        };
+        scripts.push((rank, offset));
+    }
+    let script_offset = match scripts.iter().min_by_key(|&&(rank, _)| rank) {
+        Some(&(_, offset)) => offset,
+        None => return Ok(Vec::new()),
+    };
+    let script_bytes = script_list_bytes.get(script_offset..).ok_or(Error)?;
+    let language_system_offset = Reader::new(script_bytes).read_u16()? as usize;
+    if language_system_offset == 0 {
+        return Ok(Vec::new());
+    }
+    let mut reader = Reader::new(script_bytes.get(language_system_offset..).ok_or(Error)?);
+    reader.skip(2)?;
+    let mut feature_indices = vec![reader.read_u16()?];
+    let feature_index_count = reader.read_u16()? as usize;
+    for _ in 0..feature_index_count {
+        feature_indices.push(reader.read_u16()?);
+    }
+    let mut lookup_indices = Vec::new();
+    for feature_index in feature_indices {
+        // the required feature is 0xFFFF when there is none
+        if feature_index == 0xFFFF {
+            continue;
+        }
+        let mut reader = Reader::new(feature_list_bytes);
+        reader.skip(2 + feature_index as usize * 6)?;
+        let tag = reader.read_u32()?.to_be_bytes();
+        if !feature_tags.contains(&&tag) {
+            continue;
+        }
+        let mut reader = Reader::new(
+            feature_list_bytes
+                .get(reader.read_u16()? as usize..)
+                .ok_or(Error)?,
+        );
+        reader.skip(2)?;
+        let lookup_index_count = reader.read_u16()? as usize;
+        for _ in 0..lookup_index_count {
+            lookup_indices.push(reader.read_u16()? as usize);
+        }
+    }
+    lookup_indices.sort();
+    lookup_indices.dedup();
+    Ok(lookup_indices)
+}
+
+// returns the subtables of a lookup if it has the given type, looking through extension
+// subtables, which only exist to allow 32 bit offsets
+fn parse_lookup_subtables(
+    bytes: &[u8],
+    lookup_index: usize,
+    lookup_type: u16,
+    extension_lookup_type: u16,
+) -> Result<Vec<&[u8]>> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(8)?;
+    let lookup_list_bytes = bytes.get(reader.read_u16()? as usize..).ok_or(Error)?;
+    let mut reader = Reader::new(lookup_list_bytes);
+    reader.skip(2 + lookup_index * 2)?;
+    let lookup_bytes = lookup_list_bytes
+        .get(reader.read_u16()? as usize..)
+        .ok_or(Error)?;
+    let mut reader = Reader::new(lookup_bytes);
+    let actual_lookup_type = reader.read_u16()?;
+    reader.skip(2)?;
+    let subtable_count = reader.read_u16()? as usize;
+    let mut subtables = Vec::new();
+    for _ in 0..subtable_count {
+        let subtable_bytes = lookup_bytes
+            .get(reader.read_u16()? as usize..)
+            .ok_or(Error)?;
+        if actual_lookup_type == lookup_type {
+            subtables.push(subtable_bytes);
+        } else if actual_lookup_type == extension_lookup_type {
+            let mut reader = Reader::new(subtable_bytes);
+            reader.skip(2)?;
+            if reader.read_u16()? == lookup_type {
+                subtables.push(
+                    subtable_bytes
+                        .get(reader.read_u32()? as usize..)
+                        .ok_or(Error)?,
+                );
+            }
+        }
+    }
+    Ok(subtables)
+}
+
+// returns the glyph indices in a coverage table, in order of coverage index
+fn parse_coverage(bytes: &[u8], offset: usize) -> Result<Vec<usize>> {
+    let mut reader = Reader::new(bytes.get(offset..).ok_or(Error)?);
+    let format = reader.read_u16()?;
+    let count = reader.read_u16()? as usize;
+    let mut glyph_indices = Vec::new();
+    for _ in 0..count {
+        match format {
+            1 => glyph_indices.push(reader.read_u16()? as usize),
+            2 => {
+                let start_glyph_index = reader.read_u16()? as usize;
+                let end_glyph_index = reader.read_u16()? as usize;
+                reader.skip(2)?;
+                glyph_indices.extend(start_glyph_index..=end_glyph_index);
+            }
+            _ => return Err(Error),
+        }
+    }
+    Ok(glyph_indices)
+}
+
+// returns the classes of the glyphs in a class definition table, glyphs it leaves out are in
+// class 0
+fn parse_class_def(bytes: &[u8], offset: usize) -> Result<HashMap<usize, usize>> {
+    let mut reader = Reader::new(bytes.get(offset..).ok_or(Error)?);
+    let format = reader.read_u16()?;
+    let mut classes = HashMap::new();
+    match format {
+        1 => {
+            let start_glyph_index = reader.read_u16()? as usize;
+            let glyph_count = reader.read_u16()? as usize;
+            for glyph_index in start_glyph_index..(start_glyph_index + glyph_count) {
+                classes.insert(glyph_index, reader.read_u16()? as usize);
+            }
+        }
+        2 => {
+            let range_count = reader.read_u16()? as usize;
+            for _ in 0..range_count {
+                let start_glyph_index = reader.read_u16()? as usize;
+                let end_glyph_index = reader.read_u16()? as usize;
+                let class = reader.read_u16()? as usize;
+                for glyph_index in start_glyph_index..=end_glyph_index {
+                    classes.insert(glyph_index, class);
+                }
+            }
+        }
+        _ => return Err(Error),
+    }
+    Ok(classes)
+}
@@ -2,6 +2,96 @@ use ttf_parser;
 use std::fs;
 use std::path::Path;
 
+fn read_resource(name: &str) -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../resources").join(name)).unwrap()
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+// returns the offset of the table record for `tag` in the table directory
+fn find_table_record(bytes: &[u8], tag: &[u8; 4]) -> usize {
+    let table_count = read_u16(bytes, 4) as usize;
+    (0..table_count)
+        .map(|index| 12 + index * 16)
+        .find(|&record| &bytes[record..record + 4] == tag)
+        .unwrap()
+}
+
+fn cmap_bytes(bytes: &[u8]) -> &[u8] {
+    let record = find_table_record(bytes, b"cmap");
+    let offset = read_u32(bytes, record + 8) as usize;
+    let length = read_u32(bytes, record + 12) as usize;
+    &bytes[offset..offset + length]
+}
+
+// returns a copy of the subtable of the cmap of the font for a platform and encoding
+fn subtable(bytes: &[u8], platform_id: u16, encoding_id: u16) -> Vec<u8> {
+    let cmap = cmap_bytes(bytes);
+    let subtable_count = read_u16(cmap, 2) as usize;
+    let offset = (0..subtable_count)
+        .map(|index| 4 + index * 8)
+        .find(|&record| read_u16(cmap, record) == platform_id && read_u16(cmap, record + 2) == encoding_id)
+        .map(|record| read_u32(cmap, record + 4) as usize)
+        .unwrap();
+    // formats 0, 4 and 6 store a 16 bit length after the format
+    let length = read_u16(cmap, offset + 2) as usize;
+    cmap[offset..offset + length].to_vec()
+}
+
+// appends `cmap` to the font and points the cmap table record at it
+fn with_cmap(bytes: &[u8], cmap: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    while bytes.len() % 4 != 0 {
+        bytes.push(0);
+    }
+    let offset = bytes.len() as u32;
+    bytes.extend_from_slice(cmap);
+    let record = find_table_record(&bytes, b"cmap");
+    bytes[record + 8..record + 12].copy_from_slice(&offset.to_be_bytes());
+    bytes[record + 12..record + 16].copy_from_slice(&(cmap.len() as u32).to_be_bytes());
+    bytes
+}
+
+// builds a cmap table out of (platform id, encoding id, subtable) entries
+fn build_cmap(subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+    let mut cmap = Vec::new();
+    cmap.extend_from_slice(&0u16.to_be_bytes());
+    cmap.extend_from_slice(&(subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + subtables.len() * 8;
+    for (platform_id, encoding_id, subtable) in subtables {
+        cmap.extend_from_slice(&platform_id.to_be_bytes());
+        cmap.extend_from_slice(&encoding_id.to_be_bytes());
+        cmap.extend_from_slice(&(offset as u32).to_be_bytes());
+        offset += subtable.len();
+    }
+    for (_, _, subtable) in subtables {
+        cmap.extend_from_slice(subtable);
+    }
+    cmap
+}
+
+// formats 12 and 13, from (start char code, end char code, glyph index) groups
+fn build_format_12(format: u16, groups: &[(u32, u32, u32)]) -> Vec<u8> {
+    let mut subtable = Vec::new();
+    subtable.extend_from_slice(&format.to_be_bytes());
+    subtable.extend_from_slice(&0u16.to_be_bytes());
+    subtable.extend_from_slice(&(16 + groups.len() as u32 * 12).to_be_bytes());
+    subtable.extend_from_slice(&0u32.to_be_bytes());
+    subtable.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+    for (start, end, glyph_index) in groups {
+        subtable.extend_from_slice(&start.to_be_bytes());
+        subtable.extend_from_slice(&end.to_be_bytes());
+        subtable.extend_from_slice(&glyph_index.to_be_bytes());
+    }
+    subtable
+}
+
 #[test]
 fn main() {
     for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../resources")).unwrap() {
@@ -11,8 +101,537 @@ fn main() {
         }
         println!("{}", path.file_stem().unwrap().to_str().unwrap());
         let font = ttf_parser::parse_ttf(&fs::read(path).unwrap()).unwrap();
-        for char_code in 0..font.char_code_to_glyph_index_map.len() {
-            assert!(font.char_code_to_glyph_index_map[char_code] <= font.glyphs.len());
+        for (_, glyph_index) in font.char_code_to_glyph_index_map.iter() {
+            assert!(glyph_index < font.glyphs.len());
+        }
+    }
+}
+
+#[test]
+fn known_characters() {
+    // glyph indices looked up by glyph name in the post table of each font. '~' and '9' end a
+    // cmap segment, which used to leave them unmapped
+    let fonts: &[(&str, &[(char, usize)])] = &[
+        ("Inconsolata-Regular.ttf", &[(' ', 3), ('A', 4), ('a', 234), ('0', 477), ('~', 632), ('€', 599), ('\u{FFFD}', 795)]),
+        ("Inconsolata-Bold.ttf", &[(' ', 3), ('A', 4), ('a', 234), ('0', 476), ('~', 631), ('€', 598), ('\u{FFFD}', 794)]),
+        ("LiberationMono-Regular.ttf", &[(' ', 3), ('0', 19), ('A', 36), ('a', 68), ('~', 97), ('Ж', 432), ('€', 544)]),
+        ("LiberationMono-Bold.ttf", &[(' ', 3), ('0', 19), ('A', 36), ('a', 68), ('~', 97), ('Ж', 432), ('€', 544)]),
+        ("Ubuntu-R.ttf", &[(' ', 3), ('0', 19), ('A', 36), ('a', 68), ('~', 97), ('Ж', 726), ('€', 98)]),
+    ];
+    for (name, chars) in fonts {
+        let font = ttf_parser::parse_ttf(&read_resource(name)).unwrap();
+        for (c, glyph_index) in chars.iter() {
+            assert_eq!(font.char_code_to_glyph_index_map.get(*c as u32), *glyph_index, "{} in {}", c, name);
+        }
+        assert_eq!(font.char_code_to_glyph_index_map.get(0x1F600), 0);
+        assert_eq!(font.char_code_to_glyph_index_map.get(0xFFFF), 0);
+    }
+}
+
+#[test]
+fn format_6_mac_roman() {
+    let bytes = read_resource("LiberationMono-Regular.ttf");
+    let subtable = subtable(&bytes, 1, 0);
+    assert_eq!(read_u16(&subtable, 0), 6);
+    let font = ttf_parser::parse_ttf(&with_cmap(&bytes, &build_cmap(&[(1, 0, subtable)]))).unwrap();
+    let unicode_font = ttf_parser::parse_ttf(&bytes).unwrap();
+    // mac roman agrees with unicode for ascii
+    for c in 0x20..0x7F {
+        assert_eq!(font.char_code_to_glyph_index_map.get(c), unicode_font.char_code_to_glyph_index_map.get(c));
+    }
+}
+
+#[test]
+fn format_0() {
+    let bytes = read_resource("LiberationMono-Regular.ttf");
+    let mut subtable = vec![0, 0, 1, 6, 0, 0];
+    subtable.extend((0..=255u8).map(|code| if code >= 0x41 && code <= 0x5A { code - 0x41 + 36 } else { 0 }));
+    let font = ttf_parser::parse_ttf(&with_cmap(&bytes, &build_cmap(&[(1, 0, subtable)]))).unwrap();
+    assert_eq!(font.char_code_to_glyph_index_map.get('A' as u32), 36);
+    assert_eq!(font.char_code_to_glyph_index_map.get('Z' as u32), 61);
+    assert_eq!(font.char_code_to_glyph_index_map.get('a' as u32), 0);
+    assert_eq!(font.char_code_to_glyph_index_map.iter().count(), 26);
+}
+
+#[test]
+fn format_12_astral_planes() {
+    let bytes = read_resource("LiberationMono-Regular.ttf");
+    let format_12 = build_format_12(12, &[(0x41, 0x5A, 36), (0x1F600, 0x1F64F, 100), (0x10FFFF, 0x10FFFF, 5)]);
+    // the bmp-only subtable of the font loses against the full unicode one
+    let format_4 = subtable(&bytes, 3, 1);
+    let font = ttf_parser::parse_ttf(&with_cmap(&bytes, &build_cmap(&[(3, 1, format_4), (3, 10, format_12)]))).unwrap();
+    let map = &font.char_code_to_glyph_index_map;
+    assert_eq!(map.get('A' as u32), 36);
+    assert_eq!(map.get('a' as u32), 0);
+    assert_eq!(map.get('😀' as u32), 100);
+    assert_eq!(map.get('🙏' as u32), 100 + 0x4F);
+    assert_eq!(map.get(0x1F650), 0);
+    assert_eq!(map.get(0x10FFFF), 5);
+    assert_eq!(map.iter().count(), 26 + 0x50 + 1);
+}
+
+#[test]
+fn format_13_many_to_one() {
+    let bytes = read_resource("LiberationMono-Regular.ttf");
+    let format_13 = build_format_12(13, &[(0x20, 0xFFFF, 3), (0x10000, 0x10FFFF, 9)]);
+    let font = ttf_parser::parse_ttf(&with_cmap(&bytes, &build_cmap(&[(0, 6, format_13)]))).unwrap();
+    let map = &font.char_code_to_glyph_index_map;
+    assert_eq!(map.get(0), 0);
+    assert_eq!(map.get(0x20), 3);
+    assert_eq!(map.get(0xFFFF), 3);
+    assert_eq!(map.get(0x10000), 9);
+    assert_eq!(map.get(0x10FFFF), 9);
+}
+
+#[test]
+fn kerning_from_gpos() {
+    let font = ttf_parser::parse_ttf(&read_resource("Ubuntu-R.ttf")).unwrap();
+    let glyph_index = |c: char| font.char_code_to_glyph_index_map.get(c as u32);
+    // the font has both tables, and kerns these pairs the same way in its kern table
+    for &(left, right, adjustment) in &[
+        ('A', 'V', -62.0),
+        ('A', 'T', -64.0),
+        ('T', 'o', -55.0),
+        ('V', 'a', -25.0),
+    ] {
+        assert_eq!(
+            font.kerning.get(glyph_index(left), glyph_index(right)),
+            adjustment,
+            "{}{}",
+            left,
+            right
+        );
+    }
+    assert_eq!(font.kerning.get(glyph_index('0'), glyph_index('1')), 0.0);
+    assert_eq!(
+        font.kerning.get(glyph_index('V'), glyph_index('A')),
+        font.kerning.get(glyph_index('A'), glyph_index('V'))
+    );
+}
+
+#[test]
+fn kerning_from_kern_table() {
+    let bytes = read_resource("Ubuntu-R.ttf");
+    let gpos_font = ttf_parser::parse_ttf(&bytes).unwrap();
+    // without a GPOS table, the kern table is used
+    let mut bytes = bytes.clone();
+    let record = find_table_record(&bytes, b"GPOS");
+    bytes[record..record + 4].copy_from_slice(b"XPOS");
+    let font = ttf_parser::parse_ttf(&bytes).unwrap();
+    assert_eq!(font.kerning.lookups.len(), 1);
+    let glyph_index = |c: char| font.char_code_to_glyph_index_map.get(c as u32);
+    assert_eq!(font.kerning.get(glyph_index('A'), glyph_index('V')), -62.0);
+    for (left, right) in [('T', 'o'), ('L', 'T'), ('a', 'b')].iter() {
+        let (left, right) = (glyph_index(*left), glyph_index(*right));
+        assert_eq!(
+            font.kerning.get(left, right),
+            gpos_font.kerning.get(left, right)
+        );
+    }
+}
+
+#[test]
+fn no_kerning_or_ligatures() {
+    for name in &["LiberationMono-Regular.ttf", "Inconsolata-Regular.ttf"] {
+        let font = ttf_parser::parse_ttf(&read_resource(name)).unwrap();
+        assert!(font.kerning.is_empty(), "{}", name);
+        assert!(font.ligatures.is_empty(), "{}", name);
+    }
+}
+
+#[test]
+fn ligatures() {
+    let font = ttf_parser::parse_ttf(&read_resource("Ubuntu-R.ttf")).unwrap();
+    let glyph_indices = |text: &str| {
+        text.chars()
+            .map(|c| font.char_code_to_glyph_index_map.get(c as u32))
+            .collect::<Vec<_>>()
+    };
+    // glyph indices of f_f, f_i, f_l, f_f_i and f_f_l in the post table. the first ligature
+    // that matches wins, so fffi is ff followed by fi
+    assert_eq!(
+        font.ligatures.substitute(&glyph_indices("fi")),
+        vec![(428, 2)]
+    );
+    assert_eq!(
+        font.ligatures.substitute(&glyph_indices("ffl")),
+        vec![(432, 3)]
+    );
+    let a = glyph_indices("a")[0];
+    let f = glyph_indices("f")[0];
+    assert_eq!(
+        font.ligatures.substitute(&glyph_indices("afflfffia")),
+        vec![(a, 1), (432, 3), (426, 2), (428, 2), (a, 1)]
+    );
+    assert_eq!(font.ligatures.substitute(&glyph_indices("f")), vec![(f, 1)]);
+    assert_eq!(font.ligatures.substitute(&[]), vec![]);
+}
+
+// charstring operands, as 16 bit integers
+fn cs(values: &[i16]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in values {
+        bytes.push(28);
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    bytes
+}
+
+// dict operands, as 32 bit integers, so that offsets can be filled in without changing sizes
+fn dict_operand(value: usize) -> Vec<u8> {
+    let mut bytes = vec![29];
+    bytes.extend_from_slice(&(value as i32).to_be_bytes());
+    bytes
+}
+
+// the count of an index is 16 bits in CFF and 32 bits in CFF2
+fn build_index(items: &[Vec<u8>], is_cff2: bool) -> Vec<u8> {
+    let mut index = Vec::new();
+    if is_cff2 {
+        index.extend_from_slice(&(items.len() as u32).to_be_bytes());
+    } else {
+        index.extend_from_slice(&(items.len() as u16).to_be_bytes());
+    }
+    if items.is_empty() {
+        return index;
+    }
+    index.push(4);
+    let mut offset = 1u32;
+    index.extend_from_slice(&offset.to_be_bytes());
+    for item in items {
+        offset += item.len() as u32;
+        index.extend_from_slice(&offset.to_be_bytes());
+    }
+    for item in items {
+        index.extend_from_slice(item);
+    }
+    index
+}
+
+fn build_cff(
+    charstrings: &[Vec<u8>],
+    global_subrs: &[Vec<u8>],
+    local_subrs: &[Vec<u8>],
+) -> Vec<u8> {
+    let top_dict = |charstrings_offset: usize, private_offset: usize| {
+        let mut dict = dict_operand(charstrings_offset);
+        dict.push(17);
+        dict.extend(dict_operand(6));
+        dict.extend(dict_operand(private_offset));
+        dict.push(18);
+        dict
+    };
+    let mut cff = vec![1, 0, 4, 4];
+    cff.extend(build_index(&[b"Test".to_vec()], false));
+    let top_dict_index_start = cff.len();
+    cff.extend(build_index(&[top_dict(0, 0)], false));
+    cff.extend(build_index(&[], false));
+    cff.extend(build_index(global_subrs, false));
+    let charstrings_offset = cff.len();
+    cff.extend(build_index(charstrings, false));
+    let private_offset = cff.len();
+    // the local subroutines follow the private dict, which is 6 bytes long
+    cff.extend(dict_operand(6));
+    cff.push(19);
+    cff.extend(build_index(local_subrs, false));
+    let top_dict_index = build_index(&[top_dict(charstrings_offset, private_offset)], false);
+    cff[top_dict_index_start..top_dict_index_start + top_dict_index.len()]
+        .copy_from_slice(&top_dict_index);
+    cff
+}
+
+// an item variation store with a single item variation data for 2 regions
+fn build_variation_store() -> Vec<u8> {
+    let mut store = Vec::new();
+    store.extend_from_slice(&1u16.to_be_bytes());
+    store.extend_from_slice(&0u32.to_be_bytes());
+    store.extend_from_slice(&1u16.to_be_bytes());
+    store.extend_from_slice(&12u32.to_be_bytes());
+    store.extend_from_slice(&0u16.to_be_bytes());
+    store.extend_from_slice(&0u16.to_be_bytes());
+    store.extend_from_slice(&2u16.to_be_bytes());
+    store.extend_from_slice(&0u16.to_be_bytes());
+    store.extend_from_slice(&1u16.to_be_bytes());
+    let mut bytes = (store.len() as u16).to_be_bytes().to_vec();
+    bytes.extend(store);
+    bytes
+}
+
+fn build_cff2(charstrings: &[Vec<u8>]) -> Vec<u8> {
+    let top_dict = |charstrings_offset: usize, fd_array_offset: usize, vstore_offset: usize| {
+        let mut dict = dict_operand(charstrings_offset);
+        dict.push(17);
+        dict.extend(dict_operand(fd_array_offset));
+        dict.extend(&[12, 36]);
+        dict.extend(dict_operand(vstore_offset));
+        dict.push(24);
+        dict
+    };
+    let top_dict_length = top_dict(0, 0, 0).len();
+    let mut cff = vec![2, 0, 5];
+    cff.extend_from_slice(&(top_dict_length as u16).to_be_bytes());
+    cff.extend(top_dict(0, 0, 0));
+    cff.extend(build_index(&[], true));
+    let vstore_offset = cff.len();
+    cff.extend(build_variation_store());
+    let charstrings_offset = cff.len();
+    cff.extend(build_index(charstrings, true));
+    let fd_array_offset = cff.len();
+    // an empty private dict at the end of the table
+    let font_dict_length = build_index(&[vec![0; 11]], true).len();
+    let mut font_dict = dict_operand(0);
+    font_dict.extend(dict_operand(fd_array_offset + font_dict_length));
+    font_dict.push(18);
+    cff.extend(build_index(&[font_dict], true));
+    cff[5..5 + top_dict_length].copy_from_slice(&top_dict(
+        charstrings_offset,
+        fd_array_offset,
+        vstore_offset,
+    ));
+    cff
+}
+
+// builds an OpenType font with CFF outlines, of which the glyphs are mapped to the chars
+// starting at A, and all have an advance width of 600
+fn build_otf(cff_tag: &[u8; 4], cff: Vec<u8>, glyph_count: u16) -> Vec<u8> {
+    let liberation = read_resource("LiberationMono-Regular.ttf");
+    let table = |tag: &[u8; 4]| {
+        let record = find_table_record(&liberation, tag);
+        let offset = read_u32(&liberation, record + 8) as usize;
+        let length = read_u32(&liberation, record + 12) as usize;
+        liberation[offset..offset + length].to_vec()
+    };
+    let mut hhea = table(b"hhea");
+    hhea[34..36].copy_from_slice(&glyph_count.to_be_bytes());
+    let mut hmtx = Vec::new();
+    for _ in 0..glyph_count {
+        hmtx.extend_from_slice(&600u16.to_be_bytes());
+        hmtx.extend_from_slice(&0i16.to_be_bytes());
+    }
+    // version 0.5 of the maxp table only has the glyph count
+    let mut maxp = 0x00005000u32.to_be_bytes().to_vec();
+    maxp.extend_from_slice(&glyph_count.to_be_bytes());
+    let cmap = build_cmap(&[(
+        3,
+        10,
+        build_format_12(12, &[(0x41, 0x41 + glyph_count as u32 - 1, 0)]),
+    )]);
+    let tables = vec![
+        (*cff_tag, cff),
+        (*b"cmap", cmap),
+        (*b"head", table(b"head")),
+        (*b"hhea", hhea),
+        (*b"hmtx", hmtx),
+        (*b"maxp", maxp),
+    ];
+    let mut bytes = b"OTTO".to_vec();
+    bytes.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&[0; 6]);
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, table) in &tables {
+        bytes.extend_from_slice(tag);
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes.extend_from_slice(&(offset as u32).to_be_bytes());
+        bytes.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) / 4 * 4;
+    }
+    for (_, table) in &tables {
+        bytes.extend_from_slice(table);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+    }
+    bytes
+}
+
+fn on_curve_points(glyph: &font::Glyph) -> Vec<(f32, f32)> {
+    glyph
+        .outline
+        .points()
+        .iter()
+        .filter(|point| point.is_on_curve)
+        .map(|point| (point.point.x, point.point.y))
+        .collect()
+}
+
+#[test]
+fn cff_outlines() {
+    let charstrings = vec![
+        // an empty notdef
+        vec![14],
+        // a rectangle, with the advance width before the first moveto
+        [
+            cs(&[550, 100, 100]),
+            vec![21],
+            cs(&[500, 700, -500]),
+            vec![6, 14],
+        ]
+        .concat(),
+        // a curve, with stem hints, an implied vstem before the hintmask, and a width
+        [
+            cs(&[550, 0, 10]),
+            vec![18],
+            cs(&[0, 10]),
+            vec![19, 0xC0],
+            cs(&[0, 0]),
+            vec![21],
+            cs(&[100, 0, 100, 100, 0, 100]),
+            vec![8, 14],
+        ]
+        .concat(),
+        // lines from a local and a global subroutine, of which the numbers are biased by 107
+        [cs(&[-107]), vec![10], cs(&[-107]), vec![29, 14]].concat(),
+        // a horizontal flex, and a second contour
+        [
+            cs(&[0, 0]),
+            vec![21],
+            cs(&[10, 10, 5, 10, 10, 10, 10]),
+            vec![12, 34],
+            cs(&[0, 20]),
+            vec![21],
+            cs(&[10, 10]),
+            vec![5, 14],
+        ]
+        .concat(),
+    ];
+    let global_subrs = vec![[cs(&[0, 10]), vec![5, 11]].concat()];
+    let local_subrs = vec![[cs(&[0, 0]), vec![21], cs(&[10, 0]), vec![5, 11]].concat()];
+    let bytes = build_otf(
+        b"CFF ",
+        build_cff(&charstrings, &global_subrs, &local_subrs),
+        5,
+    );
+    let font = ttf_parser::parse_ttf(&bytes).unwrap();
+    assert_eq!(font.glyphs.len(), 5);
+    assert_eq!(font.char_code_to_glyph_index_map.get('B' as u32), 1);
+    assert!(font.glyphs[0].outline.points().is_empty());
+    assert_eq!(font.glyphs[1].horizontal_metrics.advance_width, 600.0);
+
+    let rectangle = &font.glyphs[1];
+    assert_eq!(
+        on_curve_points(rectangle),
+        vec![
+            (100.0, 100.0),
+            (600.0, 100.0),
+            (600.0, 800.0),
+            (100.0, 800.0)
+        ]
+    );
+    assert_eq!(rectangle.outline.points().len(), 4);
+    assert_eq!(rectangle.bounds.p_min.x, 100.0);
+    assert_eq!(rectangle.bounds.p_max.y, 800.0);
+
+    // the cubic curve is split into quadratic curves, each within half a unit of it
+    let curve = font.glyphs[2].outline.points();
+    assert!(curve.len() > 3);
+    assert_eq!(
+        on_curve_points(&font.glyphs[2]).last(),
+        Some(&(200.0, 200.0))
+    );
+    let cubic = |t: f32| {
+        let s = 1.0 - t;
+        (
+            3.0 * s * s * t * 100.0 + 3.0 * s * t * t * 200.0 + t * t * t * 200.0,
+            3.0 * s * t * t * 100.0 + t * t * t * 200.0,
+        )
+    };
+    for piece in curve.windows(3).step_by(2) {
+        let (p0, p1, p2) = (piece[0].point, piece[1].point, piece[2].point);
+        assert!(piece[0].is_on_curve && !piece[1].is_on_curve && piece[2].is_on_curve);
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let s = 1.0 - t;
+            let x = s * s * p0.x + 2.0 * s * t * p1.x + t * t * p2.x;
+            let y = s * s * p0.y + 2.0 * s * t * p1.y + t * t * p2.y;
+            let distance = (0..=1000)
+                .map(|step| {
+                    let (cx, cy) = cubic(step as f32 / 1000.0);
+                    ((cx - x) * (cx - x) + (cy - y) * (cy - y)).sqrt()
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!(distance < 0.6, "{}", distance);
         }
     }
+
+    assert_eq!(
+        on_curve_points(&font.glyphs[3]),
+        vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]
+    );
+
+    let flex = &font.glyphs[4];
+    assert_eq!(flex.outline.contours().count(), 2);
+    let first_contour = flex.outline.contours().next().unwrap().points();
+    assert_eq!(first_contour.last().unwrap().point.x, 60.0);
+    assert_eq!(first_contour.last().unwrap().point.y, 0.0);
+}
+
+#[test]
+fn cff_curve_end_points() {
+    // each glyph moves to the origin and draws with one operator, the curves that end on the
+    // axis they start on have to end exactly there
+    let operators: Vec<(&[i16], Vec<u8>, (f32, f32))> = vec![
+        // hflex1, the last dy makes up for dy1, dy2 and dy5
+        (&[10, 5, 10, 5, 10, 10, 10, -5, 10], vec![12, 36], (60.0, 0.0)),
+        // flex, with a flex depth that does not move anything
+        (
+            &[10, 5, 10, 5, 10, 0, 10, 0, 10, -5, 10, -5, 50],
+            vec![12, 35],
+            (60.0, 0.0),
+        ),
+        // flex1 ends horizontally when it moves more along x, and vertically otherwise
+        (&[10, 5, 10, 5, 10, 0, 10, 0, 10, -5, 10], vec![12, 37], (60.0, 0.0)),
+        (&[5, 10, 5, 10, 0, 10, 0, 10, -5, 10, 10], vec![12, 37], (0.0, 60.0)),
+        // vvcurveto and hhcurveto, with a dx1 and a dy1 for the first curve
+        (&[5, 10, 10, 10, 20, 10, 10, 10, 20], vec![26], (25.0, 80.0)),
+        (&[5, 10, 10, 10, 20, 10, 10, 10, 20], vec![27], (80.0, 25.0)),
+        // hvcurveto and vhcurveto, with a last move along the other axis
+        (&[10, 10, 10, 10, 10, 10, 10, 10, 5], vec![31], (40.0, 45.0)),
+        (&[10, 10, 10, 10, 5], vec![30], (20.0, 25.0)),
+        // rcurveline and rlinecurve
+        (&[10, 0, 10, 10, 0, 10, 5, 5], vec![24], (25.0, 25.0)),
+        (&[5, 5, 10, 0, 10, 10, 0, 10], vec![25], (25.0, 25.0)),
+    ];
+    let charstrings: Vec<Vec<u8>> = operators
+        .iter()
+        .map(|(args, operator, _)| {
+            [cs(&[0, 0]), vec![21], cs(args), operator.clone(), vec![14]].concat()
+        })
+        .collect();
+    let bytes = build_otf(
+        b"CFF ",
+        build_cff(&charstrings, &[], &[]),
+        charstrings.len() as u16,
+    );
+    let font = ttf_parser::parse_ttf(&bytes).unwrap();
+    for (glyph, (_, operator, end)) in font.glyphs.iter().zip(operators.iter()) {
+        let points = on_curve_points(glyph);
+        assert_eq!(points.first(), Some(&(0.0, 0.0)), "{:?}", operator);
+        assert_eq!(points.last(), Some(end), "{:?}", operator);
+    }
+    // the line of rcurveline comes after its curve, and the one of rlinecurve before
+    let rcurveline = on_curve_points(&font.glyphs[8]);
+    assert_eq!(rcurveline[rcurveline.len() - 2], (20.0, 20.0));
+    assert_eq!(on_curve_points(&font.glyphs[9])[1], (5.0, 5.0));
+}
+
+#[test]
+fn cff2_outlines() {
+    // the blend keeps the default value of 300 and drops its deltas for the 2 regions
+    let charstring = [
+        cs(&[100, 200]),
+        vec![21],
+        cs(&[300, 10, 20, 1]),
+        vec![16],
+        cs(&[0]),
+        vec![5],
+        cs(&[0, 300]),
+        vec![5],
+    ]
+    .concat();
+    let font = ttf_parser::parse_ttf(&build_otf(b"CFF2", build_cff2(&[charstring]), 1)).unwrap();
+    assert_eq!(
+        on_curve_points(&font.glyphs[0]),
+        vec![(100.0, 200.0), (400.0, 200.0), (400.0, 500.0)]
+    );
+    assert_eq!(font.glyphs[0].horizontal_metrics.advance_width, 600.0);
 }
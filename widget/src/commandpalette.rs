@@ -0,0 +1,119 @@
+// a searchable list of every command in a Menu, so platforms without a native menu can reach them
+use render::*;
+use crate::fuzzypicker::*;
+
+#[derive(Clone)]
+pub struct CommandPaletteItem {
+    pub name: String,
+    pub path: String,
+    pub key: String,
+    pub signal: Signal,
+    pub value: usize,
+    pub enabled: bool
+}
+
+#[derive(Clone, PartialEq)]
+pub enum CommandPaletteEvent {
+    None,
+    Fire {signal: Signal, value: usize},
+    Cancel
+}
+
+#[derive(Clone)]
+pub struct CommandPalette {
+    pub picker: FuzzyPicker,
+    pub _items: Vec<CommandPaletteItem>
+}
+
+impl CommandPalette {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            picker: FuzzyPicker::proto(cx),
+            _items: Vec::new()
+        }
+    }
+
+    // depth first, so the palette lists commands in menu order
+    pub fn flatten_menu(menu: &Menu) -> Vec<CommandPaletteItem> {
+        fn flatten_recur(menu: &Menu, path: &str, items: &mut Vec<CommandPaletteItem>) {
+            match menu {
+                Menu::Main {items: sub_items} => {
+                    for item in sub_items {
+                        flatten_recur(item, path, items);
+                    }
+                },
+                Menu::Sub {name, items: sub_items, ..} => {
+                    let path = if path.len() > 0 {format!("{} > {}", path, name)} else {name.clone()};
+                    for item in sub_items {
+                        flatten_recur(item, &path, items);
+                    }
+                },
+                Menu::Item {name, key, signal, value, enabled} => {
+                    items.push(CommandPaletteItem {
+                        name: name.clone(),
+                        path: path.to_string(),
+                        key: key.clone(),
+                        signal: *signal,
+                        value: *value,
+                        enabled: *enabled
+                    });
+                },
+                Menu::Line => ()
+            }
+        }
+        let mut items = Vec::new();
+        flatten_recur(menu, "", &mut items);
+        items
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.picker.is_open()
+    }
+
+    pub fn open(&mut self, cx: &mut Cx, menu: &Menu) {
+        self._items = Self::flatten_menu(menu);
+        let platform_type = &cx.platform_type;
+        let picker_items = self._items.iter().map( | item | {
+            let mut detail = item.path.clone();
+            let key_label = Menu::key_label(&item.key, platform_type);
+            if key_label.len() > 0 {
+                detail.push_str(&format!("  {}", key_label));
+            }
+            if !item.enabled {
+                detail.push_str("  (disabled)");
+            }
+            FuzzyPickerItem {
+                label: item.name.clone(),
+                detail: detail,
+                // disabled commands stay findable but sink below the enabled ones
+                boost: if item.enabled {0.} else {-2.}
+            }
+        }).collect();
+        self.picker.open(cx, picker_items);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        self.picker.close(cx);
+    }
+
+    // fires the signal of the chosen command, selecting a disabled command just closes the palette
+    pub fn handle_command_palette(&mut self, cx: &mut Cx, event: &mut Event) -> CommandPaletteEvent {
+        match self.picker.handle_fuzzy_picker(cx, event) {
+            FuzzyPickerEvent::Select {index} => {
+                if let Some(item) = self._items.get(index) {
+                    if item.enabled {
+                        cx.send_signal(item.signal, item.value);
+                        return CommandPaletteEvent::Fire {signal: item.signal, value: item.value}
+                    }
+                }
+                CommandPaletteEvent::Cancel
+            },
+            FuzzyPickerEvent::Cancel => CommandPaletteEvent::Cancel,
+            FuzzyPickerEvent::None => CommandPaletteEvent::None
+        }
+    }
+
+    pub fn draw_command_palette(&mut self, cx: &mut Cx) {
+        self.picker.draw_fuzzy_picker(cx);
+    }
+}
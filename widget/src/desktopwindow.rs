@@ -76,6 +76,10 @@ impl DesktopWindow {
     pub fn handle_desktop_window(&mut self, cx: &mut Cx, event: &mut Event) -> DesktopWindowEvent {
         //self.main_view.handle_scroll_bars(cx, event);
         //self.inner_view.handle_scroll_bars(cx, event);
+        if let Some(menu) = self._last_menu.take() {
+            self.window_menu.handle_window_menu(cx, event, &menu);
+            self._last_menu = Some(menu);
+        }
         if let ButtonEvent::Clicked = self.vr_btn.handle_button(cx, event) {
             if self.window.vr_is_presenting(cx) {
                 self.window.vr_stop_presenting(cx);
@@ -115,7 +119,10 @@ impl DesktopWindow {
                 },
                 Event::WindowDragQuery(dq) => {
                     if dq.window_id == window_id {
-                        if dq.abs.x < self.caption_size.x && dq.abs.y < self.caption_size.y {
+                        if self.window_menu.bar_contains(dq.abs) {
+                            // the menu bar handles its own clicks
+                        }
+                        else if dq.abs.x < self.caption_size.x && dq.abs.y < self.caption_size.y {
                             if dq.abs.x < 50. {
                                 dq.response = WindowDragQueryResponse::SysMenu;
                             }
@@ -174,12 +181,6 @@ impl DesktopWindow {
                         ..Default::default()
                     });
                     
-                    // we need to draw the window menu here.
-                    if let Some(_menu) = menu {
-                        // lets draw the thing, check with the clone if it changed
-                        // then draw it
-                    }
-                    
                     self.min_btn.draw_desktop_button(cx, DesktopButtonType::WindowsMin);
                     if self.window.is_fullscreen(cx) {
                         self.max_btn.draw_desktop_button(cx, DesktopButtonType::WindowsMaxToggled);
@@ -197,6 +198,13 @@ impl DesktopWindow {
                     // we need to store our caption rect somewhere.
                     self.caption_size = Vec2 {x: cx.get_width_left(), y: cx.get_height_left()};
                     self.caption_text.draw_text(cx, &self.caption);
+                    // the menu bar goes left, next to the system menu area
+                    if let Some(menu) = menu {
+                        cx.change_turtle_align_x(0.0);
+                        cx.reset_turtle_pos();
+                        cx.move_turtle(50., 0.);
+                        self.window_menu.draw_window_menu(cx, menu);
+                    }
                     self.caption_bg.end_quad(cx, &bg_inst);
                     cx.turtle_new_line();
                 },
@@ -216,9 +224,23 @@ impl DesktopWindow {
                     cx.turtle_new_line();
                 },
                 _ => {
-                    
+                    // no native menu and no caption, just a menu bar
+                    if let Some(menu) = menu {
+                        let bg_inst = self.caption_bg.begin_quad(cx, Layout {
+                            walk: Walk::wh(Width::Fill, Height::Fix(26.)),
+                            ..Default::default()
+                        });
+                        self.window_menu.draw_window_menu(cx, menu);
+                        self.caption_bg.end_quad(cx, &bg_inst);
+                        cx.turtle_new_line();
+                    }
                 }
             }
+            // keep a copy so events can navigate the menu
+            if let PlatformType::OSX = cx.platform_type {}
+            else if self._last_menu.as_ref() != menu {
+                self._last_menu = menu.cloned();
+            }
             self.caption_view.end_view(cx);
         }
         cx.turtle_new_line();
@@ -234,6 +256,11 @@ impl DesktopWindow {
     
     pub fn end_desktop_window(&mut self, cx: &mut Cx) {
         self.inner_view.end_view(cx);
+        // menu popups go over the window content
+        if let Some(menu) = self._last_menu.take() {
+            self.window_menu.draw_window_menu_popups(cx, &menu);
+            self._last_menu = Some(menu);
+        }
         // lets draw a VR button top right over the UI.
         if cx.vr_can_present { // show a switch-to-VRMode button
             cx.reset_turtle_pos();
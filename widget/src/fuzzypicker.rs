@@ -0,0 +1,383 @@
+use render::*;
+use crate::widgettheme::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyPickerItem {
+    pub label: String,
+    pub detail: String,
+    // added to the match score, lets callers rank things like recent files higher
+    pub boost: f64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FuzzyPickerEvent {
+    None,
+    Select {index: usize},
+    Cancel
+}
+
+// a popup with a query line and a fuzzy filtered list, the owner decides where focus goes when it closes
+#[derive(Clone)]
+pub struct FuzzyPicker {
+    pub view: View,
+    pub bg: Quad,
+    pub item_bg: Quad,
+    pub text: Text,
+    pub max_visible: usize,
+    pub width: f32,
+
+    pub _is_open: bool,
+    pub _set_key_focus: bool,
+    pub _items: Vec<FuzzyPickerItem>,
+    pub _query: String,
+    pub _filtered: Vec<usize>,
+    pub _selected: usize,
+    pub _scroll_top: usize,
+    pub _view_area: Area,
+    pub _item_areas: Vec<Area>,
+}
+
+impl FuzzyPicker {
+    pub fn proto(cx: &mut Cx) -> Self {
+        Self {
+            view: View::proto_overlay(cx),
+            bg: Quad {
+                z: 20.,
+                ..Quad::proto_with_shader(cx, Self::def_bg_shader(), "FuzzyPicker.bg")
+            },
+            item_bg: Quad {
+                z: 20.1,
+                ..Quad::proto(cx)
+            },
+            text: Text {
+                z: 20.2,
+                wrapping: Wrapping::Ellipsis(560.),
+                ..Text::proto(cx)
+            },
+            max_visible: 14,
+            width: 600.,
+            _is_open: false,
+            _set_key_focus: false,
+            _items: Vec::new(),
+            _query: String::new(),
+            _filtered: Vec::new(),
+            _selected: 0,
+            _scroll_top: 0,
+            _view_area: Area::Empty,
+            _item_areas: Vec::new(),
+        }
+    }
+
+    pub fn layout_bg() -> LayoutId {uid!()}
+    pub fn layout_query() -> LayoutId {uid!()}
+    pub fn layout_item() -> LayoutId {uid!()}
+    pub fn text_style_item() -> TextStyleId {uid!()}
+    pub fn color_bg() -> ColorId {uid!()}
+    pub fn color_query() -> ColorId {uid!()}
+    pub fn color_item_selected() -> ColorId {uid!()}
+    pub fn color_item_text() -> ColorId {uid!()}
+    pub fn color_item_text_selected() -> ColorId {uid!()}
+    pub fn color_item_detail() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::layout_bg().set_base(cx, Layout {
+            padding: Padding {l: 4., t: 4., r: 4., b: 4.},
+            walk: Walk::wh(Width::Fill, Height::Compute),
+            ..Layout::default()
+        });
+        Self::layout_query().set_base(cx, Layout {
+            padding: Padding {l: 6., t: 0., r: 6., b: 0.},
+            align: Align::left_center(),
+            walk: Walk::wh(Width::Fill, Height::Fix(24.)),
+            ..Layout::default()
+        });
+        Self::layout_item().set_base(cx, Layout {
+            padding: Padding {l: 6., t: 0., r: 6., b: 0.},
+            align: Align::left_center(),
+            walk: Walk::wh(Width::Fill, Height::Fix(20.)),
+            ..Layout::default()
+        });
+        Self::text_style_item().set_base(cx, Theme::text_style_normal().base(cx));
+        Self::color_bg().set_base(cx, Theme::color_bg_normal().base(cx));
+        Self::color_query().set_base(cx, Theme::color_text_focus().base(cx));
+        Self::color_item_selected().set_base(cx, Theme::color_bg_marked().base(cx));
+        Self::color_item_text().set_base(cx, Theme::color_text_deselected_focus().base(cx));
+        Self::color_item_text_selected().set_base(cx, Theme::color_text_selected_focus().base(cx));
+        Self::color_item_detail().set_base(cx, Theme::color_text_defocus().base(cx));
+    }
+
+    pub fn def_bg_shader() -> ShaderGen {
+        Quad::def_quad_shader().compose(shader_ast!({
+            fn pixel() -> vec4 {
+                df_viewport(pos * vec2(w, h));
+                df_box(0., 0., w, h, 3.);
+                return df_fill(color);
+            }
+        }))
+    }
+
+    // scores pattern as a case insensitive subsequence of word, rewarding runs and word boundaries
+    pub fn fuzzy_score(pattern: &[char], word: &[char]) -> Option<f64> {
+        if pattern.len() > word.len() {
+            return None
+        }
+        let mut score = 0.0;
+        let mut last_match: Option<usize> = None;
+        let mut wi = 0;
+        for pc in pattern {
+            let pl = pc.to_lowercase().next().unwrap_or(*pc);
+            loop {
+                if wi >= word.len() {
+                    return None
+                }
+                let wc = word[wi];
+                if wc.to_lowercase().next().unwrap_or(wc) == pl {
+                    break;
+                }
+                wi += 1;
+            }
+            score += 1.0;
+            if *pc == word[wi] {
+                score += 0.25;
+            }
+            if wi == 0 {
+                score += 3.0;
+            }
+            else {
+                let prev = word[wi - 1];
+                if prev == '_' || prev == '/' || prev == '.' || prev == ' ' || prev.is_lowercase() && word[wi].is_uppercase() {
+                    score += 2.0;
+                }
+            }
+            if let Some(last) = last_match {
+                if last + 1 == wi {
+                    score += 2.0;
+                }
+                else {
+                    score -= 0.1 * (wi - last - 1) as f64;
+                }
+            }
+            last_match = Some(wi);
+            wi += 1;
+        }
+        // shorter words that match equally well win
+        score -= 0.02 * (word.len() - pattern.len()) as f64;
+        Some(score)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self._is_open
+    }
+
+    // items are shown in the given order while the query is empty
+    pub fn open(&mut self, cx: &mut Cx, items: Vec<FuzzyPickerItem>) {
+        self._items = items;
+        self._query.truncate(0);
+        self._is_open = true;
+        self._set_key_focus = true;
+        // keep keys away from whatever had focus until we are drawn
+        cx.set_key_focus(Area::Empty);
+        self.filter_items(cx);
+        cx.redraw_child_area(Area::All);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        if self._is_open {
+            self._is_open = false;
+            self._items.truncate(0);
+            self._filtered.truncate(0);
+            if cx.has_key_focus(self._view_area) {
+                cx.set_key_focus(Area::Empty);
+            }
+            cx.redraw_child_area(Area::All);
+        }
+    }
+
+    pub fn set_query(&mut self, cx: &mut Cx, query: &str) {
+        self._query = query.to_string();
+        self.filter_items(cx);
+    }
+
+    pub fn get_query(&self) -> &str {
+        &self._query
+    }
+
+    pub fn filter_items(&mut self, cx: &mut Cx) {
+        let pattern: Vec<char> = self._query.chars().filter( | c | !c.is_whitespace()).collect();
+        if pattern.len() == 0 {
+            self._filtered = (0..self._items.len()).collect();
+        }
+        else {
+            let mut scored = Vec::new();
+            let mut chars = Vec::new();
+            for (index, item) in self._items.iter().enumerate() {
+                chars.truncate(0);
+                chars.extend(item.label.chars());
+                let score = if let Some(score) = Self::fuzzy_score(&pattern, &chars) {
+                    Some(score)
+                }
+                else {
+                    chars.truncate(0);
+                    chars.extend(item.detail.chars());
+                    Self::fuzzy_score(&pattern, &chars).map( | score | score - 1.0)
+                };
+                if let Some(score) = score {
+                    scored.push((index, score + item.boost));
+                }
+            }
+            scored.sort_by( | a, b | b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+            self._filtered = scored.iter().map( | (index, _) | *index).collect();
+        }
+        self._selected = 0;
+        self._scroll_top = 0;
+        self.view.redraw_view_area(cx);
+    }
+
+    pub fn move_selection(&mut self, cx: &mut Cx, delta: isize) {
+        if self._filtered.len() == 0 {
+            return
+        }
+        let len = self._filtered.len() as isize;
+        let sel = self._selected as isize + delta;
+        self._selected = if delta.abs() == 1 {
+            ((sel % len + len) % len) as usize
+        }
+        else {
+            sel.max(0).min(len - 1) as usize
+        };
+        if self._selected < self._scroll_top {
+            self._scroll_top = self._selected;
+        }
+        else if self._selected >= self._scroll_top + self.max_visible {
+            self._scroll_top = self._selected + 1 - self.max_visible;
+        }
+        self.view.redraw_view_area(cx);
+    }
+
+    fn select(&mut self, cx: &mut Cx, filtered_index: usize) -> FuzzyPickerEvent {
+        if let Some(index) = self._filtered.get(filtered_index).cloned() {
+            self.close(cx);
+            return FuzzyPickerEvent::Select {index: index}
+        }
+        FuzzyPickerEvent::None
+    }
+
+    pub fn handle_fuzzy_picker(&mut self, cx: &mut Cx, event: &mut Event) -> FuzzyPickerEvent {
+        if !self._is_open {
+            return FuzzyPickerEvent::None
+        }
+        for (i, area) in self._item_areas.iter().enumerate() {
+            match event.hits(cx, *area, HitOpt::default()) {
+                Event::FingerHover(_fe) => {
+                    cx.set_hover_mouse_cursor(MouseCursor::Default);
+                },
+                Event::FingerDown(_fe) => {
+                    let index = self._scroll_top + i;
+                    return self.select(cx, index);
+                },
+                _ => ()
+            }
+        }
+        match event.hits(cx, self._view_area, HitOpt::default()) {
+            Event::KeyFocusLost(_) => {
+                self.close(cx);
+                return FuzzyPickerEvent::Cancel
+            },
+            Event::KeyDown(ke) => match ke.key_code {
+                KeyCode::ArrowUp => self.move_selection(cx, -1),
+                KeyCode::ArrowDown => self.move_selection(cx, 1),
+                KeyCode::PageUp => self.move_selection(cx, -(self.max_visible as isize)),
+                KeyCode::PageDown => self.move_selection(cx, self.max_visible as isize),
+                KeyCode::Backspace => {
+                    if self._query.pop().is_some() {
+                        self.filter_items(cx);
+                    }
+                },
+                KeyCode::Escape => {
+                    self.close(cx);
+                    return FuzzyPickerEvent::Cancel
+                },
+                KeyCode::Return => {
+                    let selected = self._selected;
+                    return self.select(cx, selected);
+                },
+                _ => ()
+            },
+            Event::TextInput(te) => {
+                if te.replace_last {
+                    self._query.pop();
+                }
+                for c in te.input.chars() {
+                    if c != '\n' && c != '\r' && c != '\t' {
+                        self._query.push(c);
+                    }
+                }
+                self.filter_items(cx);
+            },
+            _ => ()
+        }
+        FuzzyPickerEvent::None
+    }
+
+    // draws centered near the top of the current turtle
+    pub fn draw_fuzzy_picker(&mut self, cx: &mut Cx) {
+        self._item_areas.truncate(0);
+        if !self._is_open {
+            return
+        }
+        let rect = cx.get_turtle_rect();
+        let width = self.width.min(rect.w - 20.).max(100.);
+        let abs_pos = Vec2 {x: rect.x + (rect.w - width) * 0.5, y: rect.y + 40.};
+        if self.view.begin_view(cx, Layout {
+            abs_origin: Some(abs_pos),
+            walk: Walk::wh(Width::Fix(width), Height::Compute),
+            ..Default::default()
+        }).is_err() {
+            return
+        }
+        let view_area = self.view.get_view_area(cx);
+        cx.update_area_refs(self._view_area, view_area);
+        self._view_area = view_area;
+        if self._set_key_focus {
+            self._set_key_focus = false;
+            cx.set_key_focus(view_area);
+        }
+
+        self.bg.color = Self::color_bg().base(cx);
+        self.text.text_style = Self::text_style_item().base(cx);
+        let color_item_selected = Self::color_item_selected().base(cx);
+        let color_item_text = Self::color_item_text().base(cx);
+        let color_item_text_selected = Self::color_item_text_selected().base(cx);
+        let color_item_detail = Self::color_item_detail().base(cx);
+        let layout_item = Self::layout_item().base(cx);
+
+        let bg_inst = self.bg.begin_quad(cx, Self::layout_bg().base(cx));
+
+        self.item_bg.color = Color::zero();
+        let query_inst = self.item_bg.begin_quad(cx, Self::layout_query().base(cx));
+        self.text.color = Self::color_query().base(cx);
+        self.text.draw_text(cx, &format!("{}|", self._query));
+        self.item_bg.end_quad(cx, &query_inst);
+        cx.turtle_new_line();
+
+        let end = (self._scroll_top + self.max_visible).min(self._filtered.len());
+        for filtered_index in self._scroll_top..end {
+            let item = &self._items[self._filtered[filtered_index]];
+            let is_selected = filtered_index == self._selected;
+            self.item_bg.color = if is_selected {color_item_selected}else {Color::zero()};
+            let inst = self.item_bg.begin_quad(cx, layout_item.clone());
+            self.text.color = if is_selected {color_item_text_selected}else {color_item_text};
+            self.text.draw_text(cx, &item.label);
+            if item.detail.len() > 0 {
+                cx.walk_turtle(Walk::wh(Width::Fix(12.), Height::Fix(0.)));
+                self.text.color = color_item_detail;
+                self.text.draw_text(cx, &item.detail);
+            }
+            let area = self.item_bg.end_quad(cx, &inst);
+            self._item_areas.push(area);
+            cx.turtle_new_line();
+        }
+        self.bg.end_quad(cx, &bg_inst);
+        self.view.end_view(cx);
+    }
+}
@@ -26,6 +26,10 @@ mod windowmenu;
 pub use crate::windowmenu::*;
 mod listlogic;
 pub use crate::listlogic::*;
+mod fuzzypicker;
+pub use crate::fuzzypicker::*;
+mod commandpalette;
+pub use crate::commandpalette::*;
 //mod textbuffer;
 //pub use crate::textbuffer::*;
 //mod editor;
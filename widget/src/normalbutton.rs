@@ -119,5 +119,13 @@ impl NormalButton {
         
         self._bg_area = self.bg.end_quad(cx, &bg_inst);
         self.animator.set_area(cx, self._bg_area);
+        
+        cx.add_access(AccessNode {
+            role: AccessRole::Button,
+            label: label.to_string(),
+            area: self._bg_area,
+            actions: vec![AccessAction::Click],
+            ..Default::default()
+        });
     }
 }
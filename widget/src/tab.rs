@@ -326,6 +326,25 @@ impl Tab {
         if let Some(bg_inst) = self._bg_inst.take() {
             self._bg_area = self.bg.end_quad(cx, &bg_inst);
             self.animator.set_area(cx, self._bg_area); // if our area changed, update animation
+            
+            cx.begin_access(AccessNode {
+                role: AccessRole::Tab,
+                label: self.label.clone(),
+                area: self._bg_area,
+                selected: self._is_selected,
+                actions: vec![AccessAction::Select],
+                ..Default::default()
+            });
+            if self.is_closeable {
+                cx.add_access(AccessNode {
+                    role: AccessRole::Button,
+                    label: "Close".to_string(),
+                    area: self.tab_close._bg_area,
+                    actions: vec![AccessAction::Click],
+                    ..Default::default()
+                });
+            }
+            cx.end_access();
         }
     }
     
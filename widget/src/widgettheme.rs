@@ -4,6 +4,7 @@ use crate::tab::*;
 use crate::desktopwindow::*;
 use crate::windowmenu::*;
 use crate::tabclose::*;
+use crate::fuzzypicker::*;
 
 pub struct Theme{}
 impl Theme{
@@ -39,23 +40,31 @@ impl Theme{
 }
 
 pub fn set_widget_theme_values(cx: &mut Cx) {
+    // cjk and symbols come from the fonts of the os
+    let fallbacks = cx.load_font_fallbacks(system_fallback_font_paths());
+    
     let font = cx.load_font("resources/Ubuntu-R.ttf");
     Theme::text_style_normal().set_base(cx, TextStyle {
         font: font,
+        fallbacks: fallbacks,
         font_size: 8.0,
         brightness: 1.0,
         curve: 0.7,
         line_spacing: 1.4,
         top_drop: 1.1,
         height_factor: 1.3,
+        shaping: true,
     });
 
     let font = cx.load_font("resources/LiberationMono-Regular.ttf");
     Theme::text_style_fixed().set_base(cx, TextStyle{
         font: font,
+        fallbacks: fallbacks,
         brightness: 1.1,
         line_spacing: 1.8,
         top_drop: 1.3,
+        // code stays on the monospace grid
+        shaping: false,
         ..TextStyle::default()
     });
     
@@ -65,6 +74,7 @@ pub fn set_widget_theme_values(cx: &mut Cx) {
     NormalButton::theme(cx);
     Tab::theme(cx);
     MenuItemDraw::theme(cx);
+    FuzzyPicker::theme(cx);
 }
 
 pub fn set_dark_widget_theme(cx: &mut Cx) {
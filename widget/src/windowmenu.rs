@@ -1,4 +1,4 @@
-// a window menu implementation
+// a window menu implementation, for platforms that don't have a native menu bar
 use render::*;
 use crate::widgettheme::*;
 
@@ -6,127 +6,91 @@ use crate::widgettheme::*;
 pub struct WindowMenu {
     pub view: View,
     pub item_draw: MenuItemDraw,
+
+    // the chain of open submenus, the first is the index in the menu bar
+    pub _open: Vec<usize>,
+    // the keyboard/hover highlight in the deepest open popup
+    pub _highlight: Option<usize>,
+    pub _set_key_focus: bool,
+    pub _view_area: Area,
+    pub _bar_rect: Rect,
+    pub _bar_areas: Vec<Area>,
+    pub _popup_areas: Vec<(usize, usize, Area)>,
 }
 
 #[derive(Clone)]
 pub struct MenuItemDraw {
     pub text: Text,
     pub item_bg: Quad,
-    //pub item_layout: LayoutId,
-    //pub name_color: ColorId,
-    //pub bg_color: ColorId,
-    //pub bg_over_color: ColorId,
-    //pub bg_selected_color: ColorId,
+    pub popup_bg: Quad,
+    pub line: Quad,
 }
 
 impl MenuItemDraw {
     pub fn proto(cx: &mut Cx) -> Self {
         Self {
             text: Text {
-                wrapping: Wrapping::Word,
+                z: 30.2,
+                wrapping: Wrapping::None,
                 ..Text::proto(cx)
             },
-            //item_layout: Layout_window_menu::id(cx),
-            item_bg: Quad::proto(cx),
-            //name_color: Color_text_selected_focus::id(cx),
-            //bg_color: Color_bg_selected::id(cx),
-            //bg_over_color: Color_bg_odd::id(cx),
-            //bg_selected_color: Color_bg_selected_over::id(cx),
-        }
-    }
-    
-    pub fn get_default_anim(&self, cx: &Cx) -> Anim {
-        Anim::new(Play::Chain {duration: 0.01}, vec![
-            Track::color(Quad::instance_color(), Ease::Lin, vec![
-                (1.0,  Theme::color_bg_selected().base(cx))
-            ])
-        ])
-    }
-    
-    pub fn get_default_anim_cut(&self, cx: &Cx) -> Anim {
-        Anim::new(Play::Cut {duration: 0.01}, vec![
-            Track::color(Quad::instance_color(), Ease::Lin, vec![
-                (0.0, Theme::color_bg_selected().base(cx))
-            ])
-        ])
-    }
-    
-    pub fn get_over_anim(&self, cx: &Cx) -> Anim {
-        Anim::new(Play::Cut {duration: 0.02}, vec![
-            Track::color(Quad::instance_color(), Ease::Lin, vec![
-                (0., Theme::color_bg_odd().base(cx)),
-            ])
-        ])
-    }
-    
-    pub fn text_style_menu_label() ->TextStyleId{uid!()}
-    
-    pub fn theme(cx:&mut Cx){ 
-        Self::text_style_menu_label().set_base(cx, Theme::text_style_normal().base(cx));
-    }   
-    
-    /*
-    pub fn draw_log_path(&mut self, cx: &mut Cx, path: &str, row: usize) {
-        self.text.color = self.path_color;
-        self.text.draw_text(cx, &format!("{}:{} - ", path, row));
-    }
-    
-    pub fn draw_log_body(&mut self, cx: &mut Cx, body: &str) {
-        self.text.color = self.message_color;
-        if body.len()>500 {
-            self.text.draw_text(cx, &body[0..500]);
-        }
-        else {
-            self.text.draw_text(cx, &body);
-        }
-    }
-    
-    pub fn draw_log_item(&mut self, cx: &mut Cx, list_item: &mut ListItem, log_item: &HubLogItem) {
-        self.item_bg.color = list_item.animator.last_color(cx.id("bg.color"));
-        let bg_inst = self.item_bg.begin_quad(cx, &self.get_line_layout());
-        
-        match log_item {
-            HubLogItem::LocPanic(loc_msg) => {
-                self.code_icon.draw_icon_walk(cx, CodeIconType::Panic);
-                self.draw_log_path(cx, &loc_msg.path, loc_msg.row);
-                self.draw_log_body(cx, &loc_msg.body);
-                
-            },
-            HubLogItem::LocError(loc_msg) => {
-                self.code_icon.draw_icon_walk(cx, CodeIconType::Error);
-                self.draw_log_path(cx, &loc_msg.path, loc_msg.row);
-                self.draw_log_body(cx, &loc_msg.body);
+            item_bg: Quad {
+                z: 30.1,
+                ..Quad::proto(cx)
             },
-            HubLogItem::LocWarning(loc_msg) => {
-                self.code_icon.draw_icon_walk(cx, CodeIconType::Warning);
-                self.draw_log_path(cx, &loc_msg.path, loc_msg.row);
-                self.draw_log_body(cx, &loc_msg.body);
+            popup_bg: Quad {
+                z: 30.,
+                ..Quad::proto(cx)
             },
-            HubLogItem::LocMessage(loc_msg) => {
-                self.draw_log_path(cx, &loc_msg.path, loc_msg.row);
-                self.draw_log_body(cx, &loc_msg.body);
+            line: Quad {
+                z: 30.1,
+                ..Quad::proto(cx)
             },
-            HubLogItem::Error(msg) => {
-                self.code_icon.draw_icon_walk(cx, CodeIconType::Error);
-                self.draw_log_body(cx, &msg);
-            },
-            HubLogItem::Warning(msg) => {
-                self.code_icon.draw_icon_walk(cx, CodeIconType::Warning);
-                self.draw_log_body(cx, &msg);
-            },
-            HubLogItem::Message(msg) => {
-                self.draw_log_body(cx, &msg);
-            }
         }
-        
-        let bg_area = self.item_bg.end_quad(cx, &bg_inst);
-        list_item.animator.update_area_refs(cx, bg_area);
-    }*/
+    }
+
+    pub fn text_style_menu_label() -> TextStyleId {uid!()}
+    pub fn layout_bar_item() -> LayoutId {uid!()}
+    pub fn layout_popup() -> LayoutId {uid!()}
+    pub fn layout_popup_item() -> LayoutId {uid!()}
+    pub fn color_popup_bg() -> ColorId {uid!()}
+    pub fn color_item_selected() -> ColorId {uid!()}
+    pub fn color_text() -> ColorId {uid!()}
+    pub fn color_text_disabled() -> ColorId {uid!()}
+    pub fn color_line() -> ColorId {uid!()}
+
+    pub fn theme(cx: &mut Cx) {
+        Self::text_style_menu_label().set_base(cx, Theme::text_style_normal().base(cx));
+        Self::layout_bar_item().set_base(cx, Layout {
+            padding: Padding {l: 8., t: 0., r: 8., b: 0.},
+            align: Align::left_center(),
+            walk: Walk::wh(Width::Compute, Height::Fill),
+            ..Layout::default()
+        });
+        Self::layout_popup().set_base(cx, Layout {
+            padding: Padding {l: 2., t: 2., r: 2., b: 2.},
+            walk: Walk::wh(Width::Fix(240.), Height::Compute),
+            ..Layout::default()
+        });
+        Self::layout_popup_item().set_base(cx, Layout {
+            padding: Padding {l: 12., t: 0., r: 12., b: 0.},
+            align: Align::left_center(),
+            walk: Walk::wh(Width::Fill, Height::Fix(22.)),
+            ..Layout::default()
+        });
+        Self::color_popup_bg().set_base(cx, Theme::color_bg_normal().base(cx));
+        Self::color_item_selected().set_base(cx, Theme::color_bg_marked().base(cx));
+        Self::color_text().set_base(cx, Theme::color_text_selected_focus().base(cx));
+        Self::color_text_disabled().set_base(cx, Theme::color_text_defocus().base(cx));
+        Self::color_line().set_base(cx, Theme::color_bg_odd().base(cx));
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum WindowMenuEvent {
     SelectItem {
+        signal: Signal,
+        value: usize
     },
     None,
 }
@@ -135,14 +99,375 @@ impl WindowMenu {
     pub fn proto(cx: &mut Cx) -> Self {
         Self {
             item_draw: MenuItemDraw::proto(cx),
-            view: View::proto(cx),
+            view: View::proto_overlay(cx),
+            _open: Vec::new(),
+            _highlight: None,
+            _set_key_focus: false,
+            _view_area: Area::Empty,
+            _bar_rect: Rect::zero(),
+            _bar_areas: Vec::new(),
+            _popup_areas: Vec::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self._open.len() > 0
+    }
+
+    // true if pos is on the menu bar, the window caption shouldn't drag there
+    pub fn bar_contains(&self, pos: Vec2) -> bool {
+        self._bar_rect.contains(pos.x, pos.y)
+    }
+
+    fn top_items(menu: &Menu) -> &[Menu] {
+        if let Menu::Main {items} = menu {items} else {&[]}
+    }
+
+    // the items shown in the popup at this depth of the open chain
+    fn popup_items<'a>(menu: &'a Menu, open: &[usize], level: usize) -> Option<&'a Vec<Menu>> {
+        let mut items = Self::top_items(menu);
+        let mut found = None;
+        for index in open.iter().take(level + 1) {
+            if let Some(Menu::Sub {items: sub_items, ..}) = items.get(*index) {
+                found = Some(sub_items);
+                items = sub_items;
+            }
+            else {
+                return None
+            }
+        }
+        found
+    }
+
+    fn is_selectable(item: &Menu) -> bool {
+        match item {
+            Menu::Item {..} | Menu::Sub {..} => true,
+            _ => false
+        }
+    }
+
+    pub fn open_menu(&mut self, cx: &mut Cx, index: usize, highlight_first: bool, menu: &Menu) {
+        self._open = vec![index];
+        self._highlight = None;
+        if highlight_first {
+            self.move_highlight(1, menu);
+        }
+        if !cx.has_key_focus(self._view_area) {
+            self._set_key_focus = true;
+            cx.set_key_focus(Area::Empty);
+        }
+        cx.redraw_child_area(Area::All);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx) {
+        if self._open.len() > 0 {
+            self._open.truncate(0);
+            self._highlight = None;
+            self._popup_areas.truncate(0);
+            if cx.has_key_focus(self._view_area) {
+                cx.set_key_focus(Area::Empty);
+            }
+            cx.redraw_child_area(Area::All);
+        }
+    }
+
+    fn move_highlight(&mut self, delta: isize, menu: &Menu) {
+        let level = self._open.len() - 1;
+        if let Some(items) = Self::popup_items(menu, &self._open, level) {
+            let len = items.len() as isize;
+            if !items.iter().any( | item | Self::is_selectable(item)) {
+                return
+            }
+            let mut index = match self._highlight {
+                Some(index) => index as isize,
+                None => if delta > 0 {-1} else {len}
+            };
+            loop {
+                index = ((index + delta) % len + len) % len;
+                if Self::is_selectable(&items[index as usize]) {
+                    break;
+                }
+            }
+            self._highlight = Some(index as usize);
+        }
+    }
+
+    fn switch_top(&mut self, cx: &mut Cx, delta: isize, menu: &Menu) {
+        let len = Self::top_items(menu).len() as isize;
+        if len == 0 || self._open.len() == 0 {
+            return
+        }
+        let index = ((self._open[0] as isize + delta) % len + len) % len;
+        self.open_menu(cx, index as usize, true, menu);
+    }
+
+    // opens a submenu or fires an item, returns the event for fired items
+    fn activate(&mut self, cx: &mut Cx, level: usize, index: usize, menu: &Menu) -> WindowMenuEvent {
+        let item = if let Some(items) = Self::popup_items(menu, &self._open, level) {
+            items.get(index).cloned()
+        }
+        else {
+            None
+        };
+        match item {
+            Some(Menu::Sub {..}) => {
+                self._open.truncate(level + 1);
+                self._open.push(index);
+                self._highlight = None;
+                self.move_highlight(1, menu);
+                self.view.redraw_view_area(cx);
+            },
+            Some(Menu::Item {signal, value, enabled, ..}) => if enabled {
+                self.close(cx);
+                cx.send_signal(signal, value);
+                return WindowMenuEvent::SelectItem {signal: signal, value: value}
+            },
+            _ => ()
         }
+        WindowMenuEvent::None
     }
-    
-    pub fn handle_window_menu(&mut self, _cx: &mut Cx, _event: &mut Event, _menu: &Menu) -> WindowMenuEvent {
+
+    pub fn handle_window_menu(&mut self, cx: &mut Cx, event: &mut Event, menu: &Menu) -> WindowMenuEvent {
+        let mut hit_menu = false;
+        for (index, area) in self._bar_areas.clone().iter().enumerate() {
+            match event.hits(cx, *area, HitOpt::default()) {
+                Event::FingerDown(_fe) => {
+                    hit_menu = true;
+                    if self._open.len() > 0 && self._open[0] == index {
+                        self.close(cx);
+                    }
+                    else {
+                        self.open_menu(cx, index, false, menu);
+                    }
+                },
+                Event::FingerHover(_fe) => {
+                    cx.set_hover_mouse_cursor(MouseCursor::Default);
+                    if self._open.len() > 0 && self._open[0] != index {
+                        self.open_menu(cx, index, false, menu);
+                    }
+                },
+                _ => ()
+            }
+        }
+        for (level, index, area) in self._popup_areas.clone() {
+            match event.hits(cx, area, HitOpt::default()) {
+                Event::FingerDown(_fe) => {
+                    hit_menu = true;
+                },
+                Event::FingerHover(_fe) => {
+                    cx.set_hover_mouse_cursor(MouseCursor::Default);
+                    if level + 1 < self._open.len() && self._open[level + 1] == index {
+                        continue;
+                    }
+                    if self._open.len() > level + 1 || self._highlight != Some(index) {
+                        self._open.truncate(level + 1);
+                        self._highlight = Some(index);
+                        let is_sub = if let Some(items) = Self::popup_items(menu, &self._open, level) {
+                            if let Some(Menu::Sub {..}) = items.get(index) {true} else {false}
+                        }
+                        else {
+                            false
+                        };
+                        if is_sub {
+                            self._open.push(index);
+                            self._highlight = None;
+                        }
+                        self.view.redraw_view_area(cx);
+                    }
+                },
+                Event::FingerUp(fe) => if fe.is_over {
+                    return self.activate(cx, level, index, menu)
+                },
+                _ => ()
+            }
+        }
+
+        match event.hits(cx, self._view_area, HitOpt::default()) {
+            Event::KeyFocusLost(_) => {
+                self.close(cx);
+            },
+            Event::KeyDown(ke) => match ke.key_code {
+                KeyCode::Escape => {
+                    if self._open.len() > 1 {
+                        self._highlight = self._open.pop();
+                        self.view.redraw_view_area(cx);
+                    }
+                    else {
+                        self.close(cx);
+                    }
+                },
+                KeyCode::ArrowUp => {
+                    self.move_highlight(-1, menu);
+                    self.view.redraw_view_area(cx);
+                },
+                KeyCode::ArrowDown => {
+                    self.move_highlight(1, menu);
+                    self.view.redraw_view_area(cx);
+                },
+                KeyCode::ArrowLeft => {
+                    if self._open.len() > 1 {
+                        self._highlight = self._open.pop();
+                        self.view.redraw_view_area(cx);
+                    }
+                    else {
+                        self.switch_top(cx, -1, menu);
+                    }
+                },
+                KeyCode::ArrowRight => {
+                    let level = self._open.len() - 1;
+                    let is_sub = match (self._highlight, Self::popup_items(menu, &self._open, level)) {
+                        (Some(highlight), Some(items)) => if let Some(Menu::Sub {..}) = items.get(highlight) {true} else {false},
+                        _ => false
+                    };
+                    if is_sub {
+                        let highlight = self._highlight.unwrap();
+                        self.activate(cx, level, highlight, menu);
+                    }
+                    else {
+                        self.switch_top(cx, 1, menu);
+                    }
+                },
+                KeyCode::Return | KeyCode::Space => if let Some(highlight) = self._highlight {
+                    let level = self._open.len() - 1;
+                    return self.activate(cx, level, highlight, menu)
+                },
+                _ => ()
+            },
+            _ => ()
+        }
+
+        match event {
+            // clicking anywhere else closes the menu
+            Event::FingerDown(_) => if !hit_menu {
+                self.close(cx);
+            },
+            // F10 opens the menu bar from the keyboard
+            Event::KeyDown(ke) => if ke.key_code == KeyCode::F10 && !self.is_open() && Self::top_items(menu).len() > 0 {
+                self.open_menu(cx, 0, true, menu);
+            },
+            _ => ()
+        }
         WindowMenuEvent::None
     }
-    
-    pub fn draw_window_menu(&mut self, _cx: &mut Cx, _menu: &Menu) {
+
+    // draws the menu bar in the current turtle
+    pub fn draw_window_menu(&mut self, cx: &mut Cx, menu: &Menu) {
+        self._bar_areas.truncate(0);
+        let color_item_selected = MenuItemDraw::color_item_selected().base(cx);
+        let layout_bar_item = MenuItemDraw::layout_bar_item().base(cx);
+        self.item_draw.text.text_style = MenuItemDraw::text_style_menu_label().base(cx);
+        self.item_draw.text.color = MenuItemDraw::color_text().base(cx);
+        let mut bar_rect: Option<Rect> = None;
+        for (index, item) in Self::top_items(menu).iter().enumerate() {
+            if let Menu::Sub {name, ..} = item {
+                let is_open = self._open.len() > 0 && self._open[0] == index;
+                self.item_draw.item_bg.color = if is_open {color_item_selected} else {Color::zero()};
+                let inst = self.item_draw.item_bg.begin_quad(cx, layout_bar_item.clone());
+                self.item_draw.text.draw_text(cx, name);
+                let area = self.item_draw.item_bg.end_quad(cx, &inst);
+                let rect = area.get_rect(cx, true);
+                bar_rect = Some(if let Some(r) = bar_rect {
+                    Rect {x: r.x, y: r.y, w: rect.x + rect.w - r.x, h: r.h.max(rect.h)}
+                }
+                else {
+                    rect
+                });
+                self._bar_areas.push(area);
+            }
+        }
+        self._bar_rect = bar_rect.unwrap_or(Rect::zero());
+    }
+
+    // draws the open popups, call this after everything else in the window so they end up on top
+    pub fn draw_window_menu_popups(&mut self, cx: &mut Cx, menu: &Menu) {
+        self._popup_areas.truncate(0);
+        if self._open.len() == 0 {
+            return
+        }
+        if self.view.begin_view(cx, Layout {
+            abs_origin: Some(Vec2::zero()),
+            ..Layout::default()
+        }).is_err() {
+            return
+        }
+        let view_area = self.view.get_view_area(cx);
+        cx.update_area_refs(self._view_area, view_area);
+        self._view_area = view_area;
+        if self._set_key_focus {
+            self._set_key_focus = false;
+            cx.set_key_focus(view_area);
+        }
+
+        let color_item_selected = MenuItemDraw::color_item_selected().base(cx);
+        let color_text = MenuItemDraw::color_text().base(cx);
+        let color_text_disabled = MenuItemDraw::color_text_disabled().base(cx);
+        let layout_popup_item = MenuItemDraw::layout_popup_item().base(cx);
+        self.item_draw.popup_bg.color = MenuItemDraw::color_popup_bg().base(cx);
+        self.item_draw.line.color = MenuItemDraw::color_line().base(cx);
+        self.item_draw.text.text_style = MenuItemDraw::text_style_menu_label().base(cx);
+
+        // the first popup hangs under the bar, the others next to their parent item
+        let mut origin = if let Some(area) = self._bar_areas.get(self._open[0]) {
+            let rect = area.get_rect(cx, true);
+            Vec2 {x: rect.x, y: rect.y + rect.h}
+        }
+        else {
+            Vec2::zero()
+        };
+        for level in 0..self._open.len() {
+            let items = if let Some(items) = Self::popup_items(menu, &self._open, level) {items} else {break};
+            let is_deepest = level == self._open.len() - 1;
+            let bg_inst = self.item_draw.popup_bg.begin_quad(cx, Layout {
+                abs_origin: Some(origin),
+                ..MenuItemDraw::layout_popup().base(cx)
+            });
+            let mut next_origin = None;
+            for (index, item) in items.iter().enumerate() {
+                match item {
+                    Menu::Line => {
+                        self.item_draw.line.draw_quad(cx, Walk {
+                            margin: Margin {l: 4., t: 3., r: 4., b: 3.},
+                            width: Width::Fill,
+                            height: Height::Fix(1.)
+                        });
+                        cx.turtle_new_line();
+                    },
+                    Menu::Item {name, key, ..} | Menu::Sub {name, key, ..} => {
+                        let enabled = if let Menu::Item {enabled, ..} = item {*enabled} else {true};
+                        let is_highlighted = if is_deepest {
+                            self._highlight == Some(index)
+                        }
+                        else {
+                            self._open[level + 1] == index
+                        };
+                        self.item_draw.item_bg.color = if is_highlighted {color_item_selected} else {Color::zero()};
+                        let inst = self.item_draw.item_bg.begin_quad(cx, layout_popup_item.clone());
+                        self.item_draw.text.color = if enabled {color_text} else {color_text_disabled};
+                        self.item_draw.text.draw_text(cx, name);
+                        cx.change_turtle_align_x(1.0);
+                        if let Menu::Sub {..} = item {
+                            self.item_draw.text.draw_text(cx, ">");
+                        }
+                        else {
+                            self.item_draw.text.draw_text(cx, &Menu::key_label(key, &cx.platform_type));
+                        }
+                        let area = self.item_draw.item_bg.end_quad(cx, &inst);
+                        if !is_deepest && self._open[level + 1] == index {
+                            let rect = area.get_rect(cx, true);
+                            next_origin = Some(Vec2 {x: rect.x + rect.w, y: rect.y - 2.});
+                        }
+                        self._popup_areas.push((level, index, area));
+                        cx.turtle_new_line();
+                    },
+                    _ => ()
+                }
+            }
+            self.item_draw.popup_bg.end_quad(cx, &bg_inst);
+            // the item rect is only final once the popup turtle has ended
+            if let Some(next_origin) = next_origin {
+                origin = next_origin;
+            }
+        }
+        self.view.end_view(cx);
     }
 }
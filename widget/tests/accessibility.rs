@@ -0,0 +1,97 @@
+use render::*;
+use widget::*;
+
+// the access tree widgets build while drawing, queried on the headless backend
+
+fn center(rect: Rect) -> Vec2 {
+    Vec2 {x: rect.x + rect.w * 0.5, y: rect.y + rect.h * 0.5}
+}
+
+#[derive(Clone)]
+enum Panel {
+    Red,
+    Green,
+}
+
+struct AccessApp {
+    desktop_window: DesktopWindow,
+    button: NormalButton,
+    dock: Dock<Panel>,
+    dock_items: DockItem<Panel>,
+}
+
+impl AccessApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        let tab = | title: &str, item: Panel | DockTab {closeable: true, title: title.to_string(), item: item};
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 400., y: 300.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            button: NormalButton::proto(cx),
+            dock: Dock::proto(cx),
+            dock_items: DockItem::TabControl {
+                current: 0,
+                previous: 0,
+                tabs: vec![tab("Red", Panel::Red), tab("Green", Panel::Green)]
+            },
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+        self.button.handle_button(cx, event);
+        let mut dock_walker = self.dock.walker(&mut self.dock_items);
+        while let Some(_item) = dock_walker.walk_handle_dock(cx, event) {}
+        self.dock.handle_dock(cx, event, &mut self.dock_items);
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.button.draw_button(cx, "Build");
+        cx.turtle_new_line();
+        self.dock.draw_dock(cx);
+        let mut dock_walker = self.dock.walker(&mut self.dock_items);
+        while let Some(_item) = dock_walker.walk_draw_dock(cx, | cx, tab_control, tab, selected | {
+            tab_control.draw_tab(cx, &tab.title, selected, tab.closeable)
+        }) {}
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn access_tree_of_buttons_and_tabs() {
+    let mut snapshot = snapshot_app!(AccessApp);
+    let tree = snapshot.access_tree();
+    assert_eq!(tree.nodes[0].node.role, AccessRole::Window);
+    assert_eq!(tree.nodes[0].bounds.w, 400.);
+
+    let button = tree.find(AccessRole::Button, "Build").expect(&tree.dump());
+    assert!(button.bounds.w > 0. && button.bounds.h > 0.);
+    assert_eq!(button.node.actions, vec![AccessAction::Click]);
+
+    let red = tree.find(AccessRole::Tab, "Red").expect(&tree.dump());
+    let green = tree.find(AccessRole::Tab, "Green").expect(&tree.dump());
+    assert!(red.node.selected && !green.node.selected);
+    assert!(green.bounds.x >= red.bounds.x + red.bounds.w);
+    let close = tree.children(red);
+    assert_eq!(close.len(), 1);
+    assert_eq!(close[0].node.role, AccessRole::Button);
+    assert_eq!(close[0].node.label, "Close");
+
+    // selecting through the bounds the tree reports
+    let pos = center(green.bounds);
+    snapshot.click(pos.x, pos.y);
+    snapshot.advance(0.5);
+
+    let tree = snapshot.access_tree();
+    assert!(tree.find(AccessRole::Tab, "Green").unwrap().node.selected);
+    assert!(!tree.find(AccessRole::Tab, "Red").unwrap().node.selected);
+    assert!(tree.find(AccessRole::Button, "Build").is_some());
+}
@@ -0,0 +1,268 @@
+use render::*;
+use widget::*;
+
+// widget logic driven through the Cx automation api, no platform event loop or gpu involved
+
+trait TestApp {
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event);
+    fn draw_app(&mut self, cx: &mut Cx);
+}
+
+fn dispatch<'a, A: TestApp>(app: &'a mut A) -> impl FnMut(&mut Cx, &mut Event) + 'a {
+    move | cx, event | {
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            return
+        }
+        app.handle_app(cx, event);
+    }
+}
+
+fn start<A: TestApp>(cx: &mut Cx, app: &mut A) {
+    cx.automation_begin();
+    cx.automation_construct(dispatch(app));
+    cx.automation_settle(dispatch(app));
+}
+
+fn finger_down(x: f32, y: f32) -> Event {
+    Event::FingerDown(FingerDownEvent {abs: Vec2 {x: x, y: y}, rel: Vec2 {x: x, y: y}, ..Default::default()})
+}
+
+fn finger_up(x: f32, y: f32) -> Event {
+    Event::FingerUp(FingerUpEvent {abs: Vec2 {x: x, y: y}, rel: Vec2 {x: x, y: y}, ..Default::default()})
+}
+
+fn finger_hover(x: f32, y: f32) -> Event {
+    Event::FingerHover(FingerHoverEvent {abs: Vec2 {x: x, y: y}, rel: Vec2 {x: x, y: y}, ..Default::default()})
+}
+
+struct TestWindow {
+    window: Window,
+    pass: Pass,
+    color_texture: Texture,
+    view: View,
+}
+
+impl TestWindow {
+    fn proto(cx: &mut Cx) -> Self {
+        Self {
+            window: Window {
+                create_inner_size: Some(Vec2 {x: 200., y: 100.}),
+                ..Window::proto(cx)
+            },
+            pass: Pass::default(),
+            color_texture: Texture::default(),
+            view: View::proto(cx),
+        }
+    }
+
+    fn begin(&mut self, cx: &mut Cx) -> ViewRedraw {
+        self.window.begin_window(cx);
+        self.pass.begin_pass(cx);
+        self.pass.add_color_texture(cx, &mut self.color_texture, ClearColor::ClearWith(color("black")));
+        self.view.begin_view(cx, Layout::default())
+    }
+
+    fn end(&mut self, cx: &mut Cx) {
+        self.view.end_view(cx);
+        self.pass.end_pass(cx);
+        self.window.end_window(cx);
+    }
+}
+
+struct ButtonApp {
+    test_window: TestWindow,
+    button: DesktopButton,
+    events: Vec<ButtonEvent>,
+}
+
+impl TestApp for ButtonApp {
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        match self.button.handle_button(cx, event) {
+            ButtonEvent::None => (),
+            button_event => self.events.push(button_event)
+        }
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.test_window.begin(cx).is_err() {
+            return
+        }
+        self.button.draw_desktop_button(cx, DesktopButtonType::WindowsClose);
+        self.test_window.end(cx);
+    }
+}
+
+#[test]
+fn button_click_and_hover_animation() {
+    let mut cx = Cx::default();
+    let mut app = ButtonApp {
+        test_window: TestWindow::proto(&mut cx),
+        button: DesktopButton::proto(&mut cx),
+        events: Vec::new(),
+    };
+    start(&mut cx, &mut app);
+
+    cx.automation_event(&mut finger_hover(10., 10.), dispatch(&mut app));
+    cx.automation_advance(0.1, dispatch(&mut app));
+    assert_eq!(app.button.animator.last_float(&cx, DesktopButton::instance_hover()), 1.);
+
+    // pressing plays the down animation, which only moves with the clock
+    cx.automation_event(&mut finger_down(10., 10.), dispatch(&mut app));
+    assert_eq!(app.button.animator.last_float(&cx, DesktopButton::instance_down()), 0.);
+    cx.automation_advance(0.05, dispatch(&mut app));
+    let down = app.button.animator.last_float(&cx, DesktopButton::instance_down());
+    assert!(down > 0.1 && down < 1.5, "down after 0.05s is {}", down);
+    cx.automation_advance(0.5, dispatch(&mut app));
+    assert_eq!(app.button.animator.last_float(&cx, DesktopButton::instance_down()), 3.1415 * 0.5);
+    assert_eq!(cx.playing_anim_areas.len(), 0);
+
+    cx.automation_event(&mut finger_up(12., 12.), dispatch(&mut app));
+    assert!(app.events == vec![ButtonEvent::Down, ButtonEvent::Clicked]);
+
+    // releasing outside the button is not a click
+    app.events.truncate(0);
+    cx.automation_event(&mut finger_down(10., 10.), dispatch(&mut app));
+    cx.automation_event(&mut finger_up(150., 80.), dispatch(&mut app));
+    assert!(app.events == vec![ButtonEvent::Down, ButtonEvent::Up]);
+
+    // a press outside never reaches the button
+    app.events.truncate(0);
+    cx.automation_event(&mut finger_down(150., 80.), dispatch(&mut app));
+    cx.automation_event(&mut finger_up(150., 80.), dispatch(&mut app));
+    assert!(app.events.len() == 0);
+}
+
+struct ListApp {
+    test_window: TestWindow,
+    list: ListLogic,
+    view: ScrollView,
+    row: Quad,
+    selected: Vec<usize>,
+}
+
+impl TestApp for ListApp {
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        let select = match event {
+            Event::KeyDown(ke) => match ke.key_code {
+                KeyCode::ArrowDown => self.list.get_next_single_selection(),
+                KeyCode::ArrowUp => self.list.get_prev_single_selection(),
+                _ => ListSelect::None
+            },
+            _ => ListSelect::None
+        };
+        let le = self.list.handle_list_logic(cx, event, select, | cx, item_event, item, _item_index | match item_event {
+            ListLogicEvent::Animate(ae) => item.animator.calc_area(cx, item.animator.area, ae.time),
+            ListLogicEvent::AnimEnded => item.animator.end(),
+            _ => ()
+        });
+        if let ListEvent::SelectSingle(index) = le {
+            self.selected.push(index);
+        }
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.test_window.begin(cx).is_err() {
+            return
+        }
+        if self.list.begin_list(cx, &mut self.view, 20.).is_ok() {
+            for i in self.list.start_item..self.list.end_item {
+                let area = self.row.draw_quad(cx, Walk::wh(Width::Fill, Height::Fix(20.))).into();
+                self.list.list_items[i].animator.set_area(cx, area);
+            }
+            self.list.walk_turtle_to_end(cx, 20.);
+            self.list.end_list(cx, &mut self.view);
+        }
+        self.test_window.end(cx);
+    }
+}
+
+#[test]
+fn list_select_with_finger_and_keys() {
+    let mut cx = Cx::default();
+    set_dark_widget_theme(&mut cx);
+    let mut app = ListApp {
+        test_window: TestWindow::proto(&mut cx),
+        list: ListLogic::default(),
+        view: ScrollView::proto(&mut cx),
+        row: Quad::proto(&mut cx),
+        selected: Vec::new(),
+    };
+    app.list.set_list_len(10);
+    start(&mut cx, &mut app);
+
+    // only the rows in the 100 high window are drawn
+    assert_eq!(app.list.start_item, 0);
+    assert_eq!(app.list.end_item, 6);
+
+    cx.automation_event(&mut finger_down(50., 45.), dispatch(&mut app));
+    cx.automation_event(&mut finger_up(50., 45.), dispatch(&mut app));
+    assert_eq!(app.selected, vec![2]);
+    assert_eq!(app.list.selection, vec![2]);
+
+    let key_down = | key_code | Event::KeyDown(KeyEvent {key_code: key_code, is_repeat: false, modifiers: KeyModifiers::default(), time: 0.});
+    cx.automation_event(&mut key_down(KeyCode::ArrowDown), dispatch(&mut app));
+    cx.automation_event(&mut key_down(KeyCode::ArrowUp), dispatch(&mut app));
+    cx.automation_event(&mut key_down(KeyCode::ArrowUp), dispatch(&mut app));
+    assert_eq!(app.selected, vec![2, 3, 2, 1]);
+    assert!(app.list.list_items[1].is_selected);
+    assert!(!app.list.list_items[2].is_selected);
+}
+
+struct TimerApp {
+    timer: Timer,
+    fired: Vec<f64>,
+}
+
+impl TestApp for TimerApp {
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        match event {
+            Event::Construct => {
+                self.timer = cx.start_timer(0.25, true);
+            },
+            Event::Timer(te) => if self.timer.is_timer(te) {
+                self.fired.push(cx.automation_time());
+                if self.fired.len() == 3 {
+                    cx.stop_timer(&mut self.timer);
+                }
+            },
+            _ => ()
+        }
+    }
+
+    fn draw_app(&mut self, _cx: &mut Cx) {
+    }
+}
+
+#[test]
+fn timers_fire_on_the_virtual_clock() {
+    let mut cx = Cx::default();
+    let mut app = TimerApp {timer: Timer::empty(), fired: Vec::new()};
+    start(&mut cx, &mut app);
+    assert_eq!(app.fired.len(), 0);
+
+    cx.automation_advance(0.6, dispatch(&mut app));
+    assert_eq!(app.fired.len(), 2);
+    cx.automation_advance(2.0, dispatch(&mut app));
+    assert_eq!(app.fired.len(), 3);
+    for (i, time) in app.fired.iter().enumerate() {
+        assert!((time - 0.25 * (i + 1) as f64).abs() < 0.02, "timer {} fired at {}", i, time);
+    }
+}
+
+#[test]
+fn virtual_clock_keeps_the_timer_phase() {
+    let mut clock = CxVirtualClock::default();
+    clock.start_timer(1, 0.1, true);
+    clock.start_timer(2, 0.25, false);
+    // a step over several intervals fires the repeating timer for each of them
+    let fired = clock.step(0.35, 1.0);
+    assert_eq!(fired, vec![1, 1, 2, 1].into_iter().map( | timer_id | Event::Timer(TimerEvent {timer_id: timer_id})).collect::<Vec<_>>());
+    assert_eq!(clock.timers.len(), 1);
+    assert!((clock.timers[0].due - 0.4).abs() < 1e-9);
+    // and the next ones stay on the 0.1 grid
+    assert_eq!(clock.step(0.06, 1.0).len(), 1);
+    assert!((clock.timers[0].due - 0.5).abs() < 1e-9);
+    clock.stop_timer(1);
+    assert_eq!(clock.step(1.0, 1.0).len(), 0);
+}
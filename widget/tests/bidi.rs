@@ -0,0 +1,158 @@
+use render::*;
+use widget::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// right to left text is laid out in visual order, while char offsets stay in logical order
+
+fn resource(name: &str) -> String {
+    format!("{}/../resources/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn chars(text: &str) -> Vec<char> {
+    text.chars().collect()
+}
+
+fn visual(text: &str, base_level: Option<u8>) -> String {
+    let chars = chars(text);
+    let levels = bidi_levels(&chars, base_level);
+    bidi_visual_order(&levels).into_iter().map( | index | chars[index]).collect()
+}
+
+#[test]
+fn levels_follow_the_strong_chars() {
+    assert_eq!(bidi_class('a'), BidiClass::L);
+    assert_eq!(bidi_class('ש'), BidiClass::R);
+    assert_eq!(bidi_class('م'), BidiClass::AL);
+    assert_eq!(bidi_class('١'), BidiClass::AN);
+    // the ornate parentheses sit among the arabic presentation forms, but are neutral
+    assert_eq!(bidi_class('\u{FD3D}'), BidiClass::AL);
+    assert_eq!(bidi_class('\u{FD3E}'), BidiClass::ON);
+    assert_eq!(bidi_class('\u{FD3F}'), BidiClass::ON);
+    assert!(!bidi_has_rtl(&chars("let x = 1;")));
+    assert!(bidi_has_rtl(&chars("x = \"שלום\"")));
+
+    // the spaces between two directions take the direction of the paragraph
+    assert_eq!(bidi_levels(&chars("ab של cd"), None), vec![0, 0, 0, 1, 1, 0, 0, 0]);
+    // the first strong char makes the paragraph right to left, numbers in it stay left to right
+    assert_eq!(bidi_levels(&chars("של 12 ab"), None), vec![1, 1, 1, 2, 2, 1, 2, 2]);
+    // numbers after left to right text are left to right text
+    assert_eq!(bidi_levels(&chars("של ab 12"), None), vec![1, 1, 1, 2, 2, 2, 2, 2]);
+    // unless the paragraph level is given
+    assert_eq!(bidi_levels(&chars("של ab"), Some(0)), vec![1, 1, 0, 0, 0]);
+    // trailing whitespace goes back to the paragraph level
+    assert_eq!(bidi_levels(&chars("ab של "), None), vec![0, 0, 0, 1, 1, 0]);
+    // every paragraph finds its own level
+    assert_eq!(bidi_levels(&chars("ש\na"), None), vec![1, 1, 0]);
+
+    assert_eq!(bidi_level_runs(&[0, 0, 1, 1, 2, 0]), vec![0..2, 2..4, 4..5, 5..6]);
+}
+
+#[test]
+fn visual_order_reverses_right_to_left_runs() {
+    assert_eq!(visual("ab שלום cd", None), "ab םולש cd");
+    assert_eq!(visual("שלום 12 ab", None), "ab 12 םולש");
+    // separators and currency symbols stay with the number they belong to
+    assert_eq!(visual("ש 1,000.50$", None), "1,000.50$ ש");
+    // arabic digits run left to right inside arabic text
+    assert_eq!(visual("مم ١٢", None), "١٢ مم");
+}
+
+struct BidiLayout {
+    area: Area,
+    chars: Vec<(usize, f32, f32)>,
+}
+
+struct BidiApp {
+    desktop_window: DesktopWindow,
+    text: Text,
+    layout: Rc<RefCell<Option<BidiLayout>>>,
+}
+
+impl BidiApp {
+    fn proto(cx: &mut Cx, layout: Rc<RefCell<Option<BidiLayout>>>) -> Self {
+        set_dark_widget_theme(cx);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 200., y: 100.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            text: text(cx),
+            layout: layout,
+        }
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        let mut chars = Vec::new();
+        let mut aligned = self.text.begin_text(cx);
+        self.text.add_text(cx, 10., 40., 0, &mut aligned, &['a', 'ש', 'ל', 'b'], | _, offset, x, w | {
+            chars.push((offset, x, w));
+            0.0
+        });
+        let area = self.text.end_text(cx, &aligned);
+        *self.layout.borrow_mut() = Some(BidiLayout {area: area, chars: chars});
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+// liberation mono has no hebrew, the missing glyph keeps every char on the same grid
+fn text(cx: &mut Cx) -> Text {
+    Text {
+        text_style: TextStyle {
+            font: cx.load_font(&resource("LiberationMono-Regular.ttf")),
+            ..TextStyle::default()
+        },
+        ..Text::proto(cx)
+    }
+}
+
+#[test]
+fn right_to_left_chars_draw_in_visual_order() {
+    let layout = Rc::new(RefCell::new(None));
+    let mut cx = Cx::default();
+    let mut app = BidiApp::proto(&mut cx, layout.clone());
+    let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+    let snapshot = Snapshot::new(cx, Box::new(move | cx, event | {
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            cxafterdraw.after_draw(cx);
+            return
+        }
+        app.desktop_window.handle_desktop_window(cx, event);
+    }));
+    let cx = &snapshot.cx;
+    let layout = layout.borrow();
+    let layout = layout.as_ref().unwrap();
+
+    // the callback sees the chars in logical order, the right to left ones start at their right edge
+    let w = layout.chars[0].2;
+    assert!(w > 0.0);
+    let expected = [(0, 10., w), (1, 10. + 3. * w, -w), (2, 10. + 2. * w, -w), (3, 10. + 3. * w, w)];
+    assert_eq!(layout.chars.len(), expected.len());
+    for ((offset, x, w), (expected_offset, expected_x, expected_w)) in layout.chars.iter().zip(expected.iter()) {
+        assert_eq!(offset, expected_offset);
+        assert!((x - expected_x).abs() < 0.01 && (w - expected_w).abs() < 0.01, "{} {} {}", offset, x, w);
+    }
+
+    // the glyphs go left to right on screen
+    let read = layout.area.get_read_ref(cx).unwrap();
+    let char_offset_o = layout.area.get_instance_offset(cx, Text::instance_char_offset().instance_type()).unwrap();
+    let advance_o = layout.area.get_instance_offset(cx, Text::instance_advance().instance_type()).unwrap();
+    let get = | index: usize, o: usize | read.buffer[read.offset + o + index * read.slots];
+    let offsets: Vec<usize> = (0..read.count).map( | index | get(index, char_offset_o) as usize).collect();
+    assert_eq!(offsets, vec![0, 2, 1, 3]);
+    let advances: Vec<f32> = (0..read.count).map( | index | get(index, advance_o)).collect();
+    assert_eq!(advances, vec![w, -w, -w, w]);
+
+    // a click lands before the char whose leading edge is closest
+    let text = text(&mut Cx::default());
+    assert_eq!(text.find_closest_offset(cx, &layout.area, Vec2 {x: 10.2, y: 45.}), 0);
+    assert_eq!(text.find_closest_offset(cx, &layout.area, Vec2 {x: 10. + 1.9 * w, y: 45.}), 2);
+    assert_eq!(text.find_closest_offset(cx, &layout.area, Vec2 {x: 10. + 2.6 * w, y: 45.}), 1);
+}
@@ -0,0 +1,118 @@
+use render::*;
+use widget::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// glyphs that don't fit the atlas empty it and draw again, and a frame that doesn't fit an empty
+// atlas grows it, nothing is put down past the texture
+
+const TEXT: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+fn resource(name: &str) -> String {
+    format!("{}/../resources/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+struct AtlasApp {
+    desktop_window: DesktopWindow,
+    text: Text,
+    area: Area,
+}
+
+impl AtlasApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 400., y: 300.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            text: Text {
+                text_style: TextStyle {
+                    font: cx.load_font(&resource("Ubuntu-R.ttf")),
+                    font_size: 20.0,
+                    ..TextStyle::default()
+                },
+                ..Text::proto(cx)
+            },
+            area: Area::Empty,
+        }
+    }
+    
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+    }
+    
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.area = self.text.draw_text(cx, TEXT);
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn alloc_stops_at_the_texture() {
+    let mut atlas = CxFontsAtlas {
+        texture_size: Vec2 {x: 34., y: 34.},
+        ..CxFontsAtlas::default()
+    };
+    // 3 rows of 3 glyphs of 10x10 with a pixel in between
+    for _ in 0..9 {
+        let tc = atlas.alloc_atlas_glyph(10., 10.).unwrap();
+        assert!(tc.tx2 <= 1.0 && tc.ty2 <= 1.0);
+    }
+    assert!(!atlas.alloc_full);
+    assert!(atlas.alloc_atlas_glyph(10., 10.).is_none());
+    assert!(atlas.alloc_full);
+    // too wide for any row
+    let mut atlas = CxFontsAtlas {
+        texture_size: Vec2 {x: 34., y: 34.},
+        ..CxFontsAtlas::default()
+    };
+    assert!(atlas.alloc_atlas_glyph(40., 1.).is_none());
+}
+
+#[test]
+fn full_atlas_resets_and_grows() {
+    let mut cx = Cx::default();
+    let app = Rc::new(RefCell::new(AtlasApp::proto(&mut cx)));
+    let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+    // a few glyphs fill this one
+    cx.fonts_atlas.texture_size = Vec2 {x: 64., y: 64.};
+    let handler_app = app.clone();
+    let snapshot = Snapshot::new(cx, Box::new(move | cx, event | {
+        let mut app = handler_app.borrow_mut();
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            cxafterdraw.after_draw(cx);
+            return
+        }
+        app.handle_app(cx, event);
+    }));
+    let cx = &snapshot.cx;
+    
+    assert!(!cx.fonts_atlas.alloc_full);
+    assert!(cx.fonts_atlas.texture_size.y > 64.);
+    assert!(cx.fonts_atlas.texture_size.y <= ATLAS_MAX_SIZE);
+    assert_eq!(cx.fonts_atlas.texture_size.x, 64.);
+    
+    // every letter drew, and every glyph sits inside the texture
+    let area = app.borrow().area;
+    assert_eq!(area.get_read_ref(cx).unwrap().count, TEXT.chars().count());
+    let font_id = app.borrow().text.text_style.font.font_id.unwrap();
+    let cxfont = &cx.fonts[font_id];
+    let font = cxfont.font_loaded.as_ref().unwrap();
+    assert_eq!(cxfont.atlas_pages.len(), 1);
+    let atlas_glyphs = &cxfont.atlas_pages[0].atlas_glyphs;
+    for c in TEXT.chars() {
+        let glyph_id = font.char_code_to_glyph_index_map.get(c as u32);
+        assert!(atlas_glyphs[glyph_id].iter().any( | tc | tc.is_some()), "{} is not in the atlas", c);
+    }
+    for tc in atlas_glyphs.iter().flat_map( | slots | slots.iter().flatten()) {
+        assert!(tc.tx1 >= 0.0 && tc.ty1 >= 0.0 && tc.tx2 <= 1.0 && tc.ty2 <= 1.0);
+    }
+}
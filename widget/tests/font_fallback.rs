@@ -0,0 +1,131 @@
+use render::*;
+use widget::*;
+
+// chars the font of a text style lacks come from its fallback fonts, which load when first needed
+
+fn resource(name: &str) -> String {
+    format!("{}/../resources/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// inconsolata has no cyrillic, liberation mono does
+fn text_style(cx: &mut Cx, shaping: bool) -> TextStyle {
+    TextStyle {
+        font: cx.load_font(&resource("Inconsolata-Regular.ttf")),
+        fallbacks: cx.load_font_fallbacks(&[&resource("Missing.ttf"), &resource("LiberationMono-Regular.ttf")]),
+        shaping: shaping,
+        ..TextStyle::default()
+    }
+}
+
+fn font_id(cx: &Cx, name: &str) -> usize {
+    cx.fonts.iter().position( | cxfont | cxfont.path == resource(name)).unwrap()
+}
+
+#[test]
+fn fallbacks_load_when_a_char_needs_them() {
+    let mut cx = Cx::default();
+    let text = Text {
+        text_style: text_style(&mut cx, true),
+        ..Text::proto(&mut cx)
+    };
+    cx.load_theme_fonts();
+    let inconsolata = font_id(&cx, "Inconsolata-Regular.ttf");
+    let liberation = font_id(&cx, "LiberationMono-Regular.ttf");
+    let missing = font_id(&cx, "Missing.ttf");
+    assert!(cx.fonts[inconsolata].font_loaded.is_some());
+    assert!(cx.fonts[liberation].font_loaded.is_none());
+
+    let shaped = text.shape_chunk(&mut cx, &['a', 'b']);
+    assert!(shaped.iter().all( | shaped | shaped.font_id == inconsolata));
+    assert!(cx.fonts[liberation].font_loaded.is_none());
+
+    let shaped = text.shape_chunk(&mut cx, &['a', 'Ж', 'Я', 'b', '😀']);
+    let font_ids: Vec<usize> = shaped.iter().map( | shaped | shaped.font_id).collect();
+    assert_eq!(font_ids, vec![inconsolata, liberation, liberation, inconsolata, inconsolata]);
+    // glyph ids of cyrillic zhe in liberation mono, and the missing glyph for what no font has
+    assert_eq!(shaped[1].glyph_id, 432);
+    assert_eq!(shaped[4].glyph_id, 0);
+    assert!(cx.fonts[liberation].font_loaded.is_some());
+    assert!(cx.fonts[missing].font_loaded.is_none() && cx.fonts[missing].failed);
+    // and a font that failed isn't read again with the theme
+    cx.load_theme_fonts();
+    assert!(cx.fonts[missing].font_loaded.is_none() && cx.fonts[missing].failed);
+    assert!(!cx.fonts[liberation].failed);
+
+    // the same list of fallbacks is only registered once
+    assert_eq!(text_style(&mut cx, true).fallbacks.fallbacks_id, text.text_style.fallbacks.fallbacks_id);
+}
+
+#[test]
+fn monospace_text_stays_on_its_grid() {
+    let mut cx = Cx::default();
+    let mut text = Text {
+        text_style: text_style(&mut cx, false),
+        ..Text::proto(&mut cx)
+    };
+    cx.load_theme_fonts();
+    let chars = ['a', 'Ж', 'b'];
+    // without shaping the fallback glyph takes the advance of the missing glyph of inconsolata
+    let shaped = text.shape_chunk(&mut cx, &chars);
+    assert_eq!(shaped[1].advance, shaped[0].advance);
+    assert_eq!(shaped[1].advance, shaped[2].advance);
+    // with shaping it keeps its own, which is wider
+    text.text_style.shaping = true;
+    let shaped = text.shape_chunk(&mut cx, &chars);
+    assert!(shaped[1].advance > shaped[0].advance);
+}
+
+struct FallbackApp {
+    desktop_window: DesktopWindow,
+    text: Text,
+}
+
+impl FallbackApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 200., y: 100.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            text: Text {
+                text_style: text_style(cx, false),
+                ..Text::proto(cx)
+            },
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.text.draw_text(cx, "let Жук = 1;");
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn fallback_glyphs_draw_from_their_own_atlas_pages() {
+    let snapshot = snapshot_app!(FallbackApp);
+    let cx = &snapshot.cx;
+    let liberation = font_id(cx, "LiberationMono-Regular.ttf");
+    assert!(cx.fonts[liberation].font_loaded.is_some());
+    assert_eq!(cx.fonts[liberation].atlas_pages.len(), 1);
+    let atlas_page = &cx.fonts[liberation].atlas_pages[0];
+    let zhe = cx.fonts[liberation].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map.get('Ж' as u32);
+    assert!(atlas_page.atlas_glyphs[zhe].iter().any( | slot | slot.is_some()));
+    // the ascii came from inconsolata, which has no glyphs in the fallback pages
+    let a = cx.fonts[liberation].font_loaded.as_ref().unwrap().char_code_to_glyph_index_map.get('a' as u32);
+    assert!(atlas_page.atlas_glyphs[a].iter().all( | slot | slot.is_none()));
+    // the fallbacks of the os are left out, the images don't depend on what the host has
+    let system_fonts: Vec<&CxFont> = cx.fonts.iter().filter( | cxfont | system_fallback_font_paths().contains(&cxfont.path.as_str())).collect();
+    assert!(system_fonts.len() > 0);
+    assert!(system_fonts.iter().all( | cxfont | cxfont.failed && cxfont.font_loaded.is_none()));
+}
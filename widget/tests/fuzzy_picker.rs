@@ -0,0 +1,36 @@
+use widget::*;
+
+// the fuzzy score orders the items of the picker and the completion popup
+
+fn score(pattern: &str, word: &str) -> Option<f64> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    FuzzyPicker::fuzzy_score(&pattern, &word)
+}
+
+fn ranked<'a>(pattern: &str, words: &[&'a str]) -> Vec<&'a str> {
+    let mut scored: Vec<(f64, &str)> = words.iter().filter_map( | word | score(pattern, word).map( | score | (score, *word))).collect();
+    scored.sort_by( | a, b | b.0.partial_cmp(&a.0).unwrap());
+    scored.into_iter().map( | (_, word) | word).collect()
+}
+
+#[test]
+fn fuzzy_score_order() {
+    // word boundaries, then camel case, then letters in the middle of a word
+    assert_eq!(ranked("fb", &["xfb", "foobar", "fooBar", "foo_bar", "bf"]), vec!["foo_bar", "fooBar", "foobar", "xfb"]);
+    // a run of letters beats the same letters spread out
+    assert_eq!(ranked("abc", &["axbxc", "abcx"]), vec!["abcx", "axbxc"]);
+    // a path part beats letters picked from all over the path
+    assert_eq!(ranked("cx", &["render/src/text.rs", "render/src/cx.rs"]), vec!["render/src/cx.rs", "render/src/text.rs"]);
+    // the same case and a shorter word win a tie
+    assert!(score("Foo", "Foo") > score("Foo", "foo"));
+    assert!(score("ab", "ab_c") > score("ab", "ab_cdef"));
+}
+
+#[test]
+fn fuzzy_score_no_match() {
+    assert_eq!(score("xyz", "abc"), None);
+    assert_eq!(score("ba", "ab"), None);
+    assert_eq!(score("abcd", "abc"), None);
+    assert!(score("", "abc").is_some());
+}
@@ -0,0 +1,168 @@
+use render::*;
+use widget::*;
+
+fn test_window(cx: &mut Cx) -> DesktopWindow {
+    DesktopWindow {
+        window: Window {
+            create_inner_size: Some(Vec2 {x: 400., y: 300.}),
+            ..Window::proto(cx)
+        },
+        ..DesktopWindow::proto(cx)
+    }
+}
+
+struct SplitterApp {
+    desktop_window: DesktopWindow,
+    splitter: Splitter,
+    fill: Quad,
+}
+
+impl SplitterApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        let mut splitter = Splitter::proto(cx);
+        splitter.set_splitter_state(SplitterAlign::First, 120., Axis::Vertical);
+        Self {
+            desktop_window: test_window(cx),
+            splitter: splitter,
+            fill: Quad::proto(cx),
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+        match self.splitter.handle_splitter(cx, event) {
+            SplitterEvent::Moving {..} | SplitterEvent::MovingEnd {..} => {
+                cx.redraw_pass_of(self.splitter._split_area);
+            },
+            _ => ()
+        }
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.splitter.begin_splitter(cx);
+        self.fill.color = color("#246");
+        self.fill.draw_quad(cx, Walk::wh(Width::Fill, Height::Fill));
+        self.splitter.mid_splitter(cx);
+        self.fill.color = color("#642");
+        self.fill.draw_quad(cx, Walk::wh(Width::Fill, Height::Fill));
+        self.splitter.end_splitter(cx);
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn splitter_drag() {
+    let mut snapshot = snapshot_app!(SplitterApp);
+    snapshot.assert_snapshot(&snapshot_path!("splitter"), 2);
+
+    snapshot.finger_down(121., 150., KeyModifiers::default());
+    snapshot.finger_move(241., 150., KeyModifiers::default());
+    snapshot.advance(1.0);
+    snapshot.assert_snapshot(&snapshot_path!("splitter_moving"), 2);
+
+    snapshot.finger_up(241., 150., KeyModifiers::default());
+    snapshot.advance(1.0);
+    snapshot.assert_snapshot(&snapshot_path!("splitter_moved"), 2);
+}
+
+#[derive(Clone)]
+enum Panel {
+    Red,
+    Green,
+    Blue
+}
+
+struct DockApp {
+    desktop_window: DesktopWindow,
+    dock: Dock<Panel>,
+    dock_items: DockItem<Panel>,
+    fill: Quad,
+}
+
+impl DockApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        let tab = | title: &str, item: Panel | DockTab {closeable: true, title: title.to_string(), item: item};
+        Self {
+            desktop_window: test_window(cx),
+            dock: Dock::proto(cx),
+            dock_items: DockItem::Splitter {
+                axis: Axis::Vertical,
+                align: SplitterAlign::First,
+                pos: 150.,
+                first: Box::new(DockItem::TabControl {
+                    current: 0,
+                    previous: 0,
+                    tabs: vec![tab("Red", Panel::Red), tab("Green", Panel::Green)]
+                }),
+                last: Box::new(DockItem::TabControl {
+                    current: 0,
+                    previous: 0,
+                    tabs: vec![tab("Blue", Panel::Blue)]
+                })
+            },
+            fill: Quad::proto(cx),
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+        let mut dock_walker = self.dock.walker(&mut self.dock_items);
+        while let Some(_item) = dock_walker.walk_handle_dock(cx, event) {}
+        self.dock.handle_dock(cx, event, &mut self.dock_items);
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.dock.draw_dock(cx);
+        let mut dock_walker = self.dock.walker(&mut self.dock_items);
+        while let Some(item) = dock_walker.walk_draw_dock(cx, | cx, tab_control, tab, selected | {
+            tab_control.draw_tab(cx, &tab.title, selected, tab.closeable)
+        }) {
+            self.fill.color = match item {
+                Panel::Red => color("#722"),
+                Panel::Green => color("#272"),
+                Panel::Blue => color("#227"),
+            };
+            self.fill.draw_quad(cx, Walk::wh(Width::Fill, Height::Fill));
+        }
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn dock_tabs() {
+    let mut snapshot = snapshot_app!(DockApp);
+    snapshot.assert_snapshot(&snapshot_path!("dock"), 2);
+
+    // select the second tab on the left
+    snapshot.click(105., 48.);
+    snapshot.advance(1.0);
+    snapshot.assert_snapshot(&snapshot_path!("dock_select"), 2);
+}
+
+#[test]
+fn raster_matches_shaders() {
+    let mut snapshot = snapshot_app!(DockApp);
+    let draw_lists = snapshot.image();
+    snapshot.raster = SnapshotRaster::Shaders;
+    let shaders = snapshot.image();
+    // the panels, the window title and the tab labels are plain quads and text. the window
+    // buttons and the close buttons of the tabs are distance fields, the draw lists only have
+    // their rects
+    for (x1, y1, x2, y2) in vec![(0, 70, 400, 300), (100, 0, 250, 28), (24, 30, 70, 68), (86, 30, 140, 68), (176, 30, 215, 68)] {
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let a = unpack_color(draw_lists.pixel(x, y));
+                let b = unpack_color(shaders.pixel(x, y));
+                assert!((0..4).all( | c | (a[c] - b[c]).abs() < 0.05), "pixel {} {} is {:?} but the shaders draw {:?}", x, y, a, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use render::*;
+use widget::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// kerning moves the glyphs that draw_text puts down, not just the width it measures
+
+fn resource(name: &str) -> String {
+    format!("{}/../resources/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+struct ShapingApp {
+    desktop_window: DesktopWindow,
+    text: Text,
+    area: Area,
+}
+
+impl ShapingApp {
+    fn proto(cx: &mut Cx) -> Self {
+        set_dark_widget_theme(cx);
+        Self {
+            desktop_window: DesktopWindow {
+                window: Window {
+                    create_inner_size: Some(Vec2 {x: 200., y: 100.}),
+                    ..Window::proto(cx)
+                },
+                ..DesktopWindow::proto(cx)
+            },
+            text: Text {
+                text_style: TextStyle {
+                    font: cx.load_font(&resource("Ubuntu-R.ttf")),
+                    font_size: 8.0,
+                    shaping: true,
+                    ..TextStyle::default()
+                },
+                ..Text::proto(cx)
+            },
+            area: Area::Empty,
+        }
+    }
+
+    fn handle_app(&mut self, cx: &mut Cx, event: &mut Event) {
+        self.desktop_window.handle_desktop_window(cx, event);
+    }
+
+    fn draw_app(&mut self, cx: &mut Cx) {
+        if self.desktop_window.begin_desktop_window(cx, None).is_err() {
+            return
+        }
+        self.area = self.text.draw_text(cx, "AV");
+        self.desktop_window.end_desktop_window(cx);
+    }
+}
+
+#[test]
+fn kerned_pair_draws_closer() {
+    let mut cx = Cx::default();
+    let app = Rc::new(RefCell::new(ShapingApp::proto(&mut cx)));
+    let mut cxafterdraw = CxAfterDraw::proto(&mut cx);
+    let handler_app = app.clone();
+    let snapshot = Snapshot::new(cx, Box::new(move | cx, event | {
+        let mut app = handler_app.borrow_mut();
+        if let Event::Draw = event {
+            app.draw_app(cx);
+            cxafterdraw.after_draw(cx);
+            return
+        }
+        app.handle_app(cx, event);
+    }));
+    let cx = &snapshot.cx;
+    let area = app.borrow().area;
+
+    // A and V kern by -62 units in ubuntu
+    let font_id = app.borrow().text.text_style.font.font_id.unwrap();
+    let font = cx.fonts[font_id].font_loaded.as_ref().unwrap();
+    let glyph_index = | c: char | font.char_code_to_glyph_index_map.get(c as u32);
+    let kerning = font.kerning.get(glyph_index('A'), glyph_index('V'));
+    assert_eq!(kerning, -62.0);
+    let scale = 8.0 * 96.0 / 72.0 / font.units_per_em;
+    let advance_a = font.glyphs[glyph_index('A')].horizontal_metrics.advance_width;
+
+    let read = area.get_read_ref(cx).unwrap();
+    assert_eq!(read.count, 2);
+    let base_x_o = area.get_instance_offset(cx, Text::instance_base_x().instance_type()).unwrap();
+    let advance_o = area.get_instance_offset(cx, Text::instance_advance().instance_type()).unwrap();
+    let get = | index: usize, o: usize | read.buffer[read.offset + o + index * read.slots];
+    let expected = (advance_a + kerning) * scale;
+    assert!((get(0, advance_o) - expected).abs() < 0.001);
+    assert!((get(1, base_x_o) - get(0, base_x_o) - expected).abs() < 0.001);
+    assert!(get(1, base_x_o) - get(0, base_x_o) < advance_a * scale);
+}